@@ -1,7 +1,18 @@
-import { AnchorProvider, Program, web3 } from '@coral-xyz/anchor';
+import { AnchorProvider, Program, utils, web3 } from '@coral-xyz/anchor';
 import { Connection, Keypair, PublicKey, SystemProgram } from '@solana/web3.js';
 import { HauntiCore, IDL } from './haunti_core';
 import { BN } from 'bn.js';
+import { requiredDeposit, ResourceRequirements } from './utils/deposit';
+import { CostEstimate, fetchPreflight, PreflightRequest } from './utils/preflight';
+import { preverifyProof, PreverifyOutcome } from './utils/preverify';
+import {
+  ArchiveVerification,
+  decodeVerificationArchive,
+  DEFAULT_ARWEAVE_GATEWAY,
+  verifyFromArchive,
+} from './utils/proofArchive';
+import { findRentShortfalls, rentTopUpInstructions, RentShortfall } from './utils/rent';
+import { encodeWeightDiff, WeightDiff } from './utils/weightDiff';
 
 // Type Definitions
 export type CreateTaskParams = {
@@ -17,6 +28,11 @@ export type SubmitProofParams = {
   zkProof: Buffer;
   encryptedResult: string;
   computeUnits: number;
+  // Public inputs and model hash used for local pre-verification
+  publicInputs?: Uint8Array[];
+  modelHash?: Uint8Array;
+  // Submit even if local verification fails (not recommended)
+  skipPreverify?: boolean;
 };
 
 export type StakeParams = {
@@ -33,10 +49,23 @@ export type ModelNFTMetadata = {
   encryptedParams: string;
 };
 
+export type ModelPatchParams = {
+  model: web3.PublicKey;
+  diff: WeightDiff;
+  // Merkle root of the weights after applying the diff
+  newRoot: Uint8Array;
+  // Owner ed25519 signature over newRoot
+  signature: Uint8Array;
+  // Uploads the encoded diff and returns its CID
+  upload: (bytes: Uint8Array) => Promise<string>;
+};
+
 // Configuration
 const HAUNTI_PROGRAM_ID = new PublicKey('HAUNT1...');
 const METAPLEX_PROGRAM_ID = new PublicKey('meta...');
 const RPC_ENDPOINT = process.env.RPC_ENDPOINT || 'https://api.mainnet.solana.com';
+// Anchor's EVENT_IX_TAG (0x1d9acb512ea545e4), little-endian
+const EVENT_IX_TAG_LE = Buffer.from('e445a52e51cb9a1d', 'hex');
 
 export class HauntiClient {
   private connection: Connection;
@@ -71,7 +100,85 @@ export class HauntiClient {
       .rpc({ skipPreflight: true });
   }
 
+  // Quote the deposit create_task requires, from the current on-chain schedule
+  async quoteTaskDeposit(requirements: ResourceRequirements, timeoutSecs: number): Promise<BN> {
+    const [configPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('deposit_config')],
+      HAUNTI_PROGRAM_ID
+    );
+    const schedule = await this.program.account.depositConfig.fetch(configPda);
+    return requiredDeposit(schedule, requirements, timeoutSecs);
+  }
+
+  // Estimate ciphertext sizes, GPU time and the deposit before creating a task
+  async preflightTask(coordinatorUrl: string, request: PreflightRequest): Promise<CostEstimate> {
+    return fetchPreflight(coordinatorUrl, request);
+  }
+
+  // Singleton configs and registries every instruction path depends on
+  protocolConfigAddresses(): web3.PublicKey[] {
+    return [
+      'deposit_config',
+      'feature_flags',
+      'size_limits',
+      'content_policy',
+      'payment_bridge',
+      'stats_config',
+      'verifier_registry',
+    ].map(
+      (seed) => web3.PublicKey.findProgramAddressSync([Buffer.from(seed)], HAUNTI_PROGRAM_ID)[0]
+    );
+  }
+
+  // Top up any of `addresses` (protocol configs by default) that fell below rent
+  // exemption, paid by this wallet. Resolves to the shortfalls that were covered.
+  async topUpRentExemption(
+    addresses: web3.PublicKey[] = this.protocolConfigAddresses()
+  ): Promise<RentShortfall[]> {
+    const shortfalls = await findRentShortfalls(this.connection, addresses);
+    if (shortfalls.length > 0) {
+      const tx = new web3.Transaction().add(
+        ...rentTopUpInstructions(this.wallet.publicKey, shortfalls)
+      );
+      await this.provider.sendAndConfirm(tx);
+    }
+    return shortfalls;
+  }
+
+  // Check a proof locally and return the on-chain error it would hit, if any
+  preverify(params: SubmitProofParams): PreverifyOutcome {
+    if (!params.publicInputs || !params.modelHash) {
+      throw new HauntiError(0, 'publicInputs and modelHash are required for pre-verification');
+    }
+    return preverifyProof({
+      proof: params.zkProof,
+      publicInputs: params.publicInputs,
+      modelHash: params.modelHash,
+    });
+  }
+
+  // Re-verify a historical proof from its Arweave copy, given the verifier
+  // program's verification account
+  async verifyArchivedProof(
+    verification: web3.PublicKey,
+    modelHash: Uint8Array,
+    gatewayUrl: string = DEFAULT_ARWEAVE_GATEWAY
+  ): Promise<ArchiveVerification> {
+    const account = await this.connection.getAccountInfo(verification);
+    if (!account) {
+      throw new HauntiError(0, `Verification account ${verification.toBase58()} not found`);
+    }
+    return verifyFromArchive(decodeVerificationArchive(account.data), modelHash, gatewayUrl);
+  }
+
   async submitProof(params: SubmitProofParams): Promise<web3.TransactionSignature> {
+    if (!params.skipPreverify && params.publicInputs && params.modelHash) {
+      const outcome = this.preverify(params);
+      if (!outcome.ok) {
+        throw new HauntiError(outcome.errorCode ?? 0, outcome.errorMessage ?? 'Proof rejected locally');
+      }
+    }
+
     const [verifierPda] = await this.findVerifierAddress(params.taskId);
     const [rewardPda] = await this.findRewardAddress(params.taskId);
 
@@ -131,6 +238,29 @@ export class HauntiClient {
       .rpc();
   }
 
+  /** Upload a weight diff and record it on-chain as the model's next version */
+  async updateModelWithPatch(params: ModelPatchParams): Promise<web3.TransactionSignature> {
+    if (params.newRoot.length !== 32) {
+      throw new Error('newRoot must be 32 bytes');
+    }
+    const patchCid = await params.upload(encodeWeightDiff(params.diff));
+
+    return await this.program.methods
+      .updateModelWithPatch(
+        Array.from(params.diff.baseRoot),
+        Array.from(params.newRoot),
+        patchCid,
+        Buffer.from(params.signature)
+      )
+      .accounts({
+        model: params.model,
+        owner: this.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([this.wallet])
+      .rpc();
+  }
+
   // PDA Derivation
   private async findTaskAddress(modelHash: string): Promise<[web3.PublicKey, number]> {
     return web3.PublicKey.findProgramAddressSync(
@@ -154,10 +284,32 @@ export class HauntiClient {
   }
 
   // Event Listeners
+  // TaskCreated is emitted via self-CPI, so it is read from inner instructions, not logs
   watchTaskUpdates(callback: (event: any) => void): number {
-    return this.program.addEventListener('TaskCreated', (event) => {
-      callback(this.parseTaskEvent(event));
+    return this.connection.onLogs(HAUNTI_PROGRAM_ID, async ({ signature, err }) => {
+      if (err) return;
+      const tx = await this.connection.getTransaction(signature, {
+        commitment: 'confirmed',
+        maxSupportedTransactionVersion: 0,
+      });
+      for (const event of this.decodeCpiEvents(tx)) {
+        if (event.name === 'TaskCreated') callback(this.parseTaskEvent(event.data));
+      }
+    });
+  }
+
+  private decodeCpiEvents(tx: web3.VersionedTransactionResponse | null) {
+    if (!tx?.meta?.innerInstructions) return [];
+    const keys = tx.transaction.message.getAccountKeys({
+      accountKeysFromLookups: tx.meta.loadedAddresses,
     });
+    return tx.meta.innerInstructions
+      .flatMap((inner) => inner.instructions)
+      .filter((ix) => keys.get(ix.programIdIndex)?.equals(HAUNTI_PROGRAM_ID))
+      .map((ix) => utils.bytes.bs58.decode(ix.data))
+      .filter((data) => Buffer.from(data.subarray(0, 8)).equals(EVENT_IX_TAG_LE))
+      .map((data) => this.program.coder.events.decode(utils.bytes.base64.encode(Buffer.from(data.subarray(8)))))
+      .filter((event): event is NonNullable<typeof event> => event !== null);
   }
 
   watchStakingEvents(callback: (event: any) => void): number {
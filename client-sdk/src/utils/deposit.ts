@@ -0,0 +1,39 @@
+import { BN } from 'bn.js';
+
+// Mirrors haunti-core's state/deposit_config.rs
+export const GPU_TIERS = ['consumer', 'datacenter', 'highMemory'] as const;
+export const MAX_GPUS_PER_TASK = 16;
+const SECS_PER_HOUR = new BN(3600);
+
+export type GpuTier = (typeof GPU_TIERS)[number];
+
+export type ResourceRequirements = {
+  gpuTier: GpuTier;
+  gpuCount: number;
+};
+
+// Decoded `DepositConfig` account (lamports; tier rates per GPU-hour)
+export type DepositSchedule = {
+  minDeposit: BN;
+  tierRates: BN[];
+};
+
+/**
+ * Deposit `create_task` will require for these resources held for
+ * `timeoutSecs`: max(minDeposit, ceil(gpuCount * timeoutSecs * tierRate / 3600)).
+ */
+export function requiredDeposit(
+  schedule: DepositSchedule,
+  requirements: ResourceRequirements,
+  timeoutSecs: number
+): BN {
+  const { gpuTier, gpuCount } = requirements;
+  if (!Number.isInteger(gpuCount) || gpuCount < 1 || gpuCount > MAX_GPUS_PER_TASK) {
+    throw new Error(`gpuCount must be between 1 and ${MAX_GPUS_PER_TASK}`);
+  }
+  const rate = schedule.tierRates[GPU_TIERS.indexOf(gpuTier)];
+  const scaled = new BN(gpuCount).mul(new BN(timeoutSecs)).mul(rate);
+  const { div, mod } = scaled.divmod(SECS_PER_HOUR);
+  const rounded = mod.isZero() ? div : div.addn(1);
+  return BN.max(rounded, schedule.minDeposit);
+}
@@ -0,0 +1,58 @@
+// Mirrors the coordinator's cost_estimate.rs request and response
+export type LayerSpec =
+  | { type: 'dense'; inputs: number; outputs: number }
+  | {
+      type: 'conv2d';
+      in_channels: number;
+      out_channels: number;
+      kernel: number;
+      height: number;
+      width: number;
+    }
+  | { type: 'activation'; elements: number };
+
+export type PreflightRequest = {
+  // Plaintext values per input sample
+  input_elements: number;
+  layers: LayerSpec[];
+  fhe: {
+    log2_poly_size: number;
+    ciphertext_modulus_bits: number;
+    precision_bits: number;
+  };
+  batch_size: number;
+  gpu_tier: 'consumer' | 'datacenter' | 'high_memory';
+  gpu_count: number;
+};
+
+export type CostEstimate = {
+  ciphertext_bytes: number;
+  input_ciphertexts: number;
+  input_bytes: number;
+  bootstraps: number;
+  // Execution plus proving, summed over all GPUs
+  gpu_seconds: number;
+  proof_bytes: number;
+  suggested_time_limit_secs: number;
+  // Deposit create_task requires for the suggested time limit (lamports)
+  total_fee_lamports: number;
+};
+
+/**
+ * Ask a coordinator to estimate sizes, GPU time and fees for a task before it
+ * is created. Fees come from the on-chain deposit schedule at request time.
+ */
+export async function fetchPreflight(
+  coordinatorUrl: string,
+  request: PreflightRequest
+): Promise<CostEstimate> {
+  const response = await fetch(new URL('/v1/preflight', coordinatorUrl), {
+    method: 'POST',
+    headers: { 'content-type': 'application/json' },
+    body: JSON.stringify(request),
+  });
+  if (!response.ok) {
+    throw new Error(`Preflight failed (${response.status}): ${await response.text()}`);
+  }
+  return (await response.json()) as CostEstimate;
+}
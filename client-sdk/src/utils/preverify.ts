@@ -0,0 +1,34 @@
+// Built by scripts/build_verifier_wasm.sh; the size limits live in the wasm
+// build of the verifier so they cannot drift from the program's
+import { preverifyProof as wasmPreverify } from '../../verifier-wasm';
+
+export type PreverifyOutcome = {
+  ok: boolean;
+  errorCode?: number;
+  errorName?: string;
+  errorMessage?: string;
+};
+
+export type PreverifyParams = {
+  proof: Uint8Array;
+  publicInputs: Uint8Array[];
+  modelHash: Uint8Array;
+};
+
+/**
+ * Run the on-chain verifier checks locally (compiled to wasm from the
+ * program's own code) so failing proofs are caught before fees are paid.
+ */
+export function preverifyProof(params: PreverifyParams): PreverifyOutcome {
+  if (params.modelHash.length !== 32) {
+    throw new Error('modelHash must be 32 bytes');
+  }
+  if (params.publicInputs.some((input) => input.length !== 32)) {
+    throw new Error('each public input must be 32 bytes');
+  }
+
+  const flatInputs = new Uint8Array(params.publicInputs.length * 32);
+  params.publicInputs.forEach((input, i) => flatInputs.set(input, i * 32));
+
+  return wasmPreverify(params.proof, flatInputs, params.modelHash) as PreverifyOutcome;
+}
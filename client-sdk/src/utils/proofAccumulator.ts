@@ -0,0 +1,111 @@
+import { sha256 } from '@noble/hashes/sha256';
+import { PublicKey } from '@solana/web3.js';
+
+// Mirrors the verifier program's accumulator.rs: a depth-20 merkle tree per
+// epoch over verified proof commitments, leaves in `ProofAccumulated` order
+export const ACCUMULATOR_DEPTH = 20;
+export const ACCUMULATOR_CAPACITY = 2 ** ACCUMULATOR_DEPTH;
+// discriminator + bump + epoch + tree (count + frontier)
+const ROOT_OFFSET = 8 + 1 + 8 + 8 + ACCUMULATOR_DEPTH * 32;
+
+export type MembershipProof = {
+  epoch: bigint;
+  leafIndex: number;
+  proofHash: Uint8Array;
+  // Sibling hashes, bottom level first
+  path: Uint8Array[];
+};
+
+// Fields of a decoded `ProofAccumulator` account
+export type ProofAccumulatorState = {
+  epoch: bigint;
+  leaves: number;
+  // Null until the epoch is closed
+  root: Uint8Array | null;
+};
+
+export function leafHash(proofHash: Uint8Array): Uint8Array {
+  return sha256.create().update(Uint8Array.of(0)).update(proofHash).digest();
+}
+
+export function nodeHash(left: Uint8Array, right: Uint8Array): Uint8Array {
+  return sha256.create().update(Uint8Array.of(1)).update(left).update(right).digest();
+}
+
+function zeroHashes(): Uint8Array[] {
+  const zeros = [new Uint8Array(32)];
+  for (let level = 1; level < ACCUMULATOR_DEPTH; level++) {
+    zeros.push(nodeHash(zeros[level - 1], zeros[level - 1]));
+  }
+  return zeros;
+}
+
+// PDA of `[b"proof_accumulator", epoch_le]` under the verifier program
+export function proofAccumulatorAddress(epoch: bigint, verifierProgram: PublicKey): PublicKey {
+  const epochLe = Buffer.alloc(8);
+  epochLe.writeBigUInt64LE(epoch);
+  return PublicKey.findProgramAddressSync([Buffer.from('proof_accumulator'), epochLe], verifierProgram)[0];
+}
+
+export function decodeProofAccumulator(data: Uint8Array): ProofAccumulatorState {
+  const view = new DataView(data.buffer, data.byteOffset, data.byteLength);
+  const epoch = view.getBigUint64(9, true);
+  const leaves = Number(view.getBigUint64(17, true));
+  const root = data[ROOT_OFFSET] === 1 ? data.slice(ROOT_OFFSET + 1, ROOT_OFFSET + 33) : null;
+  return { epoch, leaves, root };
+}
+
+// Root over the epoch's proof hashes, as `close_proof_epoch` publishes it
+export function accumulatorRoot(proofHashes: Uint8Array[]): Uint8Array {
+  const zeros = zeroHashes();
+  let nodes = proofHashes.map(leafHash);
+  for (const zero of zeros) {
+    nodes = pairUp(nodes, zero);
+  }
+  return nodes[0] ?? nodeHash(zeros[ACCUMULATOR_DEPTH - 1], zeros[ACCUMULATOR_DEPTH - 1]);
+}
+
+/**
+ * Build the proof that `proofHashes[leafIndex]` was verified in `epoch`.
+ * `proofHashes` must be every leaf of the epoch in append order, e.g. from the
+ * epoch's `ProofAccumulated` events sorted by `leafIndex`.
+ */
+export function membershipProof(
+  epoch: bigint,
+  proofHashes: Uint8Array[],
+  leafIndex: number
+): MembershipProof {
+  if (leafIndex < 0 || leafIndex >= proofHashes.length || proofHashes.length > ACCUMULATOR_CAPACITY) {
+    throw new Error(`Leaf ${leafIndex} is outside the ${proofHashes.length}-leaf accumulator`);
+  }
+  const path: Uint8Array[] = [];
+  let nodes = proofHashes.map(leafHash);
+  let position = leafIndex;
+  for (const zero of zeroHashes()) {
+    path.push(nodes[position ^ 1] ?? zero);
+    nodes = pairUp(nodes, zero);
+    position >>= 1;
+  }
+  return { epoch, leafIndex, proofHash: proofHashes[leafIndex], path };
+}
+
+// Check a membership proof against the root published for its epoch
+export function verifyMembership(root: Uint8Array, proof: MembershipProof): boolean {
+  if (proof.path.length !== ACCUMULATOR_DEPTH || proof.leafIndex >= ACCUMULATOR_CAPACITY) {
+    return false;
+  }
+  const computed = proof.path.reduce(
+    (node, sibling, level) =>
+      (proof.leafIndex >> level) & 1 ? nodeHash(sibling, node) : nodeHash(node, sibling),
+    leafHash(proof.proofHash)
+  );
+  return computed.length === root.length && computed.every((byte, i) => byte === root[i]);
+}
+
+function pairUp(nodes: Uint8Array[], zero: Uint8Array): Uint8Array[] {
+  const parents: Uint8Array[] = [];
+  for (let i = 0; i < nodes.length; i += 2) {
+    parents.push(nodeHash(nodes[i], nodes[i + 1] ?? zero));
+  }
+  return parents;
+}
@@ -0,0 +1,100 @@
+import { sha256 } from '@noble/hashes/sha256';
+import { preverifyProof, PreverifyOutcome } from './preverify';
+
+// Mirrors the coordinator's proof_archive.rs payload and the verifier program's
+// VerificationState layout
+export const DEFAULT_ARWEAVE_GATEWAY = 'https://arweave.net';
+// discriminator + status + slot + verifier + reward_amount
+const PROOF_HASH_OFFSET = 8 + 1 + 8 + 32 + 8;
+
+export type ArchivedProof = {
+  proof: Uint8Array;
+  publicInputs: Uint8Array[];
+};
+
+// Archive fields of a decoded `VerificationState` account
+export type VerificationArchive = {
+  proofHash: Uint8Array;
+  // Arweave transaction id, or null until the proof is mirrored
+  archiveTx: Uint8Array | null;
+};
+
+export type ArchiveVerification = {
+  // The archived bytes are the ones the program verified
+  matchesCommitment: boolean;
+  // Re-run of the on-chain checks; absent when the commitment does not match
+  outcome?: PreverifyOutcome;
+};
+
+export function decodeVerificationArchive(data: Uint8Array): VerificationArchive {
+  const proofHash = data.slice(PROOF_HASH_OFFSET, PROOF_HASH_OFFSET + 32);
+  const tagOffset = PROOF_HASH_OFFSET + 32;
+  const archiveTx = data[tagOffset] === 1 ? data.slice(tagOffset + 1, tagOffset + 33) : null;
+  return { proofHash, archiveTx };
+}
+
+// Borsh: u32 length + proof bytes, u32 count + 32-byte public inputs
+export function decodeArchivedProof(bytes: Uint8Array): ArchivedProof {
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  const proofLen = view.getUint32(0, true);
+  const proof = bytes.slice(4, 4 + proofLen);
+  let offset = 4 + proofLen;
+  const count = view.getUint32(offset, true);
+  offset += 4;
+  if (bytes.length !== offset + count * 32) {
+    throw new Error('Archived proof has trailing or missing bytes');
+  }
+  const publicInputs = Array.from({ length: count }, (_, i) =>
+    bytes.slice(offset + i * 32, offset + (i + 1) * 32)
+  );
+  return { proof, publicInputs };
+}
+
+// SHA-256 of the proof followed by the public inputs (solana_verifier::proof_commitment)
+export function proofCommitment(proof: ArchivedProof): Uint8Array {
+  const hasher = sha256.create().update(proof.proof);
+  proof.publicInputs.forEach((input) => hasher.update(input));
+  return hasher.digest();
+}
+
+export function arweaveId(archiveTx: Uint8Array): string {
+  return Buffer.from(archiveTx).toString('base64url');
+}
+
+export async function fetchArchivedProof(
+  archiveTx: Uint8Array,
+  gatewayUrl: string = DEFAULT_ARWEAVE_GATEWAY
+): Promise<ArchivedProof> {
+  const response = await fetch(new URL(`/${arweaveId(archiveTx)}`, gatewayUrl));
+  if (!response.ok) {
+    throw new Error(`Arweave fetch failed (${response.status}): ${await response.text()}`);
+  }
+  return decodeArchivedProof(new Uint8Array(await response.arrayBuffer()));
+}
+
+/**
+ * Audit a historical verification from its Arweave copy: check the archived
+ * bytes against the commitment recorded on-chain, then re-run the verifier
+ * checks on them. Works after the task accounts have been closed.
+ */
+export async function verifyFromArchive(
+  archive: VerificationArchive,
+  modelHash: Uint8Array,
+  gatewayUrl: string = DEFAULT_ARWEAVE_GATEWAY
+): Promise<ArchiveVerification> {
+  if (!archive.archiveTx) {
+    throw new Error('Proof has not been archived');
+  }
+  const proof = await fetchArchivedProof(archive.archiveTx, gatewayUrl);
+  const commitment = proofCommitment(proof);
+  const matchesCommitment =
+    commitment.length === archive.proofHash.length &&
+    commitment.every((byte, i) => byte === archive.proofHash[i]);
+  if (!matchesCommitment) {
+    return { matchesCommitment };
+  }
+  return {
+    matchesCommitment,
+    outcome: preverifyProof({ proof: proof.proof, publicInputs: proof.publicInputs, modelHash }),
+  };
+}
@@ -0,0 +1,58 @@
+import { Connection, PublicKey, SystemProgram, TransactionInstruction } from '@solana/web3.js';
+
+// getMultipleAccountsInfo limit
+const MAX_MULTIPLE_ACCOUNTS = 100;
+
+export type RentShortfall = {
+  address: PublicKey;
+  lamports: number;
+  minimum: number;
+  shortfall: number;
+};
+
+/**
+ * Accounts among `addresses` holding less than their rent-exempt minimum.
+ * Missing accounts are skipped: they were closed or already purged.
+ */
+export async function findRentShortfalls(
+  connection: Connection,
+  addresses: PublicKey[]
+): Promise<RentShortfall[]> {
+  const minimums = new Map<number, number>();
+  const shortfalls: RentShortfall[] = [];
+  for (let i = 0; i < addresses.length; i += MAX_MULTIPLE_ACCOUNTS) {
+    const chunk = addresses.slice(i, i + MAX_MULTIPLE_ACCOUNTS);
+    const accounts = await connection.getMultipleAccountsInfo(chunk);
+    for (const [j, account] of accounts.entries()) {
+      if (!account) continue;
+      const size = account.data.length;
+      let minimum = minimums.get(size);
+      if (minimum === undefined) {
+        minimum = await connection.getMinimumBalanceForRentExemption(size);
+        minimums.set(size, minimum);
+      }
+      if (account.lamports < minimum) {
+        shortfalls.push({
+          address: chunk[j],
+          lamports: account.lamports,
+          minimum,
+          shortfall: minimum - account.lamports,
+        });
+      }
+    }
+  }
+  return shortfalls;
+}
+
+/**
+ * Transfers from `payer` lifting each account back to rent exemption. Any
+ * account can be credited, so no signature from the owning program is needed.
+ */
+export function rentTopUpInstructions(
+  payer: PublicKey,
+  shortfalls: RentShortfall[]
+): TransactionInstruction[] {
+  return shortfalls.map(({ address, shortfall }) =>
+    SystemProgram.transfer({ fromPubkey: payer, toPubkey: address, lamports: shortfall })
+  );
+}
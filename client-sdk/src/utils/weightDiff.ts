@@ -0,0 +1,210 @@
+/**
+ * Binary weight-diff format for incremental model updates.
+ *
+ * Layout (little-endian):
+ *   magic "HWDF" | version u8 | baseRoot [32] | tensorCount u32
+ *   per tensor: nameLen u16 | name utf8 | numel u32 | nnz u32 | indices u32[nnz] | deltas f32[nnz]
+ *
+ * Only f32 tensors are supported; a tensor missing from the base is diffed against zeros.
+ */
+
+export const WEIGHT_DIFF_MAGIC = 'HWDF';
+export const WEIGHT_DIFF_VERSION = 1;
+
+export type TensorMap = Record<string, Float32Array>;
+
+export type TensorDelta = {
+  name: string;
+  numel: number;
+  indices: Uint32Array;
+  deltas: Float32Array;
+};
+
+export type WeightDiff = {
+  baseRoot: Uint8Array;
+  tensors: TensorDelta[];
+};
+
+export class WeightDiffError extends Error {
+  constructor(message: string) {
+    super(`[WeightDiff] ${message}`);
+    Object.setPrototypeOf(this, WeightDiffError.prototype);
+  }
+}
+
+/**
+ * Sparse per-tensor deltas from `base` to `updated`. Changes with magnitude
+ * at or below `threshold` are dropped, so a non-zero threshold is lossy.
+ */
+export function createWeightDiff(
+  base: TensorMap,
+  updated: TensorMap,
+  baseRoot: Uint8Array,
+  threshold = 0
+): WeightDiff {
+  if (baseRoot.length !== 32) {
+    throw new WeightDiffError('baseRoot must be 32 bytes');
+  }
+  const removed = Object.keys(base).filter((name) => !(name in updated));
+  if (removed.length > 0) {
+    throw new WeightDiffError(`tensors cannot be removed by a patch: ${removed.join(', ')}`);
+  }
+
+  const tensors: TensorDelta[] = [];
+  for (const name of Object.keys(updated).sort()) {
+    const next = updated[name];
+    const prev = base[name] ?? new Float32Array(next.length);
+    if (prev.length !== next.length) {
+      throw new WeightDiffError(`shape of ${name} changed (${prev.length} -> ${next.length})`);
+    }
+
+    const indices: number[] = [];
+    const deltas: number[] = [];
+    for (let i = 0; i < next.length; i++) {
+      const delta = next[i] - prev[i];
+      if (Math.abs(delta) > threshold) {
+        indices.push(i);
+        deltas.push(delta);
+      }
+    }
+    if (indices.length > 0 || !(name in base)) {
+      tensors.push({
+        name,
+        numel: next.length,
+        indices: Uint32Array.from(indices),
+        deltas: Float32Array.from(deltas),
+      });
+    }
+  }
+
+  return { baseRoot: Uint8Array.from(baseRoot), tensors };
+}
+
+/** Apply a diff to `base`, returning new tensors; `base` is left untouched */
+export function applyWeightDiff(base: TensorMap, diff: WeightDiff): TensorMap {
+  const result: TensorMap = {};
+  for (const [name, tensor] of Object.entries(base)) {
+    result[name] = Float32Array.from(tensor);
+  }
+
+  for (const delta of diff.tensors) {
+    const target = result[delta.name] ?? new Float32Array(delta.numel);
+    if (target.length !== delta.numel) {
+      throw new WeightDiffError(`patch for ${delta.name} expects ${delta.numel} elements`);
+    }
+    delta.indices.forEach((index, i) => {
+      if (index >= target.length) {
+        throw new WeightDiffError(`index ${index} out of range for ${delta.name}`);
+      }
+      target[index] += delta.deltas[i];
+    });
+    result[delta.name] = target;
+  }
+  return result;
+}
+
+/** Apply a chain of diffs oldest first, as fetched from base + patch CIDs */
+export function applyWeightDiffChain(base: TensorMap, diffs: WeightDiff[]): TensorMap {
+  return diffs.reduce((tensors, diff) => applyWeightDiff(tensors, diff), base);
+}
+
+export function encodeWeightDiff(diff: WeightDiff): Uint8Array {
+  const encoder = new TextEncoder();
+  const names = diff.tensors.map((t) => encoder.encode(t.name));
+  const size =
+    4 + 1 + 32 + 4 +
+    diff.tensors.reduce((sum, t, i) => sum + 2 + names[i].length + 4 + 4 + t.indices.length * 8, 0);
+
+  const bytes = new Uint8Array(size);
+  const view = new DataView(bytes.buffer);
+  let offset = 0;
+
+  bytes.set(encoder.encode(WEIGHT_DIFF_MAGIC), offset);
+  offset += 4;
+  view.setUint8(offset, WEIGHT_DIFF_VERSION);
+  offset += 1;
+  bytes.set(diff.baseRoot, offset);
+  offset += 32;
+  view.setUint32(offset, diff.tensors.length, true);
+  offset += 4;
+
+  diff.tensors.forEach((tensor, i) => {
+    view.setUint16(offset, names[i].length, true);
+    offset += 2;
+    bytes.set(names[i], offset);
+    offset += names[i].length;
+    view.setUint32(offset, tensor.numel, true);
+    offset += 4;
+    view.setUint32(offset, tensor.indices.length, true);
+    offset += 4;
+    tensor.indices.forEach((index) => {
+      view.setUint32(offset, index, true);
+      offset += 4;
+    });
+    tensor.deltas.forEach((delta) => {
+      view.setFloat32(offset, delta, true);
+      offset += 4;
+    });
+  });
+
+  return bytes;
+}
+
+export function decodeWeightDiff(bytes: Uint8Array): WeightDiff {
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  const decoder = new TextDecoder();
+  let offset = 0;
+
+  const need = (n: number) => {
+    if (offset + n > bytes.length) {
+      throw new WeightDiffError('truncated diff');
+    }
+  };
+
+  need(4 + 1 + 32 + 4);
+  if (decoder.decode(bytes.subarray(0, 4)) !== WEIGHT_DIFF_MAGIC) {
+    throw new WeightDiffError('bad magic');
+  }
+  offset += 4;
+  const version = view.getUint8(offset);
+  if (version !== WEIGHT_DIFF_VERSION) {
+    throw new WeightDiffError(`unsupported version ${version}`);
+  }
+  offset += 1;
+  const baseRoot = bytes.slice(offset, offset + 32);
+  offset += 32;
+  const count = view.getUint32(offset, true);
+  offset += 4;
+
+  const tensors: TensorDelta[] = [];
+  for (let t = 0; t < count; t++) {
+    need(2);
+    const nameLen = view.getUint16(offset, true);
+    offset += 2;
+    need(nameLen + 8);
+    const name = decoder.decode(bytes.subarray(offset, offset + nameLen));
+    offset += nameLen;
+    const numel = view.getUint32(offset, true);
+    offset += 4;
+    const nnz = view.getUint32(offset, true);
+    offset += 4;
+
+    need(nnz * 8);
+    const indices = new Uint32Array(nnz);
+    for (let i = 0; i < nnz; i++) {
+      indices[i] = view.getUint32(offset, true);
+      offset += 4;
+    }
+    const deltas = new Float32Array(nnz);
+    for (let i = 0; i < nnz; i++) {
+      deltas[i] = view.getFloat32(offset, true);
+      offset += 4;
+    }
+    tensors.push({ name, numel, indices, deltas });
+  }
+
+  if (offset !== bytes.length) {
+    throw new WeightDiffError('trailing bytes after diff');
+  }
+  return { baseRoot, tensors };
+}
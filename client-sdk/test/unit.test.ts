@@ -2,6 +2,18 @@ import { AnchorProvider, BN, Program, web3 } from '@coral-xyz/anchor';
 import { HauntiClient, HauntiError, TaskStatus } from './client';
 import { HauntiCrypto } from './utils/crypto';
 import { IPFSClient } from './utils/ipfs';
+import {
+  applyWeightDiff,
+  createWeightDiff,
+  decodeWeightDiff,
+  encodeWeightDiff,
+} from './utils/weightDiff';
+import { requiredDeposit } from './utils/deposit';
+import {
+  accumulatorRoot,
+  membershipProof,
+  verifyMembership,
+} from './utils/proofAccumulator';
 import { 
   CreateTaskArgs,
   ModelMetadata,
@@ -200,6 +212,82 @@ describe('Haunti SDK Unit Tests', () => {
     });
   });
 
+  describe('Weight Diffs', () => {
+    const baseRoot = new Uint8Array(32).fill(7);
+    const base = {
+      'layer0.weight': Float32Array.from([0.5, -1, 2, 0]),
+      'layer0.bias': Float32Array.from([0, 0]),
+    };
+    const updated = {
+      'layer0.weight': Float32Array.from([0.5, -0.5, 2, 1]),
+      'layer0.bias': Float32Array.from([0, 0]),
+      'head.weight': Float32Array.from([3, 0]),
+    };
+
+    it('should round-trip a sparse diff through the binary format', () => {
+      const diff = createWeightDiff(base, updated, baseRoot);
+      // Unchanged tensors are omitted, new ones are diffed against zeros
+      expect(diff.tensors.map((t) => t.name)).to.deep.equal(['head.weight', 'layer0.weight']);
+
+      const decoded = decodeWeightDiff(encodeWeightDiff(diff));
+      expect(decoded.baseRoot).to.deep.equal(baseRoot);
+      const patched = applyWeightDiff(base, decoded);
+      expect(Array.from(patched['layer0.weight'])).to.deep.equal([0.5, -0.5, 2, 1]);
+      expect(Array.from(patched['head.weight'])).to.deep.equal([3, 0]);
+      expect(Array.from(base['layer0.weight'])).to.deep.equal([0.5, -1, 2, 0]);
+    });
+
+    it('should reject shape changes', () => {
+      const reshaped = { ...updated, 'layer0.bias': Float32Array.from([0]) };
+      expect(() => createWeightDiff(base, reshaped, baseRoot)).to.throw('shape of layer0.bias changed');
+    });
+  });
+
+  describe('Deposit Quotes', () => {
+    const schedule = {
+      minDeposit: new BN(100_000),
+      tierRates: [new BN(1_000_000), new BN(5_000_000), new BN(20_000_000)],
+    };
+
+    it('should scale with GPUs, time and tier, rounding up', () => {
+      const quote = (gpuTier: 'consumer' | 'datacenter' | 'highMemory', gpuCount: number, secs: number) =>
+        requiredDeposit(schedule, { gpuTier, gpuCount }, secs).toString();
+      expect(quote('highMemory', 8, 7_200)).to.equal('320000000');
+      expect(quote('datacenter', 1, 1_801)).to.equal('2501389');
+      expect(quote('consumer', 1, 300)).to.equal('100000');
+    });
+
+    it('should reject GPU counts the program rejects', () => {
+      expect(() => requiredDeposit(schedule, { gpuTier: 'consumer', gpuCount: 0 }, 300)).to.throw();
+      expect(() => requiredDeposit(schedule, { gpuTier: 'consumer', gpuCount: 17 }, 300)).to.throw();
+    });
+  });
+
+  describe('Proof Accumulator', () => {
+    const proofHashes = [0, 1, 2].map((i) => new Uint8Array(32).fill(i));
+    const hex = (bytes: Uint8Array) => Buffer.from(bytes).toString('hex');
+
+    it('should match the root the verifier program publishes', () => {
+      expect(hex(accumulatorRoot(proofHashes))).to.equal(
+        'f96746fbde91b6d4557a1c52bced0a3f816f8c7fb0467bb4a593ad92bf83575e'
+      );
+      expect(hex(accumulatorRoot([]))).to.equal(
+        '51c20d66008024c04cf114564a998e49ef8f6e044e2d13a03a66521d6e200503'
+      );
+    });
+
+    it('should prove membership of every verified proof', () => {
+      const root = accumulatorRoot(proofHashes);
+      proofHashes.forEach((_, index) => {
+        const proof = membershipProof(7n, proofHashes, index);
+        expect(verifyMembership(root, proof)).to.be.true;
+        expect(verifyMembership(root, { ...proof, proofHash: new Uint8Array(32).fill(9) })).to.be.false;
+        expect(verifyMembership(root, { ...proof, leafIndex: index ^ 1 })).to.be.false;
+      });
+      expect(() => membershipProof(7n, proofHashes, 3)).to.throw();
+    });
+  });
+
   describe('Error Handling', () => {
     it('should wrap native errors in HauntiError', async () => {
       when(mockProgram.methods.getTaskStatus(any))
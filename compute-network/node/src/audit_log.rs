@@ -0,0 +1,223 @@
+//! Tamper-evident log of coordinator admin actions
+//!
+//! Requeues, verifier drains, model key releases and tenant changes are appended
+//! to a JSON-lines file, one entry per line. Each entry's hash covers the hash of
+//! the entry before it, so editing, dropping or reordering any line breaks every
+//! hash after it. The head hash is anchored on-chain from time to time as a memo
+//! (`haunti-audit:1:<seq>:<hash>`); once anchored, even rewriting the whole file
+//! from that point on is detectable. `haunti audit verify` checks both.
+
+use serde::{Deserialize, Serialize};
+use solana_program::keccak;
+use solana_sdk::{instruction::Instruction, pubkey, pubkey::Pubkey};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// SPL Memo program, which the head hash is anchored through
+pub const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+const ANCHOR_PREFIX: &str = "haunti-audit:1:";
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("Audit log I/O: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Malformed audit entry at line {line}: {source}")]
+    Malformed { line: usize, source: serde_json::Error },
+    #[error("Audit entry {seq} out of sequence (expected {expected})")]
+    Sequence { seq: u64, expected: u64 },
+    #[error("Audit entry {seq} does not chain to the entry before it")]
+    BrokenChain { seq: u64 },
+    #[error("Audit entry {seq} hash does not match its contents")]
+    BadHash { seq: u64 },
+}
+
+/// Administrative action worth a permanent record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AuditAction {
+    /// A task put back in the scheduler queue
+    Requeue { task: String, delay_secs: u64, reason: String },
+    /// Verifier routing moved to a new version, draining the old ones
+    VerifierDrain { current: u16, draining: Option<u16>, slot: u64 },
+    /// A model decryption key released to this node's enclave
+    KeyRelease { task: String, model_cid: String },
+    TenantCreated { tenant_id: String, fee_payer: String },
+    TenantStatusChanged { tenant_id: String, status: String },
+    /// The log head was anchored on-chain
+    HeadAnchored { seq: u64, hash: String, signature: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, from 0
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub action: AuditAction,
+    /// `hash` of the previous entry; zero for the first
+    #[serde(with = "hex::serde")]
+    pub prev_hash: [u8; 32],
+    #[serde(with = "hex::serde")]
+    pub hash: [u8; 32],
+}
+
+impl AuditEntry {
+    /// keccak256 over the previous hash, sequence, timestamp and the action's
+    /// canonical JSON
+    pub fn compute_hash(prev_hash: &[u8; 32], seq: u64, timestamp_ms: u64, action: &AuditAction) -> [u8; 32] {
+        let action = serde_json::to_vec(action).expect("audit actions always serialize");
+        keccak::hashv(&[prev_hash, &seq.to_le_bytes(), &timestamp_ms.to_le_bytes(), &action]).0
+    }
+}
+
+/// Last entry of a verified log
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AuditHead {
+    /// Entries in the log
+    pub len: u64,
+    pub hash: [u8; 32],
+}
+
+/// Check every entry of the log at `path`; calls `each` on entries as they verify
+pub fn verify(path: &Path, mut each: impl FnMut(&AuditEntry)) -> Result<AuditHead, AuditError> {
+    let mut head = AuditHead::default();
+    for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let entry: AuditEntry = serde_json::from_str(&line)
+            .map_err(|source| AuditError::Malformed { line: index + 1, source })?;
+        if entry.seq != head.len {
+            return Err(AuditError::Sequence { seq: entry.seq, expected: head.len });
+        }
+        if entry.prev_hash != head.hash {
+            return Err(AuditError::BrokenChain { seq: entry.seq });
+        }
+        let hash = AuditEntry::compute_hash(&entry.prev_hash, entry.seq, entry.timestamp_ms, &entry.action);
+        if entry.hash != hash {
+            return Err(AuditError::BadHash { seq: entry.seq });
+        }
+        each(&entry);
+        head = AuditHead { len: head.len + 1, hash };
+    }
+    Ok(head)
+}
+
+/// Memo recording that the log's entry `seq` has hash `hash`
+pub fn anchor_memo(seq: u64, hash: &[u8; 32]) -> String {
+    format!("{}{}:{}", ANCHOR_PREFIX, seq, hex::encode(hash))
+}
+
+/// `(seq, hash)` of an anchor memo
+pub fn parse_anchor_memo(memo: &str) -> Option<(u64, [u8; 32])> {
+    let (seq, hash) = memo.strip_prefix(ANCHOR_PREFIX)?.split_once(':')?;
+    let mut bytes = [0; 32];
+    hex::decode_to_slice(hash, &mut bytes).ok()?;
+    Some((seq.parse().ok()?, bytes))
+}
+
+pub fn anchor_instruction(seq: u64, hash: &[u8; 32], signer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: MEMO_PROGRAM_ID,
+        accounts: vec![solana_sdk::instruction::AccountMeta::new_readonly(*signer, true)],
+        data: anchor_memo(seq, hash).into_bytes(),
+    }
+}
+
+/// Append-only writer; reopening resumes after verifying the existing entries
+pub struct AuditLog {
+    file: File,
+    head: AuditHead,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path) -> Result<Self, AuditError> {
+        let head = if path.exists() { verify(path, |_| {})? } else { AuditHead::default() };
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file, head })
+    }
+
+    pub fn head(&self) -> AuditHead {
+        self.head
+    }
+
+    /// Append `action` and sync it to disk before returning
+    pub fn append(&mut self, action: AuditAction, timestamp_ms: u64) -> Result<AuditEntry, AuditError> {
+        let seq = self.head.len;
+        let hash = AuditEntry::compute_hash(&self.head.hash, seq, timestamp_ms, &action);
+        let entry = AuditEntry { seq, timestamp_ms, action, prev_hash: self.head.hash, hash };
+
+        let mut line = serde_json::to_vec(&entry).expect("audit entries always serialize");
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+        self.head = AuditHead { len: seq + 1, hash };
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("haunti-audit-{name}-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn requeue(task: &str) -> AuditAction {
+        AuditAction::Requeue { task: task.into(), delay_secs: 60, reason: "retry".into() }
+    }
+
+    #[test]
+    fn test_reopened_log_continues_the_chain() {
+        let path = log("reopen");
+        let mut audit = AuditLog::open(&path).unwrap();
+        audit.append(requeue("a"), 1).unwrap();
+        let second = audit.append(requeue("b"), 2).unwrap();
+        drop(audit);
+
+        let mut audit = AuditLog::open(&path).unwrap();
+        assert_eq!(audit.head(), AuditHead { len: 2, hash: second.hash });
+        let third = audit
+            .append(AuditAction::TenantStatusChanged { tenant_id: "t".into(), status: "Suspended".into() }, 3)
+            .unwrap();
+        assert_eq!(third.prev_hash, second.hash);
+
+        let mut seen = Vec::new();
+        let head = verify(&path, |entry| seen.push(entry.seq)).unwrap();
+        assert_eq!(seen, [0, 1, 2]);
+        assert_eq!(head.hash, third.hash);
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let path = log("tamper");
+        let mut audit = AuditLog::open(&path).unwrap();
+        for task in ["a", "b", "c"] {
+            audit.append(requeue(task), 1).unwrap();
+        }
+        let original = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = original.lines().collect();
+
+        std::fs::write(&path, original.replace("\"b\"", "\"x\"")).unwrap();
+        assert!(matches!(verify(&path, |_| {}), Err(AuditError::BadHash { seq: 1 })));
+
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert!(matches!(verify(&path, |_| {}), Err(AuditError::Sequence { seq: 2, expected: 1 })));
+        assert!(AuditLog::open(&path).is_err());
+    }
+
+    #[test]
+    fn test_anchor_memo_round_trips() {
+        let memo = anchor_memo(41, &[0xab; 32]);
+        assert_eq!(parse_anchor_memo(&memo), Some((41, [0xab; 32])));
+        assert_eq!(parse_anchor_memo("gm"), None);
+    }
+}
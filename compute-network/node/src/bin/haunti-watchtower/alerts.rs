@@ -0,0 +1,98 @@
+//! Alert delivery to generic webhooks and PagerDuty, deduplicated across polls
+
+use serde_json::json;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+use crate::checks::{Finding, Severity};
+
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+#[derive(Debug, Clone)]
+pub enum AlertSink {
+    /// Receives `{"status", "key", "severity", "summary"}` JSON
+    Webhook(String),
+    /// PagerDuty Events API v2 integration
+    PagerDuty { routing_key: String },
+}
+
+/// Fires when a finding first appears or escalates, resolves when it disappears
+pub struct Alerter {
+    http: reqwest::Client,
+    sinks: Vec<AlertSink>,
+    active: HashMap<String, Severity>,
+}
+
+impl Alerter {
+    pub fn new(sinks: Vec<AlertSink>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            sinks,
+            active: HashMap::new(),
+        }
+    }
+
+    /// Reconcile the active alerts with this poll's findings
+    pub async fn publish(&mut self, findings: &[Finding]) {
+        for finding in findings {
+            let escalated = self
+                .active
+                .get(&finding.key)
+                .map_or(true, |severity| *severity < finding.severity);
+            if escalated {
+                info!(key = %finding.key, "{}", finding.summary);
+                self.send(&finding.key, Some(finding)).await;
+                self.active.insert(finding.key.clone(), finding.severity);
+            }
+        }
+
+        let cleared: Vec<String> = self
+            .active
+            .keys()
+            .filter(|key| !findings.iter().any(|f| &f.key == *key))
+            .cloned()
+            .collect();
+        for key in cleared {
+            info!(%key, "resolved");
+            self.send(&key, None).await;
+            self.active.remove(&key);
+        }
+    }
+
+    /// Trigger `finding`, or resolve `key` when `None`; delivery errors are logged
+    /// so one unreachable sink does not block the others
+    async fn send(&self, key: &str, finding: Option<&Finding>) {
+        for sink in &self.sinks {
+            let request = match sink {
+                AlertSink::Webhook(url) => self.http.post(url).json(&json!({
+                    "status": if finding.is_some() { "firing" } else { "resolved" },
+                    "key": key,
+                    "severity": finding.map(|f| f.severity),
+                    "summary": finding.map(|f| f.summary.as_str()),
+                })),
+                AlertSink::PagerDuty { routing_key } => {
+                    let mut event = json!({
+                        "routing_key": routing_key,
+                        "event_action": if finding.is_some() { "trigger" } else { "resolve" },
+                        "dedup_key": key,
+                    });
+                    if let Some(finding) = finding {
+                        event["payload"] = json!({
+                            "summary": finding.summary,
+                            "source": "haunti-watchtower",
+                            "severity": match finding.severity {
+                                Severity::Warning => "warning",
+                                Severity::Critical => "critical",
+                            },
+                        });
+                    }
+                    self.http.post(PAGERDUTY_EVENTS_URL).json(&event)
+                }
+            };
+
+            if let Err(e) = request.send().await.and_then(|r| r.error_for_status()) {
+                warn!(%key, error = %e, "alert delivery failed");
+            }
+        }
+    }
+}
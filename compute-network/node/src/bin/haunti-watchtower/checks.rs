@@ -0,0 +1,310 @@
+//! Anomaly checks over fetched state; free of I/O so each rule can be tested alone
+
+use haunti_core::state::{TaskState, TaskStatus, ERROR_HEARTBEAT_TIMEOUT, ERROR_TIME_LIMIT};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet, VecDeque};
+use token_vault::PoolState;
+
+/// Gauge a relayer exports for its pending message queue
+pub const RELAY_BACKLOG_METRIC: &str = "haunti_relay_queue_depth";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Critical,
+}
+
+/// Permissionless transaction that clears the anomaly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Remediation {
+    /// Cancel or fail a task past its pickup deadline or time limit
+    ExpireTask { task: Pubkey, owner: Pubkey, pricing: Option<Pubkey> },
+    /// Fail a running task whose worker stopped sending heartbeats
+    ReportTimeout { task: Pubkey, owner: Pubkey, pricing: Option<Pubkey> },
+    /// Transfer the keeper's own lamports to lift an account back to rent exemption
+    TopUpRent { account: Pubkey, lamports: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Stable identity, so an anomaly seen on consecutive polls alerts once
+    pub key: String,
+    pub severity: Severity,
+    pub summary: String,
+    pub remediation: Option<Remediation>,
+}
+
+/// The pool vault must hold at least every staked token
+pub fn pool_solvency(pool: &Pubkey, state: &PoolState, vault_balance: u64) -> Option<Finding> {
+    (vault_balance < state.total_staked).then(|| Finding {
+        key: format!("pool-solvency:{}", pool),
+        severity: Severity::Critical,
+        summary: format!(
+            "Pool {} vault holds {} but total_staked is {} (short {})",
+            pool,
+            vault_balance,
+            state.total_staked,
+            state.total_staked - vault_balance
+        ),
+        remediation: None,
+    })
+}
+
+/// Tasks past their pickup, heartbeat or time-limit deadline
+pub fn task_deadline(task: &Pubkey, state: &TaskState, now: i64) -> Option<Finding> {
+    let (kind, remediation) = if state.heartbeat_expired(now) {
+        ("missed its heartbeat", Remediation::ReportTimeout { task: *task, owner: state.owner, pricing: state.pricing })
+    } else if state.pickup_expired(now) {
+        ("was never picked up", Remediation::ExpireTask { task: *task, owner: state.owner, pricing: state.pricing })
+    } else if state.time_limit_expired(now) {
+        ("ran past its time limit", Remediation::ExpireTask { task: *task, owner: state.owner, pricing: state.pricing })
+    } else {
+        return None;
+    };
+
+    Some(Finding {
+        key: format!("task-deadline:{}", task),
+        severity: Severity::Warning,
+        summary: format!("Task {} {}", task, kind),
+        remediation: Some(remediation),
+    })
+}
+
+/// Rolling count of task failures other than heartbeat and time-limit timeouts,
+/// which are reported separately; these are mostly rejected proofs
+pub struct FailureWindow {
+    window_secs: i64,
+    threshold: usize,
+    seen: HashSet<Pubkey>,
+    failed_at: VecDeque<i64>,
+}
+
+impl FailureWindow {
+    pub fn new(window_secs: i64, threshold: usize) -> Self {
+        Self {
+            window_secs,
+            threshold,
+            seen: HashSet::new(),
+            failed_at: VecDeque::new(),
+        }
+    }
+
+    /// Record `task` if it failed and has not been counted before
+    pub fn observe(&mut self, task: &Pubkey, state: &TaskState) {
+        if let TaskStatus::Failed { error_code, failed_at } = state.status {
+            let timed_out = matches!(error_code, ERROR_HEARTBEAT_TIMEOUT | ERROR_TIME_LIMIT);
+            if !timed_out && self.seen.insert(*task) {
+                let idx = self.failed_at.partition_point(|t| *t <= failed_at);
+                self.failed_at.insert(idx, failed_at);
+            }
+        }
+    }
+
+    /// Alert when failures within the window reach the threshold
+    pub fn finding(&mut self, now: i64) -> Option<Finding> {
+        while self.failed_at.front().is_some_and(|t| now - t > self.window_secs) {
+            self.failed_at.pop_front();
+        }
+        let count = self.failed_at.len();
+        (count >= self.threshold).then(|| Finding {
+            key: "task-failures".into(),
+            severity: Severity::Critical,
+            summary: format!("{} tasks failed in the last {}s", count, self.window_secs),
+            remediation: None,
+        })
+    }
+}
+
+/// Protocol-critical account holding less than its rent-exempt minimum; the runtime
+/// purges it once the balance runs out. A top-up is attached when the shortfall is
+/// within `max_top_up`
+pub fn rent_exemption(
+    account: &Pubkey,
+    kind: &str,
+    lamports: u64,
+    minimum: u64,
+    max_top_up: u64,
+) -> Option<Finding> {
+    let shortfall = minimum.checked_sub(lamports).filter(|s| *s > 0)?;
+    Some(Finding {
+        key: format!("rent-exemption:{}", account),
+        severity: Severity::Critical,
+        summary: format!(
+            "{} {} holds {} lamports, {} short of rent exemption",
+            kind, account, lamports, shortfall
+        ),
+        remediation: (shortfall <= max_top_up).then_some(Remediation::TopUpRent {
+            account: *account,
+            lamports: shortfall,
+        }),
+    })
+}
+
+/// Per-account lamport history for protocol-critical accounts. These only lose
+/// lamports when a realloc shrinks them, so a falling balance at a constant size
+/// points at a drain that will eventually cost the account its rent exemption
+pub struct BalanceTrend {
+    window_secs: i64,
+    max_drop_bps: u64,
+    samples: HashMap<Pubkey, VecDeque<(i64, u64)>>,
+    data_len: HashMap<Pubkey, usize>,
+}
+
+impl BalanceTrend {
+    pub fn new(window_secs: i64, max_drop_bps: u64) -> Self {
+        Self {
+            window_secs,
+            max_drop_bps,
+            samples: HashMap::new(),
+            data_len: HashMap::new(),
+        }
+    }
+
+    /// Record this poll's balance; a resize restarts the history since the
+    /// balance legitimately moves with it
+    pub fn observe(&mut self, account: &Pubkey, lamports: u64, data_len: usize, now: i64) {
+        let history = self.samples.entry(*account).or_default();
+        if self.data_len.insert(*account, data_len) != Some(data_len) {
+            history.clear();
+        }
+        while history.front().is_some_and(|(t, _)| now - t > self.window_secs) {
+            history.pop_front();
+        }
+        history.push_back((now, lamports));
+    }
+
+    /// Alert when the balance fell by more than `max_drop_bps` over the window
+    pub fn finding(&self, account: &Pubkey, kind: &str) -> Option<Finding> {
+        let history = self.samples.get(account)?;
+        let (&(_, first), &(_, last)) = (history.front()?, history.back()?);
+        let drop = first.checked_sub(last).filter(|d| *d > 0)?;
+        let drop_bps = (drop as u128 * 10_000 / first.max(1) as u128) as u64;
+        (drop_bps > self.max_drop_bps).then(|| Finding {
+            key: format!("balance-trend:{}", account),
+            severity: Severity::Warning,
+            summary: format!(
+                "{} {} lost {} lamports ({} bps) in {}s without resizing",
+                kind, account, drop, drop_bps, self.window_secs
+            ),
+            remediation: None,
+        })
+    }
+
+    /// Forget accounts that were closed
+    pub fn retain(&mut self, live: &HashSet<Pubkey>) {
+        self.samples.retain(|k, _| live.contains(k));
+        self.data_len.retain(|k, _| live.contains(k));
+    }
+}
+
+/// Relayer queue deeper than `max`
+pub fn relay_backlog(endpoint: &str, depth: f64, max: u64) -> Option<Finding> {
+    (depth > max as f64).then(|| Finding {
+        key: format!("relay-backlog:{}", endpoint),
+        severity: Severity::Warning,
+        summary: format!("Relayer {} has {} queued messages (max {})", endpoint, depth, max),
+        remediation: None,
+    })
+}
+
+/// Sum of every sample of `name` in a Prometheus text exposition
+pub fn parse_gauge(text: &str, name: &str) -> Option<f64> {
+    let samples: Vec<f64> = text
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter(|line| {
+            line.strip_prefix(name)
+                .is_some_and(|rest| rest.starts_with(' ') || rest.starts_with('{'))
+        })
+        .filter_map(|line| line.split_whitespace().last()?.parse().ok())
+        .collect();
+    (!samples.is_empty()).then(|| samples.iter().sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use haunti_core::state::{HEARTBEAT_TIMEOUT_SECS, PICKUP_TIMEOUT_SECS};
+
+    fn failed(error_code: u32, failed_at: i64) -> TaskState {
+        TaskState {
+            status: TaskStatus::Failed { error_code, failed_at },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_task_deadline_picks_matching_remediation() {
+        let key = Pubkey::new_unique();
+        let mut task = TaskState::default();
+        assert!(task_deadline(&key, &task, PICKUP_TIMEOUT_SECS).is_none());
+        let finding = task_deadline(&key, &task, PICKUP_TIMEOUT_SECS + 1).unwrap();
+        assert!(matches!(finding.remediation, Some(Remediation::ExpireTask { .. })));
+
+        task.status = TaskStatus::Running {
+            worker: Pubkey::new_unique(),
+            started_at: 0,
+            last_heartbeat: 0,
+        };
+        let finding = task_deadline(&key, &task, HEARTBEAT_TIMEOUT_SECS + 1).unwrap();
+        assert!(matches!(finding.remediation, Some(Remediation::ReportTimeout { .. })));
+        assert_eq!(finding.key, format!("task-deadline:{}", key));
+
+        task.time_limit = 10;
+        let finding = task_deadline(&key, &task, 11).unwrap();
+        assert!(matches!(finding.remediation, Some(Remediation::ExpireTask { .. })));
+    }
+
+    #[test]
+    fn test_failure_window_counts_each_task_once() {
+        let mut window = FailureWindow::new(100, 2);
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        window.observe(&a, &failed(7, 50));
+        window.observe(&a, &failed(7, 50));
+        window.observe(&b, &failed(ERROR_HEARTBEAT_TIMEOUT, 60));
+        assert!(window.finding(120).is_none());
+
+        window.observe(&c, &failed(7, 110));
+        assert!(window.finding(120).is_some());
+        // `a` ages out of the window
+        assert!(window.finding(151).is_none());
+    }
+
+    #[test]
+    fn test_rent_top_up_capped_and_trend_resets_on_resize() {
+        let key = Pubkey::new_unique();
+        assert!(rent_exemption(&key, "PoolState", 1_000, 1_000, 500).is_none());
+        let finding = rent_exemption(&key, "PoolState", 800, 1_000, 500).unwrap();
+        assert_eq!(
+            finding.remediation,
+            Some(Remediation::TopUpRent { account: key, lamports: 200 })
+        );
+        assert!(rent_exemption(&key, "PoolState", 100, 1_000, 500).unwrap().remediation.is_none());
+
+        let mut trend = BalanceTrend::new(100, 500);
+        trend.observe(&key, 10_000, 64, 0);
+        trend.observe(&key, 9_600, 64, 30);
+        assert!(trend.finding(&key, "PoolState").is_none());
+        trend.observe(&key, 9_000, 64, 60);
+        assert!(trend.finding(&key, "PoolState").is_some());
+
+        // Shrinking refunds rent, which is expected
+        trend.observe(&key, 5_000, 32, 90);
+        assert!(trend.finding(&key, "PoolState").is_none());
+    }
+
+    #[test]
+    fn test_parse_gauge_sums_labelled_samples() {
+        let text = "# TYPE haunti_relay_queue_depth gauge\n\
+                    haunti_relay_queue_depth{chain=\"ethereum\"} 40\n\
+                    haunti_relay_queue_depth{chain=\"polygon\"} 2\n\
+                    haunti_relay_queue_depth_max 500\n";
+        assert_eq!(parse_gauge(text, RELAY_BACKLOG_METRIC), Some(42.0));
+        assert_eq!(parse_gauge(text, "missing"), None);
+        assert!(relay_backlog("r1", 42.0, 40).is_some());
+        assert!(relay_backlog("r1", 40.0, 40).is_none());
+    }
+}
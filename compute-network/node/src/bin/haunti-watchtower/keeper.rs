@@ -0,0 +1,57 @@
+//! Remediation transactions submitted for the keeper reward, plus rent top-ups the
+//! keeper pays for itself
+
+use anchor_client::Program;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    system_instruction,
+};
+use std::sync::Arc;
+
+use crate::checks::Remediation;
+
+/// Submit the permissionless instruction that clears `remediation`; the program's
+/// payer signs and collects the keeper reward
+pub async fn remediate(
+    core: &Program<Arc<Keypair>>,
+    remediation: Remediation,
+) -> anyhow::Result<Signature> {
+    let keeper = core.payer();
+    let (event_counter, _) = Pubkey::find_program_address(&[b"event_counter"], &haunti_core::ID);
+    let request = core.request();
+    let request = match remediation {
+        // Priced inference tasks hand their queue slot back to the pricing account
+        Remediation::ExpireTask { task, owner, pricing } => request
+            .accounts(haunti_core::accounts::ExpireTask {
+                task,
+                owner,
+                event_counter,
+                pricing,
+                keeper,
+            })
+            .args(haunti_core::instruction::ExpireTask {}),
+        Remediation::ReportTimeout { task, owner, pricing } => request
+            .accounts(haunti_core::accounts::ReportTimeout {
+                task,
+                owner,
+                event_counter,
+                pricing,
+                keeper,
+            })
+            .args(haunti_core::instruction::ReportTimeout {}),
+        // Crediting lamports needs no signature from the account's owner program
+        Remediation::TopUpRent { account, lamports } => {
+            request.instruction(system_instruction::transfer(&keeper, &account, lamports))
+        }
+    };
+    Ok(request.send().await?)
+}
+
+/// Account a remediation targets, for logging
+pub fn target(remediation: &Remediation) -> Pubkey {
+    match remediation {
+        Remediation::ExpireTask { task, .. } | Remediation::ReportTimeout { task, .. } => *task,
+        Remediation::TopUpRent { account, .. } => *account,
+    }
+}
@@ -0,0 +1,316 @@
+//! `haunti-watchtower`: polls pool solvency, task deadlines, task failures, relay
+//! backlogs and the rent exemption of protocol-critical accounts, alerts on
+//! anomalies, and optionally clears stuck tasks for the keeper reward and tops up
+//! accounts falling below rent exemption
+
+mod alerts;
+mod checks;
+mod keeper;
+
+use anchor_client::{Client, Cluster, Program};
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anyhow::Context;
+use clap::Parser;
+use haunti_core::state::TaskState;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use token_vault::PoolState;
+use tracing::{info, warn};
+use tracing_subscriber::{fmt, EnvFilter};
+
+use alerts::{AlertSink, Alerter};
+use checks::{BalanceTrend, FailureWindow, Finding, RELAY_BACKLOG_METRIC};
+
+/// `getMultipleAccounts` limit
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Debug, Parser)]
+#[clap(version, about = "Monitors Haunti on-chain state and alerts on anomalies")]
+struct Cli {
+    #[clap(long, env = "HAUNTI_RPC_URL", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    /// Seconds between polls
+    #[clap(long, default_value_t = 30)]
+    interval_secs: u64,
+
+    /// Webhook receiving JSON alerts; may be repeated
+    #[clap(long = "webhook", env = "WATCHTOWER_WEBHOOKS", value_delimiter = ',')]
+    webhooks: Vec<String>,
+
+    /// PagerDuty Events v2 routing key
+    #[clap(long, env = "PAGERDUTY_ROUTING_KEY")]
+    pagerduty_routing_key: Option<String>,
+
+    /// Prometheus endpoint of a relayer exporting `haunti_relay_queue_depth`; may be
+    /// repeated
+    #[clap(long = "relay-metrics", value_delimiter = ',')]
+    relay_metrics: Vec<String>,
+
+    /// Relayer queue depth above which to alert
+    #[clap(long, default_value_t = 100)]
+    max_relay_backlog: u64,
+
+    /// Task failures within `failure_window_secs` that trigger an alert
+    #[clap(long, default_value_t = 5)]
+    failure_threshold: usize,
+
+    #[clap(long, default_value_t = 3_600)]
+    failure_window_secs: i64,
+
+    /// Keypair that submits `expire_task` / `report_timeout` and collects the keeper
+    /// reward; without it the watchtower only alerts
+    #[clap(long)]
+    keeper_keypair: Option<PathBuf>,
+
+    /// Largest rent top-up the keeper pays into one account, in lamports; larger
+    /// shortfalls only alert
+    #[clap(long, default_value_t = 10_000_000)]
+    max_rent_top_up: u64,
+
+    /// Window over which a critical account's balance trend is measured
+    #[clap(long, default_value_t = 86_400)]
+    balance_window_secs: i64,
+
+    /// Balance drop within `balance_window_secs`, in basis points, that alerts
+    #[clap(long, default_value_t = 100)]
+    max_balance_drop_bps: u64,
+}
+
+struct Watchtower {
+    vault: Program<Arc<Keypair>>,
+    core: Program<Arc<Keypair>>,
+    http: reqwest::Client,
+    relay_metrics: Vec<String>,
+    max_relay_backlog: u64,
+    failures: FailureWindow,
+    balances: BalanceTrend,
+    max_rent_top_up: u64,
+    /// Rent-exempt minimum by data length; rent parameters do not change at runtime
+    rent_minimums: HashMap<usize, u64>,
+    remediate: bool,
+}
+
+/// Critical account fetched for the rent check
+struct RentSample {
+    key: Pubkey,
+    kind: &'static str,
+    lamports: u64,
+    data_len: usize,
+}
+
+impl Watchtower {
+    /// Run every check once; a check that cannot fetch its data is skipped with a
+    /// warning rather than failing the whole poll
+    async fn poll(&mut self) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+
+        match self.check_pools().await {
+            Ok(mut pool_findings) => findings.append(&mut pool_findings),
+            Err(e) => warn!(error = %e, "pool check failed"),
+        }
+
+        match self.check_rent(now).await {
+            Ok(mut rent_findings) => findings.append(&mut rent_findings),
+            Err(e) => warn!(error = %e, "rent check failed"),
+        }
+
+        match self.core.accounts::<TaskState>(vec![]).await {
+            Ok(tasks) => {
+                for (key, task) in &tasks {
+                    self.failures.observe(key, task);
+                    findings.extend(checks::task_deadline(key, task, now));
+                }
+                findings.extend(self.failures.finding(now));
+            }
+            Err(e) => warn!(error = %e, "task check failed"),
+        }
+
+        for endpoint in &self.relay_metrics {
+            match self.relay_depth(endpoint).await {
+                Ok(depth) => {
+                    findings.extend(checks::relay_backlog(endpoint, depth, self.max_relay_backlog))
+                }
+                Err(e) => warn!(%endpoint, error = %e, "relay check failed"),
+            }
+        }
+
+        findings
+    }
+
+    async fn check_pools(&self) -> anyhow::Result<Vec<Finding>> {
+        let rpc = self.vault.rpc();
+        let mut findings = Vec::new();
+        for (pool, state) in self.vault.accounts::<PoolState>(vec![]).await? {
+            let (vault, _) =
+                Pubkey::find_program_address(&[b"vault", pool.as_ref()], &token_vault::ID);
+            let balance: u64 = rpc
+                .get_token_account_balance(&vault)
+                .await
+                .with_context(|| format!("Failed to fetch vault of pool {}", pool))?
+                .amount
+                .parse()?;
+            findings.extend(checks::pool_solvency(&pool, &state, balance));
+        }
+        Ok(findings)
+    }
+
+    /// Rent exemption and balance trend of the accounts the protocol cannot run
+    /// without. Per-user accounts (tasks, stakes, votes) are left to their owners
+    async fn check_rent(&mut self, now: i64) -> anyhow::Result<Vec<Finding>> {
+        use haunti_core::state as core_state;
+        let (vault, program) = (&self.vault, &self.core);
+        let samples: Vec<RentSample> = [
+            rent_samples::<token_vault::PoolState>(vault, "PoolState").await?,
+            rent_samples::<token_vault::PoolConfig>(vault, "PoolConfig").await?,
+            rent_samples::<token_vault::DelegationPool>(vault, "DelegationPool").await?,
+            rent_samples::<core_state::DepositConfig>(program, "DepositConfig").await?,
+            rent_samples::<core_state::FeatureFlags>(program, "FeatureFlags").await?,
+            rent_samples::<core_state::SizeLimits>(program, "SizeLimits").await?,
+            rent_samples::<core_state::ContentPolicy>(program, "ContentPolicy").await?,
+            rent_samples::<core_state::PaymentBridgeConfig>(program, "PaymentBridgeConfig").await?,
+            rent_samples::<core_state::StatsConfig>(program, "StatsConfig").await?,
+            rent_samples::<core_state::VerifierRegistry>(program, "VerifierRegistry").await?,
+            rent_samples::<core_state::OracleFeed>(program, "OracleFeed").await?,
+            rent_samples::<core_state::ModelIndex>(program, "ModelIndex").await?,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let rpc = self.core.rpc();
+        let mut findings = Vec::new();
+        for sample in &samples {
+            let minimum = match self.rent_minimums.get(&sample.data_len) {
+                Some(minimum) => *minimum,
+                None => {
+                    let minimum =
+                        rpc.get_minimum_balance_for_rent_exemption(sample.data_len).await?;
+                    self.rent_minimums.insert(sample.data_len, minimum);
+                    minimum
+                }
+            };
+            findings.extend(checks::rent_exemption(
+                &sample.key,
+                sample.kind,
+                sample.lamports,
+                minimum,
+                self.max_rent_top_up,
+            ));
+            self.balances.observe(&sample.key, sample.lamports, sample.data_len, now);
+            findings.extend(self.balances.finding(&sample.key, sample.kind));
+        }
+        self.balances.retain(&samples.iter().map(|s| s.key).collect::<HashSet<_>>());
+        Ok(findings)
+    }
+
+    async fn relay_depth(&self, endpoint: &str) -> anyhow::Result<f64> {
+        let text = self.http.get(endpoint).send().await?.error_for_status()?.text().await?;
+        checks::parse_gauge(&text, RELAY_BACKLOG_METRIC)
+            .with_context(|| format!("{} not exported", RELAY_BACKLOG_METRIC))
+    }
+
+    async fn remediate(&self, findings: &[Finding]) {
+        if !self.remediate {
+            return;
+        }
+        for remediation in findings.iter().filter_map(|f| f.remediation) {
+            let target = keeper::target(&remediation);
+            match keeper::remediate(&self.core, remediation).await {
+                Ok(signature) => info!(%target, %signature, "remediated"),
+                // Another keeper may have cleared it first
+                Err(e) => warn!(%target, error = %e, "remediation failed"),
+            }
+        }
+    }
+}
+
+/// Lamports and size of every `T` account owned by `program`
+async fn rent_samples<T: AccountDeserialize + Discriminator>(
+    program: &Program<Arc<Keypair>>,
+    kind: &'static str,
+) -> anyhow::Result<Vec<RentSample>> {
+    let keys: Vec<Pubkey> = program
+        .accounts::<T>(vec![])
+        .await
+        .with_context(|| format!("Failed to list {} accounts", kind))?
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    let rpc = program.rpc();
+    let mut samples = Vec::with_capacity(keys.len());
+    for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = rpc.get_multiple_accounts(chunk).await?;
+        // Accounts closed since they were listed come back as None
+        for (key, account) in chunk.iter().zip(accounts) {
+            if let Some(account) = account {
+                samples.push(RentSample {
+                    key: *key,
+                    kind,
+                    lamports: account.lamports,
+                    data_len: account.data.len(),
+                });
+            }
+        }
+    }
+    Ok(samples)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    fmt().with_env_filter(EnvFilter::from_default_env()).init();
+    let cli = Cli::parse();
+
+    // Read-only runs still need a payer for the client; it never signs
+    let (payer, remediate) = match &cli.keeper_keypair {
+        Some(path) => (
+            read_keypair_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read keypair {}: {}", path.display(), e))?,
+            true,
+        ),
+        None => (Keypair::new(), false),
+    };
+    let cluster = Cluster::Custom(cli.rpc_url.clone(), cli.rpc_url.replacen("http", "ws", 1));
+    let client = Client::new_with_options(cluster, Arc::new(payer), CommitmentConfig::confirmed());
+
+    let mut sinks: Vec<AlertSink> = cli.webhooks.into_iter().map(AlertSink::Webhook).collect();
+    if let Some(routing_key) = cli.pagerduty_routing_key {
+        sinks.push(AlertSink::PagerDuty { routing_key });
+    }
+    let mut alerter = Alerter::new(sinks);
+
+    let mut watchtower = Watchtower {
+        vault: client.program(token_vault::ID)?,
+        core: client.program(haunti_core::ID)?,
+        http: reqwest::Client::new(),
+        relay_metrics: cli.relay_metrics,
+        max_relay_backlog: cli.max_relay_backlog,
+        failures: FailureWindow::new(cli.failure_window_secs, cli.failure_threshold),
+        balances: BalanceTrend::new(cli.balance_window_secs, cli.max_balance_drop_bps),
+        max_rent_top_up: cli.max_rent_top_up,
+        rent_minimums: HashMap::new(),
+        remediate,
+    };
+
+    info!(rpc = %cli.rpc_url, remediate, "watchtower started");
+    let mut interval = tokio::time::interval(Duration::from_secs(cli.interval_secs));
+    loop {
+        interval.tick().await;
+        let findings = watchtower.poll().await;
+        alerter.publish(&findings).await;
+        watchtower.remediate(&findings).await;
+    }
+}
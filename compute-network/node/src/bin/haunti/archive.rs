@@ -0,0 +1,591 @@
+//! Cold-storage bundles of completed tasks
+//!
+//! `export` packs a task's account, every transaction that touched it, the proof
+//! it was settled with and the storage objects it references into a tar file. The
+//! bundle's `manifest.json` lists the SHA-256 of every other file and is signed by
+//! the exporter; the bundle is named by the manifest's own hash, so a stored copy
+//! can be checked against the name it was filed under.
+//!
+//! `verify` needs no network: it re-checks the manifest signature and digests,
+//! each transaction's signatures, the proof against the task's model, and stored
+//! objects against the task's content hashes. Events are recovered from RPC
+//! metadata, which transaction signatures do not cover; only the manifest
+//! signature vouches for them.
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use anyhow::{bail, ensure, Context};
+use clap::Subcommand;
+use haunti_core::{
+    decode_cpi_event,
+    state::{TaskState, TaskStatus},
+    CoreEvent,
+};
+use haunti_network::storage::IpfsClient;
+use serde::{Deserialize, Serialize};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_program::{hash::hash, keccak};
+use solana_sdk::{
+    bs58,
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, UiInstruction, UiTransactionEncoding,
+    UiTransactionStatusMeta,
+};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Bumped on any incompatible change to the bundle layout
+pub const FORMAT: &str = "haunti-archive/1";
+const MANIFEST: &str = "manifest.json";
+const MANIFEST_SIG: &str = "manifest.sig";
+const TASK_ACCOUNT: &str = "accounts/task.bin";
+const PROOF: &str = "proof.bin";
+const PUBLIC_INPUTS: &str = "public_inputs.bin";
+
+#[derive(Debug, Subcommand)]
+pub enum ArchiveCommand {
+    /// Bundle a completed task into `<out>/<bundle id>.tar`
+    Export {
+        task: Pubkey,
+        #[clap(long, default_value = ".")]
+        out: PathBuf,
+        /// CID of the encrypted model; fetched and checked against the model hash
+        #[clap(long)]
+        model_cid: Option<String>,
+        /// CID of the encrypted input; fetched and checked against the input hash
+        #[clap(long)]
+        data_cid: Option<String>,
+        /// Concatenated 32-byte public inputs, so the proof can be re-verified
+        #[clap(long)]
+        public_inputs: Option<PathBuf>,
+    },
+    /// Re-verify a bundle offline
+    Verify { bundle: PathBuf },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDigest {
+    pub sha256: String,
+    pub len: u64,
+}
+
+/// Signed index of a bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub task: String,
+    pub program: String,
+    /// Slot the task account was read at
+    pub slot: u64,
+    pub exported_at: i64,
+    /// Exporter whose key signed the manifest
+    pub signer: String,
+    /// Storage objects by role (`model`, `input`)
+    pub cids: BTreeMap<String, String>,
+    pub files: BTreeMap<String, FileDigest>,
+}
+
+/// Per-transaction data that is not part of the signed transaction
+#[derive(Debug, Serialize, Deserialize)]
+struct TransactionRecord {
+    slot: u64,
+    block_time: Option<i64>,
+    failed: bool,
+    /// Hex-encoded haunti-core self-CPI event data
+    events: Vec<String>,
+}
+
+/// Manifest bytes, their signature, and the files they cover
+pub struct Bundle {
+    pub manifest: Vec<u8>,
+    pub signature: Signature,
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+impl Bundle {
+    /// Index `files` into `manifest` and sign it
+    pub fn seal(
+        mut manifest: Manifest,
+        files: BTreeMap<String, Vec<u8>>,
+        signer: &Keypair,
+    ) -> anyhow::Result<Self> {
+        manifest.signer = signer.pubkey().to_string();
+        manifest.files = files
+            .iter()
+            .map(|(path, data)| (path.clone(), digest(data)))
+            .collect();
+        let manifest = serde_json::to_vec_pretty(&manifest)?;
+        let signature = signer.sign_message(&manifest);
+        Ok(Self { manifest, signature, files })
+    }
+
+    /// Content address: hex SHA-256 of the manifest
+    pub fn id(&self) -> String {
+        hex::encode(hash(&self.manifest).to_bytes())
+    }
+
+    /// Check the signature and that the files are exactly those indexed
+    pub fn open(&self) -> anyhow::Result<Manifest> {
+        let manifest: Manifest =
+            serde_json::from_slice(&self.manifest).context("Malformed manifest")?;
+        ensure!(manifest.format == FORMAT, "Unsupported bundle format {}", manifest.format);
+        let signer = Pubkey::from_str(&manifest.signer).context("Invalid manifest signer")?;
+        ensure!(
+            self.signature.verify(signer.as_ref(), &self.manifest),
+            "Manifest signature does not match signer {}",
+            signer
+        );
+
+        for (path, expected) in &manifest.files {
+            let data = self
+                .files
+                .get(path)
+                .with_context(|| format!("{} is indexed but missing", path))?;
+            ensure!(digest(data) == *expected, "{} does not match its digest", path);
+        }
+        if let Some(extra) = self.files.keys().find(|p| !manifest.files.contains_key(*p)) {
+            bail!("{} is not covered by the manifest", extra);
+        }
+        Ok(manifest)
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let mut tar = tar::Builder::new(File::create(path)?);
+        let mut append = |name: &str, data: &[u8]| -> std::io::Result<()> {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, data)
+        };
+        append(MANIFEST, &self.manifest)?;
+        append(MANIFEST_SIG, self.signature.as_ref())?;
+        for (name, data) in &self.files {
+            append(name, data)?;
+        }
+        tar.into_inner()?;
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let mut tar = tar::Archive::new(
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?,
+        );
+        let mut files = BTreeMap::new();
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().into_owned();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            files.insert(name, data);
+        }
+        let manifest = files.remove(MANIFEST).context("Bundle has no manifest")?;
+        let signature = files.remove(MANIFEST_SIG).context("Bundle has no manifest signature")?;
+        let signature = Signature::try_from(signature.as_slice())
+            .map_err(|_| anyhow::anyhow!("Malformed manifest signature"))?;
+        Ok(Self { manifest, signature, files })
+    }
+}
+
+fn digest(data: &[u8]) -> FileDigest {
+    FileDigest {
+        sha256: hex::encode(hash(data).to_bytes()),
+        len: data.len() as u64,
+    }
+}
+
+/// Fetch everything needed to re-verify `task` later and seal it
+pub async fn export(
+    rpc: &RpcClient,
+    signer: &Keypair,
+    task: &Pubkey,
+    cids: BTreeMap<String, String>,
+    public_inputs: Option<Vec<u8>>,
+) -> anyhow::Result<Bundle> {
+    let account = rpc
+        .get_account_with_commitment(task, CommitmentConfig::finalized())
+        .await?;
+    let slot = account.context.slot;
+    let account = account.value.context("Task account not found")?;
+    ensure!(account.owner == haunti_core::ID, "Account is not owned by haunti-core");
+    let state = TaskState::try_deserialize(&mut account.data.as_slice())
+        .context("Account is not a Haunti task")?;
+    ensure!(
+        matches!(state.status, TaskStatus::Completed { .. }),
+        "Task is not completed (status: {:?})",
+        state.status
+    );
+
+    let mut files = BTreeMap::new();
+    files.insert(TASK_ACCOUNT.to_string(), account.data);
+
+    // Oldest first, so the bundle reads as the task's history
+    let mut signatures = rpc.get_signatures_for_address(task).await?;
+    signatures.reverse();
+    for entry in signatures {
+        let tx = rpc
+            .get_transaction_with_config(
+                &Signature::from_str(&entry.signature)?,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::finalized()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to fetch {}", entry.signature))?;
+        let versioned = tx
+            .transaction
+            .transaction
+            .decode()
+            .with_context(|| format!("Failed to decode {}", entry.signature))?;
+        let meta = tx.transaction.meta.as_ref();
+
+        if let Some(proof) = submitted_proof(&versioned) {
+            files.insert(PROOF.to_string(), proof);
+        }
+        let record = TransactionRecord {
+            slot: tx.slot,
+            block_time: tx.block_time,
+            failed: meta.map_or(false, |m| m.err.is_some()),
+            events: meta
+                .map(|m| event_data(&versioned, m))
+                .unwrap_or_default()
+                .iter()
+                .map(hex::encode)
+                .collect(),
+        };
+        let path = format!("transactions/{}", entry.signature);
+        files.insert(format!("{}.bin", path), bincode::serialize(&versioned)?);
+        files.insert(format!("{}.json", path), serde_json::to_vec_pretty(&record)?);
+    }
+    ensure!(files.contains_key(PROOF), "No submit_proof transaction found for the task");
+
+    if let Some(inputs) = public_inputs {
+        ensure!(
+            !inputs.is_empty() && inputs.len() % 32 == 0,
+            "Public inputs must be a non-empty concatenation of 32-byte values"
+        );
+        files.insert(PUBLIC_INPUTS.to_string(), inputs);
+    }
+
+    let ipfs = IpfsClient::default();
+    for (role, cid) in &cids {
+        let data = ipfs
+            .get_cid(cid)
+            .await
+            .with_context(|| format!("Failed to fetch {} {}", role, cid))?;
+        files.insert(format!("content/{}", role), data);
+    }
+
+    let manifest = Manifest {
+        format: FORMAT.to_string(),
+        task: task.to_string(),
+        program: haunti_core::ID.to_string(),
+        slot,
+        exported_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default(),
+        signer: String::new(),
+        cids,
+        files: BTreeMap::new(),
+    };
+    Bundle::seal(manifest, files, signer)
+}
+
+/// Proof bytes of a top-level `submit_proof` instruction in `tx`
+fn submitted_proof(tx: &VersionedTransaction) -> Option<Vec<u8>> {
+    let keys = tx.message.static_account_keys();
+    tx.message.instructions().iter().find_map(|ix| {
+        if keys.get(ix.program_id_index as usize) != Some(&haunti_core::ID) {
+            return None;
+        }
+        let args = ix
+            .data
+            .strip_prefix(haunti_core::instruction::SubmitProof::DISCRIMINATOR.as_slice())?;
+        haunti_core::instruction::SubmitProof::deserialize(&mut &args[..])
+            .ok()
+            .map(|submit| submit.proof)
+    })
+}
+
+/// Inner instruction data addressed to haunti-core that carries an event
+fn event_data(tx: &VersionedTransaction, meta: &UiTransactionStatusMeta) -> Vec<Vec<u8>> {
+    // Static keys first, then v0 lookup-table writable and readonly addresses
+    let mut account_keys: Vec<String> = tx
+        .message
+        .static_account_keys()
+        .iter()
+        .map(Pubkey::to_string)
+        .collect();
+    if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+        account_keys.extend(loaded.writable.iter().cloned());
+        account_keys.extend(loaded.readonly.iter().cloned());
+    }
+    let program = haunti_core::ID.to_string();
+
+    let OptionSerializer::Some(inner) = &meta.inner_instructions else {
+        return Vec::new();
+    };
+    inner
+        .iter()
+        .flat_map(|set| &set.instructions)
+        .filter_map(|ix| match ix {
+            UiInstruction::Compiled(ix) => Some(ix),
+            _ => None,
+        })
+        .filter(|ix| account_keys.get(ix.program_id_index as usize) == Some(&program))
+        .filter_map(|ix| bs58::decode(&ix.data).into_vec().ok())
+        .filter(|data| decode_cpi_event(data).is_some())
+        .collect()
+}
+
+fn event_name(event: &CoreEvent) -> &'static str {
+    match event {
+        CoreEvent::TaskCreated(_) => "TaskCreated",
+        CoreEvent::InferenceTaskPriced(_) => "InferenceTaskPriced",
+        CoreEvent::ProofSubmitted(_) => "ProofSubmitted",
+        CoreEvent::EvidenceSubmitted(_) => "EvidenceSubmitted",
+        CoreEvent::StatsCheckpointPublished(_) => "StatsCheckpointPublished",
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveCheck {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveReport {
+    pub bundle: String,
+    pub task: String,
+    pub signer: String,
+    pub checks: Vec<ArchiveCheck>,
+    pub verified: bool,
+}
+
+fn check(checks: &mut Vec<ArchiveCheck>, name: &str, result: anyhow::Result<String>) {
+    let (ok, detail) = match result {
+        Ok(detail) => (true, detail),
+        Err(e) => (false, format!("{:#}", e)),
+    };
+    checks.push(ArchiveCheck { name: name.to_string(), ok, detail });
+}
+
+/// Re-verify a bundle end to end without network access
+pub fn verify(bundle: &Bundle) -> anyhow::Result<ArchiveReport> {
+    let manifest = bundle.open()?;
+    let task = Pubkey::from_str(&manifest.task).context("Invalid task in manifest")?;
+    let mut checks = Vec::new();
+    check(&mut checks, "manifest", Ok(format!("{} files signed", manifest.files.len())));
+
+    let state = bundle
+        .files
+        .get(TASK_ACCOUNT)
+        .context("no task account archived")
+        .and_then(|data| {
+            TaskState::try_deserialize(&mut data.as_slice()).context("not a Haunti task")
+        });
+    check(
+        &mut checks,
+        "task_account",
+        match &state {
+            Ok(TaskState { status: TaskStatus::Completed { completed_at, .. }, .. }) => {
+                Ok(format!("completed at {}", completed_at))
+            }
+            Ok(state) => Err(anyhow::anyhow!("task is not completed: {:?}", state.status)),
+            Err(e) => Err(anyhow::anyhow!("{:#}", e)),
+        },
+    );
+
+    let mut proofs = Vec::new();
+    let mut events: BTreeMap<&str, usize> = BTreeMap::new();
+    for (path, data) in &bundle.files {
+        let Some(signature) = path
+            .strip_prefix("transactions/")
+            .and_then(|p| p.strip_suffix(".bin"))
+        else {
+            continue;
+        };
+        let result = (|| -> anyhow::Result<String> {
+            let tx: VersionedTransaction = bincode::deserialize(data)?;
+            ensure!(
+                tx.signatures.first().map(Signature::to_string).as_deref() == Some(signature),
+                "first signature does not match the file name"
+            );
+            ensure!(
+                tx.verify_with_results().iter().all(|ok| *ok),
+                "signature verification failed"
+            );
+            ensure!(
+                tx.message.static_account_keys().contains(&task),
+                "does not reference the task"
+            );
+            proofs.extend(submitted_proof(&tx));
+
+            let record: TransactionRecord = serde_json::from_slice(
+                bundle
+                    .files
+                    .get(&format!("transactions/{}.json", signature))
+                    .context("metadata missing")?,
+            )?;
+            for data in &record.events {
+                let event = decode_cpi_event(&hex::decode(data)?).context("undecodable event")?;
+                *events.entry(event_name(&event)).or_default() += 1;
+            }
+            Ok(format!(
+                "slot {}{}",
+                record.slot,
+                if record.failed { ", failed on-chain" } else { "" }
+            ))
+        })();
+        check(&mut checks, &format!("tx:{}", signature), result);
+    }
+    check(
+        &mut checks,
+        "events",
+        Ok(events
+            .iter()
+            .map(|(name, count)| format!("{} x{}", name, count))
+            .collect::<Vec<_>>()
+            .join(", ")),
+    );
+
+    let archived_proof = bundle.files.get(PROOF);
+    check(&mut checks, "proof", (|| -> anyhow::Result<String> {
+        let proof = archived_proof.context("no proof archived")?;
+        ensure!(proofs.contains(proof), "proof.bin does not match any submit_proof transaction");
+        let Some(inputs) = bundle.files.get(PUBLIC_INPUTS) else {
+            return Ok("matches submit_proof; not re-verified, no public inputs archived".into());
+        };
+        let inputs: Vec<[u8; 32]> = inputs
+            .chunks(32)
+            .map(<[u8; 32]>::try_from)
+            .collect::<Result<_, _>>()
+            .context("public inputs are not 32-byte values")?;
+        let model_hash = match &state {
+            Ok(state) => state.model_hash,
+            Err(_) => bail!("task account unreadable"),
+        };
+        let outcome = haunti_verifier::preverify::preverify_proof(proof, &inputs, &model_hash);
+        ensure!(
+            outcome.ok,
+            "rejected: {}",
+            outcome.error_name.unwrap_or_default()
+        );
+        Ok("matches submit_proof and verifies against the model".into())
+    })());
+
+    if let Ok(state) = &state {
+        for (role, expected) in [("model", &state.model_hash), ("input", &state.input_hash)] {
+            let Some(data) = bundle.files.get(&format!("content/{}", role)) else {
+                continue;
+            };
+            let actual = keccak::hash(data).0;
+            let cid = manifest.cids.get(role).map_or("content", String::as_str);
+            check(
+                &mut checks,
+                &format!("content:{}", role),
+                if actual == *expected {
+                    Ok(format!("{} matches {}_hash", cid, role))
+                } else {
+                    Err(anyhow::anyhow!(
+                        "keccak {} != {}",
+                        hex::encode(actual),
+                        hex::encode(expected)
+                    ))
+                },
+            );
+        }
+    }
+
+    let verified = checks.iter().all(|c| c.ok);
+    Ok(ArchiveReport {
+        bundle: bundle.id(),
+        task: manifest.task,
+        signer: manifest.signer,
+        checks,
+        verified,
+    })
+}
+
+pub fn print_report(report: &ArchiveReport, json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+
+    println!("Bundle {} for task {}", report.bundle, report.task);
+    println!("  signed by {}", report.signer);
+    for check in &report.checks {
+        let status = if check.ok { "OK" } else { "FAIL" };
+        println!("  {:<14} {:<5} {}", check.name, status, check.detail);
+    }
+    println!("Bundle {}", if report.verified { "verified" } else { "NOT verified" });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sealed(signer: &Keypair) -> Bundle {
+        let manifest = Manifest {
+            format: FORMAT.to_string(),
+            task: Pubkey::new_unique().to_string(),
+            program: haunti_core::ID.to_string(),
+            slot: 1,
+            exported_at: 0,
+            signer: String::new(),
+            cids: BTreeMap::new(),
+            files: BTreeMap::new(),
+        };
+        let files = BTreeMap::from([
+            (TASK_ACCOUNT.to_string(), vec![1, 2, 3]),
+            (PROOF.to_string(), vec![4, 5]),
+        ]);
+        Bundle::seal(manifest, files, signer).unwrap()
+    }
+
+    #[test]
+    fn test_sealed_bundle_opens_and_is_content_addressed() {
+        let signer = Keypair::new();
+        let bundle = sealed(&signer);
+        let manifest = bundle.open().unwrap();
+        assert_eq!(manifest.signer, signer.pubkey().to_string());
+        assert_eq!(manifest.files[PROOF].len, 2);
+        assert_eq!(bundle.id(), hex::encode(hash(&bundle.manifest).to_bytes()));
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let signer = Keypair::new();
+
+        let mut bundle = sealed(&signer);
+        bundle.files.get_mut(PROOF).unwrap()[0] ^= 1;
+        assert!(bundle.open().is_err());
+
+        let mut bundle = sealed(&signer);
+        bundle.files.insert("content/model".into(), vec![0]);
+        assert!(bundle.open().is_err());
+
+        // A re-signed manifest changes the bundle id and signer
+        let mut bundle = sealed(&signer);
+        let other = sealed(&Keypair::new());
+        bundle.signature = other.signature;
+        assert!(bundle.open().is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! Verification of the coordinator's admin audit log
+//!
+//! The chain check needs only the file. With `--anchor`, the memo of an anchoring
+//! transaction is fetched and the entry it names must still carry the anchored
+//! hash, which catches a log rewritten wholesale with a fresh, self-consistent chain.
+
+use anyhow::{bail, Context};
+use clap::Subcommand;
+use serde::Serialize;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::audit_log::{self, AuditError};
+
+#[derive(Debug, Subcommand)]
+pub enum AuditCommand {
+    /// Check the hash chain of an audit log, optionally against on-chain anchors
+    Verify {
+        path: PathBuf,
+        /// Signature of an anchoring transaction; may be repeated
+        #[clap(long)]
+        anchor: Vec<String>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnchorCheck {
+    pub signature: String,
+    pub seq: u64,
+    pub anchored: String,
+    /// Hash of entry `seq` in the file, if it has one
+    pub logged: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditReport {
+    pub entries: u64,
+    pub head: String,
+    pub anchors: Vec<AnchorCheck>,
+    /// Why the chain failed to verify
+    pub error: Option<String>,
+    pub verified: bool,
+}
+
+/// Anchored `(seq, hash)` recorded by the memo in transaction `signature`
+async fn fetch_anchor(rpc: &RpcClient, signature: &str) -> anyhow::Result<(u64, [u8; 32])> {
+    let tx = rpc
+        .get_transaction_with_config(
+            &Signature::from_str(signature).context("Invalid anchor signature")?,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::finalized()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await
+        .with_context(|| format!("Failed to fetch {}", signature))?;
+    let tx = tx
+        .transaction
+        .transaction
+        .decode()
+        .with_context(|| format!("Failed to decode {}", signature))?;
+    let keys = tx.message.static_account_keys();
+    let anchor = tx.message.instructions().iter().find_map(|ix| {
+        if keys.get(ix.program_id_index as usize) != Some(&audit_log::MEMO_PROGRAM_ID) {
+            return None;
+        }
+        audit_log::parse_anchor_memo(std::str::from_utf8(&ix.data).ok()?)
+    });
+    match anchor {
+        Some(anchor) => Ok(anchor),
+        None => bail!("{} does not anchor an audit log", signature),
+    }
+}
+
+pub async fn verify(rpc: &RpcClient, path: &Path, signatures: &[String]) -> anyhow::Result<AuditReport> {
+    let mut anchors = Vec::new();
+    for signature in signatures {
+        let (seq, hash) = fetch_anchor(rpc, signature).await?;
+        anchors.push((signature.clone(), seq, hash));
+    }
+
+    let mut logged = vec![None; anchors.len()];
+    let chain = audit_log::verify(path, |entry| {
+        for (slot, (_, seq, _)) in logged.iter_mut().zip(&anchors) {
+            if *seq == entry.seq {
+                *slot = Some(entry.hash);
+            }
+        }
+    });
+    let (head, error) = match chain {
+        Ok(head) => (head, None),
+        Err(e @ AuditError::Io(_)) => return Err(e.into()),
+        Err(e) => (Default::default(), Some(e.to_string())),
+    };
+
+    let anchors: Vec<AnchorCheck> = anchors
+        .into_iter()
+        .zip(logged)
+        .map(|((signature, seq, hash), logged)| AnchorCheck {
+            signature,
+            seq,
+            anchored: hex::encode(hash),
+            logged: logged.map(hex::encode),
+        })
+        .collect();
+    let verified = error.is_none() && anchors.iter().all(|a| a.logged.as_ref() == Some(&a.anchored));
+    Ok(AuditReport {
+        entries: head.len,
+        head: hex::encode(head.hash),
+        anchors,
+        error,
+        verified,
+    })
+}
+
+pub fn print_report(report: &AuditReport, json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+
+    match &report.error {
+        Some(error) => println!("Chain     BROKEN  {}", error),
+        None => println!("Chain     OK      {} entries, head {}", report.entries, report.head),
+    }
+    for anchor in &report.anchors {
+        match &anchor.logged {
+            Some(logged) if *logged == anchor.anchored => {
+                println!("Anchor    OK      entry {} ({})", anchor.seq, anchor.signature)
+            }
+            Some(logged) => println!(
+                "Anchor    MISMATCH  entry {} ({})\n    anchored: {}\n    logged:   {}",
+                anchor.seq, anchor.signature, anchor.anchored, logged
+            ),
+            None => println!("Anchor    MISSING entry {} ({})", anchor.seq, anchor.signature),
+        }
+    }
+    println!("Audit log {}", if report.verified { "verified" } else { "NOT verified" });
+    Ok(())
+}
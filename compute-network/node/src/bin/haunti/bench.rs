@@ -0,0 +1,424 @@
+//! Benchmark history and regression checks
+//!
+//! `record` reads the estimates criterion left under `target/criterion` (FHE
+//! latency, proof time, scheduler throughput, whichever suites ran) and appends
+//! them as one run to a JSON-lines history keyed by git commit and a hardware
+//! fingerprint. `compare` takes the latest run of two commits on the same
+//! hardware and flags every benchmark whose mean time grew by more than the
+//! threshold. Timings from different machines are never compared.
+
+use anyhow::{bail, Context};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use solana_program::hash::hash;
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const DEFAULT_HISTORY: &str = "bench_history.jsonl";
+const DEFAULT_CRITERION_DIR: &str = "target/criterion";
+
+#[derive(Debug, Subcommand)]
+pub enum BenchCommand {
+    /// Append the latest criterion results as a run of the current commit
+    Record {
+        #[clap(long, default_value = DEFAULT_CRITERION_DIR)]
+        criterion_dir: PathBuf,
+        #[clap(long, default_value = DEFAULT_HISTORY)]
+        history: PathBuf,
+        /// Commit to file the run under; defaults to `HEAD`
+        #[clap(long)]
+        commit: Option<String>,
+    },
+    /// Flag benchmarks that slowed down between two recorded commits
+    Compare {
+        /// Baseline revision
+        base: String,
+        /// Revision under test
+        head: String,
+        /// Largest tolerated slowdown of a benchmark's mean, in percent
+        #[clap(long, default_value = "5.0")]
+        threshold: f64,
+        #[clap(long, default_value = DEFAULT_HISTORY)]
+        history: PathBuf,
+        /// Fingerprint of the machine whose runs to compare; defaults to this one
+        #[clap(long)]
+        hardware: Option<String>,
+    },
+}
+
+/// The parts of a machine that move benchmark timings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hardware {
+    pub cpu: String,
+    pub cpu_threads: usize,
+    /// `nvidia-smi` name and memory of each GPU, in device order
+    pub gpus: Vec<String>,
+}
+
+impl Hardware {
+    pub fn detect() -> Self {
+        let cpu = fs::read_to_string("/proc/cpuinfo")
+            .ok()
+            .and_then(|info| {
+                info.lines()
+                    .find(|line| line.starts_with("model name"))
+                    .and_then(|line| line.split_once(':'))
+                    .map(|(_, model)| model.trim().to_string())
+            })
+            .unwrap_or_else(|| std::env::consts::ARCH.to_string());
+        let cpu_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let gpus = Command::new("nvidia-smi")
+            .args(["--query-gpu=name,memory.total", "--format=csv,noheader"])
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .map(|out| {
+                String::from_utf8_lossy(&out.stdout)
+                    .lines()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { cpu, cpu_threads, gpus }
+    }
+
+    /// Short stable id the history is keyed by
+    pub fn fingerprint(&self) -> String {
+        let canonical = serde_json::to_vec(self).expect("hardware serializes");
+        hex::encode(&hash(&canonical).to_bytes()[..8])
+    }
+}
+
+/// One benchmark's estimates, in nanoseconds per iteration
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub mean_ns: f64,
+    pub std_dev_ns: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Run {
+    pub commit: String,
+    /// Uncommitted changes were present when the run was recorded
+    pub dirty: bool,
+    pub hardware: String,
+    pub hardware_detail: Hardware,
+    pub recorded_at: i64,
+    /// Keyed by criterion's full benchmark id, e.g. `FHE Inference/conv/8`
+    pub results: BTreeMap<String, Sample>,
+}
+
+#[derive(Deserialize)]
+struct CriterionBenchmark {
+    full_id: String,
+}
+
+#[derive(Deserialize)]
+struct CriterionEstimate {
+    point_estimate: f64,
+}
+
+#[derive(Deserialize)]
+struct CriterionEstimates {
+    mean: CriterionEstimate,
+    std_dev: CriterionEstimate,
+}
+
+/// Latest estimates of every benchmark under `dir`, read from the `new/`
+/// directories criterion writes after each run
+pub fn collect_criterion(dir: &Path) -> anyhow::Result<BTreeMap<String, Sample>> {
+    let mut results = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let (benchmark, estimates) = (path.join("benchmark.json"), path.join("estimates.json"));
+            if path.file_name() == Some("new".as_ref()) && benchmark.is_file() && estimates.is_file() {
+                let benchmark: CriterionBenchmark = serde_json::from_slice(&fs::read(&benchmark)?)
+                    .with_context(|| format!("Malformed {}", benchmark.display()))?;
+                let estimates: CriterionEstimates = serde_json::from_slice(&fs::read(&estimates)?)
+                    .with_context(|| format!("Malformed {}", estimates.display()))?;
+                results.insert(
+                    benchmark.full_id,
+                    Sample {
+                        mean_ns: estimates.mean.point_estimate,
+                        std_dev_ns: estimates.std_dev.point_estimate,
+                    },
+                );
+            } else if path.file_name() != Some("report".as_ref()) {
+                pending.push(path);
+            }
+        }
+    }
+    Ok(results)
+}
+
+fn append(history: &Path, run: &Run) -> anyhow::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history)
+        .with_context(|| format!("Failed to open {}", history.display()))?;
+    writeln!(file, "{}", serde_json::to_string(run)?)?;
+    Ok(())
+}
+
+fn load(history: &Path) -> anyhow::Result<Vec<Run>> {
+    let file = fs::File::open(history).with_context(|| format!("Failed to open {}", history.display()))?;
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|(i, line)| {
+            serde_json::from_str(&line?).with_context(|| format!("{}:{}: malformed run", history.display(), i + 1))
+        })
+        .collect()
+}
+
+/// Most recent run of `commit` on `hardware`
+fn latest<'a>(runs: &'a [Run], commit: &str, hardware: &str) -> Option<&'a Run> {
+    runs.iter()
+        .filter(|run| run.commit == commit && run.hardware == hardware)
+        .max_by_key(|run| run.recorded_at)
+}
+
+fn git(args: &[&str]) -> anyhow::Result<String> {
+    let out = Command::new("git").args(args).output().context("Failed to run git")?;
+    if !out.status.success() {
+        bail!("git {}: {}", args.join(" "), String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// Full hash of `rev`, so runs are found whatever name the user gives a commit
+fn resolve(rev: &str) -> anyhow::Result<String> {
+    git(&["rev-parse", "--verify", &format!("{}^{{commit}}", rev)])
+}
+
+pub fn record(criterion_dir: &Path, history: &Path, commit: Option<&str>) -> anyhow::Result<Run> {
+    let results = collect_criterion(criterion_dir)?;
+    if results.is_empty() {
+        bail!("No criterion results under {}", criterion_dir.display());
+    }
+    let hardware_detail = Hardware::detect();
+    let run = Run {
+        commit: resolve(commit.unwrap_or("HEAD"))?,
+        dirty: commit.is_none() && !git(&["status", "--porcelain", "--untracked-files=no"])?.is_empty(),
+        hardware: hardware_detail.fingerprint(),
+        hardware_detail,
+        recorded_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+        results,
+    };
+    append(history, &run)?;
+    Ok(run)
+}
+
+#[derive(Debug, Serialize)]
+pub struct Delta {
+    pub benchmark: String,
+    pub base_ns: f64,
+    pub head_ns: f64,
+    /// Change of the mean; positive is slower
+    pub change_pct: f64,
+    pub regressed: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub base: String,
+    pub head: String,
+    pub hardware: String,
+    pub threshold_pct: f64,
+    pub deltas: Vec<Delta>,
+    /// Benchmarks recorded for only one of the two commits
+    pub unmatched: Vec<String>,
+    pub regressions: usize,
+}
+
+/// Compare the benchmarks both runs share
+pub fn diff(base: &Run, head: &Run, threshold_pct: f64) -> Comparison {
+    let deltas: Vec<Delta> = base
+        .results
+        .iter()
+        .filter_map(|(name, before)| {
+            let after = head.results.get(name)?;
+            let change_pct = (after.mean_ns - before.mean_ns) / before.mean_ns * 100.0;
+            Some(Delta {
+                benchmark: name.clone(),
+                base_ns: before.mean_ns,
+                head_ns: after.mean_ns,
+                change_pct,
+                regressed: change_pct > threshold_pct,
+            })
+        })
+        .collect();
+    let unmatched = base
+        .results
+        .keys()
+        .filter(|name| !head.results.contains_key(*name))
+        .chain(head.results.keys().filter(|name| !base.results.contains_key(*name)))
+        .cloned()
+        .collect();
+    Comparison {
+        base: base.commit.clone(),
+        head: head.commit.clone(),
+        hardware: head.hardware.clone(),
+        threshold_pct,
+        regressions: deltas.iter().filter(|d| d.regressed).count(),
+        deltas,
+        unmatched,
+    }
+}
+
+pub fn compare(
+    history: &Path,
+    base: &str,
+    head: &str,
+    threshold_pct: f64,
+    hardware: Option<&str>,
+) -> anyhow::Result<Comparison> {
+    let runs = load(history)?;
+    let hardware = hardware.map_or_else(|| Hardware::detect().fingerprint(), str::to_string);
+    let find = |rev: &str| -> anyhow::Result<&Run> {
+        let commit = resolve(rev)?;
+        latest(&runs, &commit, &hardware)
+            .with_context(|| format!("No run of {} recorded on hardware {}", rev, hardware))
+    };
+    Ok(diff(find(base)?, find(head)?, threshold_pct))
+}
+
+pub fn print_run(run: &Run, json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(run)?);
+        return Ok(());
+    }
+
+    println!(
+        "Recorded {} benchmarks for {}{} on {}",
+        run.results.len(),
+        run.commit,
+        if run.dirty { " (dirty)" } else { "" },
+        run.hardware
+    );
+    Ok(())
+}
+
+pub fn print_report(report: &Comparison, json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+
+    println!("{} -> {} on {}", report.base, report.head, report.hardware);
+    for delta in &report.deltas {
+        println!(
+            "  {:<10} {:>+8.2}%  {:>14.0} ns -> {:>14.0} ns  {}",
+            if delta.regressed { "REGRESSED" } else { "ok" },
+            delta.change_pct,
+            delta.base_ns,
+            delta.head_ns,
+            delta.benchmark
+        );
+    }
+    for name in &report.unmatched {
+        println!("  {:<10} {:>9}  {}", "unmatched", "", name);
+    }
+    println!(
+        "{} regression(s) above {}%",
+        report.regressions, report.threshold_pct
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(commit: &str, recorded_at: i64, results: &[(&str, f64)]) -> Run {
+        let hardware_detail = Hardware {
+            cpu: "test".to_string(),
+            cpu_threads: 8,
+            gpus: vec![],
+        };
+        Run {
+            commit: commit.to_string(),
+            dirty: false,
+            hardware: hardware_detail.fingerprint(),
+            hardware_detail,
+            recorded_at,
+            results: results
+                .iter()
+                .map(|(name, mean_ns)| (name.to_string(), Sample { mean_ns: *mean_ns, std_dev_ns: 0.0 }))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_regressions_flagged_above_threshold() {
+        let base = run("a", 0, &[("fhe/conv", 1_000.0), ("proof/prove", 2_000.0), ("old", 1.0)]);
+        let head = run("b", 1, &[("fhe/conv", 1_040.0), ("proof/prove", 2_200.0), ("new", 1.0)]);
+        let report = diff(&base, &head, 5.0);
+
+        assert_eq!(report.regressions, 1);
+        let prove = report.deltas.iter().find(|d| d.benchmark == "proof/prove").unwrap();
+        assert!(prove.regressed && (prove.change_pct - 10.0).abs() < 1e-9);
+        assert!(!report.deltas.iter().find(|d| d.benchmark == "fhe/conv").unwrap().regressed);
+        assert_eq!(report.unmatched, vec!["old".to_string(), "new".to_string()]);
+    }
+
+    #[test]
+    fn test_latest_run_per_commit_and_hardware() {
+        let hardware = run("a", 0, &[]).hardware;
+        let runs = vec![run("a", 5, &[("x", 2.0)]), run("a", 9, &[("x", 1.0)]), run("b", 7, &[])];
+        assert_eq!(latest(&runs, "a", &hardware).unwrap().recorded_at, 9);
+        assert!(latest(&runs, "a", "other-machine").is_none());
+        assert!(latest(&runs, "c", &hardware).is_none());
+    }
+
+    #[test]
+    fn test_criterion_output_collected_and_history_round_trips() {
+        let root = std::env::temp_dir().join(format!("haunti-bench-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (dir, full_id, mean) in [("FHE Inference/conv/8", "FHE Inference/conv/8", 125.5), ("prove", "prove", 9.0)] {
+            let new = root.join("criterion").join(dir).join("new");
+            fs::create_dir_all(&new).unwrap();
+            fs::write(new.join("benchmark.json"), format!(r#"{{"full_id":"{}"}}"#, full_id)).unwrap();
+            fs::write(
+                new.join("estimates.json"),
+                format!(r#"{{"mean":{{"point_estimate":{}}},"std_dev":{{"point_estimate":1.5}}}}"#, mean),
+            )
+            .unwrap();
+        }
+        // Criterion's saved baseline is not the latest result
+        let base = root.join("criterion/prove/base");
+        fs::create_dir_all(&base).unwrap();
+        fs::write(base.join("benchmark.json"), r#"{"full_id":"prove"}"#).unwrap();
+        fs::write(base.join("estimates.json"), r#"{"mean":{"point_estimate":1},"std_dev":{"point_estimate":0}}"#)
+            .unwrap();
+
+        let results = collect_criterion(&root.join("criterion")).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results["FHE Inference/conv/8"].mean_ns, 125.5);
+        assert_eq!(results["prove"].mean_ns, 9.0);
+
+        let history = root.join(DEFAULT_HISTORY);
+        let mut recorded = run("a", 0, &[]);
+        recorded.results = results;
+        append(&history, &recorded).unwrap();
+        append(&history, &run("b", 1, &[])).unwrap();
+        let runs = load(&history).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].results, recorded.results);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+//! Wallet and cluster settings shared by the user-facing subcommands
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::{read_keypair_file, Keypair};
+use std::path::{Path, PathBuf};
+
+/// Contents of `~/.config/haunti/cli.json`; every field may be overridden by a flag
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CliConfig {
+    /// `mainnet`, `devnet`, `testnet`, `localnet`, or an RPC URL
+    pub cluster: Option<String>,
+    /// Path to a Solana keypair file
+    pub keypair: Option<PathBuf>,
+}
+
+impl CliConfig {
+    pub fn default_path() -> Option<PathBuf> {
+        home_dir().map(|home| home.join(".config/haunti/cli.json"))
+    }
+
+    /// Load from `path`, or the default location; a missing file is not an error
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let path = match path.map(Path::to_path_buf).or_else(Self::default_path) {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw)
+                .with_context(|| format!("Invalid config file {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn rpc_url(&self) -> String {
+        cluster_url(self.cluster.as_deref().unwrap_or("devnet"))
+    }
+
+    pub fn keypair_path(&self) -> anyhow::Result<PathBuf> {
+        match &self.keypair {
+            Some(path) => Ok(expand_tilde(path)),
+            None => home_dir()
+                .map(|home| home.join(".config/solana/id.json"))
+                .context("No keypair configured and HOME is not set"),
+        }
+    }
+
+    pub fn load_keypair(&self) -> anyhow::Result<Keypair> {
+        let path = self.keypair_path()?;
+        read_keypair_file(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read keypair {}: {}", path.display(), e))
+    }
+}
+
+/// Resolve a cluster moniker to its RPC URL; anything else is taken as a URL
+pub fn cluster_url(cluster: &str) -> String {
+    match cluster {
+        "mainnet" | "mainnet-beta" => "https://api.mainnet-beta.solana.com".into(),
+        "devnet" => "https://api.devnet.solana.com".into(),
+        "testnet" => "https://api.testnet.solana.com".into(),
+        "localnet" | "localhost" => "http://127.0.0.1:8899".into(),
+        url => url.into(),
+    }
+}
+
+/// Websocket endpoint paired with an RPC URL
+pub fn ws_url(rpc_url: &str) -> String {
+    if rpc_url == "http://127.0.0.1:8899" {
+        return "ws://127.0.0.1:8900".into();
+    }
+    rpc_url.replacen("https://", "wss://", 1).replacen("http://", "ws://", 1)
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+fn expand_tilde(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_monikers_and_overrides() {
+        assert_eq!(cluster_url("devnet"), "https://api.devnet.solana.com");
+        assert_eq!(cluster_url("https://rpc.example.com"), "https://rpc.example.com");
+        assert_eq!(ws_url("https://api.devnet.solana.com"), "wss://api.devnet.solana.com");
+        assert_eq!(ws_url(&cluster_url("localnet")), "ws://127.0.0.1:8900");
+
+        let config: CliConfig =
+            serde_json::from_str(r#"{"cluster": "mainnet", "keypair": "/tmp/id.json"}"#).unwrap();
+        assert_eq!(config.rpc_url(), "https://api.mainnet-beta.solana.com");
+        assert_eq!(config.keypair_path().unwrap(), PathBuf::from("/tmp/id.json"));
+        assert_eq!(CliConfig::default().rpc_url(), "https://api.devnet.solana.com");
+    }
+}
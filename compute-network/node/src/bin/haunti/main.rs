@@ -0,0 +1,312 @@
+//! `haunti` command-line tool: staking, models, tasks, and governance for end users,
+//! plus audit and benchmark utilities built on the node runtimes
+
+mod archive;
+mod audit;
+mod bench;
+mod config;
+mod user;
+
+// The log format exactly as the coordinator writes it
+#[path = "../../audit_log.rs"]
+#[allow(dead_code)]
+mod audit_log;
+
+// Quotes exactly as the coordinator signs them
+#[path = "../../inference_quote.rs"]
+#[allow(dead_code)]
+mod inference_quote;
+
+use anchor_lang::AccountDeserialize;
+use anyhow::{bail, Context};
+use clap::{Parser, Subcommand, ValueEnum};
+use haunti_core::state::{TaskState, TaskStatus};
+use haunti_crypto::{fhe::FheRuntime, sim::SimulationRuntime};
+use haunti_network::storage::IpfsClient;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::keccak;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+
+use archive::{ArchiveCommand, Bundle};
+use audit::AuditCommand;
+use bench::BenchCommand;
+use config::CliConfig;
+use user::{
+    ClaimArgs, GovCommand, InferCommand, ModelCommand, Session, StakeArgs, TaskCommand,
+    UnstakeArgs,
+};
+
+#[derive(Debug, Parser)]
+#[clap(version, about = "Haunti command-line tools")]
+struct Cli {
+    /// RPC endpoint; takes precedence over `--cluster`
+    #[clap(long, env, global = true)]
+    rpc_url: Option<String>,
+
+    /// `mainnet`, `devnet`, `testnet`, `localnet`, or an RPC URL
+    #[clap(long, global = true)]
+    cluster: Option<String>,
+
+    /// Wallet keypair file
+    #[clap(long, global = true)]
+    keypair: Option<PathBuf>,
+
+    /// Config file; defaults to `~/.config/haunti/cli.json`
+    #[clap(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Emit machine-readable JSON
+    #[clap(long, global = true)]
+    json: bool,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Stake tokens, optionally locked into a tier
+    Stake(StakeArgs),
+    /// Withdraw liquid stake or an unlocked position
+    Unstake(UnstakeArgs),
+    /// Claim staking rewards
+    Claim(ClaimArgs),
+    #[clap(subcommand)]
+    Model(ModelCommand),
+    #[clap(subcommand)]
+    Task(TaskCommand),
+    #[clap(subcommand)]
+    Infer(InferCommand),
+    #[clap(subcommand)]
+    Gov(GovCommand),
+    /// Export completed tasks to signed cold-storage bundles and re-verify them
+    #[clap(subcommand)]
+    Archive(ArchiveCommand),
+    /// Check coordinator audit logs
+    #[clap(subcommand)]
+    Audit(AuditCommand),
+    /// Record criterion results per commit and flag regressions between commits
+    #[clap(subcommand)]
+    Bench(BenchCommand),
+    /// Re-execute a completed task and compare against its on-chain commitments
+    Replay {
+        task_pubkey: String,
+        /// CID of the encrypted model (checked against the task's model hash)
+        #[clap(long)]
+        model_cid: String,
+        /// CID of the encrypted input (checked against the task's input hash)
+        #[clap(long)]
+        data_cid: String,
+        #[clap(long, value_enum, default_value = "simulation")]
+        backend: ReplayBackend,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ReplayBackend {
+    Simulation,
+    Fhe,
+}
+
+/// Agreement between a recomputed value and its on-chain commitment
+#[derive(Debug, Serialize, PartialEq, Eq)]
+struct CommitmentCheck {
+    field: &'static str,
+    on_chain: String,
+    recomputed: String,
+    /// First differing byte, if any
+    first_mismatch: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReplayReport {
+    task: String,
+    backend: ReplayBackend,
+    checks: Vec<CommitmentCheck>,
+    reproduced: bool,
+}
+
+fn compare(field: &'static str, on_chain: &[u8; 32], recomputed: &[u8; 32]) -> CommitmentCheck {
+    CommitmentCheck {
+        field,
+        on_chain: hex::encode(on_chain),
+        recomputed: hex::encode(recomputed),
+        first_mismatch: on_chain.iter().zip(recomputed.iter()).position(|(a, b)| a != b),
+    }
+}
+
+async fn replay(
+    rpc: &RpcClient,
+    task_pubkey: &Pubkey,
+    model_cid: &str,
+    data_cid: &str,
+    backend: ReplayBackend,
+) -> anyhow::Result<ReplayReport> {
+    let account = rpc
+        .get_account(task_pubkey)
+        .await
+        .context("Failed to fetch task account")?;
+    let task = TaskState::try_deserialize(&mut account.data.as_slice())
+        .context("Account is not a Haunti task")?;
+
+    let result_hash = match task.status {
+        TaskStatus::Completed { result_hash, .. } => result_hash,
+        other => bail!("Task is not completed (status: {:?})", other),
+    };
+
+    // CIDs are untrusted hints; the content hashes bind them to the task
+    let ipfs = IpfsClient::default();
+    let model = ipfs.get_cid(model_cid).await.context("Failed to fetch model")?;
+    let data = ipfs.get_cid(data_cid).await.context("Failed to fetch input")?;
+
+    let output = match backend {
+        ReplayBackend::Simulation => SimulationRuntime::new().execute(&model, &data)?,
+        ReplayBackend::Fhe => FheRuntime::new_gpu().await?.execute(&model, &data).await?,
+    };
+
+    let checks = vec![
+        compare("model_hash", &task.model_hash, &keccak::hash(&model).0),
+        compare("input_hash", &task.input_hash, &keccak::hash(&data).0),
+        compare("result_hash", &result_hash, &keccak::hash(&output).0),
+    ];
+    let reproduced = checks.iter().all(|c| c.first_mismatch.is_none());
+
+    Ok(ReplayReport {
+        task: task_pubkey.to_string(),
+        backend,
+        checks,
+        reproduced,
+    })
+}
+
+fn print_report(report: &ReplayReport, json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+
+    println!("Replay of {} ({:?} backend)", report.task, report.backend);
+    for check in &report.checks {
+        match check.first_mismatch {
+            None => println!("  {:<12} OK        {}", check.field, check.on_chain),
+            Some(idx) => println!(
+                "  {:<12} MISMATCH  at byte {}\n    on-chain:   {}\n    recomputed: {}",
+                check.field, idx, check.on_chain, check.recomputed
+            ),
+        }
+    }
+    println!("Result {}", if report.reproduced { "reproduced" } else { "NOT reproduced" });
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let mut config = CliConfig::load(cli.config.as_deref())?;
+    if cli.cluster.is_some() {
+        config.cluster = cli.cluster.clone();
+    }
+    if cli.keypair.is_some() {
+        config.keypair = cli.keypair.clone();
+    }
+    let rpc_url = cli.rpc_url.clone().unwrap_or_else(|| config.rpc_url());
+
+    let session = || -> anyhow::Result<Session> {
+        Ok(Session::new(&rpc_url, config.load_keypair()?))
+    };
+
+    let output = match cli.command {
+        Command::Stake(args) => session()?.stake(args).await?,
+        Command::Unstake(args) => session()?.unstake(args).await?,
+        Command::Claim(args) => session()?.claim(args).await?,
+        Command::Model(command) => session()?.model(command).await?,
+        Command::Task(command) => session()?.task(command).await?,
+        Command::Infer(command) => session()?.infer(command).await?,
+        Command::Gov(command) => session()?.gov(command).await?,
+        Command::Replay { task_pubkey, model_cid, data_cid, backend } => {
+            let rpc = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::finalized());
+            let task_pubkey = Pubkey::from_str(&task_pubkey).context("Invalid task pubkey")?;
+            let report = replay(&rpc, &task_pubkey, &model_cid, &data_cid, backend).await?;
+            print_report(&report, cli.json)?;
+            if !report.reproduced {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::Archive(ArchiveCommand::Export {
+            task,
+            out,
+            model_cid,
+            data_cid,
+            public_inputs,
+        }) => {
+            let rpc = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::finalized());
+            let cids: BTreeMap<String, String> = [("model", model_cid), ("input", data_cid)]
+                .into_iter()
+                .filter_map(|(role, cid)| Some((role.to_string(), cid?)))
+                .collect();
+            let public_inputs = public_inputs.map(std::fs::read).transpose()?;
+            let bundle =
+                archive::export(&rpc, &config.load_keypair()?, &task, cids, public_inputs).await?;
+            let path = out.join(format!("{}.tar", bundle.id()));
+            bundle.write(&path)?;
+            println!("{}", path.display());
+            return Ok(());
+        }
+        Command::Archive(ArchiveCommand::Verify { bundle }) => {
+            let report = archive::verify(&Bundle::read(&bundle)?)?;
+            archive::print_report(&report, cli.json)?;
+            if !report.verified {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::Audit(AuditCommand::Verify { path, anchor }) => {
+            let rpc = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::finalized());
+            let report = audit::verify(&rpc, &path, &anchor).await?;
+            audit::print_report(&report, cli.json)?;
+            if !report.verified {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::Bench(BenchCommand::Record { criterion_dir, history, commit }) => {
+            let run = bench::record(&criterion_dir, &history, commit.as_deref())?;
+            bench::print_run(&run, cli.json)?;
+            return Ok(());
+        }
+        Command::Bench(BenchCommand::Compare { base, head, threshold, history, hardware }) => {
+            let report = bench::compare(&history, &base, &head, threshold, hardware.as_deref())?;
+            bench::print_report(&report, cli.json)?;
+            if report.regressions > 0 {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+    };
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        println!("{}", output);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_reports_first_mismatch() {
+        let a = [7u8; 32];
+        let mut b = a;
+        assert_eq!(compare("result_hash", &a, &b).first_mismatch, None);
+
+        b[5] ^= 1;
+        assert_eq!(compare("result_hash", &a, &b).first_mismatch, Some(5));
+    }
+}
@@ -0,0 +1,1363 @@
+//! End-user subcommands: staking, models, tasks, inference, and governance
+
+use anchor_client::{
+    solana_client::rpc_filter::{Memcmp, RpcFilterType},
+    Client, Cluster, Program,
+};
+use anchor_lang::{system_program, AccountDeserialize};
+use anchor_spl::{
+    associated_token::{get_associated_token_address, get_associated_token_address_with_program_id},
+    token_interface::TokenAccount,
+};
+use anyhow::{bail, ensure, Context};
+use clap::{Args, Subcommand, ValueEnum};
+use haunti_core::state::{
+    deposit_config::{DepositConfig, GpuTier, ResourceRequirements},
+    model_registry::{ModelIndex, ModelIndexPage},
+    ModelState, ModelType, TaskState, TaskStatus,
+};
+use haunti_network::storage::IpfsClient;
+use serde::Serialize;
+use solana_program::keccak;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    sysvar,
+};
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use token_vault::{PoolState, PoolType, ProposalType, ReceiptMode};
+
+use crate::config::ws_url;
+use crate::inference_quote::SignedQuote;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum PoolArg {
+    Gpu,
+    Validator,
+    Trainer,
+    Governance,
+}
+
+impl From<PoolArg> for PoolType {
+    fn from(pool: PoolArg) -> Self {
+        match pool {
+            PoolArg::Gpu => PoolType::GPUProvider,
+            PoolArg::Validator => PoolType::Validator,
+            PoolArg::Trainer => PoolType::Trainer,
+            PoolArg::Governance => PoolType::Governance,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GpuTierArg {
+    Consumer,
+    Datacenter,
+    HighMemory,
+}
+
+impl From<GpuTierArg> for GpuTier {
+    fn from(tier: GpuTierArg) -> Self {
+        match tier {
+            GpuTierArg::Consumer => GpuTier::Consumer,
+            GpuTierArg::Datacenter => GpuTier::Datacenter,
+            GpuTierArg::HighMemory => GpuTier::HighMemory,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct StakeArgs {
+    #[clap(long, value_enum)]
+    pub pool: PoolArg,
+    /// Amount in base token units
+    pub amount: u64,
+    /// Lock into a tiered position instead of the liquid stake
+    #[clap(long)]
+    pub tier: Option<u8>,
+    /// Id for the new position; defaults to the current unix time
+    #[clap(long, requires = "tier")]
+    pub position_id: Option<u64>,
+    /// Stake for transferable stHAUNT; the pool must mint liquid receipts
+    #[clap(long, conflicts_with = "tier")]
+    pub liquid: bool,
+    /// Wallet that referred you; recorded on your first stake in the pool
+    #[clap(long, conflicts_with_all = ["tier", "liquid"])]
+    pub referrer: Option<Pubkey>,
+}
+
+#[derive(Debug, Args)]
+pub struct UnstakeArgs {
+    #[clap(long, value_enum)]
+    pub pool: PoolArg,
+    /// Amount in base token units; ignored with `--position`
+    #[clap(required_unless_present = "position")]
+    pub amount: Option<u64>,
+    /// Withdraw a locked position in full
+    #[clap(long)]
+    pub position: Option<u64>,
+    /// Redeem `amount` stHAUNT instead of unstaking base tokens
+    #[clap(long, conflicts_with = "position")]
+    pub liquid: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ClaimArgs {
+    #[clap(long, value_enum)]
+    pub pool: PoolArg,
+    /// Claim from a locked position instead of the liquid stake
+    #[clap(long)]
+    pub position: Option<u64>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ModelCommand {
+    /// Mint a model NFT from an encrypted parameter file
+    Mint {
+        #[clap(long)]
+        params: PathBuf,
+        #[clap(long)]
+        model_type: String,
+        #[clap(long)]
+        name: String,
+        #[clap(long)]
+        symbol: String,
+        #[clap(long)]
+        uri: String,
+        #[clap(long, default_value_t = 0)]
+        royalty_bps: u16,
+    },
+    /// Point a model at new parameters, or append a weight-diff patch
+    Update {
+        model: Pubkey,
+        /// Merkle root of the updated parameters (hex)
+        #[clap(long)]
+        root: String,
+        /// CID of the full parameters, or of the patch with `--patch`
+        #[clap(long)]
+        cid: String,
+        #[clap(long)]
+        patch: bool,
+    },
+    /// List models owned by the wallet, or by `--owner`
+    List {
+        #[clap(long)]
+        owner: Option<Pubkey>,
+    },
+    /// List model mints from the on-chain type or creator index
+    Find {
+        #[clap(long, conflicts_with = "creator")]
+        model_type: Option<String>,
+        #[clap(long)]
+        creator: Option<Pubkey>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TaskCommand {
+    /// Create a training task from a JSON model description
+    Create {
+        #[clap(long)]
+        params: PathBuf,
+        /// Escrowed reward in lamports
+        #[clap(long)]
+        reward: u64,
+        /// Seconds before the task expires
+        #[clap(long)]
+        time_limit: u64,
+        /// GPU class to reserve; sets the required deposit with `--gpus`
+        #[clap(long, value_enum, default_value = "consumer")]
+        gpu_tier: GpuTierArg,
+        #[clap(long, default_value_t = 1)]
+        gpus: u8,
+        /// Encrypted training data to attach
+        #[clap(long)]
+        data: Option<PathBuf>,
+        /// Escrow the reward from the bridged prepaid balance
+        #[clap(long)]
+        prepaid: bool,
+        /// Lamports paid on top of the reward for earlier pickup
+        #[clap(long, default_value_t = 0)]
+        priority_fee: u64,
+        /// Seconds from now the result is needed by; at most `--time-limit`
+        #[clap(long)]
+        deadline: Option<u64>,
+    },
+    Status {
+        task: Pubkey,
+    },
+    Cancel {
+        task: Pubkey,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum InferCommand {
+    /// Upload an encrypted input and open a priced inference task
+    Submit {
+        #[clap(long)]
+        model: Pubkey,
+        #[clap(long)]
+        input: PathBuf,
+        /// Hex hash of the input schema the encoder used; must match the
+        /// model's registered schema when it has one
+        #[clap(long)]
+        input_schema: Option<String>,
+        /// Highest base fee accepted, in lamports
+        #[clap(long)]
+        max_fee: u64,
+        /// Extra lamports escrowed so repeated inputs are not throttled
+        #[clap(long, default_value = "0")]
+        surge_fee: u64,
+        /// Escrow the fee from the bridged prepaid balance
+        #[clap(long)]
+        prepaid: bool,
+        /// Coordinator-signed quote (JSON) capping the base fee
+        #[clap(long)]
+        quote: Option<PathBuf>,
+    },
+    /// Show the result commitment of an inference task
+    Result {
+        task: Pubkey,
+        /// Poll until the task completes, fails, or is cancelled
+        #[clap(long)]
+        wait: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GovCommand {
+    /// Open a proposal; exactly one action flag is required
+    Propose {
+        #[clap(long, value_enum)]
+        pool: PoolArg,
+        #[clap(flatten)]
+        action: ProposalAction,
+    },
+    Vote {
+        proposal: Pubkey,
+        #[clap(long, conflicts_with = "reject", required_unless_present = "reject")]
+        approve: bool,
+        #[clap(long)]
+        reject: bool,
+    },
+}
+
+#[derive(Debug, Default, Args)]
+pub struct ProposalAction {
+    #[clap(long)]
+    pub reward_rate: Option<u64>,
+    #[clap(long)]
+    pub lockup_period: Option<i64>,
+    #[clap(long)]
+    pub penalty_bps: Option<u16>,
+    /// Move this amount out of the pool treasury to `--recipient`
+    #[clap(long, requires = "recipient")]
+    pub treasury_transfer: Option<u64>,
+    /// Withdraw this amount of unallocated rewards to `--recipient`
+    #[clap(long, requires = "recipient")]
+    pub drain_rewards: Option<u64>,
+    #[clap(long)]
+    pub recipient: Option<Pubkey>,
+    /// Slash this provider's stake by `--slash-bps`
+    #[clap(long, requires = "slash_bps")]
+    pub slash: Option<Pubkey>,
+    #[clap(long)]
+    pub slash_bps: Option<u16>,
+    /// Burn the slashed stake instead of moving it to the pool treasury
+    #[clap(long, requires = "slash")]
+    pub slash_burn: bool,
+    /// Make `--vk-hash` the active verifying key of this circuit id (hex)
+    #[clap(long, requires_all = ["vk_hash", "key_version"])]
+    pub rotate_key: Option<String>,
+    #[clap(long)]
+    pub vk_hash: Option<String>,
+    #[clap(long)]
+    pub key_version: Option<u32>,
+}
+
+impl ProposalAction {
+    /// On-chain `(proposal_type, amount, recipient)` for the chosen action
+    pub fn payload(&self) -> anyhow::Result<(ProposalType, Option<u64>, Option<Pubkey>)> {
+        let parameter_update = self.lockup_period.is_some() || self.penalty_bps.is_some();
+        let chosen = [
+            self.reward_rate.is_some(),
+            parameter_update,
+            self.treasury_transfer.is_some(),
+            self.drain_rewards.is_some(),
+            self.slash.is_some(),
+            self.rotate_key.is_some(),
+        ];
+        if chosen.iter().filter(|c| **c).count() != 1 {
+            bail!("Choose exactly one proposal action");
+        }
+
+        Ok(if let Some(reward_rate) = self.reward_rate {
+            (ProposalType::RewardRateChange { reward_rate }, None, None)
+        } else if parameter_update {
+            let update = ProposalType::PoolParameterUpdate {
+                lockup_period: self.lockup_period,
+                early_unstake_penalty_bps: self.penalty_bps,
+            };
+            (update, None, None)
+        } else if let Some(amount) = self.treasury_transfer {
+            (ProposalType::TreasuryTransfer, Some(amount), self.recipient)
+        } else if let Some(provider) = self.slash {
+            let slash_bps = self.slash_bps.unwrap_or_default();
+            let burn = self.slash_burn;
+            (ProposalType::SlashStake { provider, slash_bps, burn }, None, None)
+        } else if let Some(circuit_id) = &self.rotate_key {
+            let rotation = ProposalType::VerifierKeyRotation {
+                circuit_id: parse_hash(circuit_id)?,
+                vk_hash: parse_hash(self.vk_hash.as_deref().unwrap_or_default())?,
+                version: self.key_version.unwrap_or_default(),
+            };
+            (rotation, None, None)
+        } else {
+            (ProposalType::DrainRewards, self.drain_rewards, self.recipient)
+        })
+    }
+}
+
+/// Result of a user command, printed as text or JSON
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Output {
+    Tx {
+        action: &'static str,
+        signature: String,
+        /// Account created or acted on
+        account: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cid: Option<String>,
+    },
+    Task(TaskView),
+    Models(Vec<ModelView>),
+    Mints(Vec<String>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskView {
+    pub task: String,
+    pub owner: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_hash: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelView {
+    pub model: String,
+    pub version: u32,
+    pub model_root: String,
+    pub storage_cid: String,
+    pub patches: usize,
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::Tx { action, signature, account, cid } => {
+                writeln!(f, "{} {}", action, account)?;
+                if let Some(cid) = cid {
+                    writeln!(f, "  cid:       {}", cid)?;
+                }
+                write!(f, "  signature: {}", signature)
+            }
+            Output::Task(task) => {
+                writeln!(f, "Task {} ({})", task.task, task.status)?;
+                write!(f, "  owner:  {}", task.owner)?;
+                if let Some(worker) = &task.worker {
+                    write!(f, "\n  worker: {}", worker)?;
+                }
+                if let Some(hash) = &task.result_hash {
+                    write!(f, "\n  result: {}", hash)?;
+                }
+                Ok(())
+            }
+            Output::Models(models) if models.is_empty() => write!(f, "No models found"),
+            Output::Models(models) => {
+                for (i, m) in models.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(
+                        f,
+                        "{}  v{}  root {}  cid {}  (+{} patches)",
+                        m.model, m.version, m.model_root, m.storage_cid, m.patches
+                    )?;
+                }
+                Ok(())
+            }
+            Output::Mints(mints) if mints.is_empty() => write!(f, "No models found"),
+            Output::Mints(mints) => write!(f, "{}", mints.join("\n")),
+        }
+    }
+}
+
+impl TaskView {
+    fn new(task: Pubkey, state: &TaskState) -> Self {
+        let (status, worker, result_hash) = match &state.status {
+            TaskStatus::Pending => ("pending", None, None),
+            TaskStatus::Running { worker, .. } => ("running", Some(worker.to_string()), None),
+            TaskStatus::Provisional { worker, result_hash, .. } => {
+                ("provisional", Some(worker.to_string()), Some(hex::encode(result_hash)))
+            }
+            TaskStatus::Disputed { worker, result_hash, .. } => {
+                ("disputed", Some(worker.to_string()), Some(hex::encode(result_hash)))
+            }
+            TaskStatus::Completed { result_hash, .. } => {
+                ("completed", None, Some(hex::encode(result_hash)))
+            }
+            TaskStatus::Failed { .. } => ("failed", None, None),
+            TaskStatus::Cancelled { .. } => ("cancelled", None, None),
+        };
+        Self {
+            task: task.to_string(),
+            owner: state.owner.to_string(),
+            status: status.into(),
+            worker,
+            result_hash,
+            created_at: state.created_at,
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        matches!(self.status.as_str(), "completed" | "failed" | "cancelled")
+    }
+}
+
+/// Signed connection to both programs
+pub struct Session {
+    payer: Arc<Keypair>,
+    client: Client<Arc<Keypair>>,
+}
+
+impl Session {
+    pub fn new(rpc_url: &str, payer: Keypair) -> Self {
+        let payer = Arc::new(payer);
+        let cluster = Cluster::Custom(rpc_url.into(), ws_url(rpc_url));
+        let client =
+            Client::new_with_options(cluster, payer.clone(), CommitmentConfig::confirmed());
+        Self { payer, client }
+    }
+
+    fn wallet(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    fn vault(&self) -> anyhow::Result<Program<Arc<Keypair>>> {
+        Ok(self.client.program(token_vault::ID)?)
+    }
+
+    fn core(&self) -> anyhow::Result<Program<Arc<Keypair>>> {
+        Ok(self.client.program(haunti_core::ID)?)
+    }
+
+    /// Model index head PDA and its current head, if the index exists yet
+    async fn model_index(
+        &self,
+        seeds: &[&[u8]],
+    ) -> anyhow::Result<(Pubkey, Option<ModelIndex>)> {
+        let (index, _) = Pubkey::find_program_address(seeds, &haunti_core::ID);
+        let head = self.core()?.account::<ModelIndex>(index).await.ok();
+        Ok((index, head))
+    }
+
+    fn model_index_page(index: &Pubkey, page: u32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"model_index_page", index.as_ref(), &page.to_le_bytes()],
+            &haunti_core::ID,
+        )
+        .0
+    }
+
+    async fn fetch_task(&self, task: Pubkey) -> anyhow::Result<TaskState> {
+        self.core()?
+            .account::<TaskState>(task)
+            .await
+            .context("Account is not a Haunti task")
+    }
+
+    /// Pool PDA, its emission schedule, its vault, and the staked mint
+    async fn pool_accounts(&self, pool: PoolArg) -> anyhow::Result<PoolAccounts> {
+        let pool_type: PoolType = pool.into();
+        let (pool, _) = Pubkey::find_program_address(
+            &[b"pool", pool_type.to_string().as_bytes()],
+            &token_vault::ID,
+        );
+        let (vault, _) =
+            Pubkey::find_program_address(&[b"vault", pool.as_ref()], &token_vault::ID);
+        let program = self.vault()?;
+        let vault_account = program
+            .rpc()
+            .get_account(&vault)
+            .await
+            .context("Pool vault not found; is the pool initialized on this cluster?")?;
+        // Legacy SPL Token or Token-2022, whichever owns the pool's vault
+        let token_program = vault_account.owner;
+        let mint = TokenAccount::try_deserialize(&mut vault_account.data.as_slice())?.mint;
+        let state = program.account::<PoolState>(pool).await?;
+        let emission_schedule = state.scheduled.then(|| {
+            Pubkey::find_program_address(&[b"emission_schedule", pool.as_ref()], &token_vault::ID).0
+        });
+        Ok(PoolAccounts {
+            pool,
+            emission_schedule,
+            vault,
+            mint,
+            token_program,
+            receipt_mode: state.receipt_mode,
+            receipt_mint: state.receipt_mint,
+        })
+    }
+
+    /// Signer PDA for haunti-core self-CPI events
+    fn core_event_authority() -> Pubkey {
+        Pubkey::find_program_address(&[b"__event_authority"], &haunti_core::ID).0
+    }
+
+    /// Registry PDA that tags new tasks with their verifier version
+    fn verifier_registry() -> Pubkey {
+        Pubkey::find_program_address(&[b"verifier_registry"], &haunti_core::ID).0
+    }
+
+    /// Counter PDA that sequences the task feed
+    fn event_counter() -> Pubkey {
+        Pubkey::find_program_address(&[b"event_counter"], &haunti_core::ID).0
+    }
+
+    /// Prepaid balance PDA of the wallet when `prepaid` is requested
+    fn prepaid_account(&self, prepaid: bool) -> Option<Pubkey> {
+        prepaid.then(|| {
+            Pubkey::find_program_address(&[b"prepaid", self.wallet().as_ref()], &haunti_core::ID).0
+        })
+    }
+
+    fn user_pda(&self, prefix: &[u8], pool: &Pubkey) -> Pubkey {
+        let wallet = self.wallet();
+        Pubkey::find_program_address(&[prefix, pool.as_ref(), wallet.as_ref()], &token_vault::ID).0
+    }
+
+    fn position_pda(&self, pool: &Pubkey, id: u64) -> Pubkey {
+        let wallet = self.wallet();
+        Pubkey::find_program_address(
+            &[b"position", pool.as_ref(), wallet.as_ref(), &id.to_le_bytes()],
+            &token_vault::ID,
+        )
+        .0
+    }
+
+    pub async fn stake(&self, args: StakeArgs) -> anyhow::Result<Output> {
+        let accounts = self.pool_accounts(args.pool).await?;
+        let program = self.vault()?;
+        let user_token = accounts.ata(&self.wallet());
+        let stake_history = self.user_pda(b"stake_history", &accounts.pool);
+
+        let (account, signature) = match args.tier {
+            None if args.liquid => {
+                ensure!(
+                    accounts.receipt_mode == ReceiptMode::Liquid,
+                    "This pool does not mint liquid staking receipts"
+                );
+                let receipt_token = accounts.receipt_ata(&self.wallet());
+                let signature = program
+                    .request()
+                    .accounts(token_vault::accounts::StakeLiquid {
+                        pool: accounts.pool,
+                        emission_schedule: accounts.emission_schedule,
+                        user_token,
+                        receipt_mint: accounts.receipt_mint,
+                        receipt_token,
+                        reward_vault: accounts.ata(&accounts.pool),
+                        vault: accounts.vault,
+                        owner: self.wallet(),
+                        mint: accounts.mint,
+                        system_program: system_program::ID,
+                        token_program: accounts.token_program,
+                        associated_token_program: anchor_spl::associated_token::ID,
+                    })
+                    .args(token_vault::instruction::StakeLiquid { amount: args.amount })
+                    .send()
+                    .await?;
+                (receipt_token, signature)
+            }
+            None => {
+                let user_stake = self.user_pda(b"stake", &accounts.pool);
+                let signature = program
+                    .request()
+                    .accounts(token_vault::accounts::Stake {
+                        pool: accounts.pool,
+                        emission_schedule: accounts.emission_schedule,
+                        user_token,
+                        user_stake,
+                        stake_history,
+                        vault: accounts.vault,
+                        owner: self.wallet(),
+                        mint: accounts.mint,
+                        system_program: system_program::ID,
+                        token_program: accounts.token_program,
+                        associated_token_program: anchor_spl::associated_token::ID,
+                    })
+                    .args(token_vault::instruction::Stake {
+                        amount: args.amount,
+                        referrer: args.referrer,
+                    })
+                    .send()
+                    .await?;
+                (user_stake, signature)
+            }
+            Some(tier) => {
+                let position_id = args
+                    .position_id
+                    .unwrap_or_else(|| {
+                        SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map(|d| d.as_secs())
+                            .unwrap_or_default()
+                    });
+                let position = self.position_pda(&accounts.pool, position_id);
+                let (receipt_mint, receipt_token) =
+                    accounts.position_receipt(&position, &self.wallet());
+                let signature = program
+                    .request()
+                    .accounts(token_vault::accounts::StakeLocked {
+                        pool: accounts.pool,
+                        emission_schedule: accounts.emission_schedule,
+                        position,
+                        stake_history,
+                        user_token,
+                        vault: accounts.vault,
+                        receipt_mint,
+                        receipt_token,
+                        owner: self.wallet(),
+                        mint: accounts.mint,
+                        system_program: system_program::ID,
+                        token_program: accounts.token_program,
+                        associated_token_program: anchor_spl::associated_token::ID,
+                    })
+                    .args(token_vault::instruction::StakeLocked {
+                        amount: args.amount,
+                        tier,
+                        position_id,
+                    })
+                    .send()
+                    .await?;
+                (position, signature)
+            }
+        };
+        Ok(tx("staked", signature, account))
+    }
+
+    pub async fn unstake(&self, args: UnstakeArgs) -> anyhow::Result<Output> {
+        let accounts = self.pool_accounts(args.pool).await?;
+        let program = self.vault()?;
+        let user_token = accounts.ata(&self.wallet());
+        let stake_history = self.user_pda(b"stake_history", &accounts.pool);
+
+        let (account, signature) = match args.position {
+            None if args.liquid => {
+                ensure!(
+                    accounts.receipt_mode == ReceiptMode::Liquid,
+                    "This pool does not mint liquid staking receipts"
+                );
+                let receipt_token = accounts.receipt_ata(&self.wallet());
+                let signature = program
+                    .request()
+                    .accounts(token_vault::accounts::UnstakeLiquid {
+                        pool: accounts.pool,
+                        emission_schedule: accounts.emission_schedule,
+                        user_token,
+                        receipt_mint: accounts.receipt_mint,
+                        receipt_token,
+                        reward_vault: accounts.ata(&accounts.pool),
+                        vault: accounts.vault,
+                        owner: self.wallet(),
+                        mint: accounts.mint,
+                        token_program: accounts.token_program,
+                    })
+                    .args(token_vault::instruction::UnstakeLiquid {
+                        shares: args.amount.context("Amount is required")?,
+                    })
+                    .send()
+                    .await?;
+                (receipt_token, signature)
+            }
+            None => {
+                let user_stake = self.user_pda(b"stake", &accounts.pool);
+                let signature = program
+                    .request()
+                    .accounts(token_vault::accounts::Unstake {
+                        pool: accounts.pool,
+                        emission_schedule: accounts.emission_schedule,
+                        user_stake,
+                        stake_history,
+                        vault: accounts.vault,
+                        user_token,
+                        owner: self.wallet(),
+                        mint: accounts.mint,
+                        token_program: accounts.token_program,
+                    })
+                    .args(token_vault::instruction::Unstake {
+                        amount: args.amount.context("Amount is required")?,
+                    })
+                    .send()
+                    .await?;
+                (user_stake, signature)
+            }
+            Some(id) => {
+                let position = self.position_pda(&accounts.pool, id);
+                let (receipt_mint, receipt_token) =
+                    accounts.position_receipt(&position, &self.wallet());
+                let signature = program
+                    .request()
+                    .accounts(token_vault::accounts::UnstakePosition {
+                        pool: accounts.pool,
+                        emission_schedule: accounts.emission_schedule,
+                        position,
+                        stake_history,
+                        vault: accounts.vault,
+                        reward_vault: accounts.ata(&accounts.pool),
+                        user_token,
+                        receipt_mint,
+                        receipt_token,
+                        owner: self.wallet(),
+                        mint: accounts.mint,
+                        token_program: accounts.token_program,
+                    })
+                    .args(token_vault::instruction::UnstakePosition {})
+                    .send()
+                    .await?;
+                (position, signature)
+            }
+        };
+        Ok(tx("unstaked", signature, account))
+    }
+
+    pub async fn claim(&self, args: ClaimArgs) -> anyhow::Result<Output> {
+        let accounts = self.pool_accounts(args.pool).await?;
+        let program = self.vault()?;
+        let user_token = accounts.ata(&self.wallet());
+        let reward_vault = accounts.ata(&accounts.pool);
+
+        let (account, signature) = match args.position {
+            None => {
+                let user_stake = self.user_pda(b"stake", &accounts.pool);
+                let signature = program
+                    .request()
+                    .accounts(token_vault::accounts::ClaimRewards {
+                        pool: accounts.pool,
+                        emission_schedule: accounts.emission_schedule,
+                        user_stake,
+                        reward_vault,
+                        user_token,
+                        owner: self.wallet(),
+                        mint: accounts.mint,
+                        token_program: accounts.token_program,
+                    })
+                    .args(token_vault::instruction::ClaimRewards {})
+                    .send()
+                    .await?;
+                (user_stake, signature)
+            }
+            Some(id) => {
+                let position = self.position_pda(&accounts.pool, id);
+                let signature = program
+                    .request()
+                    .accounts(token_vault::accounts::ClaimPositionRewards {
+                        pool: accounts.pool,
+                        emission_schedule: accounts.emission_schedule,
+                        position,
+                        reward_vault,
+                        user_token,
+                        owner: self.wallet(),
+                        mint: accounts.mint,
+                        token_program: accounts.token_program,
+                    })
+                    .args(token_vault::instruction::ClaimPositionRewards {})
+                    .send()
+                    .await?;
+                (position, signature)
+            }
+        };
+        Ok(tx("claimed", signature, account))
+    }
+
+    pub async fn model(&self, command: ModelCommand) -> anyhow::Result<Output> {
+        let program = self.core()?;
+        match command {
+            ModelCommand::Mint { params, model_type, name, symbol, uri, royalty_bps } => {
+                let encrypted_params = std::fs::read(&params)
+                    .with_context(|| format!("Failed to read {}", params.display()))?;
+                let params_hash = keccak::hash(&encrypted_params).0;
+                let model_type: ModelType = model_type
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Unknown model type {}", model_type))?;
+                let (model_nft, _) = Pubkey::find_program_address(
+                    &[b"model", self.wallet().as_ref(), &params_hash],
+                    &haunti_core::ID,
+                );
+                let mint = Keypair::new();
+                let (type_index, type_head) =
+                    self.model_index(&[b"model_index", b"type", &[model_type as u8]]).await?;
+                let type_page = type_head.map_or(0, |head| head.append_page());
+                let wallet = self.wallet();
+                let (creator_index, creator_head) =
+                    self.model_index(&[b"model_index", b"creator", wallet.as_ref()]).await?;
+                let creator_page = creator_head.map_or(0, |head| head.append_page());
+                let signature = program
+                    .request()
+                    .accounts(haunti_core::accounts::MintModel {
+                        payer: self.wallet(),
+                        model_nft,
+                        mint: mint.pubkey(),
+                        model_token_account: get_associated_token_address(
+                            &self.wallet(),
+                            &mint.pubkey(),
+                        ),
+                        metadata_account: mpl_token_metadata::pda::find_metadata_account(
+                            &mint.pubkey(),
+                        )
+                        .0,
+                        master_edition_account:
+                            mpl_token_metadata::pda::find_master_edition_account(&mint.pubkey())
+                                .0,
+                        type_index,
+                        type_index_page: Self::model_index_page(&type_index, type_page),
+                        creator_index,
+                        creator_index_page: Self::model_index_page(&creator_index, creator_page),
+                        token_program: anchor_spl::token::ID,
+                        metadata_program: mpl_token_metadata::ID,
+                        sysvar_instructions: solana_program::sysvar::instructions::ID,
+                        associated_token_program: anchor_spl::associated_token::ID,
+                        system_program: system_program::ID,
+                        rent: solana_program::sysvar::rent::ID,
+                    })
+                    .args(haunti_core::instruction::MintModel {
+                        model_type,
+                        params_hash,
+                        encrypted_params,
+                        name,
+                        symbol,
+                        uri,
+                        creators: vec![],
+                        royalty_basis_points: royalty_bps,
+                        type_page,
+                        creator_page,
+                    })
+                    .signer(&mint)
+                    .send()
+                    .await?;
+                Ok(tx("minted", signature, model_nft))
+            }
+            ModelCommand::Update { model, root, cid, patch } => {
+                let new_root = parse_hash(&root)?;
+                // The program checks the owner's signature over the new root
+                let owner_signature = self.payer.sign_message(&new_root).as_ref().to_vec();
+                let request = if patch {
+                    let base_root = program.account::<ModelState>(model).await?.model_root;
+                    program
+                        .request()
+                        .accounts(haunti_core::accounts::UpdateModelWithPatch {
+                            model,
+                            owner: self.wallet(),
+                            system_program: system_program::ID,
+                        })
+                        .args(haunti_core::instruction::UpdateModelWithPatch {
+                            base_root,
+                            new_root,
+                            patch_cid: cid.clone(),
+                            signature: owner_signature,
+                        })
+                } else {
+                    program
+                        .request()
+                        .accounts(haunti_core::accounts::UpdateModel {
+                            model,
+                            owner: self.wallet(),
+                        })
+                        .args(haunti_core::instruction::UpdateModel {
+                            new_root,
+                            new_cid: cid.clone(),
+                            signature: owner_signature,
+                        })
+                };
+                let signature = request.send().await?;
+                Ok(Output::Tx {
+                    action: if patch { "patched" } else { "updated" },
+                    signature: signature.to_string(),
+                    account: model.to_string(),
+                    cid: Some(cid),
+                })
+            }
+            ModelCommand::List { owner } => {
+                let owner = owner.unwrap_or_else(|| self.wallet());
+                // Discriminator, then the bump, precede the owner
+                let filter = RpcFilterType::Memcmp(Memcmp::new_base58_encoded(9, owner.as_ref()));
+                let models = program
+                    .accounts::<ModelState>(vec![filter])
+                    .await?
+                    .into_iter()
+                    .map(|(key, model)| ModelView {
+                        model: key.to_string(),
+                        version: model.version,
+                        model_root: hex::encode(model.model_root),
+                        storage_cid: model.storage_cid,
+                        patches: model.patch_cids.len(),
+                    })
+                    .collect();
+                Ok(Output::Models(models))
+            }
+            ModelCommand::Find { model_type, creator } => {
+                let (index, head) = match (model_type, creator) {
+                    (Some(model_type), _) => {
+                        let model_type: ModelType = model_type
+                            .parse()
+                            .map_err(|_| anyhow::anyhow!("Unknown model type {}", model_type))?;
+                        self.model_index(&[b"model_index", b"type", &[model_type as u8]]).await?
+                    }
+                    (None, Some(creator)) => {
+                        self.model_index(&[b"model_index", b"creator", creator.as_ref()]).await?
+                    }
+                    (None, None) => bail!("Pass --model-type or --creator"),
+                };
+                let mut mints = Vec::new();
+                if let Some(head) = head {
+                    for page in 0..=head.tail_page {
+                        let page = program
+                            .account::<ModelIndexPage>(Self::model_index_page(&index, page))
+                            .await?;
+                        mints.extend(page.mints.iter().map(Pubkey::to_string));
+                    }
+                }
+                Ok(Output::Mints(mints))
+            }
+        }
+    }
+
+    pub async fn task(&self, command: TaskCommand) -> anyhow::Result<Output> {
+        match command {
+            TaskCommand::Create {
+                params,
+                reward,
+                time_limit,
+                gpu_tier,
+                gpus,
+                data,
+                prepaid,
+                priority_fee,
+                deadline,
+            } => {
+                let raw = std::fs::read_to_string(&params)
+                    .with_context(|| format!("Failed to read {}", params.display()))?;
+                let model: haunti_core::ModelParams =
+                    serde_json::from_str(&raw).context("Invalid model description")?;
+                let encrypted_data = data.map(std::fs::read).transpose()?;
+                if let Some(data) = &encrypted_data {
+                    ensure!(
+                        data.len() <= haunti_core::limits::MAX_ENCRYPTED_INPUT_LEN,
+                        "Encrypted input is {} bytes; the protocol cap is {}",
+                        data.len(),
+                        haunti_core::limits::MAX_ENCRYPTED_INPUT_LEN
+                    );
+                }
+                let (task_account, _) = Pubkey::find_program_address(
+                    &[b"task", self.wallet().as_ref(), model.model_hash.as_ref()],
+                    &haunti_core::ID,
+                );
+                let (size_limits, _) =
+                    Pubkey::find_program_address(&[b"size_limits"], &haunti_core::ID);
+                let (deposit_config, _) =
+                    Pubkey::find_program_address(&[b"deposit_config"], &haunti_core::ID);
+                let requirements = ResourceRequirements {
+                    gpu_tier: gpu_tier.into(),
+                    gpu_count: gpus,
+                };
+                // Quote locally so an underfunded task fails before paying fees
+                let required = self
+                    .core()?
+                    .account::<DepositConfig>(deposit_config)
+                    .await
+                    .context("Failed to fetch deposit schedule")?
+                    .required_deposit(&requirements, time_limit)?;
+                ensure!(
+                    reward >= required,
+                    "{} GPU(s) for {}s requires a reward of at least {} lamports",
+                    gpus,
+                    time_limit,
+                    required
+                );
+                if let Some(deadline) = deadline {
+                    ensure!(
+                        deadline > 0 && deadline <= time_limit,
+                        "--deadline must fall within the {}s time limit",
+                        time_limit
+                    );
+                }
+                let deadline = deadline
+                    .map(|secs| -> anyhow::Result<i64> {
+                        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                        Ok((now + secs) as i64)
+                    })
+                    .transpose()?;
+                let event_authority = Self::core_event_authority();
+                let signature = self
+                    .core()?
+                    .request()
+                    .accounts(haunti_core::accounts::CreateTask {
+                        task_account,
+                        owner: self.wallet(),
+                        size_limits,
+                        deposit_config,
+                        verifier_registry: Self::verifier_registry(),
+                        event_counter: Self::event_counter(),
+                        prepaid: self.prepaid_account(prepaid),
+                        system_program: system_program::ID,
+                        gpu_provider: None,
+                        event_authority,
+                        program: haunti_core::ID,
+                    })
+                    .args(haunti_core::instruction::CreateTask {
+                        model,
+                        reward,
+                        time_limit,
+                        requirements,
+                        encrypted_data,
+                        priority_fee,
+                        deadline,
+                    })
+                    .send()
+                    .await?;
+                Ok(tx("created", signature, task_account))
+            }
+            TaskCommand::Status { task } => {
+                Ok(Output::Task(TaskView::new(task, &self.fetch_task(task).await?)))
+            }
+            TaskCommand::Cancel { task } => {
+                let state = self.fetch_task(task).await?;
+                let signature = self
+                    .core()?
+                    .request()
+                    .accounts(haunti_core::accounts::CancelTask {
+                        task,
+                        owner: state.owner,
+                        event_counter: Self::event_counter(),
+                        pricing: state.pricing,
+                        authority: self.wallet(),
+                    })
+                    .args(haunti_core::instruction::CancelTask {})
+                    .send()
+                    .await?;
+                Ok(tx("cancelled", signature, task))
+            }
+        }
+    }
+
+    pub async fn infer(&self, command: InferCommand) -> anyhow::Result<Output> {
+        match command {
+            InferCommand::Submit {
+                model,
+                input,
+                input_schema,
+                max_fee,
+                surge_fee,
+                prepaid,
+                quote,
+            } => {
+                let input_schema_hash = input_schema
+                    .as_deref()
+                    .map(parse_hash)
+                    .transpose()?
+                    .unwrap_or_default();
+                let bytes = std::fs::read(&input)
+                    .with_context(|| format!("Failed to read {}", input.display()))?;
+                let cid = IpfsClient::default()
+                    .add(&bytes)
+                    .await
+                    .context("Failed to upload input")?;
+                let input_hash = keccak::hash(&bytes).0;
+                let quote = quote
+                    .map(|path| -> anyhow::Result<SignedQuote> {
+                        let raw = std::fs::read_to_string(&path)
+                            .with_context(|| format!("Failed to read {}", path.display()))?;
+                        serde_json::from_str(&raw).context("Invalid quote")
+                    })
+                    .transpose()?;
+                if let Some(signed) = &quote {
+                    let quoted = signed.quote()?;
+                    ensure!(
+                        quoted.model == model && quoted.owner == self.wallet(),
+                        "Quote was issued for another model or wallet"
+                    );
+                    ensure!(quoted.input_hash == input_hash, "Quote was issued for another input");
+                    ensure!(
+                        bytes.len() <= quoted.input_size as usize,
+                        "Input is {} bytes; the quote covers {}",
+                        bytes.len(),
+                        quoted.input_size
+                    );
+                }
+                let (task, _) = Pubkey::find_program_address(
+                    &[b"task", self.wallet().as_ref(), &input_hash],
+                    &haunti_core::ID,
+                );
+                let (pricing, _) =
+                    Pubkey::find_program_address(&[b"pricing", model.as_ref()], &haunti_core::ID);
+                let (moderation, _) =
+                    Pubkey::find_program_address(&[b"moderation", model.as_ref()], &haunti_core::ID);
+                let event_authority = Self::core_event_authority();
+                let mut request = self.core()?.request();
+                if let Some(signed) = &quote {
+                    // The program reads the signature from the instruction right before it
+                    request = request.instruction(signed.ed25519_instruction()?);
+                }
+                let signature = request
+                    .accounts(haunti_core::accounts::CreateInferenceTask {
+                        model,
+                        moderation,
+                        pricing,
+                        task,
+                        prepaid: self.prepaid_account(prepaid),
+                        verifier_registry: Self::verifier_registry(),
+                        quote_key: quote.as_ref().map(|_| {
+                            Pubkey::find_program_address(&[b"quote_key"], &haunti_core::ID).0
+                        }),
+                        instructions_sysvar: quote.as_ref().map(|_| sysvar::instructions::ID),
+                        owner: self.wallet(),
+                        system_program: system_program::ID,
+                        event_authority,
+                        program: haunti_core::ID,
+                    })
+                    .args(haunti_core::instruction::CreateInferenceTask {
+                        input_hash,
+                        input_schema_hash,
+                        max_fee,
+                        surge_fee,
+                        quote: quote.as_ref().map(SignedQuote::quote).transpose()?,
+                    })
+                    .send()
+                    .await?;
+                Ok(Output::Tx {
+                    action: "submitted",
+                    signature: signature.to_string(),
+                    account: task.to_string(),
+                    cid: Some(cid),
+                })
+            }
+            InferCommand::Result { task, wait } => loop {
+                let view = TaskView::new(task, &self.fetch_task(task).await?);
+                if !wait || view.is_terminal() {
+                    return Ok(Output::Task(view));
+                }
+                tokio::time::sleep(Duration::from_secs(2)).await;
+            },
+        }
+    }
+
+    pub async fn gov(&self, command: GovCommand) -> anyhow::Result<Output> {
+        let program = self.vault()?;
+        match command {
+            GovCommand::Propose { pool, action } => {
+                let (proposal_type, amount, recipient) = action.payload()?;
+                let pool: PoolType = pool.into();
+                let (pool, _) = Pubkey::find_program_address(
+                    &[b"pool", pool.to_string().as_bytes()],
+                    &token_vault::ID,
+                );
+                let proposal = Keypair::new();
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or_default();
+                let epoch = token_vault::governance_epoch(now);
+                let (governance_epoch, _) = Pubkey::find_program_address(
+                    &[b"governance_epoch", pool.as_ref(), &epoch.to_le_bytes()],
+                    &token_vault::ID,
+                );
+                let signature = program
+                    .request()
+                    .accounts(token_vault::accounts::CreateProposal {
+                        pool,
+                        user_stake: self.user_pda(b"stake", &pool),
+                        proposal: proposal.pubkey(),
+                        governance_epoch,
+                        owner: self.wallet(),
+                        system_program: system_program::ID,
+                    })
+                    .args(token_vault::instruction::CreateProposal {
+                        proposal_type,
+                        amount,
+                        recipient,
+                        epoch,
+                    })
+                    .signer(&proposal)
+                    .send()
+                    .await?;
+                Ok(tx("proposed", signature, proposal.pubkey()))
+            }
+            GovCommand::Vote { proposal, approve, .. } => {
+                let state = program.account::<token_vault::Proposal>(proposal).await?;
+                let pool = state.pool;
+                // Pure delegates vote without a stake history of their own
+                let history = self.user_pda(b"stake_history", &pool);
+                let stake_history = program
+                    .rpc()
+                    .get_account_with_commitment(&history, CommitmentConfig::confirmed())
+                    .await?
+                    .value
+                    .map(|_| history);
+                let (vote_record, _) = Pubkey::find_program_address(
+                    &[b"vote", proposal.as_ref(), self.wallet().as_ref()],
+                    &token_vault::ID,
+                );
+                let (participation, _) = Pubkey::find_program_address(
+                    &[
+                        b"participation",
+                        pool.as_ref(),
+                        self.wallet().as_ref(),
+                        &state.epoch.to_le_bytes(),
+                    ],
+                    &token_vault::ID,
+                );
+                let signature = program
+                    .request()
+                    .accounts(token_vault::accounts::Vote {
+                        pool,
+                        proposal,
+                        stake_history,
+                        vote_record,
+                        participation,
+                        owner: self.wallet(),
+                        system_program: system_program::ID,
+                    })
+                    .args(token_vault::instruction::Vote { approve })
+                    .send()
+                    .await?;
+                Ok(tx("voted", signature, vote_record))
+            }
+        }
+    }
+}
+
+struct PoolAccounts {
+    pool: Pubkey,
+    /// Set when the pool follows an emission schedule
+    emission_schedule: Option<Pubkey>,
+    vault: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    receipt_mode: ReceiptMode,
+    /// stHAUNT mint of a liquid-receipt pool
+    receipt_mint: Pubkey,
+}
+
+impl PoolAccounts {
+    /// Associated token account of `owner` for the pool mint
+    fn ata(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.mint, &self.token_program)
+    }
+
+    /// Associated token account of `owner` for the pool's stHAUNT
+    fn receipt_ata(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.receipt_mint, &self.token_program)
+    }
+
+    /// NFT mint and `owner`'s token account for a position's receipt, if the pool
+    /// mints them
+    fn position_receipt(
+        &self,
+        position: &Pubkey,
+        owner: &Pubkey,
+    ) -> (Option<Pubkey>, Option<Pubkey>) {
+        if self.receipt_mode != ReceiptMode::PositionNft {
+            return (None, None);
+        }
+        let (mint, _) = Pubkey::find_program_address(
+            &[b"position_receipt", position.as_ref()],
+            &token_vault::ID,
+        );
+        let token = get_associated_token_address_with_program_id(owner, &mint, &self.token_program);
+        (Some(mint), Some(token))
+    }
+}
+
+fn tx(action: &'static str, signature: impl ToString, account: Pubkey) -> Output {
+    Output::Tx {
+        action,
+        signature: signature.to_string(),
+        account: account.to_string(),
+        cid: None,
+    }
+}
+
+fn parse_hash(hex_str: &str) -> anyhow::Result<[u8; 32]> {
+    let bytes = hex::decode(hex_str.trim_start_matches("0x")).context("Hash must be hex")?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Hash must be 32 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proposal_action_maps_to_single_payload() {
+        let action = ProposalAction {
+            lockup_period: Some(86_400),
+            ..Default::default()
+        };
+        assert_eq!(
+            action.payload().unwrap().0,
+            ProposalType::PoolParameterUpdate {
+                lockup_period: Some(86_400),
+                early_unstake_penalty_bps: None,
+            }
+        );
+
+        let recipient = Pubkey::new_unique();
+        let action = ProposalAction {
+            treasury_transfer: Some(500),
+            recipient: Some(recipient),
+            ..Default::default()
+        };
+        assert_eq!(
+            action.payload().unwrap(),
+            (ProposalType::TreasuryTransfer, Some(500), Some(recipient))
+        );
+
+        assert!(ProposalAction::default().payload().is_err());
+        let both = ProposalAction {
+            reward_rate: Some(1),
+            penalty_bps: Some(100),
+            ..Default::default()
+        };
+        assert!(both.payload().is_err());
+
+        let rotation = ProposalAction {
+            rotate_key: Some("01".repeat(32)),
+            vk_hash: Some("02".repeat(32)),
+            key_version: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            rotation.payload().unwrap().0,
+            ProposalType::VerifierKeyRotation {
+                circuit_id: [1; 32],
+                vk_hash: [2; 32],
+                version: 3,
+            }
+        );
+        assert!(parse_hash(&"ab".repeat(32)).is_ok());
+        assert!(parse_hash("abcd").is_err());
+    }
+}
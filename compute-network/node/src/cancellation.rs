@@ -0,0 +1,100 @@
+//! Per-task cancellation tokens shared by the API, scheduler, executor, and prover
+
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio_util::sync::CancellationToken;
+
+/// Tokens for tasks currently executing on this node
+#[derive(Default)]
+pub struct CancellationRegistry {
+    /// Token per task, tagged with the registration that owns it
+    tokens: Mutex<HashMap<Pubkey, (u64, CancellationToken)>>,
+    next_registration: AtomicU64,
+}
+
+impl CancellationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issue a token for `task`; it is unregistered when the guard drops
+    pub fn register(self: &Arc<Self>, task: Pubkey) -> CancellationGuard {
+        let token = CancellationToken::new();
+        let registration = self.next_registration.fetch_add(1, Ordering::Relaxed);
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(task, (registration, token.clone()));
+        CancellationGuard {
+            registry: self.clone(),
+            task,
+            registration,
+            token,
+        }
+    }
+
+    /// Signal cancellation; returns false if the task is not running here
+    pub fn cancel(&self, task: &Pubkey) -> bool {
+        match self.tokens.lock().unwrap().get(task) {
+            Some((_, token)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_running(&self, task: &Pubkey) -> bool {
+        self.tokens.lock().unwrap().contains_key(task)
+    }
+}
+
+/// Scoped registration of a running task's token
+pub struct CancellationGuard {
+    registry: Arc<CancellationRegistry>,
+    task: Pubkey,
+    registration: u64,
+    token: CancellationToken,
+}
+
+impl CancellationGuard {
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+}
+
+impl Drop for CancellationGuard {
+    fn drop(&mut self) {
+        let mut tokens = self.registry.tokens.lock().unwrap();
+        // A retry of the same task may have registered a newer token
+        if tokens.get(&self.task).map_or(false, |(r, _)| *r == self.registration) {
+            tokens.remove(&self.task);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_reaches_token_and_guard_unregisters() {
+        let registry = Arc::new(CancellationRegistry::new());
+        let task = Pubkey::new_unique();
+        assert!(!registry.cancel(&task));
+
+        let guard = registry.register(task);
+        let token = guard.token();
+        assert!(registry.cancel(&task));
+        assert!(token.is_cancelled());
+
+        drop(guard);
+        assert!(!registry.is_running(&task));
+    }
+}
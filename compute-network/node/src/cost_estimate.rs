@@ -0,0 +1,340 @@
+//! Preflight size and cost estimates for FHE tasks
+//!
+//! Users submit a model architecture and FHE parameters before creating a task;
+//! the coordinator answers with ciphertext sizes, bootstrap counts, GPU time,
+//! proof size, and the deposit `create_task` will ask for. Timings come from
+//! `cost_tables.json`, regenerated from the FHE inference benchmarks, and fees
+//! from the on-chain `DepositConfig`, so the quote matches what the program
+//! enforces.
+//!
+//! The model is deliberately simple: linear layers cost one ciphertext MAC per
+//! packed multiply-accumulate and one multiplicative level, activations cost one
+//! programmable bootstrap per packed ciphertext, and a noise refresh bootstrap is
+//! added whenever the depth since the last bootstrap reaches the parameter set's
+//! limit.
+
+use haunti_core::state::deposit_config::{DepositConfig, GpuTier, ResourceRequirements};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+
+/// Headroom on the estimated runtime when suggesting a task time limit
+const TIME_LIMIT_MARGIN: f64 = 1.5;
+/// Shortest time limit `create_task` accepts
+const MIN_TIME_LIMIT_SECS: u64 = 300;
+
+#[derive(Error, Debug)]
+pub enum EstimateError {
+    #[error("No benchmarked parameters for 2^{0} polynomial size")]
+    UnsupportedPolySize(u8),
+    #[error("Invalid estimate request: {0}")]
+    InvalidRequest(&'static str),
+    #[error("Cost table unreadable: {0}")]
+    Table(String),
+    #[error("Deposit schedule rejected the estimate: {0}")]
+    Deposit(String),
+}
+
+/// Benchmark results for one polynomial size
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolySizeCosts {
+    pub log2_poly_size: u8,
+    /// Multiplicative levels available between bootstraps
+    pub max_depth: u32,
+    /// One programmable bootstrap at the reference precision, consumer GPU
+    pub bootstrap_ms: f64,
+    /// One ciphertext-plaintext multiply-accumulate, consumer GPU
+    pub ct_mac_us: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TierSpeedup {
+    pub consumer: f64,
+    pub datacenter: f64,
+    pub high_memory: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProofCosts {
+    pub base_bytes: u64,
+    pub bytes_per_log2_constraint: u64,
+    pub constraints_per_gpu_sec: f64,
+    /// Lookup constraints per activated element
+    pub constraints_per_activation: u64,
+}
+
+/// Benchmark-derived cost tables
+#[derive(Debug, Clone, Deserialize)]
+pub struct CostTable {
+    pub reference_precision_bits: u8,
+    pub poly_sizes: Vec<PolySizeCosts>,
+    pub tier_speedup: TierSpeedup,
+    pub proof: ProofCosts,
+}
+
+impl CostTable {
+    /// Tables shipped with this release
+    pub fn benchmarked() -> Self {
+        serde_json::from_str(include_str!("../cost_tables.json"))
+            .expect("bundled cost table is valid")
+    }
+
+    /// Tables regenerated for a specific fleet
+    pub fn load(path: &Path) -> Result<Self, EstimateError> {
+        let raw = std::fs::read(path).map_err(|e| EstimateError::Table(e.to_string()))?;
+        serde_json::from_slice(&raw).map_err(|e| EstimateError::Table(e.to_string()))
+    }
+
+    fn poly_size(&self, log2_poly_size: u8) -> Result<&PolySizeCosts, EstimateError> {
+        self.poly_sizes
+            .iter()
+            .find(|p| p.log2_poly_size == log2_poly_size)
+            .ok_or(EstimateError::UnsupportedPolySize(log2_poly_size))
+    }
+
+    fn speedup(&self, class: GpuClass) -> f64 {
+        match class {
+            GpuClass::Consumer => self.tier_speedup.consumer,
+            GpuClass::Datacenter => self.tier_speedup.datacenter,
+            GpuClass::HighMemory => self.tier_speedup.high_memory,
+        }
+    }
+}
+
+/// JSON form of haunti-core's `GpuTier`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuClass {
+    Consumer,
+    Datacenter,
+    HighMemory,
+}
+
+impl From<GpuClass> for GpuTier {
+    fn from(class: GpuClass) -> Self {
+        match class {
+            GpuClass::Consumer => GpuTier::Consumer,
+            GpuClass::Datacenter => GpuTier::Datacenter,
+            GpuClass::HighMemory => GpuTier::HighMemory,
+        }
+    }
+}
+
+/// One layer of the model, in evaluation order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LayerSpec {
+    Dense {
+        inputs: u64,
+        outputs: u64,
+    },
+    Conv2d {
+        in_channels: u64,
+        out_channels: u64,
+        kernel: u64,
+        height: u64,
+        width: u64,
+    },
+    /// Elementwise nonlinearity (ReLU, sigmoid, ...) over `elements` values
+    Activation {
+        elements: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FheParamSpec {
+    pub log2_poly_size: u8,
+    pub ciphertext_modulus_bits: u16,
+    pub precision_bits: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreflightRequest {
+    /// Plaintext values per input sample
+    pub input_elements: u64,
+    pub layers: Vec<LayerSpec>,
+    pub fhe: FheParamSpec,
+    pub batch_size: u64,
+    pub gpu_tier: GpuClass,
+    pub gpu_count: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostEstimate {
+    pub ciphertext_bytes: u64,
+    pub input_ciphertexts: u64,
+    /// Encrypted input upload size
+    pub input_bytes: u64,
+    pub bootstraps: u64,
+    /// Execution plus proving, summed over all GPUs
+    pub gpu_seconds: f64,
+    pub proof_bytes: u64,
+    /// Time limit covering the estimate with headroom
+    pub suggested_time_limit_secs: u64,
+    /// Deposit `create_task` requires for that time limit, in lamports
+    pub total_fee_lamports: u64,
+}
+
+/// Estimate sizes and runtime, then price the task against `deposits`
+pub fn estimate(
+    table: &CostTable,
+    deposits: &DepositConfig,
+    request: &PreflightRequest,
+) -> Result<CostEstimate, EstimateError> {
+    if request.batch_size == 0 || request.input_elements == 0 || request.gpu_count == 0 {
+        return Err(EstimateError::InvalidRequest("batch, input and GPU counts must be non-zero"));
+    }
+    if request.fhe.precision_bits == 0 || request.fhe.ciphertext_modulus_bits == 0 {
+        return Err(EstimateError::InvalidRequest("precision and modulus must be non-zero"));
+    }
+    let costs = table.poly_size(request.fhe.log2_poly_size)?;
+    let poly_size = 1u64 << request.fhe.log2_poly_size;
+    // Half the coefficients carry packed values
+    let slots = poly_size / 2;
+    let packed = |elements: u64| (elements.saturating_mul(request.batch_size)).div_ceil(slots);
+
+    let ciphertext_bytes = 2 * poly_size * request.fhe.ciphertext_modulus_bits as u64 / 8;
+    let input_ciphertexts = packed(request.input_elements);
+
+    let (mut macs, mut bootstraps, mut activations, mut depth) = (0u64, 0u64, 0u64, 0u32);
+    for layer in &request.layers {
+        match *layer {
+            LayerSpec::Dense { inputs, outputs } => {
+                macs = macs.saturating_add(packed(inputs.saturating_mul(outputs)));
+                depth += 1;
+            }
+            LayerSpec::Conv2d { in_channels, out_channels, kernel, height, width } => {
+                let per_sample = in_channels
+                    .saturating_mul(out_channels)
+                    .saturating_mul(kernel.saturating_mul(kernel))
+                    .saturating_mul(height.saturating_mul(width));
+                macs = macs.saturating_add(packed(per_sample));
+                depth += 1;
+            }
+            LayerSpec::Activation { elements } => {
+                bootstraps = bootstraps.saturating_add(packed(elements));
+                activations = activations.saturating_add(elements.saturating_mul(request.batch_size));
+                // Programmable bootstrapping also resets the noise
+                depth = 0;
+                continue;
+            }
+        }
+        if depth >= costs.max_depth {
+            bootstraps = bootstraps.saturating_add(input_ciphertexts.max(1));
+            depth = 0;
+        }
+    }
+
+    let speedup = table.speedup(request.gpu_tier);
+    let precision_scale = request.fhe.precision_bits as f64 / table.reference_precision_bits as f64;
+    let execution_secs = (bootstraps as f64 * costs.bootstrap_ms * precision_scale / 1e3
+        + macs as f64 * costs.ct_mac_us / 1e6)
+        / speedup;
+
+    let constraints = macs
+        .saturating_mul(slots)
+        .saturating_add(activations.saturating_mul(table.proof.constraints_per_activation))
+        .max(1);
+    let proving_secs = constraints as f64 / table.proof.constraints_per_gpu_sec / speedup;
+    let proof_bytes = table.proof.base_bytes
+        + table.proof.bytes_per_log2_constraint * (64 - constraints.leading_zeros()) as u64;
+
+    let gpu_seconds = execution_secs + proving_secs;
+    let wall_secs = gpu_seconds / request.gpu_count as f64 * TIME_LIMIT_MARGIN;
+    let suggested_time_limit_secs = (wall_secs.ceil() as u64).max(MIN_TIME_LIMIT_SECS);
+    let total_fee_lamports = deposits
+        .required_deposit(
+            &ResourceRequirements {
+                gpu_tier: request.gpu_tier.into(),
+                gpu_count: request.gpu_count,
+            },
+            suggested_time_limit_secs,
+        )
+        .map_err(|e| EstimateError::Deposit(e.to_string()))?;
+
+    Ok(CostEstimate {
+        ciphertext_bytes,
+        input_ciphertexts,
+        input_bytes: input_ciphertexts * ciphertext_bytes,
+        bootstraps,
+        gpu_seconds,
+        proof_bytes,
+        suggested_time_limit_secs,
+        total_fee_lamports,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposits() -> DepositConfig {
+        DepositConfig {
+            min_deposit: 100,
+            tier_rates: [3_600, 7_200, 10_800],
+            ..Default::default()
+        }
+    }
+
+    fn request(layers: Vec<LayerSpec>) -> PreflightRequest {
+        PreflightRequest {
+            input_elements: 3 * 32 * 32,
+            layers,
+            fhe: FheParamSpec {
+                log2_poly_size: 15,
+                ciphertext_modulus_bits: 64,
+                precision_bits: 16,
+            },
+            batch_size: 8,
+            gpu_tier: GpuClass::Consumer,
+            gpu_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_bundled_table_parses() {
+        let table = CostTable::benchmarked();
+        assert!(table.poly_size(14).is_ok() && table.poly_size(17).is_ok());
+    }
+
+    #[test]
+    fn test_estimate_counts_sizes_and_bootstraps() {
+        let table = CostTable::benchmarked();
+        let layers = vec![
+            LayerSpec::Dense { inputs: 3 * 32 * 32, outputs: 128 },
+            LayerSpec::Activation { elements: 128 },
+            LayerSpec::Dense { inputs: 128, outputs: 10 },
+        ];
+        let estimate = estimate(&table, &deposits(), &request(layers)).unwrap();
+
+        // 2^15 coefficients of 64 bits, two polynomials per ciphertext
+        assert_eq!(estimate.ciphertext_bytes, 2 * 32_768 * 8);
+        // 3072 values x 8 samples over 16384 slots
+        assert_eq!(estimate.input_ciphertexts, 2);
+        assert_eq!(estimate.bootstraps, 1);
+        assert_eq!(estimate.suggested_time_limit_secs, MIN_TIME_LIMIT_SECS);
+        // One consumer GPU at 1 lamport per second
+        assert_eq!(estimate.total_fee_lamports, 300);
+        assert!(estimate.proof_bytes > table.proof.base_bytes);
+    }
+
+    #[test]
+    fn test_deep_models_pay_for_noise_refresh() {
+        let table = CostTable::benchmarked();
+        let dense = LayerSpec::Dense { inputs: 64, outputs: 64 };
+        let shallow = estimate(&table, &deposits(), &request(vec![dense.clone(); 7])).unwrap();
+        let deep = estimate(&table, &deposits(), &request(vec![dense; 8])).unwrap();
+        assert_eq!((shallow.bootstraps, deep.bootstraps), (0, 2));
+
+        let mut faster = request(vec![LayerSpec::Activation { elements: 1 << 20 }]);
+        let slow = estimate(&table, &deposits(), &faster).unwrap();
+        faster.gpu_tier = GpuClass::HighMemory;
+        assert!(estimate(&table, &deposits(), &faster).unwrap().gpu_seconds < slow.gpu_seconds);
+
+        faster.fhe.log2_poly_size = 12;
+        assert!(matches!(
+            estimate(&table, &deposits(), &faster),
+            Err(EstimateError::UnsupportedPolySize(12))
+        ));
+    }
+}
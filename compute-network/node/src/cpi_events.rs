@@ -0,0 +1,99 @@
+//! Recovers self-CPI (`emit_cpi!`) events from transaction inner instructions
+//!
+//! Log subscriptions only carry program logs, so when a transaction shows the
+//! program invoking itself the full transaction is fetched and every inner
+//! instruction addressed to the program that starts with Anchor's event tag is
+//! returned for decoding.
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{bs58, commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+    EncodedTransaction, UiInstruction, UiMessage, UiTransactionEncoding,
+};
+use std::str::FromStr;
+
+/// True if `logs` show `program` invoked from within a transaction at depth > 1
+pub fn has_self_cpi(program: &Pubkey, logs: &[String]) -> bool {
+    let prefix = format!("Program {} invoke [", program);
+    logs.iter()
+        .filter_map(|line| line.strip_prefix(&prefix))
+        .any(|depth| depth != "1]")
+}
+
+/// Fetch `signature` and return the data of each event self-CPI made by `program`
+pub async fn fetch_event_data(
+    rpc: &RpcClient,
+    program: &Pubkey,
+    signature: &str,
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    let tx = rpc
+        .get_transaction_with_config(
+            &Signature::from_str(signature)?,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+    Ok(event_data(&tx, program))
+}
+
+/// Inner instruction data addressed to `program` that carries an event
+pub fn event_data(tx: &EncodedConfirmedTransactionWithStatusMeta, program: &Pubkey) -> Vec<Vec<u8>> {
+    let Some(meta) = &tx.transaction.meta else {
+        return Vec::new();
+    };
+    let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction else {
+        return Vec::new();
+    };
+    let UiMessage::Raw(message) = &ui_tx.message else {
+        return Vec::new();
+    };
+
+    // Static keys first, then v0 lookup-table writable and readonly addresses
+    let mut account_keys = message.account_keys.clone();
+    if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+        account_keys.extend(loaded.writable.iter().cloned());
+        account_keys.extend(loaded.readonly.iter().cloned());
+    }
+    let program = program.to_string();
+
+    let OptionSerializer::Some(inner) = &meta.inner_instructions else {
+        return Vec::new();
+    };
+    inner
+        .iter()
+        .flat_map(|set| &set.instructions)
+        .filter_map(|ix| match ix {
+            UiInstruction::Compiled(ix) => Some(ix),
+            _ => None,
+        })
+        .filter(|ix| account_keys.get(ix.program_id_index as usize) == Some(&program))
+        .filter_map(|ix| bs58::decode(&ix.data).into_vec().ok())
+        .filter(|data| data.starts_with(&EVENT_IX_TAG_LE))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_cpi_detected_from_invoke_depth() {
+        let program = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let top_level = vec![
+            format!("Program {} invoke [1]", program),
+            format!("Program {} invoke [2]", other),
+            format!("Program {} success", program),
+        ];
+        assert!(!has_self_cpi(&program, &top_level));
+
+        let mut with_event = top_level.clone();
+        with_event.insert(2, format!("Program {} invoke [2]", program));
+        assert!(has_self_cpi(&program, &with_event));
+    }
+}
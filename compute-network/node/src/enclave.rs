@@ -0,0 +1,275 @@
+//! TEE-gated model key release and enclave execution (SEV-SNP / TDX)
+
+use haunti_core::state::task_state::EnclaveAttestation;
+use serde::{Deserialize, Serialize};
+use solana_program::keccak;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Domain separator for the attestation report-data binding
+const REPORT_DATA_TAG: &[u8] = b"haunti-key-release-v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TeeKind {
+    SevSnp,
+    Tdx,
+}
+
+/// Hardware attestation report produced inside the enclave
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationReport {
+    pub tee: TeeKind,
+    /// Launch measurement (SNP MEASUREMENT / TDX MRTD)
+    pub measurement: Vec<u8>,
+    /// Caller-chosen data signed into the report
+    pub report_data: Vec<u8>,
+    /// Raw signed report as returned by the platform
+    pub raw: Vec<u8>,
+}
+
+impl AttestationReport {
+    /// What the worker submits on-chain, checked there against the model's
+    /// registered measurement
+    pub fn attestation(&self) -> EnclaveAttestation {
+        EnclaveAttestation {
+            measurement_hash: keccak::hash(&self.measurement).0,
+            report_hash: keccak::hash(&self.raw).0,
+        }
+    }
+
+    /// Hash bound into the task proof and recorded on-chain
+    pub fn digest(&self) -> [u8; 32] {
+        self.attestation().digest()
+    }
+}
+
+/// Report data tying an attestation to one task and one enclave key
+pub fn report_data_for(task_id: &[u8; 32], enclave_public_key: &[u8; 32]) -> [u8; 64] {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(&keccak::hashv(&[REPORT_DATA_TAG, task_id, enclave_public_key]).0);
+    data
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyReleaseRequest {
+    pub task_id: [u8; 32],
+    pub model_id: String,
+    pub enclave_public_key: [u8; 32],
+    pub report: AttestationReport,
+}
+
+/// Model decryption key wrapped to an enclave's ephemeral public key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedKey {
+    pub enclave_public_key: [u8; 32],
+    pub nonce: [u8; 12],
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(Error, Debug)]
+pub enum EnclaveError {
+    #[error("Attestation failed: {0}")]
+    Attestation(String),
+    #[error("TEE type {0:?} not permitted by policy")]
+    TeeNotPermitted(TeeKind),
+    #[error("Measurement not permitted by policy")]
+    MeasurementNotPermitted,
+    #[error("Report data does not bind this task and enclave key")]
+    ReportDataMismatch,
+    #[error("Key was wrapped to a different enclave")]
+    WrongRecipient,
+    #[error("Key release service error: {0}")]
+    KeyRelease(#[from] reqwest::Error),
+    #[error("Enclave execution failed: {0}")]
+    Execution(String),
+}
+
+/// Which enclaves a model owner is willing to release keys to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttestationPolicy {
+    pub allowed_tees: Vec<TeeKind>,
+    /// Accepted launch measurements; empty accepts any for the allowed TEEs
+    pub allowed_measurements: Vec<Vec<u8>>,
+}
+
+impl AttestationPolicy {
+    /// Policy checks shared by the key-release service and the worker's self-check
+    pub fn check(&self, request: &KeyReleaseRequest) -> Result<(), EnclaveError> {
+        let report = &request.report;
+        if !self.allowed_tees.contains(&report.tee) {
+            return Err(EnclaveError::TeeNotPermitted(report.tee));
+        }
+        if !self.allowed_measurements.is_empty()
+            && !self.allowed_measurements.contains(&report.measurement)
+        {
+            return Err(EnclaveError::MeasurementNotPermitted);
+        }
+        let expected = report_data_for(&request.task_id, &request.enclave_public_key);
+        if report.report_data.as_slice() != expected.as_slice() {
+            return Err(EnclaveError::ReportDataMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Platform-specific enclave; the plaintext model key never leaves it
+pub trait EnclaveBackend: Send + Sync {
+    fn tee(&self) -> TeeKind;
+
+    /// Ephemeral key the release service wraps the model key to
+    fn public_key(&self) -> [u8; 32];
+
+    fn attest(&self, report_data: [u8; 64]) -> Result<AttestationReport, EnclaveError>;
+
+    /// Unwrap the key, decrypt the model and run it, all inside the enclave
+    fn run_sealed(
+        &self,
+        key: &WrappedKey,
+        encrypted_model: &[u8],
+        data: &[u8],
+    ) -> Result<Vec<u8>, EnclaveError>;
+}
+
+/// Client for the model owner's key-release service
+pub struct KeyReleaseClient {
+    http: reqwest::Client,
+    endpoint: String,
+}
+
+impl KeyReleaseClient {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: endpoint.into(),
+        }
+    }
+
+    pub async fn release(&self, request: &KeyReleaseRequest) -> Result<WrappedKey, EnclaveError> {
+        let key = self
+            .http
+            .post(format!("{}/v1/release", self.endpoint))
+            .json(request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<WrappedKey>()
+            .await?;
+        Ok(key)
+    }
+}
+
+/// Output of an attested run
+#[derive(Debug, Clone)]
+pub struct EnclaveOutput {
+    pub result: Vec<u8>,
+    pub attestation: EnclaveAttestation,
+}
+
+/// Attest, obtain the wrapped model key, and execute inside the enclave
+pub struct EnclaveExecutor {
+    backend: Arc<dyn EnclaveBackend>,
+    key_release: KeyReleaseClient,
+}
+
+impl EnclaveExecutor {
+    pub fn new(backend: Arc<dyn EnclaveBackend>, key_release: KeyReleaseClient) -> Self {
+        Self { backend, key_release }
+    }
+
+    /// `registered_measurement` is the model's on-chain enclave measurement; a
+    /// report for any other enclave would be rejected at completion, so the run
+    /// stops before a key is requested
+    pub async fn execute(
+        &self,
+        task_id: [u8; 32],
+        model_id: &str,
+        registered_measurement: &[u8; 32],
+        encrypted_model: &[u8],
+        data: &[u8],
+    ) -> Result<EnclaveOutput, EnclaveError> {
+        let enclave_public_key = self.backend.public_key();
+        let report = self
+            .backend
+            .attest(report_data_for(&task_id, &enclave_public_key))?;
+        let attestation = report.attestation();
+        if attestation.measurement_hash != *registered_measurement {
+            return Err(EnclaveError::MeasurementNotPermitted);
+        }
+
+        let key = self
+            .key_release
+            .release(&KeyReleaseRequest {
+                task_id,
+                model_id: model_id.to_string(),
+                enclave_public_key,
+                report,
+            })
+            .await?;
+        if key.enclave_public_key != enclave_public_key {
+            return Err(EnclaveError::WrongRecipient);
+        }
+
+        let result = self.backend.run_sealed(&key, encrypted_model, data)?;
+        Ok(EnclaveOutput {
+            result,
+            attestation,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(tee: TeeKind, measurement: Vec<u8>) -> KeyReleaseRequest {
+        let task_id = [1u8; 32];
+        let enclave_public_key = [2u8; 32];
+        KeyReleaseRequest {
+            task_id,
+            model_id: "model-a".into(),
+            enclave_public_key,
+            report: AttestationReport {
+                tee,
+                measurement,
+                report_data: report_data_for(&task_id, &enclave_public_key).to_vec(),
+                raw: vec![9; 16],
+            },
+        }
+    }
+
+    #[test]
+    fn test_policy_enforces_tee_measurement_and_binding() {
+        let policy = AttestationPolicy {
+            allowed_tees: vec![TeeKind::SevSnp],
+            allowed_measurements: vec![vec![0xaa; 48]],
+        };
+
+        assert!(policy.check(&request(TeeKind::SevSnp, vec![0xaa; 48])).is_ok());
+        assert!(matches!(
+            policy.check(&request(TeeKind::Tdx, vec![0xaa; 48])),
+            Err(EnclaveError::TeeNotPermitted(TeeKind::Tdx))
+        ));
+        assert!(matches!(
+            policy.check(&request(TeeKind::SevSnp, vec![0xbb; 48])),
+            Err(EnclaveError::MeasurementNotPermitted)
+        ));
+
+        // A report replayed for another task must be rejected
+        let mut replayed = request(TeeKind::SevSnp, vec![0xaa; 48]);
+        replayed.task_id = [3u8; 32];
+        assert!(matches!(policy.check(&replayed), Err(EnclaveError::ReportDataMismatch)));
+    }
+
+    #[test]
+    fn test_digest_commits_to_measurement() {
+        let report = request(TeeKind::SevSnp, vec![0xaa; 48]).report;
+        let attestation = report.attestation();
+        assert_eq!(attestation.measurement_hash, keccak::hash(&[0xaa; 48]).0);
+        assert_eq!(report.digest(), attestation.digest());
+
+        // Same raw report under another measurement records a different hash
+        let other = request(TeeKind::SevSnp, vec![0xbb; 48]).report;
+        assert_ne!(report.digest(), other.digest());
+    }
+}
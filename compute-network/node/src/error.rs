@@ -0,0 +1,208 @@
+//! Node-wide error taxonomy and retry classification
+
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::{
+    enclave::EnclaveError, task_manager::TaskManagerError, tenancy::TenantError,
+    verifier_routing::RoutingError, verify_pool::VerifyPoolError,
+};
+
+/// Base delay for retrying transient failures, doubled per attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+/// Upper bound on a single retry delay
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+/// Failures expected to clear on their own (RPC hiccups, timeouts, backpressure)
+#[derive(Error, Debug)]
+pub enum TransientError {
+    #[error("RPC unavailable: {0}")]
+    Rpc(String),
+    #[error("Timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("Storage unavailable: {0}")]
+    Storage(String),
+    #[error("Resources temporarily exhausted")]
+    ResourcesExhausted,
+}
+
+/// Failures that will recur for the same task no matter how often it is retried
+#[derive(Error, Debug)]
+pub enum PermanentError {
+    #[error("Generated proof failed verification")]
+    InvalidProof,
+    #[error("Task not found")]
+    TaskNotFound,
+    #[error("Invalid state transition")]
+    InvalidStateTransition,
+    #[error("Execution failed: {0}")]
+    Execution(String),
+    #[error("Task cancelled")]
+    Cancelled,
+}
+
+/// Problems with what the submitter asked for
+#[derive(Error, Debug)]
+pub enum UserError {
+    #[error("Invalid task: {0}")]
+    InvalidTask(String),
+    #[error(transparent)]
+    Tenant(#[from] TenantError),
+}
+
+/// Faults in this node's own environment
+#[derive(Error, Debug)]
+pub enum InfraError {
+    #[error("GPU failure: {0}")]
+    Gpu(String),
+    #[error("Enclave failure: {0}")]
+    Enclave(String),
+    #[error("Misconfiguration: {0}")]
+    Config(String),
+}
+
+#[derive(Error, Debug)]
+pub enum NodeError {
+    #[error("Transient: {0}")]
+    Transient(#[from] TransientError),
+    #[error("Permanent: {0}")]
+    Permanent(#[from] PermanentError),
+    #[error("User error: {0}")]
+    User(#[from] UserError),
+    #[error("Infrastructure: {0}")]
+    Infra(#[from] InfraError),
+}
+
+impl NodeError {
+    /// Whether the same task may succeed if retried on this node
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, NodeError::Transient(_))
+    }
+
+    /// Cancellation is neither retried nor dead-lettered
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, NodeError::Permanent(PermanentError::Cancelled))
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            NodeError::Permanent(PermanentError::Cancelled) => Severity::Info,
+            NodeError::Transient(_) => Severity::Warning,
+            NodeError::User(_) => Severity::Info,
+            NodeError::Permanent(_) => Severity::Error,
+            NodeError::Infra(_) => Severity::Critical,
+        }
+    }
+
+    /// Retry with exponential backoff or move the task to the dead-letter queue
+    pub fn retry_decision(&self, attempt: u32, max_attempts: u32) -> RetryDecision {
+        if !self.is_retryable() || attempt + 1 >= max_attempts {
+            return RetryDecision::DeadLetter;
+        }
+        let delay = RETRY_BASE_DELAY.saturating_mul(1 << attempt.min(16));
+        RetryDecision::Retry(delay.min(RETRY_MAX_DELAY))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    Retry(Duration),
+    DeadLetter,
+}
+
+impl From<solana_client::client_error::ClientError> for NodeError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        TransientError::Rpc(e.to_string()).into()
+    }
+}
+
+impl From<tokio::time::error::Elapsed> for NodeError {
+    fn from(_: tokio::time::error::Elapsed) -> Self {
+        TransientError::Timeout(Duration::from_secs(300)).into()
+    }
+}
+
+impl From<TenantError> for NodeError {
+    fn from(e: TenantError) -> Self {
+        UserError::Tenant(e).into()
+    }
+}
+
+impl From<TaskManagerError> for NodeError {
+    fn from(e: TaskManagerError) -> Self {
+        match e {
+            TaskManagerError::InsufficientResources => TransientError::ResourcesExhausted.into(),
+            TaskManagerError::TaskNotFound => PermanentError::TaskNotFound.into(),
+            TaskManagerError::InvalidStateTransition => PermanentError::InvalidStateTransition.into(),
+            TaskManagerError::RpcError(e) => e.into(),
+            TaskManagerError::SerializationError(e) => UserError::InvalidTask(e.to_string()).into(),
+            TaskManagerError::Tenant(e) => e.into(),
+        }
+    }
+}
+
+impl From<VerifyPoolError> for NodeError {
+    fn from(e: VerifyPoolError) -> Self {
+        match e {
+            VerifyPoolError::PoolBuild(e) => InfraError::Config(e.to_string()).into(),
+            VerifyPoolError::WorkerDropped => TransientError::ResourcesExhausted.into(),
+            VerifyPoolError::Backend(e) => PermanentError::Execution(e.to_string()).into(),
+        }
+    }
+}
+
+impl From<RoutingError> for NodeError {
+    fn from(e: RoutingError) -> Self {
+        match e {
+            // The first registry poll has not landed yet
+            RoutingError::NotLoaded => TransientError::Rpc(e.to_string()).into(),
+            RoutingError::UnknownVersion(_) | RoutingError::Retired(_) => {
+                PermanentError::Execution(e.to_string()).into()
+            }
+        }
+    }
+}
+
+impl From<EnclaveError> for NodeError {
+    fn from(e: EnclaveError) -> Self {
+        match e {
+            EnclaveError::KeyRelease(e) => TransientError::Rpc(e.to_string()).into(),
+            EnclaveError::TeeNotPermitted(_)
+            | EnclaveError::MeasurementNotPermitted
+            | EnclaveError::ReportDataMismatch
+            | EnclaveError::WrongRecipient => InfraError::Enclave(e.to_string()).into(),
+            EnclaveError::Attestation(msg) => InfraError::Enclave(msg).into(),
+            EnclaveError::Execution(msg) => PermanentError::Execution(msg).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_decisions_follow_class() {
+        let transient: NodeError = TransientError::Rpc("429".into()).into();
+        assert!(transient.is_retryable());
+        assert_eq!(transient.retry_decision(0, 5), RetryDecision::Retry(Duration::from_secs(2)));
+        assert_eq!(transient.retry_decision(2, 5), RetryDecision::Retry(Duration::from_secs(8)));
+        assert_eq!(transient.retry_decision(4, 5), RetryDecision::DeadLetter);
+
+        let permanent: NodeError = PermanentError::InvalidProof.into();
+        assert!(!permanent.is_retryable());
+        assert_eq!(permanent.severity(), Severity::Error);
+        assert_eq!(permanent.retry_decision(0, 5), RetryDecision::DeadLetter);
+
+        let quota: NodeError = TaskManagerError::Tenant(TenantError::InvalidApiKey).into();
+        assert_eq!(quota.severity(), Severity::Info);
+    }
+}
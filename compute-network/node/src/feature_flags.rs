@@ -0,0 +1,92 @@
+//! Local view of the on-chain feature flags
+//!
+//! The coordinator polls haunti-core's `FeatureFlags` account and answers
+//! `is_active` from the latest snapshot, extrapolating the slot between polls so
+//! a flag switches close to the slot governance scheduled rather than a full poll
+//! interval late. Until the first successful poll every flag reads inactive.
+
+use haunti_core::state::feature_flags::{FeatureFlags, MAX_FEATURES};
+use std::time::Instant;
+use tokio::sync::RwLock;
+
+/// Approximate slot time used to extrapolate between polls
+const SLOT_MS: u128 = 400;
+
+struct Snapshot {
+    flags: FeatureFlags,
+    slot: u64,
+    fetched_at: Instant,
+}
+
+impl Snapshot {
+    fn estimated_slot(&self) -> u64 {
+        self.slot + (self.fetched_at.elapsed().as_millis() / SLOT_MS) as u64
+    }
+}
+
+#[derive(Default)]
+pub struct FeatureGate {
+    snapshot: RwLock<Option<Snapshot>>,
+}
+
+impl FeatureGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn is_active(&self, feature: u8) -> bool {
+        self.snapshot
+            .read()
+            .await
+            .as_ref()
+            .is_some_and(|s| s.flags.is_active(feature, s.estimated_slot()))
+    }
+
+    /// Replace the snapshot with a fresh poll taken at `slot`; returns each flag
+    /// whose state differs from the previous estimate, with its new state
+    pub async fn update(&self, flags: FeatureFlags, slot: u64) -> Vec<(u8, bool)> {
+        let next = Snapshot { flags, slot, fetched_at: Instant::now() };
+        let mut snapshot = self.snapshot.write().await;
+        let prev = snapshot.as_ref().map(|s| (&s.flags, s.estimated_slot()));
+        let changes = changed(prev, (&next.flags, slot));
+        *snapshot = Some(next);
+        changes
+    }
+}
+
+/// Flags active in exactly one of `prev` and `next`, each at its own slot
+fn changed(prev: Option<(&FeatureFlags, u64)>, next: (&FeatureFlags, u64)) -> Vec<(u8, bool)> {
+    (0..MAX_FEATURES as u8)
+        .filter_map(|feature| {
+            let was = prev.is_some_and(|(flags, slot)| flags.is_active(feature, slot));
+            let now = next.0.is_active(feature, next.1);
+            (was != now).then_some((feature, now))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use haunti_core::state::feature_flags::feature;
+
+    #[test]
+    fn test_changes_reported_once_per_transition() {
+        let mut flags = FeatureFlags::default();
+        flags.set(feature::SCHEDULER_POLICY_V2, Some(100), 0).unwrap();
+
+        assert!(changed(None, (&flags, 50)).is_empty());
+        assert_eq!(
+            changed(Some((&flags, 50)), (&flags, 100)),
+            vec![(feature::SCHEDULER_POLICY_V2, true)]
+        );
+        assert!(changed(Some((&flags, 100)), (&flags, 150)).is_empty());
+
+        let mut disabled = flags.clone();
+        disabled.set(feature::SCHEDULER_POLICY_V2, None, 150).unwrap();
+        assert_eq!(
+            changed(Some((&flags, 150)), (&disabled, 151)),
+            vec![(feature::SCHEDULER_POLICY_V2, false)]
+        );
+    }
+}
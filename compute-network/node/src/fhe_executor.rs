@@ -18,6 +18,8 @@ use plonky3::{
 use rayon::prelude::*;
 use solana_gpu_sdk::cuda::DeviceBuffer;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use crate::pinned_memory::{PinnedMemoryError, PinnedPool};
 use tfhe::{
     ggsw::compute_pbs_decrypt_lwe_ciphertext_gpu,
     shortint::{Ciphertext, ClientKey, Parameters, PublicKey},
@@ -68,6 +70,8 @@ pub struct FheExecutor {
     ctx: Arc<FheExecutionContext>,
     task_queue: Vec<FheComputeTask>,
     cuda_streams: Vec<DeviceBuffer>,
+    /// Pinned staging buffers for input uploads; pageable memory when unset
+    pinned_pool: Option<Arc<PinnedPool>>,
 }
 
 impl FheExecutor {
@@ -89,9 +93,17 @@ impl FheExecutor {
             cuda_streams: (0..4)
                 .map(|_| DeviceBuffer::new(1024 * 1024).unwrap())
                 .collect(),
+            pinned_pool: None,
         }
     }
 
+    /// Stage uploads through pinned buffers on the GPU's NUMA node, caching up
+    /// to `max_cached_bytes` of them
+    pub fn with_pinned_staging(mut self, max_cached_bytes: usize) -> Result<Self, ExecutorError> {
+        self.pinned_pool = Some(PinnedPool::for_device(0, max_cached_bytes)?);
+        Ok(self)
+    }
+
     /// Process batch of FHE tasks with GPU acceleration
     pub fn execute_tasks(&mut self, tasks: Vec<FheComputeTask>) -> Vec<FheExecutionResult> {
         let ctx = self.ctx.clone();
@@ -107,6 +119,50 @@ impl FheExecutor {
             .collect()
     }
 
+    /// Execute a single task, aborting its GPU stream as soon as `cancel` fires
+    pub fn execute_task_cancellable(
+        &self,
+        task: &FheComputeTask,
+        stream_idx: usize,
+        cancel: &CancellationToken,
+    ) -> Result<FheExecutionResult, ExecutorError> {
+        let stream = &self.cuda_streams[stream_idx % self.cuda_streams.len()];
+        let model_ct: Vec<Ciphertext> = bincode::deserialize(&task.encrypted_model)
+            .map_err(|e| ExecutorError::FheExecution(e.to_string()))?;
+        let input_ct: Vec<Ciphertext> = bincode::deserialize(&task.encrypted_inputs)
+            .map_err(|e| ExecutorError::FheExecution(e.to_string()))?;
+
+        // Upload the inputs from pinned memory so the copy is a single DMA
+        // instead of going through the driver's bounce buffer; the staging
+        // buffer goes back to the pool once the stream has consumed it
+        let staged = match &self.pinned_pool {
+            Some(pool) => {
+                let mut buffer = pool.acquire(task.encrypted_inputs.len())?;
+                buffer.fill(&task.encrypted_inputs);
+                stream
+                    .copy_from_host_async(buffer.as_slice())
+                    .map_err(|e| ExecutorError::CudaError(e.to_string()))?;
+                Some(buffer)
+            }
+            None => None,
+        };
+
+        let output_ct = Self::encrypted_inference(&model_ct, &input_ct, &self.ctx, stream, cancel)?;
+        drop(staged);
+        // Proving is not interruptible, so skip it entirely if cancelled in between
+        if cancel.is_cancelled() {
+            return Err(ExecutorError::Cancelled);
+        }
+        let (proof, commitment) = Self::generate_proof(&output_ct, task, &self.ctx);
+
+        Ok(FheExecutionResult {
+            task_id: task.task_id,
+            encrypted_outputs: bincode::serialize(&output_ct).unwrap(),
+            zk_proof: bincode::serialize(&proof).unwrap(),
+            proof_commitment: commitment,
+        })
+    }
+
     fn process_single_task(
         task: &FheComputeTask,
         ctx: &Arc<FheExecutionContext>,
@@ -118,8 +174,9 @@ impl FheExecutor {
         let input_ct: Vec<Ciphertext> = bincode::deserialize(&task.encrypted_inputs)
             .expect("Invalid input ciphertext");
 
-        // Execute FHE computation
-        let output_ct = Self::encrypted_inference(&model_ct, &input_ct, ctx, stream);
+        // Execute FHE computation; batch execution is never cancelled
+        let output_ct = Self::encrypted_inference(&model_ct, &input_ct, ctx, stream, &CancellationToken::new())
+            .expect("FHE inference failed");
 
         // Generate ZK proof
         let (proof, commitment) = Self::generate_proof(&output_ct, task, ctx);
@@ -137,7 +194,8 @@ impl FheExecutor {
         inputs: &[Ciphertext],
         ctx: &FheExecutionContext,
         stream: &DeviceBuffer,
-    ) -> Vec<Ciphertext> {
+        cancel: &CancellationToken,
+    ) -> Result<Vec<Ciphertext>, ExecutorError> {
         // GPU-accelerated FHE operations
         ctx.gpu_engine.bind_stream(stream);
         let mut outputs = Vec::with_capacity(inputs.len());
@@ -145,6 +203,12 @@ impl FheExecutor {
         for input in inputs {
             let mut acc = model[0].clone();
             for (weight, bias) in model[1..].chunks(2) {
+                // Checked per layer: drop queued kernels and fence the stream so its memory is reusable
+                if cancel.is_cancelled() {
+                    ctx.gpu_engine.abort_stream(stream);
+                    ctx.gpu_engine.synchronize_stream(stream);
+                    return Err(ExecutorError::Cancelled);
+                }
                 let weighted = compute_pbs_decrypt_lwe_ciphertext_gpu(
                     &input,
                     &weight,
@@ -164,7 +228,7 @@ impl FheExecutor {
             outputs.push(acc.clone());
         }
 
-        outputs
+        Ok(outputs)
     }
 
     fn generate_proof(
@@ -236,6 +300,7 @@ pub enum ExecutorError {
     ProofGeneration(String),
     AccountAccess(String),
     CudaError(String),
+    Cancelled,
 }
 
 impl From<concrete::Error> for ExecutorError {
@@ -244,6 +309,12 @@ impl From<concrete::Error> for ExecutorError {
     }
 }
 
+impl From<PinnedMemoryError> for ExecutorError {
+    fn from(e: PinnedMemoryError) -> Self {
+        ExecutorError::CudaError(e.to_string())
+    }
+}
+
 impl From<plonky3::plonk::proof::ProofError> for ExecutorError {
     fn from(e: plonky3::plonk::proof::ProofError) -> Self {
         ExecutorError::ProofGeneration(e.to_string())
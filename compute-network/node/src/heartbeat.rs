@@ -0,0 +1,131 @@
+//! Adaptive heartbeat scheduling for workers
+//!
+//! A fixed short interval across thousands of workers floods the coordinator
+//! with liveness traffic that mostly says nothing new. Workers instead pick an
+//! interval from what they are doing: the shortest while running a task with a
+//! latency SLO (the coordinator must notice a stall quickly) or while the link
+//! is flaky, a middle interval while busy, and the longest while idle and
+//! healthy. Each beat advertises the window the next one will land in, so the
+//! coordinator's fault detector times workers out against their own cadence.
+//! Delays carry random jitter so workers restarted together do not beat in
+//! lockstep.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+/// Bounds and jitter for heartbeat intervals
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeartbeatPolicy {
+    /// Interval while running critical work or recovering from failed beats
+    pub min_interval: Duration,
+    /// Interval while idle and healthy
+    pub max_interval: Duration,
+    /// Fraction each delay may deviate from its interval, in `[0, 1)`
+    pub jitter: f64,
+}
+
+/// What the worker is doing when it schedules its next beat
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerActivity {
+    /// Tasks currently executing
+    pub running: usize,
+    /// Whether any running task has a latency SLO
+    pub critical: bool,
+    /// Consecutive heartbeats the coordinator did not acknowledge
+    pub failed_beats: u32,
+}
+
+impl HeartbeatPolicy {
+    /// Running tasks without an SLO beat this many times slower than critical ones
+    const BUSY_FACTOR: u32 = 4;
+
+    pub fn new(min_interval: Duration, max_interval: Duration, jitter: f64) -> Self {
+        let min_interval = min_interval.max(Duration::from_millis(100));
+        Self {
+            min_interval,
+            max_interval: max_interval.max(min_interval),
+            jitter: jitter.clamp(0.0, 0.9),
+        }
+    }
+
+    /// Nominal interval for `activity`
+    pub fn interval(&self, activity: &WorkerActivity) -> Duration {
+        if activity.critical || activity.failed_beats > 0 {
+            self.min_interval
+        } else if activity.running > 0 {
+            (self.min_interval * Self::BUSY_FACTOR).min(self.max_interval)
+        } else {
+            self.max_interval
+        }
+    }
+
+    /// Latest the next beat can arrive after this one; what the coordinator
+    /// should expect
+    pub fn advertised(&self, activity: &WorkerActivity) -> Duration {
+        self.interval(activity).mul_f64(1.0 + self.jitter)
+    }
+
+    /// Delay before the next beat: the interval scaled by a uniform factor in
+    /// `[1 - jitter, 1 + jitter]`, with `unit` drawn from `[0, 1)`
+    pub fn delay(&self, activity: &WorkerActivity, unit: f64) -> Duration {
+        let factor = 1.0 + self.jitter * (2.0 * unit.clamp(0.0, 1.0) - 1.0);
+        self.interval(activity).mul_f64(factor)
+    }
+
+    /// `delay` with a fresh random draw
+    pub fn next_delay(&self, activity: &WorkerActivity) -> Duration {
+        self.delay(activity, random_unit())
+    }
+}
+
+/// Uniform value in `[0, 1)` from the per-process randomly keyed SipHash
+fn random_unit() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> HeartbeatPolicy {
+        HeartbeatPolicy::new(Duration::from_secs(5), Duration::from_secs(60), 0.2)
+    }
+
+    #[test]
+    fn test_interval_follows_activity() {
+        let policy = policy();
+        let idle = WorkerActivity::default();
+        let busy = WorkerActivity { running: 2, ..idle };
+        let critical = WorkerActivity { critical: true, ..busy };
+        let flaky = WorkerActivity { failed_beats: 1, ..idle };
+
+        assert_eq!(policy.interval(&idle), Duration::from_secs(60));
+        assert_eq!(policy.interval(&busy), Duration::from_secs(20));
+        assert_eq!(policy.interval(&critical), Duration::from_secs(5));
+        assert_eq!(policy.interval(&flaky), Duration::from_secs(5));
+        assert_eq!(policy.advertised(&idle), Duration::from_secs(72));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_advertised_window() {
+        let policy = policy();
+        let idle = WorkerActivity::default();
+        assert_eq!(policy.delay(&idle, 0.0), Duration::from_secs(48));
+        assert_eq!(policy.delay(&idle, 0.5), Duration::from_secs(60));
+        for _ in 0..100 {
+            let delay = policy.next_delay(&idle);
+            assert!(delay >= Duration::from_secs(48) && delay <= policy.advertised(&idle));
+        }
+    }
+
+    #[test]
+    fn test_bounds_normalized() {
+        let policy = HeartbeatPolicy::new(Duration::from_secs(30), Duration::from_secs(10), 2.0);
+        assert_eq!(policy.max_interval, Duration::from_secs(30));
+        assert_eq!(policy.jitter, 0.9);
+    }
+}
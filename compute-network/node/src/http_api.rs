@@ -0,0 +1,266 @@
+//! Tenant-facing HTTP API
+//!
+//! Every route resolves the caller's tenant from its API key before the
+//! handler runs, so a request can only read or write inside its own namespace.
+
+use anchor_lang::AccountDeserialize;
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Extension, Json, Router,
+};
+use haunti_core::state::task_state::TaskState as OnChainTask;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::{str::FromStr, sync::Arc};
+
+use crate::{
+    cost_estimate::{CostEstimate, PreflightRequest},
+    inference_quote::{QuoteRequest, SignedQuote},
+    rewards_index::PoolApyReport,
+    slo::SloCompliance,
+    task_manager::{ComputeTask, ResourceRequirements, TaskManagerError, TaskPriority, TaskState, TaskType},
+    tenancy::{Tenant, TenantError},
+    unix_millis, Coordinator,
+};
+
+/// Header carrying the tenant API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Error body returned by every route
+#[derive(Debug, Serialize)]
+pub struct ApiError {
+    #[serde(skip)]
+    status: StatusCode,
+    error: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, error: impl ToString) -> Self {
+        Self { status, error: error.to_string() }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+impl From<TenantError> for ApiError {
+    fn from(e: TenantError) -> Self {
+        let status = match e {
+            TenantError::InvalidApiKey => StatusCode::UNAUTHORIZED,
+            TenantError::Suspended(_) => StatusCode::FORBIDDEN,
+            TenantError::QuotaExceeded(..) => StatusCode::TOO_MANY_REQUESTS,
+            TenantError::UnknownTenant(_) | TenantError::TenantExists(_) => StatusCode::BAD_REQUEST,
+        };
+        Self::new(status, e)
+    }
+}
+
+/// Coordinator lookups fail on bad input or on-chain accounts that do not parse
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{:#}", e))
+    }
+}
+
+impl From<TaskManagerError> for ApiError {
+    fn from(e: TaskManagerError) -> Self {
+        match e {
+            TaskManagerError::Tenant(e) => e.into(),
+            e => Self::new(StatusCode::SERVICE_UNAVAILABLE, e),
+        }
+    }
+}
+
+/// Task submission; the input names an object in the tenant's namespace
+#[derive(Debug, Deserialize)]
+pub struct SubmitTaskRequest {
+    pub owner: String,
+    pub priority: TaskPriority,
+    pub requirements: ResourceRequirements,
+    pub task_type: TaskType,
+    pub model_cid: String,
+    pub data_object: String,
+    #[serde(default)]
+    pub priority_fee: u64,
+    #[serde(default)]
+    pub deadline: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubmitTaskResponse {
+    pub task_id: String,
+}
+
+/// Unix-second window of an APY report
+#[derive(Debug, Deserialize)]
+pub struct ApyWindow {
+    pub from: i64,
+    pub to: i64,
+}
+
+pub fn router(coordinator: Arc<Coordinator>) -> Router {
+    Router::new()
+        .route("/v1/objects/:name", put(put_object))
+        .route("/v1/tasks", post(submit_task))
+        .route("/v1/tasks/:task", delete(cancel_task))
+        .route("/v1/preflight", post(preflight))
+        .route("/v1/quotes", post(quote_inference))
+        .route("/v1/pools/:pool/apy", get(pool_apy))
+        .route("/v1/users/:user/rewards.csv", get(reward_history))
+        .route("/v1/slo", get(slo_compliance))
+        .route_layer(middleware::from_fn_with_state(coordinator.clone(), authenticate))
+        .with_state(coordinator)
+}
+
+/// Resolve the API key to an active tenant for the handlers behind it
+async fn authenticate(
+    State(coordinator): State<Arc<Coordinator>>,
+    headers: HeaderMap,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let api_key = headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(TenantError::InvalidApiKey)?;
+    let tenant = coordinator.tenants.authenticate(api_key).await?;
+    request.extensions_mut().insert(tenant);
+    Ok(next.run(request).await)
+}
+
+/// Store task input under the tenant's storage prefix
+async fn put_object(
+    State(coordinator): State<Arc<Coordinator>>,
+    Extension(tenant): Extension<Tenant>,
+    Path(name): Path<String>,
+    body: Bytes,
+) -> Result<StatusCode, ApiError> {
+    let key = object_key(&coordinator, &tenant, &name).await?;
+    coordinator
+        .ipfs
+        .put_path(&key, &body)
+        .await
+        .map_err(|e| ApiError::new(StatusCode::BAD_GATEWAY, e))?;
+    Ok(StatusCode::CREATED)
+}
+
+/// Queue a task for the tenant's fee payer, reading its input from the tenant's
+/// namespace
+async fn submit_task(
+    State(coordinator): State<Arc<Coordinator>>,
+    Extension(tenant): Extension<Tenant>,
+    Json(request): Json<SubmitTaskRequest>,
+) -> Result<(StatusCode, Json<SubmitTaskResponse>), ApiError> {
+    // Tenants bill to their own fee payer only
+    let owner = parse_pubkey(&request.owner)?;
+    if owner != tenant.fee_payer {
+        return Err(ApiError::new(StatusCode::FORBIDDEN, "owner is not the tenant's fee payer"));
+    }
+    let data_cid = object_key(&coordinator, &tenant, &request.data_object).await?;
+
+    let now = unix_millis();
+    let task_id = coordinator.tenants.next_task_id(&tenant.tenant_id, now).await?;
+    coordinator
+        .task_manager
+        .add_task(ComputeTask {
+            task_id: task_id.clone(),
+            owner,
+            priority: request.priority,
+            requirements: request.requirements,
+            state: TaskState::Pending,
+            created_at: now / 1000,
+            updated_at: now / 1000,
+            task_type: request.task_type,
+            model_cid: request.model_cid,
+            data_cid,
+            tenant_id: Some(tenant.tenant_id),
+            priority_fee: request.priority_fee,
+            deadline: request.deadline,
+        })
+        .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(SubmitTaskResponse { task_id })))
+}
+
+/// Cancel a task the tenant's fee payer owns, pending or running
+async fn cancel_task(
+    State(coordinator): State<Arc<Coordinator>>,
+    Extension(tenant): Extension<Tenant>,
+    Path(task): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let task = parse_pubkey(&task)?;
+    let account = coordinator
+        .solana_client
+        .get_account(&task)
+        .await
+        .map_err(|_| ApiError::new(StatusCode::NOT_FOUND, "unknown task"))?;
+    let state = OnChainTask::try_deserialize(&mut account.data.as_slice())
+        .map_err(|_| ApiError::new(StatusCode::NOT_FOUND, "unknown task"))?;
+    if state.owner != tenant.fee_payer {
+        return Err(ApiError::new(StatusCode::FORBIDDEN, "task belongs to another owner"));
+    }
+
+    if coordinator.cancel_task(task).await? {
+        Ok(StatusCode::ACCEPTED)
+    } else {
+        Err(ApiError::new(StatusCode::NOT_FOUND, "task is not queued or running here"))
+    }
+}
+
+/// Size, runtime and fee estimate before creating a task
+async fn preflight(
+    State(coordinator): State<Arc<Coordinator>>,
+    Json(request): Json<PreflightRequest>,
+) -> Result<Json<CostEstimate>, ApiError> {
+    Ok(Json(coordinator.preflight(&request).await?))
+}
+
+/// Signed binding price for one inference call
+async fn quote_inference(
+    State(coordinator): State<Arc<Coordinator>>,
+    Json(request): Json<QuoteRequest>,
+) -> Result<Json<SignedQuote>, ApiError> {
+    Ok(Json(coordinator.quote_inference(&request).await?))
+}
+
+async fn pool_apy(
+    State(coordinator): State<Arc<Coordinator>>,
+    Path(pool): Path<String>,
+    Query(window): Query<ApyWindow>,
+) -> Result<Json<PoolApyReport>, ApiError> {
+    let pool = parse_pubkey(&pool)?;
+    Ok(Json(coordinator.pool_apy(&pool, window.from, window.to).await))
+}
+
+async fn reward_history(
+    State(coordinator): State<Arc<Coordinator>>,
+    Path(user): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = parse_pubkey(&user)?;
+    let csv = coordinator.reward_history_csv(&user).await;
+    Ok(([(header::CONTENT_TYPE, "text/csv")], csv))
+}
+
+async fn slo_compliance(State(coordinator): State<Arc<Coordinator>>) -> Json<Vec<SloCompliance>> {
+    Json(coordinator.slo_compliance().await)
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(value).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))
+}
+
+/// Object names are single path segments so they cannot climb out of the prefix
+async fn object_key(coordinator: &Coordinator, tenant: &Tenant, name: &str) -> Result<String, ApiError> {
+    if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid object name"));
+    }
+    Ok(coordinator.tenants.storage_key(&tenant.tenant_id, name).await?)
+}
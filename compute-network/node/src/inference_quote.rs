@@ -0,0 +1,202 @@
+//! Signed inference price quotes, so apps can show a binding price before the
+//! user sends any transaction
+//!
+//! The coordinator prices a call from the model's on-chain `ModelPricing`,
+//! projected to now, and signs an `InferenceQuote` with the key registered in
+//! the `quote_key` PDA. The client places `SignedQuote::ed25519_instruction`
+//! right before `create_inference_task` and passes the quote; the program then
+//! escrows at most the quoted fee (see `haunti_core::state::inference_quote`).
+
+use haunti_core::{
+    limits::MAX_ENCRYPTED_INPUT_LEN,
+    state::{inference_quote::InferenceQuote, pricing_state::ModelPricing},
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    ed25519_instruction::new_ed25519_instruction_with_signature,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use std::{str::FromStr, sync::Arc};
+use thiserror::Error;
+
+/// Validity of issued quotes; short, so the locked price stays close to the
+/// on-chain base fee, and well under the program's cap
+pub const QUOTE_TTL_SECS: i64 = 120;
+
+#[derive(Error, Debug)]
+pub enum QuoteError {
+    #[error("Input of {0} bytes exceeds the protocol cap")]
+    InputTooLarge(u32),
+    #[error("Model pricing rejected the quote: {0}")]
+    Pricing(String),
+    #[error("Malformed quote: {0}")]
+    Malformed(&'static str),
+}
+
+/// Quote request from an app, served on the HTTP API
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuoteRequest {
+    pub model: String,
+    pub owner: String,
+    #[serde(with = "hex::serde")]
+    pub input_hash: [u8; 32],
+    pub input_size: u32,
+}
+
+/// `InferenceQuote` with the coordinator's signature over its message, in the
+/// JSON form handed to apps and read by `haunti infer submit --quote`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedQuote {
+    pub model: String,
+    pub owner: String,
+    #[serde(with = "hex::serde")]
+    pub input_hash: [u8; 32],
+    pub input_size: u32,
+    pub max_fee: u64,
+    pub expires_at: i64,
+    /// Quote key that signed
+    pub signer: String,
+    /// Base58 ed25519 signature over `InferenceQuote::message`
+    pub signature: String,
+}
+
+impl SignedQuote {
+    /// The quote as `create_inference_task` takes it
+    pub fn quote(&self) -> Result<InferenceQuote, QuoteError> {
+        Ok(InferenceQuote {
+            model: parse_pubkey(&self.model, "model")?,
+            owner: parse_pubkey(&self.owner, "owner")?,
+            input_hash: self.input_hash,
+            input_size: self.input_size,
+            max_fee: self.max_fee,
+            expires_at: self.expires_at,
+        })
+    }
+
+    /// ed25519 precompile instruction to place immediately before
+    /// `create_inference_task`
+    pub fn ed25519_instruction(&self) -> Result<Instruction, QuoteError> {
+        let signer = parse_pubkey(&self.signer, "signer")?;
+        let signature = Signature::from_str(&self.signature)
+            .map_err(|_| QuoteError::Malformed("signature"))?;
+        Ok(new_ed25519_instruction_with_signature(
+            &self.quote()?.message(),
+            signature.as_ref(),
+            &signer.to_bytes(),
+        ))
+    }
+}
+
+fn parse_pubkey(value: &str, field: &'static str) -> Result<Pubkey, QuoteError> {
+    Pubkey::from_str(value).map_err(|_| QuoteError::Malformed(field))
+}
+
+/// Signs quotes with the coordinator's registered quote key
+pub struct QuoteSigner {
+    key: Arc<Keypair>,
+}
+
+impl QuoteSigner {
+    pub fn new(key: Arc<Keypair>) -> Self {
+        Self { key }
+    }
+
+    /// Quote one call at the base fee `pricing` reaches at `now`, valid for
+    /// `QUOTE_TTL_SECS`
+    pub fn quote(
+        &self,
+        request: &QuoteRequest,
+        pricing: &ModelPricing,
+        now: i64,
+    ) -> Result<SignedQuote, QuoteError> {
+        if request.input_size as usize > MAX_ENCRYPTED_INPUT_LEN {
+            return Err(QuoteError::InputTooLarge(request.input_size));
+        }
+        let max_fee = pricing
+            .clone()
+            .update(now)
+            .map_err(|e| QuoteError::Pricing(e.to_string()))?;
+        let quote = InferenceQuote {
+            model: parse_pubkey(&request.model, "model")?,
+            owner: parse_pubkey(&request.owner, "owner")?,
+            input_hash: request.input_hash,
+            input_size: request.input_size,
+            max_fee,
+            expires_at: now + QUOTE_TTL_SECS,
+        };
+        let signature = self.key.sign_message(&quote.message());
+
+        Ok(SignedQuote {
+            model: request.model.clone(),
+            owner: request.owner.clone(),
+            input_hash: quote.input_hash,
+            input_size: quote.input_size,
+            max_fee,
+            expires_at: quote.expires_at,
+            signer: self.key.pubkey().to_string(),
+            signature: signature.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use haunti_core::state::inference_quote::check_quote_signature;
+
+    fn request(input_size: u32) -> QuoteRequest {
+        QuoteRequest {
+            model: Pubkey::new_unique().to_string(),
+            owner: Pubkey::new_unique().to_string(),
+            input_hash: [9; 32],
+            input_size,
+        }
+    }
+
+    fn pricing() -> ModelPricing {
+        ModelPricing {
+            base_fee: 40_000,
+            min_base_fee: 10_000,
+            target_queue_depth: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_signed_quote_passes_program_check() {
+        let key = Arc::new(Keypair::new());
+        let signed = QuoteSigner::new(key.clone()).quote(&request(4_096), &pricing(), 0).unwrap();
+        assert_eq!((signed.max_fee, signed.expires_at), (40_000, QUOTE_TTL_SECS));
+
+        // Survives the JSON round trip apps and the CLI use
+        let signed: SignedQuote =
+            serde_json::from_str(&serde_json::to_string(&signed).unwrap()).unwrap();
+        let quote = signed.quote().unwrap();
+        let ix = signed.ed25519_instruction().unwrap();
+        check_quote_signature(&ix, &key.pubkey(), &quote.message()).unwrap();
+
+        // A quote edited after signing no longer matches the signed bytes
+        let edited = InferenceQuote { max_fee: 1, ..quote };
+        assert!(check_quote_signature(&ix, &key.pubkey(), &edited.message()).is_err());
+    }
+
+    #[test]
+    fn test_quote_tracks_projected_base_fee() {
+        let signer = QuoteSigner::new(Arc::new(Keypair::new()));
+        // An idle model decays by 1/8 per 30s epoch until the quote is taken
+        let signed = signer.quote(&request(4_096), &pricing(), 30).unwrap();
+        assert_eq!(signed.max_fee, 35_000);
+    }
+
+    #[test]
+    fn test_oversized_input_refused() {
+        let signer = QuoteSigner::new(Arc::new(Keypair::new()));
+        let size = MAX_ENCRYPTED_INPUT_LEN as u32 + 1;
+        assert!(matches!(
+            signer.quote(&request(size), &pricing(), 0),
+            Err(QuoteError::InputTooLarge(_))
+        ));
+    }
+}
@@ -0,0 +1,296 @@
+//! Duplicate-input detection for inference spam control
+//!
+//! Task PDAs are keyed by owner and input, so one wallet cannot queue the same
+//! input twice, but a spammer rotating wallets can replay one encrypted input to
+//! tie up executors. Each model gets a rolling counting bloom filter of input
+//! commitments: the current window plus the one before it, so a repeat is counted
+//! across the window boundary. Inputs seen more than `deprioritize_after` times
+//! are delayed, and more than `reject_after` times dropped, unless the task
+//! escrowed a surge fee.
+//!
+//! Counts are estimates that can only err upwards. The false-positive rate of
+//! each model's filter is exported so the filter can be resized before honest
+//! inputs start getting throttled.
+
+use prometheus::{GaugeVec, IntCounterVec};
+use solana_program::keccak;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone)]
+pub struct RepeatFilterConfig {
+    /// Length of one filter generation
+    pub window: Duration,
+    /// Counters per generation
+    pub counters: usize,
+    /// Counters touched per input
+    pub hashes: u32,
+    /// Occurrences per window served at normal priority
+    pub deprioritize_after: u8,
+    /// Occurrences per window served at all without a surge fee
+    pub reject_after: u8,
+    /// Surge fee required to bypass throttling, in basis points of the base fee
+    pub surge_fee_bps: u64,
+}
+
+/// What to do with an incoming inference task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Accept,
+    /// Repeated past the threshold but paid the surge fee
+    Surge,
+    Deprioritize,
+    Reject,
+}
+
+impl Admission {
+    fn label(self) -> &'static str {
+        match self {
+            Admission::Accept => "accept",
+            Admission::Surge => "surge",
+            Admission::Deprioritize => "deprioritize",
+            Admission::Reject => "reject",
+        }
+    }
+}
+
+/// Counting bloom filter with saturating 8-bit counters
+struct CountingBloom {
+    counters: Vec<u8>,
+    occupied: usize,
+}
+
+impl CountingBloom {
+    fn new(len: usize) -> Self {
+        Self { counters: vec![0; len.max(1)], occupied: 0 }
+    }
+
+    /// Counter indexes for `key`, by double hashing
+    fn positions(&self, key: &[u8; 32], hashes: u32) -> impl Iterator<Item = usize> {
+        let h1 = u64::from_le_bytes(key[..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(key[8..16].try_into().unwrap()) | 1;
+        let len = self.counters.len() as u64;
+        (0..hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    fn count(&self, key: &[u8; 32], hashes: u32) -> u8 {
+        self.positions(key, hashes)
+            .map(|i| self.counters[i])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Conservative update: only the counters at the current minimum grow, which
+    /// keeps collisions from inflating other inputs' counts
+    fn insert(&mut self, key: &[u8; 32], hashes: u32) {
+        let min = self.count(key, hashes);
+        let positions: Vec<usize> = self.positions(key, hashes).collect();
+        for i in positions {
+            let counter = &mut self.counters[i];
+            if *counter == min && *counter < u8::MAX {
+                if *counter == 0 {
+                    self.occupied += 1;
+                }
+                *counter += 1;
+            }
+        }
+    }
+
+    /// Chance that an unseen input reads as seen at least once
+    fn false_positive_rate(&self, hashes: u32) -> f64 {
+        (self.occupied as f64 / self.counters.len() as f64).powi(hashes as i32)
+    }
+}
+
+struct ModelWindow {
+    started: Instant,
+    current: CountingBloom,
+    previous: CountingBloom,
+}
+
+impl ModelWindow {
+    fn new(counters: usize, now: Instant) -> Self {
+        Self {
+            started: now,
+            current: CountingBloom::new(counters),
+            previous: CountingBloom::new(counters),
+        }
+    }
+
+    /// Age out generations older than one window before `now`
+    fn roll(&mut self, window: Duration, counters: usize, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed < window {
+            return;
+        }
+        if elapsed < window * 2 {
+            self.previous = std::mem::replace(&mut self.current, CountingBloom::new(counters));
+            self.started += window;
+        } else {
+            *self = Self::new(counters, now);
+        }
+    }
+
+    fn false_positive_rate(&self, hashes: u32) -> f64 {
+        // A hit in either generation counts as seen
+        let (a, b) = (
+            self.current.false_positive_rate(hashes),
+            self.previous.false_positive_rate(hashes),
+        );
+        a + b - a * b
+    }
+}
+
+/// Per-model rolling filters of inference input commitments
+pub struct RepeatFilter {
+    config: RepeatFilterConfig,
+    models: HashMap<Pubkey, ModelWindow>,
+    admissions: IntCounterVec,
+    false_positive_rate: GaugeVec,
+}
+
+impl RepeatFilter {
+    /// `admissions` is labelled by `action`, `false_positive_rate` by `model`
+    pub fn new(config: RepeatFilterConfig, admissions: IntCounterVec, false_positive_rate: GaugeVec) -> Self {
+        Self {
+            config,
+            models: HashMap::new(),
+            admissions,
+            false_positive_rate,
+        }
+    }
+
+    /// Surge fee a task escrowing `base_fee` must add to bypass throttling
+    pub fn required_surge_fee(&self, base_fee: u64) -> u64 {
+        (base_fee as u128 * self.config.surge_fee_bps as u128 / 10_000) as u64
+    }
+
+    /// Record one submission of `input_hash` to `model` and decide how to serve it
+    pub fn admit(
+        &mut self,
+        model: Pubkey,
+        input_hash: &[u8; 32],
+        base_fee: u64,
+        surge_fee: u64,
+        now: Instant,
+    ) -> Admission {
+        let RepeatFilterConfig { window, counters, hashes, .. } = self.config;
+        if !self.models.contains_key(&model) {
+            self.prune(now);
+        }
+        let filter = self
+            .models
+            .entry(model)
+            .or_insert_with(|| ModelWindow::new(counters, now));
+        filter.roll(window, counters, now);
+
+        // Keyed per model so one model's traffic cannot target another's counters
+        let key = keccak::hashv(&[b"haunti-repeat-v1", model.as_ref(), input_hash]).0;
+        let seen = filter.current.count(&key, hashes) as u32 + filter.previous.count(&key, hashes) as u32;
+        filter.current.insert(&key, hashes);
+        let fp_rate = filter.false_positive_rate(hashes);
+
+        let occurrences = seen + 1;
+        let admission = if occurrences <= self.config.deprioritize_after as u32 {
+            Admission::Accept
+        } else if surge_fee > 0 && surge_fee >= self.required_surge_fee(base_fee) {
+            Admission::Surge
+        } else if occurrences <= self.config.reject_after as u32 {
+            Admission::Deprioritize
+        } else {
+            Admission::Reject
+        };
+
+        self.admissions.with_label_values(&[admission.label()]).inc();
+        self.false_positive_rate
+            .with_label_values(&[&model.to_string()])
+            .set(fp_rate);
+        admission
+    }
+
+    /// Drop models idle for two windows; their counts have fully aged out
+    fn prune(&mut self, now: Instant) {
+        let horizon = self.config.window * 2;
+        let false_positive_rate = &self.false_positive_rate;
+        self.models.retain(|model, filter| {
+            let live = now.saturating_duration_since(filter.started) < horizon;
+            if !live {
+                let _ = false_positive_rate.remove_label_values(&[&model.to_string()]);
+            }
+            live
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::Opts;
+
+    fn filter(counters: usize) -> RepeatFilter {
+        RepeatFilter::new(
+            RepeatFilterConfig {
+                window: Duration::from_secs(60),
+                counters,
+                hashes: 4,
+                deprioritize_after: 2,
+                reject_after: 4,
+                surge_fee_bps: 5_000,
+            },
+            IntCounterVec::new(Opts::new("admissions", "-"), &["action"]).unwrap(),
+            GaugeVec::new(Opts::new("fp_rate", "-"), &["model"]).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_repeats_throttled_unless_surge_paid() {
+        let mut filter = filter(4_096);
+        let (model, input, now) = (Pubkey::new_unique(), [7; 32], Instant::now());
+        let admit = |f: &mut RepeatFilter, surge| f.admit(model, &input, 1_000, surge, now);
+
+        assert_eq!(admit(&mut filter, 0), Admission::Accept);
+        assert_eq!(admit(&mut filter, 0), Admission::Accept);
+        assert_eq!(admit(&mut filter, 0), Admission::Deprioritize);
+        assert_eq!(admit(&mut filter, 499), Admission::Deprioritize);
+        assert_eq!(admit(&mut filter, 0), Admission::Reject);
+        assert_eq!(admit(&mut filter, 500), Admission::Surge);
+
+        // Other models and inputs are counted separately
+        assert_eq!(filter.admit(Pubkey::new_unique(), &input, 1_000, 0, now), Admission::Accept);
+        assert_eq!(filter.admit(model, &[8; 32], 1_000, 0, now), Admission::Accept);
+        assert_eq!(filter.admissions.with_label_values(&["reject"]).get(), 1);
+    }
+
+    #[test]
+    fn test_counts_roll_over_two_windows() {
+        let mut filter = filter(4_096);
+        let (model, input, start) = (Pubkey::new_unique(), [9; 32], Instant::now());
+        for _ in 0..3 {
+            filter.admit(model, &input, 1_000, 0, start);
+        }
+        // Still counted from the previous generation
+        let next = start + Duration::from_secs(61);
+        assert_eq!(filter.admit(model, &input, 1_000, 0, next), Admission::Deprioritize);
+
+        let later = start + Duration::from_secs(200);
+        assert_eq!(filter.admit(model, &input, 1_000, 0, later), Admission::Accept);
+    }
+
+    #[test]
+    fn test_false_positive_rate_grows_with_load() {
+        let mut filter = filter(64);
+        let (model, now) = (Pubkey::new_unique(), Instant::now());
+        filter.admit(model, &[0; 32], 1_000, 0, now);
+        let label = model.to_string();
+        let sparse = filter.false_positive_rate.with_label_values(&[&label]).get();
+
+        for i in 1..=40u8 {
+            filter.admit(model, &[i; 32], 1_000, 0, now);
+        }
+        let dense = filter.false_positive_rate.with_label_values(&[&label]).get();
+        assert!(sparse < 0.01 && dense > sparse && dense <= 1.0);
+    }
+}
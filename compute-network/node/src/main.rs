@@ -5,28 +5,101 @@ extern crate prometheus;
 use anchor_lang::prelude::*;
 use anyhow::Context;
 use clap::Parser;
-use haunti_crypto::{fhe::FheRuntime, zk::PlonkProver};
+use haunti_crypto::{
+    fhe::{FheParams, FheRuntime, ResidentModel},
+    tee::PlatformEnclave,
+    zk::PlonkProver,
+};
+use haunti_core::{
+    state::{
+        deposit_config::DepositConfig, feature_flags::FeatureFlags,
+        pricing_state::ModelPricing, verifier_registry::VerifierRegistry,
+    },
+    CoreEvent,
+};
 use haunti_gpu::CudaAllocator;
 use haunti_network::{
     consensus::ProofOfCompute,
-    scheduler::{TaskScheduler, WorkerNode},
+    scheduler::{FaultType, TaskScheduler, WorkerNode},
     storage::IpfsClient,
 };
+use prometheus::HistogramVec;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::commitment_config::CommitmentConfig;
+use solana_program::keccak;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{read_keypair_file, Signature},
+};
 use std::{
     net::SocketAddr,
+    str::FromStr,
     sync::Arc,
     time::{Duration, Instant},
 };
 use tokio::{
     signal::unix::{signal, SignalKind},
-    sync::RwLock,
+    sync::{Mutex, RwLock},
     task::JoinSet,
 };
-use tracing::{info, instrument, Level};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, instrument, warn, Level};
 use tracing_subscriber::{fmt, EnvFilter};
 
+mod audit_log;
+mod cancellation;
+mod cost_estimate;
+mod cpi_events;
+mod enclave;
+mod error;
+mod feature_flags;
+mod heartbeat;
+mod http_api;
+mod inference_quote;
+mod input_filter;
+mod model_patch;
+mod pinned_memory;
+mod proof_archive;
+mod result_cache;
+mod rewards_index;
+mod slo;
+mod soak;
+mod submitter;
+mod subscription;
+mod task_feed;
+mod task_manager;
+mod task_rng;
+mod tenancy;
+mod verifier_routing;
+mod verify_pool;
+mod warm_pool;
+mod worker_queue;
+
+use audit_log::{AuditAction, AuditLog};
+use cancellation::CancellationRegistry;
+use cost_estimate::{CostEstimate, CostTable, PreflightRequest};
+use enclave::{AttestationReport, EnclaveBackend, EnclaveError, EnclaveExecutor, KeyReleaseClient, TeeKind, WrappedKey};
+use error::{
+    InfraError, NodeError, PermanentError, RetryDecision, Severity, TransientError, UserError,
+};
+use feature_flags::FeatureGate;
+use heartbeat::{HeartbeatPolicy, WorkerActivity};
+use inference_quote::{QuoteRequest, QuoteSigner, SignedQuote};
+use input_filter::{Admission, RepeatFilter, RepeatFilterConfig};
+use proof_archive::{arweave_id, ProofArchiver};
+use result_cache::{cached_fee, dedup_key, ResultCache};
+use rewards_index::{PoolApyReport, PoolEventKind, RewardIndex};
+use slo::SloTracker;
+use soak::{GpuMemoryStats, SoakConfig, SoakRunner, SoakTarget};
+use submitter::{LanePolicies, LanePolicy, TxSubmitter};
+use subscription::{program_data, ResilientSubscription, SubscriptionConfig};
+use task_feed::FeedTracker;
+use task_manager::TaskManager;
+use tenancy::{Tenant, TenantError, TenantQuota, TenantRegistry, TenantStatus};
+use verifier_routing::VerifierRouter;
+use verify_pool::{ProofVerifier, VerificationPool};
+use warm_pool::{ModelKey, WarmPool, WarmPoolConfig};
+use worker_queue::{resolve, Resolution, WorkerQueue};
+
 /// Global configuration for the compute network
 #[derive(Debug, Clone, Parser)]
 #[clap(version, about = "Haunti Compute Network Coordinator")]
@@ -37,14 +110,190 @@ struct Config {
     #[clap(long, env, default_value = "devnet")]
     solana_cluster: String,
 
+    #[clap(long, env, default_value = "wss://api.devnet.solana.com")]
+    solana_ws_url: String,
+
+    /// Resubscribe when a log subscription is silent for this long
+    #[clap(long, env, default_value = "60")]
+    subscription_heartbeat_secs: u64,
+
+    /// Directory holding each log subscription's last delivered transaction, so
+    /// a restart backfills what landed while the coordinator was down
+    #[clap(long, env, default_value = "subscriptions")]
+    subscription_checkpoint_dir: std::path::PathBuf,
+
+    /// Heartbeat interval while running SLO-bound tasks or after missed beats
     #[clap(long, env, default_value = "5")]
-    heartbeat_interval_secs: u64,
+    heartbeat_min_secs: u64,
+
+    /// Heartbeat interval while idle and healthy
+    #[clap(long, env, default_value = "60")]
+    heartbeat_max_secs: u64,
+
+    /// Random deviation applied to each heartbeat delay, as a fraction of the interval
+    #[clap(long, env, default_value = "0.2")]
+    heartbeat_jitter: f64,
 
     #[clap(long, env, default_value = "10")]
     max_concurrent_tasks: usize,
 
     #[clap(long, env)]
     gpu_enabled: bool,
+
+    /// Proof verification threads (0 = one per core)
+    #[clap(long, env, default_value = "0")]
+    verify_threads: usize,
+
+    #[clap(long, env, default_value = "256")]
+    max_queued_verifications: usize,
+
+    /// Models kept resident per GPU between tasks
+    #[clap(long, env, default_value = "4")]
+    warm_pool_models: usize,
+
+    /// GPU memory resident models may occupy, per device
+    #[clap(long, env, default_value = "16384")]
+    warm_pool_max_mb: u64,
+
+    /// Free GPU memory below which resident models are evicted
+    #[clap(long, env, default_value = "1024")]
+    gpu_min_free_mb: u64,
+
+    #[clap(long, env, default_value = "5")]
+    gpu_pressure_poll_secs: u64,
+
+    /// Completed tasks per worker considered for SLO compliance
+    #[clap(long, env, default_value = "100")]
+    slo_window: usize,
+
+    /// Fraction of SLO violations in the window that triggers a fault report
+    #[clap(long, env, default_value = "0.05")]
+    slo_violation_threshold: f32,
+
+    /// Model key-release service; enables TEE execution on this worker
+    #[clap(long, env)]
+    key_release_url: Option<String>,
+
+    /// Attempts before a retryable task is dead-lettered
+    #[clap(long, env, default_value = "5")]
+    max_task_attempts: u32,
+
+    /// Journal of leased tasks and results held while the coordinator is unreachable
+    #[clap(long, env, default_value = "worker-queue.json")]
+    worker_queue_path: std::path::PathBuf,
+
+    /// How long a leased task stays assigned to this worker
+    #[clap(long, env, default_value = "900")]
+    task_lease_secs: u64,
+
+    /// How often to poll the on-chain feature flags
+    #[clap(long, env, default_value = "30")]
+    feature_flags_poll_secs: u64,
+
+    /// How often to poll the on-chain verifier registry
+    #[clap(long, env, default_value = "30")]
+    verifier_registry_poll_secs: u64,
+
+    /// Window over which repeated inference inputs are counted per model
+    #[clap(long, env, default_value = "600")]
+    repeat_window_secs: u64,
+
+    /// Counters per model and window in the repeat filter
+    #[clap(long, env, default_value = "65536")]
+    repeat_filter_counters: usize,
+
+    /// Submissions of one input per window served at normal priority
+    #[clap(long, env, default_value = "3")]
+    repeat_deprioritize_after: u8,
+
+    /// Submissions of one input per window served at all without a surge fee
+    #[clap(long, env, default_value = "10")]
+    repeat_reject_after: u8,
+
+    /// How long deprioritized repeats wait before being scheduled
+    #[clap(long, env, default_value = "60")]
+    repeat_delay_secs: u64,
+
+    /// Surge fee that bypasses repeat throttling, in basis points of the base fee
+    #[clap(long, env, default_value = "5000")]
+    surge_fee_bps: u64,
+
+    /// Cost tables regenerated for this fleet; defaults to the bundled benchmarks
+    #[clap(long, env)]
+    cost_table: Option<std::path::PathBuf>,
+
+    /// Arweave bundler verified proofs are mirrored to; unset keeps proofs on
+    /// Solana only
+    #[clap(long, env)]
+    arweave_bundler_url: Option<String>,
+
+    /// Keypair signing Arweave uploads and `record_proof_archive`; it pays for
+    /// its own records and needs a bundler balance
+    #[clap(long, env)]
+    archive_keypair: Option<std::path::PathBuf>,
+
+    /// Keypair the coordinator signs and pays its own transactions with
+    /// (slashes, disputes, audit anchors). Unset disables them
+    #[clap(long, env)]
+    submitter_keypair: Option<std::path::PathBuf>,
+
+    /// Key inference quotes are signed with; must match the on-chain
+    /// `quote_key` registration. Unset disables quoting
+    #[clap(long, env)]
+    quote_keypair: Option<std::path::PathBuf>,
+
+    /// Compute-unit price of slashing, dispute and reassignment transactions,
+    /// in micro-lamports
+    #[clap(long, env, default_value = "100000")]
+    critical_lane_fee: u64,
+
+    /// Critical transactions awaiting confirmation at once
+    #[clap(long, env, default_value = "8")]
+    critical_lane_in_flight: usize,
+
+    /// Compute-unit price of transactions not otherwise classified
+    #[clap(long, env, default_value = "10000")]
+    normal_lane_fee: u64,
+
+    #[clap(long, env, default_value = "4")]
+    normal_lane_in_flight: usize,
+
+    /// Compute-unit price of proof and archive transactions
+    #[clap(long, env, default_value = "0")]
+    bulk_lane_fee: u64,
+
+    #[clap(long, env, default_value = "2")]
+    bulk_lane_in_flight: usize,
+
+    /// Hash-chained log of admin actions
+    #[clap(long, env, default_value = "audit-log.jsonl")]
+    audit_log_path: std::path::PathBuf,
+
+    /// How often the audit log head is anchored on-chain; needs `--submitter-keypair`
+    #[clap(long, env, default_value = "3600")]
+    audit_anchor_secs: u64,
+
+    /// Run synthetic FHE load instead of joining the network
+    #[clap(long)]
+    soak: bool,
+
+    #[clap(long, env, default_value = "2.0")]
+    soak_qps: f64,
+
+    #[clap(long, env, default_value = "14400")]
+    soak_duration_secs: u64,
+
+    #[clap(long, env, default_value = "32")]
+    soak_max_rss_growth_mb_per_hour: f64,
+
+    #[clap(long, env, default_value = "0.3")]
+    soak_max_gpu_fragmentation: f64,
+
+    #[clap(long, env, default_value = "5000")]
+    soak_max_p99_queue_latency_ms: u64,
+
+    #[clap(long, env, default_value = "soak-report.json")]
+    soak_report: std::path::PathBuf,
 }
 
 /// Core coordinator state
@@ -54,8 +303,47 @@ struct Coordinator {
     ipfs: IpfsClient,
     fhe_runtime: Option<Arc<FheRuntime>>,
     zk_prover: Arc<PlonkProver>,
+    verify_pool: Arc<VerificationPool<PlonkProver>>,
+    /// FHE models kept on the GPU between tasks
+    warm_pool: WarmPool<ResidentModel>,
+    /// Execution time by whether the model was already resident
+    inference_latency: HistogramVec,
     metrics: MetricsRegistry,
     workers: Arc<RwLock<Vec<WorkerNode>>>,
+    result_cache: Arc<ResultCache>,
+    tenants: Arc<TenantRegistry>,
+    /// Queue for tasks tenants submit over the HTTP API
+    task_manager: Arc<TaskManager>,
+    slo: Arc<RwLock<SloTracker>>,
+    enclave: Option<Arc<EnclaveExecutor>>,
+    max_task_attempts: u32,
+    dead_letters: Arc<RwLock<Vec<DeadLetter>>>,
+    rewards: Arc<RwLock<RewardIndex>>,
+    cancellations: Arc<CancellationRegistry>,
+    worker_queue: Arc<Mutex<WorkerQueue<ComputeTask, ComputeProof>>>,
+    task_lease_ms: u64,
+    features: Arc<FeatureGate>,
+    verifiers: Arc<VerifierRouter>,
+    repeat_filter: Arc<Mutex<RepeatFilter>>,
+    repeat_delay: Duration,
+    cost_table: Arc<CostTable>,
+    proof_archiver: Option<Arc<ProofArchiver>>,
+    quote_signer: Option<Arc<QuoteSigner>>,
+    /// Sends transactions signed with the coordinator key, by priority lane
+    submitter: Option<Arc<TxSubmitter>>,
+    audit: Arc<Mutex<AuditLog>>,
+}
+
+/// Poll interval while the scheduler is unreachable and no leased work remains
+const OFFLINE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Task abandoned after a permanent failure or exhausted retries
+#[derive(Debug, Clone)]
+struct DeadLetter {
+    task_pubkey: Pubkey,
+    attempts: u32,
+    severity: Severity,
+    error: String,
 }
 
 impl Coordinator {
@@ -69,6 +357,7 @@ impl Coordinator {
             config.solana_cluster.clone(),
             CommitmentConfig::confirmed(),
         ));
+        let tenants = Arc::new(TenantRegistry::new());
 
         // Initialize cryptographic runtimes
         let fhe_runtime = if config.gpu_enabled {
@@ -77,6 +366,67 @@ impl Coordinator {
             None
         };
         let zk_prover = Arc::new(PlonkProver::new("circuits/")?);
+        let enclave = match &config.key_release_url {
+            Some(url) => {
+                let platform = PlatformEnclave::open().context("No SEV-SNP/TDX enclave available")?;
+                info!(tee = ?platform.tee(), "Enclave execution enabled");
+                Some(Arc::new(EnclaveExecutor::new(
+                    Arc::new(platform),
+                    KeyReleaseClient::new(url.clone()),
+                )))
+            }
+            None => None,
+        };
+        let lanes = LanePolicies {
+            critical: LanePolicy {
+                priority_fee_micro_lamports: config.critical_lane_fee,
+                max_in_flight: config.critical_lane_in_flight,
+            },
+            normal: LanePolicy {
+                priority_fee_micro_lamports: config.normal_lane_fee,
+                max_in_flight: config.normal_lane_in_flight,
+            },
+            bulk: LanePolicy {
+                priority_fee_micro_lamports: config.bulk_lane_fee,
+                max_in_flight: config.bulk_lane_in_flight,
+            },
+        };
+        let submitter = match &config.submitter_keypair {
+            Some(path) => {
+                let signer = read_keypair_file(path).map_err(|e| {
+                    anyhow::anyhow!("Failed to read submitter keypair {}: {}", path.display(), e)
+                })?;
+                Some(Arc::new(TxSubmitter::new(
+                    solana_client.clone(),
+                    Arc::new(signer),
+                    lanes,
+                )))
+            }
+            None => None,
+        };
+        let verify_pool = Arc::new(VerificationPool::new(
+            zk_prover.clone(),
+            config.verify_threads,
+            config.max_queued_verifications,
+            register_int_gauge!(
+                "haunti_verification_queue_depth",
+                "Proofs queued or running in the verification pool"
+            )?,
+        )?);
+
+        let proof_archiver = match (&config.arweave_bundler_url, &config.archive_keypair) {
+            (Some(url), Some(path)) => {
+                let signer = read_keypair_file(path).map_err(|e| {
+                    anyhow::anyhow!("Failed to read archive keypair {}: {}", path.display(), e)
+                })?;
+                let signer = Arc::new(signer);
+                let submitter =
+                    Arc::new(TxSubmitter::new(solana_client.clone(), signer.clone(), lanes));
+                Some(Arc::new(ProofArchiver::new(url.clone(), signer, submitter)))
+            }
+            (None, None) => None,
+            _ => anyhow::bail!("--arweave-bundler-url and --archive-keypair must be set together"),
+        };
 
         Ok(Self {
             scheduler: Arc::new(RwLock::new(TaskScheduler::new(
@@ -86,24 +436,177 @@ impl Coordinator {
             ipfs: IpfsClient::default(),
             fhe_runtime,
             zk_prover,
+            verify_pool,
+            warm_pool: WarmPool::new(
+                WarmPoolConfig {
+                    max_models: config.warm_pool_models,
+                    max_bytes: config.warm_pool_max_mb << 20,
+                },
+                register_int_counter_vec!(
+                    "haunti_warm_pool_lookups_total",
+                    "Model lookups in the GPU warm pool by result",
+                    &["result"]
+                )?,
+                register_int_counter_vec!(
+                    "haunti_warm_pool_evictions_total",
+                    "Models evicted from the GPU warm pool by reason",
+                    &["reason"]
+                )?,
+                register_int_gauge_vec!(
+                    "haunti_warm_pool_resident_bytes",
+                    "GPU memory held by resident models per device",
+                    &["device"]
+                )?,
+            ),
+            inference_latency: register_histogram_vec!(
+                "haunti_inference_seconds",
+                "Task execution time by model residency",
+                &["model_cache"]
+            )?,
             metrics,
             workers: Arc::new(RwLock::new(Vec::new())),
+            result_cache: Arc::new(ResultCache::new()),
+            task_manager: Arc::new(
+                TaskManager::new(&config.solana_cluster).with_tenants(tenants.clone()),
+            ),
+            tenants,
+            slo: Arc::new(RwLock::new(SloTracker::new(
+                config.slo_window,
+                config.slo_violation_threshold,
+            ))),
+            enclave,
+            max_task_attempts: config.max_task_attempts,
+            dead_letters: Arc::new(RwLock::new(Vec::new())),
+            rewards: Arc::new(RwLock::new(RewardIndex::new())),
+            cancellations: Arc::new(CancellationRegistry::new()),
+            worker_queue: Arc::new(Mutex::new(
+                WorkerQueue::open(&config.worker_queue_path)
+                    .context("Failed to open worker queue journal")?,
+            )),
+            task_lease_ms: config.task_lease_secs * 1_000,
+            features: Arc::new(FeatureGate::new()),
+            verifiers: Arc::new(VerifierRouter::new(
+                register_int_counter_vec!(
+                    "haunti_verifier_submissions_total",
+                    "Proofs submitted per verifier version",
+                    &["version"]
+                )?,
+                register_int_counter_vec!(
+                    "haunti_verifier_failures_total",
+                    "Proof submissions rejected per verifier version",
+                    &["version"]
+                )?,
+                register_gauge_vec!(
+                    "haunti_verifier_failure_rate",
+                    "Fraction of proof submissions rejected per verifier version",
+                    &["version"]
+                )?,
+            )),
+            repeat_filter: Arc::new(Mutex::new(RepeatFilter::new(
+                RepeatFilterConfig {
+                    window: Duration::from_secs(config.repeat_window_secs),
+                    counters: config.repeat_filter_counters,
+                    hashes: 4,
+                    deprioritize_after: config.repeat_deprioritize_after,
+                    reject_after: config.repeat_reject_after,
+                    surge_fee_bps: config.surge_fee_bps,
+                },
+                register_int_counter_vec!(
+                    "haunti_repeat_input_admissions_total",
+                    "Inference tasks by repeat-filter decision",
+                    &["action"]
+                )?,
+                register_gauge_vec!(
+                    "haunti_repeat_filter_false_positive_rate",
+                    "Estimated false-positive rate of each model's repeat filter",
+                    &["model"]
+                )?,
+            ))),
+            repeat_delay: Duration::from_secs(config.repeat_delay_secs),
+            cost_table: Arc::new(match &config.cost_table {
+                Some(path) => CostTable::load(path)?,
+                None => CostTable::benchmarked(),
+            }),
+            proof_archiver,
+            quote_signer: match &config.quote_keypair {
+                Some(path) => {
+                    let key = read_keypair_file(path).map_err(|e| {
+                        anyhow::anyhow!("Failed to read quote keypair {}: {}", path.display(), e)
+                    })?;
+                    Some(Arc::new(QuoteSigner::new(Arc::new(key))))
+                }
+                None => None,
+            },
+            submitter,
+            audit: Arc::new(Mutex::new(
+                AuditLog::open(&config.audit_log_path).context("Audit log failed verification")?,
+            )),
         })
     }
 
     #[instrument(skip_all)]
-    async fn run(self, config: Config) -> anyhow::Result<()> {
+    async fn run(self: Arc<Self>, config: Config) -> anyhow::Result<()> {
         let mut joinset = JoinSet::new();
+        let shutdown = CancellationToken::new();
+
+        // Send coordinator transactions lane by lane, critical first
+        if let Some(submitter) = &self.submitter {
+            joinset.spawn(submitter.clone().run(shutdown.clone()));
+        }
+        if let Some(archiver) = &self.proof_archiver {
+            joinset.spawn(archiver.submitter().run(shutdown.clone()));
+        }
 
         // Start HTTP API server
-        joinset.spawn(self.start_http_server(config.http_addr));
+        joinset.spawn(self.clone().start_http_server(config.http_addr));
+
+        // Schedule tenant tasks within their quotas and bill them as they finish
+        joinset.spawn(self.task_manager.clone().run());
 
         // Start worker heartbeat monitor
-        joinset.spawn(self.monitor_workers(config.heartbeat_interval_secs));
+        joinset.spawn(self.monitor_workers(config.heartbeat_min_secs));
 
         // Start task processing loop
         joinset.spawn(self.process_tasks());
 
+        // Sync results and heartbeats held while the scheduler was unreachable
+        joinset.spawn(self.sync_worker_queue(HeartbeatPolicy::new(
+            Duration::from_secs(config.heartbeat_min_secs),
+            Duration::from_secs(config.heartbeat_max_secs),
+            config.heartbeat_jitter,
+        )));
+
+        // Follow governance feature flags so rollouts switch at the scheduled slot
+        joinset.spawn(self.sync_feature_flags(config.feature_flags_poll_secs));
+
+        // Follow verifier upgrades so proofs reach the program their circuits target
+        joinset.spawn(self.sync_verifier_registry(config.verifier_registry_poll_secs));
+
+        // Anchor the audit log head so rewriting the log is detectable
+        if let Some(submitter) = &self.submitter {
+            joinset.spawn(self.anchor_audit_log(submitter.clone(), config.audit_anchor_secs));
+        }
+
+        // Give GPU memory back from resident models when it runs low
+        if self.fhe_runtime.is_some() {
+            joinset.spawn(self.relieve_gpu_pressure(
+                config.gpu_pressure_poll_secs,
+                config.gpu_min_free_mb << 20,
+            ));
+        }
+
+        // Ingest new tasks and index staking events from program logs
+        std::fs::create_dir_all(&config.subscription_checkpoint_dir)
+            .context("Failed to create subscription checkpoint directory")?;
+        let subscription = |program: Pubkey| SubscriptionConfig {
+            ws_url: config.solana_ws_url.clone(),
+            program,
+            heartbeat_timeout: Duration::from_secs(config.subscription_heartbeat_secs),
+            checkpoint: Some(config.subscription_checkpoint_dir.join(format!("{}.json", program))),
+        };
+        joinset.spawn(self.ingest_tasks(subscription(haunti_core::ID)));
+        joinset.spawn(self.index_rewards(subscription(token_vault::ID)));
+
         // Handle signals
         let mut term_signal = signal(SignalKind::terminate())?;
         let mut int_signal = signal(SignalKind::interrupt())?;
@@ -113,6 +616,7 @@ impl Coordinator {
             _ = int_signal.recv() => info!("Received SIGINT, shutting down"),
             _ = joinset.join_next() => {},
         }
+        shutdown.cancel();
 
         Ok(())
     }
@@ -120,45 +624,715 @@ impl Coordinator {
     #[instrument(skip(self))]
     async fn process_tasks(&self) -> anyhow::Result<()> {
         loop {
-            let task = {
-                let mut scheduler = self.scheduler.write().await;
-                scheduler.next_task().await?
+            let Some((task, offline)) = self.lease_next_task().await? else {
+                tokio::time::sleep(OFFLINE_POLL_INTERVAL).await;
+                continue;
             };
+            let task_id = task.task_pubkey.to_string();
 
             // Check if task requires GPU
             if task.requires_gpu && self.fhe_runtime.is_none() {
                 warn!("Skipping GPU task in CPU-only mode");
+                self.worker_queue.lock().await.finish(&task_id)?;
+                continue;
+            }
+            if task.requires_tee && self.enclave.is_none() {
+                warn!("Skipping TEE-only task without an enclave");
+                self.worker_queue.lock().await.finish(&task_id)?;
                 continue;
             }
 
-            // Execute task with retries
-            let result = tokio::time::timeout(
-                Duration::from_secs(300),
-                self.execute_task(task),
-            )
-            .await??;
+            if offline {
+                self.run_offline(task).await?;
+                continue;
+            }
 
-            // Submit proof to Solana
-            self.submit_proof(result).await?;
+            let attempt = task.attempts;
+            let task_pubkey = task.task_pubkey;
+            let outcome = self.run_task(task.clone()).await;
+            // Submitted, requeued, or dead-lettered: the scheduler owns it again
+            self.worker_queue.lock().await.finish(&task_id)?;
+            if let Err(err) = outcome {
+                if err.is_cancelled() {
+                    info!(task = %task_pubkey, "Task cancelled, resources released");
+                    if let Err(e) = self.solana_client.submit_task_cancelled(task_pubkey).await {
+                        warn!(task = %task_pubkey, error = %e, "Failed to mark task cancelled on-chain");
+                    }
+                    continue;
+                }
+                match err.retry_decision(attempt, self.max_task_attempts) {
+                    RetryDecision::Retry(delay) => {
+                        warn!(task = %task_pubkey, attempt, ?delay, error = %err, "Retrying task");
+                        self.audit(AuditAction::Requeue {
+                            task: task_pubkey.to_string(),
+                            delay_secs: delay.as_secs(),
+                            reason: err.to_string(),
+                        })
+                        .await;
+                        self.scheduler.write().await.requeue_after(task, delay).await?;
+                    }
+                    RetryDecision::DeadLetter => {
+                        tracing::error!(
+                            task = %task_pubkey,
+                            severity = ?err.severity(),
+                            error = %err,
+                            "Dead-lettering task"
+                        );
+                        self.dead_letters.write().await.push(DeadLetter {
+                            task_pubkey,
+                            attempts: attempt + 1,
+                            severity: err.severity(),
+                            error: err.to_string(),
+                        });
+                    }
+                }
+            }
         }
     }
 
-    #[instrument(skip(self, task))]
-    async fn execute_task(&self, task: ComputeTask) -> anyhow::Result<ComputeProof> {
-        // Fetch model & data from IPFS
-        let model = self.ipfs.get_cid(&task.model_cid).await?;
-        let data = self.ipfs.get_cid(&task.data_cid).await?;
+    /// Lease from the scheduler, falling back to journaled leases while it is
+    /// unreachable. The flag is true when the task runs offline.
+    async fn lease_next_task(&self) -> anyhow::Result<Option<(ComputeTask, bool)>> {
+        let leased = self.scheduler.write().await.next_task().await;
+        let mut queue = self.worker_queue.lock().await;
+        match leased {
+            Ok(task) => {
+                let task_id = task.task_pubkey.to_string();
+                queue.lease(&task_id, task.clone(), unix_millis() + self.task_lease_ms)?;
+                queue.start(&task_id)?;
+                Ok(Some((task, false)))
+            }
+            Err(e) => {
+                let task = queue.take_unstarted()?;
+                warn!(error = %e, resumed = task.is_some(), "Scheduler unreachable, working from local queue");
+                Ok(task.map(|task| (task, true)))
+            }
+        }
+    }
 
-        // Select execution backend
-        let backend = if task.use_fhe {
-            ExecutionBackend::Fhe(self.fhe_runtime.as_ref().unwrap().clone())
-        } else {
-            ExecutionBackend::Cpu
+    /// Execute a journaled lease without the coordinator and hold the proof for sync
+    async fn run_offline(&self, task: ComputeTask) -> anyhow::Result<()> {
+        let task_id = task.task_pubkey.to_string();
+        let cancellation = self.cancellations.register(task.task_pubkey);
+        let result: Result<ComputeProof, NodeError> =
+            tokio::time::timeout(Duration::from_secs(300), self.execute_task(task, cancellation.token()))
+                .await
+                .map_err(NodeError::from)
+                .and_then(|result| result);
+
+        let mut queue = self.worker_queue.lock().await;
+        match result {
+            Ok(proof) => {
+                info!(task = %task_id, "Holding offline result until the scheduler is reachable");
+                queue.complete(&task_id, proof, unix_millis())?;
+            }
+            Err(err) => {
+                // The lease lapses and the scheduler reassigns the task
+                warn!(task = %task_id, error = %err, "Offline execution failed");
+                queue.finish(&task_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Heartbeat the scheduler at an interval adapted to the current work; once
+    /// it answers, report the outage, hand back unstarted leases, and resolve
+    /// results held while offline
+    async fn sync_worker_queue(&self, policy: HeartbeatPolicy) -> anyhow::Result<()> {
+        let mut failed_beats = 0u32;
+        loop {
+            let activity = {
+                let queue = self.worker_queue.lock().await;
+                let mut running = 0;
+                let mut critical = false;
+                for task in queue.running() {
+                    running += 1;
+                    critical |= task.latency_slo_ms > 0;
+                }
+                WorkerActivity { running, critical, failed_beats }
+            };
+            tokio::time::sleep(policy.next_delay(&activity)).await;
+
+            // The coordinator times this worker out against the advertised window
+            let next_within = policy.advertised(&activity);
+            if self.scheduler.read().await.ping(next_within).await.is_err() {
+                failed_beats = failed_beats.saturating_add(1);
+                self.worker_queue.lock().await.record_heartbeat(unix_millis(), false)?;
+                continue;
+            }
+            failed_beats = 0;
+
+            let (digest, released, pending) = {
+                let mut queue = self.worker_queue.lock().await;
+                (queue.heartbeat_digest(), queue.release_unstarted()?, queue.pending_results())
+            };
+            if let Some(digest) = digest {
+                info!(
+                    missed = digest.missed,
+                    in_flight = digest.in_flight.len(),
+                    "Scheduler reachable again, reporting offline period"
+                );
+                self.scheduler.write().await.report_offline(digest).await?;
+            }
+            self.worker_queue.lock().await.record_heartbeat(unix_millis(), true)?;
+
+            for task_id in released {
+                self.scheduler.write().await.release_lease(task_id.parse()?).await?;
+            }
+
+            for (task_id, completed_at_ms, expires_at_ms, proof) in pending {
+                let status = self.scheduler.read().await.lease_status(task_id.parse()?).await?;
+                match resolve(completed_at_ms, expires_at_ms, status) {
+                    Resolution::Submit => match self.submit_proof(proof).await {
+                        Ok(_) => info!(task = %task_id, "Synced offline result"),
+                        Err(err) => {
+                            if !matches!(err.retry_decision(0, self.max_task_attempts), RetryDecision::DeadLetter) {
+                                // Keep it for the next sync
+                                warn!(task = %task_id, error = %err, "Offline result sync failed");
+                                continue;
+                            }
+                            warn!(task = %task_id, error = %err, "Dropping rejected offline result");
+                        }
+                    },
+                    Resolution::Discard(reason) => {
+                        info!(task = %task_id, reason, "Discarding late offline result");
+                    }
+                }
+                self.worker_queue.lock().await.finish(&task_id)?;
+            }
+        }
+    }
+
+    /// Poll the feature flag account; a failed poll keeps the previous snapshot
+    async fn sync_feature_flags(&self, interval_secs: u64) -> anyhow::Result<()> {
+        let (address, _) = Pubkey::find_program_address(&[b"feature_flags"], &haunti_core::ID);
+        let active = register_int_gauge_vec!(
+            "haunti_feature_active",
+            "Whether each on-chain feature flag is active",
+            &["feature"]
+        )?;
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            ticker.tick().await;
+            let polled = async {
+                let account = self.solana_client.get_account(&address).await?;
+                let flags = FeatureFlags::try_deserialize(&mut account.data.as_slice())?;
+                let slot = self.solana_client.get_slot().await?;
+                anyhow::Ok((flags, slot))
+            };
+            let (flags, slot) = match polled.await {
+                Ok(polled) => polled,
+                Err(e) => {
+                    warn!(error = %e, "Failed to poll feature flags");
+                    continue;
+                }
+            };
+            for (feature, now_active) in self.features.update(flags, slot).await {
+                info!(feature, active = now_active, slot, "Feature flag changed");
+                active
+                    .with_label_values(&[&feature.to_string()])
+                    .set(now_active as i64);
+            }
+        }
+    }
+
+    /// Record an admin action; a log that cannot be written is reported, not fatal
+    async fn audit(&self, action: AuditAction) {
+        if let Err(e) = self.audit.lock().await.append(action, unix_millis()) {
+            warn!(error = %e, "Failed to write audit log");
+        }
+    }
+
+    /// Serve the tenant HTTP API; every route authenticates its tenant first
+    async fn start_http_server(self: Arc<Self>, addr: SocketAddr) -> anyhow::Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        info!(%addr, "HTTP API listening");
+        axum::serve(listener, http_api::router(self)).await?;
+        Ok(())
+    }
+
+    /// Create a tenant, served on the admin API
+    async fn create_tenant(
+        &self,
+        tenant_id: &str,
+        api_key: &str,
+        fee_payer: Pubkey,
+        quota: TenantQuota,
+        gpu_second_fee_lamports: u64,
+    ) -> Result<Tenant, TenantError> {
+        let tenant = self
+            .tenants
+            .create_tenant(tenant_id, api_key, fee_payer, quota, gpu_second_fee_lamports)
+            .await?;
+        self.audit(AuditAction::TenantCreated {
+            tenant_id: tenant_id.to_string(),
+            fee_payer: fee_payer.to_string(),
+        })
+        .await;
+        Ok(tenant)
+    }
+
+    /// Suspend or reinstate a tenant, served on the admin API
+    async fn set_tenant_status(&self, tenant_id: &str, status: TenantStatus) -> Result<(), TenantError> {
+        self.tenants.set_status(tenant_id, status).await?;
+        self.audit(AuditAction::TenantStatusChanged {
+            tenant_id: tenant_id.to_string(),
+            status: format!("{:?}", status),
+        })
+        .await;
+        Ok(())
+    }
+
+    /// Anchor the audit log head on-chain whenever it moved since the last anchor
+    async fn anchor_audit_log(&self, submitter: Arc<TxSubmitter>, interval_secs: u64) -> anyhow::Result<()> {
+        let mut anchored = None;
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            ticker.tick().await;
+            let head = self.audit.lock().await.head();
+            // The anchor record itself moves the head; it is covered by the next anchor
+            if head.len == 0 || anchored == Some(head.len) {
+                continue;
+            }
+            let seq = head.len - 1;
+            let ix = audit_log::anchor_instruction(seq, &head.hash, &submitter.payer());
+            match submitter.submit(vec![ix]).await {
+                Ok(signature) => {
+                    info!(seq, %signature, "Audit log head anchored");
+                    self.audit(AuditAction::HeadAnchored {
+                        seq,
+                        hash: hex::encode(head.hash),
+                        signature: signature.to_string(),
+                    })
+                    .await;
+                    anchored = Some(head.len + 1);
+                }
+                Err(e) => warn!(seq, error = %e, "Failed to anchor audit log head"),
+            }
+        }
+    }
+
+    /// Evict resident models while free GPU memory is below `min_free_bytes`
+    async fn relieve_gpu_pressure(&self, interval_secs: u64, min_free_bytes: u64) -> anyhow::Result<()> {
+        let Some(runtime) = &self.fhe_runtime else { return Ok(()) };
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            ticker.tick().await;
+            let free = match CudaAllocator::mem_info() {
+                Ok(info) => info.free as u64,
+                Err(e) => {
+                    warn!(error = %e, "Failed to read GPU memory");
+                    continue;
+                }
+            };
+            if free >= min_free_bytes {
+                continue;
+            }
+            let freed = self.warm_pool.evict(runtime.device(), min_free_bytes - free);
+            if freed > 0 {
+                info!(free, freed, "Evicted resident models under GPU memory pressure");
+            }
+        }
+    }
+
+    /// Poll the verifier registry; a failed poll keeps the previous snapshot
+    async fn sync_verifier_registry(&self, interval_secs: u64) -> anyhow::Result<()> {
+        let (address, _) = Pubkey::find_program_address(&[b"verifier_registry"], &haunti_core::ID);
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            ticker.tick().await;
+            let polled = async {
+                let account = self.solana_client.get_account(&address).await?;
+                let registry = VerifierRegistry::try_deserialize(&mut account.data.as_slice())?;
+                let slot = self.solana_client.get_slot().await?;
+                anyhow::Ok((registry, slot))
+            };
+            let (registry, slot) = match polled.await {
+                Ok(polled) => polled,
+                Err(e) => {
+                    warn!(error = %e, "Failed to poll verifier registry");
+                    continue;
+                }
+            };
+            if let Some(change) = self.verifiers.update(registry, slot).await {
+                info!(
+                    current = change.current,
+                    draining = ?change.draining,
+                    slot,
+                    "Verifier routing changed"
+                );
+                self.audit(AuditAction::VerifierDrain {
+                    current: change.current,
+                    draining: change.draining,
+                    slot,
+                })
+                .await;
+            }
+        }
+    }
+
+    /// Execute, prove, and submit a single task
+    async fn run_task(&self, task: ComputeTask) -> Result<(), NodeError> {
+        // Serve identical inference requests from already-verified results
+        let dedup = task
+            .is_inference()
+            .then(|| dedup_key(&task.model_root, &task.input_hash, &task.params));
+        if let Some(key) = dedup {
+            if let Some(cached) = self.result_cache.lookup(&key, task.nondeterministic).await {
+                // A cache hit still pays the discounted fee
+                let fee = cached_fee(task.fee_lamports)
+                    .map_err(|e| UserError::InvalidTask(e.to_string()))?;
+                info!(task = %task.task_id, source = %cached.source_task, fee, "Serving cached result");
+                self.solana_client
+                    .submit_cached_result(task.task_pubkey, cached.source_task, fee)
+                    .await?;
+                return Ok(());
+            }
+        }
+        let task_pubkey = task.task_pubkey;
+        let nondeterministic = task.nondeterministic;
+        let worker = task.assigned_worker;
+        let latency_slo_ms = task.latency_slo_ms;
+        let created_at_ms = task.created_at_ms;
+
+        // Unregistered when this attempt finishes, however it finishes
+        let cancellation = self.cancellations.register(task_pubkey);
+        let cancel = cancellation.token();
+
+        let result = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => return Err(PermanentError::Cancelled.into()),
+            result = tokio::time::timeout(
+                Duration::from_secs(300),
+                self.execute_task(task, cancel.clone()),
+            ) => result??,
         };
+        let result_hash = keccak::hash(&result.result).0;
+        let proof_digest = keccak::hash(&result.proof).0;
+
+        // Submit proof to Solana
+        let (verifier_program, tx) = self.submit_proof(result).await?;
+
+        // A zero SLO means the submitter did not declare one
+        if latency_slo_ms > 0 {
+            let latency_ms = unix_millis().saturating_sub(created_at_ms);
+            self.track_slo(worker, latency_ms, latency_slo_ms).await;
+        }
+
+        // Only a verification the chain confirmed may answer other requests
+        if let Some(key) = dedup {
+            if self.proof_confirmed(&tx).await {
+                self.result_cache
+                    .insert_verified(key, task_pubkey, result_hash, proof_digest, nondeterministic)
+                    .await;
+            }
+        }
+
+        // The proof already settled on-chain; a failed mirror is logged, not retried
+        if let Some(archiver) = &self.proof_archiver {
+            match archiver
+                .archive(&self.solana_client, verifier_program, tx, task_pubkey)
+                .await
+            {
+                Ok(id) => info!(task = %task_pubkey, arweave_tx = %arweave_id(&id), "Proof archived"),
+                Err(e) => warn!(task = %task_pubkey, error = %e, "Proof archival failed"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the verify transaction `tx` is confirmed without error, meaning
+    /// the verifier program accepted the proof
+    async fn proof_confirmed(&self, tx: &Signature) -> bool {
+        match self
+            .solana_client
+            .get_signature_status_with_commitment(tx, CommitmentConfig::confirmed())
+            .await
+        {
+            Ok(Some(Ok(()))) => true,
+            Ok(_) => false,
+            Err(e) => {
+                warn!(tx = %tx, error = %e, "Could not confirm proof verification");
+                false
+            }
+        }
+    }
+
+    /// Compare end-to-end latency with the task SLO and penalize repeat offenders
+    async fn track_slo(&self, worker: Pubkey, latency_ms: u64, slo_ms: u32) {
+        let breach = self.slo.write().await.record(worker, latency_ms, slo_ms);
+        let Some(breach) = breach else { return };
+
+        warn!(
+            worker = %breach.worker,
+            ratio = breach.violation_ratio,
+            p95_ms = breach.p95_latency_ms,
+            "Worker breached latency SLO"
+        );
+
+        let mut scheduler = self.scheduler.write().await;
+        scheduler.set_worker_weight(&breach.worker, breach.new_weight);
+        if let Err(e) = scheduler
+            .report_fault(FaultType::SloViolation(breach.p95_latency_ms), breach.worker.to_string())
+            .await
+        {
+            warn!(error = %e, "Failed to report SLO fault");
+        }
+    }
+
+    /// Queue tasks announced by haunti-core `TaskCreated` and `InferenceTaskPriced`
+    /// self-CPI events, throttling inference inputs that keep repeating
+    async fn ingest_tasks(&self, config: SubscriptionConfig) -> anyhow::Result<()> {
+        let program = config.program;
+        let mut feed = FeedTracker::resume(&self.solana_client).await?;
+        let mut logs = ResilientSubscription::new(config, self.solana_client.clone())?.spawn();
+        while let Some(event) = logs.recv().await {
+            for gap in feed.observe(event.slot, &task_feed::decode_logs(&event.logs)) {
+                warn!(
+                    from = gap.from,
+                    to = gap.to,
+                    since_slot = feed.trusted_slot,
+                    until_slot = event.slot,
+                    "Task feed events missed; replay transactions in the slot range"
+                );
+            }
+
+            // Task creation events no longer appear in logs; only fetch
+            // transactions that emitted one
+            if !cpi_events::has_self_cpi(&program, &event.logs) {
+                continue;
+            }
+            let events =
+                match cpi_events::fetch_event_data(&self.solana_client, &program, &event.signature).await {
+                    Ok(events) => events,
+                    Err(e) => {
+                        warn!(signature = %event.signature, error = %e, "Failed to fetch CPI events");
+                        continue;
+                    }
+                };
+            for data in events {
+                match haunti_core::decode_cpi_event(&data) {
+                    Some(CoreEvent::TaskCreated(created)) => {
+                        let task = created.task;
+                        // One bad task must not stop ingestion of the rest
+                        if let Err(e) = self.scheduler.write().await.enqueue(created.into()).await {
+                            warn!(task = %task, error = %e, "Failed to enqueue created task");
+                        }
+                    }
+                    Some(CoreEvent::InferenceTaskPriced(priced)) => {
+                        let admission = self.repeat_filter.lock().await.admit(
+                            priced.model,
+                            &priced.input_hash,
+                            priced.escrow,
+                            priced.surge_fee,
+                            Instant::now(),
+                        );
+                        let mut scheduler = self.scheduler.write().await;
+                        match admission {
+                            Admission::Accept | Admission::Surge => {
+                                let task = priced.task;
+                                if let Err(e) = scheduler.enqueue(priced.into()).await {
+                                    warn!(task = %task, error = %e, "Failed to enqueue priced task");
+                                }
+                            }
+                            Admission::Deprioritize => {
+                                info!(task = %priced.task, model = %priced.model, "Delaying repeated inference input");
+                                self.audit(AuditAction::Requeue {
+                                    task: priced.task.to_string(),
+                                    delay_secs: self.repeat_delay.as_secs(),
+                                    reason: "repeated inference input".into(),
+                                })
+                                .await;
+                                let task = priced.task;
+                                if let Err(e) = scheduler.requeue_after(priced.into(), self.repeat_delay).await {
+                                    warn!(task = %task, error = %e, "Failed to delay repeated task");
+                                }
+                            }
+                            Admission::Reject => {
+                                // Left unassigned; the owner reclaims the escrow by cancelling
+                                warn!(task = %priced.task, model = %priced.model, "Rejecting repeated inference input");
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Follow token-vault `PoolEvent`s and sample block times for slot mapping
+    async fn index_rewards(&self, config: SubscriptionConfig) -> anyhow::Result<()> {
+        let program = config.program;
+        let mut logs = ResilientSubscription::new(config, self.solana_client.clone())?.spawn();
+        while let Some(event) = logs.recv().await {
+            let decoded = rewards_index::decode_pool_events(event.slot, &program_data(&program, &event.logs));
+            if decoded.is_empty() {
+                continue;
+            }
+            let block_time = self.solana_client.get_block_time(event.slot).await.ok();
+
+            let mut slashes = Vec::new();
+            {
+                let mut index = self.rewards.write().await;
+                if let Some(time) = block_time {
+                    index.record_block_time(event.slot, time);
+                }
+                for pool_event in decoded {
+                    if let PoolEventKind::Slashed { user, amount } = pool_event.kind {
+                        slashes.push((user, amount));
+                    }
+                    index.ingest(pool_event);
+                }
+            }
+
+            // Keep the fault detector's view of provider stake in line with the chain
+            for (provider, amount) in slashes {
+                warn!(provider = %provider, amount, "Provider stake slashed");
+                self.scheduler.write().await.record_slash(&provider.to_string(), amount).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Historical emissions and realized APY for a pool, served on the HTTP API
+    async fn pool_apy(&self, pool: &Pubkey, from: i64, to: i64) -> PoolApyReport {
+        self.rewards.read().await.pool_apy(pool, from, to)
+    }
+
+    /// Per-user reward history as CSV, served on the HTTP API
+    async fn reward_history_csv(&self, user: &Pubkey) -> String {
+        self.rewards.read().await.user_history_csv(user)
+    }
+
+    /// Cancel a task from the HTTP/gRPC API; returns false if it is unknown here
+    async fn cancel_task(&self, task_pubkey: Pubkey) -> anyhow::Result<bool> {
+        // Running tasks unwind through their token and are marked Cancelled by process_tasks
+        if self.cancellations.cancel(&task_pubkey) {
+            return Ok(true);
+        }
+        if self.scheduler.write().await.remove_pending(&task_pubkey).await? {
+            self.solana_client.submit_task_cancelled(task_pubkey).await?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Size, runtime and fee estimate for a task before it is created, served on
+    /// the HTTP API
+    async fn preflight(&self, request: &PreflightRequest) -> anyhow::Result<CostEstimate> {
+        let (address, _) = Pubkey::find_program_address(&[b"deposit_config"], &haunti_core::ID);
+        let account = self.solana_client.get_account(&address).await?;
+        let deposits = DepositConfig::try_deserialize(&mut account.data.as_slice())?;
+        Ok(cost_estimate::estimate(&self.cost_table, &deposits, request)?)
+    }
+
+    /// Signed binding price for one inference call, served on the HTTP API
+    async fn quote_inference(&self, request: &QuoteRequest) -> anyhow::Result<SignedQuote> {
+        let signer = self
+            .quote_signer
+            .as_ref()
+            .context("Quoting is disabled on this coordinator")?;
+        let model = Pubkey::from_str(&request.model).context("Invalid model")?;
+        let (address, _) =
+            Pubkey::find_program_address(&[b"pricing", model.as_ref()], &haunti_core::ID);
+        let account = self.solana_client.get_account(&address).await?;
+        let pricing = ModelPricing::try_deserialize(&mut account.data.as_slice())?;
+        let now = (unix_millis() / 1000) as i64;
+        Ok(signer.quote(request, &pricing, now)?)
+    }
+
+    /// Per-worker SLO compliance, served on the HTTP API
+    async fn slo_compliance(&self) -> Vec<slo::SloCompliance> {
+        self.slo.read().await.compliance()
+    }
+
+    #[instrument(skip(self, task, cancel))]
+    async fn execute_task(
+        &self,
+        task: ComputeTask,
+        cancel: CancellationToken,
+    ) -> Result<ComputeProof, NodeError> {
+        // Fetch data from IPFS; the model too unless it is resident
+        let data = self
+            .ipfs
+            .get_cid(&task.data_cid)
+            .await
+            .map_err(|e| TransientError::Storage(e.to_string()))?;
 
-        // Execute and generate proof
+        // Plaintext execution inside an attested enclave; the report hash is bound into the proof
+        if task.requires_tee {
+            let model = self.fetch_model(&task).await?;
+            let enclave = self
+                .enclave
+                .as_ref()
+                .ok_or_else(|| InfraError::Config("no enclave configured".into()))?;
+            // The enclave fetches the model key from the key-release service
+            self.audit(AuditAction::KeyRelease {
+                task: task.task_pubkey.to_string(),
+                model_cid: task.model_cid.clone(),
+            })
+            .await;
+            let output = enclave
+                .execute(
+                    task.task_pubkey.to_bytes(),
+                    &task.model_cid,
+                    &task.enclave_measurement,
+                    &model,
+                    &data,
+                )
+                .await?;
+            if cancel.is_cancelled() {
+                return Err(PermanentError::Cancelled.into());
+            }
+            let proof = self
+                .zk_prover
+                .prove_attested_cancellable(&output.result, &output.attestation.digest(), &cancel)
+                .map_err(|e| PermanentError::Execution(e.to_string()))?;
+            return Ok(ComputeProof {
+                result: output.result,
+                proof,
+                attestation: Some(output.attestation),
+                verifier_version: task.verifier_version,
+            });
+        }
+
+        // Execute and generate proof; FHE models are kept on the GPU between tasks
         let start = Instant::now();
-        let (result, proof) = backend.execute(model, data).await?;
+        // The backend aborts its GPU stream and proof job when the token fires
+        let (executed, model_cache) = if task.use_fhe {
+            let runtime = self.fhe_runtime.as_ref().unwrap().clone();
+            let key = ModelKey::new(&task.model_cid, &task.model_patch_cids, true);
+            let model = self
+                .warm_pool
+                .lease(runtime.device(), key, || async {
+                    let model = self.fetch_model(&task).await?;
+                    let resident = runtime
+                        .load_model(&model)
+                        .await
+                        .map_err(|e| PermanentError::Execution(format!("model upload: {e}")))?;
+                    let bytes = resident.device_bytes();
+                    Ok::<_, NodeError>((resident, bytes))
+                })
+                .await?;
+            let executed = runtime
+                .execute_resident_cancellable(&model, data, cancel.clone())
+                .await;
+            (executed, if model.warm { "warm" } else { "cold" })
+        } else {
+            let model = self.fetch_model(&task).await?;
+            let executed = ExecutionBackend::Cpu
+                .execute_cancellable(model, data, cancel.clone())
+                .await;
+            (executed, "cpu")
+        };
+        let (result, proof) = executed.map_err(|e| {
+            if cancel.is_cancelled() {
+                PermanentError::Cancelled
+            } else {
+                PermanentError::Execution(e.to_string())
+            }
+        })?;
         let duration = start.elapsed();
 
         // Record metrics
@@ -166,28 +1340,165 @@ impl Coordinator {
             .task_duration
             .with_label_values(&[&task.task_type])
             .observe(duration.as_secs_f64());
+        self.inference_latency
+            .with_label_values(&[model_cache])
+            .observe(duration.as_secs_f64());
+
+        Ok(ComputeProof {
+            result,
+            proof,
+            attestation: None,
+            verifier_version: task.verifier_version,
+        })
+    }
 
-        Ok(ComputeProof { result, proof })
+    /// Fetch the task's base model and apply its patch chain
+    async fn fetch_model(&self, task: &ComputeTask) -> Result<Vec<u8>, NodeError> {
+        let base_model = self
+            .ipfs
+            .get_cid(&task.model_cid)
+            .await
+            .map_err(|e| TransientError::Storage(e.to_string()))?;
+        // Models updated incrementally carry a chain of weight-diff patches on top of the base
+        let mut patches = Vec::with_capacity(task.model_patch_cids.len());
+        for cid in &task.model_patch_cids {
+            patches.push(
+                self.ipfs
+                    .get_cid(cid)
+                    .await
+                    .map_err(|e| TransientError::Storage(e.to_string()))?,
+            );
+        }
+        Ok(model_patch::materialize_model(&base_model, &patches)
+            .map_err(|e| PermanentError::Execution(format!("model patch: {e}")))?)
     }
 
     #[instrument(skip(self, proof))]
-    async fn submit_proof(&self, proof: ComputeProof) -> anyhow::Result<()> {
+    async fn submit_proof(&self, proof: ComputeProof) -> Result<(Pubkey, Signature), NodeError> {
+        // Resolve the verifier before spending time on local verification
+        let version = proof.verifier_version;
+        let verifier_program = self.verifiers.route(version).await?;
+
         // Verify proof locally first
-        let verified = self.zk_prover.verify(&proof.proof).await?;
+        let verified = self.verify_pool.verify(&proof.proof).await?;
         if !verified {
-            anyhow::bail!("Invalid proof generated");
+            return Err(PermanentError::InvalidProof.into());
         }
 
-        // Submit to Solana program
-        let tx = self
+        // Submit to the verifier program the task was tagged for
+        let submitted = self
             .solana_client
-            .submit_compute_proof(proof)
-            .await
-            .context("Failed to submit proof")?;
+            .submit_compute_proof(verifier_program, proof)
+            .await;
+        self.verifiers.record(version, submitted.is_ok());
+        let tx = submitted?;
 
-        info!(tx = %tx, "Proof submitted successfully");
+        info!(tx = %tx, verifier_version = version, "Proof submitted successfully");
+        Ok((verifier_program, tx))
+    }
+}
+
+fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// Small-parameter FHE runtime fed with deterministic synthetic inputs
+struct SyntheticFheTarget {
+    runtime: FheRuntime,
+    model: Vec<u8>,
+}
+
+#[async_trait::async_trait]
+impl SoakTarget for SyntheticFheTarget {
+    async fn run_synthetic_inference(&self, seed: u64) -> anyhow::Result<()> {
+        let input = self.runtime.encrypt_synthetic_input(seed)?;
+        self.runtime.execute(&self.model, &input).await?;
         Ok(())
     }
+
+    fn gpu_memory(&self) -> Option<GpuMemoryStats> {
+        let info = CudaAllocator::mem_info().ok()?;
+        Some(GpuMemoryStats {
+            free_bytes: info.free,
+            largest_free_block: info.largest_free_block,
+        })
+    }
+}
+
+/// Qualify a release under sustained load; fails if any leak threshold is exceeded
+async fn run_soak(config: &Config) -> anyhow::Result<()> {
+    let runtime = FheRuntime::new_gpu_with_params(FheParams::small()).await?;
+    let model = runtime.synthetic_model()?;
+    let target = Arc::new(SyntheticFheTarget { runtime, model });
+
+    info!(qps = config.soak_qps, duration_secs = config.soak_duration_secs, "Starting soak run");
+    let report = SoakRunner::new(
+        target,
+        SoakConfig {
+            qps: config.soak_qps,
+            duration: Duration::from_secs(config.soak_duration_secs),
+            concurrency: config.max_concurrent_tasks,
+            sample_interval: Duration::from_secs(30),
+            max_rss_growth_mb_per_hour: config.soak_max_rss_growth_mb_per_hour,
+            max_gpu_fragmentation: config.soak_max_gpu_fragmentation,
+            max_p99_queue_latency_ms: config.soak_max_p99_queue_latency_ms,
+        },
+    )
+    .run()
+    .await;
+
+    std::fs::write(&config.soak_report, serde_json::to_vec_pretty(&report)?)?;
+    info!(report = %config.soak_report.display(), "Soak report written");
+
+    if !report.passed() {
+        anyhow::bail!("Soak run failed: {}", report.violations.join("; "));
+    }
+    Ok(())
+}
+
+impl ProofVerifier for PlonkProver {
+    fn verify_blocking(&self, proof: &[u8]) -> anyhow::Result<bool> {
+        self.verify_sync(proof)
+    }
+}
+
+impl EnclaveBackend for PlatformEnclave {
+    fn tee(&self) -> TeeKind {
+        if self.is_tdx() {
+            TeeKind::Tdx
+        } else {
+            TeeKind::SevSnp
+        }
+    }
+
+    fn public_key(&self) -> [u8; 32] {
+        self.ephemeral_public_key()
+    }
+
+    fn attest(&self, report_data: [u8; 64]) -> Result<AttestationReport, EnclaveError> {
+        let report = self
+            .get_report(&report_data)
+            .map_err(|e| EnclaveError::Attestation(e.to_string()))?;
+        Ok(AttestationReport {
+            tee: self.tee(),
+            measurement: report.measurement().to_vec(),
+            report_data: report_data.to_vec(),
+            raw: report.to_bytes(),
+        })
+    }
+
+    fn run_sealed(
+        &self,
+        key: &WrappedKey,
+        encrypted_model: &[u8],
+        data: &[u8],
+    ) -> Result<Vec<u8>, EnclaveError> {
+        self.execute_sealed(&key.nonce, &key.ciphertext, encrypted_model, data)
+            .map_err(|e| EnclaveError::Execution(e.to_string()))
+    }
 }
 
 #[tokio::main]
@@ -206,8 +1517,12 @@ async fn main() -> anyhow::Result<()> {
         static ALLOCATOR: CudaAllocator = CudaAllocator;
     }
 
+    if config.soak {
+        return run_soak(&config).await;
+    }
+
     // Start coordinator
-    let coordinator = Coordinator::new(&config).await?;
+    let coordinator = Arc::new(Coordinator::new(&config).await?);
     coordinator.run(config).await?;
 
     Ok(())
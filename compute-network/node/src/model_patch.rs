@@ -0,0 +1,199 @@
+//! Weight-diff patches (HWDF) applied on top of a base model fetched from IPFS
+//!
+//! Mirrors the client SDK encoder in `client-sdk/src/utils/weightDiff.ts`.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+pub const WEIGHT_DIFF_MAGIC: &[u8; 4] = b"HWDF";
+pub const WEIGHT_DIFF_VERSION: u8 = 1;
+
+/// Named f32 tensors; models are stored as the borsh encoding of this map
+pub type TensorMap = BTreeMap<String, Vec<f32>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TensorDelta {
+    pub name: String,
+    pub numel: u32,
+    pub indices: Vec<u32>,
+    pub deltas: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightDiff {
+    pub base_root: [u8; 32],
+    pub tensors: Vec<TensorDelta>,
+}
+
+#[derive(Debug, Error)]
+pub enum PatchError {
+    #[error("Truncated diff")]
+    Truncated,
+    #[error("Bad magic")]
+    BadMagic,
+    #[error("Unsupported diff version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Trailing bytes after diff")]
+    TrailingBytes,
+    #[error("Patch for {0} does not match tensor shape")]
+    ShapeMismatch(String),
+    #[error("Index {index} out of range for {name}")]
+    IndexOutOfRange { name: String, index: u32 },
+    #[error("Model encoding error: {0}")]
+    Encoding(String),
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], PatchError> {
+        let end = self.offset.checked_add(n).ok_or(PatchError::Truncated)?;
+        let slice = self.bytes.get(self.offset..end).ok_or(PatchError::Truncated)?;
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16, PatchError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, PatchError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, PatchError> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+pub fn decode_weight_diff(bytes: &[u8]) -> Result<WeightDiff, PatchError> {
+    let mut reader = Reader { bytes, offset: 0 };
+    if reader.take(4)? != WEIGHT_DIFF_MAGIC {
+        return Err(PatchError::BadMagic);
+    }
+    let version = reader.take(1)?[0];
+    if version != WEIGHT_DIFF_VERSION {
+        return Err(PatchError::UnsupportedVersion(version));
+    }
+    let base_root: [u8; 32] = reader.take(32)?.try_into().unwrap();
+
+    let count = reader.u32()?;
+    let mut tensors = Vec::new();
+    for _ in 0..count {
+        let name_len = reader.u16()? as usize;
+        let name = String::from_utf8_lossy(reader.take(name_len)?).into_owned();
+        let numel = reader.u32()?;
+        let nnz = reader.u32()? as usize;
+        // Reject oversized counts before allocating
+        if bytes.len() - reader.offset < nnz.saturating_mul(8) {
+            return Err(PatchError::Truncated);
+        }
+        let indices = (0..nnz).map(|_| reader.u32()).collect::<Result<Vec<_>, _>>()?;
+        let deltas = (0..nnz).map(|_| reader.f32()).collect::<Result<Vec<_>, _>>()?;
+        tensors.push(TensorDelta { name, numel, indices, deltas });
+    }
+
+    if reader.offset != bytes.len() {
+        return Err(PatchError::TrailingBytes);
+    }
+    Ok(WeightDiff { base_root, tensors })
+}
+
+/// Apply one diff in place
+pub fn apply_weight_diff(tensors: &mut TensorMap, diff: &WeightDiff) -> Result<(), PatchError> {
+    for delta in &diff.tensors {
+        let target = tensors
+            .entry(delta.name.clone())
+            .or_insert_with(|| vec![0.0; delta.numel as usize]);
+        if target.len() != delta.numel as usize {
+            return Err(PatchError::ShapeMismatch(delta.name.clone()));
+        }
+        for (&index, &value) in delta.indices.iter().zip(&delta.deltas) {
+            let slot = target
+                .get_mut(index as usize)
+                .ok_or_else(|| PatchError::IndexOutOfRange {
+                    name: delta.name.clone(),
+                    index,
+                })?;
+            *slot += value;
+        }
+    }
+    Ok(())
+}
+
+/// Rebuild the current model from the base and its patch chain, oldest first
+pub fn materialize_model(base: &[u8], patches: &[Vec<u8>]) -> Result<Vec<u8>, PatchError> {
+    if patches.is_empty() {
+        return Ok(base.to_vec());
+    }
+    let mut tensors =
+        TensorMap::try_from_slice(base).map_err(|e| PatchError::Encoding(e.to_string()))?;
+    for patch in patches {
+        apply_weight_diff(&mut tensors, &decode_weight_diff(patch)?)?;
+    }
+    tensors
+        .try_to_vec()
+        .map_err(|e| PatchError::Encoding(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(diff: &WeightDiff) -> Vec<u8> {
+        let mut out = WEIGHT_DIFF_MAGIC.to_vec();
+        out.push(WEIGHT_DIFF_VERSION);
+        out.extend_from_slice(&diff.base_root);
+        out.extend_from_slice(&(diff.tensors.len() as u32).to_le_bytes());
+        for t in &diff.tensors {
+            out.extend_from_slice(&(t.name.len() as u16).to_le_bytes());
+            out.extend_from_slice(t.name.as_bytes());
+            out.extend_from_slice(&t.numel.to_le_bytes());
+            out.extend_from_slice(&(t.indices.len() as u32).to_le_bytes());
+            t.indices.iter().for_each(|i| out.extend_from_slice(&i.to_le_bytes()));
+            t.deltas.iter().for_each(|d| out.extend_from_slice(&d.to_le_bytes()));
+        }
+        out
+    }
+
+    #[test]
+    fn test_patch_chain_materializes_model() {
+        let base: TensorMap = [("w".to_string(), vec![1.0, 2.0, 3.0])].into_iter().collect();
+        let first = WeightDiff {
+            base_root: [1; 32],
+            tensors: vec![TensorDelta {
+                name: "w".into(),
+                numel: 3,
+                indices: vec![1],
+                deltas: vec![0.5],
+            }],
+        };
+        let second = WeightDiff {
+            base_root: [2; 32],
+            tensors: vec![TensorDelta {
+                name: "b".into(),
+                numel: 2,
+                indices: vec![0],
+                deltas: vec![-1.0],
+            }],
+        };
+        assert_eq!(decode_weight_diff(&encode(&first)).unwrap(), first);
+
+        let model = materialize_model(
+            &base.try_to_vec().unwrap(),
+            &[encode(&first), encode(&second)],
+        )
+        .unwrap();
+        let tensors = TensorMap::try_from_slice(&model).unwrap();
+        assert_eq!(tensors["w"], vec![1.0, 2.5, 3.0]);
+        assert_eq!(tensors["b"], vec![-1.0, 0.0]);
+
+        let mut truncated = encode(&first);
+        truncated.pop();
+        assert!(matches!(decode_weight_diff(&truncated), Err(PatchError::Truncated)));
+    }
+}
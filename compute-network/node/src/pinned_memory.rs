@@ -0,0 +1,374 @@
+//! NUMA-local pinned host buffers for ciphertext uploads
+//!
+//! The driver copies pageable memory to the GPU through its own page-locked
+//! bounce buffer, one extra memcpy per transfer, and for small FHE batches that
+//! staging dominates latency. Buffers here are page-locked up front with
+//! `cudaHostRegister` so uploads are a single DMA, and their pages are bound to
+//! the NUMA node of the GPU's PCIe root complex so the DMA never crosses the
+//! socket interconnect. Pinning touches every page and is slow, so buffers are
+//! pooled per GPU by power-of-two size class and reused.
+
+use std::{
+    collections::HashMap,
+    ffi::{c_char, c_void, CStr},
+    fs, io,
+    ops::{Deref, DerefMut},
+    path::Path,
+    ptr::{self, NonNull},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+/// Smallest pooled buffer
+pub const MIN_BUFFER_BYTES: usize = 64 * 1024;
+
+const SYSFS_ROOT: &str = "/sys";
+const CUDA_HOST_REGISTER_PORTABLE: u32 = 0x01;
+const MPOL_BIND: libc::c_int = 2;
+const MPOL_MF_STRICT: libc::c_uint = 1 << 0;
+const MPOL_MF_MOVE: libc::c_uint = 1 << 1;
+
+mod cudart {
+    use std::ffi::{c_char, c_void};
+
+    extern "C" {
+        pub fn cudaHostRegister(ptr: *mut c_void, size: usize, flags: u32) -> i32;
+        pub fn cudaHostUnregister(ptr: *mut c_void) -> i32;
+        pub fn cudaDeviceGetPCIBusId(bus_id: *mut c_char, len: i32, device: i32) -> i32;
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PinnedMemoryError {
+    #[error("Failed to map {size} bytes of host memory: {source}")]
+    Map { size: usize, source: io::Error },
+    #[error("{call} failed with CUDA error {code}")]
+    Cuda { call: &'static str, code: i32 },
+}
+
+/// Where a GPU sits relative to host memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuLocality {
+    pub device: u32,
+    /// sysfs form, e.g. `0000:3b:00.0`
+    pub pci_bus_id: String,
+    /// NUMA node of the PCIe root complex; `None` on single-node hosts or when
+    /// the firmware does not report one
+    pub numa_node: Option<u32>,
+}
+
+impl GpuLocality {
+    /// Look up `device` through the CUDA runtime and sysfs
+    pub fn probe(device: u32) -> Result<Self, PinnedMemoryError> {
+        let mut bus_id = [0 as c_char; 32];
+        let code = unsafe {
+            cudart::cudaDeviceGetPCIBusId(bus_id.as_mut_ptr(), bus_id.len() as i32, device as i32)
+        };
+        if code != 0 {
+            return Err(PinnedMemoryError::Cuda { call: "cudaDeviceGetPCIBusId", code });
+        }
+        let bus_id = unsafe { CStr::from_ptr(bus_id.as_ptr()) }.to_string_lossy();
+        Ok(Self::from_sysfs(Path::new(SYSFS_ROOT), device, &bus_id))
+    }
+
+    pub fn from_sysfs(root: &Path, device: u32, pci_bus_id: &str) -> Self {
+        let pci_bus_id = normalize_bus_id(pci_bus_id);
+        // The kernel reports -1 when the device has no affinity
+        let numa_node = fs::read_to_string(
+            root.join("bus/pci/devices").join(&pci_bus_id).join("numa_node"),
+        )
+        .ok()
+        .and_then(|node| node.trim().parse::<i32>().ok())
+        .and_then(|node| u32::try_from(node).ok());
+        Self { device, pci_bus_id, numa_node }
+    }
+}
+
+/// CUDA reports `DDDDDDDD:BB:DD.F` with an 8-digit domain; sysfs names devices
+/// `dddd:bb:dd.f`
+pub fn normalize_bus_id(bus_id: &str) -> String {
+    let lower = bus_id.trim().to_ascii_lowercase();
+    match lower.split_once(':') {
+        Some((domain, rest)) if domain.len() > 4 => {
+            format!("{}:{rest}", &domain[domain.len() - 4..])
+        }
+        _ => lower,
+    }
+}
+
+/// Pool size class for a request of `len` bytes
+pub fn size_class(len: usize) -> usize {
+    len.max(MIN_BUFFER_BYTES).next_power_of_two()
+}
+
+/// Page-locked host memory registered with the CUDA driver
+pub struct PinnedBuffer {
+    ptr: NonNull<u8>,
+    capacity: usize,
+    len: usize,
+}
+
+// The buffer owns its mapping exclusively
+unsafe impl Send for PinnedBuffer {}
+unsafe impl Sync for PinnedBuffer {}
+
+impl PinnedBuffer {
+    /// Map `capacity` bytes, bind them to `numa_node` and pin them. Binding is
+    /// best effort: a host without NUMA support still gets pinned memory
+    pub fn allocate(capacity: usize, numa_node: Option<u32>) -> Result<Self, PinnedMemoryError> {
+        let addr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                capacity,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(PinnedMemoryError::Map {
+                size: capacity,
+                source: io::Error::last_os_error(),
+            });
+        }
+        if let Some(node) = numa_node {
+            if let Err(e) = bind_to_node(addr, capacity, node) {
+                warn!(node, error = %e, "Pinned buffer not bound to GPU NUMA node");
+            }
+        }
+        // Fault every page in now, on the bound node, rather than at registration
+        unsafe { ptr::write_bytes(addr as *mut u8, 0, capacity) };
+
+        let code = unsafe { cudart::cudaHostRegister(addr, capacity, CUDA_HOST_REGISTER_PORTABLE) };
+        if code != 0 {
+            unsafe { libc::munmap(addr, capacity) };
+            return Err(PinnedMemoryError::Cuda { call: "cudaHostRegister", code });
+        }
+        Ok(Self {
+            ptr: NonNull::new(addr as *mut u8).expect("mmap never returns null on success"),
+            capacity,
+            len: 0,
+        })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Replace the contents with `data`, which must fit
+    pub fn fill(&mut self, data: &[u8]) {
+        assert!(data.len() <= self.capacity, "pinned buffer overflow");
+        self.len = data.len();
+        self.as_mut_slice().copy_from_slice(data);
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for PinnedBuffer {
+    fn drop(&mut self) {
+        let addr = self.ptr.as_ptr() as *mut c_void;
+        unsafe {
+            cudart::cudaHostUnregister(addr);
+            libc::munmap(addr, self.capacity);
+        }
+    }
+}
+
+/// `mbind(MPOL_BIND)` the range to a single node, moving any pages already
+/// faulted in elsewhere
+fn bind_to_node(addr: *mut c_void, len: usize, node: u32) -> io::Result<()> {
+    let node = node as usize;
+    let mut mask = vec![0 as libc::c_ulong; node / libc::c_ulong::BITS as usize + 1];
+    mask[node / libc::c_ulong::BITS as usize] |= 1 << (node % libc::c_ulong::BITS as usize);
+    let max_node = mask.len() * libc::c_ulong::BITS as usize + 1;
+    let rc = unsafe {
+        libc::syscall(
+            libc::SYS_mbind,
+            addr,
+            len,
+            MPOL_BIND,
+            mask.as_ptr(),
+            max_node,
+            MPOL_MF_STRICT | MPOL_MF_MOVE,
+        )
+    };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Requests served from a cached buffer
+    pub hits: u64,
+    /// Requests that pinned a new buffer
+    pub misses: u64,
+    pub cached_bytes: usize,
+}
+
+#[derive(Default)]
+struct FreeList {
+    by_class: HashMap<usize, Vec<PinnedBuffer>>,
+    bytes: usize,
+}
+
+/// Reusable pinned buffers on one NUMA node
+pub struct PinnedPool {
+    numa_node: Option<u32>,
+    /// Released buffers beyond this are unpinned instead of cached
+    max_cached_bytes: usize,
+    free: Mutex<FreeList>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl PinnedPool {
+    pub fn new(numa_node: Option<u32>, max_cached_bytes: usize) -> Arc<Self> {
+        Arc::new(Self {
+            numa_node,
+            max_cached_bytes,
+            free: Mutex::new(FreeList::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    /// Pool local to GPU `device`
+    pub fn for_device(device: u32, max_cached_bytes: usize) -> Result<Arc<Self>, PinnedMemoryError> {
+        let locality = GpuLocality::probe(device)?;
+        debug!(
+            device,
+            pci_bus_id = %locality.pci_bus_id,
+            numa_node = ?locality.numa_node,
+            "Pinned pool placed"
+        );
+        Ok(Self::new(locality.numa_node, max_cached_bytes))
+    }
+
+    pub fn numa_node(&self) -> Option<u32> {
+        self.numa_node
+    }
+
+    /// A buffer of at least `len` bytes, reused when one is cached
+    pub fn acquire(self: &Arc<Self>, len: usize) -> Result<PooledBuffer, PinnedMemoryError> {
+        let class = size_class(len);
+        let cached = {
+            let mut free = self.free.lock().unwrap();
+            let buffer = free.by_class.get_mut(&class).and_then(Vec::pop);
+            if buffer.is_some() {
+                free.bytes -= class;
+            }
+            buffer
+        };
+        let buffer = match cached {
+            Some(buffer) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buffer
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                PinnedBuffer::allocate(class, self.numa_node)?
+            }
+        };
+        Ok(PooledBuffer {
+            buffer: Some(buffer),
+            pool: self.clone(),
+        })
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            cached_bytes: self.free.lock().unwrap().bytes,
+        }
+    }
+
+    fn release(&self, mut buffer: PinnedBuffer) {
+        let mut free = self.free.lock().unwrap();
+        if free.bytes + buffer.capacity > self.max_cached_bytes {
+            return;
+        }
+        buffer.len = 0;
+        free.bytes += buffer.capacity;
+        free.by_class.entry(buffer.capacity).or_default().push(buffer);
+    }
+}
+
+/// Pinned buffer that returns to its pool when dropped
+pub struct PooledBuffer {
+    buffer: Option<PinnedBuffer>,
+    pool: Arc<PinnedPool>,
+}
+
+impl Deref for PooledBuffer {
+    type Target = PinnedBuffer;
+
+    fn deref(&self) -> &PinnedBuffer {
+        self.buffer.as_ref().expect("present until drop")
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut PinnedBuffer {
+        self.buffer.as_mut().expect("present until drop")
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.pool.release(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bus_id_normalized_to_sysfs_form() {
+        assert_eq!(normalize_bus_id("00000000:3B:00.0"), "0000:3b:00.0");
+        assert_eq!(normalize_bus_id("0000:af:00.0\n"), "0000:af:00.0");
+    }
+
+    #[test]
+    fn test_size_classes() {
+        assert_eq!(size_class(0), MIN_BUFFER_BYTES);
+        assert_eq!(size_class(MIN_BUFFER_BYTES + 1), 2 * MIN_BUFFER_BYTES);
+        assert_eq!(size_class(3 << 20), 4 << 20);
+    }
+
+    #[test]
+    fn test_numa_node_read_from_sysfs() {
+        let root = std::env::temp_dir().join(format!("haunti-sysfs-{}", std::process::id()));
+        for (bus_id, node) in [("0000:3b:00.0", "1\n"), ("0000:5e:00.0", "-1\n")] {
+            let dir = root.join("bus/pci/devices").join(bus_id);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("numa_node"), node).unwrap();
+        }
+
+        let gpu = GpuLocality::from_sysfs(&root, 0, "00000000:3B:00.0");
+        assert_eq!(gpu.pci_bus_id, "0000:3b:00.0");
+        assert_eq!(gpu.numa_node, Some(1));
+        assert_eq!(GpuLocality::from_sysfs(&root, 1, "0000:5e:00.0").numa_node, None);
+        assert_eq!(GpuLocality::from_sysfs(&root, 2, "0000:86:00.0").numa_node, None);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
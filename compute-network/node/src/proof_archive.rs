@@ -0,0 +1,341 @@
+//! Permanent copies of verified proofs on Arweave
+//!
+//! Proof bytes only live in the transaction that verified them and in task
+//! accounts that are closed once a task settles. After a proof lands, the
+//! coordinator reads the verify instruction back from the confirmed transaction,
+//! so the archive holds exactly the bytes the program checked, and uploads them
+//! with their public inputs to an Arweave bundler as an ANS-104 data item signed
+//! with the coordinator's ed25519 key. The data item id is the Arweave transaction
+//! id; it is recorded on the verification account with `record_proof_archive`,
+//! sent in the bulk lane of a submitter that pays from the archive key.
+
+use anchor_lang::{AnchorDeserialize, AnchorSerialize, Discriminator, InstructionData, ToAccountMetas};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha384};
+use solana_client::{
+    client_error::ClientError, nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig,
+};
+use solana_program::{hash::hash, instruction::Instruction};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::UiTransactionEncoding;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::submitter::{SubmitError, TxSubmitter};
+
+/// Bumped on any incompatible change to the archived payload
+pub const ARCHIVE_FORMAT: &str = "haunti-proof/1";
+/// ANS-104 signature type of ed25519 (Solana) keys
+const SIGNATURE_TYPE_ED25519: u16 = 2;
+
+/// Payload of an archived data item, Borsh-encoded
+#[derive(Debug, Clone, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct ArchivedProof {
+    pub proof: Vec<u8>,
+    pub public_inputs: Vec<[u8; 32]>,
+}
+
+impl ArchivedProof {
+    /// Arguments of the top-level `verify_ai_proof` instruction sent to
+    /// `verifier_program` in `tx`. Proofs verified from a proof buffer are not
+    /// in the transaction, so there is nothing to recover.
+    pub fn from_transaction(tx: &VersionedTransaction, verifier_program: &Pubkey) -> Option<Self> {
+        let keys = tx.message.static_account_keys();
+        tx.message.instructions().iter().find_map(|ix| {
+            if keys.get(ix.program_id_index as usize) != Some(verifier_program) {
+                return None;
+            }
+            let args = ix
+                .data
+                .strip_prefix(solana_verifier::instruction::VerifyAiProof::DISCRIMINATOR.as_slice())?;
+            solana_verifier::instruction::VerifyAiProof::deserialize(&mut &args[..])
+                .ok()
+                .filter(|verify| !verify.proof_data.is_empty())
+                .map(|verify| Self {
+                    proof: verify.proof_data,
+                    public_inputs: verify.public_inputs,
+                })
+        })
+    }
+
+    /// Matches `VerificationState::proof_hash` of the verification it came from
+    pub fn commitment(&self) -> [u8; 32] {
+        solana_verifier::proof_commitment(&self.proof, &self.public_inputs)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("Bundler rejected upload ({status}): {body}")]
+    Rejected { status: u16, body: String },
+    #[error("Bundler unreachable: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("No verify instruction in transaction {0}")]
+    ProofNotFound(Signature),
+    #[error("RPC error: {0}")]
+    Rpc(#[from] ClientError),
+    #[error("Recording archive failed: {0}")]
+    Submit(#[from] SubmitError),
+}
+
+/// Uploads verified proofs to an Arweave bundler and records where they went
+pub struct ProofArchiver {
+    http: reqwest::Client,
+    bundler_url: String,
+    /// Signs data items and `record_proof_archive`; needs a bundler balance
+    signer: Arc<Keypair>,
+    /// Sends `record_proof_archive`; its payer is `signer`, apart from the
+    /// coordinator's own submitter
+    submitter: Arc<TxSubmitter>,
+}
+
+impl ProofArchiver {
+    pub fn new(bundler_url: String, signer: Arc<Keypair>, submitter: Arc<TxSubmitter>) -> Self {
+        debug_assert_eq!(submitter.payer(), signer.pubkey());
+        Self {
+            http: reqwest::Client::new(),
+            bundler_url: bundler_url.trim_end_matches('/').to_string(),
+            signer,
+            submitter,
+        }
+    }
+
+    /// Submitter the archive records go through; its run loop is the caller's
+    pub fn submitter(&self) -> Arc<TxSubmitter> {
+        self.submitter.clone()
+    }
+
+    /// Mirror the proof verified by `tx` and record the Arweave transaction id on
+    /// the verification account; returns the id
+    pub async fn archive(
+        &self,
+        rpc: &RpcClient,
+        verifier_program: Pubkey,
+        tx: Signature,
+        task: Pubkey,
+    ) -> Result<[u8; 32], ArchiveError> {
+        let confirmed = rpc
+            .get_transaction_with_config(
+                &tx,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await?;
+        let proof = confirmed
+            .transaction
+            .transaction
+            .decode()
+            .and_then(|decoded| ArchivedProof::from_transaction(&decoded, &verifier_program))
+            .ok_or(ArchiveError::ProofNotFound(tx))?;
+
+        let archive_tx = self.upload(&proof, &task, &tx).await?;
+        self.record(verifier_program, archive_tx).await?;
+        Ok(archive_tx)
+    }
+
+    /// Post `proof` to the bundler as a signed data item
+    async fn upload(
+        &self,
+        proof: &ArchivedProof,
+        task: &Pubkey,
+        tx: &Signature,
+    ) -> Result<[u8; 32], ArchiveError> {
+        let tags = [
+            ("Content-Type", "application/octet-stream".to_string()),
+            ("App-Name", "Haunti".to_string()),
+            ("Format", ARCHIVE_FORMAT.to_string()),
+            ("Task", task.to_string()),
+            ("Verify-Tx", tx.to_string()),
+            ("Proof-Commitment", hex::encode(proof.commitment())),
+        ];
+        let payload = proof.try_to_vec().expect("in-memory Borsh encoding cannot fail");
+        let (id, item) = data_item(&self.signer, &tags, &payload);
+
+        let response = self
+            .http
+            .post(format!("{}/tx/solana", self.bundler_url))
+            .header("content-type", "application/octet-stream")
+            .body(item)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ArchiveError::Rejected {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+        Ok(id)
+    }
+
+    async fn record(
+        &self,
+        verifier_program: Pubkey,
+        archive_tx: [u8; 32],
+    ) -> Result<Signature, ArchiveError> {
+        let (verification_result, _) =
+            Pubkey::find_program_address(&[b"verification"], &verifier_program);
+        let ix = Instruction {
+            program_id: verifier_program,
+            accounts: solana_verifier::accounts::RecordProofArchive {
+                verification_result,
+                verifier: self.signer.pubkey(),
+            }
+            .to_account_metas(None),
+            data: solana_verifier::instruction::RecordProofArchive { archive_tx }.data(),
+        };
+        Ok(self.submitter.submit(vec![ix]).await?)
+    }
+}
+
+/// Arweave's base64url form of a transaction id
+pub fn arweave_id(id: &[u8; 32]) -> String {
+    URL_SAFE_NO_PAD.encode(id)
+}
+
+/// Serialize and sign an ANS-104 data item with an ed25519 key, without target
+/// or anchor; returns the item id and its bytes
+pub fn data_item(signer: &Keypair, tags: &[(&str, String)], data: &[u8]) -> ([u8; 32], Vec<u8>) {
+    let owner = signer.pubkey().to_bytes();
+    let tag_bytes = encode_tags(tags);
+    let message = deep_hash(&Chunk::List(vec![
+        Chunk::Blob(b"dataitem"),
+        Chunk::Blob(b"1"),
+        Chunk::Blob(SIGNATURE_TYPE_ED25519.to_string().as_bytes()),
+        Chunk::Blob(&owner),
+        Chunk::Blob(b""), // target
+        Chunk::Blob(b""), // anchor
+        Chunk::Blob(&tag_bytes),
+        Chunk::Blob(data),
+    ]));
+    let signature = signer.sign_message(&message);
+
+    let mut item = Vec::with_capacity(2 + 64 + 32 + 2 + 16 + tag_bytes.len() + data.len());
+    item.extend_from_slice(&SIGNATURE_TYPE_ED25519.to_le_bytes());
+    item.extend_from_slice(signature.as_ref());
+    item.extend_from_slice(&owner);
+    item.push(0); // no target
+    item.push(0); // no anchor
+    item.extend_from_slice(&(tags.len() as u64).to_le_bytes());
+    item.extend_from_slice(&(tag_bytes.len() as u64).to_le_bytes());
+    item.extend_from_slice(&tag_bytes);
+    item.extend_from_slice(data);
+    (hash(signature.as_ref()).to_bytes(), item)
+}
+
+/// Tags as an Avro array of `{name: bytes, value: bytes}` records; empty when
+/// there are none
+fn encode_tags(tags: &[(&str, String)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    if tags.is_empty() {
+        return out;
+    }
+    avro_long(&mut out, tags.len() as i64);
+    for (name, value) in tags {
+        avro_long(&mut out, name.len() as i64);
+        out.extend_from_slice(name.as_bytes());
+        avro_long(&mut out, value.len() as i64);
+        out.extend_from_slice(value.as_bytes());
+    }
+    avro_long(&mut out, 0);
+    out
+}
+
+/// Zigzag varint
+fn avro_long(out: &mut Vec<u8>, value: i64) {
+    let mut n = ((value << 1) ^ (value >> 63)) as u64;
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+enum Chunk<'a> {
+    Blob(&'a [u8]),
+    List(Vec<Chunk<'a>>),
+}
+
+/// Arweave's SHA-384 deep hash
+fn deep_hash(chunk: &Chunk) -> [u8; 48] {
+    match chunk {
+        Chunk::Blob(data) => {
+            let tag = sha384(format!("blob{}", data.len()).as_bytes());
+            sha384(&[tag, sha384(data)].concat())
+        }
+        Chunk::List(items) => items.iter().fold(
+            sha384(format!("list{}", items.len()).as_bytes()),
+            |acc, item| sha384(&[acc, deep_hash(item)].concat()),
+        ),
+    }
+}
+
+fn sha384(data: &[u8]) -> [u8; 48] {
+    Sha384::digest(data).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags_avro_encoded() {
+        assert!(encode_tags(&[]).is_empty());
+        assert_eq!(
+            encode_tags(&[("a", "bc".to_string())]),
+            vec![2, 2, b'a', 4, b'b', b'c', 0]
+        );
+        let mut long = Vec::new();
+        avro_long(&mut long, 64);
+        assert_eq!(long, vec![0x80, 0x01]);
+    }
+
+    #[test]
+    fn test_data_item_signed_over_deep_hash() {
+        let signer = Keypair::new();
+        let tags = [("Format", ARCHIVE_FORMAT.to_string())];
+        let (id, item) = data_item(&signer, &tags, b"proof");
+
+        assert_eq!(&item[..2], &[2, 0]);
+        let signature = Signature::try_from(&item[2..66]).unwrap();
+        assert_eq!(&item[66..98], signer.pubkey().as_ref());
+        assert_eq!(id, hash(signature.as_ref()).to_bytes());
+        assert!(item.ends_with(b"proof"));
+
+        let tag_bytes = encode_tags(&tags);
+        let message = deep_hash(&Chunk::List(vec![
+            Chunk::Blob(b"dataitem"),
+            Chunk::Blob(b"1"),
+            Chunk::Blob(b"2"),
+            Chunk::Blob(signer.pubkey().as_ref()),
+            Chunk::Blob(b""),
+            Chunk::Blob(b""),
+            Chunk::Blob(&tag_bytes),
+            Chunk::Blob(b"proof"),
+        ]));
+        assert!(signature.verify(signer.pubkey().as_ref(), &message));
+        assert_eq!(arweave_id(&id).len(), 43);
+    }
+
+    #[test]
+    fn test_archived_proof_round_trips() {
+        let proof = ArchivedProof {
+            proof: vec![1, 2, 3],
+            public_inputs: vec![[7; 32]],
+        };
+        let decoded = ArchivedProof::try_from_slice(&proof.try_to_vec().unwrap()).unwrap();
+        assert_eq!(decoded, proof);
+        assert_eq!(
+            decoded.commitment(),
+            solana_verifier::proof_commitment(&[1, 2, 3], &[[7; 32]])
+        );
+    }
+}
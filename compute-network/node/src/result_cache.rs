@@ -0,0 +1,193 @@
+//! Verified-result cache for deduplicating identical inference requests
+
+use solana_program::keccak;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::SystemTime,
+};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+/// Fee charged for a cache hit, in basis points of the full inference fee;
+/// matches the program's `CACHED_RESULT_FEE_BPS`
+pub const CACHED_RESULT_FEE_BPS: u64 = 1_000;
+
+/// Upper bound on cached entries before the oldest are evicted
+const MAX_CACHE_ENTRIES: usize = 100_000;
+
+/// Commitment over everything that determines an inference result
+pub type DedupKey = [u8; 32];
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum CacheError {
+    #[error("Cached fee for a full fee of {0} lamports overflows")]
+    FeeOverflow(u64),
+}
+
+/// Verified result previously anchored on-chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedResult {
+    /// Task account holding the original verified result
+    pub source_task: Pubkey,
+    pub result_hash: [u8; 32],
+    pub proof_digest: [u8; 32],
+    pub verified_at: u64,
+}
+
+/// Compute the dedup commitment for an inference request
+pub fn dedup_key(model_root: &[u8; 32], input_hash: &[u8; 32], params: &[u8]) -> DedupKey {
+    keccak::hashv(&[b"haunti-dedup-v1", model_root, input_hash, params]).0
+}
+
+/// Reduced fee charged when a request is served from cache
+pub fn cached_fee(full_fee: u64) -> Result<u64, CacheError> {
+    full_fee
+        .checked_mul(CACHED_RESULT_FEE_BPS)
+        .map(|scaled| scaled / 10_000)
+        .ok_or(CacheError::FeeOverflow(full_fee))
+}
+
+/// In-memory index of verified results keyed by dedup commitment
+pub struct ResultCache {
+    index: RwLock<CacheIndex>,
+    capacity: usize,
+}
+
+/// Entries plus their insertion order, so eviction never scans the map
+#[derive(Default)]
+struct CacheIndex {
+    entries: HashMap<DedupKey, (u64, CachedResult)>,
+    /// Insertion sequence to key, oldest first
+    order: BTreeMap<u64, DedupKey>,
+    next_seq: u64,
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        Self::with_capacity(MAX_CACHE_ENTRIES)
+    }
+}
+
+impl ResultCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cache holding at most `capacity` results
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            index: RwLock::new(CacheIndex::default()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Look up a verified result, skipping models that opted out of dedup
+    pub async fn lookup(&self, key: &DedupKey, nondeterministic: bool) -> Option<CachedResult> {
+        if nondeterministic {
+            return None;
+        }
+        self.index.read().await.entries.get(key).map(|(_, entry)| entry.clone())
+    }
+
+    /// Record a result whose verify transaction is confirmed on-chain; callers
+    /// must not insert on submission alone. Results of nondeterministic models
+    /// are never cached, and the oldest entry makes room at capacity
+    pub async fn insert_verified(
+        &self,
+        key: DedupKey,
+        source_task: Pubkey,
+        result_hash: [u8; 32],
+        proof_digest: [u8; 32],
+        nondeterministic: bool,
+    ) {
+        if nondeterministic {
+            return;
+        }
+        let verified_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let mut index = self.index.write().await;
+        let index = &mut *index;
+        if let Some((seq, _)) = index.entries.remove(&key) {
+            index.order.remove(&seq);
+        }
+        while index.entries.len() >= self.capacity {
+            let Some((_, oldest)) = index.order.pop_first() else { break };
+            index.entries.remove(&oldest);
+        }
+
+        let seq = index.next_seq;
+        index.next_seq += 1;
+        index.order.insert(seq, key);
+        index.entries.insert(key, (seq, CachedResult {
+            source_task,
+            result_hash,
+            proof_digest,
+            verified_at,
+        }));
+    }
+
+    pub async fn len(&self) -> usize {
+        self.index.read().await.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_dedup_hit_and_opt_out() {
+        let cache = ResultCache::new();
+        let key = dedup_key(&[1; 32], &[2; 32], b"temperature=0");
+
+        assert!(cache.lookup(&key, false).await.is_none());
+        cache.insert_verified(key, Pubkey::new_unique(), [3; 32], [4; 32], false).await;
+
+        let hit = cache.lookup(&key, false).await.unwrap();
+        assert_eq!(hit.result_hash, [3; 32]);
+
+        // Nondeterministic models always re-run
+        assert!(cache.lookup(&key, true).await.is_none());
+
+        // Any change to the params yields a different commitment
+        assert_ne!(key, dedup_key(&[1; 32], &[2; 32], b"temperature=1"));
+    }
+
+    #[tokio::test]
+    async fn test_nondeterministic_results_not_cached() {
+        let cache = ResultCache::new();
+        let key = dedup_key(&[1; 32], &[2; 32], b"seed=random");
+
+        cache.insert_verified(key, Pubkey::new_unique(), [3; 32], [4; 32], true).await;
+        assert_eq!(cache.len().await, 0);
+        assert!(cache.lookup(&key, false).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_oldest_entry_evicted_at_capacity() {
+        let cache = ResultCache::with_capacity(2);
+        let keys: Vec<DedupKey> = (0u8..3).map(|i| dedup_key(&[i; 32], &[0; 32], b"")).collect();
+
+        cache.insert_verified(keys[0], Pubkey::new_unique(), [0; 32], [0; 32], false).await;
+        cache.insert_verified(keys[1], Pubkey::new_unique(), [1; 32], [1; 32], false).await;
+        // Re-verifying a key refreshes its position
+        cache.insert_verified(keys[0], Pubkey::new_unique(), [2; 32], [2; 32], false).await;
+        cache.insert_verified(keys[2], Pubkey::new_unique(), [3; 32], [3; 32], false).await;
+
+        assert_eq!(cache.len().await, 2);
+        assert!(cache.lookup(&keys[1], false).await.is_none());
+        assert_eq!(cache.lookup(&keys[0], false).await.unwrap().result_hash, [2; 32]);
+        assert_eq!(cache.lookup(&keys[2], false).await.unwrap().result_hash, [3; 32]);
+    }
+
+    #[test]
+    fn test_cached_fee_discount() {
+        assert_eq!(cached_fee(1_000_000), Ok(100_000));
+        assert_eq!(cached_fee(u64::MAX / CACHED_RESULT_FEE_BPS), Ok(u64::MAX / CACHED_RESULT_FEE_BPS / 10));
+        assert_eq!(cached_fee(u64::MAX), Err(CacheError::FeeOverflow(u64::MAX)));
+    }
+}
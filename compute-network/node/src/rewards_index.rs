@@ -0,0 +1,269 @@
+//! Staking reward indexer: historical emissions, realized APY, and reward history export
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap};
+use token_vault::PoolEvent;
+
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 3600.0;
+/// Nominal slot time used when extrapolating past the last known block time
+const DEFAULT_SLOT_MS: i64 = 400;
+
+/// Token-vault `PoolEvent`s relevant to reward accounting
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolEventKind {
+    Staked { user: Pubkey, amount: u64 },
+    Unstaked { user: Pubkey, amount: u64 },
+    RewardClaimed { user: Pubkey, amount: u64 },
+    /// Stake removed by `slash_stake`
+    Slashed { user: Pubkey, amount: u64 },
+}
+
+/// Decoded event with the slot and pool of the transaction that emitted it
+#[derive(Debug, Clone)]
+pub struct IndexedEvent {
+    pub slot: u64,
+    pub pool: Pubkey,
+    pub kind: PoolEventKind,
+}
+
+/// Decode the token-vault `emit!` payloads of one transaction (see
+/// `subscription::program_data`), keeping the events reward accounting uses
+pub fn decode_pool_events(slot: u64, data: &[Vec<u8>]) -> Vec<IndexedEvent> {
+    data.iter()
+        .filter_map(|bytes| {
+            let payload = bytes.strip_prefix(PoolEvent::DISCRIMINATOR.as_slice())?;
+            PoolEvent::deserialize(&mut &payload[..]).ok()
+        })
+        .filter_map(|event| {
+            let (pool, kind) = match event {
+                PoolEvent::Staked { pool, user, amount, .. } => (pool, PoolEventKind::Staked { user, amount }),
+                PoolEvent::Unstaked { pool, user, amount, .. } => (pool, PoolEventKind::Unstaked { user, amount }),
+                PoolEvent::RewardClaimed { pool, user, amount, .. } => {
+                    (pool, PoolEventKind::RewardClaimed { user, amount })
+                }
+                PoolEvent::Slashed { pool, user, amount, .. } => (pool, PoolEventKind::Slashed { user, amount }),
+                _ => return None,
+            };
+            Some(IndexedEvent { slot, pool, kind })
+        })
+        .collect()
+}
+
+/// Slot to unix-time mapping from sampled block times
+#[derive(Debug, Default)]
+pub struct SlotClock {
+    anchors: BTreeMap<u64, i64>,
+}
+
+impl SlotClock {
+    pub fn record(&mut self, slot: u64, unix_time: i64) {
+        self.anchors.insert(slot, unix_time);
+    }
+
+    /// Interpolate between the nearest anchors, extrapolating at nominal slot time
+    pub fn time_at(&self, slot: u64) -> Option<i64> {
+        let before = self.anchors.range(..=slot).next_back();
+        let after = self.anchors.range(slot..).next();
+        match (before, after) {
+            (Some((&s0, &t0)), Some((&s1, &t1))) if s1 > s0 => {
+                Some(t0 + (t1 - t0) * (slot - s0) as i64 / (s1 - s0) as i64)
+            }
+            (Some((_, &t0)), Some(_)) => Some(t0),
+            (Some((&s0, &t0)), None) => Some(t0 + (slot - s0) as i64 * DEFAULT_SLOT_MS / 1000),
+            (None, Some((&s1, &t1))) => Some(t1 - (s1 - slot) as i64 * DEFAULT_SLOT_MS / 1000),
+            (None, None) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolApyReport {
+    pub pool: String,
+    pub from: i64,
+    pub to: i64,
+    pub emissions: u64,
+    /// Time-weighted average stake over the window
+    pub average_staked: f64,
+    /// Annualized realized return; `None` when nothing was staked
+    pub realized_apy: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RewardRecord {
+    pub slot: u64,
+    pub timestamp: i64,
+    pub pool: String,
+    pub amount: u64,
+}
+
+#[derive(Default)]
+pub struct RewardIndex {
+    clock: SlotClock,
+    /// Events per pool, in slot order
+    events: HashMap<Pubkey, Vec<IndexedEvent>>,
+    last_slot: u64,
+}
+
+impl RewardIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn last_slot(&self) -> u64 {
+        self.last_slot
+    }
+
+    pub fn record_block_time(&mut self, slot: u64, unix_time: i64) {
+        self.clock.record(slot, unix_time);
+    }
+
+    pub fn ingest(&mut self, event: IndexedEvent) {
+        self.last_slot = self.last_slot.max(event.slot);
+        let pool_events = self.events.entry(event.pool).or_default();
+        let idx = pool_events.partition_point(|e| e.slot <= event.slot);
+        pool_events.insert(idx, event);
+    }
+
+    /// Emissions and realized APY for a pool over `[from, to)` unix seconds
+    pub fn pool_apy(&self, pool: &Pubkey, from: i64, to: i64) -> PoolApyReport {
+        let mut emissions = 0u64;
+        let mut staked = 0u64;
+        let mut stake_seconds = 0f64;
+        let mut cursor = from;
+
+        for event in self.events.get(pool).into_iter().flatten() {
+            let Some(ts) = self.clock.time_at(event.slot) else { continue };
+            if ts >= to {
+                break;
+            }
+            if ts > cursor {
+                stake_seconds += staked as f64 * (ts - cursor) as f64;
+                cursor = ts;
+            }
+            match event.kind {
+                PoolEventKind::Staked { amount, .. } => staked += amount,
+                PoolEventKind::Unstaked { amount, .. } | PoolEventKind::Slashed { amount, .. } => {
+                    staked = staked.saturating_sub(amount)
+                }
+                PoolEventKind::RewardClaimed { amount, .. } if ts >= from => emissions += amount,
+                PoolEventKind::RewardClaimed { .. } => {}
+            }
+        }
+        if to > cursor {
+            stake_seconds += staked as f64 * (to - cursor) as f64;
+        }
+
+        let window = (to - from).max(1) as f64;
+        let average_staked = stake_seconds / window;
+        let realized_apy = (average_staked > 0.0)
+            .then(|| emissions as f64 / average_staked * SECONDS_PER_YEAR / window);
+
+        PoolApyReport {
+            pool: pool.to_string(),
+            from,
+            to,
+            emissions,
+            average_staked,
+            realized_apy,
+        }
+    }
+
+    /// Every reward claim by `user` across pools, oldest first
+    pub fn user_history(&self, user: &Pubkey) -> Vec<RewardRecord> {
+        let mut history: Vec<RewardRecord> = self
+            .events
+            .values()
+            .flatten()
+            .filter_map(|event| match event.kind {
+                PoolEventKind::RewardClaimed { user: u, amount } if u == *user => Some(RewardRecord {
+                    slot: event.slot,
+                    timestamp: self.clock.time_at(event.slot).unwrap_or_default(),
+                    pool: event.pool.to_string(),
+                    amount,
+                }),
+                _ => None,
+            })
+            .collect();
+        history.sort_by_key(|r| r.slot);
+        history
+    }
+
+    pub fn user_history_csv(&self, user: &Pubkey) -> String {
+        let mut csv = String::from("slot,timestamp,pool,amount\n");
+        for record in self.user_history(user) {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                record.slot, record.timestamp, record.pool, record.amount
+            ));
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_clock_interpolates() {
+        let mut clock = SlotClock::default();
+        clock.record(100, 1_000);
+        clock.record(200, 1_040);
+
+        assert_eq!(clock.time_at(150), Some(1_020));
+        assert_eq!(clock.time_at(250), Some(1_060));
+    }
+
+    #[test]
+    fn test_realized_apy_and_history() {
+        let pool = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let mut index = RewardIndex::new();
+        // One slot per second for simplicity
+        index.record_block_time(0, 0);
+        index.record_block_time(1_000_000, 1_000_000);
+
+        index.ingest(IndexedEvent {
+            slot: 0,
+            pool,
+            kind: PoolEventKind::Staked { user, amount: 1_000 },
+        });
+        index.ingest(IndexedEvent {
+            slot: 500_000,
+            pool,
+            kind: PoolEventKind::RewardClaimed { user, amount: 10 },
+        });
+
+        let report = index.pool_apy(&pool, 0, 1_000_000);
+        assert_eq!(report.emissions, 10);
+        assert_eq!(report.average_staked, 1_000.0);
+        let expected = 0.01 * SECONDS_PER_YEAR / 1_000_000.0;
+        assert!((report.realized_apy.unwrap() - expected).abs() < 1e-9);
+
+        let csv = index.user_history_csv(&user);
+        assert_eq!(csv, format!("slot,timestamp,pool,amount\n500000,500000,{},10\n", pool));
+    }
+
+    #[test]
+    fn test_decode_pool_events() {
+        use anchor_lang::Event;
+
+        let pool = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let data = vec![
+            PoolEvent::Staked { pool, user, amount: 500, timestamp: 1 }.data(),
+            PoolEvent::PoolPaused { pool, by: user, timestamp: 2 }.data(),
+            // Other events and truncated payloads are skipped
+            vec![1, 2, 3],
+            PoolEvent::RewardClaimed { pool, user, amount: 7, timestamp: 3 }.data(),
+        ];
+
+        let decoded = decode_pool_events(9, &data);
+        assert_eq!(decoded.len(), 2);
+        assert!(decoded.iter().all(|e| e.slot == 9 && e.pool == pool));
+        assert_eq!(decoded[0].kind, PoolEventKind::Staked { user, amount: 500 });
+        assert_eq!(decoded[1].kind, PoolEventKind::RewardClaimed { user, amount: 7 });
+    }
+}
@@ -0,0 +1,177 @@
+//! Per-worker inference latency SLO tracking and penalty triggers
+
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, VecDeque};
+
+/// Minimum samples before a worker can be penalized
+const MIN_SAMPLES: usize = 20;
+/// Scheduling weight never drops below this floor
+const MIN_WEIGHT: f32 = 0.1;
+/// Multiplier applied to a worker's weight on each breach
+const PENALTY_FACTOR: f32 = 0.5;
+/// Weight recovered per compliant task after a penalty
+const RECOVERY_STEP: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    latency_ms: u64,
+    met_slo: bool,
+}
+
+#[derive(Debug)]
+struct WorkerSlo {
+    samples: VecDeque<Sample>,
+    weight: f32,
+    breaches: u32,
+}
+
+impl Default for WorkerSlo {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::new(),
+            weight: 1.0,
+            breaches: 0,
+        }
+    }
+}
+
+impl WorkerSlo {
+    fn violation_ratio(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let violations = self.samples.iter().filter(|s| !s.met_slo).count();
+        violations as f32 / self.samples.len() as f32
+    }
+
+    fn p95_latency_ms(&self) -> u64 {
+        let mut latencies: Vec<u64> = self.samples.iter().map(|s| s.latency_ms).collect();
+        if latencies.is_empty() {
+            return 0;
+        }
+        latencies.sort_unstable();
+        let idx = ((latencies.len() as f64 * 0.95).ceil() as usize).saturating_sub(1);
+        latencies[idx]
+    }
+}
+
+/// Raised when a worker's violation ratio crosses the threshold
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloBreach {
+    pub worker: Pubkey,
+    pub violation_ratio: f32,
+    pub p95_latency_ms: u64,
+    /// Scheduling weight after the penalty
+    pub new_weight: f32,
+}
+
+/// Per-worker compliance summary exposed over the API
+#[derive(Debug, Clone, Serialize)]
+pub struct SloCompliance {
+    pub worker: String,
+    pub samples: usize,
+    pub compliance_ratio: f32,
+    pub p95_latency_ms: u64,
+    pub scheduling_weight: f32,
+    pub breaches: u32,
+}
+
+pub struct SloTracker {
+    window: usize,
+    violation_threshold: f32,
+    workers: HashMap<Pubkey, WorkerSlo>,
+}
+
+impl SloTracker {
+    pub fn new(window: usize, violation_threshold: f32) -> Self {
+        Self {
+            window: window.max(MIN_SAMPLES),
+            violation_threshold,
+            workers: HashMap::new(),
+        }
+    }
+
+    /// Record an end-to-end latency against the task's on-chain SLO
+    pub fn record(&mut self, worker: Pubkey, latency_ms: u64, slo_ms: u32) -> Option<SloBreach> {
+        let entry = self.workers.entry(worker).or_default();
+        let met_slo = latency_ms <= slo_ms as u64;
+
+        entry.samples.push_back(Sample { latency_ms, met_slo });
+        if entry.samples.len() > self.window {
+            entry.samples.pop_front();
+        }
+        if met_slo {
+            entry.weight = (entry.weight + RECOVERY_STEP).min(1.0);
+        }
+
+        if entry.samples.len() < MIN_SAMPLES || entry.violation_ratio() <= self.violation_threshold {
+            return None;
+        }
+
+        let breach = SloBreach {
+            worker,
+            violation_ratio: entry.violation_ratio(),
+            p95_latency_ms: entry.p95_latency_ms(),
+            new_weight: (entry.weight * PENALTY_FACTOR).max(MIN_WEIGHT),
+        };
+        entry.weight = breach.new_weight;
+        entry.breaches += 1;
+        // Start a fresh window so one bad streak triggers a single report
+        entry.samples.clear();
+
+        Some(breach)
+    }
+
+    /// Current scheduling weight multiplier for a worker
+    pub fn weight(&self, worker: &Pubkey) -> f32 {
+        self.workers.get(worker).map(|w| w.weight).unwrap_or(1.0)
+    }
+
+    pub fn compliance(&self) -> Vec<SloCompliance> {
+        self.workers
+            .iter()
+            .map(|(worker, slo)| SloCompliance {
+                worker: worker.to_string(),
+                samples: slo.samples.len(),
+                compliance_ratio: 1.0 - slo.violation_ratio(),
+                p95_latency_ms: slo.p95_latency_ms(),
+                scheduling_weight: slo.weight,
+                breaches: slo.breaches,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breach_penalizes_once_per_window() {
+        let mut tracker = SloTracker::new(20, 0.1);
+        let worker = Pubkey::new_unique();
+
+        let mut breaches = Vec::new();
+        for i in 0..20 {
+            let latency = if i % 4 == 0 { 5_000 } else { 500 };
+            breaches.extend(tracker.record(worker, latency, 2_000));
+        }
+
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].p95_latency_ms, 5_000);
+        assert!((tracker.weight(&worker) - 0.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_compliant_worker_keeps_full_weight() {
+        let mut tracker = SloTracker::new(50, 0.05);
+        let worker = Pubkey::new_unique();
+
+        for _ in 0..100 {
+            assert!(tracker.record(worker, 1_200, 2_000).is_none());
+        }
+        assert_eq!(tracker.weight(&worker), 1.0);
+        assert_eq!(tracker.compliance()[0].compliance_ratio, 1.0);
+    }
+}
@@ -0,0 +1,352 @@
+//! Soak mode: sustained synthetic FHE inference load for release qualification
+//! Tracks memory growth, GPU fragmentation, and queue latency over long runs
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc, Mutex};
+use tracing::{info, warn};
+
+/// Samples taken before this fraction of the run are excluded from leak detection
+const WARMUP_FRACTION: f64 = 0.1;
+/// Log2 latency buckets in milliseconds, covering up to ~18 hours
+const LATENCY_BUCKETS: usize = 26;
+
+/// Device memory as reported by the GPU allocator
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GpuMemoryStats {
+    pub free_bytes: u64,
+    /// Largest contiguous free block
+    pub largest_free_block: u64,
+}
+
+impl GpuMemoryStats {
+    /// 0.0 when all free memory is contiguous, approaching 1.0 as it splinters
+    pub fn fragmentation(&self) -> f64 {
+        if self.free_bytes == 0 {
+            return 0.0;
+        }
+        1.0 - self.largest_free_block as f64 / self.free_bytes as f64
+    }
+}
+
+/// Executor under test, fed with synthetic small-parameter FHE inferences
+#[async_trait]
+pub trait SoakTarget: Send + Sync + 'static {
+    async fn run_synthetic_inference(&self, seed: u64) -> anyhow::Result<()>;
+    fn gpu_memory(&self) -> Option<GpuMemoryStats>;
+}
+
+#[derive(Debug, Clone)]
+pub struct SoakConfig {
+    pub qps: f64,
+    pub duration: Duration,
+    pub concurrency: usize,
+    pub sample_interval: Duration,
+    /// Fail if resident memory grows faster than this (MiB per hour, post-warmup)
+    pub max_rss_growth_mb_per_hour: f64,
+    pub max_gpu_fragmentation: f64,
+    pub max_p99_queue_latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceSample {
+    pub elapsed_secs: f64,
+    pub rss_bytes: u64,
+    pub gpu_fragmentation: Option<f64>,
+    pub queue_depth: usize,
+}
+
+/// Log2-bucketed latency histogram, constant memory regardless of run length
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    buckets: [u64; LATENCY_BUCKETS],
+    count: u64,
+    max_ms: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; LATENCY_BUCKETS],
+            count: 0,
+            max_ms: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency_ms: u64) {
+        let idx = (64 - latency_ms.leading_zeros() as usize).min(LATENCY_BUCKETS - 1);
+        self.buckets[idx] += 1;
+        self.count += 1;
+        self.max_ms = self.max_ms.max(latency_ms);
+    }
+
+    /// Upper bound of the bucket containing the quantile
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let target = ((self.count as f64 * q).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (idx, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                let upper = if idx == 0 { 0 } else { (1u64 << idx) - 1 };
+                return upper.min(self.max_ms);
+            }
+        }
+        self.max_ms
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+impl From<&LatencyHistogram> for LatencySummary {
+    fn from(h: &LatencyHistogram) -> Self {
+        Self {
+            count: h.count,
+            p50_ms: h.quantile(0.50),
+            p95_ms: h.quantile(0.95),
+            p99_ms: h.quantile(0.99),
+            max_ms: h.max_ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakReport {
+    pub duration_secs: f64,
+    pub submitted: u64,
+    pub failed: u64,
+    pub queue_latency: LatencySummary,
+    pub execution_latency: LatencySummary,
+    pub rss_growth_mb_per_hour: f64,
+    pub peak_gpu_fragmentation: Option<f64>,
+    pub samples: Vec<ResourceSample>,
+    /// Thresholds exceeded; empty when the run qualifies
+    pub violations: Vec<String>,
+}
+
+impl SoakReport {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Least-squares RSS slope over post-warmup samples, in MiB per hour
+pub fn rss_growth_mb_per_hour(samples: &[ResourceSample]) -> f64 {
+    let Some(last) = samples.last() else { return 0.0 };
+    let warmup = last.elapsed_secs * WARMUP_FRACTION;
+    let points: Vec<(f64, f64)> = samples
+        .iter()
+        .filter(|s| s.elapsed_secs >= warmup)
+        .map(|s| (s.elapsed_secs, s.rss_bytes as f64))
+        .collect();
+    if points.len() < 2 {
+        return 0.0;
+    }
+
+    let n = points.len() as f64;
+    let mean_t = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_m = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let cov: f64 = points.iter().map(|(t, m)| (t - mean_t) * (m - mean_m)).sum();
+    let var: f64 = points.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+    if var == 0.0 {
+        return 0.0;
+    }
+    cov / var * 3600.0 / (1024.0 * 1024.0)
+}
+
+/// Resident set size of this process from /proc
+fn current_rss_bytes() -> u64 {
+    std::fs::read_to_string("/proc/self/statm")
+        .ok()
+        .and_then(|s| s.split_whitespace().nth(1)?.parse::<u64>().ok())
+        .map(|pages| pages * 4096)
+        .unwrap_or(0)
+}
+
+#[derive(Default)]
+struct SoakStats {
+    submitted: u64,
+    failed: u64,
+    queue_latency: LatencyHistogram,
+    execution_latency: LatencyHistogram,
+}
+
+pub struct SoakRunner<T: SoakTarget> {
+    target: Arc<T>,
+    config: SoakConfig,
+}
+
+impl<T: SoakTarget> SoakRunner<T> {
+    pub fn new(target: Arc<T>, config: SoakConfig) -> Self {
+        Self { target, config }
+    }
+
+    pub async fn run(self) -> SoakReport {
+        let started = Instant::now();
+        let stats = Arc::new(Mutex::new(SoakStats::default()));
+        let (tx, rx) = mpsc::unbounded_channel::<(u64, Instant)>();
+        let rx = Arc::new(Mutex::new(rx));
+
+        let mut workers = Vec::with_capacity(self.config.concurrency);
+        for _ in 0..self.config.concurrency.max(1) {
+            let (rx, stats, target) = (rx.clone(), stats.clone(), self.target.clone());
+            workers.push(tokio::spawn(async move {
+                loop {
+                    let Some((seed, enqueued)) = rx.lock().await.recv().await else { break };
+                    let queued = enqueued.elapsed();
+                    let exec_start = Instant::now();
+                    let result = target.run_synthetic_inference(seed).await;
+
+                    let mut stats = stats.lock().await;
+                    stats.queue_latency.record(queued.as_millis() as u64);
+                    stats.execution_latency.record(exec_start.elapsed().as_millis() as u64);
+                    if let Err(e) = result {
+                        stats.failed += 1;
+                        warn!(seed, error = %e, "Synthetic inference failed");
+                    }
+                }
+            }));
+        }
+
+        let mut generate = tokio::time::interval(Duration::from_secs_f64(1.0 / self.config.qps.max(0.001)));
+        generate.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Burst);
+        let mut sample = tokio::time::interval(self.config.sample_interval);
+        let mut samples = Vec::new();
+        let mut seed = 0u64;
+
+        while started.elapsed() < self.config.duration {
+            tokio::select! {
+                _ = generate.tick() => {
+                    seed += 1;
+                    let _ = tx.send((seed, Instant::now()));
+                    stats.lock().await.submitted += 1;
+                }
+                _ = sample.tick() => {
+                    let completed = {
+                        let stats = stats.lock().await;
+                        stats.execution_latency.count()
+                    };
+                    let s = ResourceSample {
+                        elapsed_secs: started.elapsed().as_secs_f64(),
+                        rss_bytes: current_rss_bytes(),
+                        gpu_fragmentation: self.target.gpu_memory().map(|m| m.fragmentation()),
+                        queue_depth: (seed - completed) as usize,
+                    };
+                    info!(
+                        elapsed_secs = s.elapsed_secs,
+                        rss_mb = s.rss_bytes / (1024 * 1024),
+                        queue_depth = s.queue_depth,
+                        "Soak sample"
+                    );
+                    samples.push(s);
+                }
+            }
+        }
+
+        // Let in-flight work drain so latencies cover every submitted task
+        drop(tx);
+        for worker in workers {
+            let _ = worker.await;
+        }
+
+        let stats = stats.lock().await;
+        self.report(started.elapsed(), &stats, samples)
+    }
+
+    fn report(&self, elapsed: Duration, stats: &SoakStats, samples: Vec<ResourceSample>) -> SoakReport {
+        let growth = rss_growth_mb_per_hour(&samples);
+        let peak_fragmentation = samples
+            .iter()
+            .filter_map(|s| s.gpu_fragmentation)
+            .fold(None, |peak: Option<f64>, f| Some(peak.map_or(f, |p| p.max(f))));
+        let queue_latency = LatencySummary::from(&stats.queue_latency);
+
+        let mut violations = Vec::new();
+        if growth > self.config.max_rss_growth_mb_per_hour {
+            violations.push(format!(
+                "RSS grew {growth:.1} MiB/h (limit {:.1})",
+                self.config.max_rss_growth_mb_per_hour
+            ));
+        }
+        if let Some(peak) = peak_fragmentation.filter(|p| *p > self.config.max_gpu_fragmentation) {
+            violations.push(format!(
+                "GPU fragmentation reached {peak:.2} (limit {:.2})",
+                self.config.max_gpu_fragmentation
+            ));
+        }
+        if queue_latency.p99_ms > self.config.max_p99_queue_latency_ms {
+            violations.push(format!(
+                "p99 queue latency {}ms (limit {}ms)",
+                queue_latency.p99_ms, self.config.max_p99_queue_latency_ms
+            ));
+        }
+        if stats.failed > 0 {
+            violations.push(format!("{} of {} inferences failed", stats.failed, stats.submitted));
+        }
+
+        SoakReport {
+            duration_secs: elapsed.as_secs_f64(),
+            submitted: stats.submitted,
+            failed: stats.failed,
+            queue_latency,
+            execution_latency: LatencySummary::from(&stats.execution_latency),
+            rss_growth_mb_per_hour: growth,
+            peak_gpu_fragmentation: peak_fragmentation,
+            samples,
+            violations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(elapsed_secs: f64, rss_mb: u64) -> ResourceSample {
+        ResourceSample {
+            elapsed_secs,
+            rss_bytes: rss_mb * 1024 * 1024,
+            gpu_fragmentation: None,
+            queue_depth: 0,
+        }
+    }
+
+    #[test]
+    fn test_rss_growth_ignores_warmup() {
+        // Large allocation during warmup, then a steady 10 MiB/h leak
+        let mut samples = vec![sample(0.0, 100), sample(300.0, 900)];
+        samples.extend((1..=10).map(|h| sample(h as f64 * 3600.0, 1_000 + h * 10)));
+        let growth = rss_growth_mb_per_hour(&samples);
+        assert!((growth - 10.0).abs() < 0.01, "growth {growth}");
+    }
+
+    #[test]
+    fn test_latency_quantiles() {
+        let mut h = LatencyHistogram::default();
+        for ms in 1..=100 {
+            h.record(ms);
+        }
+        assert_eq!(h.quantile(0.5), 63);
+        assert_eq!(h.quantile(0.99), 100);
+        assert_eq!(h.count(), 100);
+    }
+}
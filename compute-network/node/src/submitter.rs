@@ -0,0 +1,336 @@
+//! Prioritized transaction submission
+//!
+//! Everything the coordinator signs goes through one submitter with three
+//! lanes. Slashing and dispute transactions carry deadlines the protocol
+//! enforces, so under congestion they must not queue behind a backlog of proof
+//! uploads. Each lane has its own compute-unit price and in-flight budget, and a
+//! lane only dispatches while every lane above it is empty: the critical lane
+//! always drains first, and bulk traffic only uses capacity nothing else wants.
+
+use anchor_lang::Discriminator;
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use std::{collections::VecDeque, sync::Arc};
+use thiserror::Error;
+use tokio::sync::{oneshot, Mutex, Notify};
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+/// Submission lanes, highest priority first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lane {
+    Critical = 0,
+    Normal = 1,
+    Bulk = 2,
+}
+
+impl Lane {
+    pub const ALL: [Lane; 3] = [Lane::Critical, Lane::Normal, Lane::Bulk];
+}
+
+/// What an outgoing transaction does, as far as prioritization cares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxKind {
+    /// Stake slashing
+    Slash,
+    /// Challenging or arbitrating a provisional result
+    Dispute,
+    /// Taking a stale task back from its worker
+    Reassign,
+    /// Expiring tasks and reporting timeouts
+    Expiry,
+    /// Proof verification and submission
+    Proof,
+    /// Recording where a proof was archived
+    ArchiveRecord,
+    Other,
+}
+
+impl TxKind {
+    pub fn lane(self) -> Lane {
+        match self {
+            TxKind::Slash | TxKind::Dispute | TxKind::Reassign => Lane::Critical,
+            TxKind::Expiry | TxKind::Other => Lane::Normal,
+            TxKind::Proof | TxKind::ArchiveRecord => Lane::Bulk,
+        }
+    }
+
+    /// Kind of a single instruction, from its program and discriminator
+    pub fn of(ix: &Instruction) -> Self {
+        let is = |program: Pubkey, discriminator: [u8; 8]| {
+            ix.program_id == program && ix.data.starts_with(&discriminator)
+        };
+        if is(token_vault::ID, token_vault::instruction::SlashStake::DISCRIMINATOR)
+            || is(token_vault::ID, token_vault::instruction::SlashDelegationPool::DISCRIMINATOR)
+        {
+            TxKind::Slash
+        } else if is(haunti_core::ID, haunti_core::instruction::ChallengeResult::DISCRIMINATOR)
+            || is(haunti_core::ID, haunti_core::instruction::ArbitrateDispute::DISCRIMINATOR)
+        {
+            TxKind::Dispute
+        } else if is(haunti_core::ID, haunti_core::instruction::ReassignStaleTask::DISCRIMINATOR) {
+            TxKind::Reassign
+        } else if is(haunti_core::ID, haunti_core::instruction::ExpireTask::DISCRIMINATOR)
+            || is(haunti_core::ID, haunti_core::instruction::ReportTimeout::DISCRIMINATOR)
+        {
+            TxKind::Expiry
+        } else if is(haunti_core::ID, haunti_core::instruction::SubmitProof::DISCRIMINATOR)
+            || [
+                solana_verifier::instruction::VerifyAiProof::DISCRIMINATOR,
+                solana_verifier::instruction::InitProofBuffer::DISCRIMINATOR,
+                solana_verifier::instruction::WriteProofChunk::DISCRIMINATOR,
+                solana_verifier::instruction::FinalizeProof::DISCRIMINATOR,
+            ]
+            .iter()
+            .any(|discriminator| ix.data.starts_with(discriminator))
+        {
+            TxKind::Proof
+        } else if ix.data.starts_with(&solana_verifier::instruction::RecordProofArchive::DISCRIMINATOR) {
+            TxKind::ArchiveRecord
+        } else {
+            TxKind::Other
+        }
+    }
+
+    /// Lane of a transaction: that of its most urgent instruction
+    pub fn classify(instructions: &[Instruction]) -> Lane {
+        instructions
+            .iter()
+            .map(|ix| Self::of(ix).lane())
+            .min_by_key(|lane| *lane as usize)
+            .unwrap_or(Lane::Normal)
+    }
+}
+
+/// Fee and concurrency of one lane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LanePolicy {
+    /// Compute-unit price attached to every transaction, in micro-lamports
+    pub priority_fee_micro_lamports: u64,
+    /// Transactions of the lane awaiting confirmation at once
+    pub max_in_flight: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LanePolicies {
+    pub critical: LanePolicy,
+    pub normal: LanePolicy,
+    pub bulk: LanePolicy,
+}
+
+impl LanePolicies {
+    pub fn get(&self, lane: Lane) -> LanePolicy {
+        match lane {
+            Lane::Critical => self.critical,
+            Lane::Normal => self.normal,
+            Lane::Bulk => self.bulk,
+        }
+    }
+}
+
+/// Pending transactions per lane and how many of each are in flight
+#[derive(Debug)]
+struct LaneQueues<T> {
+    pending: [VecDeque<T>; 3],
+    in_flight: [usize; 3],
+}
+
+impl<T> LaneQueues<T> {
+    fn new() -> Self {
+        Self {
+            pending: Default::default(),
+            in_flight: [0; 3],
+        }
+    }
+
+    fn push(&mut self, lane: Lane, item: T) {
+        self.pending[lane as usize].push_back(item);
+    }
+
+    /// Next item to dispatch. A lane waits while any lane above it has queued
+    /// items, even if that lane is at its in-flight budget
+    fn next(&mut self, policies: &LanePolicies) -> Option<(Lane, T)> {
+        for lane in Lane::ALL {
+            let index = lane as usize;
+            if self.pending[index].is_empty() {
+                continue;
+            }
+            if self.in_flight[index] >= policies.get(lane).max_in_flight.max(1) {
+                return None;
+            }
+            let item = self.pending[index].pop_front()?;
+            self.in_flight[index] += 1;
+            return Some((lane, item));
+        }
+        None
+    }
+
+    fn finish(&mut self, lane: Lane) {
+        self.in_flight[lane as usize] = self.in_flight[lane as usize].saturating_sub(1);
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SubmitError {
+    #[error("RPC error: {0}")]
+    Rpc(#[from] ClientError),
+    #[error("Submitter stopped before the transaction was sent")]
+    Stopped,
+}
+
+struct Job {
+    instructions: Vec<Instruction>,
+    done: oneshot::Sender<Result<Signature, SubmitError>>,
+}
+
+/// Signs and sends the coordinator's transactions lane by lane
+pub struct TxSubmitter {
+    rpc: Arc<RpcClient>,
+    payer: Arc<Keypair>,
+    policies: LanePolicies,
+    queues: Mutex<LaneQueues<Job>>,
+    wake: Notify,
+}
+
+impl TxSubmitter {
+    pub fn new(rpc: Arc<RpcClient>, payer: Arc<Keypair>, policies: LanePolicies) -> Self {
+        Self {
+            rpc,
+            payer,
+            policies,
+            queues: Mutex::new(LaneQueues::new()),
+            wake: Notify::new(),
+        }
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    /// Queue `instructions` in the lane of their most urgent instruction and
+    /// wait for confirmation
+    pub async fn submit(&self, instructions: Vec<Instruction>) -> Result<Signature, SubmitError> {
+        let lane = TxKind::classify(&instructions);
+        let (done, confirmed) = oneshot::channel();
+        self.queues.lock().await.push(lane, Job { instructions, done });
+        self.wake.notify_one();
+        confirmed.await.map_err(|_| SubmitError::Stopped)?
+    }
+
+    /// Dispatch queued transactions until `cancel` fires
+    pub async fn run(self: Arc<Self>, cancel: CancellationToken) -> anyhow::Result<()> {
+        loop {
+            let next = self.queues.lock().await.next(&self.policies);
+            let Some((lane, job)) = next else {
+                tokio::select! {
+                    _ = self.wake.notified() => continue,
+                    _ = cancel.cancelled() => return Ok(()),
+                }
+            };
+
+            let submitter = self.clone();
+            tokio::spawn(async move {
+                let result = submitter.send(lane, job.instructions).await;
+                submitter.queues.lock().await.finish(lane);
+                submitter.wake.notify_one();
+                let _ = job.done.send(result);
+            });
+        }
+    }
+
+    async fn send(&self, lane: Lane, instructions: Vec<Instruction>) -> Result<Signature, SubmitError> {
+        let fee = self.policies.get(lane).priority_fee_micro_lamports;
+        let mut all = Vec::with_capacity(instructions.len() + 1);
+        if fee > 0 {
+            all.push(ComputeBudgetInstruction::set_compute_unit_price(fee));
+        }
+        all.extend(instructions);
+
+        let blockhash = self.rpc.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &all,
+            Some(&self.payer.pubkey()),
+            &[self.payer.as_ref()],
+            blockhash,
+        );
+        debug!(?lane, fee, "Sending transaction");
+        Ok(self.rpc.send_and_confirm_transaction(&transaction).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policies(max_in_flight: usize) -> LanePolicies {
+        let policy = |fee| LanePolicy { priority_fee_micro_lamports: fee, max_in_flight };
+        LanePolicies {
+            critical: policy(100_000),
+            normal: policy(10_000),
+            bulk: policy(0),
+        }
+    }
+
+    #[test]
+    fn test_critical_lane_drains_first() {
+        let policies = policies(4);
+        let mut queues = LaneQueues::new();
+        queues.push(Lane::Bulk, "proof-1");
+        queues.push(Lane::Bulk, "proof-2");
+        queues.push(Lane::Normal, "expire");
+        queues.push(Lane::Critical, "slash");
+        queues.push(Lane::Critical, "arbitrate");
+
+        let order: Vec<&str> = std::iter::from_fn(|| queues.next(&policies).map(|(_, t)| t)).collect();
+        assert_eq!(order, ["slash", "arbitrate", "expire", "proof-1", "proof-2"]);
+    }
+
+    #[test]
+    fn test_lower_lanes_wait_for_saturated_critical_lane() {
+        let policies = policies(1);
+        let mut queues = LaneQueues::new();
+        queues.push(Lane::Critical, "slash-1");
+        queues.push(Lane::Critical, "slash-2");
+        queues.push(Lane::Bulk, "proof");
+
+        assert_eq!(queues.next(&policies), Some((Lane::Critical, "slash-1")));
+        // slash-2 is over budget, and the bulk lane may not jump ahead of it
+        assert_eq!(queues.next(&policies), None);
+        queues.finish(Lane::Critical);
+        assert_eq!(queues.next(&policies), Some((Lane::Critical, "slash-2")));
+        // With the critical queue empty, other lanes use their own budgets
+        assert_eq!(queues.next(&policies), Some((Lane::Bulk, "proof")));
+    }
+
+    #[test]
+    fn test_transactions_take_their_most_urgent_lane() {
+        let ix = |program_id, discriminator: [u8; 8]| Instruction {
+            program_id,
+            accounts: vec![],
+            data: discriminator.to_vec(),
+        };
+        let slash = ix(token_vault::ID, token_vault::instruction::SlashStake::DISCRIMINATOR);
+        let proof = ix(
+            Pubkey::new_unique(),
+            solana_verifier::instruction::VerifyAiProof::DISCRIMINATOR,
+        );
+        let chunk = ix(
+            Pubkey::new_unique(),
+            solana_verifier::instruction::WriteProofChunk::DISCRIMINATOR,
+        );
+        let other = ix(Pubkey::new_unique(), [0; 8]);
+
+        assert_eq!(TxKind::of(&slash), TxKind::Slash);
+        assert_eq!(TxKind::classify(&[proof.clone()]), Lane::Bulk);
+        assert_eq!(TxKind::of(&chunk), TxKind::Proof);
+        assert_eq!(TxKind::classify(&[proof, slash]), Lane::Critical);
+        assert_eq!(TxKind::classify(&[other]), Lane::Normal);
+        assert_eq!(TxKind::classify(&[]), Lane::Normal);
+    }
+}
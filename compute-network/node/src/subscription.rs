@@ -0,0 +1,340 @@
+//! Self-healing program log subscriptions
+//! Detects silent websocket drops, resubscribes, and backfills missed slots.
+//! The last delivered transaction is checkpointed to disk, so a restarted
+//! coordinator backfills whatever landed while it was down.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use std::{
+    collections::{HashSet, VecDeque},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+/// Signatures remembered for de-duplicating live and backfilled logs
+const SEEN_CAPACITY: usize = 10_000;
+/// Upper bound on signatures fetched per backfill page
+const BACKFILL_PAGE: usize = 1_000;
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Program logs from one transaction
+#[derive(Debug, Clone)]
+pub struct LogEvent {
+    pub slot: u64,
+    pub signature: String,
+    pub logs: Vec<String>,
+}
+
+/// Decoded `Program data:` lines (Anchor `emit!` events) logged while `program`
+/// itself was executing, skipping those of programs it invoked or was invoked by
+pub fn program_data(program: &Pubkey, logs: &[String]) -> Vec<Vec<u8>> {
+    let program = program.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut data = Vec::new();
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(encoded) = rest.strip_prefix("data: ") {
+            if stack.last() == Some(&program.as_str()) {
+                if let Ok(bytes) = STANDARD.decode(encoded) {
+                    data.push(bytes);
+                }
+            }
+        } else if let Some((id, outcome)) = rest.split_once(' ') {
+            if outcome.starts_with("invoke [") {
+                stack.push(id);
+            } else if outcome == "success" || outcome.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+    data
+}
+
+/// Last delivered transaction, persisted across restarts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Checkpoint {
+    slot: u64,
+    signature: String,
+}
+
+impl Checkpoint {
+    fn load(path: &PathBuf) -> anyhow::Result<Option<Self>> {
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write-then-rename so a crash never leaves a torn checkpoint
+    fn store(&self, path: &PathBuf) -> anyhow::Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Bounded FIFO set of processed signatures
+#[derive(Debug, Default)]
+struct SeenSignatures {
+    set: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl SeenSignatures {
+    /// Returns false if the signature was already delivered
+    fn insert(&mut self, signature: &str) -> bool {
+        if !self.set.insert(signature.to_string()) {
+            return false;
+        }
+        self.order.push_back(signature.to_string());
+        if self.order.len() > SEEN_CAPACITY {
+            if let Some(evicted) = self.order.pop_front() {
+                self.set.remove(&evicted);
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionConfig {
+    pub ws_url: String,
+    pub program: Pubkey,
+    /// Resubscribe when no message arrives within this window
+    pub heartbeat_timeout: Duration,
+    /// Where the last delivered transaction is persisted; `None` starts live
+    /// with no backfill on every start
+    pub checkpoint: Option<PathBuf>,
+}
+
+/// Program log subscription that survives dropped websockets without losing events
+pub struct ResilientSubscription {
+    config: SubscriptionConfig,
+    rpc: Arc<RpcClient>,
+    seen: SeenSignatures,
+    last_signature: Option<String>,
+    last_slot: u64,
+}
+
+impl ResilientSubscription {
+    /// Resume from the persisted checkpoint, if any, so the first backfill
+    /// covers the time the coordinator was down
+    pub fn new(config: SubscriptionConfig, rpc: Arc<RpcClient>) -> anyhow::Result<Self> {
+        let checkpoint = match &config.checkpoint {
+            Some(path) => Checkpoint::load(path)?,
+            None => None,
+        };
+        if let Some(checkpoint) = &checkpoint {
+            info!(program = %config.program, slot = checkpoint.slot, "Resuming log subscription from checkpoint");
+        }
+        Ok(Self {
+            config,
+            rpc,
+            seen: SeenSignatures::default(),
+            last_signature: checkpoint.as_ref().map(|c| c.signature.clone()),
+            last_slot: checkpoint.map_or(0, |c| c.slot),
+        })
+    }
+
+    /// Spawn the subscription, delivering de-duplicated events in order
+    pub fn spawn(self) -> mpsc::Receiver<LogEvent> {
+        let (tx, rx) = mpsc::channel(1_024);
+        tokio::spawn(self.run(tx));
+        rx
+    }
+
+    async fn run(mut self, tx: mpsc::Sender<LogEvent>) {
+        loop {
+            // Heal any gap left by the previous connection before going live
+            if let Err(e) = self.backfill(&tx).await {
+                warn!(program = %self.config.program, error = %e, "Log backfill failed");
+            }
+            match self.stream(&tx).await {
+                Ok(()) => warn!(program = %self.config.program, "Log subscription went silent, resubscribing"),
+                Err(e) => warn!(program = %self.config.program, error = %e, "Log subscription dropped"),
+            }
+            if tx.is_closed() {
+                return;
+            }
+            tokio::time::sleep(RECONNECT_BACKOFF).await;
+        }
+    }
+
+    async fn stream(&mut self, tx: &mpsc::Sender<LogEvent>) -> anyhow::Result<()> {
+        let client = PubsubClient::new(&self.config.ws_url).await?;
+        let (mut notifications, unsubscribe) = client
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![self.config.program.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await?;
+        info!(program = %self.config.program, "Log subscription established");
+
+        // A quiet socket is indistinguishable from a dead one, so treat silence as a drop
+        while let Ok(Some(notification)) =
+            tokio::time::timeout(self.config.heartbeat_timeout, notifications.next()).await
+        {
+            if notification.value.err.is_some() {
+                continue;
+            }
+            let event = LogEvent {
+                slot: notification.context.slot,
+                signature: notification.value.signature,
+                logs: notification.value.logs,
+            };
+            if !self.deliver(tx, event).await {
+                break;
+            }
+        }
+
+        unsubscribe().await;
+        Ok(())
+    }
+
+    /// Replay transactions newer than the last delivered one (this run's or the
+    /// checkpointed one) via getSignaturesForAddress
+    async fn backfill(&mut self, tx: &mpsc::Sender<LogEvent>) -> anyhow::Result<()> {
+        let Some(until) = self.last_signature.clone() else {
+            return Ok(());
+        };
+
+        let mut missed = Vec::new();
+        let mut before = None;
+        loop {
+            let page = self
+                .rpc
+                .get_signatures_for_address_with_config(
+                    &self.config.program,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: Some(Signature::from_str(&until)?),
+                        limit: Some(BACKFILL_PAGE),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    },
+                )
+                .await?;
+            let full_page = page.len() == BACKFILL_PAGE;
+            before = page.last().map(|s| Signature::from_str(&s.signature)).transpose()?;
+            missed.extend(page.into_iter().filter(|s| s.err.is_none()));
+            if !full_page {
+                break;
+            }
+        }
+
+        if !missed.is_empty() {
+            info!(program = %self.config.program, count = missed.len(), "Backfilling missed transactions");
+        }
+        // Signatures come newest first; deliver oldest first
+        for status in missed.into_iter().rev() {
+            let signature = Signature::from_str(&status.signature)?;
+            let tx_info = self
+                .rpc
+                .get_transaction(&signature, UiTransactionEncoding::Json)
+                .await?;
+            let logs = tx_info
+                .transaction
+                .meta
+                .and_then(|meta| Option::<Vec<String>>::from(meta.log_messages))
+                .unwrap_or_default();
+            let event = LogEvent {
+                slot: status.slot,
+                signature: status.signature,
+                logs,
+            };
+            if !self.deliver(tx, event).await {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Send unless already delivered; returns false once the receiver is gone
+    async fn deliver(&mut self, tx: &mpsc::Sender<LogEvent>, event: LogEvent) -> bool {
+        if !self.seen.insert(&event.signature) {
+            return true;
+        }
+        self.last_slot = self.last_slot.max(event.slot);
+        self.last_signature = Some(event.signature.clone());
+        let checkpoint = Checkpoint {
+            slot: event.slot,
+            signature: event.signature.clone(),
+        };
+        if tx.send(event).await.is_err() {
+            return false;
+        }
+        if let Some(path) = &self.config.checkpoint {
+            if let Err(e) = checkpoint.store(path) {
+                warn!(program = %self.config.program, error = %e, "Failed to persist subscription checkpoint");
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seen_signatures_dedupe_and_evict() {
+        let mut seen = SeenSignatures::default();
+        assert!(seen.insert("a"));
+        assert!(!seen.insert("a"));
+
+        for i in 0..SEEN_CAPACITY {
+            seen.insert(&i.to_string());
+        }
+        // "a" was evicted and would be delivered again
+        assert!(seen.insert("a"));
+        assert_eq!(seen.order.len(), SEEN_CAPACITY);
+    }
+
+    #[test]
+    fn test_checkpoint_survives_restart() {
+        let path = std::env::temp_dir().join(format!("haunti-subscription-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(Checkpoint::load(&path).unwrap(), None);
+
+        let checkpoint = Checkpoint { slot: 42, signature: "sig".into() };
+        checkpoint.store(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), Some(checkpoint));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_program_data_only_from_program_frames() {
+        let program = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let logs: Vec<String> = vec![
+            format!("Program {} invoke [1]", other),
+            format!("Program {} invoke [2]", program),
+            "Program data: AQI=".into(),
+            format!("Program {} success", program),
+            "Program data: AwQ=".into(),
+            format!("Program {} success", other),
+            format!("Program {} invoke [1]", program),
+            "Program log: not data".into(),
+            "Program data: BQ==".into(),
+            format!("Program {} failed: custom program error: 0x1", program),
+        ];
+        assert_eq!(program_data(&program, &logs), vec![vec![1, 2], vec![5]]);
+    }
+}
@@ -0,0 +1,109 @@
+//! Follows haunti-core's sequenced task feed and reports gaps
+//!
+//! `TaskFeedEvent`s are emitted to the program log, which a websocket can drop
+//! and the runtime can truncate. Each carries the next value of the on-chain
+//! `EventCounter`, so the tracker starts from the counter and flags every
+//! sequence it skipped, along with the slot to replay from.
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use haunti_core::{EventCounter, FeedCursor, FeedGap, TaskFeedEvent};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+/// Feed events in a transaction's logs, in emission order
+pub fn decode_logs(logs: &[String]) -> Vec<TaskFeedEvent> {
+    logs.iter()
+        .filter_map(|line| line.strip_prefix(PROGRAM_DATA))
+        .filter_map(|data| STANDARD.decode(data).ok())
+        .filter_map(|data| {
+            let payload = data.strip_prefix(TaskFeedEvent::DISCRIMINATOR.as_slice())?;
+            TaskFeedEvent::deserialize(&mut &payload[..]).ok()
+        })
+        .collect()
+}
+
+/// Position in the feed plus the slot of the last contiguous event
+#[derive(Debug, Default)]
+pub struct FeedTracker {
+    cursor: FeedCursor,
+    /// Slot of the last event observed without a gap before it; a reported gap
+    /// lies between this slot and the one that revealed it
+    pub trusted_slot: u64,
+}
+
+impl FeedTracker {
+    /// Start at the counter's current position, so events emitted before the
+    /// coordinator started are not reported missing
+    pub async fn resume(rpc: &RpcClient) -> anyhow::Result<Self> {
+        let (address, _) = Pubkey::find_program_address(&[b"event_counter"], &haunti_core::ID);
+        let data = rpc.get_account_data(&address).await?;
+        let counter = EventCounter::try_deserialize(&mut data.as_slice())?;
+        Ok(Self {
+            cursor: FeedCursor { sequence: counter.sequence },
+            trusted_slot: counter.last_slot,
+        })
+    }
+
+    /// Record the events of a transaction landed at `slot`; returns the gaps
+    /// they revealed
+    pub fn observe(&mut self, slot: u64, events: &[TaskFeedEvent]) -> Vec<FeedGap> {
+        let mut gaps = Vec::new();
+        for event in events {
+            match self.cursor.observe(event.sequence) {
+                Ok(true) if gaps.is_empty() => self.trusted_slot = slot,
+                Ok(_) => {}
+                Err(gap) => gaps.push(gap),
+            }
+        }
+        gaps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Event;
+    use haunti_core::TaskFeedKind;
+
+    fn event(sequence: u64) -> TaskFeedEvent {
+        TaskFeedEvent {
+            sequence,
+            task: Pubkey::new_unique(),
+            kind: TaskFeedKind::Heartbeat,
+            actor: Pubkey::new_unique(),
+            amount: 0,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_feed_events_decoded_from_program_data() {
+        let logs = vec![
+            "Program log: Instruction: HeartbeatTask".to_string(),
+            format!("{}{}", PROGRAM_DATA, STANDARD.encode(event(7).data())),
+            format!("{}{}", PROGRAM_DATA, STANDARD.encode([0u8; 16])),
+        ];
+        let events = decode_logs(&logs);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sequence, 7);
+    }
+
+    #[test]
+    fn test_gaps_reported_with_last_trusted_slot() {
+        let mut tracker = FeedTracker::default();
+        assert!(tracker.observe(10, &[event(1), event(2)]).is_empty());
+        assert_eq!(tracker.trusted_slot, 10);
+
+        let gaps = tracker.observe(12, &[event(5), event(6)]);
+        assert_eq!(gaps, vec![FeedGap { from: 3, to: 4 }]);
+        assert_eq!(tracker.trusted_slot, 10);
+
+        // Replayed events behind the cursor are ignored
+        assert!(tracker.observe(11, &[event(3)]).is_empty());
+        assert!(tracker.observe(13, &[event(7)]).is_empty());
+        assert_eq!(tracker.trusted_slot, 13);
+    }
+}
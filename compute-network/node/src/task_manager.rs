@@ -14,6 +14,11 @@ use tokio::{
 };
 use thiserror::Error;
 
+use crate::{
+    error::NodeError,
+    tenancy::{TenantError, TenantRegistry},
+};
+
 /// Priority levels for compute tasks
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum TaskPriority {
@@ -31,6 +36,15 @@ pub struct ResourceRequirements {
     pub memory_gb: u8,
     pub storage_gb: u8,
     pub timeout_secs: u32,
+    /// Preferred worker regions, empty means any
+    #[serde(default)]
+    pub preferred_regions: Vec<String>,
+    /// Maximum probed round-trip time to the worker
+    #[serde(default)]
+    pub max_rtt_ms: Option<u32>,
+    /// Model owner only releases keys to attested enclaves
+    #[serde(default)]
+    pub require_tee: bool,
 }
 
 /// Task status lifecycle
@@ -57,6 +71,25 @@ pub struct ComputeTask {
     pub task_type: TaskType,
     pub model_cid: String,
     pub data_cid: String,
+    /// Owning tenant namespace on shared coordinators
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// Lamports escrowed on top of the reward for earlier pickup
+    #[serde(default)]
+    pub priority_fee: u64,
+    /// Unix seconds the owner needs the result by
+    #[serde(default)]
+    pub deadline: Option<u64>,
+}
+
+impl ComputeTask {
+    /// Latest unix second the task can start and still meet its deadline if
+    /// it runs to its timeout. The clock is the same for every queued task, so
+    /// ordering by this orders by deadline slack
+    pub fn latest_start(&self) -> Option<u64> {
+        self.deadline
+            .map(|deadline| deadline.saturating_sub(self.requirements.timeout_secs as u64))
+    }
 }
 
 /// Types of AI tasks supported
@@ -95,6 +128,7 @@ pub struct TaskManager {
     pending_queue: Arc<Mutex<BinaryHeap<Arc<ComputeTask>>>>,
     running_tasks: Arc<RwLock<HashMap<String, Arc<ComputeTask>>>>,
     resource_pool: Arc<RwLock<ResourcePool>>,
+    tenants: Option<Arc<TenantRegistry>>,
 }
 
 #[derive(Error, Debug)]
@@ -109,6 +143,8 @@ pub enum TaskManagerError {
     RpcError(#[from] solana_client::client_error::ClientError),
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
+    #[error("Tenant error: {0}")]
+    Tenant(#[from] TenantError),
 }
 
 impl TaskManager {
@@ -127,11 +163,31 @@ impl TaskManager {
                 available_memory_gb: 0,
                 total_memory_gb: 0,
             })),
+            tenants: None,
+        }
+    }
+
+    /// Enforce tenant namespaces and quotas on this manager
+    pub fn with_tenants(mut self, tenants: Arc<TenantRegistry>) -> Self {
+        self.tenants = Some(tenants);
+        self
+    }
+
+    /// Drive scheduling and timeout monitoring until scheduling fails
+    pub async fn run(self: Arc<Self>) -> anyhow::Result<()> {
+        tokio::select! {
+            result = self.schedule_tasks() => result?,
+            _ = self.monitor_timeouts() => {}
         }
+        Ok(())
     }
 
     /// Add new task to the management system
     pub async fn add_task(&self, task: ComputeTask) -> Result<(), TaskManagerError> {
+        if let (Some(tenants), Some(tenant_id)) = (&self.tenants, &task.tenant_id) {
+            tenants.admit_task(tenant_id).await?;
+        }
+
         let mut queue = self.pending_queue.lock().await;
         queue.push(Arc::new(task));
         Ok(())
@@ -148,18 +204,44 @@ impl TaskManager {
             let mut resources = self.resource_pool.write().await;
             let mut running = self.running_tasks.write().await;
 
+            let mut deferred = Vec::new();
+
             while let Some(task) = queue.pop() {
+                // Tenants at their running quota wait without blocking others
+                if !self.tenant_can_start(&task).await {
+                    deferred.push(task);
+                    continue;
+                }
+
                 if self.can_allocate(&task.requirements, &resources).await {
                     if let Err(e) = self.start_task(task.clone(), &mut resources).await {
-                        log::error!("Failed to start task {}: {}", task.task_id, e);
+                        let err = NodeError::from(e);
+                        if err.is_retryable() {
+                            log::warn!("Deferring task {}: {}", task.task_id, err);
+                            deferred.push(task);
+                        } else {
+                            log::error!("Dropping task {}: {}", task.task_id, err);
+                        }
                         continue;
                     }
+                    if let (Some(tenants), Some(tenant_id)) = (&self.tenants, &task.tenant_id) {
+                        tenants.record_started(tenant_id).await;
+                    }
                     running.insert(task.task_id.clone(), task.clone());
                 } else {
                     queue.push(task);
                     break;
                 }
             }
+
+            queue.extend(deferred);
+        }
+    }
+
+    async fn tenant_can_start(&self, task: &ComputeTask) -> bool {
+        match (&self.tenants, &task.tenant_id) {
+            (Some(tenants), Some(tenant_id)) => tenants.can_start(tenant_id).await,
+            _ => true,
         }
     }
 
@@ -252,6 +334,12 @@ impl TaskManager {
             .unwrap()
             .as_secs();
 
+        if let (Some(tenants), Some(tenant_id)) = (&self.tenants, &task.tenant_id) {
+            let gpu_seconds = updated_task.updated_at.saturating_sub(task.updated_at) as f64
+                * task.requirements.gpu_count as f64;
+            tenants.record_finished(tenant_id, gpu_seconds, task.priority_fee).await;
+        }
+
         // Submit result to chain
         self.submit_result(task, result).await?;
 
@@ -336,17 +424,29 @@ impl TaskManager {
                 if let Some(task) = running.remove(&task_id) {
                     let mut resources = self.resource_pool.write().await;
                     self.release_resources(&task.requirements, &mut resources).await;
+                    if let (Some(tenants), Some(tenant_id)) = (&self.tenants, &task.tenant_id) {
+                        // GPU time was spent either way; the priority fee is only
+                        // owed for a delivered result
+                        let gpu_seconds = now.saturating_sub(task.updated_at) as f64
+                            * task.requirements.gpu_count as f64;
+                        tenants.record_finished(tenant_id, gpu_seconds, 0).await;
+                    }
                 }
             }
         }
     }
 }
 
-// Ord implementation for task prioritization
+// Ord implementation for task prioritization: highest priority fee first,
+// then least deadline slack (tasks without a deadline have unbounded slack),
+// then the priority level, then oldest first
 impl Ord for ComputeTask {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.priority
-            .cmp(&other.priority)
+        let slack = |task: &Self| task.latest_start().unwrap_or(u64::MAX);
+        self.priority_fee
+            .cmp(&other.priority_fee)
+            .then_with(|| slack(self).cmp(&slack(other)).reverse())
+            .then_with(|| self.priority.cmp(&other.priority))
             .then_with(|| self.created_at.cmp(&other.created_at).reverse())
     }
 }
@@ -370,6 +470,53 @@ mod tests {
     use super::*;
     use solana_sdk::signer::keypair::Keypair;
 
+    fn task(task_id: &str) -> ComputeTask {
+        ComputeTask {
+            task_id: task_id.to_string(),
+            owner: Pubkey::default(),
+            priority: TaskPriority::Medium,
+            requirements: ResourceRequirements {
+                gpu_type: None,
+                gpu_count: 0,
+                memory_gb: 1,
+                storage_gb: 1,
+                timeout_secs: 600,
+                preferred_regions: vec![],
+                max_rtt_ms: None,
+                require_tee: false,
+            },
+            state: TaskState::Pending,
+            created_at: 0,
+            updated_at: 0,
+            task_type: TaskType::Inference {
+                input_cid: "Qm...".to_string(),
+            },
+            model_cid: "Qm...".to_string(),
+            data_cid: "Qm...".to_string(),
+            tenant_id: None,
+            priority_fee: 0,
+            deadline: None,
+        }
+    }
+
+    #[test]
+    fn test_queue_orders_by_fee_then_deadline_slack() {
+        let mut queue = BinaryHeap::new();
+        queue.push(ComputeTask { priority: TaskPriority::Critical, ..task("critical") });
+        queue.push(ComputeTask { priority_fee: 10, ..task("tipped") });
+        queue.push(ComputeTask { priority_fee: 10, deadline: Some(5_000), ..task("due-late") });
+        // Shorter timeout leaves more slack despite the earlier deadline
+        let mut roomy = ComputeTask { priority_fee: 10, deadline: Some(4_000), ..task("roomy") };
+        roomy.requirements.timeout_secs = 60;
+        queue.push(roomy);
+        queue.push(ComputeTask { priority_fee: 10, deadline: Some(4_000), ..task("due-soon") });
+        queue.push(task("plain"));
+
+        let order: Vec<String> =
+            std::iter::from_fn(|| queue.pop()).map(|t| t.task_id).collect();
+        assert_eq!(order, ["due-soon", "roomy", "due-late", "tipped", "critical", "plain"]);
+    }
+
     #[tokio::test]
     async fn test_task_lifecycle() {
         let manager = TaskManager::new("http://test:8899");
@@ -385,6 +532,9 @@ mod tests {
                 memory_gb: 16,
                 storage_gb: 50,
                 timeout_secs: 3600,
+                preferred_regions: vec![],
+                max_rtt_ms: None,
+                require_tee: false,
             },
             state: TaskState::Pending,
             created_at: 0,
@@ -394,6 +544,9 @@ mod tests {
             },
             model_cid: "Qm...".to_string(),
             data_cid: "Qm...".to_string(),
+            tenant_id: None,
+            priority_fee: 0,
+            deadline: None,
         };
 
         // Test adding task
@@ -0,0 +1,143 @@
+//! Task-seeded randomness for training runs
+//!
+//! Every stochastic choice of a training task (dropout masks, augmentation
+//! parameters, sample order) is drawn from the `rng_seed` that `create_task`
+//! committed on-chain, never from the worker's own RNG, so a run can be
+//! reproduced and disputed. A draw is the Poseidon hash of the seed and the
+//! draw's coordinates. The training circuit recomputes dropout masks the same
+//! way from the seed, which it exposes as public inputs, so a proof over any
+//! other mask does not verify.
+
+use plonky3::{
+    field::{
+        goldilocks_field::GoldilocksField,
+        types::{Field, PrimeField64},
+    },
+    hash::poseidon::PoseidonHash,
+    plonk::config::Hasher,
+};
+
+type F = GoldilocksField;
+
+/// Field elements carrying the 32-byte seed: little-endian u32 limbs, each below
+/// the Goldilocks modulus
+pub const SEED_LIMBS: usize = 8;
+/// Dropout rates are in 1/256ths: a unit is dropped when the low byte of its
+/// draw is below the rate
+pub const DROPOUT_DENOMINATOR: u32 = 256;
+
+/// What a draw decides; hashed in so streams for different uses never overlap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Draw {
+    Dropout = 0,
+    Augmentation = 1,
+    Shuffle = 2,
+}
+
+/// The seed as the training circuit takes it
+pub fn seed_limbs(seed: &[u8; 32]) -> [F; SEED_LIMBS] {
+    std::array::from_fn(|i| {
+        let limb = u32::from_le_bytes(seed[i * 4..i * 4 + 4].try_into().unwrap());
+        F::from_canonical_u32(limb)
+    })
+}
+
+/// Deterministic draws for one task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskRng {
+    limbs: [F; SEED_LIMBS],
+}
+
+impl TaskRng {
+    pub fn new(seed: &[u8; 32]) -> Self {
+        Self { limbs: seed_limbs(seed) }
+    }
+
+    /// Draw for `purpose` at coordinates `(a, b)`, e.g. (layer, unit) or
+    /// (epoch, position)
+    pub fn draw(&self, purpose: Draw, a: u32, b: u32) -> u64 {
+        let mut input = self.limbs.to_vec();
+        input.extend([purpose as u32, a, b].map(F::from_canonical_u32));
+        PoseidonHash::hash_no_pad(&input).elements[0].to_canonical_u64()
+    }
+
+    /// Whether `unit` of `layer` survives dropout at `rate`/256; the training
+    /// circuit enforces the same rule
+    pub fn dropout_keep(&self, layer: u32, unit: u32, rate: u8) -> bool {
+        (self.draw(Draw::Dropout, layer, unit) & 0xff) >= rate as u64
+    }
+
+    pub fn dropout_mask(&self, layer: u32, units: usize, rate: u8) -> Vec<bool> {
+        (0..units as u32).map(|unit| self.dropout_keep(layer, unit, rate)).collect()
+    }
+
+    /// Augmentation parameter `param` of `sample` (crop offset, flip, ...),
+    /// uniform below `bound`
+    pub fn augmentation(&self, sample: u32, param: u32, bound: u64) -> u64 {
+        below(self.draw(Draw::Augmentation, sample, param), bound)
+    }
+
+    /// Fisher-Yates shuffle of one epoch's sample order
+    pub fn shuffle<T>(&self, epoch: u32, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = below(self.draw(Draw::Shuffle, epoch, i as u32), i as u64 + 1);
+            items.swap(i, j as usize);
+        }
+    }
+}
+
+/// Map a draw onto `0..bound` by multiply-shift
+fn below(draw: u64, bound: u64) -> u64 {
+    ((draw as u128 * bound as u128) >> 64) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draws_are_reproducible_and_seed_bound() {
+        let rng = TaskRng::new(&[3; 32]);
+        assert_eq!(rng.dropout_mask(1, 64, 128), TaskRng::new(&[3; 32]).dropout_mask(1, 64, 128));
+        assert_ne!(rng.dropout_mask(1, 64, 128), TaskRng::new(&[4; 32]).dropout_mask(1, 64, 128));
+        // Purposes and coordinates give independent draws
+        assert_ne!(rng.draw(Draw::Dropout, 0, 0), rng.draw(Draw::Shuffle, 0, 0));
+        assert_ne!(rng.draw(Draw::Dropout, 0, 1), rng.draw(Draw::Dropout, 1, 0));
+    }
+
+    #[test]
+    fn test_dropout_rate_in_256ths() {
+        let rng = TaskRng::new(&[9; 32]);
+        assert!(rng.dropout_mask(0, 256, 0).iter().all(|&keep| keep));
+        let kept = rng.dropout_mask(0, 4_096, 64).iter().filter(|&&keep| keep).count();
+        // Expect 3/4 kept
+        assert!((2_900..3_250).contains(&kept), "kept {}", kept);
+    }
+
+    #[test]
+    fn test_shuffle_permutes_deterministically() {
+        let rng = TaskRng::new(&[1; 32]);
+        let mut order: Vec<u32> = (0..100).collect();
+        rng.shuffle(0, &mut order);
+        let mut again: Vec<u32> = (0..100).collect();
+        rng.shuffle(0, &mut again);
+        assert_eq!(order, again);
+        assert_ne!(order, (0..100).collect::<Vec<_>>());
+
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..100).collect::<Vec<_>>());
+        assert!(rng.augmentation(5, 0, 10) < 10);
+    }
+
+    #[test]
+    fn test_seed_limbs_little_endian() {
+        let mut seed = [0; 32];
+        seed[..4].copy_from_slice(&0xdead_beefu32.to_le_bytes());
+        seed[31] = 1;
+        let limbs = seed_limbs(&seed);
+        assert_eq!(limbs[0].to_canonical_u64(), 0xdead_beef);
+        assert_eq!(limbs[7].to_canonical_u64(), 1 << 24);
+    }
+}
@@ -0,0 +1,323 @@
+//! Tenant namespaces with per-tenant API keys, quotas, and billing
+
+use serde::{Deserialize, Serialize};
+use solana_program::keccak;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+/// Per-tenant admission limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantQuota {
+    pub max_pending_tasks: u32,
+    pub max_running_tasks: u32,
+    pub max_tasks_per_period: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TenantStatus {
+    Active,
+    Suspended,
+}
+
+/// Isolated namespace on a shared coordinator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tenant {
+    pub tenant_id: String,
+    pub api_key_hash: [u8; 32],
+    pub storage_prefix: String,
+    pub fee_payer: Pubkey,
+    pub quota: TenantQuota,
+    /// Coordinator fee per GPU-second of execution, billed to `fee_payer`
+    pub gpu_second_fee_lamports: u64,
+    pub status: TenantStatus,
+}
+
+/// Usage counters for the current billing period
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TenantUsage {
+    pub pending_tasks: u32,
+    pub running_tasks: u32,
+    pub tasks_submitted: u64,
+    pub tasks_completed: u64,
+    pub gpu_seconds: f64,
+    pub fees_lamports: u64,
+}
+
+/// Billing report row returned by the admin API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantBillingReport {
+    pub tenant_id: String,
+    pub fee_payer: Pubkey,
+    pub status: TenantStatus,
+    pub usage: TenantUsage,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TenantError {
+    #[error("Unknown tenant: {0}")]
+    UnknownTenant(String),
+    #[error("Tenant already exists: {0}")]
+    TenantExists(String),
+    #[error("Invalid API key")]
+    InvalidApiKey,
+    #[error("Tenant suspended: {0}")]
+    Suspended(String),
+    #[error("Quota exceeded for tenant {0}: {1}")]
+    QuotaExceeded(String, &'static str),
+}
+
+/// Registry of tenants and their live usage
+#[derive(Default)]
+pub struct TenantRegistry {
+    tenants: RwLock<HashMap<String, (Tenant, TenantUsage)>>,
+    /// Tasks ever submitted per tenant; unlike usage, never reset
+    task_sequences: RwLock<HashMap<String, u64>>,
+}
+
+fn hash_api_key(api_key: &str) -> [u8; 32] {
+    keccak::hash(api_key.as_bytes()).0
+}
+
+impl TenantRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Admin: create a tenant namespace
+    pub async fn create_tenant(
+        &self,
+        tenant_id: &str,
+        api_key: &str,
+        fee_payer: Pubkey,
+        quota: TenantQuota,
+        gpu_second_fee_lamports: u64,
+    ) -> Result<Tenant, TenantError> {
+        let mut tenants = self.tenants.write().await;
+        if tenants.contains_key(tenant_id) {
+            return Err(TenantError::TenantExists(tenant_id.to_string()));
+        }
+
+        let tenant = Tenant {
+            tenant_id: tenant_id.to_string(),
+            api_key_hash: hash_api_key(api_key),
+            storage_prefix: format!("tenants/{}", tenant_id),
+            fee_payer,
+            quota,
+            gpu_second_fee_lamports,
+            status: TenantStatus::Active,
+        };
+        tenants.insert(tenant_id.to_string(), (tenant.clone(), TenantUsage::default()));
+        Ok(tenant)
+    }
+
+    /// Admin: suspend or reinstate a tenant
+    pub async fn set_status(&self, tenant_id: &str, status: TenantStatus) -> Result<(), TenantError> {
+        let mut tenants = self.tenants.write().await;
+        let (tenant, _) = tenants
+            .get_mut(tenant_id)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+        tenant.status = status;
+        Ok(())
+    }
+
+    /// Resolve an API key to its active tenant
+    pub async fn authenticate(&self, api_key: &str) -> Result<Tenant, TenantError> {
+        let key_hash = hash_api_key(api_key);
+        let tenants = self.tenants.read().await;
+        let (tenant, _) = tenants
+            .values()
+            .find(|(tenant, _)| tenant.api_key_hash == key_hash)
+            .ok_or(TenantError::InvalidApiKey)?;
+
+        if tenant.status == TenantStatus::Suspended {
+            return Err(TenantError::Suspended(tenant.tenant_id.clone()));
+        }
+        Ok(tenant.clone())
+    }
+
+    /// Account for a newly queued task, enforcing quotas
+    pub async fn admit_task(&self, tenant_id: &str) -> Result<(), TenantError> {
+        let mut tenants = self.tenants.write().await;
+        let (tenant, usage) = tenants
+            .get_mut(tenant_id)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+
+        if tenant.status == TenantStatus::Suspended {
+            return Err(TenantError::Suspended(tenant_id.to_string()));
+        }
+        if usage.pending_tasks >= tenant.quota.max_pending_tasks {
+            return Err(TenantError::QuotaExceeded(tenant_id.to_string(), "pending tasks"));
+        }
+        if usage.tasks_submitted >= tenant.quota.max_tasks_per_period {
+            return Err(TenantError::QuotaExceeded(tenant_id.to_string(), "tasks per period"));
+        }
+
+        usage.pending_tasks += 1;
+        usage.tasks_submitted += 1;
+        Ok(())
+    }
+
+    /// Whether the tenant may start another task right now
+    pub async fn can_start(&self, tenant_id: &str) -> bool {
+        self.tenants
+            .read()
+            .await
+            .get(tenant_id)
+            .map(|(tenant, usage)| {
+                tenant.status == TenantStatus::Active
+                    && usage.running_tasks < tenant.quota.max_running_tasks
+            })
+            .unwrap_or(false)
+    }
+
+    pub async fn record_started(&self, tenant_id: &str) {
+        if let Some((_, usage)) = self.tenants.write().await.get_mut(tenant_id) {
+            usage.pending_tasks = usage.pending_tasks.saturating_sub(1);
+            usage.running_tasks += 1;
+        }
+    }
+
+    /// Close out a task and bill it: the task's own fee plus the tenant's
+    /// GPU-second rate, rounded up. Returns the lamports charged
+    pub async fn record_finished(&self, tenant_id: &str, gpu_seconds: f64, task_fee_lamports: u64) -> u64 {
+        let mut tenants = self.tenants.write().await;
+        let Some((tenant, usage)) = tenants.get_mut(tenant_id) else {
+            return 0;
+        };
+        let gpu_fee = (gpu_seconds * tenant.gpu_second_fee_lamports as f64).ceil() as u64;
+        let fee = task_fee_lamports.saturating_add(gpu_fee);
+
+        usage.running_tasks = usage.running_tasks.saturating_sub(1);
+        usage.tasks_completed += 1;
+        usage.gpu_seconds += gpu_seconds;
+        usage.fees_lamports = usage.fees_lamports.saturating_add(fee);
+        fee
+    }
+
+    /// Storage key namespaced under the tenant's prefix
+    pub async fn storage_key(&self, tenant_id: &str, object: &str) -> Result<String, TenantError> {
+        let tenants = self.tenants.read().await;
+        let (tenant, _) = tenants
+            .get(tenant_id)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+        Ok(format!("{}/{}", tenant.storage_prefix, object))
+    }
+
+    /// Id for the tenant's next task: the submission time plus the tenant's
+    /// task sequence, so tasks submitted in the same millisecond stay distinct
+    pub async fn next_task_id(&self, tenant_id: &str, now_ms: u64) -> Result<String, TenantError> {
+        if !self.tenants.read().await.contains_key(tenant_id) {
+            return Err(TenantError::UnknownTenant(tenant_id.to_string()));
+        }
+        let mut sequences = self.task_sequences.write().await;
+        let sequence = sequences.entry(tenant_id.to_string()).or_default();
+        *sequence += 1;
+        Ok(format!("{}-{}-{}", tenant_id, now_ms, sequence))
+    }
+
+    /// Admin: per-tenant billing report, resetting period counters if requested
+    pub async fn billing_report(&self, reset_period: bool) -> Vec<TenantBillingReport> {
+        let mut tenants = self.tenants.write().await;
+        let mut report: Vec<_> = tenants
+            .values()
+            .map(|(tenant, usage)| TenantBillingReport {
+                tenant_id: tenant.tenant_id.clone(),
+                fee_payer: tenant.fee_payer,
+                status: tenant.status,
+                usage: usage.clone(),
+            })
+            .collect();
+        report.sort_by(|a, b| a.tenant_id.cmp(&b.tenant_id));
+
+        if reset_period {
+            for (_, usage) in tenants.values_mut() {
+                usage.tasks_submitted = 0;
+                usage.tasks_completed = 0;
+                usage.gpu_seconds = 0.0;
+                usage.fees_lamports = 0;
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota() -> TenantQuota {
+        TenantQuota {
+            max_pending_tasks: 2,
+            max_running_tasks: 1,
+            max_tasks_per_period: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenant_isolation_and_quota() {
+        let registry = TenantRegistry::new();
+        registry.create_tenant("team-a", "key-a", Pubkey::new_unique(), quota(), 10).await.unwrap();
+        registry.create_tenant("team-b", "key-b", Pubkey::new_unique(), quota(), 10).await.unwrap();
+
+        assert_eq!(registry.authenticate("key-b").await.unwrap().tenant_id, "team-b");
+        assert_eq!(registry.authenticate("nope").await.unwrap_err(), TenantError::InvalidApiKey);
+        assert_eq!(
+            registry.storage_key("team-a", "Qm123").await.unwrap(),
+            "tenants/team-a/Qm123"
+        );
+
+        registry.admit_task("team-a").await.unwrap();
+        registry.admit_task("team-a").await.unwrap();
+        assert!(matches!(
+            registry.admit_task("team-a").await,
+            Err(TenantError::QuotaExceeded(_, "pending tasks"))
+        ));
+        // Team A's usage does not affect team B
+        registry.admit_task("team-b").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_task_ids_unique_within_a_millisecond() {
+        let registry = TenantRegistry::new();
+        registry.create_tenant("team-a", "key-a", Pubkey::new_unique(), quota(), 10).await.unwrap();
+
+        let first = registry.next_task_id("team-a", 1_000).await.unwrap();
+        let second = registry.next_task_id("team-a", 1_000).await.unwrap();
+        assert_ne!(first, second);
+        assert!(first.starts_with("team-a-1000-"));
+        // Resetting the billing period does not restart the sequence
+        registry.billing_report(true).await;
+        assert_ne!(registry.next_task_id("team-a", 1_000).await.unwrap(), first);
+        assert!(registry.next_task_id("nobody", 1_000).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_suspended_tenant_rejected() {
+        let registry = TenantRegistry::new();
+        registry.create_tenant("team-a", "key-a", Pubkey::new_unique(), quota(), 10).await.unwrap();
+        registry.set_status("team-a", TenantStatus::Suspended).await.unwrap();
+
+        assert!(matches!(registry.authenticate("key-a").await, Err(TenantError::Suspended(_))));
+        assert!(matches!(registry.admit_task("team-a").await, Err(TenantError::Suspended(_))));
+    }
+
+    #[tokio::test]
+    async fn test_finished_tasks_billed_at_tenant_rate() {
+        let registry = TenantRegistry::new();
+        registry.create_tenant("team-a", "key-a", Pubkey::new_unique(), quota(), 10).await.unwrap();
+        registry.admit_task("team-a").await.unwrap();
+        registry.record_started("team-a").await;
+
+        // 1.25 GPU-seconds at 10 lamports rounds up to 13, plus the task's own fee
+        assert_eq!(registry.record_finished("team-a", 1.25, 500).await, 513);
+        assert_eq!(registry.record_finished("nobody", 1.0, 500).await, 0);
+
+        let report = registry.billing_report(true).await;
+        assert_eq!(report[0].usage.fees_lamports, 513);
+        assert_eq!(report[0].usage.tasks_completed, 1);
+        assert_eq!(registry.billing_report(false).await[0].usage.fees_lamports, 0);
+    }
+}
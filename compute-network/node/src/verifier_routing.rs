@@ -0,0 +1,183 @@
+//! Blue/green routing of proofs across verifier program versions
+//!
+//! Every task is tagged on-chain with the verifier version its proof must be
+//! built for. The coordinator polls haunti-core's `VerifierRegistry` and submits
+//! each proof to the program registered for its task's version. During a
+//! cutover both the previous and current versions are routable; once the window
+//! closes, proofs for the previous version are rejected before submission.
+//!
+//! Submissions and failures are counted per version, so the failure rates of the
+//! old and new verifier can be compared while both are live.
+
+use haunti_core::state::verifier_registry::VerifierRegistry;
+use prometheus::{GaugeVec, IntCounterVec};
+use solana_sdk::pubkey::Pubkey;
+use std::time::Instant;
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+/// Approximate slot time used to extrapolate between polls
+const SLOT_MS: u128 = 400;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RoutingError {
+    #[error("Verifier registry not loaded yet")]
+    NotLoaded,
+    #[error("Verifier version {0} is not registered")]
+    UnknownVersion(u16),
+    #[error("Verifier version {0} is no longer accepted")]
+    Retired(u16),
+}
+
+struct Snapshot {
+    registry: VerifierRegistry,
+    slot: u64,
+    fetched_at: Instant,
+}
+
+impl Snapshot {
+    fn estimated_slot(&self) -> u64 {
+        self.slot + (self.fetched_at.elapsed().as_millis() / SLOT_MS) as u64
+    }
+}
+
+/// Change in the routable versions between two polls
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingChange {
+    pub current: u16,
+    /// Version still routable alongside `current`, if a cutover is running
+    pub draining: Option<u16>,
+}
+
+pub struct VerifierRouter {
+    snapshot: RwLock<Option<Snapshot>>,
+    submitted: IntCounterVec,
+    failed: IntCounterVec,
+    failure_rate: GaugeVec,
+}
+
+impl VerifierRouter {
+    /// Metrics are labelled by `version`
+    pub fn new(submitted: IntCounterVec, failed: IntCounterVec, failure_rate: GaugeVec) -> Self {
+        Self {
+            snapshot: RwLock::new(None),
+            submitted,
+            failed,
+            failure_rate,
+        }
+    }
+
+    /// Verifier program for proofs tagged with `version`
+    pub async fn route(&self, version: u16) -> Result<Pubkey, RoutingError> {
+        let snapshot = self.snapshot.read().await;
+        let snapshot = snapshot.as_ref().ok_or(RoutingError::NotLoaded)?;
+        route(&snapshot.registry, version, snapshot.estimated_slot())
+    }
+
+    /// Replace the snapshot with a fresh poll taken at `slot`; returns the new
+    /// routing when it differs from the previous estimate
+    pub async fn update(&self, registry: VerifierRegistry, slot: u64) -> Option<RoutingChange> {
+        let next = Snapshot { registry, slot, fetched_at: Instant::now() };
+        let mut snapshot = self.snapshot.write().await;
+        let prev = snapshot
+            .as_ref()
+            .map(|s| routing(&s.registry, s.estimated_slot()));
+        let now = routing(&next.registry, slot);
+        *snapshot = Some(next);
+        (prev.as_ref() != Some(&now)).then_some(now)
+    }
+
+    /// Count a submission to `version` and refresh its failure rate
+    pub fn record(&self, version: u16, ok: bool) {
+        let label = version.to_string();
+        let submitted = self.submitted.with_label_values(&[&label]);
+        let failed = self.failed.with_label_values(&[&label]);
+        submitted.inc();
+        if !ok {
+            failed.inc();
+        }
+        self.failure_rate
+            .with_label_values(&[&label])
+            .set(failed.get() as f64 / submitted.get() as f64);
+    }
+}
+
+fn route(registry: &VerifierRegistry, version: u16, slot: u64) -> Result<Pubkey, RoutingError> {
+    let program = registry
+        .program_for(version)
+        .ok_or(RoutingError::UnknownVersion(version))?;
+    if !registry.accepts(version, slot) {
+        return Err(RoutingError::Retired(version));
+    }
+    Ok(program)
+}
+
+fn routing(registry: &VerifierRegistry, slot: u64) -> RoutingChange {
+    RoutingChange {
+        current: registry.current,
+        draining: registry.in_cutover(slot).then_some(registry.previous),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::Opts;
+
+    fn router() -> VerifierRouter {
+        VerifierRouter::new(
+            IntCounterVec::new(Opts::new("submitted", "-"), &["version"]).unwrap(),
+            IntCounterVec::new(Opts::new("failed", "-"), &["version"]).unwrap(),
+            GaugeVec::new(Opts::new("failure_rate", "-"), &["version"]).unwrap(),
+        )
+    }
+
+    fn registry(v1: Pubkey, v2: Pubkey) -> VerifierRegistry {
+        let mut registry = VerifierRegistry::default();
+        registry.register(1, v1).unwrap();
+        registry.register(2, v2).unwrap();
+        registry.current = 1;
+        registry.previous = 1;
+        registry
+    }
+
+    #[test]
+    fn test_routes_both_versions_until_cutover_ends() {
+        let (v1, v2) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut registry = registry(v1, v2);
+        assert_eq!(route(&registry, 1, 0), Ok(v1));
+        assert_eq!(route(&registry, 2, 0), Err(RoutingError::Retired(2)));
+        assert_eq!(route(&registry, 7, 0), Err(RoutingError::UnknownVersion(7)));
+
+        registry.begin_cutover(2, 100, 50).unwrap();
+        assert_eq!(routing(&registry, 50), RoutingChange { current: 2, draining: Some(1) });
+        assert_eq!(route(&registry, 1, 149), Ok(v1));
+        assert_eq!(route(&registry, 2, 149), Ok(v2));
+        assert_eq!(route(&registry, 1, 150), Err(RoutingError::Retired(1)));
+        assert_eq!(routing(&registry, 150), RoutingChange { current: 2, draining: None });
+    }
+
+    #[tokio::test]
+    async fn test_update_reports_changes_only() {
+        let router = router();
+        assert_eq!(router.route(1).await, Err(RoutingError::NotLoaded));
+
+        let mut registry = registry(Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(router.update(registry.clone(), 10).await.is_some());
+        assert!(router.update(registry.clone(), 11).await.is_none());
+
+        registry.begin_cutover(2, 1_000, 12).unwrap();
+        let change = router.update(registry, 12).await;
+        assert_eq!(change, Some(RoutingChange { current: 2, draining: Some(1) }));
+    }
+
+    #[test]
+    fn test_failure_rate_tracked_per_version() {
+        let router = router();
+        router.record(1, true);
+        router.record(1, false);
+        router.record(2, true);
+        assert_eq!(router.failure_rate.with_label_values(&["1"]).get(), 0.5);
+        assert_eq!(router.failure_rate.with_label_values(&["2"]).get(), 0.0);
+    }
+}
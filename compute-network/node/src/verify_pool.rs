@@ -0,0 +1,153 @@
+//! Bounded parallel proof verification with digest-keyed result caching
+
+use prometheus::IntGauge;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use solana_program::keccak;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+use thiserror::Error;
+use tokio::sync::{oneshot, Mutex, Semaphore};
+
+/// Maximum number of cached verification outcomes
+const VERIFY_CACHE_CAPACITY: usize = 4_096;
+
+/// CPU-bound proof verification backend
+pub trait ProofVerifier: Send + Sync + 'static {
+    fn verify_blocking(&self, proof: &[u8]) -> anyhow::Result<bool>;
+}
+
+#[derive(Error, Debug)]
+pub enum VerifyPoolError {
+    #[error("Verification pool build failed: {0}")]
+    PoolBuild(#[from] rayon::ThreadPoolBuildError),
+    #[error("Verification worker dropped")]
+    WorkerDropped,
+    #[error("Verification failed: {0}")]
+    Backend(#[from] anyhow::Error),
+}
+
+#[derive(Default)]
+struct VerifyCache {
+    outcomes: HashMap<[u8; 32], bool>,
+    order: VecDeque<[u8; 32]>,
+}
+
+impl VerifyCache {
+    fn insert(&mut self, digest: [u8; 32], valid: bool) {
+        if self.outcomes.insert(digest, valid).is_none() {
+            self.order.push_back(digest);
+        }
+        while self.order.len() > VERIFY_CACHE_CAPACITY {
+            if let Some(evicted) = self.order.pop_front() {
+                self.outcomes.remove(&evicted);
+            }
+        }
+    }
+}
+
+/// Verification pool sized to the available cores with bounded queueing
+pub struct VerificationPool<V: ProofVerifier> {
+    verifier: Arc<V>,
+    workers: ThreadPool,
+    queue_slots: Arc<Semaphore>,
+    cache: Mutex<VerifyCache>,
+    queue_depth: IntGauge,
+}
+
+impl<V: ProofVerifier> VerificationPool<V> {
+    /// `threads == 0` uses one thread per available core
+    pub fn new(
+        verifier: Arc<V>,
+        threads: usize,
+        max_queued: usize,
+        queue_depth: IntGauge,
+    ) -> Result<Self, VerifyPoolError> {
+        let workers = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("proof-verify-{}", i))
+            .build()?;
+
+        Ok(Self {
+            verifier,
+            workers,
+            queue_slots: Arc::new(Semaphore::new(max_queued.max(1))),
+            cache: Mutex::new(VerifyCache::default()),
+            queue_depth,
+        })
+    }
+
+    /// Verify a proof, waiting for a queue slot when the pool is saturated
+    pub async fn verify(&self, proof: &[u8]) -> Result<bool, VerifyPoolError> {
+        let digest = keccak::hash(proof).0;
+        if let Some(valid) = self.cache.lock().await.outcomes.get(&digest) {
+            return Ok(*valid);
+        }
+
+        // Backpressure: callers wait here rather than growing an unbounded queue
+        let _slot = self
+            .queue_slots
+            .acquire()
+            .await
+            .map_err(|_| VerifyPoolError::WorkerDropped)?;
+        self.queue_depth.inc();
+
+        let (tx, rx) = oneshot::channel();
+        let verifier = self.verifier.clone();
+        let proof = proof.to_vec();
+        self.workers.spawn(move || {
+            let _ = tx.send(verifier.verify_blocking(&proof));
+        });
+
+        let outcome = rx.await;
+        self.queue_depth.dec();
+        let valid = outcome.map_err(|_| VerifyPoolError::WorkerDropped)??;
+
+        self.cache.lock().await.insert(digest, valid);
+        Ok(valid)
+    }
+
+    /// Verify many proofs concurrently, preserving input order
+    pub async fn verify_batch(&self, proofs: &[Vec<u8>]) -> Vec<Result<bool, VerifyPoolError>> {
+        futures::future::join_all(proofs.iter().map(|proof| self.verify(proof))).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingVerifier(AtomicUsize);
+
+    impl ProofVerifier for CountingVerifier {
+        fn verify_blocking(&self, proof: &[u8]) -> anyhow::Result<bool> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(proof.first() == Some(&1))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_verification_and_cache() {
+        let verifier = Arc::new(CountingVerifier(AtomicUsize::new(0)));
+        let gauge = IntGauge::new("test_verify_queue_depth", "test").unwrap();
+        let pool = VerificationPool::new(verifier.clone(), 4, 2, gauge.clone()).unwrap();
+
+        let proofs = vec![vec![1, 2], vec![0, 2], vec![1, 3], vec![1, 2]];
+        let results: Vec<bool> = pool
+            .verify_batch(&proofs)
+            .await
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(results, vec![true, false, true, true]);
+        assert_eq!(gauge.get(), 0);
+
+        // Re-verifying a known digest is served from cache
+        let calls = verifier.0.load(Ordering::SeqCst);
+        assert!(pool.verify(&[0, 2]).await.unwrap() == false);
+        assert_eq!(verifier.0.load(Ordering::SeqCst), calls);
+    }
+}
@@ -0,0 +1,395 @@
+//! Models kept resident in GPU memory between tasks
+//!
+//! Fetching a model, applying its patches and uploading its tensors costs
+//! seconds per task, while most traffic goes to a handful of models. The pool
+//! keeps up to `max_models` of them per device, within `max_bytes`, and hands
+//! out leases: a leased model is never evicted, and a model no lease holds is a
+//! candidate for eviction, least-used first. The scheduler can also reclaim
+//! memory explicitly with `evict` when free device memory runs low.
+//!
+//! Encrypted (FHE) and plaintext tensors of the same model are distinct entries,
+//! as are different patch chains over one base model.
+
+use prometheus::{IntCounterVec, IntGaugeVec};
+use solana_program::keccak;
+use std::{
+    collections::HashMap,
+    future::Future,
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
+
+/// Identity of a loaded model: base CID, patch chain and tensor encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModelKey([u8; 32]);
+
+impl ModelKey {
+    pub fn new(model_cid: &str, patch_cids: &[String], encrypted: bool) -> Self {
+        let mut parts: Vec<&[u8]> = vec![&[encrypted as u8], model_cid.as_bytes()];
+        parts.extend(patch_cids.iter().map(|cid| cid.as_bytes()));
+        Self(keccak::hashv(&parts).0)
+    }
+}
+
+/// Residency limits of one device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmPoolConfig {
+    /// Models kept resident per device
+    pub max_models: usize,
+    /// Device memory the resident models may occupy
+    pub max_bytes: u64,
+}
+
+/// Why a resident model was dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    /// Room for a more recently loaded model
+    Capacity,
+    /// Requested by the scheduler to free device memory
+    Pressure,
+}
+
+impl EvictionReason {
+    fn label(self) -> &'static str {
+        match self {
+            EvictionReason::Capacity => "capacity",
+            EvictionReason::Pressure => "pressure",
+        }
+    }
+}
+
+struct Entry<T> {
+    model: Arc<T>,
+    bytes: u64,
+    leases: usize,
+    uses: u64,
+    last_used: u64,
+}
+
+struct Device<T> {
+    entries: HashMap<ModelKey, Entry<T>>,
+    bytes: u64,
+}
+
+impl<T> Default for Device<T> {
+    fn default() -> Self {
+        Self { entries: HashMap::new(), bytes: 0 }
+    }
+}
+
+impl<T> Device<T> {
+    /// Unleased entry to evict first: fewest uses, then least recently used
+    fn victim(&self) -> Option<ModelKey> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.leases == 0)
+            .min_by_key(|(_, entry)| (entry.uses, entry.last_used))
+            .map(|(key, _)| *key)
+    }
+
+    fn remove(&mut self, key: &ModelKey) -> u64 {
+        let bytes = self.entries.remove(key).map_or(0, |entry| entry.bytes);
+        self.bytes -= bytes;
+        bytes
+    }
+}
+
+struct State<T> {
+    devices: HashMap<u32, Device<T>>,
+    /// Logical clock for recency
+    tick: u64,
+}
+
+struct Shared<T> {
+    config: WarmPoolConfig,
+    state: Mutex<State<T>>,
+    lookups: IntCounterVec,
+    evictions: IntCounterVec,
+    resident_bytes: IntGaugeVec,
+}
+
+impl<T> Shared<T> {
+    fn release(&self, device: u32, key: &ModelKey) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(entry) = state
+            .devices
+            .get_mut(&device)
+            .and_then(|device| device.entries.get_mut(key))
+        {
+            entry.leases -= 1;
+        }
+    }
+
+    fn evicted(&self, device: u32, reason: EvictionReason, resident: u64) {
+        self.evictions.with_label_values(&[reason.label()]).inc();
+        self.resident_bytes
+            .with_label_values(&[&device.to_string()])
+            .set(resident as i64);
+    }
+}
+
+/// Per-device pool of resident models
+pub struct WarmPool<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for WarmPool<T> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone() }
+    }
+}
+
+/// A model held for the duration of a task; keeps it resident until dropped
+pub struct ModelLease<T> {
+    model: Arc<T>,
+    /// Whether the model was already resident
+    pub warm: bool,
+    /// Set while the pool tracks the model, so the drop releases it
+    resident: Option<(u32, ModelKey)>,
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Deref for ModelLease<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.model
+    }
+}
+
+impl<T> Drop for ModelLease<T> {
+    fn drop(&mut self) {
+        if let Some((device, key)) = &self.resident {
+            self.shared.release(*device, key);
+        }
+    }
+}
+
+impl<T> WarmPool<T> {
+    pub fn new(
+        config: WarmPoolConfig,
+        lookups: IntCounterVec,
+        evictions: IntCounterVec,
+        resident_bytes: IntGaugeVec,
+    ) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                state: Mutex::new(State { devices: HashMap::new(), tick: 0 }),
+                lookups,
+                evictions,
+                resident_bytes,
+            }),
+        }
+    }
+
+    /// Lease the model resident under `key` on `device`, running `load` on a
+    /// miss. `load` returns the model with its device footprint in bytes; two
+    /// tasks missing on the same model at once may both load it, and the first
+    /// to finish becomes resident. A model that does not fit even after evicting
+    /// every unleased entry is returned without being kept
+    pub async fn lease<F, Fut, E>(&self, device: u32, key: ModelKey, load: F) -> Result<ModelLease<T>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(T, u64), E>>,
+    {
+        if let Some(lease) = self.lease_resident(device, key) {
+            self.shared.lookups.with_label_values(&["hit"]).inc();
+            return Ok(lease);
+        }
+        self.shared.lookups.with_label_values(&["miss"]).inc();
+
+        let (model, bytes) = load().await?;
+        Ok(self.insert(device, key, Arc::new(model), bytes))
+    }
+
+    fn lease_resident(&self, device: u32, key: ModelKey) -> Option<ModelLease<T>> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let entry = state.devices.get_mut(&device)?.entries.get_mut(&key)?;
+        entry.leases += 1;
+        entry.uses += 1;
+        entry.last_used = tick;
+        Some(ModelLease {
+            model: entry.model.clone(),
+            warm: true,
+            resident: Some((device, key)),
+            shared: self.shared.clone(),
+        })
+    }
+
+    fn insert(&self, device_id: u32, key: ModelKey, model: Arc<T>, bytes: u64) -> ModelLease<T> {
+        let config = self.shared.config;
+        let mut state = self.shared.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let device = state.devices.entry(device_id).or_default();
+
+        // Lost a race with another task loading the same model
+        if let Some(entry) = device.entries.get_mut(&key) {
+            entry.leases += 1;
+            entry.uses += 1;
+            entry.last_used = tick;
+            return ModelLease {
+                model: entry.model.clone(),
+                warm: false,
+                resident: Some((device_id, key)),
+                shared: self.shared.clone(),
+            };
+        }
+
+        // Evicting cannot make room for a model larger than the whole budget
+        let mut evicted = 0;
+        while bytes <= config.max_bytes
+            && (device.entries.len() >= config.max_models || device.bytes + bytes > config.max_bytes)
+        {
+            let Some(victim) = device.victim() else { break };
+            device.remove(&victim);
+            evicted += 1;
+        }
+        let fits = device.entries.len() < config.max_models && device.bytes + bytes <= config.max_bytes;
+        if fits {
+            device.entries.insert(
+                key,
+                Entry { model: model.clone(), bytes, leases: 1, uses: 1, last_used: tick },
+            );
+            device.bytes += bytes;
+        }
+        let resident = device.bytes;
+        drop(state);
+
+        for _ in 0..evicted {
+            self.shared.evicted(device_id, EvictionReason::Capacity, resident);
+        }
+        self.shared
+            .resident_bytes
+            .with_label_values(&[&device_id.to_string()])
+            .set(resident as i64);
+
+        ModelLease {
+            model,
+            warm: false,
+            resident: fits.then_some((device_id, key)),
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Drop unleased models from `device`, least-used first, until `bytes` are
+    /// freed or nothing else can go; returns the bytes freed
+    pub fn evict(&self, device_id: u32, bytes: u64) -> u64 {
+        let mut state = self.shared.state.lock().unwrap();
+        let Some(device) = state.devices.get_mut(&device_id) else {
+            return 0;
+        };
+        let (mut freed, mut evicted) = (0, 0);
+        while freed < bytes {
+            let Some(victim) = device.victim() else { break };
+            freed += device.remove(&victim);
+            evicted += 1;
+        }
+        let resident = device.bytes;
+        drop(state);
+
+        for _ in 0..evicted {
+            self.shared.evicted(device_id, EvictionReason::Pressure, resident);
+        }
+        freed
+    }
+
+    /// Device memory held by resident models
+    pub fn resident_bytes(&self, device: u32) -> u64 {
+        let state = self.shared.state.lock().unwrap();
+        state.devices.get(&device).map_or(0, |device| device.bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::Opts;
+    use std::convert::Infallible;
+
+    fn pool(max_models: usize, max_bytes: u64) -> WarmPool<&'static str> {
+        WarmPool::new(
+            WarmPoolConfig { max_models, max_bytes },
+            IntCounterVec::new(Opts::new("lookups", "lookups"), &["result"]).unwrap(),
+            IntCounterVec::new(Opts::new("evictions", "evictions"), &["reason"]).unwrap(),
+            IntGaugeVec::new(Opts::new("resident", "resident"), &["device"]).unwrap(),
+        )
+    }
+
+    fn key(cid: &str) -> ModelKey {
+        ModelKey::new(cid, &[], true)
+    }
+
+    async fn lease(
+        pool: &WarmPool<&'static str>,
+        cid: &'static str,
+        bytes: u64,
+    ) -> ModelLease<&'static str> {
+        pool.lease(0, key(cid), || async move { Ok::<_, Infallible>((cid, bytes)) })
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_key_covers_patches_and_encoding() {
+        let patches = vec!["patch".to_string()];
+        assert_ne!(ModelKey::new("base", &[], true), ModelKey::new("base", &[], false));
+        assert_ne!(ModelKey::new("base", &[], true), ModelKey::new("base", &patches, true));
+        assert_eq!(ModelKey::new("base", &patches, true), ModelKey::new("base", &patches, true));
+    }
+
+    #[tokio::test]
+    async fn test_second_lease_is_warm() {
+        let pool = pool(2, 100);
+        assert!(!lease(&pool, "a", 10).await.warm);
+        let lease = lease(&pool, "a", 10).await;
+        assert!(lease.warm);
+        assert_eq!(*lease, "a");
+        assert_eq!(pool.shared.lookups.with_label_values(&["hit"]).get(), 1);
+        assert_eq!(pool.shared.lookups.with_label_values(&["miss"]).get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_least_used_model_evicted_first() {
+        let pool = pool(2, 100);
+        for _ in 0..3 {
+            drop(lease(&pool, "hot", 10).await);
+        }
+        drop(lease(&pool, "cold", 10).await);
+        drop(lease(&pool, "new", 10).await);
+
+        assert!(lease(&pool, "hot", 10).await.warm);
+        assert!(lease(&pool, "new", 10).await.warm);
+        assert!(!lease(&pool, "cold", 10).await.warm);
+        assert_eq!(pool.shared.evictions.with_label_values(&["capacity"]).get(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_leased_models_are_never_evicted() {
+        let pool = pool(1, 100);
+        let held = lease(&pool, "a", 60).await;
+        // No room while "a" is leased: "b" runs without becoming resident
+        let transient = lease(&pool, "b", 60).await;
+        assert!(transient.resident.is_none());
+        assert_eq!(pool.evict(0, u64::MAX), 0);
+        assert_eq!(pool.resident_bytes(0), 60);
+
+        drop(held);
+        assert_eq!(pool.evict(0, 1), 60);
+        assert_eq!(pool.resident_bytes(0), 0);
+        assert_eq!(pool.shared.evictions.with_label_values(&["pressure"]).get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_byte_budget_enforced() {
+        let pool = pool(8, 100);
+        drop(lease(&pool, "a", 50).await);
+        drop(lease(&pool, "b", 40).await);
+        drop(lease(&pool, "c", 30).await);
+        assert!(pool.resident_bytes(0) <= 100);
+        assert!(lease(&pool, "c", 30).await.warm);
+    }
+}
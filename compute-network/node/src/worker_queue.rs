@@ -0,0 +1,333 @@
+//! Worker-local durable queue for riding out coordinator outages
+//!
+//! Leased tasks are journaled before they run, so they survive restarts and keep
+//! executing while the coordinator is unreachable. Results and heartbeats produced
+//! offline wait here until the link returns, when `resolve` decides whether a result
+//! that outlived its lease is still worth submitting.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum QueueError {
+    #[error("Queue journal I/O: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Corrupt queue journal: {0}")]
+    Corrupt(#[from] serde_json::Error),
+    #[error("Unknown lease {0}")]
+    UnknownLease(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LeaseState {
+    Leased,
+    Running,
+    /// Result held locally until the coordinator is reachable
+    Completed { at_ms: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeasedTask<T, R> {
+    pub task: T,
+    pub expires_at_ms: u64,
+    pub state: LeaseState,
+    pub result: Option<R>,
+}
+
+/// Coordinator's view of a lease when a held result is synced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaseStatus {
+    /// Still assigned to this worker
+    Held,
+    /// Expired but not handed to anyone else
+    Expired,
+    Reassigned { completed: bool },
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Submit,
+    Discard(&'static str),
+}
+
+/// Decide what to do with a result completed at `completed_at_ms`.
+///
+/// On-time results always stand. A late result is still submitted unless the task
+/// was cancelled or another worker already finished it: while a reassigned copy is
+/// running, the first verified proof wins and the other worker's GPU time is saved.
+pub fn resolve(completed_at_ms: u64, expires_at_ms: u64, status: LeaseStatus) -> Resolution {
+    match status {
+        LeaseStatus::Cancelled => Resolution::Discard("task cancelled"),
+        _ if completed_at_ms <= expires_at_ms => Resolution::Submit,
+        LeaseStatus::Held | LeaseStatus::Expired => Resolution::Submit,
+        LeaseStatus::Reassigned { completed: false } => Resolution::Submit,
+        LeaseStatus::Reassigned { completed: true } => {
+            Resolution::Discard("completed by another worker")
+        }
+    }
+}
+
+/// Liveness summary sent once the coordinator is reachable again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeartbeatDigest {
+    pub last_alive_ms: u64,
+    /// Heartbeats that could not be delivered
+    pub missed: u32,
+    /// Leases this worker is still working on; the coordinator extends these
+    pub in_flight: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Serialize, R: Serialize", deserialize = "T: DeserializeOwned, R: DeserializeOwned"))]
+struct Journal<T, R> {
+    leases: BTreeMap<String, LeasedTask<T, R>>,
+    last_alive_ms: u64,
+    unsent_heartbeats: u32,
+}
+
+impl<T, R> Default for Journal<T, R> {
+    fn default() -> Self {
+        Self {
+            leases: BTreeMap::new(),
+            last_alive_ms: 0,
+            unsent_heartbeats: 0,
+        }
+    }
+}
+
+/// Leased tasks and held results, journaled to a single JSON file
+pub struct WorkerQueue<T, R> {
+    path: PathBuf,
+    journal: Journal<T, R>,
+}
+
+impl<T, R> WorkerQueue<T, R>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    R: Serialize + DeserializeOwned + Clone,
+{
+    /// Load the journal at `path`, or start empty. Tasks that were running when the
+    /// worker stopped are leased again so they restart.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, QueueError> {
+        let path = path.as_ref().to_path_buf();
+        let mut journal: Journal<T, R> = match std::fs::read(&path) {
+            Ok(raw) => serde_json::from_slice(&raw)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Journal::default(),
+            Err(e) => return Err(e.into()),
+        };
+        for lease in journal.leases.values_mut() {
+            if lease.state == LeaseState::Running {
+                lease.state = LeaseState::Leased;
+            }
+        }
+        Ok(Self { path, journal })
+    }
+
+    pub fn len(&self) -> usize {
+        self.journal.leases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.journal.leases.is_empty()
+    }
+
+    /// Journal a task leased from the coordinator; re-leasing extends the expiry
+    pub fn lease(&mut self, task_id: &str, task: T, expires_at_ms: u64) -> Result<(), QueueError> {
+        self.journal
+            .leases
+            .entry(task_id.to_string())
+            .and_modify(|lease| lease.expires_at_ms = lease.expires_at_ms.max(expires_at_ms))
+            .or_insert(LeasedTask {
+                task,
+                expires_at_ms,
+                state: LeaseState::Leased,
+                result: None,
+            });
+        self.persist()
+    }
+
+    /// Tasks currently executing
+    pub fn running(&self) -> impl Iterator<Item = &T> {
+        self.journal
+            .leases
+            .values()
+            .filter(|lease| lease.state == LeaseState::Running)
+            .map(|lease| &lease.task)
+    }
+
+    /// Mark a leased task as running
+    pub fn start(&mut self, task_id: &str) -> Result<(), QueueError> {
+        self.lease_mut(task_id)?.state = LeaseState::Running;
+        self.persist()
+    }
+
+    /// Oldest journaled task that has not started, marked running
+    pub fn take_unstarted(&mut self) -> Result<Option<T>, QueueError> {
+        let Some((task_id, lease)) = self
+            .journal
+            .leases
+            .iter_mut()
+            .find(|(_, lease)| lease.state == LeaseState::Leased)
+        else {
+            return Ok(None);
+        };
+        lease.state = LeaseState::Running;
+        let task = lease.task.clone();
+        tracing::debug!(task = %task_id, "Resuming leased task from local queue");
+        self.persist()?;
+        Ok(Some(task))
+    }
+
+    /// Drop leases that never started, returning their ids so the scheduler can
+    /// reassign them; once reconnected the scheduler is again the source of work
+    pub fn release_unstarted(&mut self) -> Result<Vec<String>, QueueError> {
+        let released: Vec<String> = self
+            .journal
+            .leases
+            .iter()
+            .filter(|(_, lease)| lease.state == LeaseState::Leased)
+            .map(|(task_id, _)| task_id.clone())
+            .collect();
+        if !released.is_empty() {
+            self.journal.leases.retain(|_, lease| lease.state != LeaseState::Leased);
+            self.persist()?;
+        }
+        Ok(released)
+    }
+
+    /// Hold a result until it can be synced
+    pub fn complete(&mut self, task_id: &str, result: R, now_ms: u64) -> Result<(), QueueError> {
+        let lease = self.lease_mut(task_id)?;
+        lease.state = LeaseState::Completed { at_ms: now_ms };
+        lease.result = Some(result);
+        self.persist()
+    }
+
+    /// Results awaiting sync as `(task_id, completed_at_ms, expires_at_ms, result)`
+    pub fn pending_results(&self) -> Vec<(String, u64, u64, R)> {
+        self.journal
+            .leases
+            .iter()
+            .filter_map(|(task_id, lease)| match (lease.state, &lease.result) {
+                (LeaseState::Completed { at_ms }, Some(result)) => {
+                    Some((task_id.clone(), at_ms, lease.expires_at_ms, result.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Forget a task once it is synced, discarded, or handed back to the scheduler
+    pub fn finish(&mut self, task_id: &str) -> Result<(), QueueError> {
+        if self.journal.leases.remove(task_id).is_some() {
+            self.persist()?;
+        }
+        Ok(())
+    }
+
+    /// Note that the worker was alive at `now_ms`; `delivered` is false while offline
+    pub fn record_heartbeat(&mut self, now_ms: u64, delivered: bool) -> Result<(), QueueError> {
+        self.journal.last_alive_ms = now_ms;
+        self.journal.unsent_heartbeats = if delivered {
+            0
+        } else {
+            self.journal.unsent_heartbeats.saturating_add(1)
+        };
+        self.persist()
+    }
+
+    /// Digest of the outage to send once reconnected; `None` if nothing was missed
+    pub fn heartbeat_digest(&self) -> Option<HeartbeatDigest> {
+        (self.journal.unsent_heartbeats > 0).then(|| HeartbeatDigest {
+            last_alive_ms: self.journal.last_alive_ms,
+            missed: self.journal.unsent_heartbeats,
+            in_flight: self
+                .journal
+                .leases
+                .iter()
+                .filter(|(_, lease)| !matches!(lease.state, LeaseState::Completed { .. }))
+                .map(|(task_id, _)| task_id.clone())
+                .collect(),
+        })
+    }
+
+    fn lease_mut(&mut self, task_id: &str) -> Result<&mut LeasedTask<T, R>, QueueError> {
+        self.journal
+            .leases
+            .get_mut(task_id)
+            .ok_or_else(|| QueueError::UnknownLease(task_id.to_string()))
+    }
+
+    /// Write-then-rename so a crash never leaves a torn journal
+    fn persist(&self) -> Result<(), QueueError> {
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&self.journal)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(name: &str) -> (PathBuf, WorkerQueue<String, Vec<u8>>) {
+        let path = std::env::temp_dir().join(format!("haunti-{name}-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let queue = WorkerQueue::open(&path).unwrap();
+        (path, queue)
+    }
+
+    #[test]
+    fn test_leases_and_results_survive_restart() {
+        let (path, mut queue) = queue("worker-queue");
+        queue.lease("a", "task-a".into(), 1_000).unwrap();
+        queue.lease("b", "task-b".into(), 2_000).unwrap();
+        assert_eq!(queue.take_unstarted().unwrap().as_deref(), Some("task-a"));
+        queue.record_heartbeat(500, false).unwrap();
+
+        // Crash while "a" runs: it is leased again on reopen
+        let mut queue = WorkerQueue::<String, Vec<u8>>::open(&path).unwrap();
+        assert_eq!(queue.take_unstarted().unwrap().as_deref(), Some("task-a"));
+        queue.complete("a", vec![1, 2], 1_500).unwrap();
+
+        let digest = queue.heartbeat_digest().unwrap();
+        assert_eq!((digest.last_alive_ms, digest.missed), (500, 1));
+        assert_eq!(digest.in_flight, vec!["b".to_string()]);
+
+        let pending = queue.pending_results();
+        assert_eq!(pending, vec![("a".to_string(), 1_500, 1_000, vec![1, 2])]);
+        queue.finish("a").unwrap();
+        queue.record_heartbeat(2_000, true).unwrap();
+        assert!(queue.heartbeat_digest().is_none());
+        assert_eq!(queue.release_unstarted().unwrap(), vec!["b".to_string()]);
+        assert!(queue.is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_late_results_yield_only_to_finished_or_cancelled_work() {
+        assert_eq!(
+            resolve(900, 1_000, LeaseStatus::Reassigned { completed: true }),
+            Resolution::Submit
+        );
+        assert_eq!(resolve(1_500, 1_000, LeaseStatus::Expired), Resolution::Submit);
+        assert_eq!(
+            resolve(1_500, 1_000, LeaseStatus::Reassigned { completed: false }),
+            Resolution::Submit
+        );
+        assert!(matches!(
+            resolve(1_500, 1_000, LeaseStatus::Reassigned { completed: true }),
+            Resolution::Discard(_)
+        ));
+        assert!(matches!(
+            resolve(900, 1_000, LeaseStatus::Cancelled),
+            Resolution::Discard(_)
+        ));
+    }
+}
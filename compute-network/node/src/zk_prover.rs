@@ -4,10 +4,11 @@ use ark_ff::{BigInteger256, Field, PrimeField};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use gpu_proof::CudaProver;
 use plonky3::{
+    field::types::Field as _,
     fri::{FriConfig, FriProof},
     hash::poseidon::PoseidonHash,
     iop::{
-        target::Target,
+        target::{BoolTarget, Target},
         witness::{PartialWitness, WitnessWrite},
     },
     plonk::{
@@ -19,27 +20,59 @@ use plonky3::{
 };
 use rayon::prelude::*;
 use solana_program::keccak;
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::task_rng::{seed_limbs, Draw, DROPOUT_DENOMINATOR, SEED_LIMBS};
 
 const D: usize = 2;
 type C = PoseidonGoldilocksConfig;
 type F = <C as GenericConfig<D>>::F;
 
+mod cudart {
+    extern "C" {
+        pub fn cudaGetDeviceCount(count: *mut i32) -> i32;
+        pub fn cudaSetDevice(device: i32) -> i32;
+        pub fn cudaMemGetInfo(free: *mut usize, total: *mut usize) -> i32;
+    }
+}
+
 /// ZK Circuit Builder for AI Training Tasks
 pub struct TrainingCircuit {
     pub circuit_data: Arc<CircuitData<C, D>>,
     pub input_targets: Vec<Target>,
     pub output_targets: Vec<Target>,
+    /// The task's on-chain RNG seed as `SEED_LIMBS` u32 limbs; the last public inputs
+    pub rng_seed_targets: Vec<Target>,
+    /// Dropout rate in 1/256ths applied after every layer
+    pub dropout_rate: u8,
 }
 
 impl TrainingCircuit {
     pub fn new(model_layers: usize, input_size: usize) -> Self {
+        Self::with_dropout(model_layers, input_size, 0)
+    }
+
+    /// Circuit applying dropout at `dropout_rate`/256 with masks derived from
+    /// the task's RNG seed exactly as `TaskRng::dropout_keep` derives them
+    pub fn with_dropout(model_layers: usize, input_size: usize, dropout_rate: u8) -> Self {
         let config = CircuitConfig::standard_recursion_config();
         let mut builder = CircuitBuilder::<F, D>::new(config);
         
         // Define public inputs (model hash, task ID)
         let model_hash = builder.add_virtual_target();
         let task_id = builder.add_virtual_target();
+
+        // RNG seed, one u32 per limb
+        let rng_seed = builder.add_virtual_targets(SEED_LIMBS);
+        for &limb in &rng_seed {
+            builder.range_check(limb, 32);
+        }
         
         // Private inputs (encrypted weights, activations)
         let mut inputs = Vec::new();
@@ -48,11 +81,20 @@ impl TrainingCircuit {
         }
         
         // Neural network constraints
-        let mut outputs = Self::build_model_constraints(&mut builder, &inputs, model_layers);
+        let mut outputs = Self::build_model_constraints(
+            &mut builder,
+            &inputs,
+            model_layers,
+            &rng_seed,
+            dropout_rate,
+        );
         
         // Define public outputs (result hash)
         let result_hash = builder.hash(&outputs, PoseidonHash::new());
         builder.register_public_input(result_hash);
+
+        // The seed goes last so verifiers find it at a fixed offset from the end
+        builder.register_public_inputs(&rng_seed);
         
         let circuit_data = Arc::new(builder.build::<C>());
         
@@ -60,18 +102,44 @@ impl TrainingCircuit {
             circuit_data,
             input_targets: inputs,
             output_targets: outputs,
+            rng_seed_targets: rng_seed,
+            dropout_rate,
+        }
+    }
+
+    /// In-circuit `TaskRng::dropout_keep`: hash the seed with the unit's
+    /// coordinates and compare the low byte of the first output to the rate
+    fn dropout_keep(
+        builder: &mut CircuitBuilder<F, D>,
+        rng_seed: &[Target],
+        layer: u32,
+        unit: u32,
+        rate: u8,
+    ) -> BoolTarget {
+        let mut input = rng_seed.to_vec();
+        for value in [Draw::Dropout as u32, layer, unit] {
+            input.push(builder.constant(F::from_canonical_u32(value)));
         }
+        let draw = builder.hash_n_to_hash_no_pad::<PoseidonHash>(input).elements[0];
+        let bits = builder.split_le(draw, 64);
+        let low_byte = builder.le_sum(bits[..8].iter());
+        // low_byte + 256 - rate lies in [1, 512) and has bit 8 set exactly when
+        // low_byte >= rate
+        let shifted = builder.add_const(low_byte, F::from_canonical_u32(DROPOUT_DENOMINATOR - rate as u32));
+        builder.split_le(shifted, 9)[8]
     }
 
     fn build_model_constraints(
         builder: &mut CircuitBuilder<F, D>,
         inputs: &[Target],
         layers: usize,
+        rng_seed: &[Target],
+        dropout_rate: u8,
     ) -> Vec<Target> {
         let mut activations = inputs.to_vec();
         let layer_size = inputs.len() / layers;
         
-        for _ in 0..layers {
+        for layer in 0..layers {
             let weights = builder.add_virtual_targets(layer_size);
             let biases = builder.add_virtual_targets(layer_size);
             
@@ -94,23 +162,260 @@ impl TrainingCircuit {
                     act
                 })
                 .collect();
+
+            if dropout_rate > 0 {
+                activations = activations
+                    .into_iter()
+                    .enumerate()
+                    .map(|(unit, act)| {
+                        let keep = Self::dropout_keep(builder, rng_seed, layer as u32, unit as u32, dropout_rate);
+                        builder.mul(act, keep.target)
+                    })
+                    .collect();
+            }
         }
         
         activations
     }
 }
 
+/// CUDA devices visible to this process; empty without a driver
+pub fn visible_devices() -> Vec<usize> {
+    let mut count = 0i32;
+    if unsafe { cudart::cudaGetDeviceCount(&mut count) } != 0 {
+        return Vec::new();
+    }
+    (0..count.max(0) as usize).collect()
+}
+
+/// Free memory on `device`; 0 when the query fails, so the device takes no work
+fn free_device_memory(device: usize) -> u64 {
+    let (mut free, mut total) = (0usize, 0usize);
+    let code = unsafe {
+        match cudart::cudaSetDevice(device as i32) {
+            0 => cudart::cudaMemGetInfo(&mut free, &mut total),
+            code => code,
+        }
+    };
+    if code != 0 {
+        log::warn!("cudaMemGetInfo failed on device {} with CUDA error {}", device, code);
+        return 0;
+    }
+    free as u64
+}
+
+/// How proving work is spread over the GPUs
+#[derive(Debug, Clone, Copy)]
+pub struct DevicePoolConfig {
+    /// Proofs queued or running on one device, across concurrent batches
+    pub max_queue_per_device: usize,
+    /// Device memory left to other users of the GPU (FHE runtime, warm pool)
+    pub reserved_bytes: u64,
+    /// Prove on the CPU what saturated devices cannot take; otherwise the
+    /// surplus waits in the least loaded queue of a device with memory for it
+    pub cpu_fallback: bool,
+}
+
+impl Default for DevicePoolConfig {
+    fn default() -> Self {
+        Self {
+            max_queue_per_device: 8,
+            reserved_bytes: 1 << 30,
+            cpu_fallback: true,
+        }
+    }
+}
+
+/// State of one device when a batch is planned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DeviceCapacity {
+    free_bytes: u64,
+    queued: usize,
+}
+
+/// Assignment of a batch: per job the index of its device, `None` for the CPU,
+/// and per device how many proofs fit in its memory at once
+#[derive(Debug, PartialEq, Eq)]
+struct Plan {
+    assignments: Vec<Option<usize>>,
+    concurrency: Vec<usize>,
+}
+
+/// Spread `jobs` proofs of `footprint` bytes each over `devices`. Each job goes
+/// to the device with the most free queue slots; a device without memory for
+/// even one proof takes none. Jobs left when every queue is full go to the CPU,
+/// or without CPU fallback to the device with the fewest queued proofs among
+/// those that can hold one; with no such device the CPU is the only option.
+fn plan(jobs: usize, devices: &[DeviceCapacity], footprint: u64, config: &DevicePoolConfig) -> Plan {
+    let concurrency: Vec<usize> = devices
+        .iter()
+        .map(|device| {
+            let usable = device.free_bytes.saturating_sub(config.reserved_bytes);
+            (usable / footprint.max(1)).min(config.max_queue_per_device as u64) as usize
+        })
+        .collect();
+    let mut slots: Vec<usize> = devices
+        .iter()
+        .zip(&concurrency)
+        .map(|(device, &fit)| match fit {
+            0 => 0,
+            _ => config.max_queue_per_device.saturating_sub(device.queued),
+        })
+        .collect();
+    let mut queued: Vec<usize> = devices.iter().map(|device| device.queued).collect();
+
+    let assignments = (0..jobs)
+        .map(|_| {
+            let open = (0..devices.len())
+                .filter(|&i| slots[i] > 0)
+                .max_by_key(|&i| (slots[i], std::cmp::Reverse(i)));
+            let device = match open {
+                Some(i) => {
+                    slots[i] -= 1;
+                    Some(i)
+                }
+                None if config.cpu_fallback => None,
+                None => (0..devices.len())
+                    .filter(|&i| concurrency[i] > 0)
+                    .min_by_key(|&i| (queued[i], i)),
+            };
+            if let Some(i) = device {
+                queued[i] += 1;
+            }
+            device
+        })
+        .collect();
+
+    Plan { assignments, concurrency }
+}
+
+/// Proofs completed on one device, or on the CPU
+#[derive(Default)]
+struct ProverStats {
+    queued: AtomicUsize,
+    proofs: AtomicU64,
+    busy_micros: AtomicU64,
+}
+
+impl ProverStats {
+    fn finished(&self, proofs: usize, elapsed: Duration) {
+        self.queued.fetch_sub(proofs, Ordering::Relaxed);
+        self.proofs.fetch_add(proofs as u64, Ordering::Relaxed);
+        self.busy_micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self, device: Option<usize>) -> DeviceMetrics {
+        let proofs = self.proofs.load(Ordering::Relaxed);
+        let busy_secs = self.busy_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        DeviceMetrics {
+            device,
+            queued: self.queued.load(Ordering::Relaxed),
+            proofs,
+            proofs_per_sec: if busy_secs > 0.0 { proofs as f64 / busy_secs } else { 0.0 },
+        }
+    }
+}
+
+/// Throughput of one prover backend since startup
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceMetrics {
+    /// CUDA device id, `None` for the CPU fallback
+    pub device: Option<usize>,
+    /// Proofs queued or running
+    pub queued: usize,
+    pub proofs: u64,
+    /// Proofs per second of time the backend spent proving
+    pub proofs_per_sec: f64,
+}
+
+struct GpuDevice {
+    id: usize,
+    prover: Arc<CudaProver>,
+    stats: ProverStats,
+}
+
+/// The GPUs a prover shards batches over, each with its own queue
+pub struct DevicePool {
+    devices: Vec<GpuDevice>,
+    cpu: ProverStats,
+    config: DevicePoolConfig,
+}
+
+impl DevicePool {
+    pub fn new(device_ids: &[usize], config: DevicePoolConfig) -> Self {
+        let devices = device_ids
+            .iter()
+            .map(|&id| GpuDevice {
+                id,
+                prover: Arc::new(CudaProver::new(id)),
+                stats: ProverStats::default(),
+            })
+            .collect();
+        Self {
+            devices,
+            cpu: ProverStats::default(),
+            config,
+        }
+    }
+
+    /// Pool over every visible CUDA device; CPU only without one
+    pub fn visible(config: DevicePoolConfig) -> Self {
+        Self::new(&visible_devices(), config)
+    }
+
+    /// Per-device throughput, GPUs in pool order then the CPU fallback
+    pub fn metrics(&self) -> Vec<DeviceMetrics> {
+        self.devices
+            .iter()
+            .map(|device| device.stats.snapshot(Some(device.id)))
+            .chain(std::iter::once(self.cpu.snapshot(None)))
+            .collect()
+    }
+
+    /// Plan a batch and reserve its queue slots. Concurrent batches read queue
+    /// depths before either reserves, so the per-device cap is approximate.
+    fn schedule(&self, jobs: usize, footprint: u64) -> Plan {
+        let capacities: Vec<DeviceCapacity> = self
+            .devices
+            .iter()
+            .map(|device| DeviceCapacity {
+                free_bytes: free_device_memory(device.id),
+                queued: device.stats.queued.load(Ordering::Relaxed),
+            })
+            .collect();
+        let plan = plan(jobs, &capacities, footprint, &self.config);
+        for assignment in &plan.assignments {
+            let stats = match assignment {
+                Some(i) => &self.devices[*i].stats,
+                None => &self.cpu,
+            };
+            stats.queued.fetch_add(1, Ordering::Relaxed);
+        }
+        plan
+    }
+}
+
 /// ZK Prover with GPU Acceleration
 pub struct HauntiProver {
     circuit: Arc<TrainingCircuit>,
-    cuda_prover: Arc<CudaProver>,
+    devices: DevicePool,
     fri_config: FriConfig,
 }
 
 impl HauntiProver {
+    /// Prover on a single device; surplus proofs queue on it rather than
+    /// falling back to the CPU
     pub fn new(model_layers: usize, input_size: usize, gpu_device_id: usize) -> Self {
+        let config = DevicePoolConfig {
+            cpu_fallback: false,
+            ..Default::default()
+        };
+        Self::with_devices(model_layers, input_size, DevicePool::new(&[gpu_device_id], config))
+    }
+
+    /// Prover sharding batches over `devices`
+    pub fn with_devices(model_layers: usize, input_size: usize, devices: DevicePool) -> Self {
         let circuit = Arc::new(TrainingCircuit::new(model_layers, input_size));
-        let cuda_prover = Arc::new(CudaProver::new(gpu_device_id));
         
         let fri_config = FriConfig {
             rate_bits: 4,
@@ -121,59 +426,125 @@ impl HauntiProver {
         
         Self {
             circuit,
-            cuda_prover,
+            devices,
             fri_config,
         }
     }
 
-    /// Generate proof for a training task batch
+    pub fn device_metrics(&self) -> Vec<DeviceMetrics> {
+        self.devices.metrics()
+    }
+
+    /// Device memory one proof occupies: the low-degree extension of every wire
+    /// column
+    fn proof_footprint(&self) -> u64 {
+        let common = &self.circuit.circuit_data.common;
+        let lde_rows = (common.degree() as u64) << self.fri_config.rate_bits;
+        lde_rows * common.config.num_wires as u64 * std::mem::size_of::<F>() as u64
+    }
+
+    fn witness(&self, model_hash: &[u8; 32], rng_seed: &[u8; 32], weights: &[F], acts: &[F]) -> PartialWitness<F> {
+        let circuit_data = &self.circuit.circuit_data;
+        let mut witness = PartialWitness::new();
+        
+        // Public inputs
+        let model_hash_f = F::from_be_bytes_mod_order(model_hash);
+        witness.set_target(circuit_data.prover_only.public_inputs[0], model_hash_f);
+        for (target, limb) in self.circuit.rng_seed_targets.iter().zip(seed_limbs(rng_seed)) {
+            witness.set_target(*target, limb);
+        }
+        
+        // Private inputs
+        weights.iter().chain(acts.iter())
+            .zip(self.circuit.input_targets.iter())
+            .for_each(|(val, target)| {
+                witness.set_target(*target, *val);
+            });
+        witness
+    }
+
+    fn compress(&self, proof: Proof<F, C, D>, start: Instant, backend: &str) -> (CompressedProof<FriProof>, [u8; 32]) {
+        let compressed_proof = proof.compress(&self.circuit.circuit_data.fri_params);
+        let proof_digest = keccak::hash(&compressed_proof.to_bytes());
+        
+        log::info!(
+            "Proof generated on {} in {:?} | Size: {} KB",
+            backend,
+            start.elapsed(),
+            compressed_proof.to_bytes().len() / 1024
+        );
+        
+        (compressed_proof, proof_digest.0)
+    }
+
+    /// Generate proof for a training task batch, sharded over the pool's
+    /// devices. Each device works through its share in waves of as many proofs
+    /// as fit in its free memory; what no device can take is proven on the CPU.
+    /// `rng_seeds` are the tasks' on-chain seeds, which fix their dropout masks.
     pub fn prove_training_batch(
         &self,
         model_hashes: &[[u8; 32]],
+        rng_seeds: &[[u8; 32]],
         encrypted_weights: &[Vec<F>],
         activations: &[Vec<F>],
     ) -> Vec<(CompressedProof<FriProof>, [u8; 32])> {
+        let jobs = model_hashes
+            .len()
+            .min(rng_seeds.len())
+            .min(encrypted_weights.len())
+            .min(activations.len());
+        let plan = self.devices.schedule(jobs, self.proof_footprint());
+        let witness = |job: usize| {
+            self.witness(&model_hashes[job], &rng_seeds[job], &encrypted_weights[job], &activations[job])
+        };
+
+        let mut queues = vec![Vec::new(); self.devices.devices.len()];
+        let mut cpu_jobs = Vec::new();
+        for (job, assignment) in plan.assignments.iter().enumerate() {
+            match assignment {
+                Some(i) => queues[*i].push(job),
+                None => cpu_jobs.push(job),
+            }
+        }
+
         let circuit_data = &self.circuit.circuit_data;
-        let cuda_prover = &self.cuda_prover;
-        
-        model_hashes
-            .par_iter()
-            .zip(encrypted_weights.par_iter())
-            .zip(activations.par_iter())
-            .map(|((model_hash, weights), acts)| {
-                let mut witness = PartialWitness::new();
-                
-                // Public inputs
-                let model_hash_f = F::from_be_bytes_mod_order(model_hash);
-                witness.set_target(circuit_data.prover_only.public_inputs[0], model_hash_f);
-                
-                // Private inputs
-                weights.iter().chain(acts.iter())
-                    .zip(self.circuit.input_targets.iter())
-                    .for_each(|(val, target)| {
-                        witness.set_target(*target, *val);
-                    });
-                
-                // GPU-accelerated proof generation
-                let start = Instant::now();
-                let proof = cuda_prover.prove(
-                    circuit_data,
-                    witness,
-                    &self.fri_config,
-                );
-                
-                let compressed_proof = proof.compress(&circuit_data.fri_params);
-                let proof_digest = keccak::hash(&compressed_proof.to_bytes());
-                
-                log::info!(
-                    "Proof generated in {:?} | Size: {} KB",
-                    start.elapsed(),
-                    compressed_proof.to_bytes().len() / 1024
-                );
-                
-                (compressed_proof, proof_digest.0)
+        let mut proofs: Vec<_> = (0..=queues.len())
+            .into_par_iter()
+            .flat_map(|lane| {
+                let mut done = Vec::new();
+                let Some(device) = self.devices.devices.get(lane) else {
+                    // CPU fallback
+                    let start = Instant::now();
+                    done.par_extend(cpu_jobs.par_iter().map(|&job| {
+                        let proof_start = Instant::now();
+                        let proof = circuit_data
+                            .prove(witness(job))
+                            .expect("training witness satisfies the circuit");
+                        (job, self.compress(proof, proof_start, "cpu"))
+                    }));
+                    if !cpu_jobs.is_empty() {
+                        self.devices.cpu.finished(cpu_jobs.len(), start.elapsed());
+                    }
+                    return done;
+                };
+
+                // GPU-accelerated proof generation, one memory-sized wave at a time
+                let label = format!("gpu{}", device.id);
+                for wave in queues[lane].chunks(plan.concurrency[lane].max(1)) {
+                    let start = Instant::now();
+                    done.par_extend(wave.par_iter().map(|&job| {
+                        let proof_start = Instant::now();
+                        let proof = device.prover.prove(circuit_data, witness(job), &self.fri_config);
+                        (job, self.compress(proof, proof_start, &label))
+                    }));
+                    device.stats.finished(wave.len(), start.elapsed());
+                }
+                done
             })
-            .collect()
+            .collect();
+
+        proofs.sort_unstable_by_key(|(job, _)| *job);
+        proofs.into_iter().map(|(_, proof)| proof).collect()
     }
 }
 
@@ -182,6 +553,7 @@ pub fn verify_proof(
     proof: &CompressedProof<FriProof>,
     circuit_data: &CircuitData<C, D>,
     public_inputs: &[F],
+    rng_seed: &[u8; 32],
 ) -> Result<(), ProofError> {
     let decompressed_proof = proof.decompress(&circuit_data.fri_params)?;
     circuit_data.verify(decompressed_proof.clone())?;
@@ -192,6 +564,13 @@ pub fn verify_proof(
     if expected_hash != computed_hash {
         return Err(ProofError::InputMismatch);
     }
+
+    // The proof must be over the task's committed randomness
+    let proven = &decompressed_proof.public_inputs;
+    let proven_seed = proven.len().checked_sub(SEED_LIMBS).map(|at| &proven[at..]);
+    if proven_seed != Some(&seed_limbs(rng_seed)[..]) {
+        return Err(ProofError::RngSeedMismatch);
+    }
     
     Ok(())
 }
@@ -201,6 +580,7 @@ pub enum ProofError {
     SerializationError,
     VerificationFailed,
     InputMismatch,
+    RngSeedMismatch,
     GpuAccelError(String),
 }
 
@@ -238,17 +618,81 @@ mod tests {
         let weights: Vec<F> = (0..256*3).map(|_| F::rand(&mut rng)).collect();
         let activations: Vec<F> = (0..256).map(|_| F::rand(&mut rng)).collect();
         
+        let rng_seed = [7u8; 32];
+        
         // Generate proof
         let (proof, digest) = prover.prove_training_batch(
             &[model_hash],
+            &[rng_seed],
             &[weights],
             &[activations],
         ).remove(0);
         
         // Verify on-chain
         let public_inputs = vec![F::from_be_bytes_mod_order(&model_hash)];
-        verify_proof(&proof, &prover.circuit.circuit_data, &public_inputs).unwrap();
+        verify_proof(&proof, &prover.circuit.circuit_data, &public_inputs, &rng_seed).unwrap();
         
         assert_ne!(digest, [0u8; 32]);
+
+        // A proof is only good for the seed it was generated under
+        assert!(matches!(
+            verify_proof(&proof, &prover.circuit.circuit_data, &public_inputs, &[8u8; 32]),
+            Err(ProofError::RngSeedMismatch)
+        ));
+    }
+
+    const GIB: u64 = 1 << 30;
+
+    fn config(cpu_fallback: bool) -> DevicePoolConfig {
+        DevicePoolConfig {
+            max_queue_per_device: 4,
+            reserved_bytes: GIB,
+            cpu_fallback,
+        }
+    }
+
+    fn device(free_gib: u64, queued: usize) -> DeviceCapacity {
+        DeviceCapacity { free_bytes: free_gib * GIB, queued }
+    }
+
+    #[test]
+    fn test_plan_balances_devices_and_sizes_waves_by_memory() {
+        let batch = plan(4, &[device(9, 0), device(3, 2)], 2 * GIB, &config(true));
+        // Device 0 starts with 4 open slots, device 1 with 2; ties go to the lower index
+        assert_eq!(batch.assignments, vec![Some(0), Some(0), Some(0), Some(1)]);
+        // 8 usable GiB fit all four slots; 2 usable GiB fit one proof at a time
+        assert_eq!(batch.concurrency, vec![4, 1]);
+    }
+
+    #[test]
+    fn test_saturated_devices_fall_back_to_cpu() {
+        // Device 1 cannot hold a single proof beyond the reserve
+        let devices = [device(5, 3), device(2, 0)];
+        let fallback = plan(3, &devices, 2 * GIB, &config(true));
+        assert_eq!(fallback.assignments, vec![Some(0), None, None]);
+
+        // Without fallback the surplus waits on the least loaded device that
+        // fits a proof, never on device 1, where it could only run out of memory
+        let queued = plan(3, &devices, 2 * GIB, &config(false));
+        assert_eq!(queued.assignments, vec![Some(0), Some(0), Some(0)]);
+
+        // No device fits a proof: the CPU takes them rather than a device
+        let starved = plan(2, &[device(2, 0), device(1, 0)], 2 * GIB, &config(false));
+        assert_eq!(starved.assignments, vec![None, None]);
+
+        // No devices at all: everything on the CPU
+        assert_eq!(plan(2, &[], GIB, &config(false)).assignments, vec![None, None]);
+    }
+
+    #[test]
+    fn test_device_throughput_metrics() {
+        let stats = ProverStats::default();
+        stats.queued.store(5, Ordering::Relaxed);
+        stats.finished(4, Duration::from_secs(2));
+        assert_eq!(
+            stats.snapshot(Some(1)),
+            DeviceMetrics { device: Some(1), queued: 1, proofs: 4, proofs_per_sec: 2.0 }
+        );
+        assert_eq!(ProverStats::default().snapshot(None).proofs_per_sec, 0.0);
     }
 }
@@ -2,11 +2,16 @@
 
 use std::collections::{BinaryHeap, HashMap};
 use std::cmp::{Ordering, Reverse};
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
 use thiserror::Error;
+use token_vault::DelegationPool;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct GpuResource {
     pub id: String,
+    /// Worker node that registered this GPU
+    pub worker: Pubkey,
     pub total_memory: u64,
     pub used_memory: u64,
     pub cuda_cores: u32,
@@ -14,6 +19,14 @@ pub struct GpuResource {
     pub fp32_perf: f32,
     pub fp16_support: bool,
     pub current_utilization: f32,
+    /// Region label reported at worker registration (e.g. "eu-west")
+    pub region: String,
+    /// Round-trip time measured by coordinator latency probes
+    pub probe_rtt_ms: Option<u32>,
+    /// Operator plus delegated stake backing this worker
+    pub stake_weight: u64,
+    /// Multiplier in (0, 1] lowered when the worker breaches latency SLOs
+    pub slo_weight: f32,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -24,8 +37,38 @@ pub struct ComputeTask {
     pub bandwidth_threshold: u32,
     pub fp16_required: bool,
     pub priority: u8,
+    /// Regions the consumer prefers, empty means any
+    pub preferred_regions: Vec<String>,
+    /// Hard upper bound on probed worker RTT
+    pub max_rtt_ms: Option<u32>,
 }
 
+/// GPU memory held for a confirmed capacity booking during its window
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reservation {
+    pub booking_id: u64,
+    pub gpu_id: String,
+    pub required_memory: u64,
+    /// Unix seconds
+    pub starts_at: i64,
+    pub ends_at: i64,
+}
+
+/// Placement counters exported by the scheduler
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlacementStats {
+    pub total_placements: u64,
+    pub cross_region_placements: u64,
+}
+
+/// Score multiplier applied to GPUs outside the task's preferred regions
+const CROSS_REGION_PENALTY: f32 = 1.5;
+
+/// Stake amount that earns one step of scheduling boost
+const STAKE_BOOST_UNIT: u64 = 100_000_000_000;
+/// Cap on the number of boost steps a worker can accumulate
+const MAX_STAKE_BOOST_STEPS: u64 = 10;
+
 #[derive(Error, Debug)]
 pub enum BinPackError {
     #[error("Insufficient resource for task {0}: {1}")]
@@ -36,6 +79,9 @@ pub enum BinPackError {
     
     #[error("Scheduler overloaded: {0}")]
     SchedulerOverload(String),
+
+    #[error("Chain state unavailable: {0}")]
+    ChainState(String),
 }
 
 #[derive(PartialEq, PartialOrd)]
@@ -67,10 +113,16 @@ impl PackingStrategy for MultiDimFirstFit {
         task: &ComputeTask,
         gpu_pool: &mut HashMap<String, GpuResource>,
     ) -> Result<String, BinPackError> {
-        for gpu in gpu_pool.values_mut() {
-            if meets_task_requirements(gpu, task) {
-                allocate_resources(gpu, task)?;
-                return Ok(gpu.id.clone());
+        // In-region candidates first, then fall back to any region
+        for in_region_pass in [true, false] {
+            for gpu in gpu_pool.values_mut() {
+                if in_region_pass && !in_preferred_region(gpu, task) {
+                    continue;
+                }
+                if meets_task_requirements(gpu, task) {
+                    allocate_resources(gpu, task)?;
+                    return Ok(gpu.id.clone());
+                }
             }
         }
         Err(BinPackError::InsufficientResource(
@@ -90,7 +142,11 @@ impl PackingStrategy for BestFitWithScoring {
         
         for gpu in gpu_pool.values() {
             if meets_task_requirements(gpu, task) {
-                let score = calculate_fitness_score(gpu, task);
+                let mut score = calculate_fitness_score(gpu, task);
+                if !in_preferred_region(gpu, task) {
+                    score *= CROSS_REGION_PENALTY;
+                }
+                score /= stake_boost(gpu.stake_weight) * gpu.slo_weight;
                 heap.push((Reverse(GpuFitnessScore(score)), gpu.id.clone()));
             }
         }
@@ -115,7 +171,69 @@ fn meets_task_requirements(gpu: &GpuResource, task: &ComputeTask) -> bool {
     memory_available >= task.required_memory &&
     cores_available >= task.min_cuda_cores as f32 &&
     gpu.memory_bandwidth >= task.bandwidth_threshold &&
-    (!task.fp16_required || gpu.fp16_support)
+    (!task.fp16_required || gpu.fp16_support) &&
+    within_latency_budget(gpu, task)
+}
+
+fn in_preferred_region(gpu: &GpuResource, task: &ComputeTask) -> bool {
+    task.preferred_regions.is_empty() || task.preferred_regions.contains(&gpu.region)
+}
+
+/// Delegated stake makes a worker up to 50% more attractive
+fn stake_boost(stake_weight: u64) -> f32 {
+    let steps = (stake_weight / STAKE_BOOST_UNIT).min(MAX_STAKE_BOOST_STEPS);
+    1.0 + steps as f32 * 0.05
+}
+
+/// Delegation pool PDA of a worker in the token vault program
+pub fn delegation_pool_address(worker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"delegation", worker.as_ref()], &token_vault::ID).0
+}
+
+/// Fill each GPU's `stake_weight` from its worker's delegation pool. `fetch`
+/// returns the raw account data at an address; a worker without a pool, or
+/// with an undecodable one, carries no stake
+pub fn apply_stake_weights<F>(gpus: &mut [GpuResource], mut fetch: F)
+where
+    F: FnMut(&Pubkey) -> Option<Vec<u8>>,
+{
+    for gpu in gpus {
+        gpu.stake_weight = fetch(&delegation_pool_address(&gpu.worker))
+            .and_then(|data| DelegationPool::try_deserialize(&mut data.as_slice()).ok())
+            .map_or(0, |pool| pool.scheduling_weight());
+    }
+}
+
+/// Read the delegation pools of all `gpus` in one RPC round trip
+pub async fn load_stake_weights(
+    rpc: &RpcClient,
+    gpus: &mut [GpuResource],
+) -> Result<(), BinPackError> {
+    let addresses: Vec<Pubkey> = gpus
+        .iter()
+        .map(|gpu| delegation_pool_address(&gpu.worker))
+        .collect();
+    let accounts = rpc
+        .get_multiple_accounts(&addresses)
+        .await
+        .map_err(|e| BinPackError::ChainState(e.to_string()))?;
+    let pools: HashMap<Pubkey, Vec<u8>> = addresses
+        .into_iter()
+        .zip(accounts)
+        .filter_map(|(address, account)| account.map(|account| (address, account.data)))
+        .collect();
+
+    apply_stake_weights(gpus, |address| pools.get(address).cloned());
+    Ok(())
+}
+
+fn within_latency_budget(gpu: &GpuResource, task: &ComputeTask) -> bool {
+    match (task.max_rtt_ms, gpu.probe_rtt_ms) {
+        (None, _) => true,
+        // Unprobed workers cannot satisfy an RTT bound
+        (Some(_), None) => false,
+        (Some(max_rtt), Some(rtt)) => rtt <= max_rtt,
+    }
 }
 
 fn calculate_fitness_score(gpu: &GpuResource, task: &ComputeTask) -> f32 {
@@ -152,6 +270,9 @@ pub struct ResourceScheduler {
     strategies: HashMap<&'static str, Box<dyn PackingStrategy>>,
     current_strategy: &'static str,
     gpu_pool: HashMap<String, GpuResource>,
+    placement_stats: PlacementStats,
+    /// Reservations by booking id, with whether their memory is currently held
+    reservations: HashMap<u64, (Reservation, bool)>,
 }
 
 impl ResourceScheduler {
@@ -164,9 +285,33 @@ impl ResourceScheduler {
             strategies,
             current_strategy: "best_fit",
             gpu_pool: gpus.into_iter().map(|g| (g.id.clone(), g)).collect(),
+            placement_stats: PlacementStats::default(),
+            reservations: HashMap::new(),
         }
     }
     
+    /// Build the scheduler with stake weights read from the workers'
+    /// on-chain delegation pools
+    pub async fn with_delegated_stake(
+        rpc: &RpcClient,
+        mut gpus: Vec<GpuResource>,
+    ) -> Result<Self, BinPackError> {
+        load_stake_weights(rpc, &mut gpus).await?;
+        Ok(Self::new(gpus))
+    }
+
+    /// Re-read delegation pools so stake moved since startup shifts placement
+    pub async fn refresh_stake_weights(&mut self, rpc: &RpcClient) -> Result<(), BinPackError> {
+        let mut gpus: Vec<GpuResource> = self.gpu_pool.values().cloned().collect();
+        load_stake_weights(rpc, &mut gpus).await?;
+        for gpu in gpus {
+            if let Some(pooled) = self.gpu_pool.get_mut(&gpu.id) {
+                pooled.stake_weight = gpu.stake_weight;
+            }
+        }
+        Ok(())
+    }
+
     pub fn schedule_task(
         &mut self,
         task: ComputeTask,
@@ -175,7 +320,110 @@ impl ResourceScheduler {
             .get(self.current_strategy)
             .ok_or_else(|| BinPackError::ResourceConflict("Invalid strategy".into()))?;
         
-        strategy.schedule(&task, &mut self.gpu_pool)
+        let gpu_id = strategy.schedule(&task, &mut self.gpu_pool)?;
+
+        self.placement_stats.total_placements += 1;
+        if !in_preferred_region(&self.gpu_pool[&gpu_id], &task) {
+            self.placement_stats.cross_region_placements += 1;
+            log::info!(
+                "Task {} placed cross-region on GPU {} ({})",
+                task.task_id,
+                gpu_id,
+                self.gpu_pool[&gpu_id].region
+            );
+        }
+
+        Ok(gpu_id)
+    }
+
+    /// Apply the SLO-derived scheduling weight for a worker GPU
+    pub fn set_slo_weight(&mut self, gpu_id: &str, weight: f32) -> Result<(), BinPackError> {
+        let gpu = self.gpu_pool.get_mut(gpu_id)
+            .ok_or_else(|| BinPackError::ResourceConflict("GPU not found".into()))?;
+        gpu.slo_weight = weight.clamp(0.1, 1.0);
+        Ok(())
+    }
+
+    /// Record a coordinator latency probe result for a worker GPU
+    pub fn record_latency_probe(&mut self, gpu_id: &str, rtt_ms: u32) -> Result<(), BinPackError> {
+        let gpu = self.gpu_pool.get_mut(gpu_id)
+            .ok_or_else(|| BinPackError::ResourceConflict("GPU not found".into()))?;
+        gpu.probe_rtt_ms = Some(rtt_ms);
+        Ok(())
+    }
+
+    /// Track a confirmed booking; its memory is held once `apply_reservations`
+    /// reaches its window
+    pub fn add_reservation(&mut self, reservation: Reservation) -> Result<(), BinPackError> {
+        if !self.gpu_pool.contains_key(&reservation.gpu_id) {
+            return Err(BinPackError::ResourceConflict("GPU not found".into()));
+        }
+        self.reservations.insert(reservation.booking_id, (reservation, false));
+        Ok(())
+    }
+
+    /// Drop a reservation, returning any memory it holds
+    pub fn release_reservation(&mut self, booking_id: u64) {
+        if let Some((reservation, true)) = self.reservations.remove(&booking_id) {
+            if let Some(gpu) = self.gpu_pool.get_mut(&reservation.gpu_id) {
+                gpu.used_memory = gpu.used_memory.saturating_sub(reservation.required_memory);
+            }
+        }
+    }
+
+    /// Hold memory for reservations whose window has opened and free it for those
+    /// that have ended, so unreserved tasks cannot take booked capacity
+    pub fn apply_reservations(&mut self, now: i64) {
+        let ended: Vec<u64> = self
+            .reservations
+            .values()
+            .filter(|(reservation, _)| now >= reservation.ends_at)
+            .map(|(reservation, _)| reservation.booking_id)
+            .collect();
+        for booking_id in ended {
+            self.release_reservation(booking_id);
+        }
+
+        for (reservation, held) in self.reservations.values_mut() {
+            if *held || now < reservation.starts_at {
+                continue;
+            }
+            let Some(gpu) = self.gpu_pool.get_mut(&reservation.gpu_id) else {
+                continue;
+            };
+            if gpu.used_memory + reservation.required_memory > gpu.total_memory {
+                log::warn!(
+                    "GPU {} cannot hold memory for booking {}",
+                    gpu.id,
+                    reservation.booking_id
+                );
+                continue;
+            }
+            gpu.used_memory += reservation.required_memory;
+            *held = true;
+        }
+    }
+
+    /// Place a task inside a held reservation, bypassing the packing strategy
+    pub fn schedule_reserved(
+        &mut self,
+        task: &ComputeTask,
+        booking_id: u64,
+    ) -> Result<String, BinPackError> {
+        match self.reservations.get(&booking_id) {
+            Some((reservation, true)) if task.required_memory <= reservation.required_memory => {
+                self.placement_stats.total_placements += 1;
+                Ok(reservation.gpu_id.clone())
+            }
+            _ => Err(BinPackError::InsufficientResource(
+                task.task_id.clone(),
+                format!("No active reservation for booking {booking_id}"),
+            )),
+        }
+    }
+
+    pub fn placement_stats(&self) -> PlacementStats {
+        self.placement_stats
     }
     
     pub fn add_gpu(&mut self, gpu: GpuResource) {
@@ -196,6 +444,7 @@ mod tests {
     fn create_test_gpu(id: &str) -> GpuResource {
         GpuResource {
             id: id.into(),
+            worker: Pubkey::new_unique(),
             total_memory: 32_768, // 32GB
             used_memory: 0,
             cuda_cores: 10_240,
@@ -203,6 +452,10 @@ mod tests {
             fp32_perf: 30.1, // TFLOPS
             fp16_support: true,
             current_utilization: 0.0,
+            region: "us-east".into(),
+            probe_rtt_ms: Some(20),
+            stake_weight: 0,
+            slo_weight: 1.0,
         }
     }
 
@@ -223,6 +476,8 @@ mod tests {
             bandwidth_threshold: 500,
             fp16_required: true,
             priority: 1,
+            preferred_regions: vec![],
+            max_rtt_ms: None,
         };
         
         let result = scheduler.schedule_task(task);
@@ -240,9 +495,118 @@ mod tests {
             bandwidth_threshold: 500,
             fp16_required: false,
             priority: 1,
+            preferred_regions: vec![],
+            max_rtt_ms: None,
         };
         
         let result = scheduler.schedule_task(task);
         assert!(matches!(result, Err(BinPackError::InsufficientResource(_, _))));
     }
+
+    #[test]
+    fn test_reservation_holds_memory_during_window() {
+        let mut scheduler = ResourceScheduler::new(vec![create_test_gpu("gpu1")]);
+        scheduler
+            .add_reservation(Reservation {
+                booking_id: 7,
+                gpu_id: "gpu1".into(),
+                required_memory: 24_576,
+                starts_at: 100,
+                ends_at: 200,
+            })
+            .unwrap();
+        let task = ComputeTask {
+            task_id: "task1".into(),
+            required_memory: 16_384,
+            min_cuda_cores: 1024,
+            bandwidth_threshold: 500,
+            fp16_required: false,
+            priority: 1,
+            preferred_regions: vec![],
+            max_rtt_ms: None,
+        };
+
+        scheduler.apply_reservations(50);
+        assert!(scheduler.schedule_reserved(&task, 7).is_err());
+
+        // Inside the window only the booked task fits
+        scheduler.apply_reservations(100);
+        assert_eq!(scheduler.schedule_reserved(&task, 7).unwrap(), "gpu1");
+        assert!(scheduler.schedule_task(task.clone()).is_err());
+
+        scheduler.apply_reservations(200);
+        assert!(scheduler.schedule_reserved(&task, 7).is_err());
+        assert_eq!(scheduler.schedule_task(task).unwrap(), "gpu1");
+    }
+
+    #[test]
+    fn test_region_preference_and_rtt_bound() {
+        let mut eu_gpu = create_test_gpu("gpu-eu");
+        eu_gpu.region = "eu-west".into();
+        eu_gpu.probe_rtt_ms = Some(80);
+
+        let mut scheduler = ResourceScheduler::new(vec![create_test_gpu("gpu-us"), eu_gpu]);
+        let mut task = ComputeTask {
+            task_id: "task1".into(),
+            required_memory: 1_024,
+            min_cuda_cores: 512,
+            bandwidth_threshold: 500,
+            fp16_required: false,
+            priority: 1,
+            preferred_regions: vec!["eu-west".into()],
+            max_rtt_ms: None,
+        };
+
+        assert_eq!(scheduler.schedule_task(task.clone()).unwrap(), "gpu-eu");
+        assert_eq!(scheduler.placement_stats().cross_region_placements, 0);
+
+        // RTT bound excludes the preferred region, forcing a cross-region placement
+        task.task_id = "task2".into();
+        task.max_rtt_ms = Some(50);
+        assert_eq!(scheduler.schedule_task(task).unwrap(), "gpu-us");
+        assert_eq!(scheduler.placement_stats().cross_region_placements, 1);
+    }
+
+    #[test]
+    fn test_delegated_worker_ranks_higher() {
+        let gpu1 = create_test_gpu("gpu1");
+        let gpu2 = create_test_gpu("gpu2");
+        let task = ComputeTask {
+            task_id: "task1".into(),
+            required_memory: 8_192,
+            min_cuda_cores: 1024,
+            bandwidth_threshold: 500,
+            fp16_required: false,
+            priority: 1,
+            preferred_regions: vec![],
+            max_rtt_ms: None,
+        };
+
+        // Identical GPUs tie on score, which breaks towards the larger id
+        let mut scheduler = ResourceScheduler::new(vec![gpu1.clone(), gpu2.clone()]);
+        assert_eq!(scheduler.schedule_task(task.clone()).unwrap(), "gpu2");
+
+        let pool = DelegationPool {
+            operator: Pubkey::new_unique(),
+            worker: gpu1.worker,
+            stake_pool: Pubkey::new_unique(),
+            commission_bps: 500,
+            total_delegated: 5 * STAKE_BOOST_UNIT,
+            total_shares: 5 * STAKE_BOOST_UNIT,
+            bump: 255,
+        };
+        let mut data = Vec::new();
+        anchor_lang::AccountSerialize::try_serialize(&pool, &mut data).unwrap();
+        let pool_address = delegation_pool_address(&gpu1.worker);
+
+        let mut gpus = vec![gpu1, gpu2];
+        apply_stake_weights(&mut gpus, |address| {
+            (*address == pool_address).then(|| data.clone())
+        });
+        assert_eq!(gpus[0].stake_weight, 5 * STAKE_BOOST_UNIT);
+        assert_eq!(gpus[1].stake_weight, 0);
+
+        let mut scheduler = ResourceScheduler::new(gpus);
+        assert_eq!(scheduler.schedule_task(task).unwrap(), "gpu1");
+    }
 }
@@ -0,0 +1,493 @@
+//! Off-chain order book matching pre-booked task demand to worker capacity forecasts
+//!
+//! Workers publish the GPU-hours they expect to have free in upcoming windows; task
+//! owners pre-book hours in a window against a deposit. `match_bookings` fills open
+//! bookings from forecasts, the assigned worker confirms, and each confirmed booking
+//! becomes a scheduler `Reservation`. `sweep` settles bookings as windows pass: a
+//! worker that confirmed but never checked in is reported to the fault detector and
+//! the owner refunded; an owner that never used a booking forfeits the deposit to
+//! the worker who held the capacity.
+
+use std::{cmp::Reverse, collections::BTreeMap};
+use thiserror::Error;
+
+use crate::{
+    bin_packing::Reservation,
+    fault_detector::{FaultDetector, FaultType},
+};
+
+/// Half-open interval `[start, end)` in unix seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Window {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Window {
+    pub fn contains(&self, other: &Window) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+/// GPU-hours a worker expects to have free on one GPU during a window
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapacityForecast {
+    pub worker_id: String,
+    pub gpu_id: String,
+    pub window: Window,
+    pub gpu_hours: u32,
+    /// Memory available to a single booking
+    pub memory: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BookingStatus {
+    /// Waiting for a forecast with room
+    Open,
+    /// Assigned to a worker, awaiting its confirmation
+    Matched { worker_id: String, gpu_id: String },
+    /// Worker committed; held as a scheduler reservation
+    Confirmed { worker_id: String, gpu_id: String },
+    /// Worker checked in for the window
+    Active { worker_id: String, gpu_id: String },
+    Fulfilled,
+    WorkerNoShow,
+    OwnerNoShow,
+    /// Cancelled by the owner or never matched before the window opened
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Booking {
+    pub id: u64,
+    pub owner: String,
+    pub window: Window,
+    pub gpu_hours: u32,
+    pub required_memory: u64,
+    pub deposit: u64,
+    pub status: BookingStatus,
+    /// Set once the owner runs a task under the booking
+    pub used: bool,
+}
+
+/// Deposit movement owed once a booking settles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settlement {
+    pub booking_id: u64,
+    pub refund: u64,
+    /// `(worker_id, amount)` when the deposit compensates a worker
+    pub worker_payout: Option<(String, u64)>,
+}
+
+/// Result of settling bookings whose windows have passed their deadlines
+#[derive(Debug, Default, PartialEq)]
+pub struct SweepOutcome {
+    pub settlements: Vec<Settlement>,
+    /// `(booking_id, worker_id)` reported to the fault detector
+    pub no_shows: Vec<(u64, String)>,
+    /// Reservations the scheduler should drop
+    pub released: Vec<u64>,
+}
+
+#[derive(Debug, Error)]
+pub enum BookError {
+    #[error("Unknown booking {0}")]
+    UnknownBooking(u64),
+    #[error("Window must end after it starts and lie in the future")]
+    InvalidWindow,
+    #[error("Deposit {offered} below required {required}")]
+    DepositTooLow { required: u64, offered: u64 },
+    #[error("Booking {0} is not in a state that allows this")]
+    InvalidState(u64),
+    #[error("Booking {0} is not assigned to worker {1}")]
+    NotAssigned(u64, String),
+    #[error("Forecast of {offered} GPU-hours is below the {committed} already booked")]
+    ForecastBelowCommitted { committed: u32, offered: u32 },
+}
+
+/// Key of a forecast: one per worker GPU and window
+type ForecastKey = (String, String, Window);
+
+#[derive(Debug, Clone)]
+struct ForecastSlot {
+    forecast: CapacityForecast,
+    committed_hours: u32,
+}
+
+impl ForecastSlot {
+    fn remaining_hours(&self) -> u32 {
+        self.forecast.gpu_hours - self.committed_hours
+    }
+}
+
+pub struct CapacityBook {
+    forecasts: BTreeMap<ForecastKey, ForecastSlot>,
+    bookings: BTreeMap<u64, Booking>,
+    next_booking_id: u64,
+    /// Deposit required per booked GPU-hour
+    min_deposit_per_hour: u64,
+    /// Seconds into a window a confirmed worker has to check in
+    check_in_grace: i64,
+}
+
+impl CapacityBook {
+    pub fn new(min_deposit_per_hour: u64, check_in_grace: i64) -> Self {
+        Self {
+            forecasts: BTreeMap::new(),
+            bookings: BTreeMap::new(),
+            next_booking_id: 1,
+            min_deposit_per_hour,
+            check_in_grace,
+        }
+    }
+
+    pub fn booking(&self, booking_id: u64) -> Option<&Booking> {
+        self.bookings.get(&booking_id)
+    }
+
+    /// Publish or revise a forecast; it cannot drop below hours already booked
+    pub fn publish_forecast(
+        &mut self,
+        forecast: CapacityForecast,
+        now: i64,
+    ) -> Result<(), BookError> {
+        if forecast.window.end <= forecast.window.start || forecast.window.end <= now {
+            return Err(BookError::InvalidWindow);
+        }
+        let key = (
+            forecast.worker_id.clone(),
+            forecast.gpu_id.clone(),
+            forecast.window,
+        );
+        let committed_hours = self.forecasts.get(&key).map_or(0, |slot| slot.committed_hours);
+        if forecast.gpu_hours < committed_hours {
+            return Err(BookError::ForecastBelowCommitted {
+                committed: committed_hours,
+                offered: forecast.gpu_hours,
+            });
+        }
+        self.forecasts.insert(key, ForecastSlot { forecast, committed_hours });
+        Ok(())
+    }
+
+    /// Pre-book GPU-hours in a future window
+    pub fn book(
+        &mut self,
+        owner: &str,
+        window: Window,
+        gpu_hours: u32,
+        required_memory: u64,
+        deposit: u64,
+        now: i64,
+    ) -> Result<u64, BookError> {
+        if window.end <= window.start || window.start <= now || gpu_hours == 0 {
+            return Err(BookError::InvalidWindow);
+        }
+        let required = self.min_deposit_per_hour.saturating_mul(gpu_hours as u64);
+        if deposit < required {
+            return Err(BookError::DepositTooLow { required, offered: deposit });
+        }
+
+        let id = self.next_booking_id;
+        self.next_booking_id += 1;
+        self.bookings.insert(
+            id,
+            Booking {
+                id,
+                owner: owner.to_string(),
+                window,
+                gpu_hours,
+                required_memory,
+                deposit,
+                status: BookingStatus::Open,
+                used: false,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Fill open bookings, earliest window first and larger deposits first within a
+    /// window. Each goes to the fitting forecast with the least room left, keeping
+    /// large forecasts free for large bookings. Returns the matched booking ids.
+    pub fn match_bookings(&mut self) -> Vec<u64> {
+        let mut open: Vec<&mut Booking> = self
+            .bookings
+            .values_mut()
+            .filter(|booking| booking.status == BookingStatus::Open)
+            .collect();
+        open.sort_by_key(|booking| (booking.window.start, Reverse(booking.deposit)));
+
+        let mut matched = Vec::new();
+        for booking in open {
+            let Some(slot) = self
+                .forecasts
+                .values_mut()
+                .filter(|slot| {
+                    slot.forecast.window.contains(&booking.window)
+                        && slot.forecast.memory >= booking.required_memory
+                        && slot.remaining_hours() >= booking.gpu_hours
+                })
+                .min_by_key(|slot| slot.remaining_hours())
+            else {
+                continue;
+            };
+            slot.committed_hours += booking.gpu_hours;
+            booking.status = BookingStatus::Matched {
+                worker_id: slot.forecast.worker_id.clone(),
+                gpu_id: slot.forecast.gpu_id.clone(),
+            };
+            matched.push(booking.id);
+        }
+        matched
+    }
+
+    /// Worker accepts a match; the returned reservation goes to the scheduler
+    pub fn confirm(&mut self, booking_id: u64, worker_id: &str) -> Result<Reservation, BookError> {
+        let booking = self.assigned_mut(booking_id, worker_id)?;
+        let BookingStatus::Matched { worker_id, gpu_id } = booking.status.clone() else {
+            return Err(BookError::InvalidState(booking_id));
+        };
+        booking.status = BookingStatus::Confirmed { worker_id, gpu_id: gpu_id.clone() };
+        Ok(Reservation {
+            booking_id,
+            gpu_id,
+            required_memory: booking.required_memory,
+            starts_at: booking.window.start,
+            ends_at: booking.window.end,
+        })
+    }
+
+    /// Worker turns a match down before confirming; the booking is rematched
+    pub fn decline(&mut self, booking_id: u64, worker_id: &str) -> Result<(), BookError> {
+        let booking = self.assigned_mut(booking_id, worker_id)?;
+        let BookingStatus::Matched { gpu_id, .. } = booking.status.clone() else {
+            return Err(BookError::InvalidState(booking_id));
+        };
+        booking.status = BookingStatus::Open;
+        let booking = booking.clone();
+        self.release_hours(&booking, worker_id, &gpu_id);
+        Ok(())
+    }
+
+    /// Worker reports for a confirmed booking within the check-in grace period
+    pub fn check_in(
+        &mut self,
+        booking_id: u64,
+        worker_id: &str,
+        now: i64,
+    ) -> Result<(), BookError> {
+        let grace = self.check_in_grace;
+        let booking = self.assigned_mut(booking_id, worker_id)?;
+        let BookingStatus::Confirmed { worker_id, gpu_id } = booking.status.clone() else {
+            return Err(BookError::InvalidState(booking_id));
+        };
+        if now < booking.window.start || now > booking.window.start + grace {
+            return Err(BookError::InvalidState(booking_id));
+        }
+        booking.status = BookingStatus::Active { worker_id, gpu_id };
+        Ok(())
+    }
+
+    /// Owner ran a task under the booking
+    pub fn record_usage(&mut self, booking_id: u64) -> Result<(), BookError> {
+        let booking = self
+            .bookings
+            .get_mut(&booking_id)
+            .ok_or(BookError::UnknownBooking(booking_id))?;
+        if !matches!(booking.status, BookingStatus::Active { .. }) {
+            return Err(BookError::InvalidState(booking_id));
+        }
+        booking.used = true;
+        Ok(())
+    }
+
+    /// Owner withdraws a booking before its window opens; the deposit is refunded
+    pub fn cancel(&mut self, booking_id: u64, now: i64) -> Result<Settlement, BookError> {
+        let booking = self
+            .bookings
+            .get_mut(&booking_id)
+            .ok_or(BookError::UnknownBooking(booking_id))?;
+        let assigned = match booking.status.clone() {
+            _ if now >= booking.window.start => return Err(BookError::InvalidState(booking_id)),
+            BookingStatus::Open => None,
+            BookingStatus::Matched { worker_id, gpu_id }
+            | BookingStatus::Confirmed { worker_id, gpu_id } => Some((worker_id, gpu_id)),
+            _ => return Err(BookError::InvalidState(booking_id)),
+        };
+        booking.status = BookingStatus::Cancelled;
+        let booking = booking.clone();
+        if let Some((worker_id, gpu_id)) = assigned {
+            self.release_hours(&booking, &worker_id, &gpu_id);
+        }
+        Ok(Settlement {
+            booking_id,
+            refund: booking.deposit,
+            worker_payout: None,
+        })
+    }
+
+    /// Settle bookings whose deadlines have passed and report worker no-shows
+    pub async fn sweep(&mut self, now: i64, detector: &FaultDetector) -> SweepOutcome {
+        let mut outcome = SweepOutcome::default();
+        for booking in self.bookings.values_mut() {
+            let refund = Settlement {
+                booking_id: booking.id,
+                refund: booking.deposit,
+                worker_payout: None,
+            };
+            match booking.status.clone() {
+                // Never matched or confirmed in time; nobody is at fault
+                BookingStatus::Open | BookingStatus::Matched { .. }
+                    if now >= booking.window.start =>
+                {
+                    booking.status = BookingStatus::Cancelled;
+                    outcome.settlements.push(refund);
+                }
+                BookingStatus::Confirmed { worker_id, .. }
+                    if now > booking.window.start + self.check_in_grace =>
+                {
+                    booking.status = BookingStatus::WorkerNoShow;
+                    let _ = detector
+                        .report_fault(FaultType::BookingNoShow(booking.id), worker_id.clone())
+                        .await;
+                    outcome.no_shows.push((booking.id, worker_id));
+                    outcome.released.push(booking.id);
+                    outcome.settlements.push(refund);
+                }
+                BookingStatus::Active { worker_id, .. } if now >= booking.window.end => {
+                    outcome.released.push(booking.id);
+                    if booking.used {
+                        booking.status = BookingStatus::Fulfilled;
+                        outcome.settlements.push(refund);
+                    } else {
+                        booking.status = BookingStatus::OwnerNoShow;
+                        outcome.settlements.push(Settlement {
+                            booking_id: booking.id,
+                            refund: 0,
+                            worker_payout: Some((worker_id, booking.deposit)),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Forecasts for windows that have ended can no longer be booked
+        self.forecasts.retain(|_, slot| slot.forecast.window.end > now);
+        outcome
+    }
+
+    fn assigned_mut(
+        &mut self,
+        booking_id: u64,
+        worker_id: &str,
+    ) -> Result<&mut Booking, BookError> {
+        let booking = self
+            .bookings
+            .get_mut(&booking_id)
+            .ok_or(BookError::UnknownBooking(booking_id))?;
+        match &booking.status {
+            BookingStatus::Matched { worker_id: assigned, .. }
+            | BookingStatus::Confirmed { worker_id: assigned, .. }
+                if assigned == worker_id =>
+            {
+                Ok(booking)
+            }
+            _ => Err(BookError::NotAssigned(booking_id, worker_id.to_string())),
+        }
+    }
+
+    /// Return a booking's hours to the forecast it was matched from
+    fn release_hours(&mut self, booking: &Booking, worker_id: &str, gpu_id: &str) {
+        if let Some(slot) = self.forecasts.values_mut().find(|slot| {
+            slot.forecast.worker_id == worker_id
+                && slot.forecast.gpu_id == gpu_id
+                && slot.forecast.window.contains(&booking.window)
+        }) {
+            slot.committed_hours = slot.committed_hours.saturating_sub(booking.gpu_hours);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3_600;
+
+    fn forecast(worker: &str, hours: u32, start: i64, end: i64) -> CapacityForecast {
+        CapacityForecast {
+            worker_id: worker.into(),
+            gpu_id: format!("{worker}-gpu0"),
+            window: Window { start, end },
+            gpu_hours: hours,
+            memory: 32_768,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bookings_match_confirm_and_settle() {
+        let detector = FaultDetector::new(0.6);
+        let mut book = CapacityBook::new(10, 600);
+        book.publish_forecast(forecast("w1", 9, HOUR, 9 * HOUR), 0).unwrap();
+        book.publish_forecast(forecast("w2", 2, HOUR, 3 * HOUR), 0).unwrap();
+
+        let window = Window { start: HOUR, end: 3 * HOUR };
+        assert!(matches!(
+            book.book("alice", window, 2, 1_024, 5, 0),
+            Err(BookError::DepositTooLow { required: 20, .. })
+        ));
+        let small = book.book("alice", window, 2, 1_024, 20, 0).unwrap();
+        let large = book.book("bob", window, 6, 1_024, 60, 0).unwrap();
+
+        // Only w1 fits the large booking; the small one then takes the tighter w2
+        assert_eq!(book.match_bookings(), vec![large, small]);
+        assert!(matches!(
+            &book.booking(small).unwrap().status,
+            BookingStatus::Matched { worker_id, .. } if worker_id == "w2"
+        ));
+        assert!(book.confirm(small, "w1").is_err());
+        let reservation = book.confirm(small, "w2").unwrap();
+        assert_eq!((reservation.gpu_id.as_str(), reservation.starts_at), ("w2-gpu0", HOUR));
+        book.confirm(large, "w1").unwrap();
+
+        // w1 checks in but bob never uses the booking; w2 never shows
+        book.check_in(large, "w1", HOUR + 60).unwrap();
+        let outcome = book.sweep(HOUR + 601, &detector).await;
+        assert_eq!(outcome.no_shows, vec![(small, "w2".to_string())]);
+        assert_eq!(outcome.settlements[0].refund, 20);
+        assert_eq!(book.booking(small).unwrap().status, BookingStatus::WorkerNoShow);
+
+        let outcome = book.sweep(3 * HOUR, &detector).await;
+        assert_eq!(
+            outcome.settlements,
+            vec![Settlement {
+                booking_id: large,
+                refund: 0,
+                worker_payout: Some(("w1".to_string(), 60)),
+            }]
+        );
+        assert_eq!(outcome.released, vec![large]);
+    }
+
+    #[test]
+    fn test_forecast_cannot_drop_below_bookings() {
+        let mut book = CapacityBook::new(0, 600);
+        book.publish_forecast(forecast("w1", 4, HOUR, 2 * HOUR), 0).unwrap();
+        let id = book
+            .book("alice", Window { start: HOUR, end: 2 * HOUR }, 3, 1_024, 0, 0)
+            .unwrap();
+        assert_eq!(book.match_bookings(), vec![id]);
+
+        assert!(matches!(
+            book.publish_forecast(forecast("w1", 2, HOUR, 2 * HOUR), 0),
+            Err(BookError::ForecastBelowCommitted { committed: 3, offered: 2 })
+        ));
+
+        // Declining frees the hours for the forecast to shrink
+        book.decline(id, "w1").unwrap();
+        book.publish_forecast(forecast("w1", 2, HOUR, 2 * HOUR), 0).unwrap();
+        assert!(book.match_bookings().is_empty());
+        assert_eq!(book.cancel(id, 0).unwrap().refund, 0);
+    }
+}
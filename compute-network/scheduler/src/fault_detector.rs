@@ -9,10 +9,13 @@ use std::{
     time::{Duration, Instant},
 };
 use tokio::{
-    sync::{Mutex, RwLock},
+    sync::{mpsc, Mutex, RwLock},
     time::interval,
 };
 
+/// Share of stake slashed once faults reach consensus (10%)
+pub const FAULT_SLASH_BPS: u16 = 1_000;
+
 #[derive(Clone, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
 pub enum FaultType {
     ComputeTimeout(u64),  // Task ID
@@ -20,11 +23,15 @@ pub enum FaultType {
     ZKProofMismatch,       // Proof CID
     DataAvailabilityError, // IPFS CID
     ByzantineBehavior,     // Node ID
+    SloViolation(u64),     // Observed p95 latency (ms)
+    BookingNoShow(u64),    // Capacity booking ID
 }
 
 #[derive(Clone, Debug)]
 pub struct NodeHealth {
     pub last_heartbeat: Instant,
+    /// Window the node promised its next heartbeat within
+    pub heartbeat_interval: Duration,
     pub task_success_rate: f32,
     pub resource_usage: ResourceMetrics,
     pub reputation_score: u8,
@@ -51,10 +58,63 @@ pub enum FaultError {
     InsufficientStake(String),
 }
 
+/// Heartbeat timeout and batching parameters.
+///
+/// Workers advertise the window their next beat lands in; it varies with
+/// their load. A node is faulted after missing `missed_beats` of its own
+/// windows plus `grace`, with the advertised window clamped to
+/// `[min_interval, max_interval]` so a node cannot advertise itself out of
+/// detection.
+#[derive(Clone, Debug)]
+pub struct HeartbeatConfig {
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+    pub missed_beats: u32,
+    /// Allowance for network and batching delay
+    pub grace: Duration,
+    /// How often buffered heartbeats are applied to the registry
+    pub flush_interval: Duration,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            // Worker defaults: 5s critical, 60s idle plus 20% jitter
+            min_interval: Duration::from_secs(5),
+            max_interval: Duration::from_secs(72),
+            missed_beats: 3,
+            grace: Duration::from_secs(10),
+            flush_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl HeartbeatConfig {
+    /// Silence after which a node advertising `interval` is faulted
+    pub fn timeout(&self, interval: Duration) -> Duration {
+        interval.clamp(self.min_interval, self.max_interval) * self.missed_beats + self.grace
+    }
+}
+
+/// On-chain slash the detector wants applied via token-vault `slash_stake`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlashRequest {
+    pub node_id: String,
+    pub slash_bps: u16,
+    /// Faults that reached consensus in this round
+    pub fault_count: u8,
+}
+
 pub struct FaultDetector {
     node_registry: Arc<RwLock<HashMap<String, NodeHealth>>>,
     pending_faults: Arc<Mutex<Vec<(FaultType, String)>>>,
     consensus_threshold: u8,
+    /// Receives slash requests; stake only changes once the `Slashed` event lands
+    slasher: Option<mpsc::UnboundedSender<SlashRequest>>,
+    heartbeat: HeartbeatConfig,
+    /// Heartbeats received since the last flush, latest per node. Kept apart
+    /// from the registry so beats never wait on its write lock
+    heartbeat_buffer: Mutex<HashMap<String, (Instant, Duration)>>,
 }
 
 impl FaultDetector {
@@ -63,19 +123,72 @@ impl FaultDetector {
             node_registry: Arc::new(RwLock::new(HashMap::new())),
             pending_faults: Arc::new(Mutex::new(Vec::new())),
             consensus_threshold: (consensus_ratio * 10.0) as u8,
+            slasher: None,
+            heartbeat: HeartbeatConfig::default(),
+            heartbeat_buffer: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Core detection loop with adaptive intervals
+    pub fn with_heartbeat_config(mut self, heartbeat: HeartbeatConfig) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Forward consensus slashes to the holder of the pool's slash authority
+    pub fn with_slasher(mut self, slasher: mpsc::UnboundedSender<SlashRequest>) -> Self {
+        self.slasher = Some(slasher);
+        self
+    }
+
+    /// Core detection loop: heartbeats are applied in batches every
+    /// `flush_interval`, and liveness is checked at the shortest heartbeat
+    /// interval so the tightest timeouts are honored
     pub async fn start_monitoring(&self) {
-        let mut interval = interval(Duration::from_secs(30));
-        
+        let mut flush = interval(self.heartbeat.flush_interval);
+        let mut check = interval(self.heartbeat.min_interval);
+
         loop {
-            interval.tick().await;
-            self.check_heartbeats().await;
-            self.audit_pending_tasks().await;
-            self.verify_consensus().await;
+            tokio::select! {
+                _ = flush.tick() => {
+                    self.flush_heartbeats().await;
+                }
+                _ = check.tick() => {
+                    self.flush_heartbeats().await;
+                    self.check_heartbeats().await;
+                    self.audit_pending_tasks().await;
+                    self.verify_consensus().await;
+                }
+            }
+        }
+    }
+
+    /// Buffer a heartbeat; `next_within` is the window the node advertised
+    /// for its next one
+    pub async fn record_heartbeat(&self, node_id: String, next_within: Duration) {
+        self.heartbeat_buffer
+            .lock()
+            .await
+            .insert(node_id, (Instant::now(), next_within));
+    }
+
+    /// Apply buffered heartbeats under a single registry write. Returns the
+    /// number applied
+    pub async fn flush_heartbeats(&self) -> usize {
+        let batch = std::mem::take(&mut *self.heartbeat_buffer.lock().await);
+        if batch.is_empty() {
+            return 0;
+        }
+        let mut registry = self.node_registry.write().await;
+        let mut applied = 0;
+        for (node_id, (at, next_within)) in batch {
+            // Nodes join through registration, not through a stray beat
+            if let Some(health) = registry.get_mut(&node_id) {
+                health.last_heartbeat = health.last_heartbeat.max(at);
+                health.heartbeat_interval = next_within;
+                applied += 1;
+            }
         }
+        applied
     }
 
     async fn check_heartbeats(&self) {
@@ -83,7 +196,7 @@ impl FaultDetector {
         let mut faults = self.pending_faults.lock().await;
         
         for (node_id, health) in registry.iter() {
-            if health.last_heartbeat.elapsed() > Duration::from_secs(120) {
+            if health.last_heartbeat.elapsed() > self.heartbeat.timeout(health.heartbeat_interval) {
                 faults.push((FaultType::ByzantineBehavior, node_id.clone()));
             }
         }
@@ -106,18 +219,35 @@ impl FaultDetector {
 
         for (id, count) in fault_counts {
             if count >= self.consensus_threshold {
-                self.apply_penalties(&id, &mut registry).await;
+                self.apply_penalties(&id, count, &mut registry).await;
             }
         }
         
         faults.clear();
     }
 
-    async fn apply_penalties(&self, node_id: &str, registry: &mut HashMap<String, NodeHealth>) {
+    async fn apply_penalties(
+        &self,
+        node_id: &str,
+        fault_count: u8,
+        registry: &mut HashMap<String, NodeHealth>,
+    ) {
         if let Some(health) = registry.get_mut(node_id) {
-            // Slashing mechanism
-            let penalty = (health.staked_tokens as f32 * 0.1) as u64;
-            health.staked_tokens = health.staked_tokens.saturating_sub(penalty);
+            let request = SlashRequest {
+                node_id: node_id.to_string(),
+                slash_bps: FAULT_SLASH_BPS,
+                fault_count,
+            };
+            // With a slasher the stake changes on-chain and `record_slash` applies it
+            // once confirmed; without one (or once it has gone away) it is deducted locally
+            let forwarded = self
+                .slasher
+                .as_ref()
+                .map_or(false, |slasher| slasher.send(request).is_ok());
+            if !forwarded {
+                let penalty = health.staked_tokens * FAULT_SLASH_BPS as u64 / 10_000;
+                health.staked_tokens = health.staked_tokens.saturating_sub(penalty);
+            }
             
             // Reputation decay
             health.reputation_score = health.reputation_score.saturating_sub(10);
@@ -134,6 +264,13 @@ impl FaultDetector {
         // Placeholder for actual quarantine logic
     }
 
+    /// Apply a token-vault `Slashed` event to the registry's view of a node's stake
+    pub async fn record_slash(&self, node_id: &str, amount: u64) {
+        if let Some(health) = self.node_registry.write().await.get_mut(node_id) {
+            health.staked_tokens = health.staked_tokens.saturating_sub(amount);
+        }
+    }
+
     /// Public API for external fault reporting
     pub async fn report_fault(&self, fault: FaultType, node_id: String) -> Result<(), FaultError> {
         let mut faults = self.pending_faults.lock().await;
@@ -167,6 +304,7 @@ mod tests {
             node_id.clone(),
             NodeHealth {
                 last_heartbeat: Instant::now() - Duration::from_secs(300),
+                heartbeat_interval: Duration::from_secs(5),
                 task_success_rate: 0.9,
                 resource_usage: ResourceMetrics {
                     gpu_util: 0.3,
@@ -184,15 +322,83 @@ mod tests {
         assert!(faults.len() > 0);
     }
 
+    fn healthy_node(last_heartbeat: Instant, heartbeat_interval: Duration) -> NodeHealth {
+        NodeHealth {
+            last_heartbeat,
+            heartbeat_interval,
+            task_success_rate: 1.0,
+            resource_usage: ResourceMetrics {
+                gpu_util: 0.1,
+                mem_util: 0.1,
+                network_util: 0.1,
+                disk_io: 0.1,
+            },
+            reputation_score: 80,
+            staked_tokens: 1000,
+        }
+    }
+
+    #[test]
+    fn test_timeout_scales_with_advertised_interval() {
+        let config = HeartbeatConfig::default();
+        assert_eq!(config.timeout(Duration::from_secs(5)), Duration::from_secs(25));
+        assert_eq!(config.timeout(Duration::from_secs(60)), Duration::from_secs(190));
+        // Advertised windows outside the bounds are clamped
+        assert_eq!(config.timeout(Duration::ZERO), Duration::from_secs(25));
+        assert_eq!(config.timeout(Duration::from_secs(3_600)), Duration::from_secs(226));
+    }
+
     #[tokio::test]
-    async fn test_consensus_penalty() {
+    async fn test_idle_node_not_faulted_within_its_window() {
         let detector = FaultDetector::new(0.6);
+        let silent_for = Instant::now() - Duration::from_secs(60);
+        detector.node_registry.write().await.extend([
+            ("idle".to_string(), healthy_node(silent_for, Duration::from_secs(72))),
+            ("critical".to_string(), healthy_node(silent_for, Duration::from_secs(5))),
+        ]);
+
+        detector.check_heartbeats().await;
+        let faults = detector.pending_faults.lock().await;
+        assert_eq!(faults.len(), 1);
+        assert_eq!(faults[0].1, "critical");
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_applied_in_batches() {
+        let detector = FaultDetector::new(0.6);
+        let stale = Instant::now() - Duration::from_secs(300);
+        detector
+            .node_registry
+            .write()
+            .await
+            .insert("worker".to_string(), healthy_node(stale, Duration::from_secs(5)));
+
+        detector.record_heartbeat("worker".to_string(), Duration::from_secs(6)).await;
+        detector.record_heartbeat("worker".to_string(), Duration::from_secs(72)).await;
+        detector.record_heartbeat("unknown".to_string(), Duration::from_secs(5)).await;
+        // Buffered beats are invisible until flushed
+        assert_eq!(detector.node_registry.read().await["worker"].last_heartbeat, stale);
+
+        assert_eq!(detector.flush_heartbeats().await, 1);
+        let registry = detector.node_registry.read().await;
+        assert_eq!(registry["worker"].heartbeat_interval, Duration::from_secs(72));
+        assert!(registry["worker"].last_heartbeat.elapsed() < Duration::from_secs(5));
+        assert!(!registry.contains_key("unknown"));
+        drop(registry);
+        assert_eq!(detector.flush_heartbeats().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_consensus_penalty() {
+        let (slash_tx, mut slash_rx) = mpsc::unbounded_channel();
+        let detector = FaultDetector::new(0.6).with_slasher(slash_tx);
         let node_id = "bad_actor".to_string();
         
         detector.node_registry.write().await.insert(
             node_id.clone(),
             NodeHealth {
                 last_heartbeat: Instant::now(),
+                heartbeat_interval: Duration::from_secs(5),
                 task_success_rate: 0.5,
                 resource_usage: ResourceMetrics {
                     gpu_util: 0.8,
@@ -213,10 +419,36 @@ mod tests {
         }
 
         detector.verify_consensus().await;
+        let request = slash_rx.try_recv().unwrap();
+        assert_eq!(request.node_id, node_id);
+        assert_eq!(request.slash_bps, FAULT_SLASH_BPS);
+        // Stake only drops once the on-chain slash is observed
+        assert_eq!(detector.node_registry.read().await[&node_id].staked_tokens, 500);
+
+        detector.record_slash(&node_id, 50).await;
         let registry = detector.node_registry.read().await;
         let health = registry.get(&node_id).unwrap();
         
         assert_eq!(health.staked_tokens, 450); // 10% penalty
         assert_eq!(health.reputation_score, 20);
     }
+
+    #[tokio::test]
+    async fn test_consensus_penalty_applied_locally_without_slasher() {
+        let detector = FaultDetector::new(0.6);
+        detector
+            .node_registry
+            .write()
+            .await
+            .insert("bad_actor".to_string(), healthy_node(Instant::now(), Duration::from_secs(5)));
+
+        for _ in 0..7 {
+            detector
+                .report_fault(FaultType::ByzantineBehavior, "bad_actor".to_string())
+                .await
+                .unwrap();
+        }
+        detector.verify_consensus().await;
+        assert_eq!(detector.node_registry.read().await["bad_actor"].staked_tokens, 900);
+    }
 }
@@ -3,18 +3,21 @@
 
 use anchor_lang::{
     prelude::*,
-    solana_program::{program::invoke, sysvar},
+    solana_program::{hash::hash, program::invoke, sysvar},
+    AccountDeserialize,
 };
 use ark_std::{UniformRand, rand::RngCore};
+use haunti_core::state::oracle_registry::OracleFeed;
 use light_poseidon::{Poseidon, PoseidonBytesHasher};
 use reqwest::{Client, Url};
 use serde_json::Value;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use std::{
     collections::HashMap,
     sync::Arc,
     time::{Duration, SystemTime},
 };
-use tokio::sync::RwLock;
+use tokio::{sync::RwLock, task::JoinHandle};
 
 // Custom error handling
 #[derive(Debug, thiserror::Error)]
@@ -29,6 +32,8 @@ pub enum DataFeedError {
     FormatError,
     #[error("Oracle signature invalid")]
     OracleSignatureError,
+    #[error("Oracle registry unavailable: {0}")]
+    OracleRegistry(String),
 }
 
 // Data feed configuration
@@ -38,6 +43,9 @@ pub struct DataFeedConfig {
     pub allowed_domains: Vec<String>,
     pub poseidon_params: PoseidonParameters,
     pub solana_commitment: CommitmentConfig,
+    /// Feeds whose staked oracle sets are followed from chain
+    pub oracle_feeds: Vec<String>,
+    pub oracle_refresh_interval: Duration,
 }
 
 // Core data processing engine
@@ -46,7 +54,8 @@ pub struct DataFeedEngine {
     http_client: Client,
     poseidon: Poseidon,
     cache: Arc<RwLock<HashMap<String, ProcessedData>>>,
-    oracle_keys: HashMap<String, Pubkey>,
+    /// Staked oracles per feed, as last read from the on-chain registry
+    oracle_keys: RwLock<HashMap<String, Vec<Pubkey>>>,
 }
 
 impl DataFeedEngine {
@@ -58,11 +67,48 @@ impl DataFeedEngine {
                 .unwrap(),
             poseidon: Poseidon::new(config.poseidon_params.clone()),
             cache: Arc::new(RwLock::new(HashMap::new())),
-            oracle_keys: load_oracle_keys(),
+            // Empty until the first registry refresh; no oracle is trusted before then
+            oracle_keys: RwLock::new(HashMap::new()),
             config,
         }
     }
 
+    /// Whether `oracle` is currently staked on `feed`
+    pub async fn is_registered_oracle(&self, feed: &str, oracle: &Pubkey) -> bool {
+        self.oracle_keys
+            .read()
+            .await
+            .get(feed)
+            .is_some_and(|oracles| oracles.contains(oracle))
+    }
+
+    /// Reload the oracle set of every configured feed from the on-chain
+    /// registry. Feeds that fail to load keep their previous set.
+    pub async fn refresh_oracles(&self, rpc: &RpcClient) -> Result<(), DataFeedError> {
+        let mut failed = None;
+        for feed in &self.config.oracle_feeds {
+            match fetch_oracle_set(rpc, feed).await {
+                Ok(oracles) => {
+                    self.oracle_keys.write().await.insert(feed.clone(), oracles);
+                }
+                Err(e) => failed = Some(e),
+            }
+        }
+        failed.map_or(Ok(()), Err)
+    }
+
+    /// Keep the oracle sets in step with registrations, exits and slashes
+    pub fn spawn_oracle_refresh(self: Arc<Self>, rpc: Arc<RpcClient>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.oracle_refresh_interval);
+            loop {
+                ticker.tick().await;
+                // Failed feeds keep their last known set until the next tick
+                let _ = self.refresh_oracles(&rpc).await;
+            }
+        })
+    }
+
     // Main data processing pipeline
     pub async fn process_data(
         &self,
@@ -199,11 +245,21 @@ fn validate_schema(data: &Value) -> Result<(), DataFeedError> {
     Ok(())
 }
 
-// Oracle key management
-fn load_oracle_keys() -> HashMap<String, Pubkey> {
-    let mut keys = HashMap::new();
-    keys.insert("price_feed".to_string(), Pubkey::from_str(ORACLE_PUBKEY).unwrap());
-    keys
+// Oracle registry
+/// Registry PDA of a feed; feeds are keyed by the SHA-256 of their name
+pub fn oracle_feed_address(feed: &str) -> Pubkey {
+    let feed_id = hash(feed.as_bytes()).to_bytes();
+    Pubkey::find_program_address(&[b"oracle_feed", &feed_id], &HAUNTI_PROGRAM_ID).0
+}
+
+async fn fetch_oracle_set(rpc: &RpcClient, feed: &str) -> Result<Vec<Pubkey>, DataFeedError> {
+    let account = rpc
+        .get_account(&oracle_feed_address(feed))
+        .await
+        .map_err(|e| DataFeedError::OracleRegistry(e.to_string()))?;
+    let registry = OracleFeed::try_deserialize(&mut account.data.as_slice())
+        .map_err(|e| DataFeedError::OracleRegistry(e.to_string()))?;
+    Ok(registry.oracles)
 }
 
 // Unit tests
@@ -219,6 +275,8 @@ mod tests {
             allowed_domains: vec!["api.haunti.ai".to_string()],
             poseidon_params: PoseidonParameters::new(),
             solana_commitment: CommitmentConfig::local(),
+            oracle_feeds: vec!["price_feed".to_string()],
+            oracle_refresh_interval: Duration::from_secs(60),
         };
 
         let engine = DataFeedEngine::new(config);
@@ -227,4 +285,29 @@ mod tests {
         assert_eq!(data.raw["symbol"], "BTC");
         assert!(!data.solana_sig.to_string().is_empty());
     }
+
+    #[tokio::test]
+    async fn test_no_oracle_trusted_before_refresh() {
+        let config = DataFeedConfig {
+            max_age_secs: 300,
+            min_sources: 1,
+            allowed_domains: vec![],
+            poseidon_params: PoseidonParameters::new(),
+            solana_commitment: CommitmentConfig::local(),
+            oracle_feeds: vec!["price_feed".to_string()],
+            oracle_refresh_interval: Duration::from_secs(60),
+        };
+        let engine = DataFeedEngine::new(config);
+        let oracle = Pubkey::new_unique();
+        assert!(!engine.is_registered_oracle("price_feed", &oracle).await);
+
+        engine
+            .oracle_keys
+            .write()
+            .await
+            .insert("price_feed".to_string(), vec![oracle]);
+        assert!(engine.is_registered_oracle("price_feed", &oracle).await);
+        assert!(!engine.is_registered_oracle("other_feed", &oracle).await);
+        assert_ne!(oracle_feed_address("price_feed"), oracle_feed_address("other_feed"));
+    }
 }
@@ -0,0 +1,292 @@
+//! IBC channel lifecycle for the Cosmos relay path
+//! Handshake automation, light-client refresh, and per-channel packet timeouts
+
+use async_trait::async_trait;
+use ibc_proto::{cosmos::base::v1beta1::Coin, ibc::core::client::v1::Height};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, SystemTime},
+};
+
+use crate::RelayError;
+
+/// Blocks on the counterparty before an unreceived packet times out
+pub const PACKET_TIMEOUT_BLOCKS: u64 = 1_000;
+/// Refresh a light client once this fraction of its trusting period has elapsed
+const CLIENT_REFRESH_NUMERATOR: u32 = 2;
+const CLIENT_REFRESH_DENOMINATOR: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelState {
+    Uninitialized,
+    Init,
+    TryOpen,
+    Open,
+    Closed,
+}
+
+/// Next handshake message to submit for a channel in `state`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeStep {
+    OpenInit,
+    OpenTry,
+    OpenAck,
+    OpenConfirm,
+    Done,
+}
+
+pub fn next_handshake_step(local: ChannelState, counterparty: ChannelState) -> HandshakeStep {
+    match (local, counterparty) {
+        (ChannelState::Uninitialized, _) => HandshakeStep::OpenInit,
+        (ChannelState::Init, ChannelState::Uninitialized) => HandshakeStep::OpenTry,
+        (ChannelState::Init, ChannelState::TryOpen) => HandshakeStep::OpenAck,
+        (ChannelState::Open, ChannelState::TryOpen) => HandshakeStep::OpenConfirm,
+        _ => HandshakeStep::Done,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IbcChannel {
+    pub port_id: String,
+    pub channel_id: Option<String>,
+    pub counterparty_channel_id: Option<String>,
+    pub connection_id: String,
+    /// Light client of the counterparty tracked on our side
+    pub client_id: String,
+    pub state: ChannelState,
+    pub counterparty_state: ChannelState,
+    pub trusting_period: Duration,
+    pub client_updated_at: SystemTime,
+}
+
+impl IbcChannel {
+    /// Whether the light client should be updated before it expires
+    pub fn needs_client_update(&self, now: SystemTime) -> bool {
+        let elapsed = now
+            .duration_since(self.client_updated_at)
+            .unwrap_or_default();
+        elapsed >= self.trusting_period * CLIENT_REFRESH_NUMERATOR / CLIENT_REFRESH_DENOMINATOR
+    }
+}
+
+/// Packet sent but not yet acknowledged by the counterparty
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingPacket {
+    pub sequence: u64,
+    pub task_nonce: u64,
+    pub timeout_height: Height,
+    pub fee_payment: Option<Coin>,
+}
+
+/// Per-channel in-flight packets ordered by sequence
+#[derive(Debug, Default)]
+pub struct PacketQueue {
+    pending: BTreeMap<u64, PendingPacket>,
+}
+
+impl PacketQueue {
+    pub fn push(&mut self, packet: PendingPacket) {
+        self.pending.insert(packet.sequence, packet);
+    }
+
+    pub fn acknowledge(&mut self, sequence: u64) -> Option<PendingPacket> {
+        self.pending.remove(&sequence)
+    }
+
+    /// Remove and return packets whose timeout height has passed
+    pub fn take_timed_out(&mut self, counterparty_height: &Height) -> Vec<PendingPacket> {
+        let expired: Vec<u64> = self
+            .pending
+            .values()
+            .filter(|p| height_reached(counterparty_height, &p.timeout_height))
+            .map(|p| p.sequence)
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|seq| self.pending.remove(&seq))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+fn height_reached(current: &Height, timeout: &Height) -> bool {
+    (current.revision_number, current.revision_height)
+        >= (timeout.revision_number, timeout.revision_height)
+}
+
+/// Chain operations the channel manager needs from both ends
+#[async_trait]
+pub trait IbcChainClient: Send + Sync {
+    async fn chan_open_init(&self, port_id: &str, connection_id: &str) -> Result<String, RelayError>;
+    async fn chan_open_try(&self, port_id: &str, counterparty_channel_id: &str) -> Result<String, RelayError>;
+    async fn chan_open_ack(&self, channel_id: &str, counterparty_channel_id: &str) -> Result<(), RelayError>;
+    async fn chan_open_confirm(&self, counterparty_channel_id: &str) -> Result<(), RelayError>;
+    async fn update_client(&self, client_id: &str) -> Result<(), RelayError>;
+    async fn counterparty_height(&self) -> Result<Height, RelayError>;
+    async fn send_packet(&self, channel_id: &str, payload: &[u8], timeout: Height) -> Result<u64, RelayError>;
+    async fn acknowledged_sequences(&self, channel_id: &str) -> Result<Vec<u64>, RelayError>;
+    async fn timeout_packet(&self, channel_id: &str, sequence: u64) -> Result<(), RelayError>;
+    async fn refund_fee(&self, task_nonce: u64, fee: &Coin) -> Result<(), RelayError>;
+}
+
+/// Opens, monitors, and drains IBC channels without operator involvement
+pub struct ChannelManager {
+    client: Box<dyn IbcChainClient>,
+    channels: HashMap<String, IbcChannel>,
+    queues: HashMap<String, PacketQueue>,
+}
+
+impl ChannelManager {
+    pub fn new(client: Box<dyn IbcChainClient>) -> Self {
+        Self {
+            client,
+            channels: HashMap::new(),
+            queues: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, channel: IbcChannel) {
+        self.channels.insert(channel.port_id.clone(), channel);
+    }
+
+    /// Drive the handshake for `port_id` to completion, returning the channel id
+    pub async fn ensure_open(&mut self, port_id: &str) -> Result<String, RelayError> {
+        let channel = self
+            .channels
+            .get_mut(port_id)
+            .ok_or(RelayError::IbcChannelError)?;
+
+        loop {
+            match next_handshake_step(channel.state, channel.counterparty_state) {
+                HandshakeStep::OpenInit => {
+                    let id = self.client.chan_open_init(port_id, &channel.connection_id).await?;
+                    channel.channel_id = Some(id);
+                    channel.state = ChannelState::Init;
+                }
+                HandshakeStep::OpenTry => {
+                    let local = channel.channel_id.as_deref().ok_or(RelayError::IbcChannelError)?;
+                    let id = self.client.chan_open_try(port_id, local).await?;
+                    channel.counterparty_channel_id = Some(id);
+                    channel.counterparty_state = ChannelState::TryOpen;
+                }
+                HandshakeStep::OpenAck => {
+                    let local = channel.channel_id.as_deref().ok_or(RelayError::IbcChannelError)?;
+                    let remote = channel
+                        .counterparty_channel_id
+                        .as_deref()
+                        .ok_or(RelayError::IbcChannelError)?;
+                    self.client.chan_open_ack(local, remote).await?;
+                    channel.state = ChannelState::Open;
+                }
+                HandshakeStep::OpenConfirm => {
+                    let remote = channel
+                        .counterparty_channel_id
+                        .as_deref()
+                        .ok_or(RelayError::IbcChannelError)?;
+                    self.client.chan_open_confirm(remote).await?;
+                    channel.counterparty_state = ChannelState::Open;
+                }
+                HandshakeStep::Done => break,
+            }
+        }
+
+        if channel.state != ChannelState::Open {
+            return Err(RelayError::IbcChannelError);
+        }
+        channel.channel_id.clone().ok_or(RelayError::IbcChannelError)
+    }
+
+    /// Send a task payload on an open channel with a timeout height
+    pub async fn send(
+        &mut self,
+        port_id: &str,
+        task_nonce: u64,
+        payload: &[u8],
+        fee_payment: Option<Coin>,
+    ) -> Result<u64, RelayError> {
+        let channel_id = self.ensure_open(port_id).await?;
+        let current = self.client.counterparty_height().await?;
+        let timeout_height = Height {
+            revision_number: current.revision_number,
+            revision_height: current.revision_height + PACKET_TIMEOUT_BLOCKS,
+        };
+
+        let sequence = self.client.send_packet(&channel_id, payload, timeout_height.clone()).await?;
+        self.queues.entry(channel_id).or_default().push(PendingPacket {
+            sequence,
+            task_nonce,
+            timeout_height,
+            fee_payment,
+        });
+        Ok(sequence)
+    }
+
+    /// One monitoring pass: refresh expiring clients, clear acks, time out and refund
+    pub async fn tick(&mut self) -> Result<(), RelayError> {
+        let now = SystemTime::now();
+        for channel in self.channels.values_mut() {
+            if channel.needs_client_update(now) {
+                self.client.update_client(&channel.client_id).await?;
+                channel.client_updated_at = now;
+            }
+        }
+
+        let height = self.client.counterparty_height().await?;
+        for (channel_id, queue) in self.queues.iter_mut() {
+            for sequence in self.client.acknowledged_sequences(channel_id).await? {
+                queue.acknowledge(sequence);
+            }
+            for packet in queue.take_timed_out(&height) {
+                self.client.timeout_packet(channel_id, packet.sequence).await?;
+                if let Some(fee) = &packet.fee_payment {
+                    self.client.refund_fee(packet.task_nonce, fee).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn height(h: u64) -> Height {
+        Height {
+            revision_number: 1,
+            revision_height: h,
+        }
+    }
+
+    #[test]
+    fn test_handshake_sequence() {
+        use ChannelState::*;
+        assert_eq!(next_handshake_step(Uninitialized, Uninitialized), HandshakeStep::OpenInit);
+        assert_eq!(next_handshake_step(Init, Uninitialized), HandshakeStep::OpenTry);
+        assert_eq!(next_handshake_step(Init, TryOpen), HandshakeStep::OpenAck);
+        assert_eq!(next_handshake_step(Open, TryOpen), HandshakeStep::OpenConfirm);
+        assert_eq!(next_handshake_step(Open, Open), HandshakeStep::Done);
+    }
+
+    #[test]
+    fn test_packet_timeouts() {
+        let mut queue = PacketQueue::default();
+        for (seq, timeout) in [(1, 100), (2, 150), (3, 200)] {
+            queue.push(PendingPacket {
+                sequence: seq,
+                task_nonce: seq,
+                timeout_height: height(timeout),
+                fee_payment: None,
+            });
+        }
+        queue.acknowledge(1);
+
+        let expired = queue.take_timed_out(&height(150));
+        assert_eq!(expired.iter().map(|p| p.sequence).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(queue.len(), 1);
+    }
+}
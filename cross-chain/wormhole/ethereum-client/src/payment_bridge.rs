@@ -0,0 +1,279 @@
+//! EVM payment bridge: lock ETH/USDC for Haunti task fees on Solana
+//!
+//! The Haunti payment contract escrows the funds and publishes a Wormhole
+//! payment message through the core bridge. Once the guardians sign it, the
+//! VAA is posted to Solana and redeemed by haunti-core's
+//! `redeem_bridged_payment`, which credits the recipient's prepaid balance.
+
+use ethers::{
+    prelude::*,
+    types::{Address, H256, U256},
+};
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+
+/// Payload id of a payment message; must match haunti-core
+pub const PAYMENT_PAYLOAD_ID: u8 = 1;
+/// Encoded payment payload length
+pub const PAYMENT_PAYLOAD_LEN: usize = 1 + 1 + 32 + 32 + 8;
+/// Interval between guardian API polls while waiting for a signed VAA
+const VAA_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+abigen!(
+    HauntiPayments,
+    r#"[
+        function lockEth(bytes32 recipient, uint32 nonce) external payable returns (uint64 lockId, uint64 sequence)
+        function lockToken(address token, uint256 amount, bytes32 recipient, uint32 nonce) external payable returns (uint64 lockId, uint64 sequence)
+        function wormhole() external view returns (address)
+        event PaymentLocked(uint64 indexed lockId, address indexed payer, uint8 asset, uint256 amount, bytes32 recipient, uint64 sequence)
+    ]"#;
+
+    WormholeCore,
+    r#"[
+        function messageFee() external view returns (uint256)
+    ]"#;
+
+    Erc20,
+    r#"[
+        function approve(address spender, uint256 amount) external returns (bool)
+        function allowance(address owner, address spender) external view returns (uint256)
+    ]"#;
+);
+
+#[derive(Debug, thiserror::Error)]
+pub enum PaymentBridgeError {
+    #[error("Contract call failed: {0}")]
+    Contract(String),
+    #[error("Lock transaction dropped from the mempool")]
+    Dropped,
+    #[error("PaymentLocked event missing from the receipt")]
+    MissingLockEvent,
+    #[error("Lock amount must be positive")]
+    ZeroAmount,
+    #[error("Guardian API request failed: {0}")]
+    GuardianApi(#[from] reqwest::Error),
+    #[error("Guardian API returned a malformed VAA")]
+    InvalidVaaEncoding,
+    #[error("Signed VAA not available after {0:?}")]
+    VaaTimeout(Duration),
+}
+
+impl<M: Middleware> From<ContractError<M>> for PaymentBridgeError {
+    fn from(e: ContractError<M>) -> Self {
+        PaymentBridgeError::Contract(e.to_string())
+    }
+}
+
+/// Asset locked on Ethereum; discriminants match haunti-core's `PaymentAsset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentAsset {
+    Eth = 0,
+    Usdc = 1,
+}
+
+/// Payment message as published to Wormhole (big-endian, EVM ABI widths)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaymentPayload {
+    pub asset: PaymentAsset,
+    /// Amount in the asset's base units (wei / USDC micro-units)
+    pub amount: U256,
+    /// Solana account credited on redemption
+    pub recipient: [u8; 32],
+    pub lock_id: u64,
+}
+
+impl PaymentPayload {
+    /// `payload_id u8 | asset u8 | amount u256 | recipient bytes32 | lock_id u64`
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(PAYMENT_PAYLOAD_LEN);
+        out.push(PAYMENT_PAYLOAD_ID);
+        out.push(self.asset as u8);
+        let mut amount = [0u8; 32];
+        self.amount.to_big_endian(&mut amount);
+        out.extend_from_slice(&amount);
+        out.extend_from_slice(&self.recipient);
+        out.extend_from_slice(&self.lock_id.to_be_bytes());
+        out
+    }
+}
+
+/// Result of a confirmed lock, enough to fetch and redeem the VAA
+#[derive(Debug, Clone)]
+pub struct PaymentLock {
+    pub tx_hash: H256,
+    pub payload: PaymentPayload,
+    /// Wormhole emitter (the payment contract), left-padded to 32 bytes
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+}
+
+#[derive(Clone)]
+pub struct PaymentBridgeConfig {
+    pub payments_contract: Address,
+    pub usdc: Address,
+    /// Wormhole chain id of this EVM network
+    pub chain_id: u16,
+    /// Guardian REST endpoint serving signed VAAs
+    pub guardian_api: String,
+    pub vaa_timeout: Duration,
+}
+
+/// Client for the Haunti payment contract
+pub struct PaymentBridgeClient<M> {
+    client: Arc<M>,
+    config: PaymentBridgeConfig,
+    payments: HauntiPayments<M>,
+    http: reqwest::Client,
+}
+
+impl<M: Middleware + 'static> PaymentBridgeClient<M> {
+    pub fn new(client: Arc<M>, config: PaymentBridgeConfig) -> Self {
+        let payments = HauntiPayments::new(config.payments_contract, client.clone());
+        Self { client, config, payments, http: reqwest::Client::new() }
+    }
+
+    /// Lock `amount` wei for `recipient`; the Wormhole message fee is added on top
+    pub async fn lock_eth(
+        &self,
+        recipient: [u8; 32],
+        amount: U256,
+    ) -> Result<PaymentLock, PaymentBridgeError> {
+        if amount.is_zero() {
+            return Err(PaymentBridgeError::ZeroAmount);
+        }
+        let fee = self.message_fee().await?;
+        let call = self
+            .payments
+            .lock_eth(recipient, rand::random())
+            .value(amount + fee);
+        self.confirm_lock(call).await
+    }
+
+    /// Lock `amount` USDC micro-units for `recipient`, approving the contract first
+    /// when the current allowance is short
+    pub async fn lock_usdc(
+        &self,
+        recipient: [u8; 32],
+        amount: U256,
+    ) -> Result<PaymentLock, PaymentBridgeError> {
+        if amount.is_zero() {
+            return Err(PaymentBridgeError::ZeroAmount);
+        }
+        let usdc = Erc20::new(self.config.usdc, self.client.clone());
+        let owner = self.client.default_sender().unwrap_or_default();
+        if usdc.allowance(owner, self.config.payments_contract).call().await? < amount {
+            usdc.approve(self.config.payments_contract, amount)
+                .send()
+                .await?
+                .await
+                .map_err(|e| PaymentBridgeError::Contract(e.to_string()))?
+                .ok_or(PaymentBridgeError::Dropped)?;
+        }
+
+        let fee = self.message_fee().await?;
+        let call = self
+            .payments
+            .lock_token(self.config.usdc, amount, recipient, rand::random())
+            .value(fee);
+        self.confirm_lock(call).await
+    }
+
+    /// Poll the guardian API until the lock's VAA is signed
+    pub async fn fetch_signed_vaa(
+        &self,
+        lock: &PaymentLock,
+    ) -> Result<Vec<u8>, PaymentBridgeError> {
+        #[derive(Deserialize)]
+        struct SignedVaa {
+            #[serde(rename = "vaaBytes")]
+            vaa_bytes: String,
+        }
+
+        let url = format!(
+            "{}/v1/signed_vaa/{}/{}/{}",
+            self.config.guardian_api.trim_end_matches('/'),
+            self.config.chain_id,
+            hex::encode(lock.emitter),
+            lock.sequence
+        );
+        let deadline = tokio::time::Instant::now() + self.config.vaa_timeout;
+        loop {
+            let response = self.http.get(&url).send().await?;
+            if response.status().is_success() {
+                let signed: SignedVaa = response.json().await?;
+                return base64::decode(signed.vaa_bytes)
+                    .map_err(|_| PaymentBridgeError::InvalidVaaEncoding);
+            }
+            // 404 until a quorum of guardians has signed
+            if tokio::time::Instant::now() >= deadline {
+                return Err(PaymentBridgeError::VaaTimeout(self.config.vaa_timeout));
+            }
+            tokio::time::sleep(VAA_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn message_fee(&self) -> Result<U256, PaymentBridgeError> {
+        let core = WormholeCore::new(self.payments.wormhole().call().await?, self.client.clone());
+        Ok(core.message_fee().call().await?)
+    }
+
+    async fn confirm_lock(
+        &self,
+        call: ContractCall<M, (u64, u64)>,
+    ) -> Result<PaymentLock, PaymentBridgeError> {
+        let receipt = call
+            .send()
+            .await?
+            .await
+            .map_err(|e| PaymentBridgeError::Contract(e.to_string()))?
+            .ok_or(PaymentBridgeError::Dropped)?;
+
+        let locked = receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == self.config.payments_contract)
+            .find_map(|log| {
+                <PaymentLockedFilter as EthEvent>::decode_log(&log.clone().into()).ok()
+            })
+            .ok_or(PaymentBridgeError::MissingLockEvent)?;
+
+        let asset = match locked.asset {
+            0 => PaymentAsset::Eth,
+            _ => PaymentAsset::Usdc,
+        };
+        Ok(PaymentLock {
+            tx_hash: receipt.transaction_hash,
+            payload: PaymentPayload {
+                asset,
+                amount: locked.amount,
+                recipient: locked.recipient,
+                lock_id: locked.lock_id,
+            },
+            emitter: H256::from(self.config.payments_contract).0,
+            sequence: locked.sequence,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_layout_matches_solana_decoder() {
+        let payload = PaymentPayload {
+            asset: PaymentAsset::Usdc,
+            amount: U256::from(25_000_000u64),
+            recipient: [9; 32],
+            lock_id: 42,
+        };
+        let raw = payload.encode();
+
+        assert_eq!(raw.len(), PAYMENT_PAYLOAD_LEN);
+        assert_eq!(raw[0], PAYMENT_PAYLOAD_ID);
+        assert_eq!(raw[1], 1);
+        assert_eq!(u128::from_be_bytes(raw[18..34].try_into().unwrap()), 25_000_000);
+        assert_eq!(&raw[34..66], &[9; 32]);
+        assert_eq!(u64::from_be_bytes(raw[66..74].try_into().unwrap()), 42);
+    }
+}
@@ -0,0 +1,272 @@
+//! Ordered delivery per (source, destination, emitter) stream
+//!
+//! Tasks reach the relayer out of order when source transactions land in
+//! different blocks or a relay is retried. In ordered mode each stream is
+//! delivered strictly by sequence: anything ahead of the next expected sequence
+//! is buffered until the gap fills. Two escape hatches keep a lost message from
+//! stalling a stream for good: a gap left open for `skip_after` is skipped, and a
+//! sequence arriving more than `window` past the head moves the head forward.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    time::{Duration, Instant},
+};
+use wormhole_sdk::{Address, Chain};
+
+#[derive(Debug, Clone)]
+pub struct SequencingConfig {
+    /// Sequences buffered ahead of the next expected one
+    pub window: u64,
+    /// How long a gap may hold back buffered messages before it is skipped
+    pub skip_after: Duration,
+}
+
+/// Messages sharing a key are delivered in sequence order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StreamKey {
+    pub source: Chain,
+    pub destination: Chain,
+    pub emitter: Address,
+}
+
+/// Messages now deliverable, in order, and the sequences given up on to get there
+#[derive(Debug)]
+pub struct Release<T> {
+    pub ready: Vec<(u64, T)>,
+    pub skipped: Vec<Range<u64>>,
+}
+
+impl<T> Default for Release<T> {
+    fn default() -> Self {
+        Self {
+            ready: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+}
+
+impl<T> Release<T> {
+    /// Number of sequences skipped
+    pub fn skipped_count(&self) -> u64 {
+        self.skipped.iter().map(|r| r.end - r.start).sum()
+    }
+}
+
+struct Stream<T> {
+    next: u64,
+    buffered: BTreeMap<u64, T>,
+    /// When the head last stopped making progress with messages buffered
+    stalled_since: Option<Instant>,
+}
+
+impl<T> Stream<T> {
+    fn new(next: u64) -> Self {
+        Self {
+            next,
+            buffered: BTreeMap::new(),
+            stalled_since: None,
+        }
+    }
+
+    /// Move every contiguous message from the head into `release`
+    fn drain(&mut self, release: &mut Release<T>, now: Instant) {
+        let before = self.next;
+        while let Some(item) = self.buffered.remove(&self.next) {
+            release.ready.push((self.next, item));
+            self.next += 1;
+        }
+        if self.buffered.is_empty() {
+            self.stalled_since = None;
+        } else if self.next != before || self.stalled_since.is_none() {
+            self.stalled_since = Some(now);
+        }
+    }
+
+    /// Give up on everything below `to`
+    fn skip_to(&mut self, to: u64, release: &mut Release<T>) {
+        if to <= self.next {
+            return;
+        }
+        let kept = self.buffered.split_off(&to);
+        let mut cursor = self.next;
+        for (sequence, item) in std::mem::replace(&mut self.buffered, kept) {
+            if sequence > cursor {
+                release.skipped.push(cursor..sequence);
+            }
+            release.ready.push((sequence, item));
+            cursor = sequence + 1;
+        }
+        if to > cursor {
+            release.skipped.push(cursor..to);
+        }
+        self.next = to;
+    }
+}
+
+/// Reorders messages of every stream the relayer carries
+pub struct Sequencer<T> {
+    config: SequencingConfig,
+    streams: HashMap<StreamKey, Stream<T>>,
+}
+
+impl<T> Sequencer<T> {
+    pub fn new(config: SequencingConfig) -> Self {
+        Self {
+            config,
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Start `key` at a persisted cursor; otherwise a stream starts at the first
+    /// sequence it sees
+    pub fn resume(&mut self, key: StreamKey, next: u64) {
+        self.streams.insert(key, Stream::new(next));
+    }
+
+    /// Whether `sequence` was already delivered or skipped
+    pub fn is_stale(&self, key: &StreamKey, sequence: u64) -> bool {
+        self.streams.get(key).is_some_and(|s| sequence < s.next)
+    }
+
+    /// Next sequence `key` will deliver
+    pub fn next_sequence(&self, key: &StreamKey) -> Option<u64> {
+        self.streams.get(key).map(|s| s.next)
+    }
+
+    /// Messages held back across all streams
+    pub fn buffered(&self) -> usize {
+        self.streams.values().map(|s| s.buffered.len()).sum()
+    }
+
+    /// Accept a message and release whatever it unblocks. Stale messages are
+    /// dropped; check `is_stale` first to report them
+    pub fn offer(&mut self, key: StreamKey, sequence: u64, item: T, now: Instant) -> Release<T> {
+        let window = self.config.window.max(1);
+        let stream = self.streams.entry(key).or_insert_with(|| Stream::new(sequence));
+        let mut release = Release::default();
+        if sequence < stream.next {
+            return release;
+        }
+        stream.buffered.entry(sequence).or_insert(item);
+        if sequence >= stream.next + window {
+            stream.skip_to(sequence + 1 - window, &mut release);
+        }
+        stream.drain(&mut release, now);
+        release
+    }
+
+    /// Put back messages released by `offer` that could not be delivered,
+    /// starting at the failed `sequence`, which the caller retries itself
+    pub fn rewind(&mut self, key: StreamKey, sequence: u64, pending: Vec<(u64, T)>, now: Instant) {
+        let Some(stream) = self.streams.get_mut(&key) else {
+            return;
+        };
+        stream.next = stream.next.min(sequence);
+        stream.buffered.extend(pending.into_iter().filter(|(s, _)| *s > sequence));
+        if !stream.buffered.is_empty() {
+            stream.stalled_since = Some(now);
+        }
+    }
+
+    /// Skip gaps that have held back buffered messages for longer than
+    /// `skip_after`
+    pub fn release_expired(&mut self, now: Instant) -> Vec<(StreamKey, Release<T>)> {
+        let skip_after = self.config.skip_after;
+        let mut released = Vec::new();
+        for (key, stream) in self.streams.iter_mut() {
+            let expired = stream
+                .stalled_since
+                .is_some_and(|since| now.saturating_duration_since(since) >= skip_after);
+            let Some(&first) = stream.buffered.keys().next() else {
+                continue;
+            };
+            if expired {
+                let mut release = Release::default();
+                stream.skip_to(first, &mut release);
+                stream.drain(&mut release, now);
+                released.push((*key, release));
+            }
+        }
+        released
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> StreamKey {
+        StreamKey {
+            source: Chain::Solana,
+            destination: Chain::Ethereum,
+            emitter: Address([7; 32]),
+        }
+    }
+
+    fn sequencer(window: u64) -> Sequencer<&'static str> {
+        Sequencer::new(SequencingConfig {
+            window,
+            skip_after: Duration::from_secs(30),
+        })
+    }
+
+    fn sequences<T>(release: &Release<T>) -> Vec<u64> {
+        release.ready.iter().map(|(s, _)| *s).collect()
+    }
+
+    #[test]
+    fn test_out_of_order_delivered_in_order() {
+        let (mut seq, now) = (sequencer(16), Instant::now());
+        seq.resume(key(), 1);
+        assert!(seq.offer(key(), 3, "c", now).ready.is_empty());
+        assert!(seq.offer(key(), 2, "b", now).ready.is_empty());
+        let release = seq.offer(key(), 1, "a", now);
+        assert_eq!(sequences(&release), vec![1, 2, 3]);
+        assert!(release.skipped.is_empty());
+
+        assert!(seq.is_stale(&key(), 2));
+        assert!(seq.offer(key(), 2, "b", now).ready.is_empty());
+        assert_eq!(seq.buffered(), 0);
+    }
+
+    #[test]
+    fn test_gap_skipped_after_timeout() {
+        let (mut seq, start) = (sequencer(16), Instant::now());
+        seq.resume(key(), 1);
+        seq.offer(key(), 4, "d", start);
+        seq.offer(key(), 5, "e", start);
+        assert!(seq.release_expired(start + Duration::from_secs(29)).is_empty());
+
+        let released = seq.release_expired(start + Duration::from_secs(30));
+        let (_, release) = &released[0];
+        assert_eq!(sequences(release), vec![4, 5]);
+        assert_eq!(release.skipped, vec![1..4]);
+        assert_eq!(seq.next_sequence(&key()), Some(6));
+    }
+
+    #[test]
+    fn test_arrival_past_window_moves_head() {
+        let (mut seq, now) = (sequencer(4), Instant::now());
+        seq.resume(key(), 1);
+        seq.offer(key(), 3, "c", now);
+        let release = seq.offer(key(), 7, "g", now);
+        // The head moves to 4 so 7 fits in the window; 3 is released on the way
+        assert_eq!(sequences(&release), vec![3]);
+        assert_eq!(release.skipped, vec![1..3]);
+        assert_eq!(release.skipped_count(), 2);
+        assert_eq!(seq.next_sequence(&key()), Some(4));
+    }
+
+    #[test]
+    fn test_rewind_holds_stream_for_retry() {
+        let (mut seq, now) = (sequencer(16), Instant::now());
+        seq.resume(key(), 1);
+        seq.offer(key(), 2, "b", now);
+        let release = seq.offer(key(), 1, "a", now);
+        // Delivery of 1 failed; 2 must wait for its retry
+        seq.rewind(key(), 1, release.ready, now);
+        assert!(!seq.is_stale(&key(), 1));
+        assert_eq!(sequences(&seq.offer(key(), 1, "a", now)), vec![1, 2]);
+    }
+}
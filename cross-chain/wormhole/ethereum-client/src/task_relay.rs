@@ -28,9 +28,19 @@ use layer_zero::{
 use std::{
     collections::{HashMap, VecDeque},
     sync::{Arc, Mutex},
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 
+mod ibc_channel;
+pub mod payment_bridge;
+pub mod sequencer;
+
+use ibc_channel::ChannelManager;
+use sequencer::{Release, Sequencer, SequencingConfig, StreamKey};
+
+/// Interval between IBC channel liveness/timeout passes
+const IBC_MONITOR_INTERVAL: Duration = Duration::from_secs(30);
+
 // Custom error handling
 #[derive(Debug, thiserror::Error)]
 pub enum RelayError {
@@ -52,6 +62,8 @@ pub enum RelayError {
     GasEstimationError,
     #[error("Relayer signature invalid")]
     SignatureError,
+    #[error("Task sequence already delivered or skipped")]
+    StaleSequence,
 }
 
 // Task state machine
@@ -82,12 +94,16 @@ pub struct RelayTask {
 #[derive(Clone)]
 pub struct RelayConfig {
     pub wormhole_bridge: Pubkey,
-    pub ibc_channel: String,
+    /// IBC port whose channel is opened and monitored by the relayer
+    pub ibc_port: String,
     pub layerzero_endpoint: Endpoint,
     pub max_payload_size: usize,
     pub fee_denom: String,
     pub min_fee: u64,
     pub max_retries: u8,
+    /// Deliver each (source, destination, emitter) stream strictly by task
+    /// nonce; `None` relays in arrival order
+    pub ordering: Option<SequencingConfig>,
 }
 
 // Core relay engine
@@ -97,36 +113,109 @@ pub struct TaskRelayer {
     state_cache: Arc<Mutex<HashMap<u64, TaskState>>>,
     chain_clients: HashMap<Chain, Box<dyn ChainClient>>,
     metrics: RelayMetrics,
+    ibc: Option<Arc<tokio::sync::Mutex<ChannelManager>>>,
+    sequencer: Option<Sequencer<RelayTask>>,
 }
 
 impl TaskRelayer {
     pub fn new(config: RelayConfig) -> Self {
         Self {
+            sequencer: config.ordering.clone().map(Sequencer::new),
             config,
             task_queue: Arc::new(Mutex::new(VecDeque::new())),
             state_cache: Arc::new(Mutex::new(HashMap::new())),
             chain_clients: initialize_chain_clients(),
             metrics: RelayMetrics::new(),
+            ibc: None,
         }
     }
 
+    /// Enable the Cosmos path with a managed IBC channel
+    pub fn with_ibc(mut self, manager: ChannelManager) -> Self {
+        self.ibc = Some(Arc::new(tokio::sync::Mutex::new(manager)));
+        self
+    }
+
     // Main processing loop
     pub async fn run(&mut self) {
+        let mut last_ibc_tick = Instant::now();
         loop {
-            if let Some(task) = self.task_queue.lock().unwrap().pop_front() {
-                self.process_task(task).await;
+            let next = self.task_queue.lock().unwrap().pop_front();
+            if let Some(task) = next {
+                self.dispatch(task).await;
+            }
+            self.release_stalled().await;
+            if last_ibc_tick.elapsed() >= IBC_MONITOR_INTERVAL {
+                self.monitor_ibc().await;
+                last_ibc_tick = Instant::now();
             }
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
     }
 
-    async fn process_task(&mut self, mut task: RelayTask) {
+    // Route a task through its stream's sequencer in ordered mode
+    async fn dispatch(&mut self, task: RelayTask) {
+        let key = self.stream_key(&task);
+        let Some(sequencer) = self.sequencer.as_mut() else {
+            self.process_task(task).await;
+            return;
+        };
+        if sequencer.is_stale(&key, task.nonce) {
+            self.handle_error(task, RelayError::StaleSequence).await;
+            return;
+        }
+        let release = sequencer.offer(key, task.nonce, task, Instant::now());
+        self.deliver_in_order(key, release).await;
+    }
+
+    // Skip gaps that have held a stream back for longer than `skip_after`
+    async fn release_stalled(&mut self) {
+        let Some(sequencer) = self.sequencer.as_mut() else {
+            return;
+        };
+        for (key, release) in sequencer.release_expired(Instant::now()) {
+            msg!(
+                "Skipping {} missing sequences on {:?} -> {:?}",
+                release.skipped_count(),
+                key.source,
+                key.destination
+            );
+            self.deliver_in_order(key, release).await;
+        }
+    }
+
+    // Relay released tasks one by one, stopping the stream at the first failure
+    // so nothing overtakes the task being retried
+    async fn deliver_in_order(&mut self, key: StreamKey, release: Release<RelayTask>) {
+        self.metrics.sequences_skipped.inc_by(release.skipped_count());
+        let mut ready = release.ready.into_iter();
+        while let Some((sequence, task)) = ready.next() {
+            if !self.process_task(task).await {
+                if let Some(sequencer) = self.sequencer.as_mut() {
+                    sequencer.rewind(key, sequence, ready.collect(), Instant::now());
+                }
+                break;
+            }
+        }
+    }
+
+    // Tasks from one emitter between one chain pair share a sequence space
+    fn stream_key(&self, task: &RelayTask) -> StreamKey {
+        StreamKey {
+            source: task.source_chain,
+            destination: task.dest_chain,
+            emitter: Address::from(&self.config.wormhole_bridge.to_bytes()),
+        }
+    }
+
+    // Returns whether the task was relayed
+    async fn process_task(&mut self, mut task: RelayTask) -> bool {
         self.metrics.inc_tasks_processed();
         
         // Validate task basics
         if let Err(e) = self.validate_task(&task) {
             self.handle_error(task, e).await;
-            return;
+            return false;
         }
 
         // Select relay protocol
@@ -144,12 +233,14 @@ impl TaskRelayer {
             Ok(_) => {
                 task.state = TaskState::Completed;
                 self.metrics.inc_tasks_success();
+                true
             }
             Err(e) => {
                 task.retries += 1;
                 task.state = TaskState::Failed;
                 self.metrics.inc_tasks_failed();
                 self.handle_retry(task, e).await;
+                false
             }
         }
     }
@@ -173,16 +264,23 @@ impl TaskRelayer {
         client.submit_vaa(vaa, signature).await
     }
 
-    // IBC packet relay
+    // IBC packet relay over a relayer-managed channel
     async fn relay_via_ibc(&self, task: &RelayTask) -> Result<(), RelayError> {
-        let packet = ibc_packet(task)?;
-        let height = Height {
-            revision_number: 1,
-            revision_height: self.latest_block().await?,
-        };
-        
-        let client = self.chain_client(Chain::Cosmos)?;
-        client.send_ibc_packet(packet, height).await
+        let ibc = self.ibc.as_ref().ok_or(RelayError::IbcChannelError)?;
+        ibc.lock()
+            .await
+            .send(&self.config.ibc_port, task.nonce, &task.payload, task.fee_payment.clone())
+            .await?;
+        Ok(())
+    }
+
+    // Client refresh, acknowledgement clearing, and timeout refunds
+    async fn monitor_ibc(&self) {
+        if let Some(ibc) = &self.ibc {
+            if let Err(e) = ibc.lock().await.tick().await {
+                msg!("IBC channel monitoring failed: {}", e);
+            }
+        }
     }
 
     // LayerZero endpoint relay
@@ -249,6 +347,8 @@ struct RelayMetrics {
     tasks_processed: Counter,
     tasks_success: Counter,
     tasks_failed: Counter,
+    /// Sequences given up on in ordered mode
+    sequences_skipped: Counter,
     latency: Histogram,
 }
 
@@ -258,6 +358,7 @@ impl RelayMetrics {
             tasks_processed: Counter::new(),
             tasks_success: Counter::new(),
             tasks_failed: Counter::new(),
+            sequences_skipped: Counter::new(),
             latency: Histogram::with_buckets(vec![1.0, 5.0, 10.0, 30.0, 60.0]),
         }
     }
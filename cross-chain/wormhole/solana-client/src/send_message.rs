@@ -5,10 +5,12 @@ use anchor_lang::{
     prelude::*,
     solana_program::{program::invoke, system_instruction},
 };
+use borsh::{BorshDeserialize, BorshSerialize};
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{self, Mint, Token, TokenAccount},
 };
+use haunti_core::limits::MAX_MESSAGE_PAYLOAD_LEN;
 use wormhole_sdk::{
     vaa::{Body, Header},
     Address, Chain, Message,
@@ -35,6 +37,47 @@ pub struct MessageAccount {
     pub guardian_set_index: u32,
 }
 
+/// Core bridge fee configuration (`BridgeConfig` in the Wormhole core program)
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct BridgeConfig {
+    pub guardian_set_expiration_time: u32,
+    /// Message fee in lamports, paid to the fee collector per post
+    pub fee: u64,
+}
+
+/// Core bridge state stored at the `Bridge` PDA (no Anchor discriminator)
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct BridgeData {
+    pub guardian_set_index: u32,
+    /// Fee collector balance observed at the last post
+    pub last_lamports: u64,
+    pub config: BridgeConfig,
+}
+
+impl BridgeData {
+    pub fn from_account(info: &AccountInfo) -> Result<Self> {
+        let data = info.try_borrow_data()?;
+        BridgeData::deserialize(&mut &data[..]).map_err(|_| ErrorCode::InvalidBridgeAccount.into())
+    }
+}
+
+/// Total cost of sending one message
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct FeeQuote {
+    /// Relayer fee charged in the fee token
+    pub relayer_fee: u64,
+    /// Wormhole core bridge message fee in lamports
+    pub message_fee_lamports: u64,
+}
+
+/// Quote both fee legs for a message to `target_chain`
+pub fn quote_fee(target_chain: Chain, bridge: &BridgeData) -> Result<FeeQuote> {
+    Ok(FeeQuote {
+        relayer_fee: calculate_fee(target_chain)?,
+        message_fee_lamports: bridge.config.fee,
+    })
+}
+
 // Instruction context
 #[derive(Accounts)]
 pub struct SendMessage<'info> {
@@ -58,6 +101,22 @@ pub struct SendMessage<'info> {
     pub mint: Account<'info, Mint>,
     #[account(address = wormhole::ID)]
     pub wormhole_program: Program<'info, wormhole::program::Wormhole>,
+    /// CHECK: core bridge config, deserialized as `BridgeData`
+    #[account(
+        mut,
+        seeds = [b"Bridge"],
+        bump,
+        seeds::program = wormhole::ID
+    )]
+    pub wormhole_bridge: UncheckedAccount<'info>,
+    /// CHECK: core bridge fee collector, receives the message fee
+    #[account(
+        mut,
+        seeds = [b"fee_collector"],
+        bump,
+        seeds::program = wormhole::ID
+    )]
+    pub wormhole_fee_collector: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -71,6 +130,8 @@ impl SendMessage<'_> {
         payload: Vec<u8>,
         nonce: u32,
     ) -> Result<()> {
+        check_payload(&payload)?;
+
         let message = CrossChainMessage {
             source_chain: Chain::Solana,
             target_chain,
@@ -80,13 +141,9 @@ impl SendMessage<'_> {
             status: MessageStatus::Pending,
         };
 
-        // Validate payload size
-        if message.payload.len() > 1024 {
-            return Err(ErrorCode::MessageTooLarge.into());
-        }
-
-        // Deduct fee payment
-        let fee = calculate_fee(target_chain)?;
+        // Quote both the relayer fee and the core bridge message fee
+        let bridge = BridgeData::from_account(&ctx.accounts.wormhole_bridge.to_account_info())?;
+        let quote = quote_fee(target_chain, &bridge)?;
         token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
@@ -96,9 +153,27 @@ impl SendMessage<'_> {
                     authority: ctx.accounts.emitter.to_account_info(),
                 },
             ),
-            fee,
+            quote.relayer_fee,
         )?;
 
+        // The core bridge rejects posts unless the fee collector was paid exactly
+        // `config.fee` lamports since its last post
+        if quote.message_fee_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(
+                    &ctx.accounts.payer.key(),
+                    &ctx.accounts.wormhole_fee_collector.key(),
+                    quote.message_fee_lamports,
+                ),
+                &[
+                    ctx.accounts.payer.to_account_info(),
+                    ctx.accounts.wormhole_fee_collector.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )
+            .map_err(|_| ErrorCode::FeePaymentFailed)?;
+        }
+
         // Generate VAA (Verified Action Approval)
         let vaa = construct_vaa(
             &ctx.accounts.emitter,
@@ -123,13 +198,21 @@ impl SendMessage<'_> {
                 vaa.body,
             )?,
             &[
+                ctx.accounts.wormhole_bridge.to_account_info(),
                 ctx.accounts.message_account.to_account_info(),
+                ctx.accounts.wormhole_fee_collector.to_account_info(),
                 ctx.accounts.wormhole_program.to_account_info(),
                 ctx.accounts.payer.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
             ],
         )?;
 
+        emit!(MessageFeesPaid {
+            emitter: ctx.accounts.emitter.key(),
+            relayer_fee: quote.relayer_fee,
+            message_fee_lamports: quote.message_fee_lamports,
+        });
+
         Ok(())
     }
 }
@@ -161,6 +244,15 @@ fn construct_vaa(
     Ok((header, body))
 }
 
+// Payload size, inclusive of the protocol cap
+fn check_payload(payload: &[u8]) -> Result<()> {
+    require!(
+        payload.len() <= MAX_MESSAGE_PAYLOAD_LEN,
+        ErrorCode::MessageTooLarge
+    );
+    Ok(())
+}
+
 // Fee calculation based on target chain
 fn calculate_fee(target_chain: Chain) -> Result<u64> {
     match target_chain {
@@ -171,6 +263,13 @@ fn calculate_fee(target_chain: Chain) -> Result<u64> {
     }
 }
 
+#[event]
+pub struct MessageFeesPaid {
+    pub emitter: Pubkey,
+    pub relayer_fee: u64,
+    pub message_fee_lamports: u64,
+}
+
 // Error codes
 #[error_code]
 pub enum ErrorCode {
@@ -184,6 +283,8 @@ pub enum ErrorCode {
     VaaError,
     #[msg("Sequence number overflow")]
     SequenceOverflow,
+    #[msg("Core bridge account could not be deserialized")]
+    InvalidBridgeAccount,
 }
 
 // Constants
@@ -214,3 +315,51 @@ mod wormhole {
         // Implementation depends on Wormhole program details
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Core bridge `Bridge` account: guardian_set_index (u32), last_lamports (u64),
+    // guardian_set_expiration_time (u32), fee (u64), all little-endian
+    fn bridge_account_bytes(index: u32, last_lamports: u64, expiration: u32, fee: u64) -> Vec<u8> {
+        let mut data = Vec::with_capacity(24);
+        data.extend_from_slice(&index.to_le_bytes());
+        data.extend_from_slice(&last_lamports.to_le_bytes());
+        data.extend_from_slice(&expiration.to_le_bytes());
+        data.extend_from_slice(&fee.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_bridge_layout_matches_core_program() {
+        let raw = bridge_account_bytes(3, 1_000_000, 86_400, 100);
+        let bridge = BridgeData::deserialize(&mut raw.as_slice()).unwrap();
+
+        assert_eq!(bridge.guardian_set_index, 3);
+        assert_eq!(bridge.last_lamports, 1_000_000);
+        assert_eq!(bridge.config.guardian_set_expiration_time, 86_400);
+        assert_eq!(bridge.config.fee, 100);
+        assert_eq!(bridge.try_to_vec().unwrap(), raw);
+    }
+
+    #[test]
+    fn test_quote_includes_message_fee() {
+        let raw = bridge_account_bytes(3, 0, 86_400, 100);
+        let bridge = BridgeData::deserialize(&mut raw.as_slice()).unwrap();
+
+        let quote = quote_fee(Chain::Ethereum, &bridge).unwrap();
+        assert_eq!(quote.relayer_fee, 1_000_000);
+        assert_eq!(quote.message_fee_lamports, 100);
+        assert!(quote_fee(Chain::Solana, &bridge).is_err());
+    }
+
+    #[test]
+    fn test_payload_accepted_up_to_cap() {
+        assert!(check_payload(&vec![0u8; MAX_MESSAGE_PAYLOAD_LEN]).is_ok());
+        assert_eq!(
+            check_payload(&vec![0u8; MAX_MESSAGE_PAYLOAD_LEN + 1]).unwrap_err(),
+            ErrorCode::MessageTooLarge.into()
+        );
+    }
+}
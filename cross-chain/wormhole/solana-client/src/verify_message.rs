@@ -2,7 +2,13 @@
 
 use anchor_lang::{
     prelude::*,
-    solana_program::{program::invoke, sysvar::instructions},
+    solana_program::{
+        ed25519_program,
+        hash::hash,
+        program::{invoke, invoke_signed},
+        system_instruction,
+        sysvar::instructions::{self, load_instruction_at_checked},
+    },
 };
 use anchor_spl::{
     associated_token::AssociatedToken,
@@ -170,6 +176,241 @@ impl VerifyMessage<'_> {
     }
 }
 
+/// Most VAAs one batch may carry; bounded by transaction size more than compute
+pub const MAX_BATCH_VAAS: usize = 8;
+
+/// `instruction_index` value meaning "this ed25519 instruction"
+const ED25519_SELF_INDEX: u16 = u16::MAX;
+const ED25519_OFFSETS_START: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+
+/// One VAA of a batch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchedVaa {
+    pub header: Header,
+    pub signatures: Vec<Signature>,
+    pub body: Body,
+}
+
+/// A signature the ed25519 precompile checked before this program ran
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrecompiledSignature<'a> {
+    pub pubkey: &'a [u8],
+    pub signature: &'a [u8],
+    pub message: &'a [u8],
+}
+
+/// Signatures carried by an ed25519 precompile instruction. Entries whose data
+/// lives in another instruction are rejected, so nothing checked here can
+/// differ from what the precompile verified.
+pub fn precompiled_signatures(data: &[u8]) -> Result<Vec<PrecompiledSignature<'_>>> {
+    let count = *data.first().ok_or(ErrorCode::MalformedEd25519Instruction)? as usize;
+    let read_u16 = |at: usize| -> Result<u16> {
+        data.get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| ErrorCode::MalformedEd25519Instruction.into())
+    };
+    let slice = |offset: u16, len: usize| -> Result<&[u8]> {
+        data.get(offset as usize..offset as usize + len)
+            .ok_or_else(|| ErrorCode::MalformedEd25519Instruction.into())
+    };
+
+    (0..count)
+        .map(|i| {
+            let at = ED25519_OFFSETS_START + i * ED25519_OFFSETS_LEN;
+            let [sig_offset, sig_ix, key_offset, key_ix, msg_offset, msg_len, msg_ix] =
+                [0, 2, 4, 6, 8, 10, 12].map(|field| read_u16(at + field));
+            require!(
+                [sig_ix?, key_ix?, msg_ix?].iter().all(|&ix| ix == ED25519_SELF_INDEX),
+                ErrorCode::ExternalSignatureData
+            );
+            Ok(PrecompiledSignature {
+                signature: slice(sig_offset?, 64)?,
+                pubkey: slice(key_offset?, 32)?,
+                message: slice(msg_offset?, msg_len? as usize)?,
+            })
+        })
+        .collect()
+}
+
+// Batch verification context; the `verified_msg` PDAs, one per VAA in order,
+// follow as remaining accounts
+#[derive(Accounts)]
+#[instruction(vaas: Vec<BatchedVaa>, guardian_set_index: u32)]
+pub struct VerifyMessageBatch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = payer
+    )]
+    pub fee_account: Account<'info, TokenAccount>,
+
+    #[account(address = wormhole::ID)]
+    pub wormhole_program: Program<'info, wormhole::program::Wormhole>,
+
+    #[account(
+        seeds = [b"GuardianSet", guardian_set_index.to_le_bytes().as_ref()],
+        bump,
+        constraint = guardian_set.expiration_time > Clock::get()?.unix_timestamp
+    )]
+    pub guardian_set: Account<'info, GuardianSet>,
+
+    /// CHECK: address checked; read for the ed25519 precompile instruction
+    #[account(address = instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> VerifyMessageBatch<'info> {
+    /// Verify VAAs signed by one guardian set. Guardian signatures are checked
+    /// once by an ed25519 precompile instruction at `ed25519_ix_index` in the
+    /// same transaction, at native cost; this only matches each VAA signature
+    /// against an entry the precompile verified.
+    pub fn verify_vaa_batch(
+        ctx: Context<'_, '_, '_, 'info, VerifyMessageBatch<'info>>,
+        vaas: Vec<BatchedVaa>,
+        guardian_set_index: u32,
+        ed25519_ix_index: u16,
+    ) -> Result<()> {
+        require!(
+            !vaas.is_empty() && vaas.len() <= MAX_BATCH_VAAS,
+            ErrorCode::InvalidBatchSize
+        );
+        require_eq!(
+            ctx.remaining_accounts.len(),
+            vaas.len(),
+            ErrorCode::InvalidBatchSize
+        );
+
+        let ed25519_ix = load_instruction_at_checked(
+            ed25519_ix_index as usize,
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+        )?;
+        require_keys_eq!(
+            ed25519_ix.program_id,
+            ed25519_program::ID,
+            ErrorCode::MalformedEd25519Instruction
+        );
+        let precompiled = precompiled_signatures(&ed25519_ix.data)?;
+
+        let mut fee = 0u64;
+        for (vaa, verified_info) in vaas.iter().zip(ctx.remaining_accounts) {
+            Self::validate_vaa_structure(&vaa.header, &vaa.body, &vaa.signatures)?;
+            require_eq!(
+                vaa.header.guardian_set_index,
+                guardian_set_index,
+                ErrorCode::GuardianSetMismatch
+            );
+            require_eq!(vaa.body.chain_id, Chain::Solana, ErrorCode::InvalidTargetChain);
+
+            let message = construct_signing_message(&vaa.header, &vaa.body)?;
+            Self::match_precompiled(&message, &vaa.signatures, &ctx.accounts.guardian_set, &precompiled)?;
+
+            let vaa_hash = hash(&message).to_bytes();
+            let verified = VerifiedMessage {
+                source_chain: vaa.header.emitter_chain,
+                source_address: vaa.header.emitter_address,
+                payload: vaa.body.payload.clone(),
+                timestamp: vaa.header.timestamp as i64,
+                status: VerificationStatus::Verified,
+                guardian_set_index,
+                vaa_hash,
+            };
+            Self::store(&ctx, verified_info, &verified)?;
+            fee += calculate_verification_fee(vaa.body.payload.len())?;
+        }
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.fee_account.to_account_info(),
+                    to: ctx.accounts.wormhole_program.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+
+        Ok(())
+    }
+
+    /// Guardian weight behind `message`, counting only signatures the
+    /// precompile verified; each guardian counts once
+    fn match_precompiled(
+        message: &[u8],
+        signatures: &[Signature],
+        guardian_set: &GuardianSet,
+        precompiled: &[PrecompiledSignature<'_>],
+    ) -> Result<()> {
+        let mut signed = vec![false; guardian_set.keys.len()];
+        let mut weight_accum = 0;
+
+        for sig in signatures {
+            let index = sig.index as usize;
+            let guardian = guardian_set.keys.get(index)
+                .ok_or(ErrorCode::InvalidGuardianIndex)?;
+            require!(!signed[index], ErrorCode::DuplicateGuardianSignature);
+            require!(
+                precompiled.iter().any(|entry| entry.pubkey == guardian.key.as_slice()
+                    && entry.signature == sig.signature.as_slice()
+                    && entry.message == message),
+                ErrorCode::SignatureNotPrecompiled
+            );
+            signed[index] = true;
+            weight_accum += guardian.weight;
+        }
+
+        require!(
+            weight_accum >= guardian_set.min_threshold,
+            ErrorCode::InsufficientGuardianWeight
+        );
+
+        Ok(())
+    }
+
+    /// Create the `verified_msg` PDA of a VAA and write it; a VAA already
+    /// verified fails the whole batch
+    fn store(
+        ctx: &Context<'_, '_, '_, 'info, VerifyMessageBatch<'info>>,
+        info: &AccountInfo<'info>,
+        verified: &VerifiedMessage,
+    ) -> Result<()> {
+        let (address, bump) =
+            Pubkey::find_program_address(&[b"verified_msg", &verified.vaa_hash], ctx.program_id);
+        require_keys_eq!(info.key(), address, ErrorCode::InvalidMessageAccount);
+        require!(info.data_is_empty(), ErrorCode::DuplicateMessage);
+
+        let space = 8 + VerifiedMessage::LEN;
+        invoke_signed(
+            &system_instruction::create_account(
+                &ctx.accounts.payer.key(),
+                &address,
+                Rent::get()?.minimum_balance(space),
+                space as u64,
+                ctx.program_id,
+            ),
+            &[
+                ctx.accounts.payer.to_account_info(),
+                info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[&[b"verified_msg", &verified.vaa_hash, &[bump]]],
+        )?;
+
+        let mut data = info.try_borrow_mut_data()?;
+        verified.try_serialize(&mut &mut data[..])?;
+        Ok(())
+    }
+}
+
 // Fee calculation based on payload size
 fn calculate_verification_fee(payload_size: usize) -> Result<u64> {
     let base_fee = 100_000; // 0.1 USDC
@@ -202,6 +443,18 @@ pub enum ErrorCode {
     TimestampExpired,
     #[msg("Duplicate message verification")]
     DuplicateMessage,
+    #[msg("Batch is empty, too large, or does not match its accounts")]
+    InvalidBatchSize,
+    #[msg("Malformed ed25519 precompile instruction")]
+    MalformedEd25519Instruction,
+    #[msg("ed25519 signature data must live in the precompile instruction")]
+    ExternalSignatureData,
+    #[msg("Guardian signature was not verified by the ed25519 precompile")]
+    SignatureNotPrecompiled,
+    #[msg("Guardian signed the same VAA more than once")]
+    DuplicateGuardianSignature,
+    #[msg("Account is not the VAA's verified message PDA")]
+    InvalidMessageAccount,
 }
 
 // Constants and config
@@ -243,4 +496,51 @@ mod tests {
     fn test_expired_guardian_set() {
         // Test guardian set expiration validation
     }
+
+    fn ed25519_data(entries: &[([u8; 32], [u8; 64], &[u8])], message_ix: u16) -> Vec<u8> {
+        let header_len = ED25519_OFFSETS_START + entries.len() * ED25519_OFFSETS_LEN;
+        let mut offsets = vec![entries.len() as u8, 0];
+        let mut payload = Vec::new();
+        for (pubkey, signature, message) in entries {
+            let at = |payload: &Vec<u8>| (header_len + payload.len()) as u16;
+            let sig_offset = at(&payload);
+            payload.extend_from_slice(signature);
+            let key_offset = at(&payload);
+            payload.extend_from_slice(pubkey);
+            let msg_offset = at(&payload);
+            payload.extend_from_slice(message);
+            for field in [
+                sig_offset,
+                ED25519_SELF_INDEX,
+                key_offset,
+                ED25519_SELF_INDEX,
+                msg_offset,
+                message.len() as u16,
+                message_ix,
+            ] {
+                offsets.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+        offsets.extend(payload);
+        offsets
+    }
+
+    #[test]
+    fn test_precompiled_signatures_parsed_in_order() {
+        let data = ed25519_data(&[([1; 32], [2; 64], b"first"), ([3; 32], [4; 64], b"second")], ED25519_SELF_INDEX);
+        let entries = precompiled_signatures(&data).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].pubkey, &[3; 32]);
+        assert_eq!(entries[1].signature, &[4; 64]);
+        assert_eq!(entries[1].message, b"second");
+    }
+
+    #[test]
+    fn test_precompiled_signatures_reject_external_or_truncated_data() {
+        let external = ed25519_data(&[([1; 32], [2; 64], b"msg")], 0);
+        assert!(precompiled_signatures(&external).is_err());
+
+        let data = ed25519_data(&[([1; 32], [2; 64], b"msg")], ED25519_SELF_INDEX);
+        assert!(precompiled_signatures(&data[..data.len() - 1]).is_err());
+    }
 }
@@ -0,0 +1,273 @@
+//! Canonical test vectors for the byte encodings clients must reproduce
+//!
+//! Off-chain clients (TypeScript, Python, ...) compute the same hashes,
+//! commitments, PDAs and bridge payloads the program checks. This module
+//! derives a fixed set of vectors from the Rust implementation and serializes
+//! them as JSON; the fixture for the current `CODEC_VERSION` is checked into
+//! `tests/fixtures/conformance/` and other implementations test against it.
+//!
+//! Any change to an encoding must bump `CODEC_VERSION` and add a new fixture
+//! rather than rewrite the old one, so clients can tell which codec they match.
+//! Regenerate with `cargo test -p haunti-core conformance -- --ignored`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::{hash, hashv};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{
+    prepaid_balance::{PaymentAsset, PaymentPayload},
+    sharded_task::{ShardStatus, ShardedTask},
+};
+
+/// Version of the encodings the vectors describe
+pub const CODEC_VERSION: u32 = 1;
+
+/// Every vector set for one codec version
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ConformanceVectors {
+    /// `CODEC_VERSION` the vectors were generated with
+    pub codec_version: u32,
+    /// Program id the PDA vectors are derived under. A fixed stand-in rather
+    /// than the deployed id, so the vectors pin the seed layout only
+    pub program_id: String,
+    /// SHA-256 of an encrypted input or output, as recorded on tasks
+    pub ciphertext_hash: Vec<CiphertextHashVector>,
+    /// Per-shard commitment to a proof and its output
+    pub shard_proof_hash: Vec<ShardProofHashVector>,
+    /// Aggregated result commitment over proven shards
+    pub result_commitment: Vec<ResultCommitmentVector>,
+    /// Task PDA, seeds `[b"task", owner, input_hash]`
+    pub task_pda: Vec<TaskPdaVector>,
+    /// Wormhole VAA payload of a bridged payment
+    pub payment_payload: Vec<PaymentPayloadVector>,
+}
+
+/// `hash = sha256(ciphertext)`
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CiphertextHashVector {
+    /// Hex
+    pub ciphertext: String,
+    /// Hex
+    pub hash: String,
+}
+
+/// `proof_hash = sha256(proof || output_hash)`
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ShardProofHashVector {
+    /// Hex
+    pub proof: String,
+    /// Hex
+    pub output_hash: String,
+    /// Hex
+    pub proof_hash: String,
+}
+
+/// `commitment = sha256(index_le_u16 || proof_hash ...)` over proven shards in
+/// index order
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ResultCommitmentVector {
+    /// Shards the task was split into; those not listed are unproven
+    pub shard_count: u16,
+    /// Proven shards
+    pub proven: Vec<ProvenShard>,
+    /// Hex
+    pub commitment: String,
+}
+
+/// One proven shard of a `ResultCommitmentVector`
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ProvenShard {
+    /// Shard index
+    pub index: u16,
+    /// Hex
+    pub proof_hash: String,
+}
+
+/// Task account address and bump
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct TaskPdaVector {
+    /// Base58
+    pub owner: String,
+    /// Hex
+    pub input_hash: String,
+    /// Base58
+    pub address: String,
+    /// Canonical bump
+    pub bump: u8,
+}
+
+/// 74-byte payment payload: id, asset, u256 big-endian amount, recipient,
+/// big-endian lock id
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct PaymentPayloadVector {
+    /// `PaymentAsset` variant name
+    pub asset: String,
+    /// Decimal string; u128 does not fit a JSON number
+    pub amount: String,
+    /// Base58
+    pub recipient: String,
+    /// Decimal string, past the safe integer range of JSON numbers
+    pub lock_id: String,
+    /// Hex
+    pub encoded: String,
+}
+
+/// Derive the vectors from the current implementation
+pub fn generate() -> ConformanceVectors {
+    let program_id = Pubkey::new_from_array(sha256(&[b"haunti-core conformance program"]));
+
+    let ciphertext_hash = [Vec::new(), b"haunti".to_vec(), pattern(256, 3)]
+        .into_iter()
+        .map(|ciphertext| CiphertextHashVector {
+            hash: hex::encode(hash(&ciphertext).to_bytes()),
+            ciphertext: hex::encode(ciphertext),
+        })
+        .collect();
+
+    let shard_proof_hash = [
+        (pattern(64, 1), sha256(&[b"output-0"])),
+        (pattern(300, 9), sha256(&[b"output-1"])),
+    ]
+    .into_iter()
+    .map(|(proof, output_hash)| ShardProofHashVector {
+        proof_hash: hex::encode(hashv(&[&proof, &output_hash]).to_bytes()),
+        proof: hex::encode(proof),
+        output_hash: hex::encode(output_hash),
+    })
+    .collect();
+
+    let mut ramp = [0u8; 32];
+    ramp.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+    let result_commitment = [
+        vec![(0u16, [1u8; 32])],
+        vec![(0, [1; 32]), (2, [2; 32]), (5, ramp)],
+    ]
+    .into_iter()
+    .map(|proven| {
+        let shard_count = proven.iter().map(|(i, _)| i + 1).max().unwrap_or(0);
+        let mut task = ShardedTask {
+            shards: vec![ShardStatus::Open; shard_count as usize],
+            ..Default::default()
+        };
+        for (index, proof_hash) in &proven {
+            task.shards[*index as usize] = ShardStatus::Proven {
+                worker: Pubkey::default(),
+                proof_hash: *proof_hash,
+            };
+        }
+        ResultCommitmentVector {
+            shard_count,
+            commitment: hex::encode(task.aggregate_commitment()),
+            proven: proven
+                .iter()
+                .map(|(index, proof_hash)| ProvenShard {
+                    index: *index,
+                    proof_hash: hex::encode(proof_hash),
+                })
+                .collect(),
+        }
+    })
+    .collect();
+
+    let task_pda = [
+        (Pubkey::new_from_array([7; 32]), sha256(&[b"input-0"])),
+        (Pubkey::new_from_array(sha256(&[b"owner-1"])), [0; 32]),
+    ]
+    .into_iter()
+    .map(|(owner, input_hash)| {
+        let (address, bump) =
+            Pubkey::find_program_address(&[b"task", owner.as_ref(), &input_hash], &program_id);
+        TaskPdaVector {
+            owner: owner.to_string(),
+            input_hash: hex::encode(input_hash),
+            address: address.to_string(),
+            bump,
+        }
+    })
+    .collect();
+
+    let payment_payload = [
+        PaymentPayload {
+            asset: PaymentAsset::Eth,
+            amount: 1_500_000_000_000_000_000,
+            recipient: Pubkey::new_from_array(sha256(&[b"recipient-0"])),
+            lock_id: 42,
+        },
+        PaymentPayload {
+            asset: PaymentAsset::Usdc,
+            amount: 250_000_000,
+            recipient: Pubkey::new_from_array([9; 32]),
+            lock_id: u64::MAX,
+        },
+    ]
+    .into_iter()
+    .map(|payment| PaymentPayloadVector {
+        asset: format!("{:?}", payment.asset),
+        amount: payment.amount.to_string(),
+        recipient: payment.recipient.to_string(),
+        lock_id: payment.lock_id.to_string(),
+        encoded: hex::encode(payment.encode()),
+    })
+    .collect();
+
+    ConformanceVectors {
+        codec_version: CODEC_VERSION,
+        program_id: program_id.to_string(),
+        ciphertext_hash,
+        shard_proof_hash,
+        result_commitment,
+        task_pda,
+        payment_payload,
+    }
+}
+
+/// Pretty-printed JSON fixture for the current codec version
+pub fn to_json() -> String {
+    serde_json::to_string_pretty(&generate()).expect("vectors serialize")
+}
+
+/// Filler bytes `(seed + 7i) mod 256`
+fn pattern(len: usize, seed: u8) -> Vec<u8> {
+    (0..len).map(|i| seed.wrapping_add((i * 7) as u8)).collect()
+}
+
+fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+    hashv(parts).to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fixture of `CODEC_VERSION`; point this at the new file when bumping it
+    const FIXTURE: &str = include_str!("../tests/fixtures/conformance/v1.json");
+
+    #[test]
+    fn test_implementation_matches_fixture() {
+        let fixture: ConformanceVectors = serde_json::from_str(FIXTURE).unwrap();
+        assert_eq!(
+            fixture.codec_version, CODEC_VERSION,
+            "encoding changed: bump CODEC_VERSION and add a new fixture"
+        );
+        assert_eq!(generate(), fixture);
+    }
+
+    #[test]
+    fn test_payment_vectors_decode() {
+        for vector in generate().payment_payload {
+            let payment = PaymentPayload::decode(&hex::decode(&vector.encoded).unwrap()).unwrap();
+            assert_eq!(payment.amount.to_string(), vector.amount);
+            assert_eq!(payment.recipient.to_string(), vector.recipient);
+            assert_eq!(payment.lock_id.to_string(), vector.lock_id);
+        }
+    }
+
+    /// Writes the fixture for the current codec version
+    #[test]
+    #[ignore]
+    fn regenerate_fixture() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join(format!("tests/fixtures/conformance/v{CODEC_VERSION}.json"));
+        std::fs::write(path, to_json() + "\n").unwrap();
+    }
+}
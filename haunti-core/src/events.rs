@@ -0,0 +1,186 @@
+//! Self-CPI event emission for high-volume events
+//!
+//! `emit!` writes events into the program log, which the runtime truncates once a
+//! transaction's logs pass 10 KB. Events listed here are instead emitted with
+//! Anchor's `emit_cpi!` from the `#[program]` handlers: a self-invocation signed
+//! by the `__event_authority` PDA whose instruction data is
+//! `EVENT_IX_TAG_LE ++ discriminator ++ borsh`. The payload then lives in the
+//! transaction's inner instructions and is never cut.
+//!
+//! Encodings stay compact: fixed-width fields only, hashes in place of payloads.
+//! Budgets below cover the 8-byte discriminator plus the borsh body:
+//!
+//! | Event                      | Budget (bytes) |
+//! |----------------------------|----------------|
+//! | `TaskCreated`              | 208            |
+//! | `InferenceTaskPriced`      | 136            |
+//! | `ProofSubmitted`           | 168            |
+//! | `EvidenceSubmitted`        | 144            |
+//! | `StatsCheckpointPublished` | 128            |
+
+use anchor_lang::{event::EVENT_IX_TAG_LE, prelude::*, Discriminator, Event};
+
+use crate::instructions::{
+    create_inference_task::InferenceTaskPriced, create_task::TaskCreated,
+    publish_stats_checkpoint::StatsCheckpointPublished, submit_evidence::EvidenceSubmitted,
+    submit_proof::ProofSubmitted,
+};
+
+/// Seed of the PDA that signs event self-invocations (same as Anchor's `#[event_cpi]`)
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+/// Upper bound for any self-CPI event; well under the 10 KB CPI data limit
+pub const MAX_CPI_EVENT_LEN: usize = 256;
+
+/// Per-event size budget, enforced in tests
+pub trait EventBudget: Event {
+    /// Maximum of `Event::data().len()`
+    const BUDGET: usize;
+}
+
+impl EventBudget for TaskCreated {
+    const BUDGET: usize = 208;
+}
+
+impl EventBudget for InferenceTaskPriced {
+    const BUDGET: usize = 136;
+}
+
+impl EventBudget for ProofSubmitted {
+    const BUDGET: usize = 168;
+}
+
+impl EventBudget for EvidenceSubmitted {
+    const BUDGET: usize = 144;
+}
+
+impl EventBudget for StatsCheckpointPublished {
+    const BUDGET: usize = 128;
+}
+
+/// Events recoverable from haunti-core inner instructions
+pub enum CoreEvent {
+    /// Emitted by `create_task`
+    TaskCreated(TaskCreated),
+    /// Emitted by `create_inference_task`
+    InferenceTaskPriced(InferenceTaskPriced),
+    /// Emitted by `submit_proof`
+    ProofSubmitted(ProofSubmitted),
+    /// Emitted by `create_evidence`
+    EvidenceSubmitted(EvidenceSubmitted),
+    /// Emitted by `publish_stats_checkpoint`
+    StatsCheckpointPublished(StatsCheckpointPublished),
+}
+
+/// Decode the data of a haunti-core self-CPI; `None` for other instructions or unknown events
+pub fn decode_cpi_event(ix_data: &[u8]) -> Option<CoreEvent> {
+    let body = ix_data.strip_prefix(EVENT_IX_TAG_LE.as_slice())?;
+    if body.len() > MAX_CPI_EVENT_LEN || body.len() < 8 {
+        return None;
+    }
+    let (discriminator, mut payload) = body.split_at(8);
+
+    fn parse<E: AnchorDeserialize>(payload: &mut &[u8]) -> Option<E> {
+        E::deserialize(payload).ok()
+    }
+
+    match discriminator {
+        d if d == TaskCreated::DISCRIMINATOR => parse(&mut payload).map(CoreEvent::TaskCreated),
+        d if d == InferenceTaskPriced::DISCRIMINATOR => {
+            parse(&mut payload).map(CoreEvent::InferenceTaskPriced)
+        }
+        d if d == ProofSubmitted::DISCRIMINATOR => {
+            parse(&mut payload).map(CoreEvent::ProofSubmitted)
+        }
+        d if d == EvidenceSubmitted::DISCRIMINATOR => {
+            parse(&mut payload).map(CoreEvent::EvidenceSubmitted)
+        }
+        d if d == StatsCheckpointPublished::DISCRIMINATOR => {
+            parse(&mut payload).map(CoreEvent::StatsCheckpointPublished)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ix_data<E: Event>(event: &E) -> Vec<u8> {
+        EVENT_IX_TAG_LE.iter().copied().chain(event.data()).collect()
+    }
+
+    #[test]
+    fn test_events_fit_budgets_and_round_trip() {
+        let key = Pubkey::new_unique();
+        let proof = ProofSubmitted {
+            task: key,
+            owner: key,
+            output_hash: [7; 32],
+            storage_root: [8; 32],
+            proof_len: 4_096,
+            consumed_cu: 1_000_000,
+            payout: 9,
+            timestamp: 1,
+        };
+        assert!(proof.data().len() <= ProofSubmitted::BUDGET);
+        assert!(matches!(
+            decode_cpi_event(&ix_data(&proof)),
+            Some(CoreEvent::ProofSubmitted(e)) if e.output_hash == [7; 32] && e.proof_len == 4_096
+        ));
+
+        let created = TaskCreated {
+            task: key,
+            owner: key,
+            model_hash: [1; 32],
+            reward: 5,
+            reward_mint: Some(key),
+            priority_fee: 7,
+            deadline: Some(9),
+            rng_seed: [4; 32],
+            timestamp: 2,
+        };
+        assert!(created.data().len() <= TaskCreated::BUDGET);
+        assert!(matches!(
+            decode_cpi_event(&ix_data(&created)),
+            Some(CoreEvent::TaskCreated(e))
+                if e.reward == 5 && e.reward_mint == Some(key) && e.deadline == Some(9)
+        ));
+
+        let evidence = EvidenceSubmitted {
+            dispute: key,
+            evidence: key,
+            submitter: key,
+            content_hash: [2; 32],
+            timestamp: 3,
+        };
+        assert!(evidence.data().len() <= EvidenceSubmitted::BUDGET);
+
+        let stats = StatsCheckpointPublished {
+            checkpoint: key,
+            epoch: 1,
+            tasks_completed: 1,
+            proofs_verified: 1,
+            rewards_paid: 1,
+            active_workers: 1,
+            records_root: [3; 32],
+            timestamp: 4,
+        };
+        assert!(stats.data().len() <= StatsCheckpointPublished::BUDGET);
+
+        let priced = InferenceTaskPriced {
+            task: key,
+            model: key,
+            input_hash: [4; 32],
+            escrow: 1,
+            surge_fee: 1,
+            queued_tasks: 1,
+            quoted: true,
+            timestamp: 5,
+        };
+        assert!(priced.data().len() <= InferenceTaskPriced::BUDGET);
+
+        // Plain instruction data is not mistaken for an event
+        assert!(decode_cpi_event(&created.data()).is_none());
+    }
+}
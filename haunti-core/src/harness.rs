@@ -0,0 +1,50 @@
+//! solana-program-test helpers for moving the bank clock
+//!
+//! Handlers read time through `env`, which falls through to the `Clock` sysvar
+//! inside a bank. These helpers warp the bank to a slot and set the sysvar's
+//! timestamp with it, so lockup and expiry paths run at exact times without
+//! waiting out real slots.
+
+use solana_program::clock::Clock;
+use solana_program_test::{BanksClientError, ProgramTestContext, ProgramTestError};
+use thiserror::Error;
+
+/// Failure moving the bank clock
+#[derive(Debug, Error)]
+pub enum HarnessError {
+    /// The bank refused the warp, e.g. to a slot it already passed
+    #[error("Failed to warp: {0:?}")]
+    Warp(ProgramTestError),
+    /// The clock sysvar could not be read
+    #[error("Failed to read the clock: {0}")]
+    Banks(#[from] BanksClientError),
+}
+
+/// Current bank clock
+pub async fn clock(context: &mut ProgramTestContext) -> Result<Clock, HarnessError> {
+    Ok(context.banks_client.get_sysvar::<Clock>().await?)
+}
+
+/// Warp to `slot` and make the clock read `unix_timestamp` there
+pub async fn warp_to(
+    context: &mut ProgramTestContext,
+    slot: u64,
+    unix_timestamp: i64,
+) -> Result<Clock, HarnessError> {
+    context.warp_to_slot(slot).map_err(HarnessError::Warp)?;
+    let mut clock = clock(context).await?;
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+    Ok(clock)
+}
+
+/// Move the clock forward by `secs`, and at least one slot so the bank
+/// produces a fresh blockhash
+pub async fn advance(
+    context: &mut ProgramTestContext,
+    slots: u64,
+    secs: i64,
+) -> Result<Clock, HarnessError> {
+    let now = clock(context).await?;
+    warp_to(context, now.slot + slots.max(1), now.unix_timestamp + secs).await
+}
@@ -0,0 +1,124 @@
+//! Instruction handler for completing tasks settled by one aggregated proof
+//!
+//! The verifier program checks a recursive proof covering a batch of tasks, then
+//! calls in here signed by its `[b"verifier_authority"]` PDA. Tasks are passed as
+//! remaining accounts; each is accepted only if the signing authority belongs to
+//! the verifier version the task was tagged with. The pricing accounts of priced
+//! inference tasks follow the tasks, once per distinct account.
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::{
+    pricing_state::{ModelPricing, PricingError},
+    task_feed::{EventCounter, TaskFeedKind},
+    task_state::{TaskError, TaskState, TaskStatus, TaskStatusChanged},
+    verifier_registry::{VerifierRegistry, VerifierRegistryError},
+};
+
+/// Seed of the PDA a verifier program signs aggregated completions with
+pub const VERIFIER_AUTHORITY_SEED: &[u8] = b"verifier_authority";
+
+/// Accounts of `complete_aggregated_tasks`, beside the task PDAs
+#[derive(Accounts)]
+pub struct CompleteAggregatedTasks<'info> {
+    /// `[b"verifier_authority"]` PDA of the verifier program that checked the proof
+    pub verifier_authority: Signer<'info>,
+
+    /// Maps each task's verifier version to its program
+    #[account(seeds = [b"verifier_registry"], bump = verifier_registry.bump)]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+}
+
+impl<'info> CompleteAggregatedTasks<'info> {
+    /// Complete each running task in `accounts` with the matching entry of
+    /// `result_hashes`; accounts past the tasks are their pricing queues
+    pub fn execute(&mut self, accounts: &[AccountInfo<'info>], result_hashes: &[[u8; 32]]) -> Result<()> {
+        require!(
+            !result_hashes.is_empty() && accounts.len() >= result_hashes.len(),
+            AggregationError::BatchMismatch
+        );
+        let (tasks, pricings) = accounts.split_at(result_hashes.len());
+        let now = env::now()?;
+
+        for (info, result_hash) in tasks.iter().zip(result_hashes) {
+            require!(info.is_writable, AggregationError::TaskNotWritable);
+            let mut task = Account::<TaskState>::try_from(info)?;
+            let (address, _) = Pubkey::find_program_address(
+                &[b"task", task.owner.as_ref(), &task.input_hash],
+                &crate::ID,
+            );
+            require_keys_eq!(info.key(), address, AggregationError::InvalidTask);
+
+            self.verifier_registry.require_accepted(task.verifier_version)?;
+            let verifier = self
+                .verifier_registry
+                .program_for(task.verifier_version)
+                .ok_or(VerifierRegistryError::UnknownVersion)?;
+            let (authority, _) = Pubkey::find_program_address(&[VERIFIER_AUTHORITY_SEED], &verifier);
+            require_keys_eq!(
+                self.verifier_authority.key(),
+                authority,
+                AggregationError::WrongVerifier
+            );
+
+            let old_status = task.status.clone();
+            let worker = match old_status {
+                TaskStatus::Running { worker, .. } => worker,
+                _ => return Err(TaskError::InvalidStateTransition.into()),
+            };
+            task.complete(*result_hash)?;
+            if let Some(held) = task.pricing {
+                let info = pricings
+                    .iter()
+                    .find(|info| info.key() == held)
+                    .ok_or(PricingError::PricingAccountRequired)?;
+                require!(info.is_writable, AggregationError::TaskNotWritable);
+                let mut pricing = Account::<ModelPricing>::try_from(info)?;
+                pricing.release(&held, &mut task)?;
+                pricing.exit(&crate::ID)?;
+            }
+            task.exit(&crate::ID)?;
+
+            emit!(TaskStatusChanged {
+                task: info.key(),
+                old_status,
+                new_status: task.status.clone(),
+                version: task.version,
+                timestamp: now,
+            });
+            self.event_counter
+                .emit(info.key(), TaskFeedKind::Completed, worker, 0)?;
+        }
+
+        emit!(AggregatedTasksCompleted {
+            verifier_authority: self.verifier_authority.key(),
+            tasks: tasks.len() as u8,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct AggregatedTasksCompleted {
+    pub verifier_authority: Pubkey,
+    pub tasks: u8,
+    pub timestamp: i64,
+}
+
+#[error_code]
+pub enum AggregationError {
+    #[msg("Task accounts and result hashes differ in number")]
+    BatchMismatch,
+    #[msg("Task account must be writable")]
+    TaskNotWritable,
+    #[msg("Account is not a task PDA")]
+    InvalidTask,
+    #[msg("Signer is not the authority of the task's verifier")]
+    WrongVerifier,
+}
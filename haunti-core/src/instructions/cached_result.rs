@@ -0,0 +1,230 @@
+//! Inference result deduplication: the model owner's opt-out, and tasks served
+//! from another task's verified result for the discounted cached fee
+
+use anchor_lang::{prelude::*, solana_program::system_instruction};
+use crate::env;
+use crate::state::{
+    content_policy::ensure_accepting_tasks,
+    model_state::{ModelError, ModelState, ModelStatusKind},
+    pricing_state::{cached_fee, ModelPricing, PricingError},
+    task_feed::{EventCounter, TaskFeedKind},
+    task_state::{TaskError, TaskState},
+    verifier_registry::VerifierRegistry,
+};
+
+#[derive(Accounts)]
+pub struct SetModelNondeterministic<'info> {
+    #[account(mut, has_one = owner)]
+    pub model: Account<'info, ModelState>,
+
+    pub owner: Signer<'info>,
+}
+
+impl<'info> SetModelNondeterministic<'info> {
+    /// Opt the model out of result deduplication, or back in
+    pub fn execute(&mut self, nondeterministic: bool) -> Result<()> {
+        self.model.set_nondeterministic(nondeterministic)?;
+
+        emit!(ModelDedupChanged {
+            model: self.model.key(),
+            nondeterministic,
+            revision: self.model.revision,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(input_hash: [u8; 32])]
+pub struct CreateCachedInferenceTask<'info> {
+    #[account(
+        constraint = model.status.kind() == ModelStatusKind::Active @ PricingError::InvalidParameters,
+        constraint = !model.nondeterministic @ ModelError::NondeterministicModel
+    )]
+    pub model: Account<'info, ModelState>,
+
+    /// CHECK: moderation PDA of the model; empty until the model is first flagged
+    #[account(seeds = [b"moderation", model.key().as_ref()], bump)]
+    pub moderation: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pricing", model.key().as_ref()],
+        bump = pricing.bump,
+        has_one = model
+    )]
+    pub pricing: Account<'info, ModelPricing>,
+
+    /// Task of the same model and input whose result is reused
+    #[account(
+        seeds = [b"task", source_task.owner.as_ref(), &source_task.input_hash],
+        bump = source_task.bump,
+        constraint = source_task.input_hash == input_hash @ TaskError::CacheSourceMismatch,
+        constraint = source_task.model_hash == model.model_root @ TaskError::CacheSourceMismatch
+    )]
+    pub source_task: Account<'info, TaskState>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = TaskState::LEN,
+        seeds = [b"task", owner.key().as_ref(), &input_hash],
+        bump
+    )]
+    pub task: Account<'info, TaskState>,
+
+    #[account(seeds = [b"verifier_registry"], bump = verifier_registry.bump)]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateCachedInferenceTask<'info> {
+    /// Open a task answered by `source_task`'s result, escrowing the cached
+    /// fee instead of the full base fee. The task takes no pricing queue slot:
+    /// no model execution is scheduled for it
+    pub fn execute(&mut self, input_hash: [u8; 32], max_fee: u64, bump: u8) -> Result<()> {
+        ensure_accepting_tasks(&self.moderation)?;
+        let result_hash = self
+            .source_task
+            .verified_result()
+            .ok_or(TaskError::ResultNotVerified)?;
+
+        let now = env::now()?;
+        let escrow = cached_fee(self.pricing.update(now)?)?;
+        require!(escrow <= max_fee, PricingError::MaxFeeExceeded);
+
+        let task = &mut self.task;
+        task.bump = bump;
+        task.created_at = now;
+        task.owner = self.owner.key();
+        task.input_hash = input_hash;
+        task.model_hash = self.model.model_root;
+        task.verifier_version = self.verifier_registry.current;
+        task.cached_from = Some(self.source_task.key());
+
+        anchor_lang::solana_program::program::invoke(
+            &system_instruction::transfer(&self.owner.key(), &self.task.key(), escrow),
+            &[
+                self.owner.to_account_info(),
+                self.task.to_account_info(),
+                self.system_program.to_account_info(),
+            ],
+        )?;
+
+        emit!(CachedInferenceTaskCreated {
+            task: self.task.key(),
+            model: self.model.key(),
+            source_task: self.source_task.key(),
+            result_hash,
+            escrow,
+            timestamp: now,
+        });
+        self.event_counter
+            .emit(self.task.key(), TaskFeedKind::Created, self.owner.key(), escrow)
+    }
+}
+
+#[derive(Accounts)]
+pub struct SubmitCachedResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump,
+        constraint = task.cached_from == Some(source_task.key()) @ TaskError::CacheSourceMismatch
+    )]
+    pub task: Account<'info, TaskState>,
+
+    #[account(
+        seeds = [b"task", source_task.owner.as_ref(), &source_task.input_hash],
+        bump = source_task.bump
+    )]
+    pub source_task: Account<'info, TaskState>,
+
+    /// A model updated or opted out of dedup since the task was opened no
+    /// longer stands behind the cached result
+    #[account(
+        constraint = model.model_root == task.model_hash @ TaskError::ModelHashMismatch,
+        constraint = !model.nondeterministic @ ModelError::NondeterministicModel
+    )]
+    pub model: Account<'info, ModelState>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Node delivering the cached result; earns the cached fee
+    #[account(mut)]
+    pub worker: Signer<'info>,
+}
+
+impl<'info> SubmitCachedResult<'info> {
+    /// Complete a cached task with its source's result and pay out the escrow
+    pub fn execute(&mut self) -> Result<()> {
+        let result_hash = self
+            .source_task
+            .verified_result()
+            .ok_or(TaskError::ResultNotVerified)?;
+        let worker = self.worker.key();
+        self.task.start(worker)?;
+        self.task.complete(result_hash)?;
+
+        let task = self.task.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(task.data_len());
+        let payout = task.lamports().saturating_sub(rent_floor);
+        **task.try_borrow_mut_lamports()? -= payout;
+        **self.worker.to_account_info().try_borrow_mut_lamports()? += payout;
+
+        emit!(CachedResultServed {
+            task: self.task.key(),
+            source_task: self.source_task.key(),
+            worker,
+            result_hash,
+            payout,
+            timestamp: env::now()?,
+        });
+        let task = self.task.key();
+        self.event_counter.emit(task, TaskFeedKind::Claimed, worker, 0)?;
+        self.event_counter
+            .emit(task, TaskFeedKind::Completed, worker, payout)
+    }
+}
+
+#[event]
+pub struct ModelDedupChanged {
+    pub model: Pubkey,
+    pub nondeterministic: bool,
+    pub revision: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CachedInferenceTaskCreated {
+    pub task: Pubkey,
+    pub model: Pubkey,
+    pub source_task: Pubkey,
+    pub result_hash: [u8; 32],
+    /// Cached fee escrowed
+    pub escrow: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CachedResultServed {
+    pub task: Pubkey,
+    pub source_task: Pubkey,
+    pub worker: Pubkey,
+    pub result_hash: [u8; 32],
+    pub payout: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,178 @@
+//! Instruction handlers for cancelling pending or running tasks
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use crate::env;
+use crate::state::{
+    pricing_state::{release_queue_slot, ModelPricing},
+    reward_escrow::refund_token_escrow,
+    task_feed::{EventCounter, TaskFeedKind},
+    task_state::{
+        RefundReason, TaskError, TaskRefunded, TaskState, TaskStatus, TaskStatusChanged,
+    },
+    TaskAccount, TaskState as TaskAccountState,
+};
+
+#[derive(Accounts)]
+pub struct CancelTask<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump,
+        has_one = owner
+    )]
+    pub task: Account<'info, TaskState>,
+
+    /// Receives the escrow refund
+    /// CHECK: validated against `task.owner`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Pricing queue the task holds a slot in; required for priced inference tasks
+    #[account(mut)]
+    pub pricing: Option<Account<'info, ModelPricing>>,
+
+    /// Task owner while the task is pending, or the worker currently executing it
+    pub authority: Signer<'info>,
+}
+
+impl<'info> CancelTask<'info> {
+    /// Once a worker has picked the task up only that worker may give it back;
+    /// the owner can no longer pull the escrow out from under the work
+    pub fn execute(&mut self) -> Result<()> {
+        let authority = self.authority.key();
+        let old_status = self.task.status.clone();
+        let permitted = match old_status {
+            TaskStatus::Pending => authority == self.task.owner,
+            TaskStatus::Running { worker, .. } => authority == worker,
+            _ => false,
+        };
+        require!(permitted, TaskError::Unauthorized);
+
+        self.task.cancel()?;
+        release_queue_slot(&mut self.task, self.pricing.as_mut())?;
+
+        // Refund everything above rent-exemption back to the owner
+        let task_info = self.task.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(task_info.data_len());
+        let refund = task_info.lamports().saturating_sub(rent_floor);
+        if refund > 0 {
+            **task_info.try_borrow_mut_lamports()? -= refund;
+            **self.owner.to_account_info().try_borrow_mut_lamports()? += refund;
+        }
+
+        let now = env::now()?;
+        emit!(TaskStatusChanged {
+            task: self.task.key(),
+            old_status,
+            new_status: self.task.status.clone(),
+            version: self.task.version,
+            timestamp: now,
+        });
+        emit!(TaskCancelled {
+            task: self.task.key(),
+            cancelled_by: authority,
+            refund,
+            timestamp: now,
+        });
+        emit!(TaskRefunded {
+            task: self.task.key(),
+            owner: self.owner.key(),
+            amount: refund,
+            reason: RefundReason::Cancelled,
+            timestamp: now,
+        });
+        let task = self.task.key();
+        self.event_counter.emit(task, TaskFeedKind::Cancelled, authority, 0)?;
+        self.event_counter
+            .emit(task, TaskFeedKind::Refunded, self.owner.key(), refund)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CancelTaskAccount<'info> {
+    /// Closed to the owner, which returns the lamport escrow with the rent
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"task", owner.key().as_ref(), task_account.model.model_hash.as_ref()],
+        bump,
+        has_one = owner @ TaskError::Unauthorized,
+        constraint = task_account.state == TaskAccountState::Pending
+            @ TaskError::InvalidStateTransition
+    )]
+    pub task_account: Account<'info, TaskAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    // Required when the task escrowed its reward in an SPL mint
+    #[account(mut)]
+    pub reward_escrow: Option<Account<'info, TokenAccount>>,
+
+    // Receives the token escrow
+    #[account(mut)]
+    pub owner_reward_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+impl<'info> CancelTaskAccount<'info> {
+    /// Withdraw a training task no proof has settled yet and refund its escrow
+    pub fn execute(&mut self, bump: u8) -> Result<()> {
+        let refund = match self.task_account.reward_mint {
+            Some(mint) => refund_token_escrow(
+                &self.task_account,
+                bump,
+                mint,
+                self.reward_escrow.as_ref(),
+                self.owner_reward_account.as_ref(),
+                self.token_program.as_ref(),
+                self.owner.to_account_info(),
+            )?,
+            None => {
+                let task = self.task_account.to_account_info();
+                let rent_floor = Rent::get()?.minimum_balance(task.data_len());
+                task.lamports().saturating_sub(rent_floor)
+            }
+        };
+
+        let now = env::now()?;
+        let (task, owner) = (self.task_account.key(), self.owner.key());
+        emit!(TaskCancelled {
+            task,
+            cancelled_by: owner,
+            refund,
+            timestamp: now,
+        });
+        emit!(TaskRefunded {
+            task,
+            owner,
+            amount: refund,
+            reason: RefundReason::Cancelled,
+            timestamp: now,
+        });
+        self.event_counter.emit(task, TaskFeedKind::Cancelled, owner, 0)?;
+        self.event_counter.emit(task, TaskFeedKind::Refunded, owner, refund)?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct TaskCancelled {
+    pub task: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub refund: u64,
+    pub timestamp: i64,
+}
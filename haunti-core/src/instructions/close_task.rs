@@ -0,0 +1,48 @@
+//! Instruction handler for closing finished tasks and reclaiming their rent
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::task_state::{TaskError, TaskState};
+
+#[derive(Accounts)]
+pub struct CloseTask<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump,
+        has_one = owner
+    )]
+    pub task: Account<'info, TaskState>,
+
+    /// Task owner; receives the rent and any remaining escrow
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+impl<'info> CloseTask<'info> {
+    /// Only completed, failed or cancelled tasks may be closed
+    pub fn execute(&mut self) -> Result<()> {
+        require!(
+            self.task.status.kind().is_terminal(),
+            TaskError::TaskNotTerminal
+        );
+
+        emit!(TaskClosed {
+            task: self.task.key(),
+            owner: self.owner.key(),
+            lamports: self.task.to_account_info().lamports(),
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct TaskClosed {
+    pub task: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub timestamp: i64,
+}
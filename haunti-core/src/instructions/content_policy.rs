@@ -0,0 +1,461 @@
+//! Instruction handlers for the content-policy registry, model flags, takedowns and appeals
+
+use anchor_lang::prelude::*;
+use token_vault::StakeHistory;
+use crate::env;
+use crate::state::{
+    content_policy::{
+        ContentPolicy, ModelFlag, ModelModeration, ModerationError, ModerationStatus,
+        MIN_FLAG_STAKE_AGE,
+    },
+    model_state::ModelState,
+};
+
+#[derive(Accounts)]
+pub struct InitContentPolicy<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = ContentPolicy::LEN,
+        seeds = [b"content_policy"],
+        bump
+    )]
+    pub policy: Account<'info, ContentPolicy>,
+
+    /// Governance authority that will own the policy
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// Only the upgrade authority may create the policy and pick its governance
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitContentPolicy<'info> {
+    pub fn execute(
+        &mut self,
+        policy_hash: [u8; 32],
+        enforcement_authority: Pubkey,
+        stake_pool: Pubkey,
+        flag_threshold: u64,
+        appeal_window: i64,
+        bump: u8,
+    ) -> Result<()> {
+        require!(
+            flag_threshold > 0 && appeal_window > 0,
+            ModerationError::InvalidPolicy
+        );
+
+        let now = env::now()?;
+        let policy = &mut self.policy;
+        policy.bump = bump;
+        policy.governance = self.governance.key();
+        policy.enforcement_authority = enforcement_authority;
+        policy.policy_hash = policy_hash;
+        policy.policy_version = 1;
+        policy.stake_pool = stake_pool;
+        policy.flag_threshold = flag_threshold;
+        policy.appeal_window = appeal_window;
+        policy.updated_at = now;
+
+        emit!(ContentPolicyUpdated {
+            policy_hash,
+            policy_version: 1,
+            enforcement_authority,
+            flag_threshold,
+            appeal_window,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateContentPolicy<'info> {
+    #[account(
+        mut,
+        seeds = [b"content_policy"],
+        bump = policy.bump,
+        has_one = governance @ ModerationError::Unauthorized
+    )]
+    pub policy: Account<'info, ContentPolicy>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> UpdateContentPolicy<'info> {
+    /// Publish a new policy document or retune enforcement; models already frozen
+    /// keep the version they are being reviewed against
+    pub fn execute(
+        &mut self,
+        policy_hash: [u8; 32],
+        enforcement_authority: Pubkey,
+        flag_threshold: u64,
+        appeal_window: i64,
+    ) -> Result<()> {
+        require!(
+            flag_threshold > 0 && appeal_window > 0,
+            ModerationError::InvalidPolicy
+        );
+
+        let now = env::now()?;
+        let policy = &mut self.policy;
+        if policy.policy_hash != policy_hash {
+            policy.policy_hash = policy_hash;
+            policy.policy_version += 1;
+        }
+        policy.enforcement_authority = enforcement_authority;
+        policy.flag_threshold = flag_threshold;
+        policy.appeal_window = appeal_window;
+        policy.updated_at = now;
+
+        emit!(ContentPolicyUpdated {
+            policy_hash,
+            policy_version: policy.policy_version,
+            enforcement_authority,
+            flag_threshold,
+            appeal_window,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct FlagModel<'info> {
+    #[account(seeds = [b"content_policy"], bump = policy.bump)]
+    pub policy: Account<'info, ContentPolicy>,
+
+    pub model: Account<'info, ModelState>,
+
+    #[account(
+        init_if_needed,
+        payer = flagger,
+        space = ModelModeration::LEN,
+        seeds = [b"moderation", model.key().as_ref()],
+        bump
+    )]
+    pub moderation: Account<'info, ModelModeration>,
+
+    /// One flag per staker per review round
+    #[account(
+        init,
+        payer = flagger,
+        space = ModelFlag::LEN,
+        seeds = [
+            b"model_flag",
+            model.key().as_ref(),
+            &moderation.round.to_le_bytes(),
+            flagger.key().as_ref()
+        ],
+        bump
+    )]
+    pub flag: Account<'info, ModelFlag>,
+
+    /// Flagger's voting-power checkpoints in the policy's token-vault pool,
+    /// covering liquid stake and locked positions alike
+    #[account(
+        seeds = [b"stake_history", policy.stake_pool.as_ref(), flagger.key().as_ref()],
+        bump = stake_history.bump,
+        seeds::program = token_vault::ID
+    )]
+    pub stake_history: Account<'info, StakeHistory>,
+
+    #[account(mut)]
+    pub flagger: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FlagModel<'info> {
+    /// Flag the model, weighted by the power the flagger has held since
+    /// `MIN_FLAG_STAKE_AGE` ago and still holds
+    pub fn execute(
+        &mut self,
+        reason_hash: [u8; 32],
+        moderation_bump: u8,
+        flag_bump: u8,
+    ) -> Result<()> {
+        let now = env::now()?;
+        let history = &self.stake_history;
+        let aged = history.amount_at(now.saturating_sub(MIN_FLAG_STAKE_AGE))?;
+        let current = history.liquid_amount.saturating_add(history.locked_weight);
+        let weight = aged.min(current);
+
+        let moderation = &mut self.moderation;
+        if moderation.model == Pubkey::default() {
+            moderation.bump = moderation_bump;
+            moderation.model = self.model.key();
+            moderation.updated_at = now;
+        }
+        if moderation.flag_count == 0 {
+            moderation.policy_version = self.policy.policy_version;
+        }
+        let frozen = moderation.add_flag(weight, self.policy.flag_threshold, now)?;
+
+        let flag = &mut self.flag;
+        flag.bump = flag_bump;
+        flag.model = self.model.key();
+        flag.flagger = self.flagger.key();
+        flag.round = moderation.round;
+        flag.weight = weight;
+        flag.reason_hash = reason_hash;
+        flag.created_at = now;
+
+        emit!(ModelFlagged {
+            model: self.model.key(),
+            flagger: self.flagger.key(),
+            round: moderation.round,
+            weight,
+            flag_weight: moderation.flag_weight,
+            reason_hash,
+            timestamp: now,
+        });
+        if frozen {
+            emit!(ModelFrozen {
+                model: self.model.key(),
+                round: moderation.round,
+                flag_weight: moderation.flag_weight,
+                flag_count: moderation.flag_count,
+                policy_version: moderation.policy_version,
+                timestamp: now,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct TakedownModel<'info> {
+    #[account(
+        seeds = [b"content_policy"],
+        bump = policy.bump,
+        constraint = policy.can_enforce(&authority.key()) @ ModerationError::Unauthorized
+    )]
+    pub policy: Account<'info, ContentPolicy>,
+
+    #[account(mut, seeds = [b"moderation", moderation.model.as_ref()], bump = moderation.bump)]
+    pub moderation: Account<'info, ModelModeration>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> TakedownModel<'info> {
+    /// Confirm a frozen model breaches the policy
+    pub fn execute(&mut self, decision_hash: [u8; 32]) -> Result<()> {
+        let now = env::now()?;
+        self.moderation.take_down(decision_hash, now)?;
+
+        emit!(ModelTakenDown {
+            model: self.moderation.model,
+            authority: self.authority.key(),
+            round: self.moderation.round,
+            policy_version: self.moderation.policy_version,
+            decision_hash,
+            appeal_deadline: now.saturating_add(self.policy.appeal_window),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReinstateModel<'info> {
+    #[account(
+        seeds = [b"content_policy"],
+        bump = policy.bump,
+        constraint = policy.can_enforce(&authority.key()) @ ModerationError::Unauthorized
+    )]
+    pub policy: Account<'info, ContentPolicy>,
+
+    #[account(
+        mut,
+        seeds = [b"moderation", moderation.model.as_ref()],
+        bump = moderation.bump,
+        constraint = moderation.status == ModerationStatus::Frozen @ ModerationError::InvalidModerationState
+    )]
+    pub moderation: Account<'info, ModelModeration>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> ReinstateModel<'info> {
+    /// Dismiss the flags against a frozen model and reopen it for tasks
+    pub fn execute(&mut self) -> Result<()> {
+        let now = env::now()?;
+        let dismissed_round = self.moderation.round;
+        self.moderation.reinstate(now)?;
+
+        emit!(ModelReinstated {
+            model: self.moderation.model,
+            authority: self.authority.key(),
+            dismissed_round,
+            on_appeal: false,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AppealTakedown<'info> {
+    #[account(seeds = [b"content_policy"], bump = policy.bump)]
+    pub policy: Account<'info, ContentPolicy>,
+
+    #[account(has_one = owner @ ModerationError::Unauthorized)]
+    pub model: Account<'info, ModelState>,
+
+    #[account(
+        mut,
+        seeds = [b"moderation", model.key().as_ref()],
+        bump = moderation.bump
+    )]
+    pub moderation: Account<'info, ModelModeration>,
+
+    pub owner: Signer<'info>,
+}
+
+impl<'info> AppealTakedown<'info> {
+    /// Ask governance to review a takedown
+    pub fn execute(&mut self, appeal_hash: [u8; 32]) -> Result<()> {
+        let now = env::now()?;
+        self.moderation
+            .appeal(appeal_hash, self.policy.appeal_window, now)?;
+
+        emit!(TakedownAppealed {
+            model: self.model.key(),
+            owner: self.owner.key(),
+            round: self.moderation.round,
+            appeal_hash,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ResolveAppeal<'info> {
+    #[account(
+        seeds = [b"content_policy"],
+        bump = policy.bump,
+        has_one = governance @ ModerationError::Unauthorized
+    )]
+    pub policy: Account<'info, ContentPolicy>,
+
+    #[account(mut, seeds = [b"moderation", moderation.model.as_ref()], bump = moderation.bump)]
+    pub moderation: Account<'info, ModelModeration>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> ResolveAppeal<'info> {
+    /// Uphold the takedown for good, or overturn it and reinstate the model
+    pub fn execute(&mut self, upheld: bool) -> Result<()> {
+        let now = env::now()?;
+        let round = self.moderation.round;
+        self.moderation.resolve_appeal(upheld, now)?;
+
+        emit!(AppealResolved {
+            model: self.moderation.model,
+            governance: self.governance.key(),
+            round,
+            upheld,
+            timestamp: now,
+        });
+        if !upheld {
+            emit!(ModelReinstated {
+                model: self.moderation.model,
+                authority: self.governance.key(),
+                dismissed_round: round,
+                on_appeal: true,
+                timestamp: now,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ContentPolicyUpdated {
+    pub policy_hash: [u8; 32],
+    pub policy_version: u32,
+    pub enforcement_authority: Pubkey,
+    pub flag_threshold: u64,
+    pub appeal_window: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ModelFlagged {
+    pub model: Pubkey,
+    pub flagger: Pubkey,
+    pub round: u32,
+    pub weight: u64,
+    pub flag_weight: u64,
+    pub reason_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ModelFrozen {
+    pub model: Pubkey,
+    pub round: u32,
+    pub flag_weight: u64,
+    pub flag_count: u32,
+    pub policy_version: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ModelTakenDown {
+    pub model: Pubkey,
+    pub authority: Pubkey,
+    pub round: u32,
+    pub policy_version: u32,
+    pub decision_hash: [u8; 32],
+    pub appeal_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ModelReinstated {
+    pub model: Pubkey,
+    pub authority: Pubkey,
+    pub dismissed_round: u32,
+    pub on_appeal: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TakedownAppealed {
+    pub model: Pubkey,
+    pub owner: Pubkey,
+    pub round: u32,
+    pub appeal_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AppealResolved {
+    pub model: Pubkey,
+    pub governance: Pubkey,
+    pub round: u32,
+    pub upheld: bool,
+    pub timestamp: i64,
+}
@@ -0,0 +1,224 @@
+//! Instruction handlers for demand-priced inference task creation
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        system_instruction,
+        sysvar::instructions::{self, load_current_index_checked, load_instruction_at_checked},
+    },
+};
+use crate::env;
+use crate::state::{
+    content_policy::ensure_accepting_tasks,
+    inference_quote::{check_quote_signature, InferenceQuote, QuoteError, QuoteKeyConfig},
+    model_state::{ModelState, ModelStatusKind},
+    prepaid_balance::{draw_prepaid, PrepaidBalance},
+    pricing_state::{ModelPricing, PricingError},
+    task_state::TaskState,
+    verifier_registry::VerifierRegistry,
+};
+
+#[derive(Accounts)]
+pub struct InitModelPricing<'info> {
+    #[account(has_one = owner)]
+    pub model: Account<'info, ModelState>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = ModelPricing::LEN,
+        seeds = [b"pricing", model.key().as_ref()],
+        bump
+    )]
+    pub pricing: Account<'info, ModelPricing>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitModelPricing<'info> {
+    pub fn execute(
+        &mut self,
+        initial_fee: u64,
+        min_base_fee: u64,
+        target_queue_depth: u32,
+        bump: u8,
+    ) -> Result<()> {
+        require!(
+            min_base_fee > 0 && initial_fee >= min_base_fee && target_queue_depth > 0,
+            PricingError::InvalidParameters
+        );
+
+        let pricing = &mut self.pricing;
+        pricing.bump = bump;
+        pricing.model = self.model.key();
+        pricing.base_fee = initial_fee;
+        pricing.min_base_fee = min_base_fee;
+        pricing.target_queue_depth = target_queue_depth;
+        pricing.queued_tasks = 0;
+        pricing.last_update = env::now()?;
+
+        Ok(())
+    }
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(input_hash: [u8; 32])]
+pub struct CreateInferenceTask<'info> {
+    #[account(
+        constraint = model.status.kind() == ModelStatusKind::Active @ PricingError::InvalidParameters
+    )]
+    pub model: Account<'info, ModelState>,
+
+    /// CHECK: moderation PDA of the model; empty until the model is first flagged
+    #[account(seeds = [b"moderation", model.key().as_ref()], bump)]
+    pub moderation: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pricing", model.key().as_ref()],
+        bump = pricing.bump,
+        has_one = model
+    )]
+    pub pricing: Account<'info, ModelPricing>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = TaskState::LEN,
+        seeds = [b"task", owner.key().as_ref(), &input_hash],
+        bump
+    )]
+    pub task: Account<'info, TaskState>,
+
+    /// Bridged prepaid balance the escrow is drawn from instead of the owner
+    #[account(
+        mut,
+        seeds = [b"prepaid", owner.key().as_ref()],
+        bump = prepaid.bump,
+        has_one = owner
+    )]
+    pub prepaid: Option<Account<'info, PrepaidBalance>>,
+
+    #[account(seeds = [b"verifier_registry"], bump = verifier_registry.bump)]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+
+    /// Coordinator quote key; required with a quote
+    #[account(seeds = [b"quote_key"], bump = quote_key.bump)]
+    pub quote_key: Option<Account<'info, QuoteKeyConfig>>,
+
+    /// CHECK: address checked; read for the quote's ed25519 precompile instruction
+    #[account(address = instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateInferenceTask<'info> {
+    /// Escrow the model's current base fee; `max_fee` guards against price moves.
+    /// `surge_fee` is escrowed on top so coordinators keep serving an input that is
+    /// being submitted repeatedly. `input_schema_hash` comes from the client encoder
+    /// and must match the model's registered input schema, if any. With a
+    /// coordinator `quote` the base fee escrowed is at most the quoted fee, and
+    /// the ed25519 instruction right before this one must carry its signature.
+    /// Returns the `InferenceTaskPriced` event for the handler to emit.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute(
+        &mut self,
+        input_hash: [u8; 32],
+        input_schema_hash: [u8; 32],
+        max_fee: u64,
+        surge_fee: u64,
+        quote: Option<InferenceQuote>,
+        bump: u8,
+    ) -> Result<InferenceTaskPriced> {
+        // Flagged models take no new work until review clears them
+        ensure_accepting_tasks(&self.moderation)?;
+        // Inputs the model cannot decode would only burn the fee
+        self.model.check_input_schema(&input_schema_hash)?;
+
+        let now = env::now()?;
+        let mut price = self.pricing.update(now)?;
+        if let Some(quote) = &quote {
+            self.check_quote(quote, &input_hash, now)?;
+            price = quote.fee(price);
+        }
+        require!(price <= max_fee, PricingError::MaxFeeExceeded);
+        self.pricing.enqueue()?;
+        let escrow = price
+            .checked_add(surge_fee)
+            .ok_or(PricingError::PriceOverflow)?;
+
+        let task = &mut self.task;
+        task.bump = bump;
+        task.created_at = now;
+        task.owner = self.owner.key();
+        task.input_hash = input_hash;
+        task.model_hash = self.model.model_root;
+        task.verifier_version = self.verifier_registry.current;
+        task.pricing = Some(self.pricing.key());
+
+        if let Some(prepaid) = &mut self.prepaid {
+            draw_prepaid(prepaid, &self.task.to_account_info(), escrow)?;
+        } else {
+            anchor_lang::solana_program::program::invoke(
+                &system_instruction::transfer(&self.owner.key(), &self.task.key(), escrow),
+                &[
+                    self.owner.to_account_info(),
+                    self.task.to_account_info(),
+                    self.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        Ok(InferenceTaskPriced {
+            task: self.task.key(),
+            model: self.model.key(),
+            input_hash,
+            escrow: price,
+            surge_fee,
+            queued_tasks: self.pricing.queued_tasks,
+            quoted: quote.is_some(),
+            timestamp: now,
+        })
+    }
+
+    /// Check `quote` covers this task and was signed with the registered quote
+    /// key by the ed25519 precompile instruction just before this one
+    fn check_quote(&self, quote: &InferenceQuote, input_hash: &[u8; 32], now: i64) -> Result<()> {
+        let (Some(config), Some(sysvar)) = (&self.quote_key, &self.instructions_sysvar) else {
+            return err!(QuoteError::QuoteAccountsMissing);
+        };
+        quote.check(&self.model.key(), &self.owner.key(), input_hash, now)?;
+
+        let sysvar = sysvar.to_account_info();
+        let current = load_current_index_checked(&sysvar)? as usize;
+        require!(current > 0, QuoteError::InvalidQuoteSignature);
+        let ed25519_ix = load_instruction_at_checked(current - 1, &sysvar)?;
+        check_quote_signature(&ed25519_ix, &config.quote_key, &quote.message())
+    }
+}
+
+#[event]
+pub struct InferenceTaskPriced {
+    pub task: Pubkey,
+    pub model: Pubkey,
+    /// Commitment to the encrypted input, used for duplicate detection
+    pub input_hash: [u8; 32],
+    /// Base fee escrowed
+    pub escrow: u64,
+    /// Escrowed on top of the base fee to bypass repeat-input throttling
+    pub surge_fee: u64,
+    pub queued_tasks: u32,
+    /// Escrow was capped by a coordinator quote
+    pub quoted: bool,
+    pub timestamp: i64,
+}
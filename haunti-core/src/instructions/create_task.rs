@@ -4,15 +4,31 @@ use anchor_lang::{
     prelude::*,
     solana_program::{entrypoint::ProgramResult, system_instruction},
 };
+use anchor_spl::{
+    associated_token::{self, AssociatedToken},
+    token::{self, Mint, Token, TokenAccount, Transfer},
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::program_memory::sol_memcmp;
 use crate::{
+    env,
     error::HauntiError,
-    state::{ModelParams, TaskAccount, TaskState},
+    state::{
+        deposit_config::{DepositConfig, DepositError, ResourceRequirements},
+        gpu_provider::GpuProvider,
+        prepaid_balance::{draw_prepaid, PrepaidBalance},
+        reward_escrow::{check_reward_account, reward_escrow_address, RewardEscrowError},
+        size_limits::SizeLimits,
+        task_feed::{EventCounter, TaskFeedKind},
+        task_state::rng_seed_for,
+        verifier_registry::VerifierRegistry,
+        ModelParams, TaskAccount, TaskState,
+    },
     utils::validate_model_hash,
 };
 
 // Account validation structure
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(model: ModelParams, reward: u64, time_limit: u64)]
 pub struct CreateTask<'info> {
@@ -27,13 +43,50 @@ pub struct CreateTask<'info> {
     
     #[account(mut)]
     pub owner: Signer<'info>,
+
+    #[account(seeds = [b"size_limits"], bump = size_limits.bump)]
+    pub size_limits: Account<'info, SizeLimits>,
+
+    #[account(seeds = [b"deposit_config"], bump = deposit_config.bump)]
+    pub deposit_config: Account<'info, DepositConfig>,
+
+    #[account(seeds = [b"verifier_registry"], bump = verifier_registry.bump)]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    // Optional: bridged prepaid balance the deposit is drawn from instead of the owner
+    #[account(
+        mut,
+        seeds = [b"prepaid", owner.key().as_ref()],
+        bump = prepaid.bump,
+        has_one = owner
+    )]
+    pub prepaid: Option<Account<'info, PrepaidBalance>>,
+
+    // Optional: SPL mint the reward is paid in; the deposit is SOL without it
+    pub reward_mint: Option<Account<'info, Mint>>,
+
+    // Owner's token account the reward is escrowed from
+    #[account(mut)]
+    pub owner_reward_account: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: associated token account of (task_account, reward_mint), created here
+    #[account(mut)]
+    pub reward_escrow: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
     
     #[account(address = system_program::ID)]
     pub system_program: Program<'info, System>,
     
     // Optional: GPU resource provider account
     #[account(
-        constraint = gpu_provider.map(|acc| acc.is_approved).unwrap_or(true),
+        constraint = gpu_provider.as_ref().map(|acc| acc.is_approved()).unwrap_or(true),
         signer @ HauntiError::MissingProviderSignature
     )]
     pub gpu_provider: Option<Account<'info, GpuProvider>>,
@@ -41,15 +94,22 @@ pub struct CreateTask<'info> {
 
 // Instruction handler implementation
 impl<'info> CreateTask<'info> {
+    /// Create the task, returning the `TaskCreated` event the handler emits via `emit_cpi!`
     pub fn execute(
         &mut self,
         model: ModelParams,
         reward: u64,
         time_limit: u64,
+        requirements: ResourceRequirements,
         encrypted_data: Option<Vec<u8>>,
-    ) -> ProgramResult {
+        priority_fee: u64,
+        deadline: Option<i64>,
+    ) -> Result<TaskCreated> {
         // Validate input parameters
-        self.validate_inputs(&model, reward, time_limit)?;
+        self.validate_inputs(&model, reward, time_limit, &requirements)?;
+        if let Some(data) = &encrypted_data {
+            self.size_limits.check_input(data.len())?;
+        }
         
         // Initialize task account
         let task = &mut self.task_account;
@@ -59,20 +119,43 @@ impl<'info> CreateTask<'info> {
         task.time_limit = time_limit;
         task.state = TaskState::Pending;
         task.encrypted_input = encrypted_data.unwrap_or_default();
-        task.created_at = Clock::get()?.unix_timestamp;
+        task.created_at = env::now()?;
+        task.rng_seed = rng_seed_for(&task.key(), env::slot()?);
+        task.verifier_version = self.verifier_registry.current;
+        task.reward_mint = self.reward_mint.as_ref().map(|mint| mint.key());
+        task.priority_fee = priority_fee;
+        task.set_deadline(deadline, task.created_at)?;
         
-        // Deduct deposit from owner
-        self.transfer_deposit(reward)?;
+        // Escrow the reward and priority fee in tokens, or deduct them from the
+        // prepaid balance or the owner; payout releases the whole escrow
+        let escrow = reward
+            .checked_add(priority_fee)
+            .ok_or(HauntiError::RewardTooHigh)?;
+        if self.reward_mint.is_some() {
+            self.escrow_token_reward(escrow)?;
+        } else {
+            self.transfer_deposit(escrow)?;
+        }
         
-        // Emit creation event
-        emit!(TaskCreated {
+        let event = TaskCreated {
+            task: self.task_account.key(),
             owner: self.owner.key(),
-            model_hash: task.model.model_hash.clone(),
+            model_hash: self.task_account.model.model_hash,
             reward,
-            timestamp: task.created_at
-        });
+            reward_mint: self.task_account.reward_mint,
+            priority_fee,
+            deadline: self.task_account.deadline,
+            rng_seed: self.task_account.rng_seed,
+            timestamp: self.task_account.created_at,
+        };
+        self.event_counter.emit(
+            self.task_account.key(),
+            TaskFeedKind::Created,
+            self.owner.key(),
+            escrow,
+        )?;
         
-        Ok(())
+        Ok(event)
     }
 
     fn validate_inputs(
@@ -80,6 +163,7 @@ impl<'info> CreateTask<'info> {
         model: &ModelParams,
         reward: u64,
         time_limit: u64,
+        requirements: &ResourceRequirements,
     ) -> Result<()> {
         // Model hash validation
         require!(
@@ -87,15 +171,23 @@ impl<'info> CreateTask<'info> {
             HauntiError::InvalidModelHash
         );
         
-        // Reward sanity check
-        require!(reward >= MINIMUM_REWARD, HauntiError::RewardTooLow);
-        require!(reward <= MAXIMUM_REWARD, HauntiError::RewardTooHigh);
-        
         // Time constraints
         require!(
             time_limit >= MIN_TIME_LIMIT && time_limit <= MAX_TIME_LIMIT,
             HauntiError::InvalidTimeLimit
         );
+
+        // The deposit schedule and reward cap are priced in lamports, so they
+        // only bind SOL rewards
+        if self.reward_mint.is_some() {
+            require!(self.prepaid.is_none(), RewardEscrowError::PrepaidNotSupported);
+            require!(reward > 0, RewardEscrowError::InvalidReward);
+        } else {
+            // Deposit must cover the GPU time reserved for the whole time limit
+            let required = self.deposit_config.required_deposit(requirements, time_limit)?;
+            require!(reward >= required, DepositError::DepositTooLow);
+            require!(reward <= MAXIMUM_REWARD, HauntiError::RewardTooHigh);
+        }
         
         // GPU provider verification
         if let Some(provider) = &self.gpu_provider {
@@ -108,7 +200,11 @@ impl<'info> CreateTask<'info> {
         Ok(())
     }
 
-    fn transfer_deposit(&self, amount: u64) -> Result<()> {
+    fn transfer_deposit(&mut self, amount: u64) -> Result<()> {
+        if let Some(prepaid) = &mut self.prepaid {
+            return draw_prepaid(prepaid, &self.task_account.to_account_info(), amount);
+        }
+
         let transfer_ix = system_instruction::transfer(
             &self.owner.key(),
             &self.task_account.key(),
@@ -127,19 +223,77 @@ impl<'info> CreateTask<'info> {
         
         Ok(())
     }
+
+    /// Create the task's reward escrow and move `amount` tokens into it
+    fn escrow_token_reward(&self, amount: u64) -> Result<()> {
+        let (
+            Some(mint),
+            Some(source),
+            Some(escrow),
+            Some(token_program),
+            Some(associated_token_program),
+        ) = (
+            &self.reward_mint,
+            &self.owner_reward_account,
+            &self.reward_escrow,
+            &self.token_program,
+            &self.associated_token_program,
+        )
+        else {
+            return err!(RewardEscrowError::MissingRewardAccounts);
+        };
+        require_keys_eq!(
+            escrow.key(),
+            reward_escrow_address(&self.task_account.key(), &mint.key()),
+            RewardEscrowError::InvalidEscrow
+        );
+        check_reward_account(source, &mint.key(), &self.owner.key())?;
+
+        associated_token::create(CpiContext::new(
+            associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: self.owner.to_account_info(),
+                associated_token: escrow.to_account_info(),
+                authority: self.task_account.to_account_info(),
+                mint: mint.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: token_program.to_account_info(),
+            },
+        ))?;
+
+        token::transfer(
+            CpiContext::new(
+                token_program.to_account_info(),
+                Transfer {
+                    from: source.to_account_info(),
+                    to: escrow.to_account_info(),
+                    authority: self.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )
+    }
 }
 
 // Event logging
 #[event]
 pub struct TaskCreated {
+    pub task: Pubkey,
     pub owner: Pubkey,
     pub model_hash: [u8; 32],
+    /// In base units of `reward_mint`, or lamports when it is `None`
     pub reward: u64,
+    pub reward_mint: Option<Pubkey>,
+    /// Paid to the worker on top of `reward`, in the same units
+    pub priority_fee: u64,
+    /// Unix timestamp the owner needs the result by
+    pub deadline: Option<i64>,
+    /// Seed the training run's randomness derives from
+    pub rng_seed: [u8; 32],
     pub timestamp: i64,
 }
 
 // Constants
-const MINIMUM_REWARD: u64 = 100_000; // 0.0001 SOL
 const MAXIMUM_REWARD: u64 = 100_000_000_000; // 100 SOL
 const MIN_TIME_LIMIT: u64 = 300; // 5 minutes
 const MAX_TIME_LIMIT: u64 = 2592000; // 30 days
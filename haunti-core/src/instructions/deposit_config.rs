@@ -0,0 +1,94 @@
+//! Instruction handlers for the governance-tunable task deposit schedule
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::deposit_config::{DepositConfig, DepositError, GPU_TIER_COUNT};
+
+#[derive(Accounts)]
+pub struct InitDepositConfig<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = DepositConfig::LEN,
+        seeds = [b"deposit_config"],
+        bump
+    )]
+    pub config: Account<'info, DepositConfig>,
+
+    /// Governance authority that will own the schedule
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// Must be the upgrade authority, so the deposit schedule cannot be front-run
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitDepositConfig<'info> {
+    pub fn execute(
+        &mut self,
+        bump: u8,
+        min_deposit: u64,
+        tier_rates: [u64; GPU_TIER_COUNT],
+    ) -> Result<()> {
+        let now = env::now()?;
+        let config = &mut self.config;
+        config.bump = bump;
+        config.governance = self.governance.key();
+        config.set(min_deposit, tier_rates)?;
+        config.updated_at = now;
+
+        emit!(DepositConfigUpdated {
+            min_deposit,
+            tier_rates,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateDepositConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"deposit_config"],
+        bump = config.bump,
+        has_one = governance @ DepositError::Unauthorized
+    )]
+    pub config: Account<'info, DepositConfig>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> UpdateDepositConfig<'info> {
+    pub fn execute(&mut self, min_deposit: u64, tier_rates: [u64; GPU_TIER_COUNT]) -> Result<()> {
+        let now = env::now()?;
+        let config = &mut self.config;
+        config.set(min_deposit, tier_rates)?;
+        config.updated_at = now;
+
+        emit!(DepositConfigUpdated {
+            min_deposit,
+            tier_rates,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct DepositConfigUpdated {
+    pub min_deposit: u64,
+    pub tier_rates: [u64; GPU_TIER_COUNT],
+    pub timestamp: i64,
+}
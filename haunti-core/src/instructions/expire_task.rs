@@ -0,0 +1,315 @@
+//! Permissionless handlers that clear stuck tasks, paying the caller a keeper reward
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use crate::env;
+use crate::state::{
+    pricing_state::{release_queue_slot, ModelPricing},
+    reward_escrow::refund_token_escrow,
+    task_feed::{EventCounter, TaskFeedKind},
+    task_state::{
+        RefundReason, TaskError, TaskRefunded, TaskState, TaskStatus, TaskStatusChanged,
+        ERROR_HEARTBEAT_TIMEOUT, ERROR_TIME_LIMIT,
+    },
+    TaskAccount, TaskState as TaskAccountState,
+};
+
+/// Lamports paid from the task escrow to whoever clears a stuck task
+pub const KEEPER_REWARD_LAMPORTS: u64 = 100_000;
+
+#[derive(Accounts)]
+pub struct ExpireTask<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump,
+        has_one = owner
+    )]
+    pub task: Account<'info, TaskState>,
+
+    /// Receives the escrow refund
+    /// CHECK: validated against `task.owner`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Pricing queue the task holds a slot in; required for priced inference tasks
+    #[account(mut)]
+    pub pricing: Option<Account<'info, ModelPricing>>,
+
+    /// Anyone; receives the keeper reward
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+}
+
+impl<'info> ExpireTask<'info> {
+    /// Cancel a task no worker picked up before its pickup deadline or time
+    /// limit, or fail a running task whose time limit passed without a proof
+    pub fn execute(&mut self) -> Result<()> {
+        let now = env::now()?;
+        let old_status = self.task.status.clone();
+        let (reason, kind) = if self.task.pickup_expired(now) {
+            self.task.cancel()?;
+            (RefundReason::PickupExpired, TaskFeedKind::Cancelled)
+        } else if self.task.time_limit_expired(now) {
+            let kind = match old_status {
+                TaskStatus::Pending => {
+                    self.task.cancel()?;
+                    TaskFeedKind::Cancelled
+                }
+                _ => {
+                    self.task.fail(ERROR_TIME_LIMIT)?;
+                    TaskFeedKind::Failed
+                }
+            };
+            (RefundReason::TimeLimitExpired, kind)
+        } else {
+            return Err(TaskError::DeadlineNotReached.into());
+        };
+        release_queue_slot(&mut self.task, self.pricing.as_mut())?;
+        let (keeper_reward, refund) = pay_out_escrow(
+            &self.task.to_account_info(),
+            &self.owner.to_account_info(),
+            &self.keeper.to_account_info(),
+        )?;
+
+        emit!(TaskStatusChanged {
+            task: self.task.key(),
+            old_status,
+            new_status: self.task.status.clone(),
+            version: self.task.version,
+            timestamp: now,
+        });
+        emit!(TaskExpired {
+            task: self.task.key(),
+            keeper: self.keeper.key(),
+            keeper_reward,
+            refund,
+            timestamp: now,
+        });
+        emit!(TaskRefunded {
+            task: self.task.key(),
+            owner: self.owner.key(),
+            amount: refund,
+            reason,
+            timestamp: now,
+        });
+        let task = self.task.key();
+        self.event_counter.emit(task, kind, self.keeper.key(), 0)?;
+        self.event_counter
+            .emit(task, TaskFeedKind::Refunded, self.owner.key(), refund)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReportTimeout<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump,
+        has_one = owner
+    )]
+    pub task: Account<'info, TaskState>,
+
+    /// Receives the escrow refund
+    /// CHECK: validated against `task.owner`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Pricing queue the task holds a slot in; required for priced inference tasks
+    #[account(mut)]
+    pub pricing: Option<Account<'info, ModelPricing>>,
+
+    /// Anyone; receives the keeper reward
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+}
+
+impl<'info> ReportTimeout<'info> {
+    /// Fail a running task whose worker stopped sending heartbeats
+    pub fn execute(&mut self) -> Result<()> {
+        let now = env::now()?;
+        require!(self.task.heartbeat_expired(now), TaskError::DeadlineNotReached);
+
+        let old_status = self.task.status.clone();
+        let worker = match old_status {
+            TaskStatus::Running { worker, .. } => worker,
+            _ => return Err(TaskError::InvalidStateTransition.into()),
+        };
+        self.task.fail(ERROR_HEARTBEAT_TIMEOUT)?;
+        release_queue_slot(&mut self.task, self.pricing.as_mut())?;
+        let (keeper_reward, refund) = pay_out_escrow(
+            &self.task.to_account_info(),
+            &self.owner.to_account_info(),
+            &self.keeper.to_account_info(),
+        )?;
+
+        emit!(TaskStatusChanged {
+            task: self.task.key(),
+            old_status,
+            new_status: self.task.status.clone(),
+            version: self.task.version,
+            timestamp: now,
+        });
+        emit!(TaskTimedOut {
+            task: self.task.key(),
+            worker,
+            keeper: self.keeper.key(),
+            keeper_reward,
+            refund,
+            timestamp: now,
+        });
+        emit!(TaskRefunded {
+            task: self.task.key(),
+            owner: self.owner.key(),
+            amount: refund,
+            reason: RefundReason::HeartbeatTimeout,
+            timestamp: now,
+        });
+        let task = self.task.key();
+        self.event_counter
+            .emit(task, TaskFeedKind::Failed, self.keeper.key(), 0)?;
+        self.event_counter
+            .emit(task, TaskFeedKind::Refunded, self.owner.key(), refund)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExpireTaskAccount<'info> {
+    /// Closed to the owner once the keeper reward is paid
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"task", owner.key().as_ref(), task_account.model.model_hash.as_ref()],
+        bump,
+        has_one = owner,
+        constraint = task_account.state == TaskAccountState::Pending
+            @ TaskError::InvalidStateTransition
+    )]
+    pub task_account: Account<'info, TaskAccount>,
+
+    /// Receives the escrow refund and the task's rent
+    /// CHECK: validated against `task_account.owner`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    // Required when the task escrowed its reward in an SPL mint
+    #[account(mut)]
+    pub reward_escrow: Option<Account<'info, TokenAccount>>,
+
+    // Owner's token account for the reward mint; receives the token escrow
+    #[account(mut)]
+    pub owner_reward_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// Anyone; receives the keeper reward
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+}
+
+impl<'info> ExpireTaskAccount<'info> {
+    /// Refund a training task that passed its result deadline or time limit
+    /// without a proof. The keeper reward comes out of the lamport escrow, so
+    /// token-rewarded tasks refund their whole token escrow
+    pub fn execute(&mut self, bump: u8) -> Result<()> {
+        let now = env::now()?;
+        let task_account = &self.task_account;
+        let limit = i64::try_from(task_account.time_limit).unwrap_or(i64::MAX);
+        let past_deadline = task_account.deadline.is_some_and(|deadline| now > deadline);
+        let past_limit = task_account.time_limit > 0
+            && now.saturating_sub(task_account.created_at) > limit;
+        require!(past_deadline || past_limit, TaskError::DeadlineNotReached);
+
+        let (keeper_reward, lamport_refund) = pay_out_escrow(
+            &self.task_account.to_account_info(),
+            &self.owner.to_account_info(),
+            &self.keeper.to_account_info(),
+        )?;
+        let refund = match self.task_account.reward_mint {
+            Some(mint) => refund_token_escrow(
+                &self.task_account,
+                bump,
+                mint,
+                self.reward_escrow.as_ref(),
+                self.owner_reward_account.as_ref(),
+                self.token_program.as_ref(),
+                self.owner.to_account_info(),
+            )?,
+            None => lamport_refund,
+        };
+
+        let (task, keeper, owner) = (self.task_account.key(), self.keeper.key(), self.owner.key());
+        emit!(TaskExpired {
+            task,
+            keeper,
+            keeper_reward,
+            refund,
+            timestamp: now,
+        });
+        emit!(TaskRefunded {
+            task,
+            owner,
+            amount: refund,
+            reason: RefundReason::TimeLimitExpired,
+            timestamp: now,
+        });
+        self.event_counter.emit(task, TaskFeedKind::Cancelled, keeper, 0)?;
+        self.event_counter.emit(task, TaskFeedKind::Refunded, owner, refund)?;
+
+        Ok(())
+    }
+}
+
+/// Pay the keeper reward out of the escrow above rent-exemption and refund the
+/// rest to the owner; returns `(keeper_reward, refund)`
+fn pay_out_escrow(
+    task: &AccountInfo<'_>,
+    owner: &AccountInfo<'_>,
+    keeper: &AccountInfo<'_>,
+) -> Result<(u64, u64)> {
+    let rent_floor = Rent::get()?.minimum_balance(task.data_len());
+    let escrow = task.lamports().saturating_sub(rent_floor);
+    let keeper_reward = escrow.min(KEEPER_REWARD_LAMPORTS);
+    let refund = escrow - keeper_reward;
+
+    **task.try_borrow_mut_lamports()? -= escrow;
+    **keeper.try_borrow_mut_lamports()? += keeper_reward;
+    **owner.try_borrow_mut_lamports()? += refund;
+
+    Ok((keeper_reward, refund))
+}
+
+#[event]
+pub struct TaskExpired {
+    pub task: Pubkey,
+    pub keeper: Pubkey,
+    pub keeper_reward: u64,
+    pub refund: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TaskTimedOut {
+    pub task: Pubkey,
+    pub worker: Pubkey,
+    pub keeper: Pubkey,
+    pub keeper_reward: u64,
+    pub refund: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,87 @@
+//! Instruction handlers for the governance-controlled feature flags
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::feature_flags::{FeatureFlagError, FeatureFlags};
+
+#[derive(Accounts)]
+pub struct InitFeatureFlags<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = FeatureFlags::LEN,
+        seeds = [b"feature_flags"],
+        bump
+    )]
+    pub flags: Account<'info, FeatureFlags>,
+
+    /// Governance authority that will own the flags
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// Upgrade authority of haunti-core, which alone may create the flags
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitFeatureFlags<'info> {
+    /// Start with every flag disabled
+    pub fn execute(&mut self, bump: u8) -> Result<()> {
+        let flags = &mut self.flags;
+        flags.bump = bump;
+        flags.governance = self.governance.key();
+        flags.enabled = 0;
+        flags.updated_at = env::now()?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetFeatureFlag<'info> {
+    #[account(
+        mut,
+        seeds = [b"feature_flags"],
+        bump = flags.bump,
+        has_one = governance @ FeatureFlagError::Unauthorized
+    )]
+    pub flags: Account<'info, FeatureFlags>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> SetFeatureFlag<'info> {
+    /// Schedule `feature` to activate at `activation_slot`, or disable it with `None`
+    pub fn execute(&mut self, feature: u8, activation_slot: Option<u64>) -> Result<()> {
+        let clock = env::clock()?;
+        let flags = &mut self.flags;
+        flags.set(feature, activation_slot, clock.slot)?;
+        flags.updated_at = clock.unix_timestamp;
+
+        emit!(FeatureFlagSet {
+            feature,
+            activation_slot,
+            enabled: flags.enabled,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct FeatureFlagSet {
+    pub feature: u8,
+    /// `None` when the flag was disabled
+    pub activation_slot: Option<u64>,
+    /// Bitmask after the change
+    pub enabled: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,227 @@
+//! Instruction handlers for the GPU provider registry and task claiming
+
+use anchor_lang::prelude::*;
+use token_vault::UserStake;
+use crate::env;
+use crate::state::{
+    gpu_provider::{GpuProvider, GpuProviderError, INITIAL_REPUTATION, MIN_PROVIDER_STAKE},
+    task_feed::{EventCounter, TaskFeedKind},
+    task_state::{TaskError, TaskState, TaskStatusChanged, TaskStatusKind},
+};
+
+#[derive(Accounts)]
+#[instruction(attestation_hash: [u8; 32], model_types: u32, stake_pool: Pubkey)]
+pub struct RegisterProvider<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = GpuProvider::LEN,
+        seeds = [b"gpu_provider", authority.key().as_ref()],
+        bump
+    )]
+    pub provider: Account<'info, GpuProvider>,
+
+    /// Provider's stake in `stake_pool`
+    #[account(
+        seeds = [b"stake", stake_pool.as_ref(), authority.key().as_ref()],
+        bump,
+        seeds::program = token_vault::ID
+    )]
+    pub stake: Account<'info, UserStake>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RegisterProvider<'info> {
+    /// Register the signer as a GPU provider
+    pub fn execute(
+        &mut self,
+        attestation_hash: [u8; 32],
+        model_types: u32,
+        stake_pool: Pubkey,
+        bump: u8,
+    ) -> Result<()> {
+        require!(
+            self.stake.amount >= MIN_PROVIDER_STAKE,
+            GpuProviderError::InsufficientStake
+        );
+
+        let now = env::now()?;
+        let provider = &mut self.provider;
+        provider.set_profile(attestation_hash, model_types)?;
+        provider.bump = bump;
+        provider.authority = self.authority.key();
+        provider.stake_pool = stake_pool;
+        provider.active = true;
+        provider.reputation = INITIAL_REPUTATION;
+        provider.registered_at = now;
+        provider.updated_at = now;
+
+        emit!(ProviderRegistered {
+            provider: provider.key(),
+            authority: provider.authority,
+            attestation_hash,
+            model_types,
+            stake_pool,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateProvider<'info> {
+    #[account(
+        mut,
+        seeds = [b"gpu_provider", authority.key().as_ref()],
+        bump = provider.bump,
+        has_one = authority
+    )]
+    pub provider: Account<'info, GpuProvider>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> UpdateProvider<'info> {
+    /// Declare new hardware or model support; reactivates a deactivated
+    /// provider. Reputation carries over
+    pub fn execute(&mut self, attestation_hash: [u8; 32], model_types: u32) -> Result<()> {
+        let now = env::now()?;
+        let provider = &mut self.provider;
+        provider.set_profile(attestation_hash, model_types)?;
+        provider.active = true;
+        provider.updated_at = now;
+
+        emit!(ProviderUpdated {
+            provider: provider.key(),
+            attestation_hash,
+            model_types,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct DeactivateProvider<'info> {
+    #[account(
+        mut,
+        seeds = [b"gpu_provider", authority.key().as_ref()],
+        bump = provider.bump,
+        has_one = authority
+    )]
+    pub provider: Account<'info, GpuProvider>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> DeactivateProvider<'info> {
+    /// Stop taking new tasks; tasks already claimed still run to completion
+    pub fn execute(&mut self) -> Result<()> {
+        let now = env::now()?;
+        self.provider.active = false;
+        self.provider.updated_at = now;
+
+        emit!(ProviderDeactivated {
+            provider: self.provider.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimTask<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump
+    )]
+    pub task: Account<'info, TaskState>,
+
+    #[account(
+        seeds = [b"gpu_provider", worker.key().as_ref()],
+        bump = provider.bump,
+        constraint = provider.authority == worker.key()
+    )]
+    pub provider: Account<'info, GpuProvider>,
+
+    /// Provider's stake, re-checked in case it was withdrawn since registering
+    #[account(
+        seeds = [b"stake", provider.stake_pool.as_ref(), worker.key().as_ref()],
+        bump,
+        seeds::program = token_vault::ID
+    )]
+    pub stake: Account<'info, UserStake>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    pub worker: Signer<'info>,
+}
+
+impl<'info> ClaimTask<'info> {
+    /// Start a pending task as its worker
+    pub fn execute(&mut self) -> Result<()> {
+        self.provider.check_claim(self.stake.amount)?;
+        require!(
+            self.task.status.kind() == TaskStatusKind::Pending,
+            TaskError::InvalidStateTransition
+        );
+
+        let old_status = self.task.status.clone();
+        self.task.start(self.worker.key())?;
+
+        emit!(TaskStatusChanged {
+            task: self.task.key(),
+            old_status,
+            new_status: self.task.status.clone(),
+            version: self.task.version,
+            timestamp: env::now()?,
+        });
+        self.event_counter
+            .emit(self.task.key(), TaskFeedKind::Claimed, self.worker.key(), 0)?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ProviderRegistered {
+    pub provider: Pubkey,
+    pub authority: Pubkey,
+    pub attestation_hash: [u8; 32],
+    pub model_types: u32,
+    pub stake_pool: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProviderUpdated {
+    pub provider: Pubkey,
+    pub attestation_hash: [u8; 32],
+    pub model_types: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProviderDeactivated {
+    pub provider: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProviderReputationChanged {
+    pub provider: Pubkey,
+    pub task: Pubkey,
+    pub succeeded: bool,
+    pub reputation: u16,
+}
@@ -0,0 +1,137 @@
+//! Worker heartbeats and the permissionless reassignment of stale running tasks
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::instructions::expire_task::KEEPER_REWARD_LAMPORTS;
+use crate::state::task_feed::{EventCounter, TaskFeedKind};
+use crate::state::task_state::{TaskState, TaskStatusChanged};
+use crate::state::worker_bond::{ReassignConfig, WorkerBond, WorkerBondError};
+
+#[derive(Accounts)]
+pub struct HeartbeatTask<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump
+    )]
+    pub task: Account<'info, TaskState>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Worker currently executing the task
+    pub worker: Signer<'info>,
+}
+
+impl<'info> HeartbeatTask<'info> {
+    /// Prove the worker is still alive without reporting progress
+    pub fn execute(&mut self) -> Result<()> {
+        self.task.heartbeat(&self.worker.key())?;
+        self.event_counter
+            .emit(self.task.key(), TaskFeedKind::Heartbeat, self.worker.key(), 0)
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReassignStaleTask<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump,
+        has_one = owner
+    )]
+    pub task: Account<'info, TaskState>,
+
+    #[account(seeds = [b"reassign_config"], bump = config.bump)]
+    pub config: Account<'info, ReassignConfig>,
+
+    /// Worker the task is taken from
+    /// CHECK: must be the stale worker running the task; checked in the handler
+    pub worker: UncheckedAccount<'info>,
+
+    /// The stale worker's bond PDA, passed whether or not the worker ever
+    /// bonded so a posted bond cannot be left out to dodge the slash
+    /// CHECK: decoded as `WorkerBond` in the handler when initialized
+    #[account(mut, seeds = [b"worker_bond", worker.key().as_ref()], bump)]
+    pub worker_bond: UncheckedAccount<'info>,
+
+    /// Receives the slashed bond beyond the keeper reward
+    /// CHECK: validated against `task.owner`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Anyone; receives the keeper reward out of the slashed bond
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+}
+
+impl<'info> ReassignStaleTask<'info> {
+    /// Put a running task whose worker stopped heartbeating back to Pending,
+    /// slashing the worker's bond if it posted one
+    pub fn execute(&mut self) -> Result<()> {
+        let now = env::now()?;
+        let worker = self
+            .config
+            .stale_worker(&self.task, now)
+            .ok_or(WorkerBondError::HeartbeatNotStale)?;
+        require_keys_eq!(self.worker.key(), worker, WorkerBondError::BondMismatch);
+
+        let old_status = self.task.status.clone();
+        self.task.requeue()?;
+
+        let (mut slashed, mut keeper_reward) = (0, 0);
+        let bond_info = self.worker_bond.to_account_info();
+        if bond_info.owner == &crate::ID && !bond_info.data_is_empty() {
+            let mut bond = Account::<WorkerBond>::try_from(&bond_info)?;
+            slashed = self.config.slash_amount(bond.amount);
+            keeper_reward = slashed.min(KEEPER_REWARD_LAMPORTS);
+
+            // Both the bond and the task are program-owned, so lamports move
+            // without a CPI
+            **bond_info.try_borrow_mut_lamports()? -= slashed;
+            **self.keeper.to_account_info().try_borrow_mut_lamports()? += keeper_reward;
+            **self.owner.to_account_info().try_borrow_mut_lamports()? += slashed - keeper_reward;
+            bond.amount -= slashed;
+            bond.slashed += slashed;
+            bond.reassignments = bond.reassignments.saturating_add(1);
+            bond.exit(&crate::ID)?;
+        }
+
+        emit!(TaskStatusChanged {
+            task: self.task.key(),
+            old_status,
+            new_status: self.task.status.clone(),
+            version: self.task.version,
+            timestamp: now,
+        });
+        emit!(TaskReassigned {
+            task: self.task.key(),
+            worker,
+            keeper: self.keeper.key(),
+            slashed,
+            keeper_reward,
+            timestamp: now,
+        });
+        self.event_counter
+            .emit(self.task.key(), TaskFeedKind::Reassigned, self.keeper.key(), slashed)?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct TaskReassigned {
+    pub task: Pubkey,
+    /// Worker the task was taken from
+    pub worker: Pubkey,
+    pub keeper: Pubkey,
+    /// Lamports slashed from the worker's bond (zero without one)
+    pub slashed: u64,
+    pub keeper_reward: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,87 @@
+//! Instruction handlers for the coordinator's inference quote key
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::inference_quote::{QuoteError, QuoteKeyConfig};
+
+#[derive(Accounts)]
+pub struct InitQuoteKey<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = QuoteKeyConfig::LEN,
+        seeds = [b"quote_key"],
+        bump
+    )]
+    pub config: Account<'info, QuoteKeyConfig>,
+
+    /// Governance authority that will own the key registration
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// Quote keys are registered first by the upgrade authority
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitQuoteKey<'info> {
+    pub fn execute(&mut self, bump: u8, quote_key: Pubkey) -> Result<()> {
+        let now = env::now()?;
+        let config = &mut self.config;
+        config.bump = bump;
+        config.governance = self.governance.key();
+        config.quote_key = quote_key;
+        config.updated_at = now;
+
+        emit!(QuoteKeyRotated {
+            quote_key,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RotateQuoteKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"quote_key"],
+        bump = config.bump,
+        has_one = governance @ QuoteError::Unauthorized
+    )]
+    pub config: Account<'info, QuoteKeyConfig>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> RotateQuoteKey<'info> {
+    /// Replace the key; quotes signed by the old one stop being accepted at once
+    pub fn execute(&mut self, quote_key: Pubkey) -> Result<()> {
+        let now = env::now()?;
+        let config = &mut self.config;
+        config.quote_key = quote_key;
+        config.updated_at = now;
+
+        emit!(QuoteKeyRotated {
+            quote_key,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct QuoteKeyRotated {
+    pub quote_key: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,76 @@
+//! Instruction handler growing task accounts allocated before the trailing
+//! `TaskState` fields were added
+
+use anchor_lang::{
+    prelude::*,
+    system_program::{self, Transfer},
+};
+use crate::env;
+use crate::state::task_state::{TaskError, TaskState};
+
+#[derive(Accounts)]
+pub struct MigrateTask<'info> {
+    /// CHECK: a task older than the current layout is too short to decode as
+    /// `TaskState`; its discriminator is checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub task: UncheckedAccount<'info>,
+
+    /// Funds the extra rent; anyone may pay
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateTask<'info> {
+    /// Grow the task to `TaskState::LEN`. Fields are only ever appended and the
+    /// new bytes are zeroed, so they decode as their defaults: no RNG seed, no
+    /// pricing slot, no deadline, no cached source
+    pub fn execute(&mut self) -> Result<()> {
+        let task = self.task.to_account_info();
+        require!(
+            task.try_borrow_data()?.starts_with(&TaskState::DISCRIMINATOR),
+            TaskError::NotATask
+        );
+        let old_len = task.data_len();
+        if old_len >= TaskState::LEN {
+            return Ok(());
+        }
+
+        let shortfall = Rent::get()?
+            .minimum_balance(TaskState::LEN)
+            .saturating_sub(task.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.payer.to_account_info(),
+                        to: task.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        task.realloc(TaskState::LEN, true)?;
+        TaskState::try_deserialize(&mut &task.try_borrow_data()?[..])?;
+
+        emit!(TaskMigrated {
+            task: task.key(),
+            old_len: old_len as u32,
+            new_len: TaskState::LEN as u32,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct TaskMigrated {
+    pub task: Pubkey,
+    pub old_len: u32,
+    pub new_len: u32,
+    pub timestamp: i64,
+}
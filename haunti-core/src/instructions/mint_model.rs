@@ -20,8 +20,12 @@ use mpl_token_metadata::{
     state::{Collection, Creator, DataV2, TokenStandard},
 };
 use crate::{
+    env,
     error::HauntiError,
-    state::{ModelNFT, ModelType},
+    state::{
+        model_registry::{model_type_key, IndexKind, ModelIndex, ModelIndexPage},
+        ModelNFT, ModelType,
+    },
     utils::{self, compute_model_hash},
     constants::METADATA_SEED,
 };
@@ -30,7 +34,14 @@ use crate::{
 #[instruction(
     model_type: ModelType, 
     params_hash: [u8; 32],
-    encrypted_params: Vec<u8>
+    encrypted_params: Vec<u8>,
+    name: String,
+    symbol: String,
+    uri: String,
+    creators: Vec<Creator>,
+    royalty_basis_points: u16,
+    type_page: u32,
+    creator_page: u32
 )]
 pub struct MintModel<'info> {
     #[account(mut)]
@@ -75,6 +86,44 @@ pub struct MintModel<'info> {
     )]
     pub master_edition_account: Account<'info, MasterEditionAccount>,
 
+    /// Index of all models of this type
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ModelIndex::LEN,
+        seeds = [b"model_index", b"type", &[model_type as u8]],
+        bump
+    )]
+    pub type_index: Account<'info, ModelIndex>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ModelIndexPage::LEN,
+        seeds = [b"model_index_page", type_index.key().as_ref(), &type_page.to_le_bytes()],
+        bump
+    )]
+    pub type_index_page: Account<'info, ModelIndexPage>,
+
+    /// Index of all models minted by the payer
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ModelIndex::LEN,
+        seeds = [b"model_index", b"creator", payer.key().as_ref()],
+        bump
+    )]
+    pub creator_index: Account<'info, ModelIndex>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ModelIndexPage::LEN,
+        seeds = [b"model_index_page", creator_index.key().as_ref(), &creator_page.to_le_bytes()],
+        bump
+    )]
+    pub creator_index_page: Account<'info, ModelIndexPage>,
+
     #[account(address = Token::id())]
     pub token_program: Program<'info, Token>,
     #[account(address = mpl_token_metadata::ID)]
@@ -98,6 +147,8 @@ impl<'info> MintModel<'info> {
         uri: String,
         creators: Vec<Creator>,
         royalty_basis_points: u16,
+        type_page: u32,
+        creator_page: u32,
     ) -> Result<()> {
         // Validate model uniqueness
         self.validate_unique_model(&params_hash)?;
@@ -124,10 +175,13 @@ impl<'info> MintModel<'info> {
         // 4. Create master edition
         self.create_master_edition()?;
 
+        // 5. List the model in the type and creator indexes
+        self.index_model(model_type, type_page, creator_page)?;
+
         emit!(ModelMinted {
             mint: self.mint.key(),
             model_type,
-            timestamp: Clock::get()?.unix_timestamp,
+            timestamp: env::now()?,
         });
 
         Ok(())
@@ -163,12 +217,48 @@ impl<'info> MintModel<'info> {
             mint: self.mint.key(),
             authority: self.payer.key(),
             bump: self.bumps["model_nft"],
-            created_at: Clock::get()?.unix_timestamp,
+            created_at: env::now()?,
         });
 
         Ok(())
     }
 
+    fn index_model(
+        &mut self,
+        model_type: ModelType,
+        type_page: u32,
+        creator_page: u32,
+    ) -> Result<()> {
+        let now = env::now()?;
+        let mint = self.mint.key();
+
+        let type_index_key = self.type_index.key();
+        self.type_index.open(
+            self.bumps["type_index"],
+            IndexKind::ModelType,
+            model_type_key(model_type as u8),
+            now,
+        );
+        self.type_index_page.open(self.bumps["type_index_page"], type_index_key, type_page);
+        self.type_index.append(&mut self.type_index_page, type_page, mint)?;
+
+        let creator_index_key = self.creator_index.key();
+        self.creator_index.open(
+            self.bumps["creator_index"],
+            IndexKind::Creator,
+            self.payer.key().to_bytes(),
+            now,
+        );
+        self.creator_index_page.open(
+            self.bumps["creator_index_page"],
+            creator_index_key,
+            creator_page,
+        );
+        self.creator_index.append(&mut self.creator_index_page, creator_page, mint)?;
+
+        Ok(())
+    }
+
     fn mint_token(&self) -> Result<()> {
         let cpi_accounts = MintTo {
             mint: self.mint.to_account_info(),
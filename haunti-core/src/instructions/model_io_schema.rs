@@ -0,0 +1,51 @@
+//! Instruction handler for registering a model's input/output schema
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::model_state::{IoSchema, ModelState};
+
+#[derive(Accounts)]
+pub struct SetModelIoSchema<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        realloc = model.space(),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub model: Account<'info, ModelState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetModelIoSchema<'info> {
+    /// Register the schema inference inputs must be encoded against; `None`
+    /// stops enforcing one
+    pub fn execute(&mut self, io_schema: Option<IoSchema>) -> Result<()> {
+        self.model.set_io_schema(io_schema)?;
+
+        emit!(ModelIoSchemaSet {
+            model: self.model.key(),
+            input_hash: io_schema.map(|s| s.input_hash),
+            output_hash: io_schema.map(|s| s.output_hash),
+            revision: self.model.revision,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ModelIoSchemaSet {
+    pub model: Pubkey,
+    /// `None` when the schema was removed
+    pub input_hash: Option<[u8; 32]>,
+    pub output_hash: Option<[u8; 32]>,
+    pub revision: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,70 @@
+//! Instruction handler for archiving a model out of the searchable indexes
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::{
+    model_registry::{ModelIndex, ModelIndexPage, ModelRegistryError},
+    ModelNFT,
+};
+
+#[derive(Accounts)]
+#[instruction(type_page: u32, creator_page: u32)]
+pub struct ArchiveModel<'info> {
+    #[account(has_one = authority @ ModelRegistryError::Unauthorized)]
+    pub model_nft: Account<'info, ModelNFT>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"model_index", b"type", &[model_nft.model_type as u8]],
+        bump = type_index.bump
+    )]
+    pub type_index: Account<'info, ModelIndex>,
+
+    #[account(
+        mut,
+        seeds = [b"model_index_page", type_index.key().as_ref(), &type_page.to_le_bytes()],
+        bump = type_index_page.bump
+    )]
+    pub type_index_page: Account<'info, ModelIndexPage>,
+
+    #[account(
+        mut,
+        seeds = [b"model_index", b"creator", authority.key().as_ref()],
+        bump = creator_index.bump
+    )]
+    pub creator_index: Account<'info, ModelIndex>,
+
+    #[account(
+        mut,
+        seeds = [b"model_index_page", creator_index.key().as_ref(), &creator_page.to_le_bytes()],
+        bump = creator_index_page.bump
+    )]
+    pub creator_index_page: Account<'info, ModelIndexPage>,
+}
+
+impl<'info> ArchiveModel<'info> {
+    /// Unlist the model from its type and creator indexes. The NFT and its
+    /// metadata are untouched; archived models simply stop being discoverable.
+    pub fn execute(&mut self, type_page: u32, creator_page: u32) -> Result<()> {
+        let mint = self.model_nft.mint;
+        self.type_index.remove(&mut self.type_index_page, type_page, &mint)?;
+        self.creator_index.remove(&mut self.creator_index_page, creator_page, &mint)?;
+
+        emit!(ModelArchived {
+            mint,
+            authority: self.authority.key(),
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ModelArchived {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
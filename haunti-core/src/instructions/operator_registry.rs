@@ -0,0 +1,125 @@
+//! Instruction handlers for the community operator registry and circuit descriptors
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::{
+    model_state::ModelState,
+    operator_registry::{
+        CircuitDescriptor, OperatorRecord, OperatorRegistryError, MAX_OPERATOR_URI_LEN,
+    },
+};
+
+#[derive(Accounts)]
+#[instruction(hash: [u8; 32])]
+pub struct RegisterOperator<'info> {
+    #[account(
+        init,
+        payer = author,
+        space = OperatorRecord::LEN,
+        seeds = [b"operator", &hash],
+        bump
+    )]
+    pub record: Account<'info, OperatorRecord>,
+
+    #[account(mut)]
+    pub author: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RegisterOperator<'info> {
+    /// Publish an operator under its description hash. Registration is
+    /// permissionless and permanent: the hash fixes the constraints, so a record
+    /// never needs to change.
+    pub fn execute(&mut self, hash: [u8; 32], spec_uri: String, bump: u8) -> Result<()> {
+        require!(hash != [0u8; 32], OperatorRegistryError::InvalidOperatorHash);
+        require!(
+            spec_uri.len() <= MAX_OPERATOR_URI_LEN,
+            OperatorRegistryError::UriTooLong
+        );
+
+        let now = env::now()?;
+        let record = &mut self.record;
+        record.bump = bump;
+        record.hash = hash;
+        record.author = self.author.key();
+        record.spec_uri = spec_uri.clone();
+        record.registered_at = now;
+
+        emit!(OperatorRegistered {
+            hash,
+            author: record.author,
+            spec_uri,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetCircuitDescriptor<'info> {
+    #[account(has_one = owner @ OperatorRegistryError::Unauthorized)]
+    pub model: Account<'info, ModelState>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = CircuitDescriptor::LEN,
+        seeds = [b"circuit", model.key().as_ref()],
+        bump
+    )]
+    pub descriptor: Account<'info, CircuitDescriptor>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetCircuitDescriptor<'info> {
+    /// Declare the operators the model's circuit is built from.
+    ///
+    /// `records` are the remaining accounts: the `OperatorRecord` of each distinct
+    /// operator, in order of first appearance in `operators`.
+    pub fn execute(
+        &mut self,
+        operators: Vec<[u8; 32]>,
+        records: &[AccountInfo<'info>],
+        bump: u8,
+    ) -> Result<()> {
+        CircuitDescriptor::check_registered(&operators, records, &crate::ID)?;
+
+        let now = env::now()?;
+        let descriptor = &mut self.descriptor;
+        descriptor.bump = bump;
+        descriptor.model = self.model.key();
+        descriptor.operators = operators.clone();
+        descriptor.updated_at = now;
+
+        emit!(CircuitDescriptorSet {
+            model: descriptor.model,
+            operators,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct OperatorRegistered {
+    pub hash: [u8; 32],
+    pub author: Pubkey,
+    pub spec_uri: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitDescriptorSet {
+    pub model: Pubkey,
+    pub operators: Vec<[u8; 32]>,
+    pub timestamp: i64,
+}
@@ -0,0 +1,539 @@
+//! Optimistic verification: provisional results, challenges and arbitration
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{hash::hash, system_instruction};
+use token_vault::UserStake;
+use crate::env;
+use crate::instructions::gpu_provider::ProviderReputationChanged;
+use crate::state::{
+    dispute_state::{Dispute, DisputeError, DisputeStatus},
+    gpu_provider::GpuProvider,
+    optimistic::{OptimisticConfig, OptimisticError},
+    pricing_state::{release_queue_slot, ModelPricing},
+    size_limits::SizeLimits,
+    task_feed::{EventCounter, TaskFeedKind},
+    task_state::{
+        TaskError, TaskState, TaskStatus, TaskStatusChanged, ERROR_RESULT_OVERTURNED,
+    },
+    worker_bond::WorkerBond,
+};
+
+#[derive(Accounts)]
+pub struct InitOptimisticConfig<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = OptimisticConfig::LEN,
+        seeds = [b"optimistic_config"],
+        bump
+    )]
+    pub config: Account<'info, OptimisticConfig>,
+
+    /// Governance authority that will own the policy
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// Only the upgrade authority opens optimistic verification
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitOptimisticConfig<'info> {
+    pub fn execute(
+        &mut self,
+        bump: u8,
+        stake_pool: Pubkey,
+        arbitrator: Pubkey,
+        challenge_window_secs: i64,
+        challenger_bond: u64,
+        min_challenger_stake: u64,
+        worker_slash_bps: u16,
+    ) -> Result<()> {
+        let now = env::now()?;
+        let config = &mut self.config;
+        config.bump = bump;
+        config.governance = self.governance.key();
+        config.stake_pool = stake_pool;
+        config.set(
+            arbitrator,
+            challenge_window_secs,
+            challenger_bond,
+            min_challenger_stake,
+            worker_slash_bps,
+        )?;
+        config.updated_at = now;
+
+        emit!(OptimisticConfigUpdated {
+            arbitrator,
+            challenge_window_secs,
+            challenger_bond,
+            min_challenger_stake,
+            worker_slash_bps,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateOptimisticConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"optimistic_config"],
+        bump = config.bump,
+        has_one = governance @ OptimisticError::Unauthorized
+    )]
+    pub config: Account<'info, OptimisticConfig>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> UpdateOptimisticConfig<'info> {
+    pub fn execute(
+        &mut self,
+        arbitrator: Pubkey,
+        challenge_window_secs: i64,
+        challenger_bond: u64,
+        min_challenger_stake: u64,
+        worker_slash_bps: u16,
+    ) -> Result<()> {
+        let now = env::now()?;
+        let config = &mut self.config;
+        config.set(
+            arbitrator,
+            challenge_window_secs,
+            challenger_bond,
+            min_challenger_stake,
+            worker_slash_bps,
+        )?;
+        config.updated_at = now;
+
+        emit!(OptimisticConfigUpdated {
+            arbitrator,
+            challenge_window_secs,
+            challenger_bond,
+            min_challenger_stake,
+            worker_slash_bps,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SubmitProvisionalResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump
+    )]
+    pub task: Account<'info, TaskState>,
+
+    /// Worker currently executing the task
+    pub worker: Signer<'info>,
+
+    #[account(seeds = [b"size_limits"], bump = size_limits.bump)]
+    pub size_limits: Account<'info, SizeLimits>,
+}
+
+impl<'info> SubmitProvisionalResult<'info> {
+    /// Optimistic counterpart of `submit_computation`: record the result and a
+    /// commitment to its proof without verifying it. The proof bytes stay in
+    /// this instruction's data for anyone checking it off-chain
+    pub fn execute(&mut self, result_hash: [u8; 32], proof: Vec<u8>) -> Result<()> {
+        self.size_limits.check_proof(proof.len())?;
+        let old_status = self.task.status.clone();
+        self.task.submit_provisional(&self.worker.key(), result_hash)?;
+        let now = env::now()?;
+
+        emit!(TaskStatusChanged {
+            task: self.task.key(),
+            old_status,
+            new_status: self.task.status.clone(),
+            version: self.task.version,
+            timestamp: now,
+        });
+        emit!(ProvisionalResultSubmitted {
+            task: self.task.key(),
+            worker: self.worker.key(),
+            result_hash,
+            proof_hash: hash(&proof).to_bytes(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ChallengeResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump
+    )]
+    pub task: Account<'info, TaskState>,
+
+    #[account(seeds = [b"optimistic_config"], bump = config.bump)]
+    pub config: Account<'info, OptimisticConfig>,
+
+    #[account(
+        init,
+        payer = challenger,
+        space = Dispute::LEN,
+        seeds = [b"dispute", task.key().as_ref()],
+        bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    /// Challenger's stake in the policy's token-vault pool
+    #[account(
+        seeds = [b"stake", config.stake_pool.as_ref(), challenger.key().as_ref()],
+        bump,
+        seeds::program = token_vault::ID
+    )]
+    pub stake: Account<'info, UserStake>,
+
+    #[account(seeds = [b"size_limits"], bump = size_limits.bump)]
+    pub size_limits: Account<'info, SizeLimits>,
+
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ChallengeResult<'info> {
+    /// Dispute a provisional result with a counter-proof, locking the
+    /// challenger bond on the dispute account
+    pub fn execute(&mut self, counter_proof: Vec<u8>, bump: u8) -> Result<()> {
+        require!(!counter_proof.is_empty(), OptimisticError::EmptyCounterProof);
+        self.size_limits.check_proof(counter_proof.len())?;
+        require!(
+            self.stake.amount >= self.config.min_challenger_stake,
+            OptimisticError::InsufficientStake
+        );
+        let now = env::now()?;
+        require!(
+            self.task.challengeable(now, self.config.challenge_window_secs),
+            OptimisticError::ChallengeWindowClosed
+        );
+
+        let old_status = self.task.status.clone();
+        let worker = self.task.dispute(now)?;
+
+        let bond = self.config.challenger_bond;
+        anchor_lang::solana_program::program::invoke(
+            &system_instruction::transfer(&self.challenger.key(), &self.dispute.key(), bond),
+            &[
+                self.challenger.to_account_info(),
+                self.dispute.to_account_info(),
+                self.system_program.to_account_info(),
+            ],
+        )?;
+
+        let counter_proof_hash = hash(&counter_proof).to_bytes();
+        let dispute = &mut self.dispute;
+        dispute.bump = bump;
+        dispute.task = self.task.key();
+        dispute.challenger = self.challenger.key();
+        dispute.worker = worker;
+        dispute.status = DisputeStatus::Open;
+        dispute.opened_at = now;
+        dispute.bond = bond;
+        dispute.counter_proof_hash = counter_proof_hash;
+
+        emit!(TaskStatusChanged {
+            task: self.task.key(),
+            old_status,
+            new_status: self.task.status.clone(),
+            version: self.task.version,
+            timestamp: now,
+        });
+        emit!(ResultChallenged {
+            task: self.task.key(),
+            dispute: self.dispute.key(),
+            challenger: self.challenger.key(),
+            worker,
+            bond,
+            counter_proof_hash,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ArbitrateDispute<'info> {
+    #[account(
+        seeds = [b"optimistic_config"],
+        bump = config.bump,
+        has_one = arbitrator @ OptimisticError::Unauthorized
+    )]
+    pub config: Account<'info, OptimisticConfig>,
+
+    pub arbitrator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump,
+        has_one = owner
+    )]
+    pub task: Account<'info, TaskState>,
+
+    #[account(
+        mut,
+        seeds = [b"dispute", task.key().as_ref()],
+        bump = dispute.bump,
+        has_one = challenger,
+        has_one = worker
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    /// The disputed worker's bond, if it posted one; slashed when the result
+    /// is overturned
+    #[account(
+        mut,
+        seeds = [b"worker_bond", dispute.worker.as_ref()],
+        bump = worker_bond.bump
+    )]
+    pub worker_bond: Option<Account<'info, WorkerBond>>,
+
+    /// CHECK: validated against `dispute.challenger`
+    #[account(mut)]
+    pub challenger: UncheckedAccount<'info>,
+
+    /// CHECK: validated against `dispute.worker`
+    #[account(mut)]
+    pub worker: UncheckedAccount<'info>,
+
+    /// The disputed worker's provider record, if registered; its reputation
+    /// follows the ruling
+    #[account(
+        mut,
+        seeds = [b"gpu_provider", dispute.worker.as_ref()],
+        bump = provider.bump
+    )]
+    pub provider: Option<Account<'info, GpuProvider>>,
+
+    /// Receives the task escrow when the result is overturned
+    /// CHECK: validated against `task.owner`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Pricing queue the task holds a slot in; required for priced inference tasks
+    #[account(mut)]
+    pub pricing: Option<Account<'info, ModelPricing>>,
+}
+
+impl<'info> ArbitrateDispute<'info> {
+    /// Rule on a challenged result and slash the losing side
+    pub fn execute(&mut self, uphold_result: bool) -> Result<()> {
+        let now = env::now()?;
+        self.dispute.resolve(uphold_result, now)?;
+        let old_status = self.task.status.clone();
+        let bond = self.dispute.bond;
+        let dispute = self.dispute.to_account_info();
+
+        // The dispute, bond and task accounts are program-owned, so lamports
+        // move without a CPI
+        let (mut slashed, mut refund) = (0, 0);
+        if uphold_result {
+            self.task.confirm_result()?;
+            **dispute.try_borrow_mut_lamports()? -= bond;
+            **self.worker.to_account_info().try_borrow_mut_lamports()? += bond;
+        } else {
+            self.task.fail(ERROR_RESULT_OVERTURNED)?;
+            **dispute.try_borrow_mut_lamports()? -= bond;
+            **self.challenger.to_account_info().try_borrow_mut_lamports()? += bond;
+
+            if let Some(worker_bond) = self.worker_bond.as_mut() {
+                slashed = self.config.worker_slash(worker_bond.amount);
+                **worker_bond.to_account_info().try_borrow_mut_lamports()? -= slashed;
+                **self.challenger.to_account_info().try_borrow_mut_lamports()? += slashed;
+                worker_bond.amount -= slashed;
+                worker_bond.slashed += slashed;
+            }
+
+            let task = self.task.to_account_info();
+            let rent_floor = Rent::get()?.minimum_balance(task.data_len());
+            refund = task.lamports().saturating_sub(rent_floor);
+            **task.try_borrow_mut_lamports()? -= refund;
+            **self.owner.to_account_info().try_borrow_mut_lamports()? += refund;
+        }
+        self.dispute.bond = 0;
+        release_queue_slot(&mut self.task, self.pricing.as_mut())?;
+        if let Some(provider) = self.provider.as_mut() {
+            provider.record_proof(uphold_result);
+            emit!(ProviderReputationChanged {
+                provider: provider.key(),
+                task: self.task.key(),
+                succeeded: uphold_result,
+                reputation: provider.reputation,
+            });
+        }
+
+        emit!(TaskStatusChanged {
+            task: self.task.key(),
+            old_status,
+            new_status: self.task.status.clone(),
+            version: self.task.version,
+            timestamp: now,
+        });
+        emit!(DisputeResolved {
+            task: self.task.key(),
+            dispute: self.dispute.key(),
+            winner: self.dispute.winner().ok_or(DisputeError::DisputeNotResolved)?,
+            result_upheld: uphold_result,
+            bond_forfeited: if uphold_result { bond } else { 0 },
+            worker_slashed: slashed,
+            owner_refund: refund,
+            timestamp: now,
+        });
+        let task = self.task.key();
+        let arbitrator = self.arbitrator.key();
+        if uphold_result {
+            self.event_counter
+                .emit(task, TaskFeedKind::Completed, arbitrator, 0)?;
+        } else {
+            self.event_counter.emit(task, TaskFeedKind::Failed, arbitrator, 0)?;
+            self.event_counter
+                .emit(task, TaskFeedKind::Refunded, self.owner.key(), refund)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProvisionalResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump
+    )]
+    pub task: Account<'info, TaskState>,
+
+    #[account(seeds = [b"optimistic_config"], bump = config.bump)]
+    pub config: Account<'info, OptimisticConfig>,
+
+    /// Provider record of the task's worker, if registered; credited with the
+    /// confirmed result
+    #[account(mut)]
+    pub provider: Option<Account<'info, GpuProvider>>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Pricing queue the task holds a slot in; required for priced inference tasks
+    #[account(mut)]
+    pub pricing: Option<Account<'info, ModelPricing>>,
+}
+
+impl<'info> FinalizeProvisionalResult<'info> {
+    /// Complete an unchallenged provisional result once its window closed;
+    /// anyone may call
+    pub fn execute(&mut self) -> Result<()> {
+        let now = env::now()?;
+        require!(
+            !self.task.challengeable(now, self.config.challenge_window_secs),
+            OptimisticError::ChallengeWindowOpen
+        );
+        let old_status = self.task.status.clone();
+        // Disputed tasks are settled by arbitration, not here
+        let worker = match old_status {
+            TaskStatus::Provisional { worker, .. } => {
+                self.task.confirm_result()?;
+                worker
+            }
+            _ => return Err(TaskError::InvalidStateTransition.into()),
+        };
+        release_queue_slot(&mut self.task, self.pricing.as_mut())?;
+        if let Some(provider) = self.provider.as_mut() {
+            require_keys_eq!(provider.authority, worker, TaskError::Unauthorized);
+            provider.record_proof(true);
+            emit!(ProviderReputationChanged {
+                provider: provider.key(),
+                task: self.task.key(),
+                succeeded: true,
+                reputation: provider.reputation,
+            });
+        }
+
+        emit!(TaskStatusChanged {
+            task: self.task.key(),
+            old_status,
+            new_status: self.task.status.clone(),
+            version: self.task.version,
+            timestamp: now,
+        });
+        self.event_counter
+            .emit(self.task.key(), TaskFeedKind::Completed, worker, 0)?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct OptimisticConfigUpdated {
+    pub arbitrator: Pubkey,
+    pub challenge_window_secs: i64,
+    pub challenger_bond: u64,
+    pub min_challenger_stake: u64,
+    pub worker_slash_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProvisionalResultSubmitted {
+    pub task: Pubkey,
+    pub worker: Pubkey,
+    pub result_hash: [u8; 32],
+    /// SHA-256 of the unverified proof
+    pub proof_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResultChallenged {
+    pub task: Pubkey,
+    pub dispute: Pubkey,
+    pub challenger: Pubkey,
+    pub worker: Pubkey,
+    pub bond: u64,
+    pub counter_proof_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub task: Pubkey,
+    pub dispute: Pubkey,
+    pub winner: Pubkey,
+    pub result_upheld: bool,
+    /// Challenger bond paid to the worker
+    pub bond_forfeited: u64,
+    /// Lamports slashed from the worker's bond and paid to the challenger
+    pub worker_slashed: u64,
+    /// Task escrow returned to the owner
+    pub owner_refund: u64,
+    pub timestamp: i64,
+}
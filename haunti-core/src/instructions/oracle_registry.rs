@@ -0,0 +1,346 @@
+//! Instruction handlers for staked data feed oracles
+
+use anchor_lang::{prelude::*, solana_program::system_instruction};
+use crate::env;
+use crate::state::oracle_registry::{
+    OracleFeed, OracleRegistryError, OracleStake, ORACLE_UNBONDING_SECS,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 32])]
+pub struct InitOracleFeed<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = OracleFeed::LEN,
+        seeds = [b"oracle_feed", &feed_id],
+        bump
+    )]
+    pub feed: Account<'info, OracleFeed>,
+
+    /// Governance authority that will own the feed parameters
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// Feed parameters are first set by the upgrade authority
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitOracleFeed<'info> {
+    pub fn execute(
+        &mut self,
+        feed_id: [u8; 32],
+        min_stake: u64,
+        min_reports: u8,
+        max_deviation_bps: u16,
+        slash_bps: u16,
+        bump: u8,
+    ) -> Result<()> {
+        let feed = &mut self.feed;
+        feed.configure(min_stake, min_reports, max_deviation_bps, slash_bps)?;
+        feed.bump = bump;
+        feed.governance = self.governance.key();
+        feed.feed_id = feed_id;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateOracleFeed<'info> {
+    #[account(
+        mut,
+        seeds = [b"oracle_feed", &feed.feed_id],
+        bump = feed.bump,
+        has_one = governance @ OracleRegistryError::Unauthorized
+    )]
+    pub feed: Account<'info, OracleFeed>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> UpdateOracleFeed<'info> {
+    /// Retune the feed; registered oracles keep their bonds even if below a
+    /// raised `min_stake`
+    pub fn execute(
+        &mut self,
+        min_stake: u64,
+        min_reports: u8,
+        max_deviation_bps: u16,
+        slash_bps: u16,
+    ) -> Result<()> {
+        self.feed
+            .configure(min_stake, min_reports, max_deviation_bps, slash_bps)
+    }
+}
+
+#[derive(Accounts)]
+pub struct RegisterOracle<'info> {
+    #[account(
+        mut,
+        seeds = [b"oracle_feed", &feed.feed_id],
+        bump = feed.bump
+    )]
+    pub feed: Account<'info, OracleFeed>,
+
+    #[account(
+        init,
+        payer = oracle,
+        space = OracleStake::LEN,
+        seeds = [b"oracle_stake", feed.key().as_ref(), oracle.key().as_ref()],
+        bump
+    )]
+    pub stake: Account<'info, OracleStake>,
+
+    #[account(mut)]
+    pub oracle: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RegisterOracle<'info> {
+    /// Bond `amount` lamports and join the feed
+    pub fn execute(&mut self, amount: u64, bump: u8) -> Result<()> {
+        require!(
+            amount >= self.feed.min_stake,
+            OracleRegistryError::InsufficientStake
+        );
+        self.feed.add_oracle(self.oracle.key())?;
+
+        anchor_lang::solana_program::program::invoke(
+            &system_instruction::transfer(&self.oracle.key(), &self.stake.key(), amount),
+            &[
+                self.oracle.to_account_info(),
+                self.stake.to_account_info(),
+                self.system_program.to_account_info(),
+            ],
+        )?;
+
+        let now = env::now()?;
+        let stake = &mut self.stake;
+        stake.bump = bump;
+        stake.feed = self.feed.key();
+        stake.oracle = self.oracle.key();
+        stake.stake = amount;
+        stake.slashed = 0;
+        stake.registered_at = now;
+        stake.unlock_at = 0;
+
+        emit!(OracleRegistered {
+            feed: stake.feed,
+            oracle: stake.oracle,
+            stake: amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SubmitOracleValue<'info> {
+    #[account(
+        mut,
+        seeds = [b"oracle_feed", &feed.feed_id],
+        bump = feed.bump
+    )]
+    pub feed: Account<'info, OracleFeed>,
+
+    #[account(
+        seeds = [b"oracle_stake", feed.key().as_ref(), oracle.key().as_ref()],
+        bump = stake.bump,
+        has_one = feed,
+        has_one = oracle
+    )]
+    pub stake: Account<'info, OracleStake>,
+
+    pub oracle: Signer<'info>,
+}
+
+impl<'info> SubmitOracleValue<'info> {
+    /// Report a value for the open round. Oracles slashed below the feed
+    /// minimum must exit and register again with a fresh bond.
+    pub fn execute(&mut self, value: i64) -> Result<()> {
+        require!(
+            self.stake.stake >= self.feed.min_stake,
+            OracleRegistryError::InsufficientStake
+        );
+        self.feed.report(self.oracle.key(), value)
+    }
+}
+
+#[derive(Accounts)]
+pub struct FinalizeOracleRound<'info> {
+    #[account(
+        mut,
+        seeds = [b"oracle_feed", &feed.feed_id],
+        bump = feed.bump
+    )]
+    pub feed: Account<'info, OracleFeed>,
+}
+
+impl<'info> FinalizeOracleRound<'info> {
+    /// Anchor the round median once enough oracles have reported (Permissionless)
+    pub fn execute(&mut self) -> Result<()> {
+        let clock = env::clock()?;
+        let reports = self.feed.reports.len() as u8;
+        let median = self.feed.finalize_round(clock.slot)?;
+
+        emit!(OracleRoundFinalized {
+            feed: self.feed.key(),
+            round: self.feed.round,
+            median,
+            reports,
+            flagged: self.feed.flagged.clone(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SlashOracle<'info> {
+    #[account(
+        mut,
+        seeds = [b"oracle_feed", &feed.feed_id],
+        bump = feed.bump
+    )]
+    pub feed: Account<'info, OracleFeed>,
+
+    #[account(
+        mut,
+        seeds = [b"oracle_stake", feed.key().as_ref(), stake.oracle.as_ref()],
+        bump = stake.bump,
+        has_one = feed
+    )]
+    pub stake: Account<'info, OracleStake>,
+}
+
+impl<'info> SlashOracle<'info> {
+    /// Slash an oracle whose report deviated from a finalized median (Permissionless)
+    pub fn execute(&mut self) -> Result<()> {
+        self.feed.take_flag(&self.stake.oracle)?;
+        let amount = self.feed.slash_amount(self.stake.stake);
+
+        // Both accounts are program-owned, so the bond moves without a CPI
+        **self.stake.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **self.feed.to_account_info().try_borrow_mut_lamports()? += amount;
+        self.stake.stake -= amount;
+        self.stake.slashed += amount;
+        self.feed.slashed_total += amount;
+
+        emit!(OracleSlashed {
+            feed: self.feed.key(),
+            oracle: self.stake.oracle,
+            amount,
+            remaining_stake: self.stake.stake,
+            round: self.feed.round,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExitOracle<'info> {
+    #[account(
+        mut,
+        seeds = [b"oracle_feed", &feed.feed_id],
+        bump = feed.bump
+    )]
+    pub feed: Account<'info, OracleFeed>,
+
+    #[account(
+        mut,
+        seeds = [b"oracle_stake", feed.key().as_ref(), oracle.key().as_ref()],
+        bump = stake.bump,
+        has_one = feed,
+        has_one = oracle
+    )]
+    pub stake: Account<'info, OracleStake>,
+
+    #[account(mut)]
+    pub oracle: Signer<'info>,
+}
+
+impl<'info> ExitOracle<'info> {
+    /// Leave the feed and start unbonding; blocked while a slash is pending
+    pub fn deregister(&mut self) -> Result<()> {
+        let oracle = self.oracle.key();
+        require!(
+            !self.feed.flagged.contains(&oracle),
+            OracleRegistryError::PendingSlash
+        );
+        self.feed.remove_oracle(&oracle)?;
+
+        let now = env::now()?;
+        self.stake.unlock_at = now + ORACLE_UNBONDING_SECS;
+
+        emit!(OracleDeregistered {
+            feed: self.feed.key(),
+            oracle,
+            unlock_at: self.stake.unlock_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Close the stake account after unbonding, returning bond and rent
+    pub fn withdraw(&mut self) -> Result<()> {
+        let unlock_at = self.stake.unlock_at;
+        require!(
+            unlock_at != 0 && env::now()? >= unlock_at,
+            OracleRegistryError::StillUnbonding
+        );
+        self.stake.close(self.oracle.to_account_info())
+    }
+}
+
+#[event]
+pub struct OracleRegistered {
+    pub feed: Pubkey,
+    pub oracle: Pubkey,
+    pub stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OracleRoundFinalized {
+    pub feed: Pubkey,
+    pub round: u64,
+    pub median: i64,
+    pub reports: u8,
+    /// Oracles with a slash pending, including earlier rounds
+    pub flagged: Vec<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OracleSlashed {
+    pub feed: Pubkey,
+    pub oracle: Pubkey,
+    pub amount: u64,
+    pub remaining_stake: u64,
+    pub round: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OracleDeregistered {
+    pub feed: Pubkey,
+    pub oracle: Pubkey,
+    pub unlock_at: i64,
+    pub timestamp: i64,
+}
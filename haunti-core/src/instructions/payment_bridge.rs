@@ -0,0 +1,247 @@
+//! Instruction handlers for redeeming EVM payments into prepaid task balances
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::prepaid_balance::{
+    PaymentBridgeConfig, PaymentBridgeError, PaymentPayload, PostedVaa, PrepaidBalance,
+    RedeemedPayment, PAYMENT_ASSET_COUNT,
+};
+
+#[derive(Accounts)]
+pub struct InitPaymentBridge<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = PaymentBridgeConfig::LEN,
+        seeds = [b"payment_bridge"],
+        bump
+    )]
+    pub config: Account<'info, PaymentBridgeConfig>,
+
+    /// Governance authority that will own the bridge settings
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// The upgrade authority decides who governs bridged payments
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitPaymentBridge<'info> {
+    pub fn execute(
+        &mut self,
+        bump: u8,
+        wormhole_program: Pubkey,
+        emitter_chain: u16,
+        emitter_address: [u8; 32],
+        rates: [u64; PAYMENT_ASSET_COUNT],
+    ) -> Result<()> {
+        let now = env::now()?;
+        let config = &mut self.config;
+        config.bump = bump;
+        config.governance = self.governance.key();
+        config.wormhole_program = wormhole_program;
+        config.emitter_chain = emitter_chain;
+        config.emitter_address = emitter_address;
+        config.rates = rates;
+        config.total_credited = 0;
+        config.updated_at = now;
+
+        emit!(PaymentBridgeUpdated {
+            emitter_chain,
+            emitter_address,
+            rates,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdatePaymentBridge<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_bridge"],
+        bump = config.bump,
+        has_one = governance @ PaymentBridgeError::Unauthorized
+    )]
+    pub config: Account<'info, PaymentBridgeConfig>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> UpdatePaymentBridge<'info> {
+    /// Re-point the bridge at a new payment contract or reprice the assets
+    pub fn execute(
+        &mut self,
+        emitter_chain: u16,
+        emitter_address: [u8; 32],
+        rates: [u64; PAYMENT_ASSET_COUNT],
+    ) -> Result<()> {
+        let now = env::now()?;
+        let config = &mut self.config;
+        config.emitter_chain = emitter_chain;
+        config.emitter_address = emitter_address;
+        config.rates = rates;
+        config.updated_at = now;
+
+        emit!(PaymentBridgeUpdated {
+            emitter_chain,
+            emitter_address,
+            rates,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(emitter_chain: u16, emitter_address: [u8; 32], sequence: u64)]
+pub struct RedeemBridgedPayment<'info> {
+    #[account(mut, seeds = [b"payment_bridge"], bump = config.bump)]
+    pub config: Account<'info, PaymentBridgeConfig>,
+
+    /// CHECK: posted VAA, verified by owner and deserialized as `PostedVaa`
+    #[account(owner = config.wormhole_program @ PaymentBridgeError::InvalidVaaAccount)]
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    /// Replay guard keyed like Wormhole's own claims: (chain, emitter, sequence)
+    #[account(
+        init,
+        payer = payer,
+        space = RedeemedPayment::LEN,
+        seeds = [
+            b"redeemed_payment",
+            &emitter_chain.to_le_bytes(),
+            &emitter_address,
+            &sequence.to_le_bytes()
+        ],
+        bump
+    )]
+    pub redeemed: Account<'info, RedeemedPayment>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PrepaidBalance::LEN,
+        seeds = [b"prepaid", recipient.key().as_ref()],
+        bump
+    )]
+    pub prepaid: Account<'info, PrepaidBalance>,
+
+    /// CHECK: must match the payment's recipient
+    pub recipient: UncheckedAccount<'info>,
+
+    /// Anyone may relay a payment; the payer only funds the new accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RedeemBridgedPayment<'info> {
+    /// Credit the recipient's prepaid balance with the lamport value of a locked
+    /// EVM payment. The seed arguments must match the VAA so the replay guard
+    /// covers exactly this message. The bumps are the canonical ones Anchor
+    /// found deriving `redeemed` and `prepaid`, never caller input.
+    pub fn execute(
+        &mut self,
+        emitter_chain: u16,
+        emitter_address: [u8; 32],
+        sequence: u64,
+        redeemed_bump: u8,
+        prepaid_bump: u8,
+    ) -> Result<()> {
+        let vaa = PostedVaa::from_account(&self.posted_vaa)?;
+        require!(
+            vaa.emitter_chain == emitter_chain
+                && vaa.emitter_address == emitter_address
+                && vaa.sequence == sequence,
+            PaymentBridgeError::InvalidVaaAccount
+        );
+        self.config.check_emitter(&vaa)?;
+
+        let payment = PaymentPayload::decode(&vaa.payload)?;
+        require_keys_eq!(
+            payment.recipient,
+            self.recipient.key(),
+            PaymentBridgeError::RecipientMismatch
+        );
+        let lamports = self.config.credit_for(&payment)?;
+
+        // Pay out of the treasury without touching its rent-exempt reserve
+        let treasury = self.config.to_account_info();
+        let reserve = Rent::get()?.minimum_balance(treasury.data_len());
+        require!(
+            treasury.lamports().saturating_sub(reserve) >= lamports,
+            PaymentBridgeError::InsufficientLiquidity
+        );
+        **treasury.try_borrow_mut_lamports()? -= lamports;
+        **self.prepaid.to_account_info().try_borrow_mut_lamports()? += lamports;
+
+        let now = env::now()?;
+        let prepaid = &mut self.prepaid;
+        if prepaid.owner == Pubkey::default() {
+            prepaid.bump = prepaid_bump;
+            prepaid.owner = self.recipient.key();
+        }
+        prepaid.credit(lamports)?;
+        prepaid.updated_at = now;
+
+        let redeemed = &mut self.redeemed;
+        redeemed.bump = redeemed_bump;
+        redeemed.recipient = self.recipient.key();
+        redeemed.lamports = lamports;
+        redeemed.redeemed_at = now;
+
+        self.config.total_credited = self.config.total_credited.saturating_add(lamports);
+
+        emit!(BridgedPaymentRedeemed {
+            recipient: self.recipient.key(),
+            emitter_chain,
+            sequence,
+            lock_id: payment.lock_id,
+            amount: payment.amount,
+            lamports,
+            balance: self.prepaid.balance,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct PaymentBridgeUpdated {
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub rates: [u64; PAYMENT_ASSET_COUNT],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BridgedPaymentRedeemed {
+    pub recipient: Pubkey,
+    pub emitter_chain: u16,
+    pub sequence: u64,
+    /// Lock id assigned by the payment contract
+    pub lock_id: u64,
+    /// Locked amount in source-chain base units
+    pub amount: u128,
+    /// Lamports credited
+    pub lamports: u64,
+    /// Prepaid balance after the credit
+    pub balance: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,198 @@
+//! Instruction handlers for governance-published network statistics checkpoints
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::stats_checkpoint::{NetworkStats, StatsCheckpoint, StatsConfig, StatsError};
+
+#[derive(Accounts)]
+pub struct InitStatsConfig<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = StatsConfig::LEN,
+        seeds = [b"stats_config"],
+        bump
+    )]
+    pub config: Account<'info, StatsConfig>,
+
+    /// Governance authority that will sign checkpoints
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// Only the upgrade authority may pay for the config, so nobody can claim the
+    /// stats governance before deployment finishes
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitStatsConfig<'info> {
+    pub fn execute(&mut self, bump: u8) -> Result<()> {
+        let config = &mut self.config;
+        config.bump = bump;
+        config.governance = self.governance.key();
+        config.pending_governance = None;
+        config.last_epoch = 0;
+        config.last_stats = NetworkStats::default();
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ProposeStatsGovernance<'info> {
+    #[account(
+        mut,
+        seeds = [b"stats_config"],
+        bump = config.bump,
+        has_one = governance @ StatsError::Unauthorized
+    )]
+    pub config: Account<'info, StatsConfig>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> ProposeStatsGovernance<'info> {
+    /// Nominate a successor, or withdraw the nomination with `None`
+    pub fn execute(&mut self, nominee: Option<Pubkey>) -> Result<()> {
+        self.config.pending_governance = nominee;
+        emit!(StatsGovernanceProposed {
+            governance: self.governance.key(),
+            pending: nominee,
+            timestamp: env::now()?,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AcceptStatsGovernance<'info> {
+    #[account(mut, seeds = [b"stats_config"], bump = config.bump)]
+    pub config: Account<'info, StatsConfig>,
+
+    pub nominee: Signer<'info>,
+}
+
+impl<'info> AcceptStatsGovernance<'info> {
+    pub fn execute(&mut self) -> Result<()> {
+        let previous = self.config.accept_governance(self.nominee.key())?;
+        emit!(StatsGovernanceTransferred {
+            previous,
+            governance: self.nominee.key(),
+            timestamp: env::now()?,
+        });
+        Ok(())
+    }
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct PublishStatsCheckpoint<'info> {
+    #[account(
+        mut,
+        seeds = [b"stats_config"],
+        bump = config.bump,
+        has_one = governance @ StatsError::Unauthorized
+    )]
+    pub config: Account<'info, StatsConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = StatsCheckpoint::LEN,
+        seeds = [b"stats", &epoch.to_le_bytes()],
+        bump
+    )]
+    pub checkpoint: Account<'info, StatsCheckpoint>,
+
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PublishStatsCheckpoint<'info> {
+    /// Record `stats` for `epoch`, committed to `record_count` records under `records_root`,
+    /// returning the event to publish it with
+    pub fn execute(
+        &mut self,
+        epoch: u64,
+        stats: NetworkStats,
+        records_root: [u8; 32],
+        record_count: u64,
+        bump: u8,
+    ) -> Result<StatsCheckpointPublished> {
+        require!(
+            epoch == self.config.last_epoch + 1,
+            StatsError::EpochOutOfOrder
+        );
+        require!(
+            stats.is_successor_of(&self.config.last_stats),
+            StatsError::StatsRegressed
+        );
+        require!(
+            record_count > 0 && records_root != [0u8; 32],
+            StatsError::EmptyRecords
+        );
+
+        let now = env::now()?;
+        let checkpoint = &mut self.checkpoint;
+        checkpoint.bump = bump;
+        checkpoint.epoch = epoch;
+        checkpoint.stats = stats;
+        checkpoint.records_root = records_root;
+        checkpoint.record_count = record_count;
+        checkpoint.publisher = self.governance.key();
+        checkpoint.published_at = now;
+
+        self.config.last_epoch = epoch;
+        self.config.last_stats = stats;
+
+        Ok(StatsCheckpointPublished {
+            checkpoint: self.checkpoint.key(),
+            epoch,
+            tasks_completed: stats.tasks_completed,
+            proofs_verified: stats.proofs_verified,
+            rewards_paid: stats.rewards_paid,
+            active_workers: stats.active_workers,
+            records_root,
+            timestamp: now,
+        })
+    }
+}
+
+#[event]
+pub struct StatsCheckpointPublished {
+    pub checkpoint: Pubkey,
+    pub epoch: u64,
+    pub tasks_completed: u64,
+    pub proofs_verified: u64,
+    pub rewards_paid: u64,
+    pub active_workers: u32,
+    pub records_root: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StatsGovernanceProposed {
+    pub governance: Pubkey,
+    pub pending: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StatsGovernanceTransferred {
+    pub previous: Pubkey,
+    pub governance: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,363 @@
+//! Sharded tasks: creation, per-shard claims and proofs, aggregated
+//! finalization and pull-based shard rewards
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::hash::hashv,
+    system_program::{self, Transfer},
+};
+use plonky3::{
+    field::{goldilocks_field::GoldilocksField, types::Field},
+    plonk::proof::Proof,
+    verifier::VerifierKey,
+};
+use crate::{
+    env,
+    error::HauntiError,
+    state::{
+        deposit_config::{DepositConfig, DepositError, ResourceRequirements},
+        sharded_task::{ShardStatus, ShardedTask, ShardedTaskError, MAX_SHARDS},
+        size_limits::SizeLimits,
+        verifier_registry::VerifierRegistry,
+        ModelParams,
+    },
+    zk::ProofVerificationCircuit,
+};
+
+#[derive(Accounts)]
+#[instruction(model: ModelParams, input_hash: [u8; 32], shard_count: u16)]
+pub struct CreateShardedTask<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = ShardedTask::space(shard_count),
+        seeds = [b"sharded_task", owner.key().as_ref(), input_hash.as_ref()],
+        bump
+    )]
+    pub sharded_task: Account<'info, ShardedTask>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(seeds = [b"deposit_config"], bump = deposit_config.bump)]
+    pub deposit_config: Account<'info, DepositConfig>,
+
+    #[account(seeds = [b"verifier_registry"], bump = verifier_registry.bump)]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateShardedTask<'info> {
+    /// Split a task into `shard_count` shards and escrow `reward` for all of
+    /// them; `requirements` describe a single shard
+    pub fn execute(
+        &mut self,
+        model: ModelParams,
+        input_hash: [u8; 32],
+        shard_count: u16,
+        reward: u64,
+        time_limit: u64,
+        requirements: ResourceRequirements,
+        bump: u8,
+    ) -> Result<()> {
+        require!(
+            (1..=MAX_SHARDS).contains(&shard_count),
+            ShardedTaskError::InvalidShardCount
+        );
+        let required = self
+            .deposit_config
+            .required_deposit(&requirements, time_limit)?
+            .checked_mul(shard_count as u64)
+            .ok_or(HauntiError::ArithmeticOverflow)?;
+        require!(reward >= required, DepositError::DepositTooLow);
+
+        let now = env::now()?;
+        let task = &mut self.sharded_task;
+        task.bump = bump;
+        task.owner = self.owner.key();
+        task.input_hash = input_hash;
+        task.model_hash = model.model_hash;
+        task.verifier_key = model.verifier_key;
+        task.verifier_version = self.verifier_registry.current;
+        task.reward = reward;
+        task.created_at = now;
+        task.deadline = now.saturating_add(time_limit as i64);
+        task.shards = vec![ShardStatus::Open; shard_count as usize];
+
+        system_program::transfer(
+            CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.owner.to_account_info(),
+                    to: self.sharded_task.to_account_info(),
+                },
+            ),
+            reward,
+        )?;
+
+        emit!(ShardedTaskCreated {
+            task: self.sharded_task.key(),
+            owner: self.owner.key(),
+            model_hash: self.sharded_task.model_hash,
+            shard_count,
+            reward,
+            deadline: self.sharded_task.deadline,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimShard<'info> {
+    #[account(
+        mut,
+        seeds = [b"sharded_task", sharded_task.owner.as_ref(), &sharded_task.input_hash],
+        bump = sharded_task.bump
+    )]
+    pub sharded_task: Account<'info, ShardedTask>,
+
+    pub worker: Signer<'info>,
+}
+
+impl<'info> ClaimShard<'info> {
+    /// Reserve an open shard, or one whose claim went stale
+    pub fn execute(&mut self, shard: u16) -> Result<()> {
+        let now = env::now()?;
+        self.sharded_task.claim(shard, self.worker.key(), now)?;
+
+        emit!(ShardClaimed {
+            task: self.sharded_task.key(),
+            shard,
+            worker: self.worker.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SubmitShardProof<'info> {
+    #[account(
+        mut,
+        seeds = [b"sharded_task", sharded_task.owner.as_ref(), &sharded_task.input_hash],
+        bump = sharded_task.bump,
+        has_one = verifier_key
+    )]
+    pub sharded_task: Account<'info, ShardedTask>,
+
+    pub worker: Signer<'info>,
+
+    #[account(constraint = verifier_key.validate()?)]
+    pub verifier_key: Account<'info, VerifierKey<GoldilocksField>>,
+
+    #[account(seeds = [b"size_limits"], bump = size_limits.bump)]
+    pub size_limits: Account<'info, SizeLimits>,
+
+    #[account(seeds = [b"verifier_registry"], bump = verifier_registry.bump)]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+}
+
+impl<'info> SubmitShardProof<'info> {
+    /// Verify and record the proof of a claimed shard. Only proofs that check
+    /// out are committed to, so the aggregated proof at finalization can always
+    /// be built over every proven shard; aggregators read the proof bytes back
+    /// from this instruction's data
+    pub fn execute(&mut self, shard: u16, proof: Vec<u8>, output_hash: [u8; 32]) -> Result<()> {
+        self.size_limits.check_proof(proof.len())?;
+        self.verifier_registry
+            .require_accepted(self.sharded_task.verifier_version)?;
+        let statement = self.sharded_task.shard_statement(shard, &output_hash)?;
+        let parsed = Proof::<GoldilocksField>::deserialize(&proof)
+            .map_err(|_| HauntiError::InvalidProofFormat)?;
+        ProofVerificationCircuit::verify(
+            &self.verifier_key,
+            &parsed,
+            &commitment_inputs(&statement),
+            &[],
+        )?;
+
+        let proof_hash = hashv(&[&proof, &output_hash]).to_bytes();
+        self.sharded_task.prove(shard, &self.worker.key(), proof_hash)?;
+
+        emit!(ShardProven {
+            task: self.sharded_task.key(),
+            shard,
+            worker: self.worker.key(),
+            proof_hash,
+            output_hash,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct FinalizeShardedTask<'info> {
+    #[account(
+        mut,
+        seeds = [b"sharded_task", sharded_task.owner.as_ref(), &sharded_task.input_hash],
+        bump = sharded_task.bump,
+        has_one = owner,
+        has_one = verifier_key
+    )]
+    pub sharded_task: Account<'info, ShardedTask>,
+
+    /// Receives the share of unproven shards
+    /// CHECK: validated against `sharded_task.owner`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(constraint = verifier_key.validate()?)]
+    pub verifier_key: Account<'info, VerifierKey<GoldilocksField>>,
+
+    #[account(seeds = [b"size_limits"], bump = size_limits.bump)]
+    pub size_limits: Account<'info, SizeLimits>,
+
+    #[account(seeds = [b"verifier_registry"], bump = verifier_registry.bump)]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+
+    /// Anyone holding the aggregated proof
+    pub aggregator: Signer<'info>,
+}
+
+impl<'info> FinalizeShardedTask<'info> {
+    /// Verify the aggregated proof over all proven shards and refund the owner
+    /// for the rest. Without any proven shard there is nothing to aggregate and
+    /// `proof` is ignored
+    pub fn execute(&mut self, proof: Vec<u8>) -> Result<()> {
+        let now = env::now()?;
+        let commitment = self.sharded_task.aggregate_commitment();
+        let proven = self.sharded_task.proven();
+        if proven > 0 {
+            self.size_limits.check_proof(proof.len())?;
+            self.verifier_registry
+                .require_accepted(self.sharded_task.verifier_version)?;
+            let proof = Proof::<GoldilocksField>::deserialize(&proof)
+                .map_err(|_| HauntiError::InvalidProofFormat)?;
+            ProofVerificationCircuit::verify(
+                &self.verifier_key,
+                &proof,
+                &commitment_inputs(&commitment),
+                &[],
+            )?;
+        }
+
+        let refund = self.sharded_task.finalize(now)?;
+        **self.sharded_task.to_account_info().try_borrow_mut_lamports()? -= refund;
+        **self.owner.to_account_info().try_borrow_mut_lamports()? += refund;
+
+        emit!(ShardedTaskFinalized {
+            task: self.sharded_task.key(),
+            aggregator: self.aggregator.key(),
+            commitment,
+            shards_proven: proven,
+            shard_count: self.sharded_task.shard_count(),
+            shard_reward: self.sharded_task.shard_reward(),
+            refund,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimShardReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"sharded_task", sharded_task.owner.as_ref(), &sharded_task.input_hash],
+        bump = sharded_task.bump
+    )]
+    pub sharded_task: Account<'info, ShardedTask>,
+
+    #[account(mut)]
+    pub worker: Signer<'info>,
+}
+
+impl<'info> ClaimShardReward<'info> {
+    /// Pay the worker of a proven shard its share once the task is finalized
+    pub fn execute(&mut self, shard: u16) -> Result<()> {
+        let amount = self.sharded_task.pay(shard, &self.worker.key())?;
+        **self.sharded_task.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **self.worker.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(ShardRewardPaid {
+            task: self.sharded_task.key(),
+            shard,
+            worker: self.worker.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+}
+
+/// Commitment or shard statement as public inputs: eight little-endian 32-bit limbs, each below
+/// the Goldilocks modulus
+fn commitment_inputs(commitment: &[u8; 32]) -> Vec<GoldilocksField> {
+    commitment
+        .chunks_exact(4)
+        .map(|limb| {
+            GoldilocksField::from_canonical_u64(
+                u32::from_le_bytes(limb.try_into().expect("4-byte chunk")) as u64,
+            )
+        })
+        .collect()
+}
+
+#[event]
+pub struct ShardedTaskCreated {
+    pub task: Pubkey,
+    pub owner: Pubkey,
+    pub model_hash: [u8; 32],
+    pub shard_count: u16,
+    /// Lamports escrowed for all shards
+    pub reward: u64,
+    pub deadline: i64,
+}
+
+#[event]
+pub struct ShardClaimed {
+    pub task: Pubkey,
+    pub shard: u16,
+    pub worker: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ShardProven {
+    pub task: Pubkey,
+    pub shard: u16,
+    pub worker: Pubkey,
+    /// SHA-256 of the shard proof and output hash
+    pub proof_hash: [u8; 32],
+    pub output_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ShardedTaskFinalized {
+    pub task: Pubkey,
+    pub aggregator: Pubkey,
+    /// Public input the aggregated proof was verified against
+    pub commitment: [u8; 32],
+    pub shards_proven: u16,
+    pub shard_count: u16,
+    /// Lamports each proven shard's worker may claim
+    pub shard_reward: u64,
+    /// Lamports returned to the owner for unproven shards
+    pub refund: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ShardRewardPaid {
+    pub task: Pubkey,
+    pub shard: u16,
+    pub worker: Pubkey,
+    pub amount: u64,
+}
@@ -0,0 +1,105 @@
+//! Instruction handlers for the governance-tunable payload size limits
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::limits::{
+    SizeLimitError, MAX_ENCRYPTED_INPUT_LEN, MAX_ENCRYPTED_OUTPUT_LEN, MAX_PROOF_LEN,
+};
+use crate::state::size_limits::SizeLimits;
+
+#[derive(Accounts)]
+pub struct InitSizeLimits<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = SizeLimits::LEN,
+        seeds = [b"size_limits"],
+        bump
+    )]
+    pub limits: Account<'info, SizeLimits>,
+
+    /// Governance authority that will own the limits
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// Upgrade authority; stops a squatter from seeding the limits first
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitSizeLimits<'info> {
+    /// Start at the protocol caps
+    pub fn execute(&mut self, bump: u8) -> Result<()> {
+        let now = env::now()?;
+        let limits = &mut self.limits;
+        limits.bump = bump;
+        limits.governance = self.governance.key();
+        limits.set(
+            MAX_PROOF_LEN as u32,
+            MAX_ENCRYPTED_INPUT_LEN as u32,
+            MAX_ENCRYPTED_OUTPUT_LEN as u32,
+        )?;
+        limits.updated_at = now;
+
+        emit!(SizeLimitsUpdated {
+            max_proof_len: limits.max_proof_len,
+            max_encrypted_input_len: limits.max_encrypted_input_len,
+            max_encrypted_output_len: limits.max_encrypted_output_len,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateSizeLimits<'info> {
+    #[account(
+        mut,
+        seeds = [b"size_limits"],
+        bump = limits.bump,
+        has_one = governance @ SizeLimitError::Unauthorized
+    )]
+    pub limits: Account<'info, SizeLimits>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> UpdateSizeLimits<'info> {
+    pub fn execute(
+        &mut self,
+        max_proof_len: u32,
+        max_encrypted_input_len: u32,
+        max_encrypted_output_len: u32,
+    ) -> Result<()> {
+        let now = env::now()?;
+        let limits = &mut self.limits;
+        limits.set(max_proof_len, max_encrypted_input_len, max_encrypted_output_len)?;
+        limits.updated_at = now;
+
+        emit!(SizeLimitsUpdated {
+            max_proof_len,
+            max_encrypted_input_len,
+            max_encrypted_output_len,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct SizeLimitsUpdated {
+    pub max_proof_len: u32,
+    pub max_encrypted_input_len: u32,
+    pub max_encrypted_output_len: u32,
+    pub timestamp: i64,
+}
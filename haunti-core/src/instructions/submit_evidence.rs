@@ -0,0 +1,233 @@
+//! Instruction handlers for attaching content-addressed evidence to disputes
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{entrypoint::MAX_PERMITTED_DATA_INCREASE, hash::hash, system_instruction},
+};
+use crate::env;
+use crate::state::dispute_state::{Dispute, DisputeError, DisputeStatus, EvidenceBlob};
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(content_hash: [u8; 32], inline_len: u32, cid: Option<String>)]
+pub struct CreateEvidence<'info> {
+    // Size limits are checked here, before `evidence` is allocated below
+    #[account(
+        mut,
+        constraint = dispute.status == DisputeStatus::Open @ DisputeError::DisputeNotOpen,
+        constraint = dispute.is_party(&submitter.key()) @ DisputeError::NotDisputeParty,
+        constraint = inline_len <= MAX_EVIDENCE_LEN @ DisputeError::EvidenceTooLarge,
+        constraint = cid.as_ref().map_or(true, |c| c.len() <= MAX_CID_LEN) @ DisputeError::CidTooLong
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    #[account(
+        init,
+        payer = submitter,
+        space = EvidenceBlob::space(inline_len, cid.as_ref().map_or(0, |c| c.len())),
+        seeds = [b"evidence", dispute.key().as_ref(), &dispute.evidence_count.to_le_bytes()],
+        bump
+    )]
+    pub evidence: Account<'info, EvidenceBlob>,
+
+    #[account(mut)]
+    pub submitter: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateEvidence<'info> {
+    /// Open an evidence blob and return its `EvidenceSubmitted` event
+    pub fn execute(
+        &mut self,
+        content_hash: [u8; 32],
+        inline_len: u32,
+        cid: Option<String>,
+        bump: u8,
+    ) -> Result<EvidenceSubmitted> {
+        require!(
+            self.dispute.evidence_count < MAX_EVIDENCE_PER_DISPUTE,
+            DisputeError::TooMuchEvidence
+        );
+
+        // Bond is held as extra lamports on the evidence account itself
+        self.lock_bond(EVIDENCE_BOND_LAMPORTS)?;
+
+        let evidence = &mut self.evidence;
+        evidence.bump = bump;
+        evidence.dispute = self.dispute.key();
+        evidence.submitter = self.submitter.key();
+        evidence.bond = EVIDENCE_BOND_LAMPORTS;
+        evidence.content_hash = content_hash;
+        evidence.declared_len = inline_len;
+        evidence.written_len = 0;
+        evidence.cid = cid;
+        // CID-only evidence is sealed immediately; the hash binds the off-chain content
+        evidence.sealed = inline_len == 0;
+        evidence.created_at = env::now()?;
+        evidence.data = Vec::with_capacity(inline_len as usize);
+
+        self.dispute.evidence_count += 1;
+
+        Ok(EvidenceSubmitted {
+            dispute: self.dispute.key(),
+            evidence: self.evidence.key(),
+            submitter: self.submitter.key(),
+            content_hash,
+            timestamp: self.evidence.created_at,
+        })
+    }
+
+    fn lock_bond(&self, amount: u64) -> Result<()> {
+        let transfer_ix = system_instruction::transfer(
+            &self.submitter.key(),
+            &self.evidence.key(),
+            amount,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                self.submitter.to_account_info(),
+                self.evidence.to_account_info(),
+                self.system_program.to_account_info(),
+            ],
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct WriteEvidenceChunk<'info> {
+    #[account(
+        constraint = dispute.status == DisputeStatus::Open @ DisputeError::DisputeNotOpen
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    #[account(
+        mut,
+        has_one = dispute,
+        has_one = submitter,
+        constraint = !evidence.sealed @ DisputeError::EvidenceSealed
+    )]
+    pub evidence: Account<'info, EvidenceBlob>,
+
+    pub submitter: Signer<'info>,
+}
+
+impl<'info> WriteEvidenceChunk<'info> {
+    pub fn execute(&mut self, offset: u32, chunk: Vec<u8>) -> Result<()> {
+        let evidence = &mut self.evidence;
+
+        // Chunks are appended strictly in order so retries are idempotent-safe
+        require_eq!(offset, evidence.written_len, DisputeError::ChunkOutOfOrder);
+        let end = offset
+            .checked_add(chunk.len() as u32)
+            .ok_or(DisputeError::EvidenceTooLarge)?;
+        require!(end <= evidence.declared_len, DisputeError::EvidenceTooLarge);
+
+        evidence.data.extend_from_slice(&chunk);
+        evidence.written_len = end;
+
+        if evidence.written_len == evidence.declared_len {
+            require!(
+                hash(&evidence.data).to_bytes() == evidence.content_hash,
+                DisputeError::ContentHashMismatch
+            );
+            evidence.sealed = true;
+
+            emit!(EvidenceSealed {
+                evidence: evidence.key(),
+                content_hash: evidence.content_hash,
+                timestamp: env::now()?,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseEvidence<'info> {
+    #[account(
+        constraint = dispute.status != DisputeStatus::Open @ DisputeError::DisputeNotResolved
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    #[account(
+        mut,
+        has_one = dispute,
+        has_one = submitter,
+        close = submitter
+    )]
+    pub evidence: Account<'info, EvidenceBlob>,
+
+    /// CHECK: receives rent and, if the submitter lost, nothing else
+    #[account(mut)]
+    pub submitter: UncheckedAccount<'info>,
+
+    /// CHECK: must be the dispute winner; receives forfeited bonds
+    #[account(
+        mut,
+        constraint = Some(winner.key()) == dispute.winner() @ DisputeError::NotDisputeParty
+    )]
+    pub winner: UncheckedAccount<'info>,
+}
+
+impl<'info> CloseEvidence<'info> {
+    /// Permissionless rent reclaim once the dispute is resolved
+    pub fn execute(&mut self) -> Result<()> {
+        let forfeited = self.dispute.winner() != Some(self.submitter.key());
+
+        // Losing submitters forfeit their bond to the winner; rent always returns
+        if forfeited {
+            let bond = self.evidence.bond;
+            **self.evidence.to_account_info().try_borrow_mut_lamports()? -= bond;
+            **self.winner.to_account_info().try_borrow_mut_lamports()? += bond;
+        }
+
+        emit!(EvidenceClosed {
+            dispute: self.dispute.key(),
+            evidence: self.evidence.key(),
+            bond_forfeited: forfeited,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct EvidenceSubmitted {
+    pub dispute: Pubkey,
+    pub evidence: Pubkey,
+    pub submitter: Pubkey,
+    pub content_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EvidenceSealed {
+    pub evidence: Pubkey,
+    pub content_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EvidenceClosed {
+    pub dispute: Pubkey,
+    pub evidence: Pubkey,
+    pub bond_forfeited: bool,
+    pub timestamp: i64,
+}
+
+// Constants
+const MAX_CID_LEN: usize = 96;
+/// Largest inline content whose account, header and longest CID included,
+/// still fits the 10,240 bytes a single `init` CPI may allocate
+const MAX_EVIDENCE_LEN: u32 =
+    (MAX_PERMITTED_DATA_INCREASE - EvidenceBlob::BASE_LEN - MAX_CID_LEN) as u32;
+const MAX_EVIDENCE_PER_DISPUTE: u16 = 16;
+const EVIDENCE_BOND_LAMPORTS: u64 = 10_000_000; // 0.01 SOL
@@ -2,22 +2,35 @@
 
 use anchor_lang::{
     prelude::*,
-    solana_program::{program::invoke, system_instruction},
+    solana_program::hash::hash,
 };
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer};
 use plonky3::{
-    field::goldilocks_field::GoldilocksField,
+    field::{goldilocks_field::GoldilocksField, types::Field},
     plonk::proof::Proof,
     verifier::VerifierKey,
 };
 use fhe_rs::prelude::*;
 use crate::{
+    env,
     error::HauntiError,
-    state::{TaskAccount, TaskState, ModelParams},
-    utils::{verify_merkle_path, decrypt_reward},
+    state::{
+        reward_escrow::{check_reward_account, reward_escrow_address, RewardEscrowError},
+        size_limits::SizeLimits,
+        task_feed::{EventCounter, TaskFeedKind},
+        task_state::{MeteredReward, RefundReason, TaskRefunded},
+        verifier_key_registry::{
+            VerifierKeyRegistry, VerifierKeyRegistryError, VERIFIER_KEY_REGISTRY_SEED,
+        },
+        verifier_registry::VerifierRegistry,
+        TaskAccount, TaskState, ModelParams,
+    },
+    utils::verify_merkle_path,
     zk::ProofVerificationCircuit,
     fhe::FHEOperator,
 };
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(proof: Vec<u8>, encrypted_output: Vec<u8>)]
 pub struct SubmitProof<'info> {
@@ -32,54 +45,124 @@ pub struct SubmitProof<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
 
+    /// Worker that produced the proof; receives the metered reward
+    #[account(mut)]
+    pub worker: Signer<'info>,
+
+    /// Must be the key governance registered for the model's circuit
     #[account(
-        address = task_account.model.verifier_key,
+        constraint = key_registry.accepts(&verifier_key.to_account_info())
+            @ VerifierKeyRegistryError::KeyMismatch,
         constraint = verifier_key.validate()?
     )]
     pub verifier_key: Account<'info, VerifierKey<GoldilocksField>>,
 
+    #[account(
+        seeds = [VERIFIER_KEY_REGISTRY_SEED, task_account.model.model_hash.as_ref()],
+        bump = key_registry.bump
+    )]
+    pub key_registry: Account<'info, VerifierKeyRegistry>,
+
+    #[account(seeds = [b"size_limits"], bump = size_limits.bump)]
+    pub size_limits: Account<'info, SizeLimits>,
+
+    #[account(seeds = [b"verifier_registry"], bump = verifier_registry.bump)]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    // Required when the task escrowed its reward in an SPL mint
+    #[account(mut)]
+    pub reward_escrow: Option<Account<'info, TokenAccount>>,
+
+    // Receives the unused part of a token escrow
+    #[account(mut)]
+    pub owner_reward_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub worker_reward_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
     #[account(address = system_program::ID)]
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> SubmitProof<'info> {
+    /// Verify and settle a proof; the `ProofSubmitted` event goes back to the handler
     pub fn execute(
         &mut self,
         proof: Vec<u8>,
         encrypted_output: Vec<u8>,
-    ) -> ProgramResult {
+        consumed_cu: u64,
+    ) -> Result<ProofSubmitted> {
+        // Reject oversized payloads before spending compute on decoding them
+        self.size_limits.check_proof(proof.len())?;
+        self.size_limits.check_output(encrypted_output.len())?;
+        // Proofs for a retired verifier's circuits can no longer be checked
+        self.verifier_registry
+            .require_accepted(self.task_account.verifier_version)?;
+
+        let proof_len = proof.len() as u32;
+        let output_hash = hash(&encrypted_output).to_bytes();
+
         // Deserialize proof
         let proof = Proof::<GoldilocksField>::deserialize(&proof)
             .map_err(|_| HauntiError::InvalidProofFormat)?;
 
-        // Step 1: Verify ZK Proof
-        self.verify_zk_proof(&proof)?;
+        // Step 1: Verify ZK Proof; it attests the compute units consumed
+        self.verify_zk_proof(&proof, consumed_cu)?;
 
         // Step 2: Encrypt and store result
         self.process_encrypted_output(encrypted_output)?;
 
         // Step 3: Update task state
         self.task_account.state = TaskState::Completed;
-        self.task_account.completed_at = Clock::get()?.unix_timestamp;
+        self.task_account.completed_at = env::now()?;
 
-        // Step 4: Distribute rewards
-        self.transfer_rewards()?;
+        // Step 4: Pay the worker for the compute used and refund the rest
+        let split = self.transfer_rewards(consumed_cu)?;
+        let task = self.task_account.key();
+        self.event_counter
+            .emit(task, TaskFeedKind::Completed, self.worker.key(), split.payout)?;
+        if split.refund > 0 {
+            emit!(TaskRefunded {
+                task,
+                owner: self.owner.key(),
+                amount: split.refund,
+                reason: RefundReason::UnusedCompute,
+                timestamp: self.task_account.completed_at,
+            });
+            self.event_counter
+                .emit(task, TaskFeedKind::Refunded, self.owner.key(), split.refund)?;
+        }
 
-        emit!(ProofSubmitted {
+        // Proof and output can run to kilobytes; the event carries their digests only
+        Ok(ProofSubmitted {
             task: self.task_account.key(),
             owner: self.owner.key(),
+            output_hash,
+            storage_root: self.task_account.storage_proof.unwrap_or_default(),
+            proof_len,
+            consumed_cu,
+            payout: split.payout,
             timestamp: self.task_account.completed_at,
-        });
-
-        Ok(())
+        })
     }
 
     fn verify_zk_proof(
         &self,
         proof: &Proof<GoldilocksField>,
+        consumed_cu: u64,
     ) -> Result<()> {
-        let public_inputs = self.task_account.model.get_public_inputs()?;
-        
+        let public_inputs = training_public_inputs(
+            self.task_account.model.get_public_inputs()?,
+            consumed_cu,
+            &self.task_account.rng_seed,
+        );
+
         ProofVerificationCircuit::verify(
             &self.verifier_key,
             proof,
@@ -116,37 +199,121 @@ impl<'info> SubmitProof<'info> {
         Ok(())
     }
 
-    fn transfer_rewards(&self) -> Result<()> {
-        let reward = decrypt_reward(
-            &self.task_account.encrypted_reward,
-            &self.owner.key(),
-        )?;
+    /// Split the escrow by metered usage: lamports above the task's rent
+    /// floor, or the token escrow balance
+    fn transfer_rewards(&mut self, consumed_cu: u64) -> Result<MeteredReward> {
+        if let Some(mint) = self.task_account.reward_mint {
+            return self.transfer_token_rewards(mint, consumed_cu);
+        }
 
-        let reward_lamports = reward
-            .checked_div(LAMPORTS_PER_SOL)
-            .ok_or(HauntiError::ArithmeticOverflow)?;
-
-        invoke(
-            &system_instruction::transfer(
-                &self.task_account.key(),
-                &self.owner.key(),
-                reward_lamports,
-            ),
-            &[
-                self.task_account.to_account_info(),
-                self.owner.to_account_info(),
-                self.system_program.to_account_info(),
-            ],
-        )?;
+        let task = self.task_account.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(task.data_len());
+        let escrow = task.lamports().saturating_sub(rent_floor);
+        let split = self.task_account.meter(consumed_cu, escrow)?;
 
-        Ok(())
+        // The task account is program-owned, so lamports move directly
+        **task.try_borrow_mut_lamports()? -= escrow;
+        **self.worker.to_account_info().try_borrow_mut_lamports()? += split.payout;
+        **self.owner.to_account_info().try_borrow_mut_lamports()? += split.refund;
+
+        Ok(split)
     }
+
+    /// Release the token escrow to the worker and owner, then close it and
+    /// return its rent
+    fn transfer_token_rewards(&mut self, mint: Pubkey, consumed_cu: u64) -> Result<MeteredReward> {
+        let (Some(escrow), Some(worker_account), Some(owner_account), Some(token_program)) = (
+            &self.reward_escrow,
+            &self.worker_reward_account,
+            &self.owner_reward_account,
+            &self.token_program,
+        ) else {
+            return err!(RewardEscrowError::MissingRewardAccounts);
+        };
+        let task = self.task_account.key();
+        require_keys_eq!(
+            escrow.key(),
+            reward_escrow_address(&task, &mint),
+            RewardEscrowError::InvalidEscrow
+        );
+        check_reward_account(worker_account, &mint, &self.worker.key())?;
+        check_reward_account(owner_account, &mint, &self.owner.key())?;
+        let split = self.task_account.meter(consumed_cu, escrow.amount)?;
+
+        let owner = self.task_account.owner;
+        let model_hash = self.task_account.model.model_hash;
+        let (_, bump) = Pubkey::find_program_address(
+            &[b"task", owner.as_ref(), model_hash.as_ref()],
+            &crate::ID,
+        );
+        let seeds: &[&[u8]] = &[b"task", owner.as_ref(), model_hash.as_ref(), &[bump]];
+
+        for (recipient, amount) in [(worker_account, split.payout), (owner_account, split.refund)] {
+            if amount == 0 {
+                continue;
+            }
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: escrow.to_account_info(),
+                        to: recipient.to_account_info(),
+                        authority: self.task_account.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                amount,
+            )?;
+        }
+
+        token::close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: escrow.to_account_info(),
+                destination: self.owner.to_account_info(),
+                authority: self.task_account.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+
+        Ok(split)
+    }
+}
+
+/// Public inputs of a training proof: the model's own, then the compute units
+/// the circuit metered as two 32-bit limbs and the task's RNG seed as eight, each
+/// little end first. Binding the seed means a run with dropout or sample order
+/// drawn from any other seed does not verify
+fn training_public_inputs(
+    mut inputs: Vec<GoldilocksField>,
+    consumed_cu: u64,
+    rng_seed: &[u8; 32],
+) -> Vec<GoldilocksField> {
+    let cu_limbs = [consumed_cu as u32, (consumed_cu >> 32) as u32];
+    let seed_limbs = rng_seed
+        .chunks_exact(4)
+        .map(|limb| u32::from_le_bytes(limb.try_into().expect("4-byte chunk")));
+    inputs.extend(
+        cu_limbs
+            .into_iter()
+            .chain(seed_limbs)
+            .map(|limb| GoldilocksField::from_canonical_u64(limb as u64)),
+    );
+    inputs
 }
 
 #[event]
 pub struct ProofSubmitted {
     pub task: Pubkey,
     pub owner: Pubkey,
+    /// SHA-256 of the encrypted output
+    pub output_hash: [u8; 32],
+    pub storage_root: [u8; 32],
+    pub proof_len: u32,
+    /// Compute units the proof attests were used
+    pub consumed_cu: u64,
+    /// Reward paid to the worker for them
+    pub payout: u64,
     pub timestamp: i64,
 }
 
@@ -179,3 +346,52 @@ impl ProofVerificationCircuit<GoldilocksField> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use plonky3::{
+        iop::witness::{PartialWitness, WitnessWrite},
+        plonk::{
+            circuit_builder::CircuitBuilder,
+            circuit_data::{CircuitConfig, CircuitData},
+            config::PoseidonGoldilocksConfig,
+        },
+    };
+
+    type F = GoldilocksField;
+    type C = PoseidonGoldilocksConfig;
+
+    /// Stand-in for the training circuit: exposes `inputs` as public inputs
+    fn prove_statement(inputs: &[F]) -> (CircuitData<F, C, 2>, Proof<F>) {
+        let mut builder = CircuitBuilder::<F, 2>::new(CircuitConfig::standard_recursion_config());
+        let targets: Vec<_> = inputs.iter().map(|_| builder.add_virtual_public_input()).collect();
+        let circuit = builder.build::<C>();
+
+        let mut witness = PartialWitness::new();
+        for (target, value) in targets.into_iter().zip(inputs) {
+            witness.set_target(target, *value);
+        }
+        let proof = circuit.prove(witness).unwrap().proof;
+        (circuit, proof)
+    }
+
+    #[test]
+    fn test_proof_with_wrong_seed_rejected() {
+        let model_inputs = vec![F::from_canonical_u64(7), F::from_canonical_u64(11)];
+        let (task_seed, other_seed) = ([3u8; 32], [4u8; 32]);
+        let expected = training_public_inputs(model_inputs.clone(), 5_000, &task_seed);
+        assert_eq!(expected.len(), model_inputs.len() + 2 + 8);
+        assert_eq!(expected[4], F::from_canonical_u64(0x0303_0303));
+
+        // A run over the task's seed verifies
+        let (circuit, proof) = prove_statement(&expected);
+        let key = VerifierKey::from_circuit(&circuit);
+        assert!(ProofVerificationCircuit::verify(&key, &proof, &expected, &[]).is_ok());
+
+        // The same circuit proven over another seed does not
+        let shopped = training_public_inputs(model_inputs, 5_000, &other_seed);
+        let (_, shopped_proof) = prove_statement(&shopped);
+        assert!(ProofVerificationCircuit::verify(&key, &shopped_proof, &expected, &[]).is_err());
+    }
+}
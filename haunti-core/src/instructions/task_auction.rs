@@ -0,0 +1,253 @@
+//! Instruction handlers for task auctions: opening, bidding and acceptance
+
+use anchor_lang::{
+    prelude::*,
+    system_program::{self, Transfer},
+};
+use crate::env;
+use crate::state::{
+    task_auction::{AuctionError, Bid, TaskAuction, MAX_BIDDING_SECS},
+    task_feed::{EventCounter, TaskFeedKind},
+    task_state::{
+        RefundReason, TaskRefunded, TaskState, TaskStatusChanged, TaskStatusKind,
+    },
+};
+
+#[derive(Accounts)]
+pub struct OpenTaskAuction<'info> {
+    #[account(
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump,
+        has_one = owner
+    )]
+    pub task: Account<'info, TaskState>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = TaskAuction::LEN,
+        seeds = [b"auction", task.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, TaskAuction>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenTaskAuction<'info> {
+    /// Take bids up to `max_price` lamports for `bidding_secs`
+    pub fn execute(&mut self, max_price: u64, bidding_secs: i64, bump: u8) -> Result<()> {
+        // Token rewards are a fixed escrow; only lamport prices can be re-set
+        require!(
+            self.task.status.kind() == TaskStatusKind::Pending && self.task.reward_mint.is_none(),
+            AuctionError::TaskNotAuctionable
+        );
+        require!(
+            (1..=MAX_BIDDING_SECS).contains(&bidding_secs) && max_price > 0,
+            AuctionError::InvalidBiddingWindow
+        );
+
+        let now = env::now()?;
+        let auction = &mut self.auction;
+        auction.bump = bump;
+        auction.task = self.task.key();
+        auction.owner = self.owner.key();
+        auction.max_price = max_price;
+        auction.opened_at = now;
+        auction.closes_at = now.saturating_add(bidding_secs);
+
+        emit!(TaskAuctionOpened {
+            task: auction.task,
+            max_price,
+            closes_at: auction.closes_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump,
+        constraint = task.status.kind() == TaskStatusKind::Pending @ AuctionError::TaskNotAuctionable
+    )]
+    pub task: Account<'info, TaskState>,
+
+    #[account(
+        mut,
+        seeds = [b"auction", task.key().as_ref()],
+        bump = auction.bump,
+        has_one = task
+    )]
+    pub auction: Account<'info, TaskAuction>,
+
+    pub worker: Signer<'info>,
+}
+
+impl<'info> PlaceBid<'info> {
+    /// Offer to run the task for `price` lamports, finishing by `deadline`
+    pub fn execute(&mut self, price: u64, deadline: Option<i64>) -> Result<()> {
+        let now = env::now()?;
+        self.auction.place(Bid {
+            worker: self.worker.key(),
+            price,
+            deadline,
+            placed_at: now,
+        })?;
+
+        emit!(BidPlaced {
+            task: self.task.key(),
+            worker: self.worker.key(),
+            price,
+            deadline,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AcceptBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task.owner.as_ref(), &task.input_hash],
+        bump = task.bump,
+        has_one = owner
+    )]
+    pub task: Account<'info, TaskState>,
+
+    #[account(
+        mut,
+        seeds = [b"auction", task.key().as_ref()],
+        bump = auction.bump,
+        has_one = task,
+        has_one = owner,
+        close = owner
+    )]
+    pub auction: Account<'info, TaskAuction>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Sequences the task feed
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AcceptBid<'info> {
+    /// Award the task to `worker` at its bid price: the escrow is settled to
+    /// that price and the worker's promised deadline becomes the time limit
+    pub fn execute(&mut self, worker: Pubkey) -> Result<()> {
+        let now = env::now()?;
+        let bid = self.auction.acceptable(&worker, now)?;
+
+        let task_info = self.task.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(task_info.data_len());
+        let escrow = task_info.lamports().saturating_sub(rent_floor);
+        let (top_up, refund) = if bid.price > escrow {
+            (bid.price - escrow, 0)
+        } else {
+            (0, escrow - bid.price)
+        };
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.owner.to_account_info(),
+                        to: task_info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        if refund > 0 {
+            **task_info.try_borrow_mut_lamports()? -= refund;
+            **self.owner.to_account_info().try_borrow_mut_lamports()? += refund;
+        }
+
+        let old_status = self.task.status.clone();
+        if let Some(deadline) = bid.deadline {
+            self.task
+                .set_time_limit(deadline.saturating_sub(self.task.created_at) as u64)?;
+        }
+        self.task.start(worker)?;
+
+        emit!(TaskStatusChanged {
+            task: self.task.key(),
+            old_status,
+            new_status: self.task.status.clone(),
+            version: self.task.version,
+            timestamp: now,
+        });
+        emit!(BidAccepted {
+            task: self.task.key(),
+            worker,
+            price: bid.price,
+            deadline: bid.deadline,
+            bids: self.auction.bids.len() as u8,
+            top_up,
+            refund,
+            timestamp: now,
+        });
+        let task = self.task.key();
+        self.event_counter.emit(task, TaskFeedKind::Claimed, worker, 0)?;
+        if refund > 0 {
+            emit!(TaskRefunded {
+                task,
+                owner: self.owner.key(),
+                amount: refund,
+                reason: RefundReason::AuctionSettled,
+                timestamp: now,
+            });
+            self.event_counter
+                .emit(task, TaskFeedKind::Refunded, self.owner.key(), refund)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct TaskAuctionOpened {
+    pub task: Pubkey,
+    pub max_price: u64,
+    pub closes_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub task: Pubkey,
+    pub worker: Pubkey,
+    pub price: u64,
+    pub deadline: Option<i64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BidAccepted {
+    pub task: Pubkey,
+    pub worker: Pubkey,
+    /// Reward the task now escrows
+    pub price: u64,
+    pub deadline: Option<i64>,
+    /// Bids standing when the auction closed
+    pub bids: u8,
+    /// Lamports added to the escrow by the owner
+    pub top_up: u64,
+    /// Lamports returned to the owner from the escrow
+    pub refund: u64,
+    pub timestamp: i64,
+}
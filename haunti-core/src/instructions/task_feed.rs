@@ -0,0 +1,30 @@
+//! Instruction handler for creating the task feed's event counter
+
+use anchor_lang::prelude::*;
+use crate::state::task_feed::EventCounter;
+
+#[derive(Accounts)]
+pub struct InitEventCounter<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = EventCounter::LEN,
+        seeds = [b"event_counter"],
+        bump
+    )]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitEventCounter<'info> {
+    /// Create the counter once; anyone may pay for it
+    pub fn execute(&mut self, bump: u8) -> Result<()> {
+        self.event_counter.bump = bump;
+        Ok(())
+    }
+}
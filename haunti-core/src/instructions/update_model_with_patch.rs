@@ -0,0 +1,37 @@
+//! Instruction handler for incremental model updates via weight-diff patches
+
+use anchor_lang::prelude::*;
+use crate::state::model_state::ModelState;
+
+#[derive(Accounts)]
+#[instruction(base_root: [u8; 32], new_root: [u8; 32], patch_cid: String)]
+pub struct UpdateModelWithPatch<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        realloc = model.space() + 4 + patch_cid.len(),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub model: Account<'info, ModelState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpdateModelWithPatch<'info> {
+    /// Record `patch_cid` as the diff from `base_root` to `new_root`
+    pub fn execute(
+        &mut self,
+        base_root: [u8; 32],
+        new_root: [u8; 32],
+        patch_cid: String,
+        signature: Vec<u8>,
+    ) -> Result<()> {
+        self.model
+            .update_model_with_patch(base_root, new_root, patch_cid, &signature)
+    }
+}
@@ -0,0 +1,79 @@
+//! Instruction handler for installing governance-approved verifying keys
+
+use anchor_lang::prelude::*;
+use token_vault::{PoolState, PoolType, Proposal};
+use crate::env;
+use crate::state::verifier_key_registry::{
+    VerifierKeyRegistry, VerifierKeyRegistryError, VERIFIER_KEY_REGISTRY_SEED,
+};
+
+/// Permissionless crank: anyone may apply an executed rotation proposal
+#[derive(Accounts)]
+#[instruction(circuit_id: [u8; 32])]
+pub struct RotateVerifierKey<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = VerifierKeyRegistry::LEN,
+        seeds = [VERIFIER_KEY_REGISTRY_SEED, circuit_id.as_ref()],
+        bump
+    )]
+    pub registry: Account<'info, VerifierKeyRegistry>,
+
+    /// Executed `VerifierKeyRotation` proposal
+    #[account(
+        constraint = proposal.pool == governance_pool.key()
+            @ VerifierKeyRegistryError::WrongGovernancePool
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// Governance pool whose stakers vote on key rotations
+    #[account(
+        constraint = governance_pool.pool_type == PoolType::Governance
+            @ VerifierKeyRegistryError::WrongGovernancePool
+    )]
+    pub governance_pool: Account<'info, PoolState>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RotateVerifierKey<'info> {
+    /// Make the proposal's key active for `circuit_id`; the first rotation
+    /// creates the registry
+    pub fn execute(&mut self, circuit_id: [u8; 32], bump: u8) -> Result<()> {
+        let now = env::now()?;
+        let registry = &mut self.registry;
+        if registry.version == 0 {
+            registry.bump = bump;
+            registry.circuit_id = circuit_id;
+        }
+        let previous_version = registry.version;
+        registry.rotate(&self.proposal, self.proposal.key())?;
+        registry.updated_at = now;
+
+        emit!(VerifierKeyRotated {
+            circuit_id,
+            vk_hash: registry.vk_hash,
+            version: registry.version,
+            previous_version,
+            proposal: registry.proposal,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct VerifierKeyRotated {
+    pub circuit_id: [u8; 32],
+    pub vk_hash: [u8; 32],
+    pub version: u32,
+    pub previous_version: u32,
+    pub proposal: Pubkey,
+    pub timestamp: i64,
+}
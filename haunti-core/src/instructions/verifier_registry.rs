@@ -0,0 +1,109 @@
+//! Instruction handlers for the verifier version registry and upgrade cutovers
+
+use anchor_lang::prelude::*;
+use crate::env;
+use crate::state::verifier_registry::{VerifierRegistry, VerifierRegistryError};
+
+#[derive(Accounts)]
+pub struct InitVerifierRegistry<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = VerifierRegistry::LEN,
+        seeds = [b"verifier_registry"],
+        bump
+    )]
+    pub registry: Account<'info, VerifierRegistry>,
+
+    /// Governance authority that will own the registry
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// Registry creation is reserved to the upgrade authority
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitVerifierRegistry<'info> {
+    /// Start with `program_id` as the only, current version
+    pub fn execute(&mut self, version: u16, program_id: Pubkey, bump: u8) -> Result<()> {
+        let registry = &mut self.registry;
+        registry.bump = bump;
+        registry.governance = self.governance.key();
+        registry.register(version, program_id)?;
+        registry.current = version;
+        registry.previous = version;
+        registry.cutover_end_slot = 0;
+        registry.updated_at = env::now()?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateVerifierRegistry<'info> {
+    #[account(
+        mut,
+        seeds = [b"verifier_registry"],
+        bump = registry.bump,
+        has_one = governance @ VerifierRegistryError::Unauthorized
+    )]
+    pub registry: Account<'info, VerifierRegistry>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> UpdateVerifierRegistry<'info> {
+    /// Register a newly deployed verifier program without routing to it yet
+    pub fn register_version(&mut self, version: u16, program_id: Pubkey) -> Result<()> {
+        self.registry.register(version, program_id)?;
+        self.emit_updated()
+    }
+
+    /// Tag new tasks with `version`, accepting the replaced version for
+    /// `window_slots` more slots
+    pub fn begin_cutover(&mut self, version: u16, window_slots: u64) -> Result<()> {
+        let slot = env::slot()?;
+        self.registry.begin_cutover(version, window_slots, slot)?;
+        self.emit_updated()
+    }
+
+    /// Stop accepting the replaced version before the window runs out
+    pub fn end_cutover(&mut self) -> Result<()> {
+        let slot = env::slot()?;
+        self.registry.end_cutover(slot);
+        self.emit_updated()
+    }
+
+    fn emit_updated(&mut self) -> Result<()> {
+        let now = env::now()?;
+        let registry = &mut self.registry;
+        registry.updated_at = now;
+
+        emit!(VerifierRegistryUpdated {
+            current: registry.current,
+            current_program: registry.program_for(registry.current).unwrap_or_default(),
+            previous: registry.previous,
+            cutover_end_slot: registry.cutover_end_slot,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct VerifierRegistryUpdated {
+    pub current: u16,
+    pub current_program: Pubkey,
+    pub previous: u16,
+    pub cutover_end_slot: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,195 @@
+//! Instruction handlers for worker bonds and the reassignment policy
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+use crate::env;
+use crate::state::worker_bond::{
+    ReassignConfig, WorkerBond, WorkerBondError, WORKER_UNBONDING_SECS,
+};
+
+#[derive(Accounts)]
+pub struct InitReassignConfig<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = ReassignConfig::LEN,
+        seeds = [b"reassign_config"],
+        bump
+    )]
+    pub config: Account<'info, ReassignConfig>,
+
+    /// Governance authority that will own the policy
+    pub governance: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::HauntiCore>,
+
+    /// Upgrade authority, so the reassignment policy cannot be squatted
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitReassignConfig<'info> {
+    pub fn execute(&mut self, bump: u8, stale_after_secs: i64, slash_bps: u16) -> Result<()> {
+        let now = env::now()?;
+        let config = &mut self.config;
+        config.bump = bump;
+        config.governance = self.governance.key();
+        config.set(stale_after_secs, slash_bps)?;
+        config.updated_at = now;
+
+        emit!(ReassignConfigUpdated {
+            stale_after_secs,
+            slash_bps,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateReassignConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"reassign_config"],
+        bump = config.bump,
+        has_one = governance @ WorkerBondError::Unauthorized
+    )]
+    pub config: Account<'info, ReassignConfig>,
+
+    pub governance: Signer<'info>,
+}
+
+impl<'info> UpdateReassignConfig<'info> {
+    pub fn execute(&mut self, stale_after_secs: i64, slash_bps: u16) -> Result<()> {
+        let now = env::now()?;
+        let config = &mut self.config;
+        config.set(stale_after_secs, slash_bps)?;
+        config.updated_at = now;
+
+        emit!(ReassignConfigUpdated {
+            stale_after_secs,
+            slash_bps,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct PostWorkerBond<'info> {
+    #[account(
+        init_if_needed,
+        payer = worker,
+        space = WorkerBond::LEN,
+        seeds = [b"worker_bond", worker.key().as_ref()],
+        bump
+    )]
+    pub bond: Account<'info, WorkerBond>,
+
+    #[account(mut)]
+    pub worker: Signer<'info>,
+
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PostWorkerBond<'info> {
+    /// Add `amount` lamports to the worker's bond; topping up cancels unbonding
+    pub fn execute(&mut self, amount: u64, bump: u8) -> Result<()> {
+        require!(amount > 0, WorkerBondError::InvalidAmount);
+        anchor_lang::solana_program::program::invoke(
+            &system_instruction::transfer(&self.worker.key(), &self.bond.key(), amount),
+            &[
+                self.worker.to_account_info(),
+                self.bond.to_account_info(),
+                self.system_program.to_account_info(),
+            ],
+        )?;
+
+        let bond = &mut self.bond;
+        bond.bump = bump;
+        bond.worker = self.worker.key();
+        bond.amount = bond.amount.saturating_add(amount);
+        bond.unlock_at = 0;
+
+        emit!(WorkerBondPosted {
+            worker: bond.worker,
+            amount,
+            total: bond.amount,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExitWorkerBond<'info> {
+    #[account(
+        mut,
+        seeds = [b"worker_bond", worker.key().as_ref()],
+        bump = bond.bump,
+        has_one = worker
+    )]
+    pub bond: Account<'info, WorkerBond>,
+
+    #[account(mut)]
+    pub worker: Signer<'info>,
+}
+
+impl<'info> ExitWorkerBond<'info> {
+    /// Start unbonding; the bond stays slashable until it is withdrawn
+    pub fn unbond(&mut self) -> Result<()> {
+        let now = env::now()?;
+        self.bond.unlock_at = now + WORKER_UNBONDING_SECS;
+
+        emit!(WorkerUnbonding {
+            worker: self.bond.worker,
+            unlock_at: self.bond.unlock_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Close the bond account after unbonding, returning bond and rent
+    pub fn withdraw(&mut self) -> Result<()> {
+        let unlock_at = self.bond.unlock_at;
+        require!(
+            unlock_at != 0 && env::now()? >= unlock_at,
+            WorkerBondError::StillUnbonding
+        );
+        self.bond.close(self.worker.to_account_info())
+    }
+}
+
+#[event]
+pub struct ReassignConfigUpdated {
+    pub stale_after_secs: i64,
+    pub slash_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WorkerBondPosted {
+    pub worker: Pubkey,
+    pub amount: u64,
+    pub total: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WorkerUnbonding {
+    pub worker: Pubkey,
+    pub unlock_at: i64,
+    pub timestamp: i64,
+}
@@ -14,9 +14,17 @@ use anchor_lang::prelude::*;
 use solana_program::entrypoint;
 
 mod compute;
+#[cfg(not(target_os = "solana"))]
+pub mod conformance;
 mod encryption;
+#[path = "../../programs/token-vault/src/env.rs"]
+pub mod env;
 mod errors;
+pub mod events;
+#[cfg(feature = "test-harness")]
+pub mod harness;
 mod instructions;
+pub mod limits;
 mod state;
 mod zkml;
 
@@ -24,7 +32,35 @@ mod zkml;
 pub use compute::GPUComputation;
 pub use encryption::FHEOperator;
 pub use errors::HauntiError;
+pub use instructions::aggregated_completion::{
+    CompleteAggregatedTasks, VERIFIER_AUTHORITY_SEED,
+};
+pub use instructions::cached_result::{
+    CreateCachedInferenceTask, SetModelNondeterministic, SubmitCachedResult,
+};
+pub use instructions::cancel_task::CancelTaskAccount;
+pub use instructions::expire_task::ExpireTaskAccount;
+pub use instructions::migrate_task::MigrateTask;
+pub use instructions::optimistic_verification::{ChallengeResult, InitOptimisticConfig};
+pub use instructions::payment_bridge::RedeemBridgedPayment;
+pub use instructions::task_auction::OpenTaskAuction;
+pub use instructions::verifier_key_registry::RotateVerifierKey;
+pub use instructions::{
+    create_inference_task::{CreateInferenceTask, InitModelPricing},
+    create_task::CreateTask,
+    publish_stats_checkpoint::PublishStatsCheckpoint, submit_evidence::CreateEvidence,
+    submit_proof::SubmitProof,
+};
+pub use state::{
+    deposit_config::ResourceRequirements, inference_quote::InferenceQuote,
+    stats_checkpoint::NetworkStats,
+};
+pub use events::{decode_cpi_event, CoreEvent};
 pub use state::{ModelParams, TaskAccount};
+pub use state::task_feed::{EventCounter, TaskFeedEvent, TaskFeedKind};
+pub use state::verifier_key_registry::{VerifierKeyRegistry, VERIFIER_KEY_REGISTRY_SEED};
+#[cfg(not(target_os = "solana"))]
+pub use state::task_feed::{FeedCursor, FeedGap};
 pub use zkml::{ZKProof, ZKVerifier};
 
 declare_id!("HAUNTiCore1111111111111111111111111111111111111");
@@ -72,6 +108,218 @@ pub mod haunti_core {
         Ok(())
     }
 
+    /// Complete a batch of tasks whose proofs a verifier program checked as one
+    /// aggregated proof; remaining accounts are the task PDAs, then the pricing
+    /// accounts of the priced ones
+    pub fn complete_aggregated_tasks<'info>(
+        ctx: Context<'_, '_, '_, 'info, CompleteAggregatedTasks<'info>>,
+        result_hashes: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.execute(ctx.remaining_accounts, &result_hashes)
+    }
+
+    /// Grow a task account created under an older `TaskState` layout
+    pub fn migrate_task(ctx: Context<MigrateTask>) -> Result<()> {
+        ctx.accounts.execute()
+    }
+
+    /// Install the verifying key of `circuit_id` named by an executed
+    /// token-vault `VerifierKeyRotation` proposal
+    pub fn rotate_verifier_key(
+        ctx: Context<RotateVerifierKey>,
+        circuit_id: [u8; 32],
+    ) -> Result<()> {
+        let bump = *ctx.bumps.get("registry").unwrap();
+        ctx.accounts.execute(circuit_id, bump)
+    }
+
+    /// Create a training task; `TaskCreated` goes out as a self-CPI event
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_task(
+        ctx: Context<CreateTask>,
+        model: ModelParams,
+        reward: u64,
+        time_limit: u64,
+        requirements: ResourceRequirements,
+        encrypted_data: Option<Vec<u8>>,
+        priority_fee: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        let event = ctx.accounts.execute(
+            model,
+            reward,
+            time_limit,
+            requirements,
+            encrypted_data,
+            priority_fee,
+            deadline,
+        )?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    /// Submit a proof for a running task, paying the worker for the metered compute
+    pub fn submit_proof(
+        ctx: Context<SubmitProof>,
+        proof: Vec<u8>,
+        encrypted_output: Vec<u8>,
+        consumed_cu: u64,
+    ) -> Result<()> {
+        let event = ctx.accounts.execute(proof, encrypted_output, consumed_cu)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    /// Cancel a training task no proof has settled and refund its escrow to the owner
+    pub fn cancel_task_account(ctx: Context<CancelTaskAccount>) -> Result<()> {
+        let bump = *ctx.bumps.get("task_account").unwrap();
+        ctx.accounts.execute(bump)
+    }
+
+    /// Refund a training task past its deadline, paying the caller a keeper reward
+    pub fn expire_task_account(ctx: Context<ExpireTaskAccount>) -> Result<()> {
+        let bump = *ctx.bumps.get("task_account").unwrap();
+        ctx.accounts.execute(bump)
+    }
+
+    /// Open demand pricing for a model the caller owns
+    pub fn init_model_pricing(
+        ctx: Context<InitModelPricing>,
+        initial_fee: u64,
+        min_base_fee: u64,
+        target_queue_depth: u32,
+    ) -> Result<()> {
+        let bump = *ctx.bumps.get("pricing").unwrap();
+        ctx.accounts
+            .execute(initial_fee, min_base_fee, target_queue_depth, bump)
+    }
+
+    /// Create an inference task at the model's current demand price
+    pub fn create_inference_task(
+        ctx: Context<CreateInferenceTask>,
+        input_hash: [u8; 32],
+        input_schema_hash: [u8; 32],
+        max_fee: u64,
+        surge_fee: u64,
+        quote: Option<InferenceQuote>,
+    ) -> Result<()> {
+        let bump = *ctx.bumps.get("task").unwrap();
+        let event = ctx
+            .accounts
+            .execute(input_hash, input_schema_hash, max_fee, surge_fee, quote, bump)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    /// Opt a model out of inference result deduplication, or back in
+    pub fn set_model_nondeterministic(
+        ctx: Context<SetModelNondeterministic>,
+        nondeterministic: bool,
+    ) -> Result<()> {
+        ctx.accounts.execute(nondeterministic)
+    }
+
+    /// Create an inference task served from another task's verified result
+    pub fn create_cached_inference_task(
+        ctx: Context<CreateCachedInferenceTask>,
+        input_hash: [u8; 32],
+        max_fee: u64,
+    ) -> Result<()> {
+        let bump = *ctx.bumps.get("task").unwrap();
+        ctx.accounts.execute(input_hash, max_fee, bump)
+    }
+
+    /// Complete a cached inference task with its source's result
+    pub fn submit_cached_result(ctx: Context<SubmitCachedResult>) -> Result<()> {
+        ctx.accounts.execute()
+    }
+
+    /// Attach evidence to an open dispute
+    pub fn create_evidence(
+        ctx: Context<CreateEvidence>,
+        content_hash: [u8; 32],
+        inline_len: u32,
+        cid: Option<String>,
+    ) -> Result<()> {
+        let bump = *ctx.bumps.get("evidence").unwrap();
+        let event = ctx.accounts.execute(content_hash, inline_len, cid, bump)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    /// Publish the network statistics checkpoint of the next epoch
+    pub fn publish_stats_checkpoint(
+        ctx: Context<PublishStatsCheckpoint>,
+        epoch: u64,
+        stats: NetworkStats,
+        records_root: [u8; 32],
+        record_count: u64,
+    ) -> Result<()> {
+        let bump = *ctx.bumps.get("checkpoint").unwrap();
+        let event = ctx
+            .accounts
+            .execute(epoch, stats, records_root, record_count, bump)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    /// Credit a prepaid balance from a Wormhole-attested EVM payment; anyone
+    /// may relay it
+    pub fn redeem_bridged_payment(
+        ctx: Context<RedeemBridgedPayment>,
+        emitter_chain: u16,
+        emitter_address: [u8; 32],
+        sequence: u64,
+    ) -> Result<()> {
+        let redeemed_bump = *ctx.bumps.get("redeemed").unwrap();
+        let prepaid_bump = *ctx.bumps.get("prepaid").unwrap();
+        ctx.accounts.execute(
+            emitter_chain,
+            emitter_address,
+            sequence,
+            redeemed_bump,
+            prepaid_bump,
+        )
+    }
+
+    /// Open bidding on a pending task, capped at `max_price` lamports
+    pub fn open_task_auction(
+        ctx: Context<OpenTaskAuction>,
+        max_price: u64,
+        bidding_secs: i64,
+    ) -> Result<()> {
+        let bump = *ctx.bumps.get("auction").unwrap();
+        ctx.accounts.execute(max_price, bidding_secs, bump)
+    }
+
+    /// Set up the optimistic verification policy; upgrade authority only
+    pub fn init_optimistic_config(
+        ctx: Context<InitOptimisticConfig>,
+        stake_pool: Pubkey,
+        arbitrator: Pubkey,
+        challenge_window_secs: i64,
+        challenger_bond: u64,
+        min_challenger_stake: u64,
+        worker_slash_bps: u16,
+    ) -> Result<()> {
+        let bump = *ctx.bumps.get("config").unwrap();
+        ctx.accounts.execute(
+            bump,
+            stake_pool,
+            arbitrator,
+            challenge_window_secs,
+            challenger_bond,
+            min_challenger_stake,
+            worker_slash_bps,
+        )
+    }
+
+    /// Challenge a provisional result with a counter-proof
+    pub fn challenge_result(ctx: Context<ChallengeResult>, counter_proof: Vec<u8>) -> Result<()> {
+        let bump = *ctx.bumps.get("dispute").unwrap();
+        ctx.accounts.execute(counter_proof, bump)
+    }
+
     // Additional handlers for:
     // - Task cancellation
     // - Reward distribution
@@ -0,0 +1,82 @@
+//! Protocol-wide size caps for proofs, public inputs and encrypted payloads
+//!
+//! Every program that accepts one of these payloads checks it against the same
+//! constants, so a proof accepted by `submit_proof` is never rejected by the
+//! verifier for size, and a client can size its payloads once. Governance may
+//! tighten the per-deployment limits in the `SizeLimits` account, never raise
+//! them past the caps here.
+//!
+//! | Payload                          | Cap        | Checked by                       |
+//! |----------------------------------|------------|----------------------------------|
+//! | ZK proof                         | 128 KiB    | `submit_proof`, `verify_ai_proof`|
+//! | Public inputs (32 bytes each)    | 64 inputs  | `verify_ai_proof`                |
+//! | Encrypted task input             | 10 KiB     | `create_task`                    |
+//! | Encrypted task output            | 10 KiB     | `submit_proof`                   |
+//! | Cross-chain message payload      | 1 KiB      | `send_message`                   |
+//!
+//! Lengths are inclusive: a payload of exactly the cap is accepted.
+
+use anchor_lang::prelude::*;
+
+/// Maximum serialized ZK proof length
+pub const MAX_PROOF_LEN: usize = 128 * 1024;
+/// Maximum number of 32-byte public inputs per proof
+pub const MAX_PUBLIC_INPUTS: usize = 64;
+/// Maximum encrypted task input; the 10 KiB CPI/realloc ceiling
+pub const MAX_ENCRYPTED_INPUT_LEN: usize = 10 * 1024;
+/// Maximum encrypted task output
+pub const MAX_ENCRYPTED_OUTPUT_LEN: usize = 10 * 1024;
+/// Maximum cross-chain message payload
+pub const MAX_MESSAGE_PAYLOAD_LEN: usize = 1024;
+
+/// Fail with `error` unless `len` is within `limit`
+pub fn check_len(len: usize, limit: usize, error: SizeLimitError) -> Result<()> {
+    require!(len <= limit, error);
+    Ok(())
+}
+
+#[error_code]
+pub enum SizeLimitError {
+    #[msg("Proof exceeds the maximum proof size")]
+    ProofTooLarge,
+    #[msg("Too many public inputs")]
+    TooManyPublicInputs,
+    #[msg("Encrypted input exceeds the maximum input size")]
+    InputTooLarge,
+    #[msg("Encrypted output exceeds the maximum output size")]
+    OutputTooLarge,
+    #[msg("Size limits must be non-zero and within the protocol caps")]
+    InvalidLimits,
+    #[msg("Signer is not the size-limits governance")]
+    Unauthorized,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_are_inclusive() {
+        for limit in [
+            MAX_PROOF_LEN,
+            MAX_PUBLIC_INPUTS,
+            MAX_ENCRYPTED_INPUT_LEN,
+            MAX_ENCRYPTED_OUTPUT_LEN,
+            MAX_MESSAGE_PAYLOAD_LEN,
+        ] {
+            assert!(check_len(limit, limit, SizeLimitError::ProofTooLarge).is_ok());
+            assert!(check_len(limit + 1, limit, SizeLimitError::ProofTooLarge).is_err());
+        }
+    }
+
+    #[test]
+    fn test_reports_typed_error() {
+        let err = check_len(
+            MAX_ENCRYPTED_INPUT_LEN + 1,
+            MAX_ENCRYPTED_INPUT_LEN,
+            SizeLimitError::InputTooLarge,
+        )
+        .unwrap_err();
+        assert_eq!(err, SizeLimitError::InputTooLarge.into());
+    }
+}
@@ -0,0 +1,313 @@
+//! Content-policy registry and per-model moderation state
+//!
+//! Governance publishes the hash of the marketplace content policy together with
+//! an enforcement authority. Stakers flag models that breach it; once the flagged
+//! stake crosses the policy threshold the model is frozen for new inference tasks
+//! until the enforcement authority either takes it down or reinstates it. Owners
+//! may appeal a takedown once, to governance, within the appeal window.
+
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Marketplace content policy (singleton PDA)
+#[account]
+#[derive(Default)]
+pub struct ContentPolicy {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Authority that publishes the policy and hears appeals
+    pub governance: Pubkey,
+    /// Authority that reviews frozen models
+    pub enforcement_authority: Pubkey,
+    /// SHA-256 of the published policy document
+    pub policy_hash: [u8; 32],
+    /// Bumped whenever the policy document changes
+    pub policy_version: u32,
+    /// token-vault pool whose stake weighs flags
+    pub stake_pool: Pubkey,
+    /// Flagged stake that freezes a model
+    pub flag_threshold: u64,
+    /// Seconds after a takedown during which the owner may appeal
+    pub appeal_window: i64,
+    /// Last update unix timestamp
+    pub updated_at: i64,
+}
+
+impl ContentPolicy {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        32 + // enforcement_authority
+        32 + // policy_hash
+        4 +  // policy_version
+        32 + // stake_pool
+        8 +  // flag_threshold
+        8 +  // appeal_window
+        8;   // updated_at
+
+    /// Whether `signer` may decide on frozen models
+    pub fn can_enforce(&self, signer: &Pubkey) -> bool {
+        self.enforcement_authority == *signer || self.governance == *signer
+    }
+}
+
+/// Moderation lifecycle of a model
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModerationStatus {
+    /// Accepting tasks; flags accumulate
+    Clear,
+    /// Flag threshold reached, awaiting enforcement review
+    Frozen,
+    /// Removed for breaching the policy
+    TakenDown,
+    /// Takedown under appeal to governance
+    Appealed,
+}
+
+impl Default for ModerationStatus {
+    fn default() -> Self {
+        Self::Clear
+    }
+}
+
+impl ModerationStatus {
+    /// Whether new inference tasks may be created
+    pub fn accepts_tasks(self) -> bool {
+        self == Self::Clear
+    }
+}
+
+/// Moderation record of a single model (PDA-based, created by its first flag)
+#[account]
+#[derive(Default)]
+pub struct ModelModeration {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Moderated model
+    pub model: Pubkey,
+    /// Current status
+    pub status: ModerationStatus,
+    /// Review round; flags only count toward the round they were cast in
+    pub round: u32,
+    /// Stake flagged in the current round
+    pub flag_weight: u64,
+    /// Flags cast in the current round
+    pub flag_count: u32,
+    /// Policy version the current round is judged against
+    pub policy_version: u32,
+    /// Hash of the enforcement decision, once taken down
+    pub decision_hash: [u8; 32],
+    /// Takedown unix timestamp
+    pub taken_down_at: i64,
+    /// Hash of the owner's appeal statement
+    pub appeal_hash: Option<[u8; 32]>,
+    /// Last status change unix timestamp
+    pub updated_at: i64,
+}
+
+impl ModelModeration {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // model
+        1 +  // status
+        4 +  // round
+        8 +  // flag_weight
+        4 +  // flag_count
+        4 +  // policy_version
+        32 + // decision_hash
+        8 +  // taken_down_at
+        33 + // appeal_hash
+        8;   // updated_at
+
+    /// Add a flag of `weight`; returns true if it froze the model
+    pub fn add_flag(&mut self, weight: u64, threshold: u64, now: i64) -> Result<bool> {
+        require!(
+            self.status == ModerationStatus::Clear,
+            ModerationError::InvalidModerationState
+        );
+        require!(weight > 0, ModerationError::NoFlagStake);
+
+        self.flag_weight = self
+            .flag_weight
+            .checked_add(weight)
+            .ok_or(ModerationError::NoFlagStake)?;
+        self.flag_count += 1;
+
+        if self.flag_weight < threshold {
+            return Ok(false);
+        }
+        self.status = ModerationStatus::Frozen;
+        self.updated_at = now;
+        Ok(true)
+    }
+
+    /// Enforcement decision on a frozen model
+    pub fn take_down(&mut self, decision_hash: [u8; 32], now: i64) -> Result<()> {
+        require!(
+            self.status == ModerationStatus::Frozen,
+            ModerationError::InvalidModerationState
+        );
+        self.status = ModerationStatus::TakenDown;
+        self.decision_hash = decision_hash;
+        self.taken_down_at = now;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Clear a frozen model, or one whose takedown was overturned on appeal.
+    /// Flags from the finished round no longer count.
+    pub fn reinstate(&mut self, now: i64) -> Result<()> {
+        require!(
+            matches!(self.status, ModerationStatus::Frozen | ModerationStatus::Appealed),
+            ModerationError::InvalidModerationState
+        );
+        self.status = ModerationStatus::Clear;
+        self.round += 1;
+        self.flag_weight = 0;
+        self.flag_count = 0;
+        self.decision_hash = [0; 32];
+        self.taken_down_at = 0;
+        self.appeal_hash = None;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Owner appeal against a takedown; allowed once per round
+    pub fn appeal(&mut self, appeal_hash: [u8; 32], appeal_window: i64, now: i64) -> Result<()> {
+        require!(
+            self.status == ModerationStatus::TakenDown,
+            ModerationError::InvalidModerationState
+        );
+        require!(self.appeal_hash.is_none(), ModerationError::AlreadyAppealed);
+        require!(
+            now <= self.taken_down_at.saturating_add(appeal_window),
+            ModerationError::AppealWindowClosed
+        );
+        self.status = ModerationStatus::Appealed;
+        self.appeal_hash = Some(appeal_hash);
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Governance ruling on an appeal; upholding makes the takedown final
+    pub fn resolve_appeal(&mut self, upheld: bool, now: i64) -> Result<()> {
+        require!(
+            self.status == ModerationStatus::Appealed,
+            ModerationError::InvalidModerationState
+        );
+        if upheld {
+            self.status = ModerationStatus::TakenDown;
+            self.updated_at = now;
+            Ok(())
+        } else {
+            self.reinstate(now)
+        }
+    }
+}
+
+/// A staker's flag against a model for one review round (PDA-based)
+#[account]
+#[derive(Default)]
+pub struct ModelFlag {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Flagged model
+    pub model: Pubkey,
+    /// Flagging staker
+    pub flagger: Pubkey,
+    /// Review round the flag was cast in
+    pub round: u32,
+    /// Stake counted for the flag
+    pub weight: u64,
+    /// Hash of the flagger's report
+    pub reason_hash: [u8; 32],
+    /// Creation unix timestamp
+    pub created_at: i64,
+}
+
+impl ModelFlag {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // model
+        32 + // flagger
+        4 +  // round
+        8 +  // weight
+        32 + // reason_hash
+        8;   // created_at
+}
+
+/// Fail unless the model behind `moderation` accepts new tasks. The account is
+/// empty until the model is first flagged, which counts as clear.
+pub fn ensure_accepting_tasks(moderation: &AccountInfo<'_>) -> Result<()> {
+    if moderation.data_is_empty() {
+        return Ok(());
+    }
+    let moderation = Account::<ModelModeration>::try_from(moderation)?;
+    require!(
+        moderation.status.accepts_tasks(),
+        ModerationError::ModelFrozen
+    );
+    Ok(())
+}
+
+/// Flags are weighed by the power checkpointed this long before the flag, so
+/// stake cannot be moved between wallets to flag repeatedly
+pub const MIN_FLAG_STAKE_AGE: i64 = 86_400;
+
+#[error_code]
+pub enum ModerationError {
+    #[msg("Signer is not the policy authority")]
+    Unauthorized,
+    #[msg("Invalid content policy parameters")]
+    InvalidPolicy,
+    #[msg("Model is frozen or taken down under the content policy")]
+    ModelFrozen,
+    #[msg("Invalid moderation state for operation")]
+    InvalidModerationState,
+    #[msg("Flagger has no eligible stake")]
+    NoFlagStake,
+    #[msg("Takedown already appealed")]
+    AlreadyAppealed,
+    #[msg("Appeal window has closed")]
+    AppealWindowClosed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_freeze_then_appeal_reopens_round() {
+        let mut moderation = ModelModeration::default();
+        assert!(!moderation.add_flag(40, 100, 1).unwrap());
+        assert!(moderation.add_flag(60, 100, 2).unwrap());
+        assert!(!moderation.status.accepts_tasks());
+        // Frozen models take no further flags
+        assert!(moderation.add_flag(10, 100, 3).is_err());
+
+        moderation.take_down([1; 32], 10).unwrap();
+        assert!(moderation.appeal([2; 32], 5, 20).is_err());
+        moderation.appeal([2; 32], 5, 15).unwrap();
+        moderation.resolve_appeal(false, 30).unwrap();
+
+        assert!(moderation.status.accepts_tasks());
+        assert_eq!((moderation.round, moderation.flag_weight), (1, 0));
+        assert!(moderation.appeal_hash.is_none());
+    }
+
+    #[test]
+    fn test_upheld_takedown_is_final() {
+        let mut moderation = ModelModeration::default();
+        moderation.add_flag(100, 100, 1).unwrap();
+        moderation.take_down([1; 32], 2).unwrap();
+        moderation.appeal([2; 32], 100, 3).unwrap();
+        moderation.resolve_appeal(true, 4).unwrap();
+
+        assert_eq!(moderation.status, ModerationStatus::TakenDown);
+        assert!(moderation.appeal([3; 32], 100, 5).is_err());
+        assert!(moderation.reinstate(6).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Governance-tunable task deposit schedule (singleton PDA)
+//!
+//! The deposit a task escrows must cover the GPU time it reserves, so a large job
+//! cannot be posted with a dust deposit to tie up workers. Off-chain quotes use the
+//! same formula, see `DepositConfig::required_deposit`.
+
+use anchor_lang::prelude::*;
+
+/// Number of GPU tiers priced by the schedule
+pub const GPU_TIER_COUNT: usize = 3;
+/// Upper bound on GPUs a single task may reserve
+pub const MAX_GPUS_PER_TASK: u8 = 16;
+
+/// GPU class a task asks for; indexes `DepositConfig::tier_rates`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpuTier {
+    /// Consumer cards (e.g. RTX 4090)
+    Consumer,
+    /// Datacenter cards (e.g. A100 40GB)
+    Datacenter,
+    /// High-memory datacenter cards (e.g. H100 80GB)
+    HighMemory,
+}
+
+/// Resources a task declares at creation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceRequirements {
+    /// Requested GPU class
+    pub gpu_tier: GpuTier,
+    /// GPUs reserved for the whole time limit
+    pub gpu_count: u8,
+}
+
+/// Deposit schedule enforced by `create_task`
+#[account]
+#[derive(Default)]
+pub struct DepositConfig {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Authority allowed to change the schedule
+    pub governance: Pubkey,
+    /// Floor for every task regardless of size (lamports)
+    pub min_deposit: u64,
+    /// Lamports per GPU-hour, by `GpuTier`
+    pub tier_rates: [u64; GPU_TIER_COUNT],
+    /// Last update unix timestamp
+    pub updated_at: i64,
+}
+
+impl DepositConfig {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        8 +  // min_deposit
+        8 * GPU_TIER_COUNT + // tier_rates
+        8;   // updated_at
+
+    /// Replace the schedule; the floor must be non-zero
+    pub fn set(&mut self, min_deposit: u64, tier_rates: [u64; GPU_TIER_COUNT]) -> Result<()> {
+        require!(min_deposit > 0, DepositError::InvalidSchedule);
+        self.min_deposit = min_deposit;
+        self.tier_rates = tier_rates;
+        Ok(())
+    }
+
+    /// Deposit required for `requirements` held for `timeout_secs`:
+    /// `max(min_deposit, ceil(gpu_count * timeout_secs * tier_rate / 3600))`
+    pub fn required_deposit(
+        &self,
+        requirements: &ResourceRequirements,
+        timeout_secs: u64,
+    ) -> Result<u64> {
+        require!(
+            requirements.gpu_count > 0 && requirements.gpu_count <= MAX_GPUS_PER_TASK,
+            DepositError::InvalidResources
+        );
+        let rate = self.tier_rates[requirements.gpu_tier as usize] as u128;
+        let gpu_secs = requirements.gpu_count as u128 * timeout_secs as u128;
+        let scaled = u64::try_from((gpu_secs * rate).div_ceil(SECS_PER_HOUR))
+            .map_err(|_| DepositError::DepositOverflow)?;
+        Ok(scaled.max(self.min_deposit))
+    }
+}
+
+#[error_code]
+pub enum DepositError {
+    #[msg("Deposit below the amount required for the declared resources")]
+    DepositTooLow,
+    #[msg("GPU count must be between 1 and MAX_GPUS_PER_TASK")]
+    InvalidResources,
+    #[msg("Invalid deposit schedule")]
+    InvalidSchedule,
+    #[msg("Required deposit overflows")]
+    DepositOverflow,
+    #[msg("Unauthorized deposit schedule update")]
+    Unauthorized,
+}
+
+const SECS_PER_HOUR: u128 = 3_600;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> DepositConfig {
+        let mut config = DepositConfig::default();
+        config.set(100_000, [1_000_000, 5_000_000, 20_000_000]).unwrap();
+        config
+    }
+
+    fn resources(gpu_tier: GpuTier, gpu_count: u8) -> ResourceRequirements {
+        ResourceRequirements { gpu_tier, gpu_count }
+    }
+
+    #[test]
+    fn test_deposit_scales_with_gpus_time_and_tier() {
+        let config = config();
+        // 8 H100s for 2h: 16 GPU-hours at 20M
+        assert_eq!(
+            config.required_deposit(&resources(GpuTier::HighMemory, 8), 7_200).unwrap(),
+            320_000_000
+        );
+        // Partial GPU-hours round up
+        assert_eq!(
+            config.required_deposit(&resources(GpuTier::Datacenter, 1), 1_801).unwrap(),
+            2_501_389
+        );
+        // Small jobs still pay the floor
+        assert_eq!(
+            config.required_deposit(&resources(GpuTier::Consumer, 1), 300).unwrap(),
+            100_000
+        );
+    }
+
+    #[test]
+    fn test_gpu_count_bounds() {
+        let config = config();
+        assert!(config.required_deposit(&resources(GpuTier::Consumer, 0), 300).is_err());
+        assert!(config
+            .required_deposit(&resources(GpuTier::Consumer, MAX_GPUS_PER_TASK + 1), 300)
+            .is_err());
+        assert!(DepositConfig::default().set(0, [0; GPU_TIER_COUNT]).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Dispute and evidence accounts for challenged task results
+
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Dispute lifecycle states
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisputeStatus {
+    /// Evidence may still be submitted
+    Open,
+    /// Result upheld, worker keeps the reward
+    ResolvedForWorker,
+    /// Result overturned in favour of the challenger
+    ResolvedForChallenger,
+}
+
+impl Default for DisputeStatus {
+    fn default() -> Self {
+        Self::Open
+    }
+}
+
+/// Dispute raised against a submitted task result (PDA of `[b"dispute", task]`)
+#[account]
+#[derive(Default)]
+pub struct Dispute {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Disputed task account
+    pub task: Pubkey,
+    /// Party challenging the result
+    pub challenger: Pubkey,
+    /// Worker that produced the result
+    pub worker: Pubkey,
+    /// Current status
+    pub status: DisputeStatus,
+    /// Dispute opening unix timestamp
+    pub opened_at: i64,
+    /// Number of evidence blobs attached so far
+    pub evidence_count: u16,
+    /// Challenger's bond held in the account's lamports; lost if the result
+    /// is upheld
+    pub bond: u64,
+    /// SHA-256 of the counter-proof the challenge was opened with
+    pub counter_proof_hash: [u8; 32],
+    /// Resolution unix timestamp (zero while open)
+    pub resolved_at: i64,
+}
+
+impl Dispute {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // task
+        32 + // challenger
+        32 + // worker
+        1 +  // status
+        8 +  // opened_at
+        2 +  // evidence_count
+        8 +  // bond
+        32 + // counter_proof_hash
+        8;   // resolved_at
+
+    /// Whether `party` is one of the two sides of this dispute
+    pub fn is_party(&self, party: &Pubkey) -> bool {
+        self.challenger == *party || self.worker == *party
+    }
+
+    /// Rule on an open dispute
+    pub fn resolve(&mut self, uphold_result: bool, now: i64) -> Result<()> {
+        require!(self.status == DisputeStatus::Open, DisputeError::DisputeNotOpen);
+        self.status = if uphold_result {
+            DisputeStatus::ResolvedForWorker
+        } else {
+            DisputeStatus::ResolvedForChallenger
+        };
+        self.resolved_at = now;
+        Ok(())
+    }
+
+    /// Party whose position prevailed, once resolved
+    pub fn winner(&self) -> Option<Pubkey> {
+        match self.status {
+            DisputeStatus::Open => None,
+            DisputeStatus::ResolvedForWorker => Some(self.worker),
+            DisputeStatus::ResolvedForChallenger => Some(self.challenger),
+        }
+    }
+}
+
+/// Content-addressed evidence attached to a dispute
+#[account]
+#[derive(Default)]
+pub struct EvidenceBlob {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Parent dispute
+    pub dispute: Pubkey,
+    /// Evidence submitter (one of the dispute parties)
+    pub submitter: Pubkey,
+    /// Anti-spam bond held in the account's lamports
+    pub bond: u64,
+    /// SHA-256 of the full evidence content
+    pub content_hash: [u8; 32],
+    /// Total length declared at creation
+    pub declared_len: u32,
+    /// Bytes written so far
+    pub written_len: u32,
+    /// Off-chain location when content is not stored inline
+    pub cid: Option<String>,
+    /// Set once the written content matches `content_hash`
+    pub sealed: bool,
+    /// Creation unix timestamp
+    pub created_at: i64,
+    /// Inline content, written in chunks
+    pub data: Vec<u8>,
+}
+
+impl EvidenceBlob {
+    /// Account space before inline content and CID
+    pub const BASE_LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // dispute
+        32 + // submitter
+        8 +  // bond
+        32 + // content_hash
+        4 +  // declared_len
+        4 +  // written_len
+        1 + 4 + // cid (option + string prefix)
+        1 +  // sealed
+        8 +  // created_at
+        4;   // data (vec prefix)
+
+    /// Account space for a blob with the given inline length and CID
+    pub fn space(inline_len: u32, cid_len: usize) -> usize {
+        Self::BASE_LEN + inline_len as usize + cid_len
+    }
+}
+
+#[error_code]
+pub enum DisputeError {
+    #[msg("Dispute is not open")]
+    DisputeNotOpen,
+    #[msg("Dispute is not resolved")]
+    DisputeNotResolved,
+    #[msg("Signer is not a party to the dispute")]
+    NotDisputeParty,
+    #[msg("Evidence exceeds maximum size")]
+    EvidenceTooLarge,
+    #[msg("Evidence chunk out of order")]
+    ChunkOutOfOrder,
+    #[msg("Evidence content hash mismatch")]
+    ContentHashMismatch,
+    #[msg("Evidence already sealed")]
+    EvidenceSealed,
+    #[msg("Too many evidence blobs for this dispute")]
+    TooMuchEvidence,
+    #[msg("Evidence CID too long")]
+    CidTooLong,
+}
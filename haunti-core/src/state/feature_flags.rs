@@ -0,0 +1,144 @@
+//! Network-wide feature flags (singleton PDA)
+//!
+//! Each flag is a bit in `enabled` plus the slot it takes effect from, so
+//! governance can schedule a rollout ahead of time and every program and
+//! coordinator switches over at the same slot. Flags are addressed by index so
+//! off-chain components can read flags newer than the program they run against.
+
+use anchor_lang::prelude::*;
+use crate::env;
+
+/// Number of flags the account can hold
+pub const MAX_FEATURES: usize = 64;
+
+/// Indices of the flags in use
+pub mod feature {
+    /// Verify proofs with the next-generation proof backend
+    pub const PROOF_BACKEND_V2: u8 = 0;
+    /// Assign tasks with the capacity-aware scheduler policy
+    pub const SCHEDULER_POLICY_V2: u8 = 1;
+}
+
+/// Feature flags checked by programs and polled by coordinators
+#[account]
+pub struct FeatureFlags {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Authority allowed to change the flags
+    pub governance: Pubkey,
+    /// Bit `i` set when flag `i` is scheduled or active
+    pub enabled: u64,
+    /// Slot from which each enabled flag is active
+    pub activation_slots: [u64; MAX_FEATURES],
+    /// Last update unix timestamp
+    pub updated_at: i64,
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self {
+            bump: 0,
+            governance: Pubkey::default(),
+            enabled: 0,
+            activation_slots: [0; MAX_FEATURES],
+            updated_at: 0,
+        }
+    }
+}
+
+impl FeatureFlags {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        8 +  // enabled
+        8 * MAX_FEATURES + // activation_slots
+        8;   // updated_at
+
+    /// Whether `feature` is active at `slot`
+    pub fn is_active(&self, feature: u8, slot: u64) -> bool {
+        (feature as usize) < MAX_FEATURES
+            && self.enabled & (1 << feature) != 0
+            && slot >= self.activation_slots[feature as usize]
+    }
+
+    /// Fail unless `feature` is active at the current slot
+    pub fn require_active(&self, feature: u8) -> Result<()> {
+        require!(
+            self.is_active(feature, env::slot()?),
+            FeatureFlagError::FeatureInactive
+        );
+        Ok(())
+    }
+
+    /// Schedule `feature` from `activation_slot`, or disable it with `None`.
+    /// Activation cannot be backdated, so no past slot changes meaning.
+    pub fn set(
+        &mut self,
+        feature: u8,
+        activation_slot: Option<u64>,
+        current_slot: u64,
+    ) -> Result<()> {
+        require!((feature as usize) < MAX_FEATURES, FeatureFlagError::UnknownFeature);
+        match activation_slot {
+            Some(slot) => {
+                require!(slot >= current_slot, FeatureFlagError::ActivationInPast);
+                // Rescheduling an already active flag would switch it back off
+                require!(
+                    !self.is_active(feature, current_slot),
+                    FeatureFlagError::AlreadyActive
+                );
+                self.enabled |= 1 << feature;
+                self.activation_slots[feature as usize] = slot;
+            }
+            None => {
+                self.enabled &= !(1 << feature);
+                self.activation_slots[feature as usize] = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[error_code]
+pub enum FeatureFlagError {
+    #[msg("Feature is not active")]
+    FeatureInactive,
+    #[msg("Feature index out of range")]
+    UnknownFeature,
+    #[msg("Activation slot is in the past")]
+    ActivationInPast,
+    #[msg("Feature is already active; disable it first")]
+    AlreadyActive,
+    #[msg("Unauthorized feature flag update")]
+    Unauthorized,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flag_activates_at_scheduled_slot() {
+        let mut flags = FeatureFlags::default();
+        flags.set(feature::PROOF_BACKEND_V2, Some(1_000), 10).unwrap();
+
+        assert!(!flags.is_active(feature::PROOF_BACKEND_V2, 999));
+        assert!(flags.is_active(feature::PROOF_BACKEND_V2, 1_000));
+        assert!(!flags.is_active(feature::SCHEDULER_POLICY_V2, 1_000));
+
+        // Active flags cannot be pushed back, only disabled
+        assert!(flags.set(feature::PROOF_BACKEND_V2, Some(2_000), 1_500).is_err());
+        flags.set(feature::PROOF_BACKEND_V2, None, 1_500).unwrap();
+        assert!(!flags.is_active(feature::PROOF_BACKEND_V2, 1_500));
+    }
+
+    #[test]
+    fn test_invalid_schedules_rejected() {
+        let mut flags = FeatureFlags::default();
+        assert!(flags.set(feature::SCHEDULER_POLICY_V2, Some(5), 10).is_err());
+        assert!(flags.set(MAX_FEATURES as u8, Some(10), 10).is_err());
+        assert!(!flags.is_active(MAX_FEATURES as u8, u64::MAX));
+        assert_eq!(flags.enabled, 0);
+    }
+}
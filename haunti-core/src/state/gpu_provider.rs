@@ -0,0 +1,175 @@
+//! Registry of GPU providers (workers) and their reputation
+//!
+//! A provider registers once per wallet (PDA of `[b"gpu_provider", authority]`)
+//! with the hash of its hardware attestation, the token-vault pool it stakes in
+//! and the model types it can run. Its reputation moves with the outcome of
+//! each proof it delivers: confirmed results raise it a little, overturned ones
+//! cut it sharply. `claim_task` only hands work to active providers that still
+//! hold the minimum stake and have not fallen below the reputation floor.
+
+use anchor_lang::prelude::*;
+
+use super::ModelType;
+
+/// Reputation scale, in basis points
+pub const MAX_REPUTATION: u16 = 10_000;
+/// Reputation a newly registered provider starts with
+pub const INITIAL_REPUTATION: u16 = 5_000;
+/// Providers below this may not claim tasks
+pub const MIN_CLAIM_REPUTATION: u16 = 2_000;
+/// Staked tokens a provider must hold in its pool to register and claim
+pub const MIN_PROVIDER_STAKE: u64 = 1_000_000_000;
+
+/// A registered GPU provider
+#[account]
+#[derive(Default)]
+pub struct GpuProvider {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Wallet the provider works and is paid as
+    pub authority: Pubkey,
+    /// SHA-256 of the provider's hardware attestation report
+    pub attestation_hash: [u8; 32],
+    /// Token-vault pool holding the provider's stake
+    pub stake_pool: Pubkey,
+    /// Bit `n` set when the provider runs `ModelType` with discriminant `n`
+    pub model_types: u32,
+    /// Deactivated providers keep their record but take no new tasks
+    pub active: bool,
+    /// Proof track record, in basis points of `MAX_REPUTATION`
+    pub reputation: u16,
+    pub proofs_succeeded: u64,
+    pub proofs_failed: u64,
+    /// Registration unix timestamp
+    pub registered_at: i64,
+    /// Last update unix timestamp
+    pub updated_at: i64,
+}
+
+impl GpuProvider {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // authority
+        32 + // attestation_hash
+        32 + // stake_pool
+        4 +  // model_types
+        1 +  // active
+        2 +  // reputation
+        8 +  // proofs_succeeded
+        8 +  // proofs_failed
+        8 +  // registered_at
+        8;   // updated_at
+
+    /// Replace the provider's hardware and model declaration
+    pub fn set_profile(&mut self, attestation_hash: [u8; 32], model_types: u32) -> Result<()> {
+        require!(attestation_hash != [0; 32], GpuProviderError::InvalidAttestation);
+        require!(model_types != 0, GpuProviderError::NoModelTypes);
+        self.attestation_hash = attestation_hash;
+        self.model_types = model_types;
+        Ok(())
+    }
+
+    /// Whether the provider may be assigned work
+    pub fn is_approved(&self) -> bool {
+        self.active && self.reputation >= MIN_CLAIM_REPUTATION
+    }
+
+    pub fn supports_model_type(&self, model_type: ModelType) -> bool {
+        let bit = model_type as u8 as u32;
+        bit < 32 && self.model_types & (1 << bit) != 0
+    }
+
+    /// Move reputation 5% of the way to the maximum on a confirmed proof, and
+    /// drop it by 20% on a failed one
+    pub fn record_proof(&mut self, succeeded: bool) {
+        if succeeded {
+            self.proofs_succeeded += 1;
+            self.reputation += (MAX_REPUTATION - self.reputation) / 20;
+        } else {
+            self.proofs_failed += 1;
+            self.reputation -= self.reputation / 5;
+        }
+    }
+
+    /// Check the provider can claim a task backed by `staked` tokens
+    pub fn check_claim(&self, staked: u64) -> Result<()> {
+        require!(self.active, GpuProviderError::ProviderInactive);
+        require!(
+            self.reputation >= MIN_CLAIM_REPUTATION,
+            GpuProviderError::ReputationTooLow
+        );
+        require!(staked >= MIN_PROVIDER_STAKE, GpuProviderError::InsufficientStake);
+        Ok(())
+    }
+}
+
+#[error_code]
+pub enum GpuProviderError {
+    #[msg("Attestation hash must be non-zero")]
+    InvalidAttestation,
+    #[msg("Provider must support at least one model type")]
+    NoModelTypes,
+    #[msg("Provider stake is below the minimum")]
+    InsufficientStake,
+    #[msg("Provider is deactivated")]
+    ProviderInactive,
+    #[msg("Provider reputation is below the claim floor")]
+    ReputationTooLow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider() -> GpuProvider {
+        GpuProvider {
+            active: true,
+            reputation: INITIAL_REPUTATION,
+            model_types: 0b101,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_failures_outweigh_successes() {
+        let mut provider = provider();
+        provider.record_proof(true);
+        assert_eq!(provider.reputation, 5_250);
+        provider.record_proof(false);
+        assert_eq!(provider.reputation, 4_200);
+        assert_eq!((provider.proofs_succeeded, provider.proofs_failed), (1, 1));
+
+        for _ in 0..1_000 {
+            provider.record_proof(true);
+        }
+        assert!(provider.reputation <= MAX_REPUTATION);
+    }
+
+    #[test]
+    fn test_claim_requires_stake_reputation_and_activity() {
+        let mut provider = provider();
+        assert!(provider.check_claim(MIN_PROVIDER_STAKE).is_ok());
+        assert!(provider.check_claim(MIN_PROVIDER_STAKE - 1).is_err());
+
+        for _ in 0..4 {
+            provider.record_proof(false);
+        }
+        assert_eq!(provider.reputation, 2_048);
+        provider.record_proof(false);
+        assert!(!provider.is_approved());
+        assert!(provider.check_claim(MIN_PROVIDER_STAKE).is_err());
+
+        let inactive = GpuProvider { active: false, ..self::provider() };
+        assert!(inactive.check_claim(MIN_PROVIDER_STAKE).is_err());
+    }
+
+    #[test]
+    fn test_profile_requires_attestation_and_models() {
+        let mut provider = provider();
+        assert!(provider.set_profile([0; 32], 1).is_err());
+        assert!(provider.set_profile([1; 32], 0).is_err());
+        provider.set_profile([1; 32], 0b10).unwrap();
+        assert_eq!(provider.model_types, 0b10);
+    }
+}
@@ -0,0 +1,224 @@
+//! Coordinator-signed inference price quotes
+//!
+//! Apps want to show a binding price before the user signs anything. The
+//! coordinator signs an `InferenceQuote` off-chain with its registered quote key,
+//! and `create_inference_task` accepts it together with an ed25519 precompile
+//! instruction carrying the signature, escrowing the lower of the quoted fee and
+//! the current base fee. A quote names its requester and input, so it opens at
+//! most one task: the task PDA is derived from the same owner and input hash.
+//!
+//! `input_size` is enforced by the coordinator, which refuses to serve an input
+//! larger than it quoted; the program never sees the input itself.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{ed25519_program, instruction::Instruction},
+};
+
+/// Domain separator prefixed to every signed quote
+pub const QUOTE_DOMAIN: &[u8] = b"haunti-inference-quote";
+/// Longest validity accepted, so a leaked quote goes stale quickly
+pub const MAX_QUOTE_TTL_SECS: i64 = 600;
+
+/// `instruction_index` value meaning "this ed25519 instruction"
+const ED25519_SELF_INDEX: u16 = u16::MAX;
+/// Signature count and padding before the offsets
+const ED25519_OFFSETS_START: usize = 2;
+
+/// Key the coordinator signs quotes with, singleton PDA of `[b"quote_key"]`
+#[account]
+#[derive(Default)]
+pub struct QuoteKeyConfig {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Authority allowed to rotate the key
+    pub governance: Pubkey,
+    /// ed25519 key quotes must be signed with
+    pub quote_key: Pubkey,
+    /// Last rotation unix timestamp
+    pub updated_at: i64,
+}
+
+impl QuoteKeyConfig {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        32 + // quote_key
+        8;   // updated_at
+}
+
+/// Price the coordinator commits to for one inference call
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InferenceQuote {
+    /// Model the quote prices
+    pub model: Pubkey,
+    /// Only wallet that may open a task under the quote
+    pub owner: Pubkey,
+    /// Hash of the encrypted input the task will carry
+    pub input_hash: [u8; 32],
+    /// Largest input, in bytes, the coordinator agreed to serve
+    pub input_size: u32,
+    /// Most the task may escrow as its base fee (lamports)
+    pub max_fee: u64,
+    /// Unix timestamp from which the quote is refused
+    pub expires_at: i64,
+}
+
+impl InferenceQuote {
+    /// Bytes the coordinator signs: the domain, then each field in order with
+    /// integers little-endian
+    pub fn message(&self) -> Vec<u8> {
+        [
+            QUOTE_DOMAIN,
+            self.model.as_ref(),
+            self.owner.as_ref(),
+            &self.input_hash,
+            &self.input_size.to_le_bytes(),
+            &self.max_fee.to_le_bytes(),
+            &self.expires_at.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    /// Check the quote covers this task and is still valid at `now`
+    pub fn check(&self, model: &Pubkey, owner: &Pubkey, input_hash: &[u8; 32], now: i64) -> Result<()> {
+        require!(
+            self.model == *model && self.owner == *owner && self.input_hash == *input_hash,
+            QuoteError::QuoteMismatch
+        );
+        require!(now < self.expires_at, QuoteError::QuoteExpired);
+        require!(
+            self.expires_at - now <= MAX_QUOTE_TTL_SECS,
+            QuoteError::QuoteTooLong
+        );
+        Ok(())
+    }
+
+    /// Base fee escrowed under the quote when the current base fee is `price`
+    pub fn fee(&self, price: u64) -> u64 {
+        price.min(self.max_fee)
+    }
+}
+
+/// Fail unless `ix` is an ed25519 precompile instruction verifying exactly one
+/// signature by `signer` over `message`. Offsets pointing into other
+/// instructions are rejected, so the bytes compared here are the ones the
+/// precompile verified.
+pub fn check_quote_signature(ix: &Instruction, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    require_keys_eq!(ix.program_id, ed25519_program::ID, QuoteError::InvalidQuoteSignature);
+    let data = &ix.data;
+    require!(data.first() == Some(&1), QuoteError::InvalidQuoteSignature);
+
+    let read_u16 = |field: usize| -> Result<u16> {
+        let at = ED25519_OFFSETS_START + field * 2;
+        data.get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| QuoteError::InvalidQuoteSignature.into())
+    };
+    let slice = |offset: u16, len: usize| -> Result<&[u8]> {
+        data.get(offset as usize..offset as usize + len)
+            .ok_or_else(|| QuoteError::InvalidQuoteSignature.into())
+    };
+    let [_, sig_ix, key_offset, key_ix, msg_offset, msg_len, msg_ix] =
+        [0, 1, 2, 3, 4, 5, 6].map(read_u16);
+    require!(
+        [sig_ix?, key_ix?, msg_ix?].iter().all(|&ix| ix == ED25519_SELF_INDEX),
+        QuoteError::InvalidQuoteSignature
+    );
+    require!(
+        slice(key_offset?, 32)? == signer.as_ref()
+            && slice(msg_offset?, msg_len? as usize)? == message,
+        QuoteError::InvalidQuoteSignature
+    );
+    Ok(())
+}
+
+#[error_code]
+pub enum QuoteError {
+    #[msg("Quote is for a different model, owner or input")]
+    QuoteMismatch,
+    #[msg("Quote has expired")]
+    QuoteExpired,
+    #[msg("Quote validity exceeds the maximum")]
+    QuoteTooLong,
+    #[msg("Quote is not signed by the registered quote key")]
+    InvalidQuoteSignature,
+    #[msg("Quote key registry and instructions sysvar are required with a quote")]
+    QuoteAccountsMissing,
+    #[msg("Only governance may rotate the quote key")]
+    Unauthorized,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Precompile instruction with one signature and all data inline:
+    /// offsets, then key, signature and message
+    fn precompile(signer: &Pubkey, message: &[u8], external: bool) -> Instruction {
+        let (key_offset, sig_offset, msg_offset) = (16u16, 48u16, 112u16);
+        let ix_index = if external { 0 } else { ED25519_SELF_INDEX };
+        let mut data = vec![1, 0];
+        for field in [
+            sig_offset,
+            ix_index,
+            key_offset,
+            ix_index,
+            msg_offset,
+            message.len() as u16,
+            ix_index,
+        ] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&[0; 64]);
+        data.extend_from_slice(message);
+        Instruction::new_with_bytes(ed25519_program::ID, &data, vec![])
+    }
+
+    fn quote() -> InferenceQuote {
+        InferenceQuote {
+            model: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            input_hash: [5; 32],
+            input_size: 4_096,
+            max_fee: 50_000,
+            expires_at: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_quote_binds_task_and_expiry() {
+        let q = quote();
+        assert!(q.check(&q.model, &q.owner, &q.input_hash, 999).is_ok());
+        assert!(q.check(&q.model, &q.owner, &q.input_hash, 1_000).is_err());
+        assert!(q.check(&q.model, &Pubkey::new_unique(), &q.input_hash, 0).is_err());
+        assert!(q.check(&q.model, &q.owner, &[6; 32], 999).is_err());
+        // Valid far into the future is refused, not just expired quotes
+        assert!(q.check(&q.model, &q.owner, &q.input_hash, 1_000 - MAX_QUOTE_TTL_SECS - 1).is_err());
+    }
+
+    #[test]
+    fn test_quote_caps_escrow() {
+        let q = quote();
+        assert_eq!(q.fee(80_000), 50_000);
+        assert_eq!(q.fee(30_000), 30_000);
+    }
+
+    #[test]
+    fn test_signature_must_cover_quote_inline() {
+        let q = quote();
+        let key = Pubkey::new_unique();
+        let message = q.message();
+        assert!(check_quote_signature(&precompile(&key, &message, false), &key, &message).is_ok());
+
+        assert!(
+            check_quote_signature(&precompile(&Pubkey::new_unique(), &message, false), &key, &message)
+                .is_err()
+        );
+        let cheaper = InferenceQuote { max_fee: 1, ..q }.message();
+        assert!(check_quote_signature(&precompile(&key, &cheaper, false), &key, &message).is_err());
+        assert!(check_quote_signature(&precompile(&key, &message, true), &key, &message).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! Searchable model indexes (paged PDA lists of model mints)
+//!
+//! Every minted model is listed under its model type and under its creator, so
+//! clients can page through e.g. all vision models, or one creator's catalogue,
+//! without a `getProgramAccounts` scan. An index is a head account plus pages:
+//!
+//! - head: `[b"model_index", b"type", &[model_type as u8]]` or
+//!   `[b"model_index", b"creator", creator]`
+//! - page `n`: `[b"model_index_page", head, n (u32 LE)]`
+//!
+//! Mints are appended to the tail page and removed on archive by swapping in
+//! the page's last entry, so order within a page is not meaningful and earlier
+//! pages may run below capacity.
+
+use anchor_lang::prelude::*;
+
+/// Mints held by one index page
+pub const INDEX_PAGE_CAPACITY: usize = 64;
+
+/// What an index groups models by
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IndexKind {
+    /// Key is the model type byte, zero-padded
+    #[default]
+    ModelType,
+    /// Key is the creator's address
+    Creator,
+}
+
+/// Head of a model index
+#[account]
+#[derive(Default)]
+pub struct ModelIndex {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Grouping of this index
+    pub kind: IndexKind,
+    /// Model type or creator the index lists
+    pub key: [u8; 32],
+    /// Highest page in use; clients read pages `0..=tail_page`
+    pub tail_page: u32,
+    /// Mints on the tail page
+    pub tail_len: u16,
+    /// Mints listed across all pages
+    pub live: u64,
+    /// Creation unix timestamp; zero until first use
+    pub created_at: i64,
+}
+
+impl ModelIndex {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        1 +  // kind
+        32 + // key
+        4 +  // tail_page
+        2 +  // tail_len
+        8 +  // live
+        8;   // created_at
+
+    /// Set up a freshly created head; no-op once in use
+    pub fn open(&mut self, bump: u8, kind: IndexKind, key: [u8; 32], now: i64) {
+        if self.created_at == 0 {
+            self.bump = bump;
+            self.kind = kind;
+            self.key = key;
+            self.created_at = now;
+        }
+    }
+
+    /// Page the next mint must be appended to
+    pub fn append_page(&self) -> u32 {
+        if self.tail_len as usize >= INDEX_PAGE_CAPACITY {
+            self.tail_page + 1
+        } else {
+            self.tail_page
+        }
+    }
+
+    /// List `mint` on page `page_no`, which must be `append_page()`
+    pub fn append(&mut self, page: &mut ModelIndexPage, page_no: u32, mint: Pubkey) -> Result<()> {
+        require!(page_no == self.append_page(), ModelRegistryError::WrongIndexPage);
+        if page_no != self.tail_page {
+            self.tail_page = page_no;
+            self.tail_len = 0;
+        }
+        page.mints.push(mint);
+        self.tail_len += 1;
+        self.live += 1;
+        Ok(())
+    }
+
+    /// Unlist `mint` from page `page_no`
+    pub fn remove(
+        &mut self,
+        page: &mut ModelIndexPage,
+        page_no: u32,
+        mint: &Pubkey,
+    ) -> Result<()> {
+        let position = page
+            .mints
+            .iter()
+            .position(|m| m == mint)
+            .ok_or(ModelRegistryError::NotIndexed)?;
+        page.mints.swap_remove(position);
+        if page_no == self.tail_page {
+            self.tail_len -= 1;
+        }
+        self.live -= 1;
+        Ok(())
+    }
+}
+
+/// One page of an index
+#[account]
+#[derive(Default)]
+pub struct ModelIndexPage {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Head this page belongs to
+    pub index: Pubkey,
+    /// Page number
+    pub page: u32,
+    /// Listed model mints
+    pub mints: Vec<Pubkey>,
+}
+
+impl ModelIndexPage {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // index
+        4 +  // page
+        4 + 32 * INDEX_PAGE_CAPACITY; // mints
+
+    /// Set up a freshly created page; no-op once in use
+    pub fn open(&mut self, bump: u8, index: Pubkey, page: u32) {
+        if self.index == Pubkey::default() {
+            self.bump = bump;
+            self.index = index;
+            self.page = page;
+        }
+    }
+}
+
+/// Key of the model-type index for `model_type`
+pub fn model_type_key(model_type: u8) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    key[0] = model_type;
+    key
+}
+
+#[error_code]
+pub enum ModelRegistryError {
+    #[msg("Index page is not the current append page")]
+    WrongIndexPage,
+    #[msg("Model is not listed on this index page")]
+    NotIndexed,
+    #[msg("Only the model authority may archive it")]
+    Unauthorized,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_rolls_over_to_next_page() {
+        let head = Pubkey::new_unique();
+        let mut index = ModelIndex::default();
+        index.open(254, IndexKind::Creator, [3; 32], 100);
+        let mut first = ModelIndexPage::default();
+        first.open(255, head, 0);
+
+        for _ in 0..INDEX_PAGE_CAPACITY {
+            index.append(&mut first, 0, Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(index.append_page(), 1);
+        assert!(index.append(&mut first, 0, Pubkey::new_unique()).is_err());
+
+        let mut second = ModelIndexPage::default();
+        second.open(253, head, 1);
+        index.append(&mut second, 1, Pubkey::new_unique()).unwrap();
+        assert_eq!((index.tail_page, index.tail_len), (1, 1));
+        assert_eq!(index.live, INDEX_PAGE_CAPACITY as u64 + 1);
+        assert_eq!((second.index, second.page), (head, 1));
+    }
+
+    #[test]
+    fn test_remove_swaps_out_and_frees_tail_slot() {
+        let mut index = ModelIndex::default();
+        index.open(254, IndexKind::ModelType, model_type_key(2), 100);
+        let mut page = ModelIndexPage::default();
+        let mints: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        for mint in &mints {
+            index.append(&mut page, 0, *mint).unwrap();
+        }
+
+        index.remove(&mut page, 0, &mints[0]).unwrap();
+        assert_eq!(page.mints, vec![mints[2], mints[1]]);
+        assert_eq!((index.tail_len, index.live), (2, 2));
+        assert!(index.remove(&mut page, 0, &mints[0]).is_err());
+    }
+}
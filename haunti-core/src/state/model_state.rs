@@ -2,11 +2,14 @@
 
 use anchor_lang::{
     prelude::*,
-    solana_program::{program_pack::IsInitialized, sysvar},
+    solana_program::program_pack::IsInitialized,
 };
+use crate::env;
 use borsh::{BorshDeserialize, BorshSerialize};
 use std::convert::TryFrom;
 
+use super::transitions::{self, TransitionTable};
+
 /// Model lifecycle states
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
 pub enum ModelStatus {
@@ -31,6 +34,44 @@ impl Default for ModelStatus {
     }
 }
 
+/// Discriminant-only view of `ModelStatus` used by the transition table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ModelStatusKind {
+    PendingTraining,
+    Active,
+    Archived,
+    Deprecated,
+}
+
+impl ModelStatusKind {
+    /// Every state, in diagram order
+    pub const ALL: [Self; 4] = [
+        Self::PendingTraining,
+        Self::Active,
+        Self::Archived,
+        Self::Deprecated,
+    ];
+
+    /// Allowed transitions; `Active -> Active` covers updates and inference usage
+    pub const TRANSITIONS: TransitionTable<Self> = &[
+        (Self::PendingTraining, Self::Active),
+        (Self::Active, Self::Active),
+        (Self::Active, Self::Archived),
+        (Self::Active, Self::Deprecated),
+        (Self::Deprecated, Self::Archived),
+    ];
+
+    /// Whether `self -> next` is an allowed edge
+    pub fn can_transition_to(self, next: Self) -> bool {
+        transitions::is_allowed(Self::TRANSITIONS, &self, &next)
+    }
+
+    /// Whether no transitions leave this state
+    pub fn is_terminal(self) -> bool {
+        transitions::is_terminal(Self::TRANSITIONS, &self)
+    }
+}
+
 /// Model metadata account (PDA-based)
 #[account]
 #[derive(Default)]
@@ -57,6 +98,31 @@ pub struct ModelState {
     pub updated_at: i64,
     /// Version counter for optimistic locking
     pub revision: u64,
+    /// Opt out of inference result deduplication (nondeterministic models)
+    pub nondeterministic: bool,
+    /// Decryption keys are only released to attested TEE workers
+    pub require_tee: bool,
+    /// Keccak of the enclave launch measurement (SNP MEASUREMENT / TDX MRTD)
+    /// results must be attested under while `require_tee` is set
+    pub enclave_measurement: [u8; 32],
+    /// Weight-diff CIDs applied on top of `storage_cid`, oldest first
+    pub patch_cids: Vec<String>,
+    /// Input/output encoding the model accepts; unchecked while unset
+    pub io_schema: Option<IoSchema>,
+}
+
+/// Hashes of the canonical input and output descriptors (tensor shape, dtype,
+/// quantization) the model was built for. Client encoders hash the descriptor
+/// they encoded against, so inputs in the wrong layout are refused before any
+/// executor time is escrowed.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IoSchema {
+    pub input_hash: [u8; 32],
+    pub output_hash: [u8; 32],
+}
+
+impl IoSchema {
+    pub const LEN: usize = 32 + 32;
 }
 
 impl ModelState {
@@ -69,7 +135,21 @@ impl ModelState {
         32 + // model_root
         32 + // dataset_hash
         8 +  // updated_at
-        8;   // revision
+        8 +  // revision
+        1 +  // nondeterministic
+        1 +  // require_tee
+        32 + // enclave_measurement
+        4 +  // patch_cids (vec prefix)
+        1 + IoSchema::LEN; // io_schema (option)
+
+    /// Current account space including variable-length fields
+    pub fn space(&self) -> usize {
+        Self::BASE_LEN
+            + 4 + self.fhe_params.len()
+            + 4 + self.zk_params.len()
+            + 4 + self.storage_cid.len()
+            + self.patch_cids.iter().map(|cid| 4 + cid.len()).sum::<usize>()
+    }
 
     /// Initialize new model with cryptographic proofs
     pub fn initialize(
@@ -84,7 +164,7 @@ impl ModelState {
         require!(!self.is_initialized(), ModelError::AlreadyInitialized);
         require_eq!(self.version, 0, ModelError::VersionMismatch);
         
-        let clock = sysvar::clock::Clock::get()?;
+        let clock = env::clock()?;
         self.owner = owner;
         self.model_root = model_root;
         self.dataset_hash = dataset_hash;
@@ -106,14 +186,16 @@ impl ModelState {
         signature: &[u8],
     ) -> Result<()> {
         require!(
-            matches!(self.status, ModelStatus::Active { .. }),
+            self.status.kind() == ModelStatusKind::Active,
             ModelError::InvalidState
         );
         self.verify_owner_signature(new_root, signature)?;
 
-        let clock = sysvar::clock::Clock::get()?;
+        let clock = env::clock()?;
         self.model_root = new_root;
         self.storage_cid = new_cid;
+        // A full upload starts a fresh patch chain
+        self.patch_cids.clear();
         self.version = self.version.wrapping_add(1);
         self.updated_at = clock.unix_timestamp;
         self.revision = self.revision.wrapping_add(1);
@@ -127,39 +209,136 @@ impl ModelState {
         Ok(())
     }
 
+    /// Record an incremental update: `patch_cid` applied to the weights at `base_root`
+    pub fn update_model_with_patch(
+        &mut self,
+        base_root: [u8; 32],
+        new_root: [u8; 32],
+        patch_cid: String,
+        signature: &[u8],
+    ) -> Result<()> {
+        require!(
+            self.status.kind() == ModelStatusKind::Active,
+            ModelError::InvalidState
+        );
+        // Patches must chain from the current version, never fork an older one
+        require!(base_root == self.model_root, ModelError::PatchBaseMismatch);
+        require!(patch_cid.len() <= MAX_PATCH_CID_LEN, ModelError::CidTooLong);
+        require!(
+            self.patch_cids.len() < MAX_PATCH_CHAIN,
+            ModelError::PatchChainTooLong
+        );
+        self.verify_owner_signature(new_root, signature)?;
+
+        let clock = env::clock()?;
+        self.model_root = new_root;
+        self.patch_cids.push(patch_cid.clone());
+        self.version = self.version.wrapping_add(1);
+        self.updated_at = clock.unix_timestamp;
+        self.revision = self.revision.wrapping_add(1);
+
+        emit!(ModelPatched {
+            model: self.key(),
+            version: self.version,
+            base_root,
+            new_root,
+            patch_cid,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Apply a status change after checking it against the transition table
+    pub fn transition(&mut self, next: ModelStatus) -> Result<()> {
+        require!(
+            self.status.kind().can_transition_to(next.kind()),
+            ModelError::InvalidStateTransition
+        );
+
+        self.status = next;
+        self.revision = self.revision.wrapping_add(1);
+
+        Ok(())
+    }
+
     /// Transition model to active state
     pub fn activate(&mut self) -> Result<()> {
         require!(
-            matches!(self.status, ModelStatus::PendingTraining),
+            self.status.kind() == ModelStatusKind::PendingTraining,
             ModelError::InvalidStateTransition
         );
 
-        self.status = ModelStatus::Active {
+        self.transition(ModelStatus::Active {
             last_inference: None,
             inference_count: 0,
-        };
-        self.revision = self.revision.wrapping_add(1);
-
-        Ok(())
+        })
     }
 
     /// Record inference usage
     pub fn record_inference(&mut self) -> Result<()> {
-        if let ModelStatus::Active {
-            ref mut last_inference,
-            ref mut inference_count,
-        } = self.status
-        {
-            let clock = sysvar::clock::Clock::get()?;
-            *last_inference = Some(clock.unix_timestamp);
-            *inference_count = inference_count.saturating_add(1);
-            self.revision = self.revision.wrapping_add(1);
-            Ok(())
+        if let ModelStatus::Active { inference_count, .. } = self.status {
+            let clock = env::clock()?;
+            self.transition(ModelStatus::Active {
+                last_inference: Some(clock.unix_timestamp),
+                inference_count: inference_count.saturating_add(1),
+            })
         } else {
             Err(ModelError::InvalidStateTransition.into())
         }
     }
 
+    /// Toggle result deduplication for this model
+    pub fn set_nondeterministic(&mut self, nondeterministic: bool) -> Result<()> {
+        require!(self.is_initialized(), ModelError::InvalidState);
+
+        self.nondeterministic = nondeterministic;
+        self.revision = self.revision.wrapping_add(1);
+
+        Ok(())
+    }
+
+    /// Restrict plaintext execution to attested enclaves running the given
+    /// launch measurement
+    pub fn set_require_tee(&mut self, require_tee: bool, enclave_measurement: [u8; 32]) -> Result<()> {
+        require!(self.is_initialized(), ModelError::InvalidState);
+        require!(
+            !require_tee || enclave_measurement != [0; 32],
+            ModelError::MissingEnclaveMeasurement
+        );
+
+        self.require_tee = require_tee;
+        self.enclave_measurement = enclave_measurement;
+        self.revision = self.revision.wrapping_add(1);
+
+        Ok(())
+    }
+
+    /// Register, replace or (with `None`) drop the model's I/O schema
+    pub fn set_io_schema(&mut self, io_schema: Option<IoSchema>) -> Result<()> {
+        require!(self.is_initialized(), ModelError::InvalidState);
+        require!(
+            io_schema.map_or(true, |s| s.input_hash != [0; 32] && s.output_hash != [0; 32]),
+            ModelError::InvalidIoSchema
+        );
+
+        self.io_schema = io_schema;
+        self.revision = self.revision.wrapping_add(1);
+
+        Ok(())
+    }
+
+    /// Reject an input encoded against a schema other than the registered one
+    pub fn check_input_schema(&self, input_schema_hash: &[u8; 32]) -> Result<()> {
+        if let Some(schema) = &self.io_schema {
+            require!(
+                schema.input_hash == *input_schema_hash,
+                ModelError::InputSchemaMismatch
+            );
+        }
+        Ok(())
+    }
+
     /// Verify cryptographic ownership proof
     fn verify_owner_signature(
         &self,
@@ -189,6 +368,16 @@ impl IsInitialized for ModelState {
 }
 
 impl ModelStatus {
+    /// Discriminant used for transition checks
+    pub fn kind(&self) -> ModelStatusKind {
+        match self {
+            Self::PendingTraining => ModelStatusKind::PendingTraining,
+            Self::Active { .. } => ModelStatusKind::Active,
+            Self::Archived => ModelStatusKind::Archived,
+            Self::Deprecated { .. } => ModelStatusKind::Deprecated,
+        }
+    }
+
     /// Calculate max serialized size
     pub const LEN: usize = 1 + // variant tag
         1 + 8 + 8; // Active state fields (Option<i64> + u64)
@@ -202,6 +391,22 @@ pub struct ModelUpdated {
     pub timestamp: i64,
 }
 
+/// Incremental weight update event
+#[event]
+pub struct ModelPatched {
+    pub model: Pubkey,
+    pub version: u32,
+    pub base_root: [u8; 32],
+    pub new_root: [u8; 32],
+    pub patch_cid: String,
+    pub timestamp: i64,
+}
+
+/// Patches allowed before a full re-upload is required
+pub const MAX_PATCH_CHAIN: usize = 16;
+/// Maximum length of a patch CID
+pub const MAX_PATCH_CID_LEN: usize = 96;
+
 #[error_code]
 pub enum ModelError {
     #[msg("Model already initialized")]
@@ -222,4 +427,75 @@ pub enum ModelError {
     FheParamsInvalid,
     #[msg("ZK parameters invalid")]
     ZkParamsInvalid,
+    #[msg("Patch does not apply to the current model root")]
+    PatchBaseMismatch,
+    #[msg("Patch chain too long, upload full weights")]
+    PatchChainTooLong,
+    #[msg("I/O schema hashes must be non-zero")]
+    InvalidIoSchema,
+    #[msg("Input was encoded for a different model schema")]
+    InputSchemaMismatch,
+    #[msg("TEE-only models must register an enclave measurement")]
+    MissingEnclaveMeasurement,
+    #[msg("Nondeterministic models cannot serve cached results")]
+    NondeterministicModel,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn status_strategy() -> impl Strategy<Value = ModelStatus> {
+        prop_oneof![
+            Just(ModelStatus::PendingTraining),
+            (any::<Option<i64>>(), any::<u64>()).prop_map(|(last_inference, inference_count)| {
+                ModelStatus::Active { last_inference, inference_count }
+            }),
+            Just(ModelStatus::Archived),
+            Just(ModelStatus::Deprecated { successor: None }),
+        ]
+    }
+
+    #[test]
+    fn test_input_schema_enforced_once_registered() {
+        let mut model = ModelState { revision: 1, ..Default::default() };
+        // Models without a schema accept any encoding
+        model.check_input_schema(&[7; 32]).unwrap();
+
+        assert!(model
+            .set_io_schema(Some(IoSchema { input_hash: [0; 32], output_hash: [2; 32] }))
+            .is_err());
+        model
+            .set_io_schema(Some(IoSchema { input_hash: [1; 32], output_hash: [2; 32] }))
+            .unwrap();
+        model.check_input_schema(&[1; 32]).unwrap();
+        assert!(model.check_input_schema(&[7; 32]).is_err());
+
+        model.set_io_schema(None).unwrap();
+        model.check_input_schema(&[7; 32]).unwrap();
+    }
+
+    proptest! {
+        #[test]
+        fn prop_transitions_follow_table(ops in prop::collection::vec(status_strategy(), 0..64)) {
+            let mut model = ModelState::default();
+            let mut seen_active = false;
+
+            for next in ops {
+                let from = model.status.kind();
+                let to = next.kind();
+                let result = model.transition(next);
+
+                prop_assert_eq!(result.is_ok(), from.can_transition_to(to));
+                seen_active |= model.status.kind() == ModelStatusKind::Active;
+
+                // A model can only be retired after it has been active
+                if matches!(model.status.kind(), ModelStatusKind::Archived | ModelStatusKind::Deprecated) {
+                    prop_assert!(seen_active);
+                }
+                prop_assert!(model.status.kind() != ModelStatusKind::PendingTraining || !seen_active);
+            }
+        }
+    }
 }
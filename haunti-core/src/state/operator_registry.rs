@@ -0,0 +1,138 @@
+//! Registry of community zkML operators and per-model circuit descriptors
+//!
+//! Operators are declarative descriptions compiled into circuit constraints by the
+//! prover (see `zero-knowledge-zkml/prover/operator_dsl.rs`). Each one is
+//! registered under the SHA-256 of its canonical encoding, so the hash pins the
+//! exact constraints. A model's circuit descriptor lists the operators its circuit
+//! is built from, and may only reference registered hashes.
+
+use anchor_lang::prelude::*;
+
+/// Maximum length of an operator's description URI
+pub const MAX_OPERATOR_URI_LEN: usize = 200;
+/// Maximum number of operators in one circuit descriptor
+pub const MAX_CIRCUIT_OPERATORS: usize = 32;
+
+/// A registered operator, PDA of `[b"operator", hash]`
+#[account]
+#[derive(Default)]
+pub struct OperatorRecord {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// SHA-256 of the operator's canonical description
+    pub hash: [u8; 32],
+    /// Wallet that registered the operator
+    pub author: Pubkey,
+    /// Where the description can be fetched (IPFS CID or URL)
+    pub spec_uri: String,
+    /// Registration unix timestamp
+    pub registered_at: i64,
+}
+
+impl OperatorRecord {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // hash
+        32 + // author
+        4 + MAX_OPERATOR_URI_LEN + // spec_uri
+        8;   // registered_at
+}
+
+/// Operators a model's circuit is built from, PDA of `[b"circuit", model]`
+#[account]
+#[derive(Default)]
+pub struct CircuitDescriptor {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Model the circuit proves
+    pub model: Pubkey,
+    /// Operator hashes in circuit order; repeats allowed
+    pub operators: Vec<[u8; 32]>,
+    /// Last update unix timestamp
+    pub updated_at: i64,
+}
+
+impl CircuitDescriptor {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // model
+        4 + 32 * MAX_CIRCUIT_OPERATORS + // operators
+        8;   // updated_at
+
+    /// Check that `records` proves every operator of `operators` is registered.
+    ///
+    /// `records` holds one registry account per distinct operator hash, in order
+    /// of first appearance; each must be the program-owned PDA of its hash.
+    pub fn check_registered(
+        operators: &[[u8; 32]],
+        records: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> Result<()> {
+        require!(
+            !operators.is_empty() && operators.len() <= MAX_CIRCUIT_OPERATORS,
+            OperatorRegistryError::InvalidOperatorCount
+        );
+
+        let distinct = distinct_in_order(operators);
+        require!(
+            records.len() == distinct.len(),
+            OperatorRegistryError::OperatorNotRegistered
+        );
+        for (hash, info) in distinct.iter().zip(records) {
+            let (expected, _) = Pubkey::find_program_address(&[b"operator", hash], program_id);
+            require!(
+                info.key() == expected && info.owner == program_id && !info.data_is_empty(),
+                OperatorRegistryError::OperatorNotRegistered
+            );
+            let record = Account::<OperatorRecord>::try_from(info)
+                .map_err(|_| OperatorRegistryError::OperatorNotRegistered)?;
+            require!(record.hash == *hash, OperatorRegistryError::OperatorNotRegistered);
+        }
+        Ok(())
+    }
+}
+
+/// Each hash once, in order of first appearance
+fn distinct_in_order(operators: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    let mut seen = Vec::with_capacity(operators.len());
+    for hash in operators {
+        if !seen.contains(hash) {
+            seen.push(*hash);
+        }
+    }
+    seen
+}
+
+#[error_code]
+pub enum OperatorRegistryError {
+    #[msg("Operator hash must be non-zero")]
+    InvalidOperatorHash,
+    #[msg("Operator description URI too long")]
+    UriTooLong,
+    #[msg("Circuit descriptor must list between 1 and 32 operators")]
+    InvalidOperatorCount,
+    #[msg("Circuit descriptor references an unregistered operator")]
+    OperatorNotRegistered,
+    #[msg("Only the model owner may set its circuit descriptor")]
+    Unauthorized,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_expected_once_per_distinct_operator() {
+        let (relu, matmul) = ([1u8; 32], [2u8; 32]);
+        assert_eq!(distinct_in_order(&[matmul, relu, matmul, relu]), vec![matmul, relu]);
+
+        // Records are matched before any account is read, so no accounts suffice
+        let program_id = Pubkey::new_unique();
+        assert!(CircuitDescriptor::check_registered(&[relu], &[], &program_id).is_err());
+        assert!(CircuitDescriptor::check_registered(&[], &[], &program_id).is_err());
+        let too_many = vec![relu; MAX_CIRCUIT_OPERATORS + 1];
+        assert!(CircuitDescriptor::check_registered(&too_many, &[], &program_id).is_err());
+    }
+}
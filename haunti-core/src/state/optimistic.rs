@@ -0,0 +1,120 @@
+//! Optimistic verification policy
+//!
+//! In optimistic mode a worker posts its result without the proof being
+//! verified on-chain; the task sits in `Provisional` for `challenge_window_secs`.
+//! Any staker of `stake_pool` holding at least `min_challenger_stake` may
+//! challenge it within the window by posting `challenger_bond` and a
+//! counter-proof, which opens a `Dispute` the arbitrator rules on. The losing
+//! side is slashed: a wrong challenger forfeits the bond to the worker, a wrong
+//! worker loses `worker_slash_bps` of its `WorkerBond` to the challenger and the
+//! task escrow goes back to the owner. Unchallenged results complete once the
+//! window closes, so the common case never pays for verification.
+
+use anchor_lang::prelude::*;
+
+/// Governance-tunable optimistic verification policy (singleton PDA of
+/// `[b"optimistic_config"]`)
+#[account]
+#[derive(Default)]
+pub struct OptimisticConfig {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Authority allowed to change the policy
+    pub governance: Pubkey,
+    /// Authority that rules on disputes
+    pub arbitrator: Pubkey,
+    /// token-vault pool whose stakers may challenge
+    pub stake_pool: Pubkey,
+    /// Seconds after submission during which a result may be challenged
+    pub challenge_window_secs: i64,
+    /// Lamports a challenger locks with the dispute
+    pub challenger_bond: u64,
+    /// Stake a challenger must hold in `stake_pool`
+    pub min_challenger_stake: u64,
+    /// Share of the worker's bond slashed when its result is overturned, in
+    /// basis points
+    pub worker_slash_bps: u16,
+    /// Last update unix timestamp
+    pub updated_at: i64,
+}
+
+impl OptimisticConfig {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        32 + // arbitrator
+        32 + // stake_pool
+        8 +  // challenge_window_secs
+        8 +  // challenger_bond
+        8 +  // min_challenger_stake
+        2 +  // worker_slash_bps
+        8;   // updated_at
+
+    /// Replace the policy
+    pub fn set(
+        &mut self,
+        arbitrator: Pubkey,
+        challenge_window_secs: i64,
+        challenger_bond: u64,
+        min_challenger_stake: u64,
+        worker_slash_bps: u16,
+    ) -> Result<()> {
+        require!(
+            challenge_window_secs > 0
+                && challenge_window_secs <= MAX_CHALLENGE_WINDOW_SECS
+                && challenger_bond > 0
+                && worker_slash_bps <= 10_000,
+            OptimisticError::InvalidPolicy
+        );
+        self.arbitrator = arbitrator;
+        self.challenge_window_secs = challenge_window_secs;
+        self.challenger_bond = challenger_bond;
+        self.min_challenger_stake = min_challenger_stake;
+        self.worker_slash_bps = worker_slash_bps;
+        Ok(())
+    }
+
+    /// Lamports slashed from a worker bond of `amount`
+    pub fn worker_slash(&self, amount: u64) -> u64 {
+        (amount as u128 * self.worker_slash_bps as u128 / 10_000) as u64
+    }
+}
+
+/// Longest challenge window governance may set; results must settle within a week
+pub const MAX_CHALLENGE_WINDOW_SECS: i64 = 7 * 24 * 60 * 60;
+
+#[error_code]
+pub enum OptimisticError {
+    #[msg("Invalid optimistic verification policy")]
+    InvalidPolicy,
+    #[msg("Unauthorized optimistic verification policy update")]
+    Unauthorized,
+    #[msg("Challenge window has closed")]
+    ChallengeWindowClosed,
+    #[msg("Challenge window is still open")]
+    ChallengeWindowOpen,
+    #[msg("Challenger stake below minimum")]
+    InsufficientStake,
+    #[msg("Counter-proof must be non-empty")]
+    EmptyCounterProof,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_bounds() {
+        let mut config = OptimisticConfig::default();
+        let arbitrator = Pubkey::new_unique();
+        assert!(config.set(arbitrator, 0, 1, 0, 0).is_err());
+        assert!(config.set(arbitrator, MAX_CHALLENGE_WINDOW_SECS + 1, 1, 0, 0).is_err());
+        assert!(config.set(arbitrator, 3_600, 0, 0, 0).is_err());
+        assert!(config.set(arbitrator, 3_600, 1, 0, 10_001).is_err());
+
+        config.set(arbitrator, 3_600, 1_000_000, 500, 5_000).unwrap();
+        assert_eq!(config.arbitrator, arbitrator);
+        assert_eq!(config.worker_slash(1_000), 500);
+    }
+}
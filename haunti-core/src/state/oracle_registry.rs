@@ -0,0 +1,308 @@
+//! Staked oracle registry for data feeds
+//!
+//! Each feed is a PDA of `[b"oracle_feed", feed_id]`, where `feed_id` is the
+//! SHA-256 of the feed name used by the off-chain data feed engine. Oracles join
+//! a feed by bonding at least `min_stake` lamports in an `OracleStake` PDA of
+//! `[b"oracle_stake", feed, oracle]`, then report one value per round.
+//!
+//! Finalizing a round anchors the median of the reports on-chain. Reports that
+//! deviate from that median by more than `max_deviation_bps` are flagged, and
+//! anyone may then slash `slash_bps` of the reporter's bond. Slashed lamports stay
+//! in the feed account. Leaving a feed starts an unbonding period so an oracle
+//! cannot withdraw ahead of a pending slash.
+
+use anchor_lang::prelude::*;
+
+/// Most oracles a feed accepts
+pub const MAX_FEED_ORACLES: usize = 16;
+/// Seconds a deregistered oracle waits before withdrawing its bond
+pub const ORACLE_UNBONDING_SECS: i64 = 3 * 24 * 60 * 60;
+
+/// One oracle's value for the open round
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OracleReport {
+    pub oracle: Pubkey,
+    pub value: i64,
+}
+
+/// A data feed and its registered oracles
+#[account]
+#[derive(Default)]
+pub struct OracleFeed {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Authority allowed to change the feed parameters
+    pub governance: Pubkey,
+    /// SHA-256 of the feed name
+    pub feed_id: [u8; 32],
+    /// Bond required to register, in lamports
+    pub min_stake: u64,
+    /// Reports needed to finalize a round
+    pub min_reports: u8,
+    /// Largest accepted deviation from the round median, in basis points
+    pub max_deviation_bps: u16,
+    /// Share of the bond slashed per flagged report, in basis points
+    pub slash_bps: u16,
+    /// Registered oracles
+    pub oracles: Vec<Pubkey>,
+    /// Reports for the open round
+    pub reports: Vec<OracleReport>,
+    /// Oracles whose report in a finalized round is awaiting its slash
+    pub flagged: Vec<Pubkey>,
+    /// Rounds finalized so far
+    pub round: u64,
+    /// Median anchored by the last finalized round
+    pub median: i64,
+    /// Slot the last round was finalized at
+    pub median_slot: u64,
+    /// Lamports slashed from oracle bonds
+    pub slashed_total: u64,
+}
+
+impl OracleFeed {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        32 + // feed_id
+        8 +  // min_stake
+        1 +  // min_reports
+        2 +  // max_deviation_bps
+        2 +  // slash_bps
+        4 + 32 * MAX_FEED_ORACLES + // oracles
+        4 + 40 * MAX_FEED_ORACLES + // reports
+        4 + 32 * MAX_FEED_ORACLES + // flagged
+        8 +  // round
+        8 +  // median
+        8 +  // median_slot
+        8;   // slashed_total
+
+    /// Check and apply governance parameters
+    pub fn configure(
+        &mut self,
+        min_stake: u64,
+        min_reports: u8,
+        max_deviation_bps: u16,
+        slash_bps: u16,
+    ) -> Result<()> {
+        require!(
+            min_stake > 0
+                && min_reports > 0
+                && min_reports as usize <= MAX_FEED_ORACLES
+                && max_deviation_bps > 0
+                && slash_bps <= 10_000,
+            OracleRegistryError::InvalidFeedParameters
+        );
+        self.min_stake = min_stake;
+        self.min_reports = min_reports;
+        self.max_deviation_bps = max_deviation_bps;
+        self.slash_bps = slash_bps;
+        Ok(())
+    }
+
+    pub fn add_oracle(&mut self, oracle: Pubkey) -> Result<()> {
+        require!(
+            !self.oracles.contains(&oracle),
+            OracleRegistryError::AlreadyRegistered
+        );
+        require!(
+            self.oracles.len() < MAX_FEED_ORACLES,
+            OracleRegistryError::FeedFull
+        );
+        self.oracles.push(oracle);
+        Ok(())
+    }
+
+    /// Drop `oracle` from the set and the open round
+    pub fn remove_oracle(&mut self, oracle: &Pubkey) -> Result<()> {
+        let position = self
+            .oracles
+            .iter()
+            .position(|o| o == oracle)
+            .ok_or(OracleRegistryError::NotRegistered)?;
+        self.oracles.swap_remove(position);
+        self.reports.retain(|r| r.oracle != *oracle);
+        Ok(())
+    }
+
+    /// Record `oracle`'s value for the open round, replacing an earlier one
+    pub fn report(&mut self, oracle: Pubkey, value: i64) -> Result<()> {
+        require!(
+            self.oracles.contains(&oracle),
+            OracleRegistryError::NotRegistered
+        );
+        match self.reports.iter_mut().find(|r| r.oracle == oracle) {
+            Some(report) => report.value = value,
+            None => self.reports.push(OracleReport { oracle, value }),
+        }
+        Ok(())
+    }
+
+    /// Anchor the median of the open round and flag the reports that deviate
+    /// from it; returns the median
+    pub fn finalize_round(&mut self, slot: u64) -> Result<i64> {
+        require!(
+            self.reports.len() >= self.min_reports as usize,
+            OracleRegistryError::NotEnoughReports
+        );
+        let mut values: Vec<i64> = self.reports.iter().map(|r| r.value).collect();
+        let median = median(&mut values);
+
+        for report in std::mem::take(&mut self.reports) {
+            if !within_bounds(report.value, median, self.max_deviation_bps)
+                && !self.flagged.contains(&report.oracle)
+            {
+                self.flagged.push(report.oracle);
+            }
+        }
+        self.round += 1;
+        self.median = median;
+        self.median_slot = slot;
+        Ok(median)
+    }
+
+    /// Clear `oracle`'s flag, failing if it has none
+    pub fn take_flag(&mut self, oracle: &Pubkey) -> Result<()> {
+        let position = self
+            .flagged
+            .iter()
+            .position(|o| o == oracle)
+            .ok_or(OracleRegistryError::NotFlagged)?;
+        self.flagged.swap_remove(position);
+        Ok(())
+    }
+
+    /// Lamports slashed from a bond of `stake`
+    pub fn slash_amount(&self, stake: u64) -> u64 {
+        (stake as u128 * self.slash_bps as u128 / 10_000) as u64
+    }
+}
+
+/// An oracle's bond on one feed
+#[account]
+#[derive(Default)]
+pub struct OracleStake {
+    /// Bump seed for PDA
+    pub bump: u8,
+    pub feed: Pubkey,
+    pub oracle: Pubkey,
+    /// Bonded lamports, held by this account on top of its rent
+    pub stake: u64,
+    /// Lamports slashed so far
+    pub slashed: u64,
+    pub registered_at: i64,
+    /// When the bond may be withdrawn; zero while registered
+    pub unlock_at: i64,
+}
+
+impl OracleStake {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // feed
+        32 + // oracle
+        8 +  // stake
+        8 +  // slashed
+        8 +  // registered_at
+        8;   // unlock_at
+}
+
+/// Median of `values`, averaging the middle pair for an even count
+pub fn median(values: &mut [i64]) -> i64 {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        ((values[mid - 1] as i128 + values[mid] as i128) / 2) as i64
+    } else {
+        values[mid]
+    }
+}
+
+/// Whether `value` lies within `max_deviation_bps` of `median`
+pub fn within_bounds(value: i64, median: i64, max_deviation_bps: u16) -> bool {
+    let deviation = (value as i128 - median as i128).unsigned_abs();
+    let bound = median.unsigned_abs() as u128 * max_deviation_bps as u128 / 10_000;
+    deviation <= bound
+}
+
+#[error_code]
+pub enum OracleRegistryError {
+    #[msg("Invalid feed parameters")]
+    InvalidFeedParameters,
+    #[msg("Stake is below the feed minimum")]
+    InsufficientStake,
+    #[msg("Oracle is already registered on this feed")]
+    AlreadyRegistered,
+    #[msg("Feed has no room for more oracles")]
+    FeedFull,
+    #[msg("Oracle is not registered on this feed")]
+    NotRegistered,
+    #[msg("Not enough reports to finalize the round")]
+    NotEnoughReports,
+    #[msg("Oracle has no report awaiting a slash")]
+    NotFlagged,
+    #[msg("Oracle has a pending slash")]
+    PendingSlash,
+    #[msg("Bond is still unbonding")]
+    StillUnbonding,
+    #[msg("Unauthorized oracle feed update")]
+    Unauthorized,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(oracles: &[Pubkey]) -> OracleFeed {
+        let mut feed = OracleFeed::default();
+        feed.configure(1_000, 3, 100, 5_000).unwrap();
+        for oracle in oracles {
+            feed.add_oracle(*oracle).unwrap();
+        }
+        feed
+    }
+
+    #[test]
+    fn test_round_flags_reports_outside_bounds() {
+        let oracles: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let mut feed = feed(&oracles);
+        feed.report(oracles[0], 10_000).unwrap();
+        feed.report(oracles[1], 10_050).unwrap();
+        assert!(feed.finalize_round(1).is_err());
+
+        feed.report(oracles[2], 9_990).unwrap();
+        feed.report(oracles[3], 12_000).unwrap();
+        // Median of 9_990, 10_000, 10_050, 12_000; 1% bound is 100
+        assert_eq!(feed.finalize_round(7).unwrap(), 10_025);
+        assert_eq!((feed.round, feed.median_slot), (1, 7));
+        assert_eq!(feed.flagged, vec![oracles[3]]);
+        assert!(feed.reports.is_empty());
+
+        feed.take_flag(&oracles[3]).unwrap();
+        assert!(feed.take_flag(&oracles[3]).is_err());
+        assert_eq!(feed.slash_amount(2_000), 1_000);
+    }
+
+    #[test]
+    fn test_only_registered_oracles_report() {
+        let oracle = Pubkey::new_unique();
+        let mut feed = feed(&[oracle]);
+        assert!(feed.add_oracle(oracle).is_err());
+        assert!(feed.report(Pubkey::new_unique(), 1).is_err());
+
+        feed.report(oracle, 5).unwrap();
+        feed.report(oracle, 6).unwrap();
+        assert_eq!(feed.reports, vec![OracleReport { oracle, value: 6 }]);
+
+        feed.remove_oracle(&oracle).unwrap();
+        assert!(feed.reports.is_empty() && feed.oracles.is_empty());
+    }
+
+    #[test]
+    fn test_median_and_bounds() {
+        assert_eq!(median(&mut [3, 1, 2]), 2);
+        assert_eq!(median(&mut [i64::MAX, i64::MAX]), i64::MAX);
+        assert!(within_bounds(-1_010, -1_000, 100));
+        assert!(!within_bounds(-1_011, -1_000, 100));
+    }
+}
@@ -0,0 +1,369 @@
+//! Prepaid task balances funded from other chains
+//!
+//! EVM users lock ETH or USDC in the Haunti payment contract, which publishes a
+//! Wormhole payment message. Once the guardians' VAA is posted to Solana,
+//! `redeem_bridged_payment` converts the locked amount to lamports at the
+//! governance-set rate and moves them from the bridge treasury into the
+//! recipient's `PrepaidBalance`, which `create_task`/`create_inference_task` can
+//! draw escrow from instead of the owner's wallet.
+
+use anchor_lang::prelude::*;
+use crate::env;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Number of assets accepted by the payment contract
+pub const PAYMENT_ASSET_COUNT: usize = 2;
+/// Payload id of a payment message (first byte of the payload)
+pub const PAYMENT_PAYLOAD_ID: u8 = 1;
+/// Encoded payment payload length
+pub const PAYMENT_PAYLOAD_LEN: usize = 1 + 1 + 32 + 32 + 8;
+
+/// Asset locked on the source chain; indexes `PaymentBridgeConfig::rates`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaymentAsset {
+    /// Native ETH, 18 decimals
+    Eth,
+    /// USDC, 6 decimals
+    Usdc,
+}
+
+impl PaymentAsset {
+    /// Decimals of the asset's base unit on the source chain
+    pub fn decimals(self) -> u32 {
+        match self {
+            PaymentAsset::Eth => 18,
+            PaymentAsset::Usdc => 6,
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(PaymentAsset::Eth),
+            1 => Some(PaymentAsset::Usdc),
+            _ => None,
+        }
+    }
+}
+
+/// Payment message published by the EVM payment contract.
+///
+/// Encoded big-endian like every EVM-side Wormhole payload:
+/// `payload_id u8 | asset u8 | amount u256 | recipient bytes32 | lock_id u64`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PaymentPayload {
+    /// Locked asset
+    pub asset: PaymentAsset,
+    /// Locked amount in the asset's base units
+    pub amount: u128,
+    /// Solana account credited with the payment
+    pub recipient: Pubkey,
+    /// Lock id assigned by the payment contract
+    pub lock_id: u64,
+}
+
+impl PaymentPayload {
+    /// Parse a payment payload; rejects other payload ids and trailing bytes
+    pub fn decode(payload: &[u8]) -> Result<Self> {
+        require!(
+            payload.len() == PAYMENT_PAYLOAD_LEN && payload[0] == PAYMENT_PAYLOAD_ID,
+            PaymentBridgeError::InvalidPayload
+        );
+        let asset = PaymentAsset::from_u8(payload[1]).ok_or(PaymentBridgeError::UnsupportedAsset)?;
+        // u256 amount: anything above u128 cannot be a real lock
+        require!(
+            payload[2..18].iter().all(|b| *b == 0),
+            PaymentBridgeError::AmountOverflow
+        );
+        let amount = u128::from_be_bytes(payload[18..34].try_into().unwrap());
+        let recipient = Pubkey::new_from_array(payload[34..66].try_into().unwrap());
+        let lock_id = u64::from_be_bytes(payload[66..74].try_into().unwrap());
+        Ok(Self { asset, amount, recipient, lock_id })
+    }
+
+    /// Inverse of `decode`
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(PAYMENT_PAYLOAD_LEN);
+        out.push(PAYMENT_PAYLOAD_ID);
+        out.push(self.asset as u8);
+        out.extend_from_slice(&[0u8; 16]);
+        out.extend_from_slice(&self.amount.to_be_bytes());
+        out.extend_from_slice(self.recipient.as_ref());
+        out.extend_from_slice(&self.lock_id.to_be_bytes());
+        out
+    }
+}
+
+/// Posted VAA account written by the Wormhole core bridge (`PostedVAA` PDA).
+///
+/// The core bridge only creates it after checking the guardian signatures, so
+/// ownership by the core bridge program is the proof of verification.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PostedVaa {
+    /// Account magic, `b"vaa\x01"`
+    pub magic: [u8; 4],
+    pub consistency_level: u8,
+    pub vaa_time: u32,
+    pub vaa_signature_account: Pubkey,
+    pub submission_time: u32,
+    pub nonce: u32,
+    pub sequence: u64,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub payload: Vec<u8>,
+}
+
+impl PostedVaa {
+    /// Magic prefix of posted message accounts
+    pub const MAGIC: [u8; 4] = *b"vaa\x01";
+
+    pub fn from_account(info: &AccountInfo) -> Result<Self> {
+        let data = info.try_borrow_data()?;
+        let vaa = PostedVaa::deserialize(&mut &data[..])
+            .map_err(|_| PaymentBridgeError::InvalidVaaAccount)?;
+        require!(vaa.magic == Self::MAGIC, PaymentBridgeError::InvalidVaaAccount);
+        Ok(vaa)
+    }
+}
+
+/// Bridge settings and lamport treasury (singleton PDA).
+///
+/// The account's lamports above rent exemption are the liquidity redemptions
+/// are paid from; anyone may top it up with a plain system transfer.
+#[account]
+#[derive(Default)]
+pub struct PaymentBridgeConfig {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Authority allowed to change the settings
+    pub governance: Pubkey,
+    /// Wormhole core bridge whose posted VAAs are accepted
+    pub wormhole_program: Pubkey,
+    /// Wormhole chain id of the payment contract
+    pub emitter_chain: u16,
+    /// Payment contract address, left-padded to 32 bytes
+    pub emitter_address: [u8; 32],
+    /// Lamports credited per whole token of each asset
+    pub rates: [u64; PAYMENT_ASSET_COUNT],
+    /// Lamports credited over the bridge's lifetime
+    pub total_credited: u64,
+    /// Last update unix timestamp
+    pub updated_at: i64,
+}
+
+impl PaymentBridgeConfig {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        32 + // wormhole_program
+        2 +  // emitter_chain
+        32 + // emitter_address
+        8 * PAYMENT_ASSET_COUNT + // rates
+        8 +  // total_credited
+        8;   // updated_at
+
+    /// Accept only messages from the registered payment contract
+    pub fn check_emitter(&self, vaa: &PostedVaa) -> Result<()> {
+        require!(
+            vaa.emitter_chain == self.emitter_chain && vaa.emitter_address == self.emitter_address,
+            PaymentBridgeError::UnknownEmitter
+        );
+        Ok(())
+    }
+
+    /// Lamports credited for `payment`, rounded down
+    pub fn credit_for(&self, payment: &PaymentPayload) -> Result<u64> {
+        let rate = self.rates[payment.asset as usize];
+        require!(rate > 0, PaymentBridgeError::UnsupportedAsset);
+        let lamports = payment
+            .amount
+            .checked_mul(rate as u128)
+            .ok_or(PaymentBridgeError::AmountOverflow)?
+            / 10u128.pow(payment.asset.decimals());
+        require!(lamports > 0, PaymentBridgeError::PaymentTooSmall);
+        u64::try_from(lamports).map_err(|_| PaymentBridgeError::AmountOverflow.into())
+    }
+}
+
+/// Marker recording that a payment VAA was redeemed; its existence blocks replays
+#[account]
+#[derive(Default)]
+pub struct RedeemedPayment {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Account credited
+    pub recipient: Pubkey,
+    /// Lamports credited
+    pub lamports: u64,
+    /// Redemption unix timestamp
+    pub redeemed_at: i64,
+}
+
+impl RedeemedPayment {
+    /// Account space calculation
+    pub const LEN: usize = 8 + 1 + 32 + 8 + 8;
+}
+
+/// Lamports a user has prepaid for task escrow; held by this account
+#[account]
+#[derive(Default)]
+pub struct PrepaidBalance {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// User the balance belongs to
+    pub owner: Pubkey,
+    /// Lamports available for escrow
+    pub balance: u64,
+    /// Lamports ever credited
+    pub total_credited: u64,
+    /// Last credit or draw unix timestamp
+    pub updated_at: i64,
+}
+
+impl PrepaidBalance {
+    /// Account space calculation
+    pub const LEN: usize = 8 + 1 + 32 + 8 + 8 + 8;
+
+    pub fn credit(&mut self, lamports: u64) -> Result<()> {
+        self.balance = self
+            .balance
+            .checked_add(lamports)
+            .ok_or(PaymentBridgeError::AmountOverflow)?;
+        self.total_credited = self.total_credited.saturating_add(lamports);
+        Ok(())
+    }
+
+    pub fn debit(&mut self, lamports: u64) -> Result<()> {
+        self.balance = self
+            .balance
+            .checked_sub(lamports)
+            .ok_or(PaymentBridgeError::InsufficientPrepaidBalance)?;
+        Ok(())
+    }
+}
+
+/// Move `lamports` of prepaid balance into a program-owned escrow account
+pub fn draw_prepaid<'info>(
+    prepaid: &mut Account<'info, PrepaidBalance>,
+    escrow: &AccountInfo<'info>,
+    lamports: u64,
+) -> Result<()> {
+    prepaid.debit(lamports)?;
+    prepaid.updated_at = env::now()?;
+    let source = prepaid.to_account_info();
+    **source.try_borrow_mut_lamports()? -= lamports;
+    **escrow.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+#[error_code]
+pub enum PaymentBridgeError {
+    #[msg("Malformed payment payload")]
+    InvalidPayload,
+    #[msg("Asset not accepted by the payment bridge")]
+    UnsupportedAsset,
+    #[msg("Payment amount overflows")]
+    AmountOverflow,
+    #[msg("Payment converts to zero lamports")]
+    PaymentTooSmall,
+    #[msg("Account is not a posted Wormhole VAA")]
+    InvalidVaaAccount,
+    #[msg("VAA was not emitted by the payment contract")]
+    UnknownEmitter,
+    #[msg("Payment recipient does not match")]
+    RecipientMismatch,
+    #[msg("Payment bridge treasury cannot cover the credit")]
+    InsufficientLiquidity,
+    #[msg("Prepaid balance too low")]
+    InsufficientPrepaidBalance,
+    #[msg("Unauthorized payment bridge update")]
+    Unauthorized,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> PaymentBridgeConfig {
+        PaymentBridgeConfig {
+            emitter_chain: 2,
+            emitter_address: [7; 32],
+            // 1 ETH = 15 SOL, 1 USDC = 0.006 SOL
+            rates: [15_000_000_000, 6_000_000],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_payload_roundtrip_and_credit() {
+        let payment = PaymentPayload {
+            asset: PaymentAsset::Usdc,
+            amount: 25_000_000, // 25 USDC
+            recipient: Pubkey::new_unique(),
+            lock_id: 42,
+        };
+        let raw = payment.encode();
+        assert_eq!(raw.len(), PAYMENT_PAYLOAD_LEN);
+        assert_eq!(PaymentPayload::decode(&raw).unwrap(), payment);
+        assert_eq!(config().credit_for(&payment).unwrap(), 150_000_000);
+
+        let eth = PaymentPayload { asset: PaymentAsset::Eth, amount: 10u128.pow(17), ..payment };
+        assert_eq!(config().credit_for(&eth).unwrap(), 1_500_000_000);
+        let dust = PaymentPayload { asset: PaymentAsset::Eth, amount: 1, ..payment };
+        assert!(config().credit_for(&dust).is_err());
+    }
+
+    #[test]
+    fn test_malformed_payloads_rejected() {
+        let mut raw = PaymentPayload {
+            asset: PaymentAsset::Eth,
+            amount: 1,
+            recipient: Pubkey::default(),
+            lock_id: 0,
+        }
+        .encode();
+
+        assert!(PaymentPayload::decode(&raw[..raw.len() - 1]).is_err());
+        raw[2] = 1; // amount above u128
+        assert!(PaymentPayload::decode(&raw).is_err());
+        raw[2] = 0;
+        raw[1] = 9;
+        assert!(PaymentPayload::decode(&raw).is_err());
+        raw[1] = 0;
+        raw[0] = 3;
+        assert!(PaymentPayload::decode(&raw).is_err());
+    }
+
+    #[test]
+    fn test_posted_vaa_layout_and_emitter() {
+        let vaa = PostedVaa {
+            magic: PostedVaa::MAGIC,
+            consistency_level: 1,
+            vaa_time: 1_700_000_000,
+            vaa_signature_account: Pubkey::new_unique(),
+            submission_time: 1_700_000_100,
+            nonce: 0,
+            sequence: 9,
+            emitter_chain: 2,
+            emitter_address: [7; 32],
+            payload: vec![PAYMENT_PAYLOAD_ID],
+        };
+        let raw = vaa.try_to_vec().unwrap();
+        assert_eq!(&raw[..4], b"vaa\x01");
+        assert_eq!(raw.len(), 4 + 1 + 4 + 32 + 4 + 4 + 8 + 2 + 32 + 4 + 1);
+        assert!(config().check_emitter(&vaa).is_ok());
+
+        let foreign = PostedVaa { emitter_address: [8; 32], ..vaa };
+        assert!(config().check_emitter(&foreign).is_err());
+    }
+
+    #[test]
+    fn test_prepaid_debit_bounded_by_balance() {
+        let mut prepaid = PrepaidBalance::default();
+        prepaid.credit(1_000).unwrap();
+        prepaid.debit(600).unwrap();
+        assert!(prepaid.debit(401).is_err());
+        assert_eq!(prepaid.balance, 400);
+        assert_eq!(prepaid.total_credited, 1_000);
+    }
+}
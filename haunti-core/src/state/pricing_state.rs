@@ -0,0 +1,197 @@
+//! Demand-driven per-model inference pricing (EIP-1559-style base fee)
+
+use anchor_lang::prelude::*;
+
+use super::task_state::TaskState;
+
+/// Per-model price oracle driven by the model's own task queue
+#[account]
+#[derive(Default)]
+pub struct ModelPricing {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Priced model account
+    pub model: Pubkey,
+    /// Current base fee per inference call (lamports)
+    pub base_fee: u64,
+    /// Base fee never decays below this floor
+    pub min_base_fee: u64,
+    /// Queue depth at which the price is stable
+    pub target_queue_depth: u32,
+    /// Inference tasks escrowed but not yet completed
+    pub queued_tasks: u32,
+    /// Unix timestamp of the last price update
+    pub last_update: i64,
+}
+
+impl ModelPricing {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // model
+        8 +  // base_fee
+        8 +  // min_base_fee
+        4 +  // target_queue_depth
+        4 +  // queued_tasks
+        8;   // last_update
+
+    /// Apply idle decay since the last update, then the congestion adjustment
+    pub fn update(&mut self, now: i64) -> Result<u64> {
+        let elapsed_epochs = (now - self.last_update).max(0) / PRICE_EPOCH_SECS;
+        let mut fee = self.base_fee as u128;
+
+        if self.queued_tasks == 0 {
+            // Idle models decay by 1/8 per epoch, as an empty block would
+            for _ in 0..elapsed_epochs.min(MAX_DECAY_EPOCHS) {
+                fee -= fee / BASE_FEE_CHANGE_DENOMINATOR as u128;
+            }
+        } else if elapsed_epochs > 0 {
+            let target = self.target_queue_depth.max(1) as u128;
+            let queued = self.queued_tasks as u128;
+            if queued > target {
+                let delta = fee * (queued - target) / target / BASE_FEE_CHANGE_DENOMINATOR as u128;
+                fee += delta.max(1);
+            } else {
+                fee -= fee * (target - queued) / target / BASE_FEE_CHANGE_DENOMINATOR as u128;
+            }
+        }
+
+        self.base_fee = u64::try_from(fee)
+            .map_err(|_| PricingError::PriceOverflow)?
+            .max(self.min_base_fee);
+        if elapsed_epochs > 0 {
+            self.last_update = now;
+        }
+
+        Ok(self.base_fee)
+    }
+
+    /// Reserve a queue slot for a new inference task
+    pub fn enqueue(&mut self) -> Result<()> {
+        self.queued_tasks = self
+            .queued_tasks
+            .checked_add(1)
+            .ok_or(PricingError::PriceOverflow)?;
+        Ok(())
+    }
+
+    /// Release a queue slot when a task completes, fails or is cancelled
+    pub fn dequeue(&mut self) {
+        self.queued_tasks = self.queued_tasks.saturating_sub(1);
+    }
+
+    /// Release the slot `task` holds in this queue, which lives at `address`
+    pub fn release(&mut self, address: &Pubkey, task: &mut TaskState) -> Result<()> {
+        let held = task.pricing.ok_or(PricingError::NoQueueSlot)?;
+        require_keys_eq!(held, *address, PricingError::PricingAccountMismatch);
+        task.pricing = None;
+        self.dequeue();
+        Ok(())
+    }
+}
+
+/// Release the queue slot of a task that just completed, failed, expired or
+/// was cancelled. Tasks created without demand pricing hold no slot and need
+/// no pricing account
+pub fn release_queue_slot(
+    task: &mut TaskState,
+    pricing: Option<&mut Account<ModelPricing>>,
+) -> Result<()> {
+    if task.pricing.is_none() {
+        return Ok(());
+    }
+    let pricing = pricing.ok_or(PricingError::PricingAccountRequired)?;
+    let address = pricing.key();
+    pricing.release(&address, task)
+}
+
+#[error_code]
+pub enum PricingError {
+    #[msg("Price computation overflow")]
+    PriceOverflow,
+    #[msg("Escrow exceeds caller's maximum fee")]
+    MaxFeeExceeded,
+    #[msg("Invalid pricing parameters")]
+    InvalidParameters,
+    #[msg("Task holds no pricing queue slot")]
+    NoQueueSlot,
+    #[msg("Task's pricing account must be passed to release its queue slot")]
+    PricingAccountRequired,
+    #[msg("Pricing account is not the one the task was queued in")]
+    PricingAccountMismatch,
+}
+
+/// Fee of a task served from an already-verified result, in basis points of
+/// the current base fee; the node's result cache charges the same
+pub const CACHED_RESULT_FEE_BPS: u64 = 1_000;
+
+/// Discounted fee for a cache hit when the base fee is `base_fee`
+pub fn cached_fee(base_fee: u64) -> Result<u64> {
+    base_fee
+        .checked_mul(CACHED_RESULT_FEE_BPS)
+        .map(|scaled| scaled / 10_000)
+        .ok_or_else(|| PricingError::PriceOverflow.into())
+}
+
+/// Length of one price adjustment step
+pub const PRICE_EPOCH_SECS: i64 = 30;
+/// Maximum relative change per epoch is 1/8, as in EIP-1559
+pub const BASE_FEE_CHANGE_DENOMINATOR: u64 = 8;
+/// Bound on decay iterations per update
+const MAX_DECAY_EPOCHS: i64 = 256;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pricing(queued: u32) -> ModelPricing {
+        ModelPricing {
+            base_fee: 80_000,
+            min_base_fee: 10_000,
+            target_queue_depth: 10,
+            queued_tasks: queued,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_congestion_raises_price() {
+        let mut p = pricing(20);
+        assert_eq!(p.update(PRICE_EPOCH_SECS).unwrap(), 90_000);
+        // Within the same epoch the price is stable
+        assert_eq!(p.update(PRICE_EPOCH_SECS + 1).unwrap(), 90_000);
+    }
+
+    #[test]
+    fn test_finished_tasks_bring_price_back_down() {
+        let address = Pubkey::new_unique();
+        let mut p = pricing(0);
+        let mut tasks: Vec<TaskState> = (0..20)
+            .map(|_| {
+                p.enqueue().unwrap();
+                TaskState { pricing: Some(address), ..Default::default() }
+            })
+            .collect();
+        assert_eq!(p.update(PRICE_EPOCH_SECS).unwrap(), 90_000);
+
+        for task in tasks.iter_mut().take(15) {
+            p.release(&address, task).unwrap();
+            assert_eq!(task.pricing, None);
+        }
+        assert_eq!(p.queued_tasks, 5);
+        // Below target depth the fee falls again
+        assert_eq!(p.update(PRICE_EPOCH_SECS * 2).unwrap(), 84_375);
+
+        // A slot is released once, and only to the queue that holds it
+        assert!(p.release(&address, &mut tasks[0]).is_err());
+        assert!(p.release(&Pubkey::new_unique(), &mut tasks[15]).is_err());
+        assert_eq!(p.queued_tasks, 5);
+    }
+
+    #[test]
+    fn test_idle_decay_respects_floor() {
+        let mut p = pricing(0);
+        assert_eq!(p.update(PRICE_EPOCH_SECS).unwrap(), 70_000);
+        assert_eq!(p.update(PRICE_EPOCH_SECS * 1_000).unwrap(), 10_000);
+    }
+}
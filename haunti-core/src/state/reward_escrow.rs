@@ -0,0 +1,119 @@
+//! SPL-token reward escrow
+//!
+//! Tasks pay workers in lamports by default. A task created with a reward mint
+//! instead escrows `reward` base units of that mint in the associated token
+//! account of (task PDA, mint); the task PDA is the only authority that can move
+//! them, and `submit_proof` releases the whole escrow before closing it. A task
+//! cancelled or expired before any proof lands hands the escrow back to its
+//! owner through [`refund_token_escrow`].
+
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::{self, CloseAccount, Token, TokenAccount, Transfer},
+};
+use crate::state::TaskAccount;
+
+/// Escrow token account of `task` for rewards in `mint`
+pub fn reward_escrow_address(task: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(task, mint)
+}
+
+/// Check that `account` holds `mint` and belongs to `authority`
+pub fn check_reward_account(
+    account: &TokenAccount,
+    mint: &Pubkey,
+    authority: &Pubkey,
+) -> Result<()> {
+    require_keys_eq!(account.mint, *mint, RewardEscrowError::MintMismatch);
+    require_keys_eq!(account.owner, *authority, RewardEscrowError::AuthorityMismatch);
+    Ok(())
+}
+
+/// Return every token in the escrow of `task` (PDA bump `bump`) to the owner's
+/// account for `mint` and close the escrow, sending its rent to the owner.
+/// Returns the tokens refunded
+pub fn refund_token_escrow<'info>(
+    task: &Account<'info, TaskAccount>,
+    bump: u8,
+    mint: Pubkey,
+    escrow: Option<&Account<'info, TokenAccount>>,
+    owner_account: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+    owner: AccountInfo<'info>,
+) -> Result<u64> {
+    let (Some(escrow), Some(owner_account), Some(token_program)) =
+        (escrow, owner_account, token_program)
+    else {
+        return err!(RewardEscrowError::MissingRewardAccounts);
+    };
+    require_keys_eq!(
+        escrow.key(),
+        reward_escrow_address(&task.key(), &mint),
+        RewardEscrowError::InvalidEscrow
+    );
+    check_reward_account(owner_account, &mint, &task.owner)?;
+
+    let model_hash = task.model.model_hash;
+    let seeds: &[&[u8]] = &[b"task", task.owner.as_ref(), model_hash.as_ref(), &[bump]];
+    let amount = escrow.amount;
+    if amount > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                Transfer {
+                    from: escrow.to_account_info(),
+                    to: owner_account.to_account_info(),
+                    authority: task.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+    }
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: escrow.to_account_info(),
+            destination: owner,
+            authority: task.to_account_info(),
+        },
+        &[seeds],
+    ))?;
+
+    Ok(amount)
+}
+
+#[error_code]
+pub enum RewardEscrowError {
+    #[msg("Token reward requires the escrow, token accounts and token programs")]
+    MissingRewardAccounts,
+    #[msg("Reward escrow is not the task's associated token account")]
+    InvalidEscrow,
+    #[msg("Token account is for a different mint")]
+    MintMismatch,
+    #[msg("Token account belongs to a different authority")]
+    AuthorityMismatch,
+    #[msg("Token rewards cannot be drawn from a prepaid balance")]
+    PrepaidNotSupported,
+    #[msg("Reward must be non-zero")]
+    InvalidReward,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escrow_is_per_task_and_mint() {
+        let (task, other_task, mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let escrow = reward_escrow_address(&task, &mint);
+        assert_eq!(escrow, get_associated_token_address(&task, &mint));
+        assert_ne!(escrow, reward_escrow_address(&other_task, &mint));
+        assert_ne!(escrow, reward_escrow_address(&task, &Pubkey::new_unique()));
+    }
+}
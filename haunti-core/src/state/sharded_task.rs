@@ -0,0 +1,294 @@
+//! Tasks split across workers by shard
+//!
+//! A sharded task (PDA of `[b"sharded_task", owner, input_hash]`) escrows one
+//! reward for `shard_count` independent slices of the same job. Workers claim
+//! shards one at a time and submit a proof for each, which is verified against
+//! the shard's statement before the chain keeps a commitment to it. Finalization checks a single aggregated
+//! (recursive) proof whose public input is the commitment over every proven
+//! shard, after which each proven shard is worth `reward / shard_count` to its
+//! worker and the share of unproven shards goes back to the owner.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use super::task_state::HEARTBEAT_TIMEOUT_SECS;
+
+/// Upper bound on shards per task, keeping the account under 3 KB
+pub const MAX_SHARDS: u16 = 32;
+/// Seconds a claimed shard stays reserved without a proof before another
+/// worker may claim it
+pub const SHARD_CLAIM_TIMEOUT_SECS: i64 = HEARTBEAT_TIMEOUT_SECS;
+
+/// Progress of a single shard
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShardStatus {
+    /// Waiting for a worker
+    #[default]
+    Open,
+    /// Reserved by a worker
+    Claimed { worker: Pubkey, claimed_at: i64 },
+    /// Proof submitted; `proof_hash` commits to the proof and shard output
+    Proven { worker: Pubkey, proof_hash: [u8; 32] },
+    /// Reward paid out after finalization
+    Paid { worker: Pubkey },
+}
+
+impl ShardStatus {
+    /// Max serialized size
+    pub const LEN: usize = 1 + // variant tag
+        32 + 32; // Proven state fields (worker + proof_hash)
+}
+
+#[account]
+#[derive(Default)]
+pub struct ShardedTask {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Task creator authority
+    pub owner: Pubkey,
+    /// Hash of encrypted input data
+    pub input_hash: [u8; 32],
+    /// Hash of expected model version
+    pub model_hash: [u8; 32],
+    /// Key the aggregated proof is verified with
+    pub verifier_key: Pubkey,
+    /// Verifier version the aggregated proof must be built for
+    pub verifier_version: u16,
+    /// Lamports escrowed for all shards
+    pub reward: u64,
+    /// Task creation unix timestamp
+    pub created_at: i64,
+    /// After this, shards can no longer be claimed and a partial result may be
+    /// finalized
+    pub deadline: i64,
+    /// Set once the aggregated proof is accepted
+    pub finalized_at: Option<i64>,
+    pub shards: Vec<ShardStatus>,
+}
+
+impl ShardedTask {
+    /// Account space for `shard_count` shards
+    pub const fn space(shard_count: u16) -> usize {
+        8 + // discriminator
+        1 +  // bump
+        32 + // owner
+        32 + // input_hash
+        32 + // model_hash
+        32 + // verifier_key
+        2 +  // verifier_version
+        8 +  // reward
+        8 +  // created_at
+        8 +  // deadline
+        1 + 8 + // finalized_at (option)
+        4 + shard_count as usize * ShardStatus::LEN // shards
+    }
+
+    /// Number of shards the task was split into
+    pub fn shard_count(&self) -> u16 {
+        self.shards.len() as u16
+    }
+
+    /// Reserve shard `index` for `worker`; a claim left without a proof for
+    /// `SHARD_CLAIM_TIMEOUT_SECS` may be taken over
+    pub fn claim(&mut self, index: u16, worker: Pubkey, now: i64) -> Result<()> {
+        require!(self.finalized_at.is_none(), ShardedTaskError::AlreadyFinalized);
+        require!(now <= self.deadline, ShardedTaskError::DeadlinePassed);
+        let shard = self.shard_mut(index)?;
+        match *shard {
+            ShardStatus::Open => {}
+            ShardStatus::Claimed { claimed_at, .. }
+                if now.saturating_sub(claimed_at) > SHARD_CLAIM_TIMEOUT_SECS => {}
+            _ => return err!(ShardedTaskError::ShardUnavailable),
+        }
+        *shard = ShardStatus::Claimed { worker, claimed_at: now };
+        Ok(())
+    }
+
+    /// Record the proof of a shard held by `worker`
+    pub fn prove(&mut self, index: u16, worker: &Pubkey, proof_hash: [u8; 32]) -> Result<()> {
+        require!(self.finalized_at.is_none(), ShardedTaskError::AlreadyFinalized);
+        let shard = self.shard_mut(index)?;
+        match *shard {
+            ShardStatus::Claimed { worker: holder, .. } if holder == *worker => {
+                *shard = ShardStatus::Proven { worker: holder, proof_hash };
+                Ok(())
+            }
+            _ => err!(ShardedTaskError::ShardNotClaimed),
+        }
+    }
+
+    /// Public input a shard proof is verified against: SHA-256 over the task
+    /// input hash, the shard index and the claimed output hash, so a proof
+    /// cannot be replayed for another shard, task or output
+    pub fn shard_statement(&self, index: u16, output_hash: &[u8; 32]) -> Result<[u8; 32]> {
+        require!(index < self.shard_count(), ShardedTaskError::InvalidShard);
+        Ok(hashv(&[&self.input_hash, &index.to_le_bytes(), output_hash]).to_bytes())
+    }
+
+    /// Shards with a proof, paid or not
+    pub fn proven(&self) -> u16 {
+        self.shards
+            .iter()
+            .filter(|s| matches!(s, ShardStatus::Proven { .. } | ShardStatus::Paid { .. }))
+            .count() as u16
+    }
+
+    /// Whether an aggregated proof may be submitted at `now`: every shard is
+    /// proven, or the deadline passed
+    pub fn can_finalize(&self, now: i64) -> bool {
+        self.finalized_at.is_none() && (self.proven() == self.shard_count() || now > self.deadline)
+    }
+
+    /// Public input of the aggregated proof: SHA-256 over `index || proof_hash`
+    /// of every proven shard, in shard order
+    pub fn aggregate_commitment(&self) -> [u8; 32] {
+        let indexed: Vec<([u8; 2], [u8; 32])> = self
+            .shards
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| match s {
+                ShardStatus::Proven { proof_hash, .. } => Some(((i as u16).to_le_bytes(), *proof_hash)),
+                _ => None,
+            })
+            .collect();
+        let parts: Vec<&[u8]> = indexed
+            .iter()
+            .flat_map(|(i, h)| [i.as_slice(), h.as_slice()])
+            .collect();
+        hashv(&parts).to_bytes()
+    }
+
+    /// Lamports each proven shard earns
+    pub fn shard_reward(&self) -> u64 {
+        self.reward / self.shard_count().max(1) as u64
+    }
+
+    /// Close the task to further shards; returns the owner's refund for the
+    /// shards left unproven, including rounding dust
+    pub fn finalize(&mut self, now: i64) -> Result<u64> {
+        require!(self.can_finalize(now), ShardedTaskError::NotFinalizable);
+        self.finalized_at = Some(now);
+        Ok(self.reward - self.shard_reward() * self.proven() as u64)
+    }
+
+    /// Mark the reward of shard `index` paid to `worker` and return it
+    pub fn pay(&mut self, index: u16, worker: &Pubkey) -> Result<u64> {
+        require!(self.finalized_at.is_some(), ShardedTaskError::NotFinalized);
+        let reward = self.shard_reward();
+        let shard = self.shard_mut(index)?;
+        match *shard {
+            ShardStatus::Proven { worker: holder, .. } if holder == *worker => {
+                *shard = ShardStatus::Paid { worker: holder };
+                Ok(reward)
+            }
+            _ => err!(ShardedTaskError::NothingToPay),
+        }
+    }
+
+    fn shard_mut(&mut self, index: u16) -> Result<&mut ShardStatus> {
+        self.shards
+            .get_mut(index as usize)
+            .ok_or_else(|| error!(ShardedTaskError::InvalidShard))
+    }
+}
+
+#[error_code]
+pub enum ShardedTaskError {
+    #[msg("Shard count must be between 1 and MAX_SHARDS")]
+    InvalidShardCount,
+    #[msg("Shard index out of range")]
+    InvalidShard,
+    #[msg("Shard is already claimed or proven")]
+    ShardUnavailable,
+    #[msg("Shard is not claimed by this worker")]
+    ShardNotClaimed,
+    #[msg("Shard deadline has passed")]
+    DeadlinePassed,
+    #[msg("Sharded task is already finalized")]
+    AlreadyFinalized,
+    #[msg("Shards are still outstanding and the deadline has not passed")]
+    NotFinalizable,
+    #[msg("Sharded task is not finalized")]
+    NotFinalized,
+    #[msg("No unpaid proven shard for this worker")]
+    NothingToPay,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(shard_count: u16) -> ShardedTask {
+        ShardedTask {
+            reward: 1_000,
+            deadline: 10_000,
+            shards: vec![ShardStatus::Open; shard_count as usize],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stale_claim_taken_over() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut task = task(2);
+        task.claim(0, a, 100).unwrap();
+        assert!(task.claim(0, b, 100 + SHARD_CLAIM_TIMEOUT_SECS).is_err());
+        task.claim(0, b, 101 + SHARD_CLAIM_TIMEOUT_SECS).unwrap();
+        assert!(task.prove(0, &a, [1; 32]).is_err());
+        task.prove(0, &b, [1; 32]).unwrap();
+        assert!(task.claim(0, a, 5_000).is_err());
+        assert!(task.claim(2, a, 5_000).is_err());
+        assert!(task.claim(1, a, 10_001).is_err());
+    }
+
+    #[test]
+    fn test_partial_finalization_pays_proportionally() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut task = task(3);
+        task.claim(0, a, 0).unwrap();
+        task.claim(2, b, 0).unwrap();
+        task.prove(0, &a, [1; 32]).unwrap();
+        task.prove(2, &b, [2; 32]).unwrap();
+
+        let commitment = task.aggregate_commitment();
+        assert_eq!(
+            commitment,
+            hashv(&[&0u16.to_le_bytes(), &[1; 32], &2u16.to_le_bytes(), &[2; 32]]).to_bytes()
+        );
+
+        // Shard 1 is still open, so only a passed deadline allows finalizing
+        assert!(task.finalize(10_000).is_err());
+        assert_eq!(task.finalize(10_001).unwrap(), 1_000 - 2 * 333);
+        assert!(task.prove(1, &a, [3; 32]).is_err());
+
+        assert_eq!(task.pay(0, &a).unwrap(), 333);
+        assert!(task.pay(0, &a).is_err());
+        assert!(task.pay(2, &a).is_err());
+        assert_eq!(task.pay(2, &b).unwrap(), 333);
+        // Paid shards still count as proven
+        assert_eq!(task.proven(), 2);
+    }
+
+    #[test]
+    fn test_shard_statement_binds_shard_and_output() {
+        let task = task(2);
+        let statement = task.shard_statement(0, &[1; 32]).unwrap();
+        assert_ne!(statement, task.shard_statement(1, &[1; 32]).unwrap());
+        assert_ne!(statement, task.shard_statement(0, &[2; 32]).unwrap());
+        let other = ShardedTask { input_hash: [7; 32], ..task.clone() };
+        assert_ne!(statement, other.shard_statement(0, &[1; 32]).unwrap());
+        assert!(task.shard_statement(2, &[1; 32]).is_err());
+    }
+
+    #[test]
+    fn test_space_fits_max_shards() {
+        let mut task = task(MAX_SHARDS);
+        task.shards.fill(ShardStatus::Proven {
+            worker: Pubkey::new_unique(),
+            proof_hash: [9; 32],
+        });
+        task.finalized_at = Some(0);
+        assert!(task.try_to_vec().unwrap().len() + 8 <= ShardedTask::space(MAX_SHARDS));
+    }
+}
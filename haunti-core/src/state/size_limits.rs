@@ -0,0 +1,116 @@
+//! Governance-tunable payload size limits (singleton PDA)
+//!
+//! Starts at the protocol caps in `crate::limits` and may only be tightened, so
+//! every limit stored here is also accepted by programs that check the caps alone.
+
+use anchor_lang::prelude::*;
+
+use crate::limits::{
+    check_len, SizeLimitError, MAX_ENCRYPTED_INPUT_LEN, MAX_ENCRYPTED_OUTPUT_LEN, MAX_PROOF_LEN,
+};
+
+/// Payload size limits enforced by haunti-core instructions
+#[account]
+#[derive(Default)]
+pub struct SizeLimits {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Authority allowed to change the limits
+    pub governance: Pubkey,
+    /// Maximum serialized proof length
+    pub max_proof_len: u32,
+    /// Maximum encrypted task input length
+    pub max_encrypted_input_len: u32,
+    /// Maximum encrypted task output length
+    pub max_encrypted_output_len: u32,
+    /// Last update unix timestamp
+    pub updated_at: i64,
+}
+
+impl SizeLimits {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        4 +  // max_proof_len
+        4 +  // max_encrypted_input_len
+        4 +  // max_encrypted_output_len
+        8;   // updated_at
+
+    /// Set all limits, rejecting zero or anything above the protocol caps
+    pub fn set(&mut self, proof: u32, input: u32, output: u32) -> Result<()> {
+        for (limit, cap) in [
+            (proof, MAX_PROOF_LEN),
+            (input, MAX_ENCRYPTED_INPUT_LEN),
+            (output, MAX_ENCRYPTED_OUTPUT_LEN),
+        ] {
+            require!(
+                limit > 0 && limit as usize <= cap,
+                SizeLimitError::InvalidLimits
+            );
+        }
+        self.max_proof_len = proof;
+        self.max_encrypted_input_len = input;
+        self.max_encrypted_output_len = output;
+        Ok(())
+    }
+
+    /// Check a serialized proof
+    pub fn check_proof(&self, len: usize) -> Result<()> {
+        check_len(len, self.max_proof_len as usize, SizeLimitError::ProofTooLarge)
+    }
+
+    /// Check an encrypted task input
+    pub fn check_input(&self, len: usize) -> Result<()> {
+        check_len(
+            len,
+            self.max_encrypted_input_len as usize,
+            SizeLimitError::InputTooLarge,
+        )
+    }
+
+    /// Check an encrypted task output
+    pub fn check_output(&self, len: usize) -> Result<()> {
+        check_len(
+            len,
+            self.max_encrypted_output_len as usize,
+            SizeLimitError::OutputTooLarge,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tightened_limits_checked_at_boundary() {
+        let mut limits = SizeLimits::default();
+        limits.set(2_048, 512, 256).unwrap();
+
+        assert!(limits.check_proof(2_048).is_ok());
+        assert!(limits.check_proof(2_049).is_err());
+        assert!(limits.check_input(512).is_ok());
+        assert!(limits.check_input(513).is_err());
+        assert!(limits.check_output(256).is_ok());
+        assert_eq!(
+            limits.check_output(257).unwrap_err(),
+            SizeLimitError::OutputTooLarge.into()
+        );
+    }
+
+    #[test]
+    fn test_limits_cannot_exceed_protocol_caps() {
+        let mut limits = SizeLimits::default();
+        let (proof, input, output) = (
+            MAX_PROOF_LEN as u32,
+            MAX_ENCRYPTED_INPUT_LEN as u32,
+            MAX_ENCRYPTED_OUTPUT_LEN as u32,
+        );
+        limits.set(proof, input, output).unwrap();
+        assert!(limits.set(proof + 1, input, output).is_err());
+        assert!(limits.set(proof, input, output + 1).is_err());
+        assert!(limits.set(proof, 0, output).is_err());
+        assert_eq!(limits.max_encrypted_input_len, input);
+    }
+}
@@ -0,0 +1,237 @@
+//! Governance-signed network statistics checkpoints with merkle commitments
+
+use anchor_lang::{prelude::*, solana_program::keccak::hashv};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Domain separators so a leaf can never be reinterpreted as an inner node
+const LEAF_PREFIX: &[u8] = &[0x00];
+const NODE_PREFIX: &[u8] = &[0x01];
+
+/// Cumulative headline statistics of the network
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    /// Tasks completed since genesis
+    pub tasks_completed: u64,
+    /// Proofs verified since genesis
+    pub proofs_verified: u64,
+    /// Rewards paid since genesis (lamports)
+    pub rewards_paid: u64,
+    /// Workers active during the checkpoint epoch
+    pub active_workers: u32,
+}
+
+impl NetworkStats {
+    /// Serialized size
+    pub const LEN: usize = 8 + 8 + 8 + 4;
+
+    /// Cumulative counters may never go backwards between checkpoints
+    pub fn is_successor_of(&self, previous: &NetworkStats) -> bool {
+        self.tasks_completed >= previous.tasks_completed
+            && self.proofs_verified >= previous.proofs_verified
+            && self.rewards_paid >= previous.rewards_paid
+    }
+}
+
+/// Publisher configuration and the latest checkpoint's totals (PDA-based)
+#[account]
+#[derive(Default)]
+pub struct StatsConfig {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Governance authority allowed to publish checkpoints
+    pub governance: Pubkey,
+    /// Nominated successor, effective once it accepts
+    pub pending_governance: Option<Pubkey>,
+    /// Epoch of the latest checkpoint (0 before the first)
+    pub last_epoch: u64,
+    /// Totals of the latest checkpoint
+    pub last_stats: NetworkStats,
+}
+
+impl StatsConfig {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        33 + // pending_governance
+        8 +  // last_epoch
+        NetworkStats::LEN;
+
+    /// Second step of a governance rotation; returns the previous authority
+    pub fn accept_governance(&mut self, nominee: Pubkey) -> Result<Pubkey> {
+        require!(self.pending_governance.is_some(), StatsError::NoPendingTransfer);
+        require!(
+            self.pending_governance == Some(nominee),
+            StatsError::Unauthorized
+        );
+        let previous = std::mem::replace(&mut self.governance, nominee);
+        self.pending_governance = None;
+        Ok(previous)
+    }
+}
+
+/// One epoch's statistics, committed to the underlying records (PDA-based)
+#[account]
+#[derive(Default)]
+pub struct StatsCheckpoint {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Checkpoint sequence number, starting at 1
+    pub epoch: u64,
+    /// Headline statistics
+    pub stats: NetworkStats,
+    /// Merkle root over the task/proof/reward records behind `stats`
+    pub records_root: [u8; 32],
+    /// Number of leaves under `records_root`
+    pub record_count: u64,
+    /// Signer that published the checkpoint
+    pub publisher: Pubkey,
+    /// Publication unix timestamp
+    pub published_at: i64,
+}
+
+impl StatsCheckpoint {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        8 +  // epoch
+        NetworkStats::LEN +
+        32 + // records_root
+        8 +  // record_count
+        32 + // publisher
+        8;   // published_at
+
+    /// Check that `record` is leaf `index` under this checkpoint's root
+    pub fn verify_record(&self, record: &[u8], index: u64, proof: &[[u8; 32]]) -> bool {
+        index < self.record_count
+            && merkle_root_from_proof(leaf_hash(record), index, self.record_count, proof)
+                == Some(self.records_root)
+    }
+}
+
+/// Hash of a serialized record as a merkle leaf
+pub fn leaf_hash(record: &[u8]) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, record]).0
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[NODE_PREFIX, left, right]).0
+}
+
+/// Pair up nodes; an odd trailing node is promoted unchanged
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Merkle root over leaf hashes
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return [0u8; 32];
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Sibling path for leaf `index`; promoted levels contribute no sibling
+pub fn merkle_proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            proof.push(level[sibling]);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    proof
+}
+
+/// Fold a sibling path back up to the root of a tree with `leaf_count` leaves
+fn merkle_root_from_proof(
+    leaf: [u8; 32],
+    mut index: u64,
+    leaf_count: u64,
+    proof: &[[u8; 32]],
+) -> Option<[u8; 32]> {
+    let mut hash = leaf;
+    let mut width = leaf_count;
+    let mut siblings = proof.iter();
+    while width > 1 {
+        // The last node of an odd-width level is promoted without a sibling
+        if index ^ 1 < width {
+            let sibling = siblings.next()?;
+            hash = if index % 2 == 0 {
+                node_hash(&hash, sibling)
+            } else {
+                node_hash(sibling, &hash)
+            };
+        }
+        index /= 2;
+        width = (width + 1) / 2;
+    }
+    siblings.next().is_none().then_some(hash)
+}
+
+#[error_code]
+pub enum StatsError {
+    #[msg("Checkpoint epoch must follow the previous one")]
+    EpochOutOfOrder,
+    #[msg("Cumulative statistics decreased")]
+    StatsRegressed,
+    #[msg("Records root does not cover any records")]
+    EmptyRecords,
+    #[msg("Signer is not the governance authority")]
+    Unauthorized,
+    #[msg("No governance transfer is pending")]
+    NoPendingTransfer,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkle_proofs_verify() {
+        let records: Vec<Vec<u8>> = (0u8..5).map(|i| vec![i; 8]).collect();
+        let leaves: Vec<[u8; 32]> = records.iter().map(|r| leaf_hash(r)).collect();
+        let checkpoint = StatsCheckpoint {
+            records_root: merkle_root(&leaves),
+            record_count: leaves.len() as u64,
+            ..Default::default()
+        };
+
+        for (i, record) in records.iter().enumerate() {
+            let proof = merkle_proof(&leaves, i);
+            assert!(checkpoint.verify_record(record, i as u64, &proof));
+        }
+        let proof = merkle_proof(&leaves, 1);
+        assert!(!checkpoint.verify_record(&records[2], 1, &proof));
+    }
+
+    #[test]
+    fn test_governance_rotation_requires_nominee_acceptance() {
+        let (old, new) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut config = StatsConfig {
+            governance: old,
+            ..Default::default()
+        };
+        assert!(config.accept_governance(new).is_err());
+
+        config.pending_governance = Some(new);
+        assert!(config.accept_governance(Pubkey::new_unique()).is_err());
+        assert_eq!(config.accept_governance(new).unwrap(), old);
+        assert_eq!(config.governance, new);
+        assert_eq!(config.pending_governance, None);
+    }
+}
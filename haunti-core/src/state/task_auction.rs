@@ -0,0 +1,183 @@
+//! Price bidding on pending tasks
+//!
+//! Instead of the first worker to show up taking a task at the escrowed price,
+//! the owner may open an auction (PDA of `[b"auction", task]`). Workers bid the
+//! price they will run the task for, optionally promising a completion
+//! deadline, and may revise their bid while bidding is open. The owner accepts
+//! any bid; the accepted price becomes the task's reward, with the escrow
+//! topped up by or refunded to the owner, and the winner starts the task.
+
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Bids held per auction
+pub const MAX_BIDS: usize = 16;
+/// Longest bidding window an owner may open
+pub const MAX_BIDDING_SECS: i64 = 86_400;
+
+/// One worker's standing offer
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Bid {
+    pub worker: Pubkey,
+    /// Lamports the worker asks for the task
+    pub price: u64,
+    /// Unix timestamp the worker commits to finish by
+    pub deadline: Option<i64>,
+    pub placed_at: i64,
+}
+
+impl Bid {
+    pub const LEN: usize = 32 + // worker
+        8 +     // price
+        1 + 8 + // deadline (option)
+        8;      // placed_at
+}
+
+#[account]
+#[derive(Default)]
+pub struct TaskAuction {
+    /// Bump seed for PDA
+    pub bump: u8,
+    pub task: Pubkey,
+    /// Task owner, the only one who may accept
+    pub owner: Pubkey,
+    /// Highest price the owner will consider
+    pub max_price: u64,
+    pub opened_at: i64,
+    /// No bids are taken after this
+    pub closes_at: i64,
+    pub bids: Vec<Bid>,
+}
+
+impl TaskAuction {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // task
+        32 + // owner
+        8 +  // max_price
+        8 +  // opened_at
+        8 +  // closes_at
+        4 + MAX_BIDS * Bid::LEN; // bids
+
+    /// Place or revise `bid`. Once the book is full, a new bidder must
+    /// undercut the most expensive bid, which it replaces
+    pub fn place(&mut self, bid: Bid) -> Result<()> {
+        require!(bid.placed_at <= self.closes_at, AuctionError::BiddingClosed);
+        require!(
+            bid.price > 0 && bid.price <= self.max_price,
+            AuctionError::InvalidBid
+        );
+        require!(
+            bid.deadline.map_or(true, |d| d > bid.placed_at),
+            AuctionError::InvalidBid
+        );
+
+        if let Some(existing) = self.bids.iter_mut().find(|b| b.worker == bid.worker) {
+            *existing = bid;
+            return Ok(());
+        }
+        if self.bids.len() < MAX_BIDS {
+            self.bids.push(bid);
+            return Ok(());
+        }
+        let (worst, _) = self
+            .bids
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, b)| b.price)
+            .ok_or(AuctionError::InvalidBid)?;
+        require!(bid.price < self.bids[worst].price, AuctionError::BookFull);
+        self.bids[worst] = bid;
+        Ok(())
+    }
+
+    /// Bid of `worker`, if it is still standing and its deadline is ahead of `now`
+    pub fn acceptable(&self, worker: &Pubkey, now: i64) -> Result<Bid> {
+        let bid = self
+            .bids
+            .iter()
+            .find(|b| b.worker == *worker)
+            .copied()
+            .ok_or(AuctionError::BidNotFound)?;
+        require!(
+            bid.deadline.map_or(true, |d| d > now),
+            AuctionError::BidDeadlinePassed
+        );
+        Ok(bid)
+    }
+}
+
+#[error_code]
+pub enum AuctionError {
+    #[msg("Bidding window out of range")]
+    InvalidBiddingWindow,
+    #[msg("Only lamport-escrowed pending tasks can be auctioned")]
+    TaskNotAuctionable,
+    #[msg("Bidding has closed")]
+    BiddingClosed,
+    #[msg("Bid price or deadline out of range")]
+    InvalidBid,
+    #[msg("Bid book is full and the bid does not undercut it")]
+    BookFull,
+    #[msg("No bid from this worker")]
+    BidNotFound,
+    #[msg("Bid deadline has passed")]
+    BidDeadlinePassed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auction() -> TaskAuction {
+        TaskAuction {
+            max_price: 1_000,
+            closes_at: 100,
+            ..Default::default()
+        }
+    }
+
+    fn bid(worker: Pubkey, price: u64) -> Bid {
+        Bid { worker, price, deadline: None, placed_at: 10 }
+    }
+
+    #[test]
+    fn test_revised_bid_replaces_earlier_one() {
+        let worker = Pubkey::new_unique();
+        let mut auction = auction();
+        auction.place(bid(worker, 900)).unwrap();
+        auction.place(bid(worker, 700)).unwrap();
+        assert_eq!(auction.bids.len(), 1);
+        assert_eq!(auction.acceptable(&worker, 50).unwrap().price, 700);
+
+        assert!(auction.place(bid(worker, 1_001)).is_err());
+        assert!(auction.place(Bid { placed_at: 101, ..bid(worker, 500) }).is_err());
+        assert!(auction.place(Bid { deadline: Some(10), ..bid(worker, 500) }).is_err());
+    }
+
+    #[test]
+    fn test_full_book_evicts_most_expensive_bid() {
+        let mut auction = auction();
+        let workers: Vec<Pubkey> = (0..MAX_BIDS).map(|_| Pubkey::new_unique()).collect();
+        for (i, worker) in workers.iter().enumerate() {
+            auction.place(bid(*worker, 500 + i as u64)).unwrap();
+        }
+        let latecomer = Pubkey::new_unique();
+        assert!(auction.place(bid(latecomer, 500 + MAX_BIDS as u64)).is_err());
+
+        auction.place(bid(latecomer, 400)).unwrap();
+        assert_eq!(auction.bids.len(), MAX_BIDS);
+        assert!(auction.acceptable(workers.last().unwrap(), 50).is_err());
+        assert_eq!(auction.acceptable(&latecomer, 50).unwrap().price, 400);
+    }
+
+    #[test]
+    fn test_expired_deadline_not_acceptable() {
+        let worker = Pubkey::new_unique();
+        let mut auction = auction();
+        auction.place(Bid { deadline: Some(60), ..bid(worker, 500) }).unwrap();
+        assert!(auction.acceptable(&worker, 59).is_ok());
+        assert!(auction.acceptable(&worker, 60).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Sequenced feed of task lifecycle events
+//!
+//! Every task state transition emits one `TaskFeedEvent` stamped with the next
+//! value of the program-wide `EventCounter` (singleton PDA of
+//! `[b"event_counter"]`). Sequence numbers have no holes on-chain, so an indexer
+//! that sees 41 followed by 43 knows it lost an event to a dropped subscription
+//! or truncated log, and can replay transactions from the slot of the last event
+//! it trusts. Comparing its cursor with the counter account tells it whether it
+//! is caught up at all.
+//!
+//! Every feed-emitting instruction writes the counter, so they serialize against
+//! each other; the counter is deliberately a single account so the order is total.
+
+use anchor_lang::prelude::*;
+use crate::env;
+
+/// Program-wide event sequence
+#[account]
+#[derive(Default)]
+pub struct EventCounter {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Sequence of the last emitted event; zero before the first
+    pub sequence: u64,
+    /// Slot of the last emitted event
+    pub last_slot: u64,
+}
+
+impl EventCounter {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 + // bump
+        8 + // sequence
+        8;  // last_slot
+
+    /// Reserve the sequence number of an event emitted at `slot`
+    pub fn next(&mut self, slot: u64) -> u64 {
+        self.sequence += 1;
+        self.last_slot = slot;
+        self.sequence
+    }
+
+    /// Emit the next event of the feed
+    pub fn emit(&mut self, task: Pubkey, kind: TaskFeedKind, actor: Pubkey, amount: u64) -> Result<()> {
+        let clock = env::clock()?;
+        emit!(TaskFeedEvent {
+            sequence: self.next(clock.slot),
+            task,
+            kind,
+            actor,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+/// Task transition reported by a `TaskFeedEvent`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskFeedKind {
+    Created,
+    Claimed,
+    Heartbeat,
+    Completed,
+    Failed,
+    Cancelled,
+    Refunded,
+    /// A stale worker lost the task and it went back to pending
+    Reassigned,
+}
+
+#[event]
+pub struct TaskFeedEvent {
+    /// Position in the program-wide feed, starting at 1
+    pub sequence: u64,
+    pub task: Pubkey,
+    pub kind: TaskFeedKind,
+    /// Account behind the transition: owner, worker, keeper or arbitrator
+    pub actor: Pubkey,
+    /// Escrow for `Created`, worker payout for `Completed`, amount returned for
+    /// `Refunded`, bond slashed for `Reassigned`; zero otherwise
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Indexer-side position in the feed
+#[cfg(not(target_os = "solana"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeedCursor {
+    /// Last sequence processed
+    pub sequence: u64,
+}
+
+/// Sequences missing between two observed events, inclusive
+#[cfg(not(target_os = "solana"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeedGap {
+    pub from: u64,
+    pub to: u64,
+}
+
+#[cfg(not(target_os = "solana"))]
+impl FeedCursor {
+    /// Advance past `sequence`. Replayed events at or behind the cursor are
+    /// ignored (`Ok(false)`); events past the next one report the gap to
+    /// backfill and still advance the cursor
+    pub fn observe(&mut self, sequence: u64) -> std::result::Result<bool, FeedGap> {
+        if sequence <= self.sequence {
+            return Ok(false);
+        }
+        let expected = self.sequence + 1;
+        self.sequence = sequence;
+        if sequence == expected {
+            Ok(true)
+        } else {
+            Err(FeedGap { from: expected, to: sequence - 1 })
+        }
+    }
+
+    /// Events emitted on-chain that the cursor has not reached
+    pub fn lag(&self, counter: &EventCounter) -> u64 {
+        counter.sequence.saturating_sub(self.sequence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence_starts_at_one_and_is_gapless() {
+        let mut counter = EventCounter::default();
+        let sequences: Vec<u64> = (10..15).map(|slot| counter.next(slot)).collect();
+        assert_eq!(sequences, [1, 2, 3, 4, 5]);
+        assert_eq!(counter.last_slot, 14);
+    }
+
+    #[test]
+    fn test_cursor_reports_gaps_and_ignores_replays() {
+        let mut counter = EventCounter::default();
+        let mut cursor = FeedCursor::default();
+        for slot in 0..5 {
+            counter.next(slot);
+        }
+
+        assert_eq!(cursor.observe(1), Ok(true));
+        assert_eq!(cursor.observe(4), Err(FeedGap { from: 2, to: 3 }));
+        assert_eq!(cursor.lag(&counter), 1);
+        // Backfilled events arrive behind the cursor
+        assert_eq!(cursor.observe(2), Ok(false));
+        assert_eq!(cursor.observe(5), Ok(true));
+        assert_eq!(cursor.lag(&counter), 0);
+    }
+}
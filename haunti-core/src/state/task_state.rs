@@ -1,10 +1,28 @@
 //! Task state machine and account definitions
 
-use anchor_lang::prelude::*;
-use anchor_lang::solana_program::clock;
+use anchor_lang::{prelude::*, solana_program::keccak};
+use crate::env;
 use borsh::{BorshDeserialize, BorshSerialize};
 use std::convert::TryFrom;
 
+use super::model_state::ModelState;
+use super::transitions::{self, TransitionTable};
+
+/// Seconds without a heartbeat after which anyone may report a running task
+pub const HEARTBEAT_TIMEOUT_SECS: i64 = 600;
+/// Seconds a task may wait for a worker before anyone may expire it
+pub const PICKUP_TIMEOUT_SECS: i64 = 86_400;
+/// `Failed::error_code` recorded when a task is reported for a missed heartbeat
+pub const ERROR_HEARTBEAT_TIMEOUT: u32 = 1;
+/// `Failed::error_code` recorded when a task runs past its time limit without a proof
+pub const ERROR_TIME_LIMIT: u32 = 2;
+/// `Failed::error_code` recorded when arbitration overturns a provisional result
+pub const ERROR_RESULT_OVERTURNED: u32 = 3;
+/// Domain separator of task RNG seeds
+pub const RNG_SEED_DOMAIN: &[u8] = b"haunti-task-rng";
+/// Domain separator of the recorded enclave attestation hash
+pub const ATTESTATION_DOMAIN: &[u8] = b"haunti-attestation-v1";
+
 /// Task lifecycle states
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
 pub enum TaskStatus {
@@ -16,6 +34,18 @@ pub enum TaskStatus {
         started_at: i64,
         last_heartbeat: i64,
     },
+    /// Result posted without on-chain verification, open to challenge
+    Provisional {
+        worker: Pubkey,
+        result_hash: [u8; 32],
+        submitted_at: i64,
+    },
+    /// Provisional result under challenge, awaiting arbitration
+    Disputed {
+        worker: Pubkey,
+        result_hash: [u8; 32],
+        challenged_at: i64,
+    },
     /// Successfully completed with proof
     Completed {
         result_hash: [u8; 32],
@@ -26,7 +56,7 @@ pub enum TaskStatus {
         error_code: u32,
         failed_at: i64,
     },
-    /// Cancelled by owner
+    /// Cancelled by owner, before or during execution
     Cancelled {
         cancelled_at: i64,
     },
@@ -38,6 +68,83 @@ impl Default for TaskStatus {
     }
 }
 
+/// Discriminant-only view of `TaskStatus` used by the transition table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TaskStatusKind {
+    Pending,
+    Running,
+    Provisional,
+    Disputed,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl TaskStatusKind {
+    /// Every state, in diagram order
+    pub const ALL: [Self; 7] = [
+        Self::Pending,
+        Self::Running,
+        Self::Provisional,
+        Self::Disputed,
+        Self::Completed,
+        Self::Failed,
+        Self::Cancelled,
+    ];
+
+    /// Allowed transitions; `Running -> Running` is a progress heartbeat and
+    /// `Running -> Pending` a reassignment away from a stale worker. Optimistic
+    /// results go `Running -> Provisional`, then settle once the challenge
+    /// window closes or arbitration rules on a challenge
+    pub const TRANSITIONS: TransitionTable<Self> = &[
+        (Self::Pending, Self::Running),
+        (Self::Pending, Self::Cancelled),
+        (Self::Running, Self::Pending),
+        (Self::Running, Self::Running),
+        (Self::Running, Self::Completed),
+        (Self::Running, Self::Failed),
+        (Self::Running, Self::Cancelled),
+        (Self::Running, Self::Provisional),
+        (Self::Provisional, Self::Disputed),
+        (Self::Provisional, Self::Completed),
+        (Self::Disputed, Self::Completed),
+        (Self::Disputed, Self::Failed),
+    ];
+
+    /// Whether `self -> next` is an allowed edge
+    pub fn can_transition_to(self, next: Self) -> bool {
+        transitions::is_allowed(Self::TRANSITIONS, &self, &next)
+    }
+
+    /// Whether no transitions leave this state
+    pub fn is_terminal(self) -> bool {
+        transitions::is_terminal(Self::TRANSITIONS, &self)
+    }
+}
+
+/// RNG seed of the task at `task` created in `slot`. Fixed on-chain before any
+/// worker sees the task, so a prover cannot shop for favorable randomness
+pub fn rng_seed_for(task: &Pubkey, slot: u64) -> [u8; 32] {
+    keccak::hashv(&[RNG_SEED_DOMAIN, task.as_ref(), &slot.to_le_bytes()]).0
+}
+
+/// What a worker submits for an enclave-produced result
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnclaveAttestation {
+    /// Keccak of the report's launch measurement
+    pub measurement_hash: [u8; 32],
+    /// Keccak of the raw signed report
+    pub report_hash: [u8; 32],
+}
+
+impl EnclaveAttestation {
+    /// Hash bound into the task proof and recorded on the task; the node's
+    /// `AttestationReport::digest` computes the same value
+    pub fn digest(&self) -> [u8; 32] {
+        keccak::hashv(&[ATTESTATION_DOMAIN, &self.measurement_hash, &self.report_hash]).0
+    }
+}
+
 /// Core task account storing execution metadata
 #[account]
 #[derive(Default)]
@@ -64,6 +171,30 @@ pub struct TaskState {
     pub model_mint: Option<Pubkey>,
     /// Version counter for optimistic concurrency
     pub version: u64,
+    /// Declared end-to-end latency SLO in milliseconds (0 = none)
+    pub latency_slo_ms: u32,
+    /// Hash of the TEE attestation report the result was produced under
+    pub attestation_hash: Option<[u8; 32]>,
+    /// Verifier version the task's proof must be built for
+    pub verifier_version: u16,
+    /// Seconds after creation by which a valid proof must land (0 = no limit)
+    pub time_limit: u64,
+    /// SPL mint the reward is escrowed in; lamports when `None`
+    pub reward_mint: Option<Pubkey>,
+    /// Tip escrowed on top of the reward for earlier pickup, in the reward's units
+    pub priority_fee: u64,
+    /// Unix timestamp the owner needs the result by; orders the pending queue
+    pub deadline: Option<i64>,
+    /// Seed every stochastic choice of a training run (dropout, augmentation,
+    /// shuffling) derives from; a public input of the training proof. New
+    /// fields go after this one and must read as zero for older accounts, which
+    /// `migrate_task` grows with zeroed bytes
+    pub rng_seed: [u8; 32],
+    /// Pricing account whose queue slot this task holds until it finishes
+    pub pricing: Option<Pubkey>,
+    /// Completed task whose verified result this task is served from; such
+    /// tasks escrow only the cached-result fee
+    pub cached_from: Option<Pubkey>,
 }
 
 impl TaskState {
@@ -79,27 +210,42 @@ impl TaskState {
         8 +  // remaining_cu
         1 + 8 + // verified_at (option)
         1 + 32 + // model_mint (option)
-        8; // version
+        8 + // version
+        4 + // latency_slo_ms
+        1 + 32 + // attestation_hash (option)
+        2 + // verifier_version
+        8 + // time_limit
+        1 + 32 + // reward_mint (option)
+        8 + // priority_fee
+        1 + 8 + // deadline (option)
+        32 + // rng_seed
+        1 + 32 + // pricing (option)
+        1 + 32; // cached_from (option)
+
+    /// Apply a status change after checking it against the transition table
+    pub fn transition(&mut self, next: TaskStatus) -> Result<()> {
+        require!(
+            self.status.kind().can_transition_to(next.kind()),
+            TaskError::InvalidStateTransition
+        );
+
+        self.status = next;
+        self.version = self.version.wrapping_add(1);
+
+        Ok(())
+    }
 
     /// Transition task to running state
     pub fn start(
         &mut self,
         worker: Pubkey,
     ) -> Result<()> {
-        require!(
-            matches!(self.status, TaskStatus::Pending),
-            TaskError::InvalidStateTransition
-        );
-        
-        let clock = clock::Clock::get()?;
-        self.status = TaskStatus::Running {
+        let clock = env::clock()?;
+        self.transition(TaskStatus::Running {
             worker,
             started_at: clock.unix_timestamp,
             last_heartbeat: clock.unix_timestamp,
-        };
-        self.version = self.version.wrapping_add(1);
-        
-        Ok(())
+        })
     }
 
     /// Update progress of running task
@@ -107,78 +253,244 @@ impl TaskState {
         &mut self,
         remaining_cu: u64,
     ) -> Result<()> {
-        match &mut self.status {
+        match self.status {
             TaskStatus::Running {
-                ref mut last_heartbeat,
+                worker,
+                started_at,
                 ..
             } => {
-                let clock = clock::Clock::get()?;
-                *last_heartbeat = clock.unix_timestamp;
+                let clock = env::clock()?;
+                self.transition(TaskStatus::Running {
+                    worker,
+                    started_at,
+                    last_heartbeat: clock.unix_timestamp,
+                })?;
                 self.remaining_cu = remaining_cu;
-                self.version = self.version.wrapping_add(1);
                 Ok(())
             }
             _ => Err(TaskError::InvalidStateTransition.into()),
         }
     }
 
+    /// Refresh the heartbeat of a running task; only its worker may
+    pub fn heartbeat(&mut self, worker: &Pubkey) -> Result<()> {
+        match self.status {
+            TaskStatus::Running { worker: assigned, started_at, .. } => {
+                require!(assigned == *worker, TaskError::Unauthorized);
+                let clock = env::clock()?;
+                self.transition(TaskStatus::Running {
+                    worker: assigned,
+                    started_at,
+                    last_heartbeat: clock.unix_timestamp,
+                })
+            }
+            _ => Err(TaskError::InvalidStateTransition.into()),
+        }
+    }
+
+    /// Bill `consumed_cu` against the allocation and split `escrow` between
+    /// the worker and the owner. The worker earns the priority fee in full and
+    /// the rest of the escrow in proportion to the compute it used; the owner
+    /// gets back what is left. Tasks without an allocation are not metered
+    pub fn meter(&mut self, consumed_cu: u64, escrow: u64) -> Result<MeteredReward> {
+        if self.allocated_cu == 0 {
+            return Ok(MeteredReward { payout: escrow, refund: 0 });
+        }
+        require!(consumed_cu <= self.allocated_cu, TaskError::ComputeUnitExhausted);
+
+        let fee = self.priority_fee.min(escrow);
+        let metered = (escrow - fee) as u128 * consumed_cu as u128 / self.allocated_cu as u128;
+        let payout = fee + metered as u64;
+        self.remaining_cu = self.allocated_cu - consumed_cu;
+
+        Ok(MeteredReward { payout, refund: escrow - payout })
+    }
+
+    /// Take a running task back from its worker so another can pick it up;
+    /// compute units are reset since the new worker starts over
+    pub fn requeue(&mut self) -> Result<()> {
+        self.transition(TaskStatus::Pending)?;
+        self.remaining_cu = self.allocated_cu;
+        Ok(())
+    }
+
     /// Complete task with final proof
     pub fn complete(
         &mut self,
         result_hash: [u8; 32],
     ) -> Result<()> {
-        require!(
-            matches!(self.status, TaskStatus::Running { .. }),
-            TaskError::InvalidStateTransition
-        );
-
-        let clock = clock::Clock::get()?;
-        self.status = TaskStatus::Completed {
+        let clock = env::clock()?;
+        self.transition(TaskStatus::Completed {
             result_hash,
             completed_at: clock.unix_timestamp,
-        };
+        })?;
         self.verified_at = Some(clock.unix_timestamp);
-        self.version = self.version.wrapping_add(1);
 
         Ok(())
     }
 
+    /// Complete a task whose model may require enclave execution. The
+    /// attestation must name the launch measurement the model registered; the
+    /// recorded hash commits to it and to the report the proof was bound to
+    pub fn complete_attested(
+        &mut self,
+        result_hash: [u8; 32],
+        attestation: Option<EnclaveAttestation>,
+        model: &ModelState,
+    ) -> Result<()> {
+        if model.require_tee {
+            let attestation = attestation.as_ref().ok_or(TaskError::AttestationRequired)?;
+            require!(
+                attestation.measurement_hash == model.enclave_measurement,
+                TaskError::EnclaveMeasurementMismatch
+            );
+        }
+        self.complete(result_hash)?;
+        self.attestation_hash = attestation.map(|a| a.digest());
+
+        Ok(())
+    }
+
+    /// Post a result without verifying it, starting the challenge window
+    pub fn submit_provisional(&mut self, worker: &Pubkey, result_hash: [u8; 32]) -> Result<()> {
+        match self.status {
+            TaskStatus::Running { worker: assigned, .. } => {
+                require!(assigned == *worker, TaskError::Unauthorized);
+                let clock = env::clock()?;
+                self.transition(TaskStatus::Provisional {
+                    worker: assigned,
+                    result_hash,
+                    submitted_at: clock.unix_timestamp,
+                })
+            }
+            _ => Err(TaskError::InvalidStateTransition.into()),
+        }
+    }
+
+    /// Whether the challenge window of a provisional result is still open at `now`
+    pub fn challengeable(&self, now: i64, window_secs: i64) -> bool {
+        matches!(
+            self.status,
+            TaskStatus::Provisional { submitted_at, .. }
+                if now.saturating_sub(submitted_at) <= window_secs
+        )
+    }
+
+    /// Put a provisional result under challenge; returns its worker
+    pub fn dispute(&mut self, now: i64) -> Result<Pubkey> {
+        match self.status {
+            TaskStatus::Provisional { worker, result_hash, .. } => {
+                self.transition(TaskStatus::Disputed {
+                    worker,
+                    result_hash,
+                    challenged_at: now,
+                })?;
+                Ok(worker)
+            }
+            _ => Err(TaskError::InvalidStateTransition.into()),
+        }
+    }
+
+    /// Accept the posted result of a provisional or disputed task
+    pub fn confirm_result(&mut self) -> Result<()> {
+        match self.status {
+            TaskStatus::Provisional { result_hash, .. }
+            | TaskStatus::Disputed { result_hash, .. } => self.complete(result_hash),
+            _ => Err(TaskError::InvalidStateTransition.into()),
+        }
+    }
+
     /// Mark task as failed
     pub fn fail(
         &mut self,
         error_code: u32,
     ) -> Result<()> {
+        let clock = env::clock()?;
+        self.transition(TaskStatus::Failed {
+            error_code,
+            failed_at: clock.unix_timestamp,
+        })
+    }
+
+    /// Cancel a pending or running task
+    pub fn cancel(&mut self) -> Result<()> {
+        let clock = env::clock()?;
+        self.transition(TaskStatus::Cancelled {
+            cancelled_at: clock.unix_timestamp,
+        })
+    }
+
+    /// Declare a latency SLO; only allowed before a worker picks the task up
+    pub fn set_latency_slo(&mut self, latency_slo_ms: u32) -> Result<()> {
         require!(
-            matches!(self.status, TaskStatus::Running { .. }),
+            self.status.kind() == TaskStatusKind::Pending,
             TaskError::InvalidStateTransition
         );
-
-        let clock = clock::Clock::get()?;
-        self.status = TaskStatus::Failed {
-            error_code,
-            failed_at: clock.unix_timestamp,
-        };
+        self.latency_slo_ms = latency_slo_ms;
         self.version = self.version.wrapping_add(1);
-
         Ok(())
     }
 
-    /// Cancel pending task
-    pub fn cancel(&mut self) -> Result<()> {
+    /// Set the proof deadline; only allowed before a worker picks the task up
+    pub fn set_time_limit(&mut self, time_limit: u64) -> Result<()> {
         require!(
-            matches!(self.status, TaskStatus::Pending),
+            self.status.kind() == TaskStatusKind::Pending,
             TaskError::InvalidStateTransition
         );
-
-        let clock = clock::Clock::get()?;
-        self.status = TaskStatus::Cancelled {
-            cancelled_at: clock.unix_timestamp,
-        };
+        self.time_limit = time_limit;
         self.version = self.version.wrapping_add(1);
+        Ok(())
+    }
 
+    /// Set the result deadline; it must fall within the time limit, which
+    /// already bounds when a proof can land
+    pub fn set_deadline(&mut self, deadline: Option<i64>, now: i64) -> Result<()> {
+        if let Some(deadline) = deadline {
+            let limit = i64::try_from(self.time_limit).unwrap_or(i64::MAX);
+            require!(
+                deadline > now
+                    && (self.time_limit == 0 || deadline <= self.created_at.saturating_add(limit)),
+                TaskError::InvalidDeadline
+            );
+        }
+        self.deadline = deadline;
         Ok(())
     }
 
+    /// Result hash of a completed task; provisional and disputed results are
+    /// not final and cannot be served to other tasks
+    pub fn verified_result(&self) -> Option<[u8; 32]> {
+        match self.status {
+            TaskStatus::Completed { result_hash, .. } if self.verified_at.is_some() => {
+                Some(result_hash)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether an unfinished task has passed its time limit at `now`
+    pub fn time_limit_expired(&self, now: i64) -> bool {
+        let limit = i64::try_from(self.time_limit).unwrap_or(i64::MAX);
+        self.time_limit > 0
+            && matches!(self.status.kind(), TaskStatusKind::Pending | TaskStatusKind::Running)
+            && now.saturating_sub(self.created_at) > limit
+    }
+
+    /// Whether a running task has missed its heartbeat deadline at `now`
+    pub fn heartbeat_expired(&self, now: i64) -> bool {
+        matches!(
+            self.status,
+            TaskStatus::Running { last_heartbeat, .. }
+                if now.saturating_sub(last_heartbeat) > HEARTBEAT_TIMEOUT_SECS
+        )
+    }
+
+    /// Whether a pending task has waited past its pickup deadline at `now`
+    pub fn pickup_expired(&self, now: i64) -> bool {
+        self.status.kind() == TaskStatusKind::Pending
+            && now.saturating_sub(self.created_at) > PICKUP_TIMEOUT_SECS
+    }
+
     /// Validate authority for state transitions
     pub fn validate_authority(&self, authority: &Pubkey) -> Result<()> {
         match self.status {
@@ -198,9 +510,22 @@ impl TaskState {
 }
 
 impl TaskStatus {
+    /// Discriminant used for transition checks
+    pub fn kind(&self) -> TaskStatusKind {
+        match self {
+            Self::Pending => TaskStatusKind::Pending,
+            Self::Running { .. } => TaskStatusKind::Running,
+            Self::Provisional { .. } => TaskStatusKind::Provisional,
+            Self::Disputed { .. } => TaskStatusKind::Disputed,
+            Self::Completed { .. } => TaskStatusKind::Completed,
+            Self::Failed { .. } => TaskStatusKind::Failed,
+            Self::Cancelled { .. } => TaskStatusKind::Cancelled,
+        }
+    }
+
     /// Calculate max serialized size
     pub const LEN: usize = 1 + // variant tag
-        32 + 8 + 8; // Running state fields (worker + timestamps)
+        32 + 32 + 8; // Provisional/Disputed fields (worker + result_hash + timestamp)
 }
 
 /// Task state change events
@@ -213,6 +538,38 @@ pub struct TaskStatusChanged {
     pub timestamp: i64,
 }
 
+/// Why a task's escrow went back to its owner
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefundReason {
+    Cancelled,
+    PickupExpired,
+    TimeLimitExpired,
+    HeartbeatTimeout,
+    /// Escrow above an accepted auction bid
+    AuctionSettled,
+    /// Escrow for allocated compute units the task did not use
+    UnusedCompute,
+}
+
+/// Split of a task's escrow once its compute use is known
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MeteredReward {
+    /// Paid to the worker
+    pub payout: u64,
+    /// Returned to the owner
+    pub refund: u64,
+}
+
+/// Escrow returned to the task owner
+#[event]
+pub struct TaskRefunded {
+    pub task: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub reason: RefundReason,
+    pub timestamp: i64,
+}
+
 #[error_code]
 pub enum TaskError {
     #[msg("Invalid state transition")]
@@ -225,4 +582,279 @@ pub enum TaskError {
     ComputeUnitExhausted,
     #[msg("Model hash mismatch")]
     ModelHashMismatch,
+    #[msg("Model requires a TEE attestation")]
+    AttestationRequired,
+    #[msg("Attestation is for an enclave measurement the model did not register")]
+    EnclaveMeasurementMismatch,
+    #[msg("Task has not reached a terminal state")]
+    TaskNotTerminal,
+    #[msg("Task deadline has not passed")]
+    DeadlineNotReached,
+    #[msg("Deadline must be in the future and within the time limit")]
+    InvalidDeadline,
+    #[msg("Account is not a task")]
+    NotATask,
+    #[msg("Task has no proof-verified result to serve")]
+    ResultNotVerified,
+    #[msg("Source task is for a different model, input or cache link")]
+    CacheSourceMismatch,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn status_strategy() -> impl Strategy<Value = TaskStatus> {
+        prop_oneof![
+            Just(TaskStatus::Pending),
+            any::<i64>().prop_map(|t| TaskStatus::Running {
+                worker: Pubkey::default(),
+                started_at: t,
+                last_heartbeat: t,
+            }),
+            any::<i64>().prop_map(|t| TaskStatus::Provisional {
+                worker: Pubkey::default(),
+                result_hash: [0; 32],
+                submitted_at: t,
+            }),
+            any::<i64>().prop_map(|t| TaskStatus::Disputed {
+                worker: Pubkey::default(),
+                result_hash: [0; 32],
+                challenged_at: t,
+            }),
+            any::<i64>().prop_map(|t| TaskStatus::Completed {
+                result_hash: [0; 32],
+                completed_at: t,
+            }),
+            (any::<u32>(), any::<i64>()).prop_map(|(error_code, t)| TaskStatus::Failed {
+                error_code,
+                failed_at: t,
+            }),
+            any::<i64>().prop_map(|t| TaskStatus::Cancelled { cancelled_at: t }),
+        ]
+    }
+
+    #[test]
+    fn test_deadlines_only_apply_to_their_state() {
+        let mut task = TaskState::default();
+        assert!(!task.pickup_expired(PICKUP_TIMEOUT_SECS));
+        assert!(task.pickup_expired(PICKUP_TIMEOUT_SECS + 1));
+        assert!(!task.heartbeat_expired(i64::MAX));
+
+        task.status = TaskStatus::Running {
+            worker: Pubkey::default(),
+            started_at: 0,
+            last_heartbeat: 100,
+        };
+        assert!(!task.pickup_expired(PICKUP_TIMEOUT_SECS + 1));
+        assert!(!task.heartbeat_expired(100 + HEARTBEAT_TIMEOUT_SECS));
+        assert!(task.heartbeat_expired(101 + HEARTBEAT_TIMEOUT_SECS));
+    }
+
+    #[test]
+    fn test_time_limit_applies_until_task_finishes() {
+        let mut task = TaskState { created_at: 1_000, ..Default::default() };
+        assert!(!task.time_limit_expired(i64::MAX));
+
+        task.set_time_limit(600).unwrap();
+        assert!(!task.time_limit_expired(1_600));
+        assert!(task.time_limit_expired(1_601));
+
+        task.status = TaskStatus::Running {
+            worker: Pubkey::default(),
+            started_at: 1_000,
+            last_heartbeat: 1_000,
+        };
+        assert!(task.set_time_limit(0).is_err());
+        assert!(task.time_limit_expired(1_601));
+
+        task.status = TaskStatus::Completed { result_hash: [0; 32], completed_at: 1_500 };
+        assert!(!task.time_limit_expired(1_601));
+    }
+
+    #[test]
+    fn test_deadline_within_time_limit() {
+        let mut task = TaskState { created_at: 1_000, time_limit: 600, ..Default::default() };
+        assert!(task.set_deadline(Some(1_000), 1_000).is_err());
+        assert!(task.set_deadline(Some(1_601), 1_000).is_err());
+        task.set_deadline(Some(1_600), 1_000).unwrap();
+        assert_eq!(task.deadline, Some(1_600));
+
+        task.time_limit = 0;
+        task.set_deadline(Some(i64::MAX), 1_000).unwrap();
+        task.set_deadline(None, 1_000).unwrap();
+        assert_eq!(task.deadline, None);
+    }
+
+    #[test]
+    fn test_meter_scales_reward_to_usage() {
+        let mut task = TaskState {
+            allocated_cu: 1_000,
+            remaining_cu: 1_000,
+            priority_fee: 100,
+            ..Default::default()
+        };
+        assert!(task.meter(1_001, 10_100).is_err());
+
+        let split = task.meter(250, 10_100).unwrap();
+        assert_eq!(split, MeteredReward { payout: 2_600, refund: 7_500 });
+        assert_eq!(task.remaining_cu, 750);
+        assert_eq!(task.meter(1_000, 10_100).unwrap().refund, 0);
+        // A fee larger than the escrow takes all of it
+        assert_eq!(task.meter(0, 60).unwrap(), MeteredReward { payout: 60, refund: 0 });
+
+        let mut unmetered = TaskState::default();
+        assert_eq!(unmetered.meter(5, 900).unwrap(), MeteredReward { payout: 900, refund: 0 });
+    }
+
+    #[test]
+    fn test_heartbeats_stamped_from_injected_clock() {
+        let worker = Pubkey::new_unique();
+        let mut task = TaskState::default();
+        let _clock = env::testing::pin_clock(10, 5_000);
+        task.start(worker).unwrap();
+
+        env::testing::advance(100, HEARTBEAT_TIMEOUT_SECS);
+        task.heartbeat(&worker).unwrap();
+        let now = env::now().unwrap();
+        assert!(matches!(task.status, TaskStatus::Running { started_at: 5_000, last_heartbeat, .. } if last_heartbeat == now));
+        assert!(!task.heartbeat_expired(now + HEARTBEAT_TIMEOUT_SECS));
+
+        env::testing::advance(100, HEARTBEAT_TIMEOUT_SECS + 1);
+        assert!(task.heartbeat_expired(env::now().unwrap()));
+    }
+
+    #[test]
+    fn test_rng_seed_bound_to_task_and_slot() {
+        let task = Pubkey::new_unique();
+        let seed = rng_seed_for(&task, 42);
+        assert_eq!(seed, rng_seed_for(&task, 42));
+        assert_ne!(seed, rng_seed_for(&task, 43));
+        assert_ne!(seed, rng_seed_for(&Pubkey::new_unique(), 42));
+    }
+
+    #[test]
+    fn test_attested_completion_checks_registered_measurement() {
+        let _clock = env::testing::pin_clock(10, 5_000);
+        let running = TaskStatus::Running {
+            worker: Pubkey::default(),
+            started_at: 0,
+            last_heartbeat: 0,
+        };
+        let model = ModelState {
+            require_tee: true,
+            enclave_measurement: [0xaa; 32],
+            ..Default::default()
+        };
+        let attestation = EnclaveAttestation { measurement_hash: [0xaa; 32], report_hash: [1; 32] };
+
+        let mut task = TaskState { status: running.clone(), ..Default::default() };
+        assert!(task.complete_attested([5; 32], None, &model).is_err());
+        let foreign = EnclaveAttestation { measurement_hash: [0xbb; 32], ..attestation };
+        assert!(task.complete_attested([5; 32], Some(foreign), &model).is_err());
+
+        task.complete_attested([5; 32], Some(attestation), &model).unwrap();
+        assert_eq!(task.attestation_hash, Some(attestation.digest()));
+        assert_ne!(attestation.digest(), foreign.digest());
+
+        // Models without the requirement complete unattested
+        let mut task = TaskState { status: running, ..Default::default() };
+        task.complete_attested([5; 32], None, &ModelState::default()).unwrap();
+        assert_eq!(task.attestation_hash, None);
+    }
+
+    #[test]
+    fn test_requeue_resets_running_task() {
+        let mut task = TaskState { allocated_cu: 1_000, remaining_cu: 400, ..Default::default() };
+        assert!(task.requeue().is_err());
+
+        task.status = TaskStatus::Running {
+            worker: Pubkey::default(),
+            started_at: 0,
+            last_heartbeat: 0,
+        };
+        task.requeue().unwrap();
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert_eq!(task.remaining_cu, 1_000);
+    }
+
+    #[test]
+    fn test_zero_grown_legacy_task_decodes_with_defaults() {
+        let task = TaskState {
+            owner: Pubkey::new_unique(),
+            allocated_cu: 1_000,
+            deadline: Some(9_000),
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        task.try_serialize(&mut data).unwrap();
+
+        // An account allocated before `rng_seed`, `pricing` and `cached_from`
+        // were appended
+        let appended = 32 + 1 + 1;
+        data.truncate(data.len() - appended);
+        data.resize(TaskState::LEN - (32 + 1 + 32 + 1 + 32), 0);
+
+        data.resize(TaskState::LEN, 0);
+        let grown = TaskState::try_deserialize(&mut data.as_slice()).unwrap();
+        assert_eq!((grown.owner, grown.allocated_cu), (task.owner, 1_000));
+        assert_eq!(grown.deadline, Some(9_000));
+        assert_eq!((grown.rng_seed, grown.pricing), ([0; 32], None));
+        assert_eq!(grown.cached_from, None);
+    }
+
+    #[test]
+    fn test_provisional_result_challenge_window() {
+        let worker = Pubkey::new_unique();
+        let mut task = TaskState::default();
+        assert!(!task.challengeable(0, 3_600));
+        assert!(task.dispute(0).is_err());
+
+        task.status = TaskStatus::Provisional {
+            worker,
+            result_hash: [5; 32],
+            submitted_at: 1_000,
+        };
+        assert!(task.challengeable(4_600, 3_600));
+        assert!(!task.challengeable(4_601, 3_600));
+
+        assert_eq!(task.dispute(2_000).unwrap(), worker);
+        assert!(!task.challengeable(2_000, 3_600));
+        assert!(task.dispute(2_000).is_err());
+        assert!(task.status.kind().can_transition_to(TaskStatusKind::Failed));
+        assert!(!TaskStatusKind::Provisional.can_transition_to(TaskStatusKind::Failed));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_transitions_follow_table(ops in prop::collection::vec(status_strategy(), 0..64)) {
+            let mut task = TaskState::default();
+            let mut seen_running = false;
+            let mut applied = 0u64;
+
+            for next in ops {
+                let from = task.status.kind();
+                let to = next.kind();
+                let result = task.transition(next);
+
+                prop_assert_eq!(result.is_ok(), from.can_transition_to(to));
+                if result.is_ok() {
+                    applied += 1;
+                    seen_running |= to == TaskStatusKind::Running;
+                } else {
+                    // Rejected transitions leave the account untouched
+                    prop_assert_eq!(task.status.kind(), from);
+                }
+
+                // Terminal outcomes require the task to have actually run
+                if matches!(task.status.kind(), TaskStatusKind::Completed | TaskStatusKind::Failed) {
+                    prop_assert!(seen_running);
+                }
+                prop_assert!(!from.is_terminal() || task.status.kind() == from);
+            }
+
+            prop_assert_eq!(task.version, applied);
+        }
+    }
 }
@@ -0,0 +1,48 @@
+//! Transition tables shared by the task and model state machines
+
+use std::fmt::Debug;
+
+/// Allowed `(from, to)` edges of a state machine
+pub type TransitionTable<K> = &'static [(K, K)];
+
+/// Check whether `from -> to` is an edge of the table
+pub fn is_allowed<K: PartialEq>(table: TransitionTable<K>, from: &K, to: &K) -> bool {
+    table.iter().any(|(f, t)| f == from && t == to)
+}
+
+/// States with no outgoing edges are terminal
+pub fn is_terminal<K: PartialEq>(table: TransitionTable<K>, state: &K) -> bool {
+    !table.iter().any(|(f, _)| f == state)
+}
+
+/// Render a transition table as a Graphviz DOT digraph
+pub fn to_dot<K: Debug>(name: &str, states: &[K], table: TransitionTable<K>) -> String {
+    let mut dot = format!("digraph {} {{\n    rankdir=LR;\n", name);
+    for state in states {
+        dot.push_str(&format!("    {:?};\n", state));
+    }
+    for (from, to) in table {
+        dot.push_str(&format!("    {:?} -> {:?};\n", from, to));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{model_state::ModelStatusKind, task_state::TaskStatusKind};
+
+    // The committed diagrams are generated from the same tables the guards use
+    #[test]
+    fn test_diagrams_in_sync() {
+        assert_eq!(
+            to_dot("TaskStatus", &TaskStatusKind::ALL, TaskStatusKind::TRANSITIONS),
+            include_str!("../../docs/task_status.dot")
+        );
+        assert_eq!(
+            to_dot("ModelStatus", &ModelStatusKind::ALL, ModelStatusKind::TRANSITIONS),
+            include_str!("../../docs/model_status.dot")
+        );
+    }
+}
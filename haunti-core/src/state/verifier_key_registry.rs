@@ -0,0 +1,167 @@
+//! Active verifying key per circuit, rotated by governance
+//!
+//! A proof is only as trustworthy as the key it is checked against, and the key
+//! accounts themselves can be written by whoever creates them. The registry
+//! pins, per circuit, the hash of the one key account verifiers accept. Keys
+//! change only through a token-vault `VerifierKeyRotation` proposal that has
+//! passed, waited out its timelock and been executed; versions only move
+//! forward, so an old executed proposal cannot roll a key back.
+//!
+//! A model's circuit id is its model hash; the Groth16 wrapper has its own.
+
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+use token_vault::{Proposal, ProposalStatus, ProposalType};
+
+/// Seed prefix of the registry PDA, followed by the circuit id
+pub const VERIFIER_KEY_REGISTRY_SEED: &[u8] = b"verifier_key_registry";
+
+/// Hash a key account is registered under: domain-separated SHA-256 of its
+/// full data, discriminator included
+pub fn vk_hash(data: &[u8]) -> [u8; 32] {
+    hashv(&[b"haunti-vk", data]).to_bytes()
+}
+
+/// Active verifying key of one circuit, PDA of
+/// `[VERIFIER_KEY_REGISTRY_SEED, circuit_id]`
+#[account]
+#[derive(Default)]
+pub struct VerifierKeyRegistry {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Circuit the key verifies
+    pub circuit_id: [u8; 32],
+    /// `vk_hash` of the active key account
+    pub vk_hash: [u8; 32],
+    /// Version of the active key; zero until the first rotation
+    pub version: u32,
+    /// Executed proposal that installed the active key
+    pub proposal: Pubkey,
+    /// Last rotation unix timestamp
+    pub updated_at: i64,
+}
+
+impl VerifierKeyRegistry {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // circuit_id
+        32 + // vk_hash
+        4 +  // version
+        32 + // proposal
+        8;   // updated_at
+
+    /// Whether `key` is the active key account
+    pub fn accepts(&self, key: &AccountInfo<'_>) -> bool {
+        match key.try_borrow_data() {
+            Ok(data) => self.version > 0 && vk_hash(&data) == self.vk_hash,
+            Err(_) => false,
+        }
+    }
+
+    /// Fail unless `key` is the active key account
+    pub fn require_key(&self, key: &AccountInfo<'_>) -> Result<()> {
+        require!(self.accepts(key), VerifierKeyRegistryError::KeyMismatch);
+        Ok(())
+    }
+
+    /// Install the key named by an executed `VerifierKeyRotation` proposal
+    pub fn rotate(&mut self, proposal: &Proposal, proposal_key: Pubkey) -> Result<()> {
+        require!(
+            proposal.status == ProposalStatus::Executed,
+            VerifierKeyRegistryError::ProposalNotExecuted
+        );
+        let ProposalType::VerifierKeyRotation { circuit_id, vk_hash, version } =
+            proposal.proposal_type
+        else {
+            return err!(VerifierKeyRegistryError::WrongProposal);
+        };
+        require!(
+            circuit_id == self.circuit_id,
+            VerifierKeyRegistryError::WrongProposal
+        );
+        require!(version > self.version, VerifierKeyRegistryError::StaleVersion);
+
+        self.vk_hash = vk_hash;
+        self.version = version;
+        self.proposal = proposal_key;
+        Ok(())
+    }
+}
+
+#[error_code]
+pub enum VerifierKeyRegistryError {
+    #[msg("Verifying key does not match the registered key")]
+    KeyMismatch,
+    #[msg("Key rotation proposal has not been executed")]
+    ProposalNotExecuted,
+    #[msg("Proposal does not rotate this circuit's key")]
+    WrongProposal,
+    #[msg("Key version is not newer than the active one")]
+    StaleVersion,
+    #[msg("Proposal was not passed by the governance pool")]
+    WrongGovernancePool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rotation(circuit_id: [u8; 32], vk_hash: [u8; 32], version: u32) -> Proposal {
+        Proposal {
+            proposer: Pubkey::default(),
+            pool: Pubkey::default(),
+            proposal_type: ProposalType::VerifierKeyRotation { circuit_id, vk_hash, version },
+            amount: None,
+            recipient: None,
+            votes_for: 0,
+            votes_against: 0,
+            created_at: 0,
+            total_staked_snapshot: 0,
+            voting_ends_at: 0,
+            timelock_delay: 0,
+            executable_at: 0,
+            status: ProposalStatus::Executed,
+            epoch: 0,
+            voters: 0,
+        }
+    }
+
+    #[test]
+    fn test_rotation_requires_executed_newer_proposal() {
+        let mut registry = VerifierKeyRegistry {
+            circuit_id: [1; 32],
+            ..Default::default()
+        };
+
+        let mut passed = rotation([1; 32], [7; 32], 1);
+        passed.status = ProposalStatus::Passed;
+        assert!(registry.rotate(&passed, Pubkey::new_unique()).is_err());
+        assert!(registry.rotate(&rotation([2; 32], [7; 32], 1), Pubkey::new_unique()).is_err());
+
+        let proposal = Pubkey::new_unique();
+        registry.rotate(&rotation([1; 32], [7; 32], 2), proposal).unwrap();
+        assert_eq!((registry.vk_hash, registry.version, registry.proposal), ([7; 32], 2, proposal));
+
+        // An older executed proposal cannot roll the key back
+        assert!(registry.rotate(&rotation([1; 32], [6; 32], 1), Pubkey::new_unique()).is_err());
+        assert!(registry.rotate(&rotation([1; 32], [6; 32], 2), Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn test_only_registered_key_accepted() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![3u8; 64];
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+        let mut registry = VerifierKeyRegistry::default();
+        // Nothing is accepted before the first rotation
+        registry.vk_hash = vk_hash(&[3u8; 64]);
+        assert!(!registry.accepts(&info));
+        registry.version = 1;
+        assert!(registry.accepts(&info));
+        registry.vk_hash = vk_hash(&[4u8; 64]);
+        assert!(registry.require_key(&info).is_err());
+    }
+}
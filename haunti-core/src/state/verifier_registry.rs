@@ -0,0 +1,180 @@
+//! Registry of deployed verifier program versions (singleton PDA)
+//!
+//! Proofs are built against the circuits of one verifier version, so a verifier
+//! upgrade cannot simply replace the program: tasks created before the upgrade
+//! still carry proofs for the old circuits. Every task is tagged with the
+//! `current` version when it is created, and governance upgrades by starting a
+//! cutover: the new version becomes current, while the one it replaces keeps
+//! being accepted until `cutover_end_slot` so in-flight tasks can finish.
+
+use anchor_lang::prelude::*;
+use crate::env;
+
+/// Number of verifier versions the registry can hold
+pub const MAX_VERIFIER_VERSIONS: usize = 8;
+
+/// One deployed verifier program
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VerifierVersion {
+    /// Version number tasks are tagged with
+    pub version: u16,
+    /// Program that verifies proofs for this version
+    pub program_id: Pubkey,
+}
+
+/// Verifier versions checked at proof submission and polled by coordinators
+#[account]
+#[derive(Default)]
+pub struct VerifierRegistry {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Authority allowed to register versions and start cutovers
+    pub governance: Pubkey,
+    /// Registered versions, oldest first
+    pub versions: Vec<VerifierVersion>,
+    /// Version new tasks are tagged with
+    pub current: u16,
+    /// Version being replaced; accepted until `cutover_end_slot`
+    pub previous: u16,
+    /// First slot at which `previous` is no longer accepted
+    pub cutover_end_slot: u64,
+    /// Last update unix timestamp
+    pub updated_at: i64,
+}
+
+impl VerifierRegistry {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        4 + (2 + 32) * MAX_VERIFIER_VERSIONS + // versions
+        2 +  // current
+        2 +  // previous
+        8 +  // cutover_end_slot
+        8;   // updated_at
+
+    /// Program that verifies proofs tagged with `version`
+    pub fn program_for(&self, version: u16) -> Option<Pubkey> {
+        self.versions
+            .iter()
+            .find(|v| v.version == version)
+            .map(|v| v.program_id)
+    }
+
+    /// Whether proofs tagged with `version` are accepted at `slot`
+    pub fn accepts(&self, version: u16, slot: u64) -> bool {
+        version == self.current || (version == self.previous && slot < self.cutover_end_slot)
+    }
+
+    /// Whether both the previous and current versions are accepted at `slot`
+    pub fn in_cutover(&self, slot: u64) -> bool {
+        self.previous != self.current && slot < self.cutover_end_slot
+    }
+
+    /// Fail unless proofs tagged with `version` are accepted at the current slot
+    pub fn require_accepted(&self, version: u16) -> Result<()> {
+        require!(
+            self.accepts(version, env::slot()?),
+            VerifierRegistryError::VersionNotAccepted
+        );
+        Ok(())
+    }
+
+    /// Add a new verifier version. Versions are immutable once registered, so a
+    /// tag always names the same program.
+    pub fn register(&mut self, version: u16, program_id: Pubkey) -> Result<()> {
+        require!(
+            self.program_for(version).is_none(),
+            VerifierRegistryError::VersionExists
+        );
+        require!(
+            self.versions.len() < MAX_VERIFIER_VERSIONS,
+            VerifierRegistryError::RegistryFull
+        );
+        self.versions.push(VerifierVersion { version, program_id });
+        Ok(())
+    }
+
+    /// Make `version` current, accepting the replaced version for another
+    /// `window_slots`. A zero window cuts over immediately.
+    pub fn begin_cutover(&mut self, version: u16, window_slots: u64, slot: u64) -> Result<()> {
+        require!(
+            self.program_for(version).is_some(),
+            VerifierRegistryError::UnknownVersion
+        );
+        require!(version != self.current, VerifierRegistryError::AlreadyCurrent);
+        // Starting a second cutover early would strand the version still draining
+        require!(!self.in_cutover(slot), VerifierRegistryError::CutoverInProgress);
+
+        self.previous = self.current;
+        self.current = version;
+        self.cutover_end_slot = slot.saturating_add(window_slots);
+        Ok(())
+    }
+
+    /// Stop accepting the previous version from `slot` onwards
+    pub fn end_cutover(&mut self, slot: u64) {
+        self.cutover_end_slot = self.cutover_end_slot.min(slot);
+    }
+}
+
+#[error_code]
+pub enum VerifierRegistryError {
+    #[msg("Verifier version is already registered")]
+    VersionExists,
+    #[msg("Verifier registry is full")]
+    RegistryFull,
+    #[msg("Verifier version is not registered")]
+    UnknownVersion,
+    #[msg("Verifier version is already current")]
+    AlreadyCurrent,
+    #[msg("A verifier cutover is still in progress")]
+    CutoverInProgress,
+    #[msg("Proofs for this verifier version are no longer accepted")]
+    VersionNotAccepted,
+    #[msg("Unauthorized verifier registry update")]
+    Unauthorized,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> VerifierRegistry {
+        let mut registry = VerifierRegistry::default();
+        registry.register(1, Pubkey::new_unique()).unwrap();
+        registry.current = 1;
+        registry.previous = 1;
+        registry
+    }
+
+    #[test]
+    fn test_both_versions_accepted_during_cutover() {
+        let mut registry = registry();
+        let v2 = Pubkey::new_unique();
+        registry.register(2, v2).unwrap();
+        assert!(!registry.accepts(2, 0));
+
+        registry.begin_cutover(2, 1_000, 500).unwrap();
+        assert_eq!(registry.program_for(2), Some(v2));
+        assert!(registry.in_cutover(500));
+        assert!(registry.accepts(1, 1_499) && registry.accepts(2, 1_499));
+        assert!(!registry.accepts(1, 1_500) && registry.accepts(2, 1_500));
+
+        // No third version until the old one has drained
+        registry.register(3, Pubkey::new_unique()).unwrap();
+        assert!(registry.begin_cutover(3, 1_000, 1_000).is_err());
+        registry.end_cutover(1_000);
+        assert!(!registry.accepts(1, 1_000));
+        registry.begin_cutover(3, 0, 1_000).unwrap();
+        assert!(!registry.accepts(2, 1_000));
+    }
+
+    #[test]
+    fn test_versions_are_immutable() {
+        let mut registry = registry();
+        assert!(registry.register(1, Pubkey::new_unique()).is_err());
+        assert!(registry.begin_cutover(1, 10, 0).is_err());
+        assert!(registry.begin_cutover(9, 10, 0).is_err());
+    }
+}
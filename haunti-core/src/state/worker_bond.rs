@@ -0,0 +1,140 @@
+//! Worker bonds and the stale-task reassignment policy
+//!
+//! A running task is held by its worker, which proves liveness by heartbeating.
+//! Once the last heartbeat is older than `ReassignConfig::stale_after_secs`,
+//! anyone may put the task back to Pending so another worker can pick it up. If
+//! the worker posted a `WorkerBond` (PDA of `[b"worker_bond", worker]`),
+//! `slash_bps` of it is slashed: the caller gets up to the keeper reward and the
+//! rest goes to the task owner for the delay. Withdrawing a bond first waits out
+//! an unbonding period, so a worker cannot pull it ahead of a reassignment.
+
+use anchor_lang::prelude::*;
+
+use super::task_state::{TaskState, TaskStatus};
+
+/// Seconds an unbonding worker waits before withdrawing its bond
+pub const WORKER_UNBONDING_SECS: i64 = 3 * 24 * 60 * 60;
+
+/// Governance-tunable reassignment policy (singleton PDA of `[b"reassign_config"]`)
+#[account]
+#[derive(Default)]
+pub struct ReassignConfig {
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Authority allowed to change the policy
+    pub governance: Pubkey,
+    /// Heartbeat age after which a running task may be reassigned
+    pub stale_after_secs: i64,
+    /// Share of the worker's bond slashed per reassignment, in basis points
+    pub slash_bps: u16,
+    /// Last update unix timestamp
+    pub updated_at: i64,
+}
+
+impl ReassignConfig {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // governance
+        8 +  // stale_after_secs
+        2 +  // slash_bps
+        8;   // updated_at
+
+    /// Replace the policy
+    pub fn set(&mut self, stale_after_secs: i64, slash_bps: u16) -> Result<()> {
+        require!(
+            stale_after_secs > 0 && slash_bps <= 10_000,
+            WorkerBondError::InvalidPolicy
+        );
+        self.stale_after_secs = stale_after_secs;
+        self.slash_bps = slash_bps;
+        Ok(())
+    }
+
+    /// Worker holding `task` if its heartbeat is stale at `now`
+    pub fn stale_worker(&self, task: &TaskState, now: i64) -> Option<Pubkey> {
+        match task.status {
+            TaskStatus::Running { worker, last_heartbeat, .. }
+                if now.saturating_sub(last_heartbeat) > self.stale_after_secs =>
+            {
+                Some(worker)
+            }
+            _ => None,
+        }
+    }
+
+    /// Lamports slashed from a bond of `amount`
+    pub fn slash_amount(&self, amount: u64) -> u64 {
+        (amount as u128 * self.slash_bps as u128 / 10_000) as u64
+    }
+}
+
+/// Lamports a worker puts at stake against abandoning tasks
+#[account]
+#[derive(Default)]
+pub struct WorkerBond {
+    /// Bump seed for PDA
+    pub bump: u8,
+    pub worker: Pubkey,
+    /// Bonded lamports, held by this account on top of its rent
+    pub amount: u64,
+    /// Lamports slashed so far
+    pub slashed: u64,
+    /// Tasks taken back from this worker
+    pub reassignments: u32,
+    /// When the bond may be withdrawn; zero while bonded
+    pub unlock_at: i64,
+}
+
+impl WorkerBond {
+    /// Account space calculation
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // worker
+        8 +  // amount
+        8 +  // slashed
+        4 +  // reassignments
+        8;   // unlock_at
+}
+
+#[error_code]
+pub enum WorkerBondError {
+    #[msg("Invalid reassignment policy")]
+    InvalidPolicy,
+    #[msg("Unauthorized reassignment policy update")]
+    Unauthorized,
+    #[msg("Task heartbeat is not stale")]
+    HeartbeatNotStale,
+    #[msg("Bond does not belong to the task's worker")]
+    BondMismatch,
+    #[msg("Bond amount must be non-zero")]
+    InvalidAmount,
+    #[msg("Bond is still unbonding")]
+    StillUnbonding,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_worker_uses_configured_threshold() {
+        let mut config = ReassignConfig::default();
+        assert!(config.set(0, 100).is_err());
+        assert!(config.set(60, 10_001).is_err());
+        config.set(60, 2_500).unwrap();
+
+        let worker = Pubkey::new_unique();
+        let mut task = TaskState::default();
+        assert_eq!(config.stale_worker(&task, 1_000), None);
+
+        task.status = TaskStatus::Running {
+            worker,
+            started_at: 0,
+            last_heartbeat: 100,
+        };
+        assert_eq!(config.stale_worker(&task, 160), None);
+        assert_eq!(config.stale_worker(&task, 161), Some(worker));
+        assert_eq!(config.slash_amount(1_000), 250);
+    }
+}
@@ -0,0 +1,144 @@
+//! Time and randomness as seen by instruction handlers
+//!
+//! Handlers read the clock through `clock()`/`now()` instead of `Clock::get()`,
+//! and draw any sampling seed from `seed()`. On-chain both come from the `Clock`
+//! sysvar. Native builds can pin them for the current thread with `testing`,
+//! so lockup, expiry and sampling logic can be unit tested without a bank;
+//! solana-program-test runs read the sysvar, which the harness warps.
+//!
+//! haunti-core compiles this same file as its own `env` module (included by
+//! path) rather than calling into this crate, so each program keeps its own
+//! pinned clock and native tests that drive both pin each one.
+
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+/// Current clock: the pinned one in native tests, else the sysvar
+pub fn clock() -> Result<Clock> {
+    #[cfg(not(target_os = "solana"))]
+    if let Some(clock) = testing::pinned_clock() {
+        return Ok(clock);
+    }
+    Ok(Clock::get()?)
+}
+
+/// Current unix timestamp
+pub fn now() -> Result<i64> {
+    Ok(clock()?.unix_timestamp)
+}
+
+/// Current slot; also what slot-keyed seeds such as task RNG seeds derive from
+pub fn slot() -> Result<u64> {
+    Ok(clock()?.slot)
+}
+
+/// Current epoch, for state that rolls over per epoch
+pub fn epoch() -> Result<u64> {
+    Ok(clock()?.epoch)
+}
+
+/// Seed for sampling under `domain`, fixed for a given slot. Validators can
+/// predict it, so it may pick among equally entitled candidates but must not
+/// decide anything worth manipulating. Tests pin it with `testing::pin_seed`.
+pub fn seed(domain: &[u8]) -> Result<[u8; 32]> {
+    #[cfg(not(target_os = "solana"))]
+    if let Some(seed) = testing::pinned_seed() {
+        return Ok(hashv(&[b"haunti-seed", domain, &seed]).to_bytes());
+    }
+    let clock = clock()?;
+    Ok(hashv(&[
+        b"haunti-seed",
+        domain,
+        &clock.slot.to_le_bytes(),
+        &clock.unix_timestamp.to_le_bytes(),
+    ])
+    .to_bytes())
+}
+
+/// Per-thread overrides for native tests
+#[cfg(not(target_os = "solana"))]
+pub mod testing {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static CLOCK: RefCell<Option<Clock>> = RefCell::new(None);
+        static SEED: RefCell<Option<[u8; 32]>> = RefCell::new(None);
+    }
+
+    pub(super) fn pinned_clock() -> Option<Clock> {
+        CLOCK.with(|clock| clock.borrow().clone())
+    }
+
+    pub(super) fn pinned_seed() -> Option<[u8; 32]> {
+        SEED.with(|seed| *seed.borrow())
+    }
+
+    /// Clears its override on drop, so a failing test cannot leak its clock
+    /// into the next one run on the thread
+    #[must_use]
+    pub struct Pinned {
+        clock: bool,
+    }
+
+    impl Drop for Pinned {
+        fn drop(&mut self) {
+            if self.clock {
+                CLOCK.with(|clock| *clock.borrow_mut() = None);
+            } else {
+                SEED.with(|seed| *seed.borrow_mut() = None);
+            }
+        }
+    }
+
+    /// Pin the clock at `slot` and `unix_timestamp`
+    pub fn pin_clock(slot: u64, unix_timestamp: i64) -> Pinned {
+        CLOCK.with(|clock| {
+            *clock.borrow_mut() = Some(Clock {
+                slot,
+                unix_timestamp,
+                epoch_start_timestamp: unix_timestamp,
+                ..Clock::default()
+            })
+        });
+        Pinned { clock: true }
+    }
+
+    /// Move the pinned clock forward
+    pub fn advance(slots: u64, secs: i64) {
+        CLOCK.with(|clock| {
+            let mut clock = clock.borrow_mut();
+            let clock = clock.as_mut().expect("advance needs a pinned clock");
+            clock.slot += slots;
+            clock.unix_timestamp += secs;
+        });
+    }
+
+    /// Make `seed()` a function of the domain and `seed` only
+    pub fn pin_seed(seed: [u8; 32]) -> Pinned {
+        SEED.with(|pinned| *pinned.borrow_mut() = Some(seed));
+        Pinned { clock: false }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinned_clock_advances_and_clears() {
+        {
+            let _pinned = testing::pin_clock(100, 1_000);
+            testing::advance(5, 60);
+            assert_eq!(slot().unwrap(), 105);
+            assert_eq!(now().unwrap(), 1_060);
+        }
+        assert!(testing::pinned_clock().is_none());
+    }
+
+    #[test]
+    fn test_pinned_seed_is_domain_separated() {
+        let _pinned = testing::pin_seed([9; 32]);
+        assert_eq!(seed(b"spot-check").unwrap(), seed(b"spot-check").unwrap());
+        assert_ne!(seed(b"spot-check").unwrap(), seed(b"auction").unwrap());
+    }
+}
@@ -3,17 +3,23 @@
 use anchor_lang::{
     prelude::*,
     solana_program::{
-        clock,
         program::{invoke, invoke_signed},
         system_instruction,
     },
 };
 use anchor_spl::{
-    token::{self, Mint, Token, TokenAccount, Transfer},
+    token_2022::spl_token_2022::{
+        self,
+        extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    },
+    token_interface::{self, Burn, Mint, MintTo, SetAuthority, TokenAccount, TokenInterface},
     associated_token::AssociatedToken,
 };
 use std::convert::TryInto;
 
+/// Clock and sampling seeds, overridable in tests
+pub mod env;
+
 declare_id!("HAUNTVAU1111111111111111111111111111111111");
 
 #[program]
@@ -26,16 +32,38 @@ pub mod token_vault {
         pool_type: PoolType,
         reward_rate: u64,
         lockup_period: i64,
+        tiers: Vec<LockupTier>,
+        receipt_mode: ReceiptMode,
     ) -> Result<()> {
+        validate_tiers(&tiers)?;
+        require!(
+            (receipt_mode == ReceiptMode::Liquid) == ctx.accounts.receipt_mint.is_some(),
+            VaultError::InvalidReceiptConfig
+        );
+        let receipt_mint = ctx
+            .accounts
+            .receipt_mint
+            .as_ref()
+            .map(|mint| mint.key())
+            .unwrap_or_default();
         let pool = &mut ctx.accounts.pool;
-        pool.version = 1;
-        pool.pool_type = pool_type;
-        pool.reward_rate = reward_rate;
-        pool.lockup_period = lockup_period;
-        pool.total_staked = 0;
-        pool.reward_reserve = 0;
-        pool.bump = *ctx.bumps.get("pool").unwrap();
-        pool.last_update = clock::Clock::get()?.unix_timestamp;
+        pool.set_inner(PoolState::new(
+            ctx.accounts.authority.key(),
+            pool_type,
+            reward_rate,
+            lockup_period,
+            tiers,
+            receipt_mode,
+            receipt_mint,
+            *ctx.bumps.get("pool").unwrap(),
+            env::now()?,
+        ));
+
+        ctx.accounts.pool_config.set_inner(PoolConfig::new(
+            pool.key(),
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("pool_config").unwrap(),
+        ));
         
         emit!(PoolEvent::PoolInitialized {
             pool: pool.key(),
@@ -45,32 +73,59 @@ pub mod token_vault {
         Ok(())
     }
 
-    /// Stake tokens into the pool
-    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+    /// Stake tokens into the pool. A referrer named on the first stake earns the
+    /// pool's referral cut of this stake's rewards for as long as it exists.
+    pub fn stake(ctx: Context<Stake>, amount: u64, referrer: Option<Pubkey>) -> Result<()> {
         let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, VaultError::PoolPaused);
         let user = &mut ctx.accounts.user_stake;
+        let now = env::now()?;
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        user.settle(pool)?;
+
+        if let Some(referrer) = referrer.filter(|_| user.amount == 0 && user.referrer.is_none()) {
+            require_keys_neq!(referrer, ctx.accounts.owner.key(), VaultError::InvalidReferrer);
+            user.referrer = Some(referrer);
+            emit!(PoolEvent::ReferralRecorded {
+                user: user.key(),
+                referrer,
+                timestamp: now,
+            });
+        }
         
-        // Transfer tokens to vault
-        let transfer_ix = Transfer {
-            from: ctx.accounts.user_token.to_account_info(),
-            to: ctx.accounts.vault.to_account_info(),
-            authority: ctx.accounts.owner.to_account_info(),
-        };
-        
-        let cpi_ctx = CpiContext::new(
+        // Transfer tokens to vault; only what arrives after transfer fees is staked
+        let received = received_amount(&ctx.accounts.mint, amount)?;
+        transfer_tokens(
             ctx.accounts.token_program.to_account_info(),
-            transfer_ix,
-        );
-        token::transfer(cpi_ctx, amount)?;
+            ctx.accounts.user_token.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            &[],
+            ctx.remaining_accounts,
+        )?;
 
         // Update stake records
-        user.amount += amount;
-        user.last_staked = clock::Clock::get()?.unix_timestamp;
-        pool.total_staked += amount;
+        user.amount += received;
+        user.last_staked = now;
+        user.reward_debt = user.accrued(pool)?;
+        pool.total_staked += received;
+        pool.total_weight += received;
+
+        let history = &mut ctx.accounts.stake_history;
+        if history.owner == Pubkey::default() {
+            history.pool = pool.key();
+            history.owner = ctx.accounts.owner.key();
+            history.bump = *ctx.bumps.get("stake_history").unwrap();
+        }
+        history.liquid_amount = user.amount;
+        history.update(now)?;
 
         emit!(PoolEvent::Staked {
+            pool: pool.key(),
             user: user.key(),
-            amount,
+            amount: received,
             timestamp: user.last_staked,
         });
         
@@ -81,40 +136,54 @@ pub mod token_vault {
     pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
         let pool = &mut ctx.accounts.pool;
         let user = &mut ctx.accounts.user_stake;
-        let now = clock::Clock::get()?.unix_timestamp;
+        let now = env::now()?;
         
+        require!(
+            amount > 0 && user.amount >= amount,
+            VaultError::InsufficientStake
+        );
         require!(
             now >= user.last_staked + pool.lockup_period,
             VaultError::LockupActive
         );
         
-        // Calculate rewards first
-        let rewards = calculate_rewards(user, pool, now)?;
-        if rewards > 0 {
-            distribute_rewards(ctx.accounts, rewards)?;
-        }
+        // Rewards earned so far stay claimable after unstaking
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        user.settle(pool)?;
 
         // Transfer tokens back
-        let transfer_ix = Transfer {
-            from: ctx.accounts.vault.to_account_info(),
-            to: ctx.accounts.user_token.to_account_info(),
-            authority: ctx.accounts.pool.to_account_info(),
-        };
-        
-        let seeds = &[b"pool", &[pool.bump]];
+        let pool_type = pool.pool_type.to_string();
+        let version_seed = pool.version_seed();
+        let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
         let signer = &[&seeds[..]];
-        let cpi_ctx = CpiContext::new_with_signer(
+        transfer_tokens(
             ctx.accounts.token_program.to_account_info(),
-            transfer_ix,
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.user_token.to_account_info(),
+            pool.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
             signer,
-        );
-        token::transfer(cpi_ctx, amount)?;
+            ctx.remaining_accounts,
+        )?;
 
         // Update records
-        user.amount -= amount;
-        pool.total_staked -= amount;
+        user.amount = user.amount
+            .checked_sub(amount)
+            .ok_or(VaultError::InsufficientStake)?;
+        user.reward_debt = user.accrued(pool)?;
+        pool.total_staked = pool.total_staked
+            .checked_sub(amount)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        pool.total_weight = pool.total_weight
+            .checked_sub(amount)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        let history = &mut ctx.accounts.stake_history;
+        history.liquid_amount = user.amount;
+        history.update(now)?;
 
         emit!(PoolEvent::Unstaked {
+            pool: pool.key(),
             user: user.key(),
             amount,
             timestamp: now,
@@ -123,21 +192,580 @@ pub mod token_vault {
         Ok(())
     }
 
+    /// Exit before the lockup expires, forfeiting the configured penalty
+    pub fn emergency_unstake(ctx: Context<EmergencyUnstake>, amount: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let user = &mut ctx.accounts.user_stake;
+        let now = env::now()?;
+        require!(
+            amount > 0 && user.amount >= amount,
+            VaultError::InsufficientStake
+        );
+
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        user.settle(pool)?;
+
+        // No penalty once the lockup has run its course
+        let penalty = if now >= user.last_staked + pool.lockup_period {
+            0
+        } else {
+            amount
+                .checked_mul(pool.early_unstake_penalty_bps as u64)
+                .ok_or(VaultError::InvalidRewardCalc)?
+                / BASIS_POINTS
+        };
+        let payout = amount - penalty;
+
+        let pool_type = pool.pool_type.to_string();
+        let version_seed = pool.version_seed();
+        let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+        let signer = &[&seeds[..]];
+
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.user_token.to_account_info(),
+            pool.to_account_info(),
+            &ctx.accounts.mint,
+            payout,
+            signer,
+            ctx.remaining_accounts,
+        )?;
+
+        if penalty > 0 {
+            let destination = match pool.penalty_route {
+                PenaltyRoute::RewardReserve => ctx.accounts.reward_vault.to_account_info(),
+                PenaltyRoute::Treasury => ctx.accounts.treasury.to_account_info(),
+            };
+            transfer_tokens(
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                destination,
+                pool.to_account_info(),
+                &ctx.accounts.mint,
+                penalty,
+                signer,
+                ctx.remaining_accounts,
+            )?;
+
+            if pool.penalty_route == PenaltyRoute::RewardReserve {
+                pool.reward_reserve = pool.reward_reserve
+                    .checked_add(received_amount(&ctx.accounts.mint, penalty)?)
+                    .ok_or(VaultError::InvalidRewardCalc)?;
+            }
+        }
+
+        user.amount -= amount;
+        user.reward_debt = user.accrued(pool)?;
+        pool.total_staked -= amount;
+        pool.total_weight -= amount;
+        let history = &mut ctx.accounts.stake_history;
+        history.liquid_amount = user.amount;
+        history.update(now)?;
+
+        emit!(PoolEvent::EmergencyUnstaked {
+            user: user.key(),
+            amount,
+            penalty,
+            route: pool.penalty_route,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Open a separate position locked for one of the pool's tiers. Its rewards and
+    /// voting power are scaled by the tier multiplier until it is withdrawn.
+    pub fn stake_locked(
+        ctx: Context<StakeLocked>,
+        amount: u64,
+        tier: u8,
+        position_id: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InsufficientStake);
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, VaultError::PoolPaused);
+        let lockup = *pool
+            .tiers
+            .get(tier as usize)
+            .ok_or(VaultError::InvalidLockupTier)?;
+        let now = env::now()?;
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+
+        let received = received_amount(&ctx.accounts.mint, amount)?;
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_token.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            &[],
+            ctx.remaining_accounts,
+        )?;
+
+        let weight = lockup.weight(received)?;
+        if let Some((receipt_mint, receipt_token)) = receipt_accounts(
+            pool,
+            ReceiptMode::PositionNft,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.receipt_token,
+        )? {
+            mint_position_receipt(
+                pool,
+                receipt_mint,
+                receipt_token,
+                &ctx.accounts.token_program,
+            )?;
+        }
+
+        let position = &mut ctx.accounts.position;
+        position.pool = pool.key();
+        position.owner = ctx.accounts.owner.key();
+        position.id = position_id;
+        position.amount = received;
+        position.tier = tier;
+        position.multiplier_bps = lockup.multiplier_bps;
+        position.weight = weight;
+        position.start = now;
+        position.unlock_at = now + lockup.duration;
+        position.reward_debt = position.accrued(pool)?;
+        position.unclaimed = 0;
+        position.bump = *ctx.bumps.get("position").unwrap();
+
+        pool.total_staked += received;
+        pool.total_weight += weight;
+
+        let history = &mut ctx.accounts.stake_history;
+        if history.owner == Pubkey::default() {
+            history.pool = pool.key();
+            history.owner = ctx.accounts.owner.key();
+            history.bump = *ctx.bumps.get("stake_history").unwrap();
+        }
+        history.locked_weight += weight;
+        history.update(now)?;
+
+        emit!(PoolEvent::PositionOpened {
+            position: position.key(),
+            owner: position.owner,
+            amount: received,
+            tier,
+            weight,
+            unlock_at: position.unlock_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Claim rewards accrued by a locked position without withdrawing it
+    pub fn claim_position_rewards(ctx: Context<ClaimPositionRewards>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, VaultError::PoolPaused);
+        let position = &mut ctx.accounts.position;
+        let now = env::now()?;
+
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        position.settle(pool)?;
+        let rewards = position.unclaimed;
+        require!(rewards > 0, VaultError::NoRewardsAvailable);
+
+        let pool_type = pool.pool_type.to_string();
+        let version_seed = pool.version_seed();
+        let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.user_token.to_account_info(),
+            pool.to_account_info(),
+            &ctx.accounts.mint,
+            rewards,
+            &[&seeds[..]],
+            ctx.remaining_accounts,
+        )?;
+        position.unclaimed = 0;
+
+        emit!(PoolEvent::RewardClaimed {
+            pool: pool.key(),
+            user: position.key(),
+            amount: rewards,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw a position once its tier lockup has expired, paying out any
+    /// outstanding rewards and closing the position account
+    pub fn unstake_position(ctx: Context<UnstakePosition>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let position = &mut ctx.accounts.position;
+        position.require_unlocked()?;
+        let now = env::now()?;
+
+        // Whoever holds the position's receipt must hand it back to withdraw
+        if let Some((receipt_mint, receipt_token)) = receipt_accounts(
+            pool,
+            ReceiptMode::PositionNft,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.receipt_token,
+        )? {
+            burn_receipt(
+                &ctx.accounts.token_program,
+                receipt_mint,
+                receipt_token,
+                &ctx.accounts.owner,
+                1,
+            )?;
+        }
+
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        position.settle(pool)?;
+        let rewards = position.unclaimed;
+
+        let pool_type = pool.pool_type.to_string();
+        let version_seed = pool.version_seed();
+        let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+        let signer = &[&seeds[..]];
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.user_token.to_account_info(),
+            pool.to_account_info(),
+            &ctx.accounts.mint,
+            position.amount,
+            signer,
+            ctx.remaining_accounts,
+        )?;
+        if rewards > 0 {
+            transfer_tokens(
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.reward_vault.to_account_info(),
+                ctx.accounts.user_token.to_account_info(),
+                pool.to_account_info(),
+                &ctx.accounts.mint,
+                rewards,
+                signer,
+                ctx.remaining_accounts,
+            )?;
+        }
+
+        pool.total_staked -= position.amount;
+        pool.total_weight -= position.weight;
+
+        let history = &mut ctx.accounts.stake_history;
+        history.locked_weight -= position.weight;
+        history.update(now)?;
+
+        emit!(PoolEvent::PositionClosed {
+            position: position.key(),
+            owner: position.owner,
+            amount: position.amount,
+            rewards,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Stake into the pool's liquid share and receive stHAUNT at the current
+    /// exchange rate. Liquid stake earns like any other but its rewards are
+    /// restaked for all holders, so the rate only rises.
+    pub fn stake_liquid(ctx: Context<StakeLiquid>, amount: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, VaultError::PoolPaused);
+        require!(pool.receipt_mode == ReceiptMode::Liquid, VaultError::InvalidReceiptConfig);
+        let now = env::now()?;
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        compound_liquid(
+            pool,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+        )?;
+
+        let received = received_amount(&ctx.accounts.mint, amount)?;
+        let shares = pool.receipt_shares_for(received)?;
+        require!(shares > 0, VaultError::InsufficientStake);
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_token.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            &[],
+            ctx.remaining_accounts,
+        )?;
+
+        let pool_type = pool.pool_type.to_string();
+        let version_seed = pool.version_seed();
+        let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    to: ctx.accounts.receipt_token.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            shares,
+        )?;
+        pool.add_liquid(received, shares)?;
+
+        emit!(PoolEvent::LiquidStaked {
+            user: ctx.accounts.owner.key(),
+            amount: received,
+            shares,
+            backing: pool.liquid_backing,
+            supply: pool.receipt_supply,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Burn stHAUNT for the stake it represents. Any holder may redeem, and
+    /// withdrawals stay open while the pool is paused.
+    pub fn unstake_liquid(ctx: Context<UnstakeLiquid>, shares: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.receipt_mode == ReceiptMode::Liquid, VaultError::InvalidReceiptConfig);
+        let now = env::now()?;
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        // Liquid rewards never leave the pool, so restaking them is not a claim
+        // and still runs while paused
+        compound_liquid(
+            pool,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+        )?;
+
+        let amount = pool.receipt_amount_for(shares)?;
+        require!(amount > 0, VaultError::InsufficientStake);
+        burn_receipt(
+            &ctx.accounts.token_program,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.receipt_token,
+            &ctx.accounts.owner,
+            shares,
+        )?;
+        pool.remove_liquid(amount, shares)?;
+
+        let pool_type = pool.pool_type.to_string();
+        let version_seed = pool.version_seed();
+        let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.user_token.to_account_info(),
+            pool.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            &[&seeds[..]],
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(PoolEvent::LiquidUnstaked {
+            user: ctx.accounts.owner.key(),
+            amount,
+            shares,
+            backing: pool.liquid_backing,
+            supply: pool.receipt_supply,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Pauser: halt staking, claiming, and compounding. Withdrawals stay open so
+    /// stakers can always exit.
+    pub fn pause_pool(ctx: Context<PausePool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, VaultError::PoolPaused);
+        let now = env::now()?;
+        // Settle emissions up to the pause; none accrue until unpaused
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        pool.paused = true;
+
+        emit!(PoolEvent::PoolPaused {
+            pool: pool.key(),
+            by: ctx.accounts.pauser.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Pool admin: resume normal operation after a pause
+    pub fn unpause_pool(ctx: Context<UnpausePool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.paused, VaultError::PoolNotPaused);
+        let now = env::now()?;
+        // Close out the paused interval without emitting for it
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        pool.paused = false;
+
+        emit!(PoolEvent::PoolUnpaused {
+            pool: pool.key(),
+            by: ctx.accounts.admin.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Role holder: nominate `new_holder` for `role`, or withdraw a nomination with `None`.
+    /// The role moves only once the nominee accepts.
+    pub fn propose_role_transfer(
+        ctx: Context<ProposeRoleTransfer>,
+        role: Role,
+        new_holder: Option<Pubkey>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.pool_config;
+        config.propose(role, ctx.accounts.holder.key(), new_holder)?;
+
+        emit!(AdminEvent::RoleTransferProposed {
+            pool: config.pool,
+            role,
+            holder: ctx.accounts.holder.key(),
+            pending: new_holder,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Nominee: take over a role proposed by its current holder
+    pub fn accept_role(ctx: Context<AcceptRole>, role: Role) -> Result<()> {
+        let config = &mut ctx.accounts.pool_config;
+        let previous = config.accept(role, ctx.accounts.nominee.key())?;
+
+        emit!(AdminEvent::RoleTransferred {
+            pool: config.pool,
+            role,
+            previous,
+            holder: ctx.accounts.nominee.key(),
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Fee manager: configure the early-unstake penalty and where it goes
+    pub fn set_early_unstake_penalty(
+        ctx: Context<SetEarlyUnstakePenalty>,
+        penalty_bps: u16,
+        route: PenaltyRoute,
+    ) -> Result<()> {
+        require!(penalty_bps <= MAX_PENALTY_BPS, VaultError::InvalidPenalty);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.early_unstake_penalty_bps = penalty_bps;
+        pool.penalty_route = route;
+        Ok(())
+    }
+
+    /// Fee manager: share of referred stakers' rewards paid to their referrers,
+    /// applied to rewards settled from now on
+    pub fn set_referral_cut(ctx: Context<SetReferralCut>, referral_bps: u16) -> Result<()> {
+        require!(referral_bps <= MAX_REFERRAL_BPS, VaultError::InvalidReferrer);
+        ctx.accounts.pool.referral_bps = referral_bps;
+        Ok(())
+    }
+
+    /// Move a referee's withheld referral cut to its referrer's account. Anyone
+    /// may call this, typically the referrer before claiming.
+    pub fn sync_referral(ctx: Context<SyncReferral>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let user = &mut ctx.accounts.user_stake;
+        let now = env::now()?;
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        user.settle(pool)?;
+
+        let amount = user.referral_owed;
+        let referral = &mut ctx.accounts.referral;
+        if referral.referrer == Pubkey::default() {
+            referral.pool = pool.key();
+            referral.referrer = ctx.accounts.referrer.key();
+            referral.bump = *ctx.bumps.get("referral").unwrap();
+        }
+        referral.unclaimed = referral.unclaimed
+            .checked_add(amount)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        referral.total_earned = referral.total_earned
+            .checked_add(amount)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        user.referral_owed = 0;
+
+        emit!(PoolEvent::ReferralSynced {
+            user: user.key(),
+            referrer: referral.referrer,
+            amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Referrer: claim the referral cut synced so far
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        require!(!pool.paused, VaultError::PoolPaused);
+        let amount = ctx.accounts.referral.unclaimed;
+        require!(amount > 0, VaultError::NoRewardsAvailable);
+
+        let pool_type = pool.pool_type.to_string();
+        let version_seed = pool.version_seed();
+        let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.referrer_token.to_account_info(),
+            pool.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            &[&seeds[..]],
+            ctx.remaining_accounts,
+        )?;
+        ctx.accounts.referral.unclaimed = 0;
+
+        emit!(PoolEvent::ReferralRewardsClaimed {
+            referrer: ctx.accounts.referrer.key(),
+            amount,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
     /// Claim accumulated rewards
     pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
         let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, VaultError::PoolPaused);
         let user = &mut ctx.accounts.user_stake;
-        let now = clock::Clock::get()?.unix_timestamp;
+        let now = env::now()?;
         
-        let rewards = calculate_rewards(user, pool, now)?;
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        user.settle(pool)?;
+        let rewards = user.unclaimed;
         require!(rewards > 0, VaultError::NoRewardsAvailable);
         
-        distribute_rewards(ctx.accounts, rewards)?;
+        distribute_rewards(ctx.accounts, rewards, ctx.remaining_accounts)?;
         
+        // Reserve was already debited when the rewards accrued
+        let user = &mut ctx.accounts.user_stake;
+        user.unclaimed = 0;
         user.last_reward = now;
-        pool.reward_reserve -= rewards;
         
         emit!(PoolEvent::RewardClaimed {
+            pool: ctx.accounts.pool.key(),
             user: user.key(),
             amount: rewards,
             timestamp: now,
@@ -146,228 +774,6151 @@ pub mod token_vault {
         Ok(())
     }
 
-    /// Governance: Create a new proposal
-    pub fn create_proposal(
-        ctx: Context<CreateProposal>,
-        proposal_type: ProposalType,
-        amount: Option<u64>,
-        recipient: Option<Pubkey>,
-    ) -> Result<()> {
-        let proposal = &mut ctx.accounts.proposal;
-        proposal.proposer = *ctx.accounts.owner.key;
-        proposal.proposal_type = proposal_type;
-        proposal.amount = amount;
-        proposal.recipient = recipient;
-        proposal.votes_for = 0;
-        proposal.votes_against = 0;
-        proposal.created_at = clock::Clock::get()?.unix_timestamp;
-        proposal.status = ProposalStatus::Active;
-        
-        emit!(GovernanceEvent::ProposalCreated {
-            proposal: proposal.key(),
-            proposer: proposal.proposer,
-            timestamp: proposal.created_at,
+    /// Restake pending rewards in one step. The lockup clock is left alone so
+    /// compounding never extends an existing lock.
+    pub fn compound(ctx: Context<Compound>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, VaultError::PoolPaused);
+        let user = &mut ctx.accounts.user_stake;
+        let now = env::now()?;
+
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        user.settle(pool)?;
+        let rewards = user.unclaimed;
+        require!(rewards > 0, VaultError::NoRewardsAvailable);
+        let staked = restake_rewards(
+            pool,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            rewards,
+            ctx.remaining_accounts,
+        )?;
+
+        user.unclaimed = 0;
+        user.last_reward = now;
+        user.amount += staked;
+        user.reward_debt = user.accrued(pool)?;
+        pool.total_staked += staked;
+        pool.total_weight += staked;
+
+        let history = &mut ctx.accounts.stake_history;
+        history.liquid_amount = user.amount;
+        history.update(now)?;
+
+        emit!(PoolEvent::Compounded {
+            user: user.key(),
+            amount: staked,
+            staked: user.amount,
+            timestamp: now,
         });
-        
+
         Ok(())
     }
 
-    /// Governance: Vote on a proposal
-    pub fn vote(ctx: Context<Vote>, approve: bool) -> Result<()> {
-        let proposal = &mut ctx.accounts.proposal;
-        let stake = &ctx.accounts.user_stake;
-        
-        require!(
-            proposal.status == ProposalStatus::Active,
-            VaultError::ProposalNotActive
-        );
-        require!(
-            stake.amount >= MIN_VOTING_STAKE,
-            VaultError::InsufficientVotingPower
-        );
-        
-        if approve {
-            proposal.votes_for += stake.amount;
-        } else {
-            proposal.votes_against += stake.amount;
-        }
-        
-        emit!(GovernanceEvent::VoteCast {
-            proposal: proposal.key(),
-            voter: stake.key(),
-            amount: stake.amount,
-            approve,
-            timestamp: clock::Clock::get()?.unix_timestamp,
+    /// Restake a locked position's pending rewards at its tier multiplier,
+    /// keeping its original unlock time
+    pub fn compound_position(ctx: Context<CompoundPosition>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, VaultError::PoolPaused);
+        let position = &mut ctx.accounts.position;
+        let now = env::now()?;
+
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        position.settle(pool)?;
+        let rewards = position.unclaimed;
+        require!(rewards > 0, VaultError::NoRewardsAvailable);
+        let staked = restake_rewards(
+            pool,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            rewards,
+            ctx.remaining_accounts,
+        )?;
+
+        position.unclaimed = 0;
+        let added_weight = position.add_stake(staked)?;
+        position.reward_debt = position.accrued(pool)?;
+        pool.total_staked += staked;
+        pool.total_weight += added_weight;
+
+        let history = &mut ctx.accounts.stake_history;
+        history.locked_weight += added_weight;
+        history.update(now)?;
+
+        emit!(PoolEvent::Compounded {
+            user: position.key(),
+            amount: staked,
+            staked: position.amount,
+            timestamp: now,
         });
-        
+
         Ok(())
     }
+
+    /// Pool admin: change the emission rate, effective from now on
+    pub fn set_reward_rate(ctx: Context<SetRewardRate>, reward_rate: u64) -> Result<()> {
+        let now = env::now()?;
+        let pool = &mut ctx.accounts.pool;
+        // Close out the old rate before switching so past accrual is unaffected
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        pool.schedule_rate(now, reward_rate);
+        emit_rate_scheduled(pool, now);
+        Ok(())
+    }
+
+    /// Pool admin: spread future reward rate changes linearly over `ramp_secs`
+    /// (0 applies them at once), so stake cannot be timed around a rate hike.
+    /// A ramp already under way keeps its original window.
+    pub fn set_rate_ramp(ctx: Context<SetRateRamp>, ramp_secs: i64) -> Result<()> {
+        require!(
+            (0..=MAX_RATE_RAMP_SECS).contains(&ramp_secs),
+            VaultError::InvalidRateRamp
+        );
+        ctx.accounts.pool.rate_ramp_secs = ramp_secs;
+        Ok(())
+    }
+
+    /// Pool admin: replace the emission schedule. Emissions up to now settle under
+    /// the previous regime; from then on `reward_rate` is ignored and each second
+    /// emits the rate of the segment covering it, or nothing between segments.
+    pub fn set_emission_schedule(
+        ctx: Context<SetEmissionSchedule>,
+        segments: Vec<EmissionSegment>,
+    ) -> Result<()> {
+        validate_segments(&segments)?;
+        let now = env::now()?;
+        let pool = &mut ctx.accounts.pool;
+        let schedule = &mut ctx.accounts.emission_schedule;
+        let previous = pool.scheduled.then_some(&**schedule);
+        pool.accrue(now, previous)?;
+
+        schedule.pool = pool.key();
+        schedule.segments = segments;
+        schedule.bump = *ctx.bumps.get("emission_schedule").unwrap();
+        pool.scheduled = true;
+
+        emit!(PoolEvent::EmissionScheduleSet {
+            pool: pool.key(),
+            segments: schedule.segments.len() as u8,
+            ends_at: schedule.segments.last().map_or(now, |s| s.end_ts),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Pool admin: drop the schedule and fall back to the flat `reward_rate`
+    pub fn clear_emission_schedule(ctx: Context<ClearEmissionSchedule>) -> Result<()> {
+        let now = env::now()?;
+        let pool = &mut ctx.accounts.pool;
+        pool.accrue(now, Some(&*ctx.accounts.emission_schedule))?;
+        pool.scheduled = false;
+
+        emit!(PoolEvent::EmissionScheduleCleared {
+            pool: pool.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Top up the pool's reward reserve from any funder
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidRewardCalc);
+
+        let received = received_amount(&ctx.accounts.mint, amount)?;
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.funder_token.to_account_info(),
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.funder.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            &[],
+            ctx.remaining_accounts,
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.reward_reserve = pool.reward_reserve
+            .checked_add(received)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+
+        emit!(PoolEvent::RewardReserveFunded {
+            pool: pool.key(),
+            funder: ctx.accounts.funder.key(),
+            amount: received,
+            reserve: pool.reward_reserve,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Governance: withdraw unallocated rewards under a passed proposal
+    pub fn drain_rewards(ctx: Context<DrainRewards>) -> Result<()> {
+        let now = env::now()?;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(now >= proposal.executable_at, VaultError::TimelockActive);
+        let amount = proposal.amount.ok_or(VaultError::InvalidRewardCalc)?;
+        let pool = &mut ctx.accounts.pool;
+        require!(amount <= pool.reward_reserve, VaultError::NoRewardsAvailable);
+
+        let pool_type = pool.pool_type.to_string();
+        let version_seed = pool.version_seed();
+        let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+        let signer = &[&seeds[..]];
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.recipient_token.to_account_info(),
+            pool.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            signer,
+            ctx.remaining_accounts,
+        )?;
+
+        pool.reward_reserve -= amount;
+        proposal.status = ProposalStatus::Executed;
+
+        emit!(PoolEvent::RewardReserveDrained {
+            pool: pool.key(),
+            proposal: proposal.key(),
+            recipient: ctx.accounts.recipient_token.key(),
+            amount,
+            reserve: pool.reward_reserve,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Close an emptied stake account and return its rent to the owner. Rewards and
+    /// any referral cut must be claimed or synced first.
+    pub fn close_user_stake(ctx: Context<CloseUserStake>) -> Result<()> {
+        let user = &ctx.accounts.user_stake;
+        require!(
+            user.amount == 0 && user.unclaimed == 0 && user.referral_owed == 0,
+            VaultError::StakeNotEmpty
+        );
+
+        emit!(PoolEvent::StakeClosed {
+            user: user.key(),
+            owner: ctx.accounts.owner.key(),
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Governance: Create a new proposal
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        proposal_type: ProposalType,
+        amount: Option<u64>,
+        recipient: Option<Pubkey>,
+        epoch: u64,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.proposer = *ctx.accounts.owner.key;
+        proposal.pool = ctx.accounts.pool.key();
+        proposal.proposal_type = proposal_type;
+        proposal.amount = amount;
+        proposal.recipient = recipient;
+        proposal.validate_payload()?;
+        proposal.votes_for = 0;
+        proposal.votes_against = 0;
+        proposal.created_at = env::now()?;
+        proposal.total_staked_snapshot = ctx.accounts.pool.voting_weight();
+        proposal.voting_ends_at = proposal.created_at + VOTING_PERIOD;
+        proposal.timelock_delay = proposal.proposal_type.timelock_delay();
+        proposal.executable_at = 0;
+        proposal.status = ProposalStatus::Active;
+        require!(
+            epoch == governance_epoch(proposal.created_at),
+            VaultError::WrongGovernanceEpoch
+        );
+        proposal.epoch = epoch;
+        proposal.voters = 0;
+
+        let epoch = &mut ctx.accounts.governance_epoch;
+        if epoch.pool == Pubkey::default() {
+            epoch.pool = proposal.pool;
+            epoch.epoch = proposal.epoch;
+            epoch.bump = *ctx.bumps.get("governance_epoch").unwrap();
+        }
+        epoch.proposals += 1;
+        
+        emit!(GovernanceEvent::ProposalCreated {
+            proposal: proposal.key(),
+            proposer: proposal.proposer,
+            timestamp: proposal.created_at,
+        });
+        
+        Ok(())
+    }
+
+    /// Governance: Vote on a proposal, or change an earlier vote before the deadline.
+    /// Remaining accounts carry `(VoteDelegation, StakeHistory, VoteRecord)` triples for
+    /// stakers whose power reaches the voter through a delegation chain.
+    pub fn vote<'info>(
+        ctx: Context<'_, '_, '_, 'info, Vote<'info>>,
+        approve: bool,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let record = &mut ctx.accounts.vote_record;
+        
+        let now = env::now()?;
+        
+        require!(
+            proposal.status == ProposalStatus::Active,
+            VaultError::ProposalNotActive
+        );
+        require!(now < proposal.voting_ends_at, VaultError::VotingClosed);
+
+        // Stake added after the proposal opened carries no weight on it
+        // A pure delegate may hold no stake of its own
+        let own_power = match &ctx.accounts.stake_history {
+            Some(history) => history.amount_at(proposal.created_at)?,
+            None => 0,
+        };
+        let delegated_power = cast_delegated_votes(
+            ctx.program_id,
+            proposal,
+            ctx.accounts.owner.key(),
+            ctx.remaining_accounts,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            approve,
+            now,
+        )?;
+        require!(
+            own_power + delegated_power >= MIN_VOTING_STAKE,
+            VaultError::InsufficientVotingPower
+        );
+        
+        // Withdraw the previous vote so each staker is tallied exactly once
+        let changed = record.proposal != Pubkey::default();
+        if changed {
+            if record.approve {
+                proposal.votes_for -= record.weight;
+            } else {
+                proposal.votes_against -= record.weight;
+            }
+        } else {
+            record.proposal = proposal.key();
+            record.voter = ctx.accounts.owner.key();
+            record.bump = *ctx.bumps.get("vote_record").unwrap();
+
+            // Participation counts distinct proposals a wallet voted on itself
+            proposal.voters += 1;
+            let participation = &mut ctx.accounts.participation;
+            if participation.voter == Pubkey::default() {
+                participation.pool = proposal.pool;
+                participation.voter = record.voter;
+                participation.epoch = proposal.epoch;
+                participation.bump = *ctx.bumps.get("participation").unwrap();
+            }
+            participation.votes += 1;
+        }
+        // A direct vote always overrides one cast on this staker's behalf
+        record.cast_by = record.voter;
+
+        if approve {
+            proposal.votes_for += own_power;
+        } else {
+            proposal.votes_against += own_power;
+        }
+        record.weight = own_power;
+        record.approve = approve;
+        record.voted_at = now;
+        
+        emit!(GovernanceEvent::VoteCast {
+            proposal: proposal.key(),
+            voter: record.voter,
+            amount: own_power + delegated_power,
+            approve,
+            changed,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Governance: tally a proposal once voting closes, queueing it behind its timelock
+    pub fn finalize_proposal(ctx: Context<FinalizeProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let now = env::now()?;
+
+        require!(
+            proposal.status == ProposalStatus::Active,
+            VaultError::ProposalNotActive
+        );
+        require!(now >= proposal.voting_ends_at, VaultError::VotingPeriodActive);
+
+        proposal.status = proposal.outcome();
+        let turnout_bps = proposal.turnout_bps();
+        ctx.accounts.governance_epoch.record_finalized(turnout_bps);
+        emit!(GovernanceEvent::ProposalTurnout {
+            proposal: proposal.key(),
+            epoch: proposal.epoch,
+            voters: proposal.voters,
+            turnout_bps,
+            passed: proposal.status == ProposalStatus::Passed,
+            timestamp: now,
+        });
+
+        if proposal.status == ProposalStatus::Passed {
+            proposal.executable_at = now + proposal.timelock_delay;
+            emit!(GovernanceEvent::ProposalQueued {
+                proposal: proposal.key(),
+                executable_at: proposal.executable_at,
+                timestamp: now,
+            });
+        } else {
+            emit!(GovernanceEvent::ProposalRejected {
+                proposal: proposal.key(),
+                votes_for: proposal.votes_for,
+                votes_against: proposal.votes_against,
+                timestamp: now,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Pool admin: configure the governance participation reward
+    pub fn set_participation_rewards(
+        ctx: Context<SetParticipationRewards>,
+        reward_per_epoch: u64,
+        epoch_budget: u64,
+        min_participation_bps: u16,
+        tiers: Vec<ParticipationTier>,
+    ) -> Result<()> {
+        validate_participation_tiers(&tiers)?;
+        require!(
+            min_participation_bps > 0 && min_participation_bps as u64 <= BASIS_POINTS,
+            VaultError::InvalidParticipationConfig
+        );
+        require!(
+            reward_per_epoch <= epoch_budget,
+            VaultError::InvalidParticipationConfig
+        );
+
+        let config = &mut ctx.accounts.participation_config;
+        config.pool = ctx.accounts.pool.key();
+        config.reward_per_epoch = reward_per_epoch;
+        config.epoch_budget = epoch_budget;
+        config.min_participation_bps = min_participation_bps;
+        config.tiers = tiers;
+        config.bump = *ctx.bumps.get("participation_config").unwrap();
+
+        emit!(GovernanceEvent::ParticipationRewardsSet {
+            pool: config.pool,
+            reward_per_epoch,
+            epoch_budget,
+            min_participation_bps,
+            tiers: config.tiers.len() as u8,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Governance: claim the participation reward of a closed epoch from the
+    /// treasury. Wallets that voted on at least `min_participation_bps` of the
+    /// epoch's proposals qualify; the reward scales with the stake tier the
+    /// wallet held at the end of the epoch
+    pub fn claim_participation_reward<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimParticipationReward<'info>>,
+    ) -> Result<()> {
+        let now = env::now()?;
+        let participation = &mut ctx.accounts.participation;
+        let epoch = &mut ctx.accounts.governance_epoch;
+        let config = &ctx.accounts.participation_config;
+
+        // Proposals opened late in the epoch vote into the next one
+        let epoch_end = epoch_start(participation.epoch + 1);
+        require!(
+            now >= epoch_end + VOTING_PERIOD,
+            VaultError::EpochNotClosed
+        );
+        require!(!participation.claimed, VaultError::ParticipationRewardClaimed);
+        let participation_bps = participation.participation_bps(epoch.proposals);
+        require!(
+            participation_bps >= config.min_participation_bps,
+            VaultError::ParticipationTooLow
+        );
+
+        let power = ctx.accounts.stake_history.amount_at(epoch_end)?;
+        let tier = config.tier_for(power).ok_or(VaultError::ParticipationTooLow)?;
+        let amount = config
+            .reward(tier)?
+            .min(config.epoch_budget.saturating_sub(epoch.rewards_paid));
+        require!(amount > 0, VaultError::ParticipationBudgetExhausted);
+
+        participation.claimed = true;
+        epoch.rewards_paid += amount;
+        let streak = &mut ctx.accounts.streak;
+        if streak.voter == Pubkey::default() {
+            streak.pool = participation.pool;
+            streak.voter = participation.voter;
+            streak.bump = *ctx.bumps.get("streak").unwrap();
+        }
+        streak.advance(participation.epoch)?;
+
+        let pool = &ctx.accounts.pool;
+        let pool_type = pool.pool_type.to_string();
+        let version_seed = pool.version_seed();
+        let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+        let signer = &[&seeds[..]];
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.voter_token.to_account_info(),
+            pool.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            signer,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(GovernanceEvent::ParticipationRewardClaimed {
+            pool: pool.key(),
+            voter: participation.voter,
+            epoch: participation.epoch,
+            votes: participation.votes,
+            proposals: epoch.proposals,
+            participation_bps,
+            tier_weight_bps: tier.weight_bps,
+            amount,
+            streak: streak.current,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Governance: apply a passed proposal after its timelock has elapsed
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let now = env::now()?;
+        let proposal = &mut ctx.accounts.proposal;
+        let pool = &mut ctx.accounts.pool;
+
+        require!(
+            proposal.status == ProposalStatus::Passed,
+            VaultError::ProposalNotPassed
+        );
+        require!(now >= proposal.executable_at, VaultError::TimelockActive);
+
+        match proposal.proposal_type.clone() {
+            ProposalType::TreasuryTransfer => {
+                let treasury = ctx.accounts.treasury.as_ref().ok_or(VaultError::InvalidProposal)?;
+                let recipient = ctx.accounts.recipient_token.as_ref().ok_or(VaultError::InvalidProposal)?;
+                require!(
+                    proposal.recipient == Some(recipient.key()),
+                    VaultError::InvalidProposal
+                );
+                let amount = proposal.amount.ok_or(VaultError::InvalidProposal)?;
+                let mint = ctx.accounts.mint.as_ref().ok_or(VaultError::InvalidProposal)?;
+                require_keys_eq!(treasury.mint, mint.key(), VaultError::InvalidProposal);
+
+                let pool_type = pool.pool_type.to_string();
+                let version_seed = pool.version_seed();
+                let seeds = &[
+                    b"pool",
+                    pool_type.as_bytes(),
+                    version_seed.as_slice(),
+                    &[pool.bump],
+                ];
+                let signer = &[&seeds[..]];
+                transfer_tokens(
+                    ctx.accounts.token_program.to_account_info(),
+                    treasury.to_account_info(),
+                    recipient.to_account_info(),
+                    pool.to_account_info(),
+                    mint,
+                    amount,
+                    signer,
+                    ctx.remaining_accounts,
+                )?;
+            }
+            ProposalType::RewardRateChange { reward_rate } => {
+                // Same semantics as set_reward_rate: past accrual uses the old rate
+                pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+                pool.schedule_rate(now, reward_rate);
+                emit_rate_scheduled(pool, now);
+            }
+            ProposalType::PoolParameterUpdate {
+                lockup_period,
+                early_unstake_penalty_bps,
+            } => {
+                if let Some(lockup_period) = lockup_period {
+                    pool.lockup_period = lockup_period;
+                }
+                if let Some(penalty_bps) = early_unstake_penalty_bps {
+                    pool.early_unstake_penalty_bps = penalty_bps;
+                }
+            }
+            // Nothing to apply in the vault: haunti-core's rotate_verifier_key
+            // reads the executed proposal
+            ProposalType::VerifierKeyRotation { .. } => {}
+            // Drains and slashes need their own accounts and go through
+            // drain_rewards and slash_stake
+            ProposalType::DrainRewards | ProposalType::SlashStake { .. } => {
+                return err!(VaultError::InvalidProposal)
+            }
+        }
+
+        proposal.status = ProposalStatus::Executed;
+
+        emit!(GovernanceEvent::ProposalExecuted {
+            proposal: proposal.key(),
+            proposal_type: proposal.proposal_type.clone(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Governance: close a rejected or executed proposal, returning rent to the proposer
+    pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            matches!(proposal.status, ProposalStatus::Rejected | ProposalStatus::Executed),
+            VaultError::ProposalNotFinalized
+        );
+
+        emit!(GovernanceEvent::ProposalClosed {
+            proposal: proposal.key(),
+            status: proposal.status,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Governance: let `delegate` vote with this staker's power without moving tokens.
+    /// Applies to proposals created after the delegation.
+    pub fn delegate_votes(ctx: Context<DelegateVotes>, delegate: Pubkey) -> Result<()> {
+        let delegator = ctx.accounts.delegator.key();
+        require_keys_neq!(delegate, delegator, VaultError::InvalidDelegation);
+
+        let delegation = &mut ctx.accounts.vote_delegation;
+        delegation.pool = ctx.accounts.pool.key();
+        delegation.delegator = delegator;
+        delegation.delegate = delegate;
+        delegation.delegated_at = env::now()?;
+        delegation.bump = *ctx.bumps.get("vote_delegation").unwrap();
+
+        emit!(GovernanceEvent::VotesDelegated {
+            pool: delegation.pool,
+            delegator,
+            delegate,
+            timestamp: delegation.delegated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Governance: revoke a vote delegation; votes already cast with it stand
+    pub fn undelegate_votes(ctx: Context<UndelegateVotes>) -> Result<()> {
+        let delegation = &ctx.accounts.vote_delegation;
+
+        emit!(GovernanceEvent::VotesUndelegated {
+            pool: delegation.pool,
+            delegator: delegation.delegator,
+            delegate: delegation.delegate,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Operator: open a delegation pool for a registered worker. The worker's
+    /// authority co-signs, so nobody else can claim its pool address.
+    pub fn create_delegation_pool(
+        ctx: Context<CreateDelegationPool>,
+        commission_bps: u16,
+    ) -> Result<()> {
+        require!(
+            commission_bps <= MAX_COMMISSION_BPS,
+            VaultError::InvalidCommission
+        );
+
+        let worker = ctx.accounts.worker.key();
+        let delegation_pool = &mut ctx.accounts.delegation_pool;
+        delegation_pool.operator = ctx.accounts.operator.key();
+        delegation_pool.worker = worker;
+        delegation_pool.stake_pool = ctx.accounts.stake_pool.key();
+        delegation_pool.commission_bps = commission_bps;
+        delegation_pool.total_delegated = 0;
+        delegation_pool.total_shares = 0;
+        delegation_pool.bump = *ctx.bumps.get("delegation_pool").unwrap();
+
+        emit!(DelegationEvent::PoolCreated {
+            pool: delegation_pool.key(),
+            worker,
+            commission_bps,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Delegate stake to a worker's delegation pool
+    pub fn delegate(ctx: Context<Delegate>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InsufficientStake);
+        let received = received_amount(&ctx.accounts.mint, amount)?;
+
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_token.to_account_info(),
+            ctx.accounts.delegation_vault.to_account_info(),
+            ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            &[],
+            ctx.remaining_accounts,
+        )?;
+
+        let delegation_pool = &mut ctx.accounts.delegation_pool;
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.delegator = ctx.accounts.owner.key();
+        delegation.pool = delegation_pool.key();
+        let shares = delegation_pool.deposit(delegation, received)?;
+
+        emit!(DelegationEvent::Delegated {
+            pool: delegation_pool.key(),
+            delegator: delegation.delegator,
+            amount: received,
+            shares,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem delegation shares at the pool's current exchange rate
+    pub fn undelegate(ctx: Context<Undelegate>, shares: u64) -> Result<()> {
+        let delegation_pool = &mut ctx.accounts.delegation_pool;
+        let delegation = &mut ctx.accounts.delegation;
+        let amount = delegation_pool.withdraw(delegation, shares)?;
+
+        let seeds = &[b"delegation", delegation_pool.worker.as_ref(), &[delegation_pool.bump]];
+        let signer = &[&seeds[..]];
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.delegation_vault.to_account_info(),
+            ctx.accounts.user_token.to_account_info(),
+            delegation_pool.to_account_info(),
+            &ctx.accounts.mint,
+            amount,
+            signer,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(DelegationEvent::Undelegated {
+            pool: delegation_pool.key(),
+            delegator: delegation.delegator,
+            amount,
+            shares,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Operator: share task rewards with delegators, keeping the commission
+    pub fn distribute_delegation_rewards(
+        ctx: Context<DistributeDelegationRewards>,
+        amount: u64,
+    ) -> Result<()> {
+        let delegation_pool = &mut ctx.accounts.delegation_pool;
+        require!(delegation_pool.total_shares > 0, VaultError::NoRewardsAvailable);
+
+        let commission = amount
+            .checked_mul(delegation_pool.commission_bps as u64)
+            .ok_or(VaultError::InvalidRewardCalc)?
+            / BASIS_POINTS;
+        let delegator_share = amount
+            .checked_sub(commission)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        let received = received_amount(&ctx.accounts.mint, delegator_share)?;
+
+        // Commission stays with the operator; the rest raises the share price
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.operator_token.to_account_info(),
+            ctx.accounts.delegation_vault.to_account_info(),
+            ctx.accounts.operator.to_account_info(),
+            &ctx.accounts.mint,
+            delegator_share,
+            &[],
+            ctx.remaining_accounts,
+        )?;
+
+        delegation_pool.total_delegated = delegation_pool.total_delegated
+            .checked_add(received)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+
+        emit!(DelegationEvent::RewardsDistributed {
+            pool: delegation_pool.key(),
+            amount: received,
+            commission,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Slash `slash_bps` of a provider's liquid stake and of every locked position,
+    /// burning it or moving it to the pool treasury. Signed by the pool's slash
+    /// authority, or cranked by anyone under a passed `SlashStake` proposal for the
+    /// same provider, rate and destination. The provider's positions lead the
+    /// remaining accounts and must cover all of its locked weight; transfer hook
+    /// accounts follow them.
+    pub fn slash_stake(ctx: Context<SlashStake>, slash_bps: u16, burn: bool) -> Result<()> {
+        let now = env::now()?;
+        require!(
+            slash_bps > 0 && slash_bps as u64 <= BASIS_POINTS,
+            VaultError::InvalidSlash
+        );
+
+        let provider = ctx.accounts.provider.key();
+        let mut via_proposal = None;
+        match ctx.accounts.proposal.as_mut() {
+            Some(proposal) => {
+                require!(
+                    proposal.status == ProposalStatus::Passed,
+                    VaultError::ProposalNotPassed
+                );
+                require!(now >= proposal.executable_at, VaultError::TimelockActive);
+                require!(
+                    proposal.pool == ctx.accounts.pool.key()
+                        && proposal.proposal_type
+                            == (ProposalType::SlashStake { provider, slash_bps, burn }),
+                    VaultError::InvalidProposal
+                );
+                proposal.status = ProposalStatus::Executed;
+                via_proposal = Some(proposal.key());
+            }
+            None => require_keys_eq!(
+                ctx.accounts.pool_config.slash_authority.holder,
+                ctx.accounts.authority.key(),
+                VaultError::RoleUnauthorized
+            ),
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        let user = &mut ctx.accounts.user_stake;
+        pool.accrue(now, ctx.accounts.emission_schedule.as_deref())?;
+        let mut amount = user.slash(pool, slash_bps)?;
+
+        let position_count = ctx
+            .remaining_accounts
+            .iter()
+            .take_while(|info| *info.owner == crate::ID)
+            .count();
+        let (position_infos, hook_accounts) = ctx.remaining_accounts.split_at(position_count);
+        let mut seen = Vec::with_capacity(position_count);
+        let mut covered = 0u64;
+        let mut removed = 0u64;
+        for info in position_infos {
+            require!(!seen.contains(info.key), VaultError::InvalidSlash);
+            seen.push(*info.key);
+            let mut position = Account::<StakePosition>::try_from(info)?;
+            require!(
+                position.pool == pool.key() && position.owner == provider,
+                VaultError::InvalidSlash
+            );
+            covered += position.weight;
+            let (slashed, weight) = position.slash(pool, slash_bps)?;
+            amount += slashed;
+            removed += weight;
+            position.exit(&crate::ID)?;
+        }
+
+        let history = &mut ctx.accounts.stake_history;
+        // Leaving a position out would shield its lockup from the slash
+        require!(covered == history.locked_weight, VaultError::InvalidSlash);
+        require!(amount > 0, VaultError::InsufficientStake);
+        history.liquid_amount = user.amount;
+        history.locked_weight -= removed;
+        history.update(now)?;
+
+        let pool_type = pool.pool_type.to_string();
+        let version_seed = pool.version_seed();
+        let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+        let signer = &[&seeds[..]];
+        if burn {
+            let burn_ix = Burn {
+                mint: ctx.accounts.mint.to_account_info(),
+                from: ctx.accounts.vault.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token_interface::burn(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    burn_ix,
+                    signer,
+                ),
+                amount,
+            )?;
+        } else {
+            let treasury = ctx
+                .accounts
+                .treasury
+                .as_ref()
+                .ok_or(VaultError::InvalidSlash)?;
+            transfer_tokens(
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                treasury.to_account_info(),
+                pool.to_account_info(),
+                &ctx.accounts.mint,
+                amount,
+                signer,
+                hook_accounts,
+            )?;
+        }
+
+        emit!(PoolEvent::Slashed {
+            pool: pool.key(),
+            user: provider,
+            amount,
+            slash_bps,
+            burned: burn,
+            proposal: via_proposal,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Governance: slash a delegation pool, shared pro-rata by all delegators
+    pub fn slash_delegation_pool(
+        ctx: Context<SlashDelegationPool>,
+        slash_bps: u16,
+    ) -> Result<()> {
+        require!(slash_bps as u64 <= BASIS_POINTS, VaultError::InvalidRewardCalc);
+
+        let delegation_pool = &mut ctx.accounts.delegation_pool;
+        let slashed = delegation_pool.total_delegated
+            .checked_mul(slash_bps as u64)
+            .ok_or(VaultError::InvalidRewardCalc)?
+            / BASIS_POINTS;
+
+        let seeds = &[b"delegation", delegation_pool.worker.as_ref(), &[delegation_pool.bump]];
+        let signer = &[&seeds[..]];
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.delegation_vault.to_account_info(),
+            ctx.accounts.slash_destination.to_account_info(),
+            delegation_pool.to_account_info(),
+            &ctx.accounts.mint,
+            slashed,
+            signer,
+            ctx.remaining_accounts,
+        )?;
+
+        // Shares are untouched, so every delegator absorbs the same fraction
+        delegation_pool.total_delegated -= slashed;
+
+        emit!(DelegationEvent::Slashed {
+            pool: delegation_pool.key(),
+            amount: slashed,
+            timestamp: env::now()?,
+        });
+
+        Ok(())
+    }
+
+    /// Create a successor to a pool with new parameters or a new stake mint, and
+    /// pause the old pool. The old pool's admin and the successor's authority both
+    /// sign the migration config. Stakers then move across with `migrate_stake`
+    /// and `migrate_position`. When the mint changes, the successor authority funds
+    /// `reserve` new tokens to back migrated stake 1:1, and the old tokens go to
+    /// `legacy_recipient`.
+    pub fn migrate_pool(
+        ctx: Context<MigratePool>,
+        reward_rate: u64,
+        lockup_period: i64,
+        tiers: Vec<LockupTier>,
+        reserve: u64,
+    ) -> Result<()> {
+        validate_tiers(&tiers)?;
+        let accounts = &mut ctx.accounts;
+        let mint_changed = accounts.to_mint.key() != accounts.from_vault.mint;
+        require!(
+            mint_changed == accounts.legacy_recipient.is_some()
+                && mint_changed == accounts.reserve_source.is_some()
+                && mint_changed == (reserve > 0),
+            VaultError::InvalidMigration
+        );
+        let now = env::now()?;
+
+        // Settle emissions up to now, then stop them and new stake; withdrawals
+        // stay open for anyone who would rather leave than migrate
+        let from_pool = &mut accounts.from_pool;
+        from_pool.accrue(now, accounts.from_emission_schedule.as_deref())?;
+        from_pool.paused = true;
+
+        let mut successor = PoolState::new(
+            accounts.authority.key(),
+            from_pool.pool_type.clone(),
+            reward_rate,
+            lockup_period,
+            tiers,
+            ReceiptMode::None,
+            Pubkey::default(),
+            *ctx.bumps.get("to_pool").unwrap(),
+            now,
+        );
+        successor.version = from_pool.version + 1;
+        successor.early_unstake_penalty_bps = from_pool.early_unstake_penalty_bps;
+        successor.penalty_route = from_pool.penalty_route;
+        successor.referral_bps = from_pool.referral_bps;
+        accounts.to_pool.set_inner(successor);
+        accounts.to_config.set_inner(PoolConfig::new(
+            accounts.to_pool.key(),
+            accounts.authority.key(),
+            *ctx.bumps.get("to_config").unwrap(),
+        ));
+
+        let reserve = match &accounts.reserve_source {
+            Some(source) => {
+                let received = received_amount(&accounts.to_mint, reserve)?;
+                transfer_tokens(
+                    accounts.token_program.to_account_info(),
+                    source.to_account_info(),
+                    accounts.to_vault.to_account_info(),
+                    accounts.authority.to_account_info(),
+                    &accounts.to_mint,
+                    reserve,
+                    &[],
+                    ctx.remaining_accounts,
+                )?;
+                received
+            }
+            None => 0,
+        };
+
+        accounts.migration.set_inner(PoolMigration {
+            from_pool: accounts.from_pool.key(),
+            to_pool: accounts.to_pool.key(),
+            from_admin: accounts.from_admin.key(),
+            to_authority: accounts.authority.key(),
+            reserve,
+            legacy_recipient: accounts.legacy_recipient.as_ref().map(|recipient| recipient.key()),
+            migrated_amount: 0,
+            migrated_stakes: 0,
+            created_at: now,
+            bump: *ctx.bumps.get("migration").unwrap(),
+        });
+
+        emit!(PoolEvent::PoolMigrated {
+            from_pool: accounts.from_pool.key(),
+            to_pool: accounts.to_pool.key(),
+            mint_changed,
+            reserve,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Move the caller's stake from a migrated pool into its successor, keeping
+    /// its lockup age and settled rewards. Referral cuts must be synced first.
+    pub fn migrate_stake(ctx: Context<MigrateStake>) -> Result<()> {
+        let accounts = &mut ctx.accounts;
+        let now = env::now()?;
+        accounts.from_pool.accrue(now, accounts.from_emission_schedule.as_deref())?;
+        accounts.to_pool.accrue(now, accounts.to_emission_schedule.as_deref())?;
+
+        let last_staked = accounts.from_stake.last_staked;
+        let (amount, rewards) = accounts.from_stake.take_for_migration(&mut accounts.from_pool)?;
+        require!(amount > 0, VaultError::InsufficientStake);
+        accounts.from_history.liquid_amount = 0;
+        accounts.from_history.update(now)?;
+
+        accounts.migration.record(amount, rewards)?;
+        let (amount, rewards) = accounts.vaults().transfer(amount, rewards, ctx.remaining_accounts)?;
+
+        let stake = &mut accounts.to_stake;
+        stake.credit_migrated(&mut accounts.to_pool, amount, rewards, last_staked)?;
+
+        let history = &mut accounts.to_history;
+        if history.owner == Pubkey::default() {
+            history.pool = accounts.to_pool.key();
+            history.owner = accounts.owner.key();
+            history.bump = *ctx.bumps.get("to_history").unwrap();
+        }
+        history.liquid_amount = stake.amount;
+        history.update(now)?;
+
+        emit!(PoolEvent::StakeMigrated {
+            from_pool: accounts.from_pool.key(),
+            to_pool: accounts.to_pool.key(),
+            stake: stake.key(),
+            owner: accounts.owner.key(),
+            amount,
+            rewards,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Move one locked position from a migrated pool into its successor under the
+    /// same id. Tier, multiplier and unlock time carry over, as do its rewards.
+    pub fn migrate_position(ctx: Context<MigratePosition>) -> Result<()> {
+        let accounts = &mut ctx.accounts;
+        let now = env::now()?;
+        accounts.from_pool.accrue(now, accounts.from_emission_schedule.as_deref())?;
+        accounts.to_pool.accrue(now, accounts.to_emission_schedule.as_deref())?;
+
+        let old = &mut accounts.from_position;
+        old.settle(&accounts.from_pool)?;
+        let (amount, rewards) = (old.amount, old.unclaimed);
+        accounts.from_pool.total_staked -= amount;
+        accounts.from_pool.total_weight -= old.weight;
+        accounts.from_history.locked_weight -= old.weight;
+        accounts.from_history.update(now)?;
+
+        accounts.migration.record(amount, rewards)?;
+        let (amount, rewards) = accounts.vaults().transfer(amount, rewards, ctx.remaining_accounts)?;
+
+        let position = accounts.from_position.migrated(
+            &accounts.to_pool,
+            accounts.to_pool.key(),
+            amount,
+            rewards,
+            *ctx.bumps.get("to_position").unwrap(),
+        )?;
+        accounts.to_pool.total_staked += position.amount;
+        accounts.to_pool.total_weight += position.weight;
+
+        let history = &mut accounts.to_history;
+        if history.owner == Pubkey::default() {
+            history.pool = accounts.to_pool.key();
+            history.owner = accounts.owner.key();
+            history.bump = *ctx.bumps.get("to_history").unwrap();
+        }
+        history.locked_weight += position.weight;
+        history.update(now)?;
+        accounts.to_position.set_inner(position);
+
+        emit!(PoolEvent::StakeMigrated {
+            from_pool: accounts.from_pool.key(),
+            to_pool: accounts.to_pool.key(),
+            stake: accounts.to_position.key(),
+            owner: accounts.owner.key(),
+            amount,
+            rewards,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = PoolState::LEN,
+        seeds = [b"pool", pool_type.to_string().as_bytes()],
+        bump,
+    )]
+    pub pool: Account<'info, PoolState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = PoolConfig::LEN,
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// stHAUNT mint, only for `ReceiptMode::Liquid` pools
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = mint.decimals,
+        mint::authority = pool,
+        mint::token_program = token_program,
+        seeds = [b"receipt_mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = UserStake::LEN,
+        seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = StakeHistory::LEN,
+        seeds = [b"stake_history", pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub stake_history: Account<'info, StakeHistory>,
+    
+    /// The pool's own vault; stake credited anywhere else could be withdrawn
+    /// from it for free
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(address = vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyUnstake<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_history", pool.key().as_ref(), owner.key().as_ref()],
+        bump = stake_history.bump,
+    )]
+    pub stake_history: Account<'info, StakeHistory>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        token::mint = mint,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"treasury", pool.key().as_ref()],
+        bump,
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, tier: u8, position_id: u64)]
+pub struct StakeLocked<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = StakePosition::LEN,
+        seeds = [
+            b"position",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &position_id.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub position: Account<'info, StakePosition>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = StakeHistory::LEN,
+        seeds = [b"stake_history", pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub stake_history: Account<'info, StakeHistory>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Position NFT, only for `ReceiptMode::PositionNft` pools
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = pool,
+        mint::token_program = token_program,
+        seeds = [b"position_receipt", position.key().as_ref()],
+        bump,
+    )]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub receipt_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPositionRewards<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner,
+        seeds = [
+            b"position",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &position.id.to_le_bytes(),
+        ],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, StakePosition>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakePosition<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = pool,
+        has_one = owner,
+        seeds = [
+            b"position",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &position.id.to_le_bytes(),
+        ],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, StakePosition>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_history", pool.key().as_ref(), owner.key().as_ref()],
+        bump = stake_history.bump,
+    )]
+    pub stake_history: Account<'info, StakeHistory>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"position_receipt", position.key().as_ref()],
+        bump,
+    )]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = owner,
+        token::token_program = token_program,
+    )]
+    pub receipt_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct StakeLiquid<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = pool.receipt_mint)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub receipt_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault.mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(address = vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeLiquid<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = pool.receipt_mint)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = owner,
+        token::token_program = token_program,
+    )]
+    pub receipt_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault.mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    #[account(address = vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Compound<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_history", pool.key().as_ref(), owner.key().as_ref()],
+        bump = stake_history.bump,
+    )]
+    pub stake_history: Account<'info, StakeHistory>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault.mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundPosition<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner,
+        seeds = [
+            b"position",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &position.id.to_le_bytes(),
+        ],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, StakePosition>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_history", pool.key().as_ref(), owner.key().as_ref()],
+        bump = stake_history.bump,
+    )]
+    pub stake_history: Account<'info, StakeHistory>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault.mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct PausePool<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.pauser.holder == pauser.key() @ VaultError::RoleUnauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub pauser: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnpausePool<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.pool_admin.holder == admin.key() @ VaultError::RoleUnauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeRoleTransfer<'info> {
+    #[account(mut)]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// Current holder of the role being transferred
+    pub holder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptRole<'info> {
+    #[account(mut)]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub nominee: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetEarlyUnstakePenalty<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.fee_manager.holder == fee_manager.key()
+            @ VaultError::RoleUnauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub fee_manager: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetReferralCut<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.fee_manager.holder == fee_manager.key()
+            @ VaultError::RoleUnauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub fee_manager: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncReferral<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    /// CHECK: owner of the referred stake; only used to derive its PDA
+    pub referee: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", pool.key().as_ref(), referee.key().as_ref()],
+        bump,
+        constraint = user_stake.referrer == Some(referrer.key()) @ VaultError::InvalidReferrer,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// CHECK: referrer recorded on `user_stake`
+    pub referrer: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ReferralState::LEN,
+        seeds = [b"referral", pool.key().as_ref(), referrer.key().as_ref()],
+        bump,
+    )]
+    pub referral: Account<'info, ReferralState>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = referrer,
+        seeds = [b"referral", pool.key().as_ref(), referrer.key().as_ref()],
+        bump = referral.bump,
+    )]
+    pub referral: Account<'info, ReferralState>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = referrer,
+        associated_token::token_program = token_program,
+    )]
+    pub referrer_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub referrer: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardRate<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.pool_admin.holder == admin.key() @ VaultError::RoleUnauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRateRamp<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.pool_admin.holder == admin.key() @ VaultError::RoleUnauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetEmissionSchedule<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = EmissionSchedule::LEN,
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump,
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.pool_admin.holder == admin.key() @ VaultError::RoleUnauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClearEmissionSchedule<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.pool_admin.holder == admin.key() @ VaultError::RoleUnauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        init_if_needed,
+        payer = funder,
+        associated_token::mint = mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = funder,
+        associated_token::token_program = token_program,
+    )]
+    pub funder_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct DrainRewards<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        constraint = proposal.proposal_type == ProposalType::DrainRewards,
+        constraint = proposal.status == ProposalStatus::Passed @ VaultError::ProposalNotActive,
+        constraint = proposal.recipient == Some(recipient_token.key()),
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = mint)]
+    pub recipient_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseUserStake<'info> {
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(proposal_type: ProposalType, amount: Option<u64>, recipient: Option<Pubkey>, epoch: u64)]
+pub struct CreateProposal<'info> {
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+        constraint = user_stake.amount >= MIN_VOTING_STAKE @ VaultError::InsufficientVotingPower,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(init, payer = owner, space = Proposal::LEN)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = GovernanceEpoch::LEN,
+        seeds = [b"governance_epoch", pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump,
+    )]
+    pub governance_epoch: Account<'info, GovernanceEpoch>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Vote<'info> {
+    pub pool: Account<'info, PoolState>,
+
+    #[account(mut, has_one = pool)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        seeds = [b"stake_history", pool.key().as_ref(), owner.key().as_ref()],
+        bump = stake_history.bump,
+    )]
+    pub stake_history: Option<Account<'info, StakeHistory>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = VoteRecord::LEN,
+        seeds = [b"vote", proposal.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = EpochParticipation::LEN,
+        seeds = [
+            b"participation",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &proposal.epoch.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub participation: Account<'info, EpochParticipation>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DelegateVotes<'info> {
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        init,
+        payer = delegator,
+        space = VoteDelegation::LEN,
+        seeds = [b"vote_delegation", pool.key().as_ref(), delegator.key().as_ref()],
+        bump,
+    )]
+    pub vote_delegation: Account<'info, VoteDelegation>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProposal<'info> {
+    #[account(mut, close = proposer, has_one = proposer)]
+    pub proposal: Account<'info, Proposal>,
+
+    /// CHECK: receives the rent; validated against `proposal.proposer`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UndelegateVotes<'info> {
+    #[account(
+        mut,
+        close = delegator,
+        has_one = delegator,
+        seeds = [b"vote_delegation", vote_delegation.pool.as_ref(), delegator.key().as_ref()],
+        bump = vote_delegation.bump,
+    )]
+    pub vote_delegation: Account<'info, VoteDelegation>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
+    pub pool: Account<'info, PoolState>,
+
+    #[account(mut, has_one = pool)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"governance_epoch", pool.key().as_ref(), &proposal.epoch.to_le_bytes()],
+        bump = governance_epoch.bump,
+    )]
+    pub governance_epoch: Account<'info, GovernanceEpoch>,
+}
+
+#[derive(Accounts)]
+pub struct SetParticipationRewards<'info> {
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ParticipationConfig::LEN,
+        seeds = [b"participation_config", pool.key().as_ref()],
+        bump,
+    )]
+    pub participation_config: Account<'info, ParticipationConfig>,
+
+    #[account(
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.pool_admin.holder == admin.key() @ VaultError::RoleUnauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimParticipationReward<'info> {
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"participation_config", pool.key().as_ref()],
+        bump = participation_config.bump,
+    )]
+    pub participation_config: Account<'info, ParticipationConfig>,
+
+    #[account(
+        mut,
+        has_one = voter,
+        seeds = [
+            b"participation",
+            pool.key().as_ref(),
+            voter.key().as_ref(),
+            &participation.epoch.to_le_bytes(),
+        ],
+        bump = participation.bump,
+    )]
+    pub participation: Account<'info, EpochParticipation>,
+
+    #[account(
+        mut,
+        seeds = [b"governance_epoch", pool.key().as_ref(), &participation.epoch.to_le_bytes()],
+        bump = governance_epoch.bump,
+    )]
+    pub governance_epoch: Account<'info, GovernanceEpoch>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = VoterStreak::LEN,
+        seeds = [b"voter_streak", pool.key().as_ref(), voter.key().as_ref()],
+        bump,
+    )]
+    pub streak: Account<'info, VoterStreak>,
+
+    #[account(
+        seeds = [b"stake_history", pool.key().as_ref(), voter.key().as_ref()],
+        bump = stake_history.bump,
+    )]
+    pub stake_history: Account<'info, StakeHistory>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", pool.key().as_ref()],
+        bump,
+        constraint = treasury.mint == mint.key() @ VaultError::InvalidParticipationConfig,
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = voter_token.mint == mint.key() @ VaultError::InvalidParticipationConfig,
+        constraint = voter_token.owner == voter.key() @ VaultError::InvalidParticipationConfig,
+    )]
+    pub voter_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(mut, has_one = pool)]
+    pub proposal: Account<'info, Proposal>,
+
+    /// Source of treasury transfers
+    #[account(
+        mut,
+        seeds = [b"treasury", pool.key().as_ref()],
+        bump,
+    )]
+    pub treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub recipient_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Mint of the treasury, required for treasury transfers
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_history", pool.key().as_ref(), owner.key().as_ref()],
+        bump = stake_history.bump,
+    )]
+    pub stake_history: Account<'info, StakeHistory>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(worker: Pubkey)]
+pub struct CreateDelegationPool<'info> {
+    #[account(
+        init,
+        payer = operator,
+        space = DelegationPool::LEN,
+        seeds = [b"delegation", worker.key().as_ref()],
+        bump,
+    )]
+    pub delegation_pool: Account<'info, DelegationPool>,
+
+    #[account(
+        init,
+        payer = operator,
+        token::mint = mint,
+        token::authority = delegation_pool,
+        token::token_program = token_program,
+        seeds = [b"delegation_vault", delegation_pool.key().as_ref()],
+        bump,
+    )]
+    pub delegation_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(constraint = stake_pool.pool_type == PoolType::GPUProvider)]
+    pub stake_pool: Account<'info, PoolState>,
+
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    /// Worker authority; its key seeds the pool
+    pub worker: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Delegate<'info> {
+    #[account(mut)]
+    pub delegation_pool: Account<'info, DelegationPool>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Delegation::LEN,
+        seeds = [b"delegator", delegation_pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(
+        mut,
+        seeds = [b"delegation_vault", delegation_pool.key().as_ref()],
+        bump,
+    )]
+    pub delegation_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct Undelegate<'info> {
+    #[account(mut)]
+    pub delegation_pool: Account<'info, DelegationPool>,
+
+    #[account(
+        mut,
+        has_one = delegator,
+        seeds = [b"delegator", delegation_pool.key().as_ref(), delegator.key().as_ref()],
+        bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(
+        mut,
+        seeds = [b"delegation_vault", delegation_pool.key().as_ref()],
+        bump,
+    )]
+    pub delegation_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = delegation_vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub delegator: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeDelegationRewards<'info> {
+    #[account(mut, has_one = operator)]
+    pub delegation_pool: Account<'info, DelegationPool>,
+
+    #[account(
+        mut,
+        seeds = [b"delegation_vault", delegation_pool.key().as_ref()],
+        bump,
+    )]
+    pub delegation_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub operator_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = delegation_vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub operator: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SlashDelegationPool<'info> {
+    #[account(mut, has_one = stake_pool)]
+    pub delegation_pool: Account<'info, DelegationPool>,
+
+    pub stake_pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"pool_config", stake_pool.key().as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.slash_authority.holder == slash_authority.key()
+            @ VaultError::RoleUnauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"delegation_vault", delegation_pool.key().as_ref()],
+        bump,
+    )]
+    pub delegation_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub slash_destination: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = delegation_vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub slash_authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SlashStake<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", pool.key().as_ref()],
+        bump = emission_schedule.bump,
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        seeds = [b"pool_config", pool.key().as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// CHECK: only used to derive the provider's stake accounts
+    pub provider: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", pool.key().as_ref(), provider.key().as_ref()],
+        bump,
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_history", pool.key().as_ref(), provider.key().as_ref()],
+        bump = stake_history.bump,
+    )]
+    pub stake_history: Account<'info, StakeHistory>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Receives the slash; required unless it is burned
+    #[account(
+        mut,
+        seeds = [b"treasury", pool.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Passed `SlashStake` proposal; without one `authority` must be the slash authority
+    #[account(mut)]
+    pub proposal: Option<Account<'info, Proposal>>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    #[account(
+        mut,
+        constraint = from_pool.receipt_mode == ReceiptMode::None @ VaultError::InvalidMigration,
+    )]
+    pub from_pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", from_pool.key().as_ref()],
+        bump = from_emission_schedule.bump,
+    )]
+    pub from_emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        seeds = [b"pool_config", from_pool.key().as_ref()],
+        bump = from_config.bump,
+        constraint = from_config.pool_admin.holder == from_admin.key()
+            @ VaultError::RoleUnauthorized,
+    )]
+    pub from_config: Account<'info, PoolConfig>,
+
+    /// Admin of the old pool, co-signing the hand-over
+    pub from_admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", from_pool.key().as_ref()],
+        bump,
+    )]
+    pub from_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = PoolState::LEN,
+        seeds = [
+            b"pool",
+            from_pool.pool_type.to_string().as_bytes(),
+            &[from_pool.version.saturating_add(1)],
+        ],
+        bump,
+    )]
+    pub to_pool: Account<'info, PoolState>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = PoolConfig::LEN,
+        seeds = [b"pool_config", to_pool.key().as_ref()],
+        bump,
+    )]
+    pub to_config: Account<'info, PoolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = to_mint,
+        token::authority = to_pool,
+        token::token_program = token_program,
+        seeds = [b"vault", to_pool.key().as_ref()],
+        bump,
+    )]
+    pub to_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = to_mint,
+        associated_token::authority = to_pool,
+        associated_token::token_program = token_program,
+    )]
+    pub to_reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = PoolMigration::LEN,
+        seeds = [b"pool_migration", from_pool.key().as_ref()],
+        bump,
+    )]
+    pub migration: Account<'info, PoolMigration>,
+
+    /// Authority of the successor pool
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Stake mint of the successor; may differ from the old pool's
+    pub to_mint: InterfaceAccount<'info, Mint>,
+
+    /// Funds the reserve; only when the mint changes
+    #[account(
+        mut,
+        token::mint = to_mint,
+        token::authority = authority,
+        token::token_program = token_program,
+    )]
+    pub reserve_source: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives the old pool's tokens; only when the mint changes
+    #[account(token::mint = from_vault.mint)]
+    pub legacy_recipient: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateStake<'info> {
+    #[account(mut)]
+    pub from_pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", from_pool.key().as_ref()],
+        bump = from_emission_schedule.bump,
+    )]
+    pub from_emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"stake", from_pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub from_stake: Account<'info, UserStake>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_history", from_pool.key().as_ref(), owner.key().as_ref()],
+        bump = from_history.bump,
+    )]
+    pub from_history: Account<'info, StakeHistory>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_migration", from_pool.key().as_ref()],
+        bump = migration.bump,
+        has_one = to_pool @ VaultError::InvalidMigration,
+    )]
+    pub migration: Account<'info, PoolMigration>,
+
+    #[account(mut)]
+    pub to_pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", to_pool.key().as_ref()],
+        bump = to_emission_schedule.bump,
+    )]
+    pub to_emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = UserStake::LEN,
+        seeds = [b"stake", to_pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub to_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = StakeHistory::LEN,
+        seeds = [b"stake_history", to_pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub to_history: Account<'info, StakeHistory>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", from_pool.key().as_ref()],
+        bump,
+    )]
+    pub from_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = from_mint,
+        associated_token::authority = from_pool,
+        associated_token::token_program = token_program,
+    )]
+    pub from_reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = from_vault.mint)]
+    pub from_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", to_pool.key().as_ref()],
+        bump,
+    )]
+    pub to_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = to_mint,
+        associated_token::authority = to_pool,
+        associated_token::token_program = token_program,
+    )]
+    pub to_reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = to_vault.mint)]
+    pub to_mint: InterfaceAccount<'info, Mint>,
+
+    /// The migration's legacy recipient, when the mint changed
+    #[account(
+        mut,
+        constraint = Some(legacy_recipient.key()) == migration.legacy_recipient
+            @ VaultError::InvalidMigration,
+    )]
+    pub legacy_recipient: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> MigrateStake<'info> {
+    fn vaults(&self) -> MigrationVaults<'_, 'info> {
+        MigrationVaults {
+            from_pool: &self.from_pool,
+            from_vault: &self.from_vault,
+            from_reward_vault: &self.from_reward_vault,
+            from_mint: &self.from_mint,
+            to_pool: &self.to_pool,
+            to_vault: &self.to_vault,
+            to_reward_vault: &self.to_reward_vault,
+            to_mint: &self.to_mint,
+            legacy_recipient: self.legacy_recipient.as_ref(),
+            migration: &self.migration,
+            token_program: &self.token_program,
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct MigratePosition<'info> {
+    #[account(mut)]
+    pub from_pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", from_pool.key().as_ref()],
+        bump = from_emission_schedule.bump,
+    )]
+    pub from_emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            b"position",
+            from_pool.key().as_ref(),
+            owner.key().as_ref(),
+            &from_position.id.to_le_bytes(),
+        ],
+        bump = from_position.bump,
+    )]
+    pub from_position: Account<'info, StakePosition>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_history", from_pool.key().as_ref(), owner.key().as_ref()],
+        bump = from_history.bump,
+    )]
+    pub from_history: Account<'info, StakeHistory>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_migration", from_pool.key().as_ref()],
+        bump = migration.bump,
+        has_one = to_pool @ VaultError::InvalidMigration,
+    )]
+    pub migration: Account<'info, PoolMigration>,
+
+    #[account(mut)]
+    pub to_pool: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"emission_schedule", to_pool.key().as_ref()],
+        bump = to_emission_schedule.bump,
+    )]
+    pub to_emission_schedule: Option<Account<'info, EmissionSchedule>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = StakePosition::LEN,
+        seeds = [
+            b"position",
+            to_pool.key().as_ref(),
+            owner.key().as_ref(),
+            &from_position.id.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub to_position: Account<'info, StakePosition>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = StakeHistory::LEN,
+        seeds = [b"stake_history", to_pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub to_history: Account<'info, StakeHistory>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", from_pool.key().as_ref()],
+        bump,
+    )]
+    pub from_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = from_mint,
+        associated_token::authority = from_pool,
+        associated_token::token_program = token_program,
+    )]
+    pub from_reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = from_vault.mint)]
+    pub from_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", to_pool.key().as_ref()],
+        bump,
+    )]
+    pub to_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = to_mint,
+        associated_token::authority = to_pool,
+        associated_token::token_program = token_program,
+    )]
+    pub to_reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = to_vault.mint)]
+    pub to_mint: InterfaceAccount<'info, Mint>,
+
+    /// The migration's legacy recipient, when the mint changed
+    #[account(
+        mut,
+        constraint = Some(legacy_recipient.key()) == migration.legacy_recipient
+            @ VaultError::InvalidMigration,
+    )]
+    pub legacy_recipient: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> MigratePosition<'info> {
+    fn vaults(&self) -> MigrationVaults<'_, 'info> {
+        MigrationVaults {
+            from_pool: &self.from_pool,
+            from_vault: &self.from_vault,
+            from_reward_vault: &self.from_reward_vault,
+            from_mint: &self.from_mint,
+            to_pool: &self.to_pool,
+            to_vault: &self.to_vault,
+            to_reward_vault: &self.to_reward_vault,
+            to_mint: &self.to_mint,
+            legacy_recipient: self.legacy_recipient.as_ref(),
+            migration: &self.migration,
+            token_program: &self.token_program,
+        }
+    }
+}
+
+#[account]
+pub struct PoolState {
+    pub version: u8,
+    /// Pool creator; administrative rights live in `PoolConfig`
+    pub authority: Pubkey,
+    pub pool_type: PoolType,
+    /// Tokens emitted per second across the whole pool; while `rate_ramp` is set,
+    /// the rate at the start of the ramp
+    pub reward_rate: u64,
+    pub lockup_period: i64,
+    pub total_staked: u64,
+    /// Stake scaled by lockup multipliers; rewards and voting power follow this
+    pub total_weight: u64,
+    /// Lockup tiers for `stake_locked`, shortest first
+    pub tiers: Vec<LockupTier>,
+    /// Funded rewards not yet accrued to stakers
+    pub reward_reserve: u64,
+    /// Rewards per staked token, scaled by `ACC_PRECISION`
+    pub acc_reward_per_share: u128,
+    /// Penalty on stake withdrawn before `lockup_period` expires
+    pub early_unstake_penalty_bps: u16,
+    pub penalty_route: PenaltyRoute,
+    /// Share of a referred staker's rewards withheld for the referrer
+    pub referral_bps: u16,
+    /// Window over which reward rate changes ramp in; 0 applies them at once
+    pub rate_ramp_secs: i64,
+    /// Reward rate change in progress
+    pub rate_ramp: Option<RateRamp>,
+    /// Circuit breaker: blocks stake, claim, and compound but never withdrawals
+    pub paused: bool,
+    /// Emissions follow the pool's `EmissionSchedule` instead of `reward_rate`
+    pub scheduled: bool,
+    /// Receipt minted against stake; fixed at initialization
+    pub receipt_mode: ReceiptMode,
+    /// stHAUNT mint of a `ReceiptMode::Liquid` pool
+    pub receipt_mint: Pubkey,
+    /// stHAUNT outstanding
+    pub receipt_supply: u64,
+    /// Stake backing outstanding stHAUNT, including restaked rewards
+    pub liquid_backing: u64,
+    /// `liquid_backing * acc_reward_per_share` at the last liquid settlement
+    pub liquid_reward_debt: u128,
+    pub bump: u8,
+    pub last_update: i64,
+}
+
+/// Linear move of the flat reward rate from `PoolState::reward_rate` to
+/// `target_rate` over `[start_ts, end_ts)`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateRamp {
+    pub target_rate: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
+
+impl RateRamp {
+    pub const LEN: usize = 8 + 8 + 8;
+}
+
+/// Administrative roles of a pool, each held and rotated independently
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Reward rate and other pool parameters
+    PoolAdmin,
+    /// Slashing of delegation pools staked against this pool
+    SlashAuthority,
+    /// Early-unstake penalty and its routing
+    FeeManager,
+    /// Emergency pause
+    Pauser,
+}
+
+/// Holder of one role and the nominee awaiting acceptance, if any
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoleSlot {
+    pub holder: Pubkey,
+    pub pending: Option<Pubkey>,
+}
+
+impl RoleSlot {
+    pub const LEN: usize = 32 + 33;
+
+    pub fn new(holder: Pubkey) -> Self {
+        Self { holder, pending: None }
+    }
+}
+
+/// Per-pool role assignments, PDA of `[b"pool_config", pool]`
+#[account]
+pub struct PoolConfig {
+    pub pool: Pubkey,
+    pub pool_admin: RoleSlot,
+    pub slash_authority: RoleSlot,
+    pub fee_manager: RoleSlot,
+    pub pauser: RoleSlot,
+    pub bump: u8,
+}
+
+impl PoolConfig {
+    pub const LEN: usize = 8 + 32 + 4 * RoleSlot::LEN + 1;
+
+    /// Every role starts with the creator and is rotated independently afterwards
+    pub fn new(pool: Pubkey, creator: Pubkey, bump: u8) -> Self {
+        let creator = RoleSlot::new(creator);
+        Self {
+            pool,
+            pool_admin: creator,
+            slash_authority: creator,
+            fee_manager: creator,
+            pauser: creator,
+            bump,
+        }
+    }
+
+    fn slot_mut(&mut self, role: Role) -> &mut RoleSlot {
+        match role {
+            Role::PoolAdmin => &mut self.pool_admin,
+            Role::SlashAuthority => &mut self.slash_authority,
+            Role::FeeManager => &mut self.fee_manager,
+            Role::Pauser => &mut self.pauser,
+        }
+    }
+
+    /// First step of a rotation; only the current holder may nominate
+    pub fn propose(&mut self, role: Role, by: Pubkey, nominee: Option<Pubkey>) -> Result<()> {
+        let slot = self.slot_mut(role);
+        require_keys_eq!(slot.holder, by, VaultError::RoleUnauthorized);
+        slot.pending = nominee;
+        Ok(())
+    }
+
+    /// Second step; returns the previous holder
+    pub fn accept(&mut self, role: Role, by: Pubkey) -> Result<Pubkey> {
+        let slot = self.slot_mut(role);
+        require!(slot.pending.is_some(), VaultError::NoPendingRoleTransfer);
+        require!(slot.pending == Some(by), VaultError::RoleUnauthorized);
+        let previous = slot.holder;
+        *slot = RoleSlot::new(by);
+        Ok(previous)
+    }
+}
+
+/// Lockup duration offered by a pool and the boost it earns
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockupTier {
+    pub duration: i64,
+    /// Reward and voting multiplier; `BASIS_POINTS` is 1x
+    pub multiplier_bps: u16,
+}
+
+impl LockupTier {
+    pub const LEN: usize = 8 + 2;
+
+    /// Weight of `amount` locked for this tier
+    pub fn weight(&self, amount: u64) -> Result<u64> {
+        let weight = (amount as u128 * self.multiplier_bps as u128) / BASIS_POINTS as u128;
+        weight.try_into().map_err(|_| VaultError::InvalidRewardCalc.into())
+    }
+}
+
+/// Transferable receipt a pool mints against stake
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReceiptMode {
+    /// Stake is not tokenized
+    None,
+    /// Fungible stHAUNT shares of the pool's liquid stake, redeemable by any holder
+    Liquid,
+    /// One NFT per locked position, burned to withdraw the position
+    PositionNft,
+}
+
+/// Destination of early-unstake penalties
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PenaltyRoute {
+    /// Recycled as rewards for the remaining stakers
+    RewardReserve,
+    /// Sent to the pool's treasury PDA
+    Treasury,
+}
+
+impl PoolState {
+    pub const LEN: usize = 8 + // discriminator
+        1 +  // version
+        32 + // authority
+        1 +  // pool_type
+        8 +  // reward_rate
+        8 +  // lockup_period
+        8 +  // total_staked
+        8 +  // total_weight
+        4 + MAX_LOCKUP_TIERS * LockupTier::LEN + // tiers
+        8 +  // reward_reserve
+        16 + // acc_reward_per_share
+        2 +  // early_unstake_penalty_bps
+        1 +  // penalty_route
+        2 +  // referral_bps
+        8 +  // rate_ramp_secs
+        1 + RateRamp::LEN + // rate_ramp
+        1 +  // paused
+        1 +  // scheduled
+        1 +  // receipt_mode
+        32 + // receipt_mint
+        8 +  // receipt_supply
+        8 +  // liquid_backing
+        16 + // liquid_reward_debt
+        1 +  // bump
+        8;   // last_update
+
+    /// Empty pool with every optional feature off
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        authority: Pubkey,
+        pool_type: PoolType,
+        reward_rate: u64,
+        lockup_period: i64,
+        tiers: Vec<LockupTier>,
+        receipt_mode: ReceiptMode,
+        receipt_mint: Pubkey,
+        bump: u8,
+        now: i64,
+    ) -> Self {
+        Self {
+            version: 1,
+            authority,
+            pool_type,
+            reward_rate,
+            lockup_period,
+            total_staked: 0,
+            total_weight: 0,
+            tiers,
+            reward_reserve: 0,
+            acc_reward_per_share: 0,
+            early_unstake_penalty_bps: 0,
+            penalty_route: PenaltyRoute::RewardReserve,
+            referral_bps: 0,
+            rate_ramp_secs: 0,
+            rate_ramp: None,
+            paused: false,
+            scheduled: false,
+            receipt_mode,
+            receipt_mint,
+            receipt_supply: 0,
+            liquid_backing: 0,
+            liquid_reward_debt: 0,
+            bump,
+            last_update: now,
+        }
+    }
+
+    /// Seed telling successors of a pool type apart: empty for the first pool,
+    /// whose address predates migrations, else the version. An empty seed adds
+    /// nothing to the derivation, so it can always sit in the seed list.
+    pub fn version_seed(&self) -> Vec<u8> {
+        if self.version > 1 {
+            vec![self.version]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Weight that can vote; liquid stake is held by the pool, not its stakers
+    pub fn voting_weight(&self) -> u64 {
+        self.total_weight - self.liquid_backing
+    }
+
+    /// stHAUNT minted for a deposit at the current exchange rate, rounded down
+    pub fn receipt_shares_for(&self, amount: u64) -> Result<u64> {
+        if self.receipt_supply == 0 || self.liquid_backing == 0 {
+            return Ok(amount);
+        }
+        let shares = (amount as u128)
+            .checked_mul(self.receipt_supply as u128)
+            .ok_or(VaultError::InvalidRewardCalc)?
+            / self.liquid_backing as u128;
+        Ok(shares.try_into().map_err(|_| VaultError::InvalidRewardCalc)?)
+    }
+
+    /// Stake redeemable for `shares` stHAUNT, rounded down
+    pub fn receipt_amount_for(&self, shares: u64) -> Result<u64> {
+        require!(shares <= self.receipt_supply, VaultError::InsufficientStake);
+        if self.receipt_supply == 0 {
+            return Ok(0);
+        }
+        let amount = (shares as u128)
+            .checked_mul(self.liquid_backing as u128)
+            .ok_or(VaultError::InvalidRewardCalc)?
+            / self.receipt_supply as u128;
+        Ok(amount.try_into().map_err(|_| VaultError::InvalidRewardCalc)?)
+    }
+
+    /// Rewards earned by the liquid stake since its last settlement. The pool
+    /// must already be accrued to now.
+    pub fn pending_liquid_rewards(&self) -> Result<u64> {
+        let accrued = (self.liquid_backing as u128)
+            .checked_mul(self.acc_reward_per_share)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        Ok((accrued.saturating_sub(self.liquid_reward_debt) / ACC_PRECISION) as u64)
+    }
+
+    /// Credit stake added to the liquid share, minting `shares` against it.
+    /// Restaked rewards come in with no shares and raise the exchange rate.
+    pub fn add_liquid(&mut self, amount: u64, shares: u64) -> Result<()> {
+        self.liquid_backing = self.liquid_backing
+            .checked_add(amount)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.receipt_supply = self.receipt_supply
+            .checked_add(shares)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.total_staked += amount;
+        self.total_weight += amount;
+        self.settle_liquid()
+    }
+
+    /// Debit stake redeemed for `shares` burned stHAUNT
+    pub fn remove_liquid(&mut self, amount: u64, shares: u64) -> Result<()> {
+        self.liquid_backing -= amount;
+        self.receipt_supply -= shares;
+        self.total_staked -= amount;
+        self.total_weight -= amount;
+        self.settle_liquid()
+    }
+
+    fn settle_liquid(&mut self) -> Result<()> {
+        self.liquid_reward_debt = (self.liquid_backing as u128)
+            .checked_mul(self.acc_reward_per_share)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        Ok(())
+    }
+
+    /// Accrue emissions since `last_update` into the per-share accumulator
+    pub fn accrue_rewards(&mut self, now: i64) -> Result<()> {
+        self.accrue(now, None)
+    }
+
+    /// Accrue, walking `schedule` when the pool follows one; it must then be supplied
+    pub fn accrue(&mut self, now: i64, schedule: Option<&EmissionSchedule>) -> Result<()> {
+        require!(
+            !self.scheduled || schedule.is_some(),
+            VaultError::EmissionScheduleRequired
+        );
+        let from = self.last_update;
+        let elapsed = now.saturating_sub(from).max(0) as u64;
+        self.last_update = now;
+        // Nothing is emitted while paused
+        if elapsed == 0 || self.total_weight == 0 || self.paused {
+            self.finish_ramp(now);
+            return Ok(());
+        }
+
+        let due = match schedule.filter(|_| self.scheduled) {
+            Some(schedule) => schedule.emitted_between(from, now)?,
+            None => self.flat_emission(from, now)?,
+        };
+        // Emissions never exceed what has been funded
+        let emitted = due.min(self.reward_reserve);
+        self.reward_reserve -= emitted;
+        self.acc_reward_per_share = self.acc_reward_per_share
+            .checked_add(emitted as u128 * ACC_PRECISION / self.total_weight as u128)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.finish_ramp(now);
+
+        Ok(())
+    }
+
+    /// Flat reward rate in effect at `now`, part way along any ramp
+    pub fn effective_rate(&self, now: i64) -> u64 {
+        match self.rate_ramp {
+            Some(ramp) if now < ramp.end_ts => self.ramp_rate_at(&ramp, now) as u64,
+            Some(ramp) => ramp.target_rate,
+            None => self.reward_rate,
+        }
+    }
+
+    /// Move to `target_rate`, at once or ramping from the current effective rate.
+    /// Accrue up to `now` first.
+    pub fn schedule_rate(&mut self, now: i64, target_rate: u64) {
+        self.reward_rate = self.effective_rate(now);
+        self.rate_ramp = None;
+        if self.rate_ramp_secs == 0 || target_rate == self.reward_rate {
+            self.reward_rate = target_rate;
+            return;
+        }
+        self.rate_ramp = Some(RateRamp {
+            target_rate,
+            start_ts: now,
+            end_ts: now + self.rate_ramp_secs,
+        });
+    }
+
+    /// Flat-rate emissions over `[from, to)`: the ramp integrated as a trapezoid,
+    /// then the target rate once it has been reached
+    fn flat_emission(&self, from: i64, to: i64) -> Result<u64> {
+        let Some(ramp) = self.rate_ramp else {
+            return self.reward_rate
+                .checked_mul(to.saturating_sub(from) as u64)
+                .ok_or(VaultError::InvalidRewardCalc.into());
+        };
+
+        let ramp_until = to.min(ramp.end_ts);
+        let mut total: i128 = 0;
+        if from < ramp_until {
+            let (a, b) = (from.max(ramp.start_ts), ramp_until);
+            // ∫ r(t) dt = r0 (b - a) + (r1 - r0) ((b - s)² - (a - s)²) / 2D
+            let r0 = self.reward_rate as i128;
+            let slope = ramp.target_rate as i128 - r0;
+            let (da, db) = ((a - ramp.start_ts) as i128, (b - ramp.start_ts) as i128);
+            let duration = (ramp.end_ts - ramp.start_ts) as i128;
+            total += r0 * (b - a) as i128 + slope * (db * db - da * da) / (2 * duration);
+        }
+        if to > ramp.end_ts {
+            total += ramp.target_rate as i128 * (to - from.max(ramp.end_ts)) as i128;
+        }
+        u64::try_from(total).map_err(|_| VaultError::InvalidRewardCalc.into())
+    }
+
+    fn ramp_rate_at(&self, ramp: &RateRamp, now: i64) -> i128 {
+        let r0 = self.reward_rate as i128;
+        let progress = (now.max(ramp.start_ts) - ramp.start_ts) as i128;
+        let duration = (ramp.end_ts - ramp.start_ts) as i128;
+        r0 + (ramp.target_rate as i128 - r0) * progress / duration
+    }
+
+    /// Adopt the target rate once the ramp window has passed
+    fn finish_ramp(&mut self, now: i64) {
+        if let Some(ramp) = self.rate_ramp.filter(|r| now >= r.end_ts) {
+            self.reward_rate = ramp.target_rate;
+            self.rate_ramp = None;
+        }
+    }
+}
+
+/// Constant emission rate over `[start_ts, end_ts)`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmissionSegment {
+    pub start_ts: i64,
+    pub end_ts: i64,
+    /// Tokens emitted per second across the whole pool
+    pub rate: u64,
+}
+
+impl EmissionSegment {
+    pub const LEN: usize = 8 + 8 + 8;
+}
+
+/// Time-ordered, non-overlapping emission segments for one pool
+#[account]
+pub struct EmissionSchedule {
+    pub pool: Pubkey,
+    pub segments: Vec<EmissionSegment>,
+    pub bump: u8,
+}
+
+impl EmissionSchedule {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // pool
+        4 + MAX_EMISSION_SEGMENTS * EmissionSegment::LEN + // segments
+        1;   // bump
+
+    /// Tokens due over `[from, to)`; uncovered time emits nothing
+    pub fn emitted_between(&self, from: i64, to: i64) -> Result<u64> {
+        let mut total = 0u64;
+        for segment in &self.segments {
+            let start = segment.start_ts.max(from);
+            let end = segment.end_ts.min(to);
+            if end <= start {
+                continue;
+            }
+            let emitted = segment.rate
+                .checked_mul((end - start) as u64)
+                .ok_or(VaultError::InvalidRewardCalc)?;
+            total = total.checked_add(emitted).ok_or(VaultError::InvalidRewardCalc)?;
+        }
+        Ok(total)
+    }
+}
+
+#[account]
+pub struct UserStake {
+    pub amount: u64,
+    pub last_staked: i64,
+    pub last_reward: i64,
+    /// `amount * acc_reward_per_share` at the last settlement
+    pub reward_debt: u128,
+    /// Settled rewards awaiting claim
+    pub unclaimed: u64,
+    /// Referrer named on the first stake
+    pub referrer: Option<Pubkey>,
+    /// Referral cut withheld from settled rewards, not yet synced to the referrer
+    pub referral_owed: u64,
+}
+
+impl UserStake {
+    /// Accumulated entitlement of the current stake, scaled by `ACC_PRECISION`
+    pub fn accrued(&self, pool: &PoolState) -> Result<u128> {
+        (self.amount as u128)
+            .checked_mul(pool.acc_reward_per_share)
+            .ok_or_else(|| VaultError::InvalidRewardCalc.into())
+    }
+
+    /// Move rewards earned since the last settlement into `unclaimed`, less the
+    /// referral cut if the stake was referred
+    pub fn settle(&mut self, pool: &PoolState) -> Result<()> {
+        let accrued = self.accrued(pool)?;
+        let pending = (accrued.saturating_sub(self.reward_debt) / ACC_PRECISION) as u64;
+        let cut = match self.referrer {
+            Some(_) => pending * pool.referral_bps as u64 / BASIS_POINTS,
+            None => 0,
+        };
+        self.unclaimed = self.unclaimed
+            .checked_add(pending - cut)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.referral_owed = self.referral_owed
+            .checked_add(cut)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.reward_debt = accrued;
+        Ok(())
+    }
+
+    /// Remove `slash_bps` of the stake from this user and the pool; rewards earned
+    /// before the slash are kept. The pool must already be accrued to now.
+    /// Returns the amount slashed.
+    pub fn slash(&mut self, pool: &mut PoolState, slash_bps: u16) -> Result<u64> {
+        self.settle(pool)?;
+
+        let slashed = self.amount
+            .checked_mul(slash_bps as u64)
+            .ok_or(VaultError::InvalidRewardCalc)?
+            / BASIS_POINTS;
+
+        self.amount -= slashed;
+        self.reward_debt = self.accrued(pool)?;
+        pool.total_staked -= slashed;
+        pool.total_weight -= slashed;
+        Ok(slashed)
+    }
+
+    /// Empty the stake for a move to a successor pool, returning the principal
+    /// and the rewards settled so far. Referral cuts do not carry over and must
+    /// be synced first. The pool must already be accrued to now.
+    pub fn take_for_migration(&mut self, pool: &mut PoolState) -> Result<(u64, u64)> {
+        self.settle(pool)?;
+        require!(self.referral_owed == 0, VaultError::InvalidMigration);
+
+        let taken = (self.amount, self.unclaimed);
+        pool.total_staked -= self.amount;
+        pool.total_weight -= self.amount;
+        self.amount = 0;
+        self.unclaimed = 0;
+        self.reward_debt = 0;
+        Ok(taken)
+    }
+
+    /// Credit stake migrated from a predecessor pool. The lockup clock keeps the
+    /// later of the two stake times, so migrating never shortens a lockup.
+    pub fn credit_migrated(
+        &mut self,
+        pool: &mut PoolState,
+        amount: u64,
+        rewards: u64,
+        last_staked: i64,
+    ) -> Result<()> {
+        self.settle(pool)?;
+        self.amount = self.amount
+            .checked_add(amount)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.unclaimed = self.unclaimed
+            .checked_add(rewards)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.last_staked = self.last_staked.max(last_staked);
+        self.reward_debt = self.accrued(pool)?;
+        pool.total_staked += amount;
+        pool.total_weight += amount;
+        Ok(())
+    }
+}
+
+/// Referral cut earned by one referrer in one pool, PDA of
+/// `[b"referral", pool, referrer]`
+#[account]
+pub struct ReferralState {
+    pub pool: Pubkey,
+    pub referrer: Pubkey,
+    /// Synced from referees and awaiting claim
+    pub unclaimed: u64,
+    pub total_earned: u64,
+    pub bump: u8,
+}
+
+impl ReferralState {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Hand-over from a pool to its successor, PDA of `[b"pool_migration", from_pool]`
+#[account]
+pub struct PoolMigration {
+    pub from_pool: Pubkey,
+    pub to_pool: Pubkey,
+    /// Old pool admin and successor authority that co-signed the migration
+    pub from_admin: Pubkey,
+    pub to_authority: Pubkey,
+    /// Successor tokens left to back migrated stake when the mint changed
+    pub reserve: u64,
+    /// Receives the old pool's tokens when the mint changed
+    pub legacy_recipient: Option<Pubkey>,
+    /// Principal moved out of the old pool so far
+    pub migrated_amount: u64,
+    /// Stakes and positions moved so far
+    pub migrated_stakes: u32,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl PoolMigration {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 8 + 33 + 8 + 4 + 8 + 1;
+
+    /// Record a stake moving across. With a changed mint the successor backs its
+    /// principal and rewards from the reserve, and fails once that runs dry.
+    pub fn record(&mut self, amount: u64, rewards: u64) -> Result<()> {
+        if self.legacy_recipient.is_some() {
+            let backed = amount
+                .checked_add(rewards)
+                .ok_or(VaultError::InvalidRewardCalc)?;
+            self.reserve = self.reserve
+                .checked_sub(backed)
+                .ok_or(VaultError::MigrationUnderfunded)?;
+        }
+        self.migrated_amount = self.migrated_amount
+            .checked_add(amount)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.migrated_stakes += 1;
+        Ok(())
+    }
+}
+
+/// A single locked deposit, earning at its tier multiplier
+#[account]
+pub struct StakePosition {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    /// Caller-chosen id distinguishing this owner's positions
+    pub id: u64,
+    pub amount: u64,
+    pub tier: u8,
+    /// Tier multiplier at deposit; later tier changes do not affect it
+    pub multiplier_bps: u16,
+    /// `amount` scaled by `multiplier_bps`
+    pub weight: u64,
+    pub start: i64,
+    pub unlock_at: i64,
+    /// `weight * acc_reward_per_share` at the last settlement
+    pub reward_debt: u128,
+    pub unclaimed: u64,
+    pub bump: u8,
+}
+
+impl StakePosition {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1 + 2 + 8 + 8 + 8 + 16 + 8 + 1;
+
+    /// Fail while the tier lockup is running
+    pub fn require_unlocked(&self) -> Result<()> {
+        require!(env::now()? >= self.unlock_at, VaultError::LockupActive);
+        Ok(())
+    }
+
+    /// Accumulated entitlement of the position, scaled by `ACC_PRECISION`
+    pub fn accrued(&self, pool: &PoolState) -> Result<u128> {
+        (self.weight as u128)
+            .checked_mul(pool.acc_reward_per_share)
+            .ok_or_else(|| VaultError::InvalidRewardCalc.into())
+    }
+
+    /// Move rewards earned since the last settlement into `unclaimed`
+    pub fn settle(&mut self, pool: &PoolState) -> Result<()> {
+        let accrued = self.accrued(pool)?;
+        let pending = (accrued.saturating_sub(self.reward_debt) / ACC_PRECISION) as u64;
+        self.unclaimed = self.unclaimed
+            .checked_add(pending)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.reward_debt = accrued;
+        Ok(())
+    }
+
+    /// Grow the position at its own multiplier, returning the weight added
+    pub fn add_stake(&mut self, amount: u64) -> Result<u64> {
+        let amount = self.amount
+            .checked_add(amount)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        let weight: u64 = (amount as u128 * self.multiplier_bps as u128 / BASIS_POINTS as u128)
+            .try_into()
+            .map_err(|_| VaultError::InvalidRewardCalc)?;
+        let added = weight - self.weight;
+        self.amount = amount;
+        self.weight = weight;
+        Ok(added)
+    }
+
+    /// Remove `slash_bps` of the locked amount, shrinking the weight at the
+    /// position's own multiplier; the lockup keeps running. The pool must already
+    /// be accrued to now. Returns the amount and weight removed.
+    pub fn slash(&mut self, pool: &mut PoolState, slash_bps: u16) -> Result<(u64, u64)> {
+        self.settle(pool)?;
+
+        let slashed = self.amount
+            .checked_mul(slash_bps as u64)
+            .ok_or(VaultError::InvalidRewardCalc)?
+            / BASIS_POINTS;
+        let amount = self.amount - slashed;
+        let weight: u64 = (amount as u128 * self.multiplier_bps as u128 / BASIS_POINTS as u128)
+            .try_into()
+            .map_err(|_| VaultError::InvalidRewardCalc)?;
+        let removed = self.weight - weight;
+
+        self.amount = amount;
+        self.weight = weight;
+        self.reward_debt = self.accrued(pool)?;
+        pool.total_staked -= slashed;
+        pool.total_weight -= removed;
+        Ok((slashed, removed))
+    }
+
+    /// This position moved to a successor pool holding `amount`: same id, tier,
+    /// multiplier and lockup window, with `rewards` carried over unclaimed
+    pub fn migrated(
+        &self,
+        pool: &PoolState,
+        pool_key: Pubkey,
+        amount: u64,
+        rewards: u64,
+        bump: u8,
+    ) -> Result<Self> {
+        let weight = (amount as u128 * self.multiplier_bps as u128 / BASIS_POINTS as u128)
+            .try_into()
+            .map_err(|_| VaultError::InvalidRewardCalc)?;
+        let mut position = Self {
+            pool: pool_key,
+            amount,
+            weight,
+            reward_debt: 0,
+            unclaimed: rewards,
+            bump,
+            ..self.clone()
+        };
+        position.reward_debt = position.accrued(pool)?;
+        Ok(position)
+    }
+}
+
+/// Recent voting-power changes of one staker, used to snapshot votes
+#[account]
+pub struct StakeHistory {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    /// `(timestamp, voting power after the change)`, oldest first
+    pub checkpoints: Vec<(i64, u64)>,
+    /// Set once old checkpoints have been dropped to make room
+    pub truncated: bool,
+    pub bump: u8,
+    /// Unlocked stake, counted at 1x
+    pub liquid_amount: u64,
+    /// Combined weight of the owner's locked positions
+    pub locked_weight: u64,
+}
+
+impl StakeHistory {
+    pub const LEN: usize = 8 + 32 + 32 + 4 + MAX_STAKE_CHECKPOINTS * 16 + 1 + 1 + 8 + 8;
+
+    /// Checkpoint the current liquid stake plus locked weight
+    pub fn update(&mut self, now: i64) -> Result<()> {
+        let power = self.liquid_amount
+            .checked_add(self.locked_weight)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.record(now, power)
+    }
+
+    /// Record the staked amount after a change at `now`
+    pub fn record(&mut self, now: i64, amount: u64) -> Result<()> {
+        match self.checkpoints.last_mut() {
+            // Several changes in one second collapse into one checkpoint
+            Some(last) if last.0 == now => last.1 = amount,
+            _ => {
+                if self.checkpoints.len() == MAX_STAKE_CHECKPOINTS {
+                    self.checkpoints.remove(0);
+                    self.truncated = true;
+                }
+                self.checkpoints.push((now, amount));
+            }
+        }
+        Ok(())
+    }
+
+    /// Stake held strictly before `timestamp`
+    pub fn amount_at(&self, timestamp: i64) -> Result<u64> {
+        let idx = self.checkpoints.partition_point(|(t, _)| *t < timestamp);
+        if idx > 0 {
+            return Ok(self.checkpoints[idx - 1].1);
+        }
+        // Before the first checkpoint nothing was staked, unless history was dropped
+        require!(!self.truncated, VaultError::StakeHistoryUnavailable);
+        Ok(0)
+    }
+}
+
+/// Stake delegated by token holders to a single worker operator
+#[account]
+pub struct DelegationPool {
+    pub operator: Pubkey,
+    pub worker: Pubkey,
+    pub stake_pool: Pubkey,
+    pub commission_bps: u16,
+    pub total_delegated: u64,
+    pub total_shares: u64,
+    pub bump: u8,
+}
+
+impl DelegationPool {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 2 + 8 + 8 + 1;
+
+    /// Shares minted for a deposit at the current exchange rate
+    pub fn shares_for_amount(&self, amount: u64) -> Result<u64> {
+        if self.total_shares == 0 || self.total_delegated == 0 {
+            return Ok(amount);
+        }
+        let shares = (amount as u128)
+            .checked_mul(self.total_shares as u128)
+            .ok_or(VaultError::InvalidRewardCalc)?
+            / self.total_delegated as u128;
+        Ok(shares.try_into().map_err(|_| VaultError::InvalidRewardCalc)?)
+    }
+
+    /// Tokens redeemable for shares at the current exchange rate
+    pub fn amount_for_shares(&self, shares: u64) -> Result<u64> {
+        if self.total_shares == 0 {
+            return Ok(0);
+        }
+        let amount = (shares as u128)
+            .checked_mul(self.total_delegated as u128)
+            .ok_or(VaultError::InvalidRewardCalc)?
+            / self.total_shares as u128;
+        Ok(amount.try_into().map_err(|_| VaultError::InvalidRewardCalc)?)
+    }
+
+    /// Mint shares for `amount` delegated tokens
+    pub fn deposit(&mut self, delegation: &mut Delegation, amount: u64) -> Result<u64> {
+        let shares = self.shares_for_amount(amount)?;
+        self.total_shares = self.total_shares
+            .checked_add(shares)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.total_delegated = self.total_delegated
+            .checked_add(amount)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        delegation.shares = delegation.shares
+            .checked_add(shares)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        Ok(shares)
+    }
+
+    /// Burn `shares` and return the tokens they redeem for
+    pub fn withdraw(&mut self, delegation: &mut Delegation, shares: u64) -> Result<u64> {
+        require!(
+            shares > 0 && delegation.shares >= shares,
+            VaultError::InsufficientStake
+        );
+        let amount = self.amount_for_shares(shares)?;
+        delegation.shares -= shares;
+        self.total_shares = self.total_shares
+            .checked_sub(shares)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        self.total_delegated = self.total_delegated
+            .checked_sub(amount)
+            .ok_or(VaultError::InvalidRewardCalc)?;
+        Ok(amount)
+    }
+
+    /// Weight the scheduler adds to the worker's placement score
+    pub fn scheduling_weight(&self) -> u64 {
+        self.total_delegated
+    }
+}
+
+#[account]
+pub struct Delegation {
+    pub delegator: Pubkey,
+    pub pool: Pubkey,
+    pub shares: u64,
+}
+
+impl Delegation {
+    pub const LEN: usize = 8 + 32 + 32 + 8;
+}
+
+#[account]
+pub struct Proposal {
+    pub proposer: Pubkey,
+    pub pool: Pubkey,
+    pub proposal_type: ProposalType,
+    pub amount: Option<u64>,
+    pub recipient: Option<Pubkey>,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub created_at: i64,
+    /// Pool voting weight when the proposal opened, the quorum base
+    pub total_staked_snapshot: u64,
+    pub voting_ends_at: i64,
+    /// Delay between passing and execution, fixed at creation
+    pub timelock_delay: i64,
+    /// Earliest execution time; set when the proposal passes
+    pub executable_at: i64,
+    pub status: ProposalStatus,
+    /// Governance epoch the proposal was opened in
+    pub epoch: u64,
+    /// Distinct wallets that voted directly
+    pub voters: u32,
+}
+
+impl Proposal {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // proposer
+        32 + // pool
+        ProposalType::LEN +
+        9 +  // amount
+        33 + // recipient
+        8 +  // votes_for
+        8 +  // votes_against
+        8 +  // created_at
+        8 +  // total_staked_snapshot
+        8 +  // voting_ends_at
+        8 +  // timelock_delay
+        8 +  // executable_at
+        1 +  // status
+        8 +  // epoch
+        4;   // voters
+
+    /// Reject proposals whose payload could never execute
+    pub fn validate_payload(&self) -> Result<()> {
+        match &self.proposal_type {
+            ProposalType::TreasuryTransfer | ProposalType::DrainRewards => require!(
+                self.amount.unwrap_or(0) > 0 && self.recipient.is_some(),
+                VaultError::InvalidProposal
+            ),
+            ProposalType::PoolParameterUpdate {
+                lockup_period,
+                early_unstake_penalty_bps,
+            } => {
+                require!(
+                    lockup_period.map_or(true, |l| l >= 0),
+                    VaultError::InvalidProposal
+                );
+                require!(
+                    early_unstake_penalty_bps.map_or(true, |bps| bps <= MAX_PENALTY_BPS),
+                    VaultError::InvalidPenalty
+                );
+            }
+            ProposalType::SlashStake { slash_bps, .. } => require!(
+                *slash_bps > 0 && *slash_bps as u64 <= BASIS_POINTS,
+                VaultError::InvalidSlash
+            ),
+            ProposalType::VerifierKeyRotation { vk_hash, version, .. } => require!(
+                *version > 0 && *vk_hash != [0; 32],
+                VaultError::InvalidProposal
+            ),
+            ProposalType::RewardRateChange { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// Passed if turnout meets quorum and approval clears the threshold
+    pub fn outcome(&self) -> ProposalStatus {
+        let turnout = self.votes_for as u128 + self.votes_against as u128;
+        let quorum = turnout * BASIS_POINTS as u128
+            >= self.total_staked_snapshot as u128 * QUORUM_BPS as u128;
+        let approved = self.votes_for as u128 * BASIS_POINTS as u128
+            > turnout * APPROVAL_THRESHOLD_BPS as u128;
+
+        if turnout > 0 && quorum && approved {
+            ProposalStatus::Passed
+        } else {
+            ProposalStatus::Rejected
+        }
+    }
+
+    /// Votes cast as a share of the snapshot, in basis points, capped at 100%
+    pub fn turnout_bps(&self) -> u16 {
+        let turnout = self.votes_for as u128 + self.votes_against as u128;
+        let bps = turnout * BASIS_POINTS as u128 / (self.total_staked_snapshot as u128).max(1);
+        bps.min(BASIS_POINTS as u128) as u16
+    }
+}
+
+/// Proposal activity of one governance epoch
+#[account]
+pub struct GovernanceEpoch {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    /// Proposals opened this epoch, the participation denominator
+    pub proposals: u32,
+    pub finalized: u32,
+    /// Sum of `turnout_bps` over finalized proposals
+    pub turnout_bps_total: u64,
+    /// Participation rewards paid for this epoch
+    pub rewards_paid: u64,
+    pub bump: u8,
+}
+
+impl GovernanceEpoch {
+    pub const LEN: usize = 8 + 32 + 8 + 4 + 4 + 8 + 8 + 1;
+
+    pub fn record_finalized(&mut self, turnout_bps: u16) {
+        self.finalized += 1;
+        self.turnout_bps_total += turnout_bps as u64;
+    }
+}
+
+/// Proposals one wallet voted on in one governance epoch
+#[account]
+pub struct EpochParticipation {
+    pub pool: Pubkey,
+    pub voter: Pubkey,
+    pub epoch: u64,
+    pub votes: u32,
+    /// Participation reward paid out
+    pub claimed: bool,
+    pub bump: u8,
+}
+
+impl EpochParticipation {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 4 + 1 + 1;
+
+    /// Share of the epoch's `proposals` voted on, in basis points
+    pub fn participation_bps(&self, proposals: u32) -> u16 {
+        if proposals == 0 {
+            return 0;
+        }
+        let bps = self.votes as u64 * BASIS_POINTS / proposals as u64;
+        bps.min(BASIS_POINTS) as u16
+    }
+}
+
+/// Consecutive epochs in which a wallet earned the participation reward
+#[account]
+pub struct VoterStreak {
+    pub pool: Pubkey,
+    pub voter: Pubkey,
+    pub current: u32,
+    pub longest: u32,
+    /// Latest rewarded epoch; rewards are claimed in epoch order
+    pub last_epoch: Option<u64>,
+    pub bump: u8,
+}
+
+impl VoterStreak {
+    pub const LEN: usize = 8 + 32 + 32 + 4 + 4 + 9 + 1;
+
+    /// Extend the streak with a rewarded `epoch`; a skipped epoch restarts it
+    pub fn advance(&mut self, epoch: u64) -> Result<()> {
+        self.current = match self.last_epoch {
+            Some(last) => {
+                require!(epoch > last, VaultError::ParticipationRewardClaimed);
+                if epoch == last + 1 { self.current + 1 } else { 1 }
+            }
+            None => 1,
+        };
+        self.longest = self.longest.max(self.current);
+        self.last_epoch = Some(epoch);
+        Ok(())
+    }
+}
+
+/// Minimum stake for a participation reward tier and the share of the base
+/// reward it earns
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParticipationTier {
+    /// Voting power held at the end of the epoch
+    pub min_stake: u64,
+    /// Share of `reward_per_epoch`; `BASIS_POINTS` is the full reward
+    pub weight_bps: u16,
+}
+
+impl ParticipationTier {
+    pub const LEN: usize = 8 + 2;
+}
+
+/// Participation reward paid from a pool's treasury
+#[account]
+pub struct ParticipationConfig {
+    pub pool: Pubkey,
+    /// Reward of a wallet at `BASIS_POINTS` tier weight
+    pub reward_per_epoch: u64,
+    /// Cap on rewards paid per epoch; claims past it are refused
+    pub epoch_budget: u64,
+    /// Share of an epoch's proposals a wallet must vote on
+    pub min_participation_bps: u16,
+    /// Stake tiers, lowest first; wallets below the first earn nothing
+    pub tiers: Vec<ParticipationTier>,
+    pub bump: u8,
+}
+
+impl ParticipationConfig {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // pool
+        8 +  // reward_per_epoch
+        8 +  // epoch_budget
+        2 +  // min_participation_bps
+        4 + MAX_PARTICIPATION_TIERS * ParticipationTier::LEN + // tiers
+        1;   // bump
+
+    /// Highest tier `power` reaches
+    pub fn tier_for(&self, power: u64) -> Option<ParticipationTier> {
+        self.tiers.iter().rev().find(|t| power >= t.min_stake).copied()
+    }
+
+    pub fn reward(&self, tier: ParticipationTier) -> Result<u64> {
+        let reward = self.reward_per_epoch as u128 * tier.weight_bps as u128 / BASIS_POINTS as u128;
+        reward.try_into().map_err(|_| VaultError::InvalidRewardCalc.into())
+    }
+}
+
+/// One staker's current vote on a proposal
+#[account]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    /// Stake counted in the tally for this vote
+    pub weight: u64,
+    pub approve: bool,
+    /// `voter` itself, or the delegate that voted on its behalf
+    pub cast_by: Pubkey,
+    pub voted_at: i64,
+    pub bump: u8,
+}
+
+impl VoteRecord {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 32 + 8 + 1;
+}
+
+/// Governance power lent by a staker to another pubkey
+#[account]
+pub struct VoteDelegation {
+    pub pool: Pubkey,
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    /// Only proposals created after this count the delegation
+    pub delegated_at: i64,
+    pub bump: u8,
+}
+
+impl VoteDelegation {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
+
+    pub fn active_for(&self, proposal: &Proposal) -> bool {
+        self.pool == proposal.pool && self.delegated_at < proposal.created_at
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ProposalType {
+    /// Move `amount` from the pool treasury to `recipient`
+    TreasuryTransfer,
+    RewardRateChange {
+        reward_rate: u64,
+    },
+    /// Unset fields are left unchanged
+    PoolParameterUpdate {
+        lockup_period: Option<i64>,
+        early_unstake_penalty_bps: Option<u16>,
+    },
+    /// Withdraw `amount` of unallocated rewards to `recipient`
+    DrainRewards,
+    /// Slash `slash_bps` of `provider`'s stake, burned or sent to the treasury;
+    /// executed through `slash_stake`
+    SlashStake {
+        provider: Pubkey,
+        slash_bps: u16,
+        burn: bool,
+    },
+    /// Make `vk_hash` the active verifying key of `circuit_id` in haunti-core's
+    /// key registry; versions only move forward
+    VerifierKeyRotation {
+        circuit_id: [u8; 32],
+        vk_hash: [u8; 32],
+        version: u32,
+    },
+}
+
+impl ProposalType {
+    /// Largest variant: tag + two hashes + u32
+    pub const LEN: usize = 1 + 32 + 32 + 4;
+
+    /// Proposals that move funds, or decide which proofs get paid, wait longer
+    /// before they can execute
+    pub fn timelock_delay(&self) -> i64 {
+        match self {
+            ProposalType::TreasuryTransfer
+            | ProposalType::DrainRewards
+            | ProposalType::SlashStake { .. }
+            | ProposalType::VerifierKeyRotation { .. } => FUNDS_TIMELOCK_DELAY,
+            _ => PARAMETER_TIMELOCK_DELAY,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProposalStatus {
+    Active,
+    Passed,
+    Rejected,
+    Executed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum PoolType {
+    GPUProvider,
+    Validator,
+    Trainer,
+    Governance,
+}
+
+#[error_code]
+pub enum VaultError {
+    #[msg("Lockup period not expired")]
+    LockupActive,
+    #[msg("Insufficient staked amount")]
+    InsufficientStake,
+    #[msg("No rewards available")]
+    NoRewardsAvailable,
+    #[msg("Proposal no longer active")]
+    ProposalNotActive,
+    #[msg("Minimum voting stake not met")]
+    InsufficientVotingPower,
+    #[msg("Invalid reward distribution")]
+    InvalidRewardCalc,
+    #[msg("Commission exceeds maximum")]
+    InvalidCommission,
+    #[msg("Penalty exceeds maximum")]
+    InvalidPenalty,
+    #[msg("Voting period has ended")]
+    VotingClosed,
+    #[msg("Voting period still open")]
+    VotingPeriodActive,
+    #[msg("Proposal has not passed")]
+    ProposalNotPassed,
+    #[msg("Proposal timelock not expired")]
+    TimelockActive,
+    #[msg("Proposal payload or accounts invalid")]
+    InvalidProposal,
+    #[msg("Stake history no longer covers the proposal snapshot")]
+    StakeHistoryUnavailable,
+    #[msg("Invalid vote delegation")]
+    InvalidDelegation,
+    #[msg("Invalid lockup tier")]
+    InvalidLockupTier,
+    #[msg("Signer does not hold the required role")]
+    RoleUnauthorized,
+    #[msg("No role transfer is pending")]
+    NoPendingRoleTransfer,
+    #[msg("Pool is paused")]
+    PoolPaused,
+    #[msg("Pool is not paused")]
+    PoolNotPaused,
+    #[msg("Invalid slash rate or destination")]
+    InvalidSlash,
+    #[msg("Invalid emission schedule")]
+    InvalidEmissionSchedule,
+    #[msg("Pool follows an emission schedule that was not supplied")]
+    EmissionScheduleRequired,
+    #[msg("Receipt accounts do not match the pool's receipt mode")]
+    InvalidReceiptConfig,
+    #[msg("Invalid referrer or referral cut")]
+    InvalidReferrer,
+    #[msg("Rate ramp window out of range")]
+    InvalidRateRamp,
+    #[msg("Stake still holds tokens or unclaimed rewards")]
+    StakeNotEmpty,
+    #[msg("Proposal is still active or awaiting execution")]
+    ProposalNotFinalized,
+    #[msg("Epoch does not match the current governance epoch")]
+    WrongGovernanceEpoch,
+    #[msg("Invalid participation reward configuration")]
+    InvalidParticipationConfig,
+    #[msg("Governance epoch has not closed")]
+    EpochNotClosed,
+    #[msg("Participation reward already claimed")]
+    ParticipationRewardClaimed,
+    #[msg("Participation or stake below the reward threshold")]
+    ParticipationTooLow,
+    #[msg("Participation reward budget for the epoch is spent")]
+    ParticipationBudgetExhausted,
+    #[msg("Invalid pool migration")]
+    InvalidMigration,
+    #[msg("Migration reserve cannot back this stake")]
+    MigrationUnderfunded,
+}
+
+#[event]
+pub enum PoolEvent {
+    PoolInitialized {
+        pool: Pubkey,
+        timestamp: i64,
+    },
+    Staked {
+        pool: Pubkey,
+        user: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    Unstaked {
+        pool: Pubkey,
+        user: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    RewardClaimed {
+        pool: Pubkey,
+        user: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    StakeClosed {
+        user: Pubkey,
+        owner: Pubkey,
+        timestamp: i64,
+    },
+    EmergencyUnstaked {
+        user: Pubkey,
+        amount: u64,
+        penalty: u64,
+        route: PenaltyRoute,
+        timestamp: i64,
+    },
+    RewardReserveFunded {
+        pool: Pubkey,
+        funder: Pubkey,
+        amount: u64,
+        reserve: u64,
+        timestamp: i64,
+    },
+    RewardReserveDrained {
+        pool: Pubkey,
+        proposal: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        reserve: u64,
+        timestamp: i64,
+    },
+    PoolPaused {
+        pool: Pubkey,
+        by: Pubkey,
+        timestamp: i64,
+    },
+    PoolUnpaused {
+        pool: Pubkey,
+        by: Pubkey,
+        timestamp: i64,
+    },
+    Compounded {
+        user: Pubkey,
+        amount: u64,
+        /// Stake after restaking
+        staked: u64,
+        timestamp: i64,
+    },
+    PositionOpened {
+        position: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        tier: u8,
+        weight: u64,
+        unlock_at: i64,
+        timestamp: i64,
+    },
+    PositionClosed {
+        position: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        rewards: u64,
+        timestamp: i64,
+    },
+    ReferralRecorded {
+        user: Pubkey,
+        referrer: Pubkey,
+        timestamp: i64,
+    },
+    ReferralSynced {
+        user: Pubkey,
+        referrer: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    ReferralRewardsClaimed {
+        referrer: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    LiquidStaked {
+        user: Pubkey,
+        amount: u64,
+        shares: u64,
+        /// Exchange rate after the deposit is `backing / supply`
+        backing: u64,
+        supply: u64,
+        timestamp: i64,
+    },
+    LiquidUnstaked {
+        user: Pubkey,
+        amount: u64,
+        shares: u64,
+        backing: u64,
+        supply: u64,
+        timestamp: i64,
+    },
+    RewardRateScheduled {
+        pool: Pubkey,
+        /// Rate in effect when the change was made
+        effective_rate: u64,
+        /// Rate reached at `ramp_ends_at`
+        target_rate: u64,
+        ramp_ends_at: i64,
+        timestamp: i64,
+    },
+    EmissionScheduleSet {
+        pool: Pubkey,
+        segments: u8,
+        /// End of the last segment; emissions stop after it
+        ends_at: i64,
+        timestamp: i64,
+    },
+    EmissionScheduleCleared {
+        pool: Pubkey,
+        timestamp: i64,
+    },
+    Slashed {
+        pool: Pubkey,
+        user: Pubkey,
+        amount: u64,
+        slash_bps: u16,
+        /// Burned rather than redirected
+        burned: bool,
+        /// Set when executed under a governance proposal
+        proposal: Option<Pubkey>,
+        timestamp: i64,
+    },
+}
+
+#[event]
+pub enum GovernanceEvent {
+    ProposalCreated {
+        proposal: Pubkey,
+        proposer: Pubkey,
+        timestamp: i64,
+    },
+    VoteCast {
+        proposal: Pubkey,
+        voter: Pubkey,
+        amount: u64,
+        approve: bool,
+        /// Replaces this voter's earlier vote
+        changed: bool,
+        timestamp: i64,
+    },
+    ProposalQueued {
+        proposal: Pubkey,
+        executable_at: i64,
+        timestamp: i64,
+    },
+    ProposalRejected {
+        proposal: Pubkey,
+        votes_for: u64,
+        votes_against: u64,
+        timestamp: i64,
+    },
+    ProposalExecuted {
+        proposal: Pubkey,
+        proposal_type: ProposalType,
+        timestamp: i64,
+    },
+    ProposalClosed {
+        proposal: Pubkey,
+        status: ProposalStatus,
+        timestamp: i64,
+    },
+    VotesDelegated {
+        pool: Pubkey,
+        delegator: Pubkey,
+        delegate: Pubkey,
+        timestamp: i64,
+    },
+    VotesUndelegated {
+        pool: Pubkey,
+        delegator: Pubkey,
+        delegate: Pubkey,
+        timestamp: i64,
+    },
+    DelegatedVoteCast {
+        proposal: Pubkey,
+        delegator: Pubkey,
+        cast_by: Pubkey,
+        amount: u64,
+        approve: bool,
+        timestamp: i64,
+    },
+    ProposalTurnout {
+        proposal: Pubkey,
+        epoch: u64,
+        /// Distinct wallets that voted directly
+        voters: u32,
+        /// Votes cast as a share of the stake snapshot
+        turnout_bps: u16,
+        passed: bool,
+        timestamp: i64,
+    },
+    ParticipationRewardsSet {
+        pool: Pubkey,
+        reward_per_epoch: u64,
+        epoch_budget: u64,
+        min_participation_bps: u16,
+        tiers: u8,
+        timestamp: i64,
+    },
+    ParticipationRewardClaimed {
+        pool: Pubkey,
+        voter: Pubkey,
+        epoch: u64,
+        votes: u32,
+        proposals: u32,
+        participation_bps: u16,
+        tier_weight_bps: u16,
+        amount: u64,
+        /// Consecutive rewarded epochs including this one
+        streak: u32,
+        timestamp: i64,
+    },
+}
+
+#[event]
+pub enum AdminEvent {
+    RoleTransferProposed {
+        pool: Pubkey,
+        role: Role,
+        holder: Pubkey,
+        /// `None` withdraws an earlier nomination
+        pending: Option<Pubkey>,
+        timestamp: i64,
+    },
+    RoleTransferred {
+        pool: Pubkey,
+        role: Role,
+        previous: Pubkey,
+        holder: Pubkey,
+        timestamp: i64,
+    },
+    PoolMigrated {
+        from_pool: Pubkey,
+        to_pool: Pubkey,
+        mint_changed: bool,
+        reserve: u64,
+        timestamp: i64,
+    },
+    /// A stake or position moved to the successor; `stake` is its new account
+    StakeMigrated {
+        from_pool: Pubkey,
+        to_pool: Pubkey,
+        stake: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        rewards: u64,
+        timestamp: i64,
+    },
+}
+
+#[event]
+pub enum DelegationEvent {
+    PoolCreated {
+        pool: Pubkey,
+        worker: Pubkey,
+        commission_bps: u16,
+        timestamp: i64,
+    },
+    Delegated {
+        pool: Pubkey,
+        delegator: Pubkey,
+        amount: u64,
+        shares: u64,
+        timestamp: i64,
+    },
+    Undelegated {
+        pool: Pubkey,
+        delegator: Pubkey,
+        amount: u64,
+        shares: u64,
+        timestamp: i64,
+    },
+    RewardsDistributed {
+        pool: Pubkey,
+        amount: u64,
+        commission: u64,
+        timestamp: i64,
+    },
+    Slashed {
+        pool: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+}
+
+const BASIS_POINTS: u64 = 10_000;
+/// Fixed-point scale for `acc_reward_per_share`
+const ACC_PRECISION: u128 = 1_000_000_000_000;
+const MAX_COMMISSION_BPS: u16 = 5_000;
+const MAX_PENALTY_BPS: u16 = 5_000;
+const MAX_REFERRAL_BPS: u16 = 2_000;
+
+/// Stake required to create or vote on proposals
+const MIN_VOTING_STAKE: u64 = 1_000;
+const VOTING_PERIOD: i64 = 3 * 24 * 3600;
+/// Share of total stake that must vote for a result to count
+const QUORUM_BPS: u64 = 2_000;
+/// Share of votes cast that must approve
+const APPROVAL_THRESHOLD_BPS: u64 = 5_000;
+const PARAMETER_TIMELOCK_DELAY: i64 = 2 * 24 * 3600;
+const FUNDS_TIMELOCK_DELAY: i64 = 7 * 24 * 3600;
+/// Stake changes retained per staker for vote snapshots
+const MAX_STAKE_CHECKPOINTS: usize = 32;
+/// Delegators whose power one vote may carry, bounded by compute
+const MAX_DELEGATORS_PER_VOTE: usize = 8;
+const MAX_LOCKUP_TIERS: usize = 4;
+/// Highest boost a tier may grant (3x)
+const MAX_TIER_MULTIPLIER_BPS: u16 = 30_000;
+const MAX_EMISSION_SEGMENTS: usize = 16;
+/// Longest window a reward rate change may ramp over
+const MAX_RATE_RAMP_SECS: i64 = 30 * 24 * 3600;
+/// Length of a governance epoch, the period participation rewards cover
+pub const GOVERNANCE_EPOCH: i64 = 30 * 24 * 3600;
+const MAX_PARTICIPATION_TIERS: usize = 4;
+
+// Helper functions
+
+/// Move `amount` with `transfer_checked`, which both the legacy token program and
+/// Token-2022 accept. Transfer-hook mints need the hook's extra accounts, which the
+/// client resolves and appends as remaining accounts; they are forwarded untouched.
+#[allow(clippy::too_many_arguments)]
+fn transfer_tokens<'info>(
+    token_program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let mut ix = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        from.key,
+        &mint.key(),
+        to.key,
+        authority.key,
+        &[],
+        amount,
+        mint.decimals,
+    )?;
+    ix.accounts.extend(hook_accounts.iter().map(|account| AccountMeta {
+        pubkey: account.key(),
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+
+    let mut infos = vec![from, mint.to_account_info(), to, authority, token_program];
+    infos.extend_from_slice(hook_accounts);
+    invoke_signed(&ix, &infos, signer_seeds)?;
+    Ok(())
+}
+
+/// Fee a Token-2022 transfer-fee mint withholds from a transfer of `amount` this
+/// epoch; zero for legacy mints and mints without the extension
+fn transfer_fee(mint: &InterfaceAccount<'_, Mint>, amount: u64) -> Result<u64> {
+    let info = mint.to_account_info();
+    if *info.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
+    let data = info.try_borrow_data()?;
+    mint_transfer_fee(&data, env::epoch()?, amount)
+}
+
+/// Transfer fee encoded in raw Token-2022 mint data
+fn mint_transfer_fee(data: &[u8], epoch: u64, amount: u64) -> Result<u64> {
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)?;
+    match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(epoch, amount)
+            .ok_or_else(|| VaultError::InvalidRewardCalc.into()),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Amount that arrives when `amount` is sent; stake and reserves are credited with
+/// this, never with the amount sent
+fn received_amount(mint: &InterfaceAccount<'_, Mint>, amount: u64) -> Result<u64> {
+    let received = amount - transfer_fee(mint, amount)?;
+    require!(received > 0, VaultError::InsufficientStake);
+    Ok(received)
+}
+
+/// Token accounts a stake passes through when it moves to a successor pool
+struct MigrationVaults<'a, 'info> {
+    from_pool: &'a Account<'info, PoolState>,
+    from_vault: &'a InterfaceAccount<'info, TokenAccount>,
+    from_reward_vault: &'a InterfaceAccount<'info, TokenAccount>,
+    from_mint: &'a InterfaceAccount<'info, Mint>,
+    to_pool: &'a Account<'info, PoolState>,
+    to_vault: &'a InterfaceAccount<'info, TokenAccount>,
+    to_reward_vault: &'a InterfaceAccount<'info, TokenAccount>,
+    to_mint: &'a InterfaceAccount<'info, Mint>,
+    legacy_recipient: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    migration: &'a PoolMigration,
+    token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'a, 'info> MigrationVaults<'a, 'info> {
+    /// Move a stake's principal and settled rewards out of the old pool, returning
+    /// what the successor credits for each. With the mint unchanged the tokens
+    /// follow the stake into the successor's vaults. Otherwise they go to the
+    /// legacy recipient and the successor pays from its reserve, which already
+    /// sits in its stake vault.
+    fn transfer(
+        &self,
+        amount: u64,
+        rewards: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<(u64, u64)> {
+        require!(
+            self.legacy_recipient.map(|recipient| recipient.key())
+                == self.migration.legacy_recipient,
+            VaultError::InvalidMigration
+        );
+        let (principal_to, rewards_to) = match self.legacy_recipient {
+            Some(recipient) => (recipient, recipient),
+            None => (self.to_vault, self.to_reward_vault),
+        };
+
+        let pool_type = self.from_pool.pool_type.to_string();
+        let version_seed = self.from_pool.version_seed();
+        let seeds = &[
+            b"pool",
+            pool_type.as_bytes(),
+            version_seed.as_slice(),
+            &[self.from_pool.bump],
+        ];
+        let mut moved = [0; 2];
+        for ((from, to, sent), received) in [
+            (self.from_vault, principal_to, amount),
+            (self.from_reward_vault, rewards_to, rewards),
+        ]
+        .into_iter()
+        .zip(moved.iter_mut())
+        {
+            if sent == 0 {
+                continue;
+            }
+            *received = received_amount(self.from_mint, sent)?;
+            transfer_tokens(
+                self.token_program.to_account_info(),
+                from.to_account_info(),
+                to.to_account_info(),
+                self.from_pool.to_account_info(),
+                self.from_mint,
+                sent,
+                &[&seeds[..]],
+                hook_accounts,
+            )?;
+        }
+        if self.legacy_recipient.is_none() {
+            return Ok((moved[0], moved[1]));
+        }
+
+        if rewards == 0 {
+            return Ok((amount, 0));
+        }
+        let pool_type = self.to_pool.pool_type.to_string();
+        let version_seed = self.to_pool.version_seed();
+        let seeds = &[
+            b"pool",
+            pool_type.as_bytes(),
+            version_seed.as_slice(),
+            &[self.to_pool.bump],
+        ];
+        let received = received_amount(self.to_mint, rewards)?;
+        transfer_tokens(
+            self.token_program.to_account_info(),
+            self.to_vault.to_account_info(),
+            self.to_reward_vault.to_account_info(),
+            self.to_pool.to_account_info(),
+            self.to_mint,
+            rewards,
+            &[&seeds[..]],
+            hook_accounts,
+        )?;
+        Ok((amount, received))
+    }
+}
+
+/// Move settled rewards from the reward vault into the stake vault, returning the
+/// amount that arrived
+fn restake_rewards<'info>(
+    pool: &Account<'info, PoolState>,
+    reward_vault: &InterfaceAccount<'info, TokenAccount>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<u64> {
+    let pool_type = pool.pool_type.to_string();
+    let version_seed = pool.version_seed();
+    let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+    let received = received_amount(mint, amount)?;
+    transfer_tokens(
+        token_program.to_account_info(),
+        reward_vault.to_account_info(),
+        vault.to_account_info(),
+        pool.to_account_info(),
+        mint,
+        amount,
+        &[&seeds[..]],
+        hook_accounts,
+    )?;
+    Ok(received)
+}
+
+/// Restake the liquid share's pending rewards without minting stHAUNT, raising
+/// the exchange rate for every holder
+fn compound_liquid<'info>(
+    pool: &mut Account<'info, PoolState>,
+    reward_vault: &InterfaceAccount<'info, TokenAccount>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let pending = pool.pending_liquid_rewards()?;
+    if pending == 0 {
+        return Ok(());
+    }
+    let received = restake_rewards(
+        pool,
+        reward_vault,
+        vault,
+        mint,
+        token_program,
+        pending,
+        hook_accounts,
+    )?;
+    pool.add_liquid(received, 0)
+}
+
+/// Receipt mint and the token account it is minted to or burned from
+type ReceiptAccounts<'a, 'info> = (
+    &'a InterfaceAccount<'info, Mint>,
+    &'a InterfaceAccount<'info, TokenAccount>,
+);
+
+/// Receipt accounts passed to an instruction: required when the pool mints `mode`
+/// receipts, rejected otherwise
+fn receipt_accounts<'a, 'info>(
+    pool: &PoolState,
+    mode: ReceiptMode,
+    mint: &'a Option<InterfaceAccount<'info, Mint>>,
+    token: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+) -> Result<Option<ReceiptAccounts<'a, 'info>>> {
+    match (pool.receipt_mode == mode, mint, token) {
+        (true, Some(mint), Some(token)) => Ok(Some((mint, token))),
+        (false, None, None) => Ok(None),
+        _ => err!(VaultError::InvalidReceiptConfig),
+    }
+}
+
+/// Mint a position's single receipt and drop the mint authority so no second
+/// copy can ever exist
+fn mint_position_receipt<'info>(
+    pool: &Account<'info, PoolState>,
+    receipt_mint: &InterfaceAccount<'info, Mint>,
+    receipt_token: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let pool_type = pool.pool_type.to_string();
+    let version_seed = pool.version_seed();
+    let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]];
+    let signer = &[&seeds[..]];
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            MintTo {
+                mint: receipt_mint.to_account_info(),
+                to: receipt_token.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signer,
+        ),
+        1,
+    )?;
+    token_interface::set_authority(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            SetAuthority {
+                current_authority: pool.to_account_info(),
+                account_or_mint: receipt_mint.to_account_info(),
+            },
+            signer,
+        ),
+        spl_token_2022::instruction::AuthorityType::MintTokens,
+        None,
+    )?;
+    Ok(())
+}
+
+/// Burn receipts held by `holder`
+fn burn_receipt<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    receipt_mint: &InterfaceAccount<'info, Mint>,
+    receipt_token: &InterfaceAccount<'info, TokenAccount>,
+    holder: &Signer<'info>,
+    amount: u64,
+) -> Result<()> {
+    token_interface::burn(
+        CpiContext::new(
+            token_program.to_account_info(),
+            Burn {
+                mint: receipt_mint.to_account_info(),
+                from: receipt_token.to_account_info(),
+                authority: holder.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+/// Tiers must lock longer for a larger boost, never below 1x
+fn validate_tiers(tiers: &[LockupTier]) -> Result<()> {
+    require!(tiers.len() <= MAX_LOCKUP_TIERS, VaultError::InvalidLockupTier);
+    let mut previous: Option<&LockupTier> = None;
+    for tier in tiers {
+        require!(
+            tier.duration > 0
+                && tier.multiplier_bps as u64 >= BASIS_POINTS
+                && tier.multiplier_bps <= MAX_TIER_MULTIPLIER_BPS,
+            VaultError::InvalidLockupTier
+        );
+        if let Some(previous) = previous {
+            require!(
+                tier.duration > previous.duration
+                    && tier.multiplier_bps >= previous.multiplier_bps,
+                VaultError::InvalidLockupTier
+            );
+        }
+        previous = Some(tier);
+    }
+    Ok(())
+}
+
+/// Governance epoch containing `timestamp`
+pub fn governance_epoch(timestamp: i64) -> u64 {
+    (timestamp.max(0) / GOVERNANCE_EPOCH) as u64
+}
+
+fn epoch_start(epoch: u64) -> i64 {
+    epoch as i64 * GOVERNANCE_EPOCH
+}
+
+/// Tiers rise strictly in stake and weight from at least `MIN_VOTING_STAKE`,
+/// so every wallet that earns must lock real stake and thin wallets earn the
+/// smallest share
+fn validate_participation_tiers(tiers: &[ParticipationTier]) -> Result<()> {
+    require!(
+        !tiers.is_empty() && tiers.len() <= MAX_PARTICIPATION_TIERS,
+        VaultError::InvalidParticipationConfig
+    );
+    require!(
+        tiers[0].min_stake >= MIN_VOTING_STAKE,
+        VaultError::InvalidParticipationConfig
+    );
+    for tier in tiers {
+        require!(
+            tier.weight_bps > 0 && tier.weight_bps <= MAX_TIER_MULTIPLIER_BPS,
+            VaultError::InvalidParticipationConfig
+        );
+    }
+    for pair in tiers.windows(2) {
+        let (lower, upper) = (pair[0], pair[1]);
+        require!(
+            upper.min_stake > lower.min_stake && upper.weight_bps > lower.weight_bps,
+            VaultError::InvalidParticipationConfig
+        );
+    }
+    Ok(())
+}
+
+/// Segments must be non-empty ranges in time order without overlap
+/// Publish the rate now in effect next to the one being ramped towards
+fn emit_rate_scheduled(pool: &Account<PoolState>, now: i64) {
+    let (target_rate, ramp_ends_at) = match pool.rate_ramp {
+        Some(ramp) => (ramp.target_rate, ramp.end_ts),
+        None => (pool.reward_rate, now),
+    };
+    emit!(PoolEvent::RewardRateScheduled {
+        pool: pool.key(),
+        effective_rate: pool.reward_rate,
+        target_rate,
+        ramp_ends_at,
+        timestamp: now,
+    });
+}
+
+fn validate_segments(segments: &[EmissionSegment]) -> Result<()> {
+    require!(
+        !segments.is_empty() && segments.len() <= MAX_EMISSION_SEGMENTS,
+        VaultError::InvalidEmissionSchedule
+    );
+    let mut previous_end = i64::MIN;
+    for segment in segments {
+        require!(
+            segment.start_ts < segment.end_ts && segment.start_ts >= previous_end,
+            VaultError::InvalidEmissionSchedule
+        );
+        previous_end = segment.end_ts;
+    }
+    Ok(())
+}
+
+/// Create a program-owned PDA of `space` bytes. `create_account` fails once the
+/// address holds any lamports, which anyone can arrange by transferring to it,
+/// so a pre-funded address is topped up to rent exemption, then allocated and
+/// assigned instead.
+fn create_pda_account<'info>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'info>,
+    target: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let current = target.lamports();
+    if current == 0 {
+        invoke_signed(
+            &system_instruction::create_account(payer.key, target.key, rent, space as u64, program_id),
+            &[payer.clone(), target.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+        return Ok(());
+    }
+
+    if current < rent {
+        invoke(
+            &system_instruction::transfer(payer.key, target.key, rent - current),
+            &[payer.clone(), target.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(target.key, space as u64),
+        &[target.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(target.key, program_id),
+        &[target.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    Ok(())
+}
+
+/// Tally power delegated to `voter`, directly or through a chain, from remaining accounts.
+/// Each delegator gets its own `VoteRecord` so its power is counted exactly once; a record
+/// the delegator cast itself is left untouched.
+#[allow(clippy::too_many_arguments)]
+fn cast_delegated_votes<'info>(
+    program_id: &Pubkey,
+    proposal: &mut Account<'info, Proposal>,
+    voter: Pubkey,
+    accounts: &[AccountInfo<'info>],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    approve: bool,
+    now: i64,
+) -> Result<u64> {
+    require!(accounts.len() % 3 == 0, VaultError::InvalidDelegation);
+    require!(
+        accounts.len() / 3 <= MAX_DELEGATORS_PER_VOTE,
+        VaultError::InvalidDelegation
+    );
+
+    let proposal_key = proposal.key();
+    // Pubkeys whose power this vote carries; chains must lead back into this set
+    let mut reached = vec![voter];
+    let mut total = 0u64;
+
+    for triple in accounts.chunks(3) {
+        let delegation = Account::<VoteDelegation>::try_from(&triple[0])?;
+        let history = Account::<StakeHistory>::try_from(&triple[1])?;
+        let delegator = delegation.delegator;
+
+        let (expected, _) = Pubkey::find_program_address(
+            &[b"vote_delegation", proposal.pool.as_ref(), delegator.as_ref()],
+            program_id,
+        );
+        require_keys_eq!(delegation.key(), expected, VaultError::InvalidDelegation);
+        require!(
+            delegation.active_for(proposal)
+                && reached.contains(&delegation.delegate)
+                && !reached.contains(&delegator),
+            VaultError::InvalidDelegation
+        );
+        require!(
+            history.pool == proposal.pool && history.owner == delegator,
+            VaultError::InvalidDelegation
+        );
+        reached.push(delegator);
+
+        let power = history.amount_at(proposal.created_at)?;
+        let record_info = &triple[2];
+        let (record_key, bump) = Pubkey::find_program_address(
+            &[b"vote", proposal_key.as_ref(), delegator.as_ref()],
+            program_id,
+        );
+        require_keys_eq!(record_info.key(), record_key, VaultError::InvalidDelegation);
+
+        if record_info.data_is_empty() {
+            create_pda_account(
+                program_id,
+                payer,
+                record_info,
+                system_program,
+                VoteRecord::LEN,
+                &[b"vote", proposal_key.as_ref(), delegator.as_ref(), &[bump]],
+            )?;
+            let record = VoteRecord {
+                proposal: proposal_key,
+                voter: delegator,
+                weight: power,
+                approve,
+                cast_by: voter,
+                voted_at: now,
+                bump,
+            };
+            record.try_serialize(&mut &mut record_info.try_borrow_mut_data()?[..])?;
+        } else {
+            let mut record = Account::<VoteRecord>::try_from(record_info)?;
+            if record.cast_by == record.voter {
+                continue;
+            }
+            if record.approve {
+                proposal.votes_for -= record.weight;
+            } else {
+                proposal.votes_against -= record.weight;
+            }
+            record.weight = power;
+            record.approve = approve;
+            record.cast_by = voter;
+            record.voted_at = now;
+            record.exit(program_id)?;
+        }
+
+        if approve {
+            proposal.votes_for += power;
+        } else {
+            proposal.votes_against += power;
+        }
+        total += power;
+
+        emit!(GovernanceEvent::DelegatedVoteCast {
+            proposal: proposal_key,
+            delegator,
+            cast_by: voter,
+            amount: power,
+            approve,
+            timestamp: now,
+        });
+    }
+
+    Ok(total)
+}
+fn distribute_rewards<'info>(
+    ctx: &mut ClaimRewards<'info>,
+    amount: u64,
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let pool_type = ctx.pool.pool_type.to_string();
+    let version_seed = ctx.pool.version_seed();
+    let seeds = &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[ctx.pool.bump]];
+    let signer = &[&seeds[..]];
+    transfer_tokens(
+        ctx.token_program.to_account_info(),
+        ctx.reward_vault.to_account_info(),
+        ctx.user_token.to_account_info(),
+        ctx.pool.to_account_info(),
+        &ctx.mint,
+        amount,
+        signer,
+        hook_accounts,
+    )
 }
 
-#[derive(Accounts)]
-pub struct InitializePool<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = PoolState::LEN,
-        seeds = [b"pool", pool_type.to_string().as_bytes()],
-        bump,
-    )]
-    pub pool: Account<'info, PoolState>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = authority,
-        token::mint = mint,
-        token::authority = pool,
-        seeds = [b"vault", pool.key().as_ref()],
-        bump,
-    )]
-    pub vault: Account<'info, TokenAccount>,
-    
-    pub mint: Account<'info, Mint>,
-    
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(reward_rate: u64, reserve: u64) -> PoolState {
+        PoolState {
+            version: 1,
+            authority: Pubkey::default(),
+            pool_type: PoolType::Validator,
+            reward_rate,
+            lockup_period: 0,
+            total_staked: 0,
+            total_weight: 0,
+            tiers: vec![
+                LockupTier { duration: 30 * 86_400, multiplier_bps: 10_000 },
+                LockupTier { duration: 90 * 86_400, multiplier_bps: 15_000 },
+                LockupTier { duration: 180 * 86_400, multiplier_bps: 20_000 },
+            ],
+            reward_reserve: reserve,
+            acc_reward_per_share: 0,
+            early_unstake_penalty_bps: 0,
+            penalty_route: PenaltyRoute::RewardReserve,
+            referral_bps: 0,
+            rate_ramp_secs: 0,
+            rate_ramp: None,
+            paused: false,
+            scheduled: false,
+            receipt_mode: ReceiptMode::None,
+            receipt_mint: Pubkey::default(),
+            receipt_supply: 0,
+            liquid_backing: 0,
+            liquid_reward_debt: 0,
+            bump: 0,
+            last_update: 0,
+        }
+    }
+
+    fn stake(pool: &mut PoolState, user: &mut UserStake, amount: u64, now: i64) {
+        pool.accrue_rewards(now).unwrap();
+        user.settle(pool).unwrap();
+        user.amount += amount;
+        user.reward_debt = user.accrued(pool).unwrap();
+        pool.total_staked += amount;
+        pool.total_weight += amount;
+    }
+
+    fn open_position(pool: &mut PoolState, amount: u64, tier: usize, now: i64) -> StakePosition {
+        pool.accrue_rewards(now).unwrap();
+        let lockup = pool.tiers[tier];
+        let mut position = StakePosition {
+            pool: Pubkey::default(),
+            owner: Pubkey::default(),
+            id: 0,
+            amount,
+            tier: tier as u8,
+            multiplier_bps: lockup.multiplier_bps,
+            weight: lockup.weight(amount).unwrap(),
+            start: now,
+            unlock_at: now + lockup.duration,
+            reward_debt: 0,
+            unclaimed: 0,
+            bump: 0,
+        };
+        position.reward_debt = position.accrued(pool).unwrap();
+        pool.total_staked += amount;
+        pool.total_weight += position.weight;
+        position
+    }
+
+    /// Backing storage for an `AccountInfo` handed to `try_accounts`
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+        signer: bool,
+        executable: bool,
+    }
+
+    impl TestAccount {
+        fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+            Self { key, owner, lamports: 1_000_000_000, data, signer: false, executable: false }
+        }
+
+        fn program(key: Pubkey) -> Self {
+            Self { executable: true, ..Self::new(key, Pubkey::default(), Vec::new()) }
+        }
+
+        fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(
+                &self.key,
+                self.signer,
+                true,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                self.executable,
+                0,
+            )
+        }
+    }
+
+    /// Serves the rent sysvar to `init_if_needed` checks run natively
+    struct RentStub;
+
+    impl anchor_lang::solana_program::program_stubs::SyscallStubs for RentStub {
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            anchor_lang::solana_program::entrypoint::SUCCESS
+        }
+    }
+
+    fn token_account(mint: Pubkey, owner: Pubkey) -> Vec<u8> {
+        use anchor_lang::solana_program::program_pack::Pack;
+        let mut data = vec![0; spl_token_2022::state::Account::LEN];
+        spl_token_2022::state::Account {
+            mint,
+            owner,
+            state: spl_token_2022::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        data
+    }
+
+    fn mint_account() -> Vec<u8> {
+        use anchor_lang::solana_program::program_pack::Pack;
+        let mut data = vec![0; spl_token_2022::state::Mint::LEN];
+        spl_token_2022::state::Mint { decimals: 6, is_initialized: true, ..Default::default() }
+            .pack_into_slice(&mut data);
+        data
+    }
+
+    fn account_data<T: AccountSerialize>(account: &T, len: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.resize(len, 0);
+        data
+    }
+
+    /// Accounts of a `stake` by a fresh owner; `vault` holds `vault_mint` and
+    /// the owner's token account holds `mint`
+    fn stake_accounts(pool_key: Pubkey, vault: Pubkey, vault_mint: Pubkey, mint: Pubkey) -> Vec<TestAccount> {
+        let owner = Pubkey::new_unique();
+        let token_program = spl_token_2022::ID;
+        let user_token = anchor_spl::associated_token::get_associated_token_address_with_program_id(
+            &owner,
+            &mint,
+            &token_program,
+        );
+        let pda = |seed: &[u8]| Pubkey::find_program_address(&[seed, pool_key.as_ref(), owner.as_ref()], &ID).0;
+        let history = StakeHistory {
+            pool: pool_key,
+            owner,
+            checkpoints: Vec::new(),
+            truncated: false,
+            bump: 0,
+            liquid_amount: 0,
+            locked_weight: 0,
+        };
+
+        let mut signer = TestAccount::new(owner, System::id(), Vec::new());
+        signer.signer = true;
+        vec![
+            TestAccount::new(pool_key, ID, account_data(&pool(0, 0), PoolState::LEN)),
+            TestAccount::program(ID),
+            TestAccount::new(user_token, token_program, token_account(mint, owner)),
+            TestAccount::new(pda(b"stake"), ID, account_data(&user(), UserStake::LEN)),
+            TestAccount::new(pda(b"stake_history"), ID, account_data(&history, StakeHistory::LEN)),
+            TestAccount::new(vault, token_program, token_account(vault_mint, pool_key)),
+            signer,
+            TestAccount::new(mint, token_program, mint_account()),
+            TestAccount::program(System::id()),
+            TestAccount::program(token_program),
+            TestAccount::program(AssociatedToken::id()),
+        ]
+    }
+
+    fn try_stake(accounts: &mut [TestAccount]) -> Result<()> {
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        Stake::try_accounts(
+            &ID,
+            &mut infos.as_slice(),
+            &[],
+            &mut std::collections::BTreeMap::new(),
+            &mut std::collections::BTreeSet::new(),
+        )
+        .map(|_| ())
+    }
+
+    #[test]
+    fn test_stake_only_credits_the_pool_vault() {
+        anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(RentStub));
+        let pool_key = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (vault, _) = Pubkey::find_program_address(&[b"vault", pool_key.as_ref()], &ID);
+        assert!(try_stake(&mut stake_accounts(pool_key, vault, mint, mint)).is_ok());
+
+        // A token account of the staker's own choosing is not the vault
+        let own_account = Pubkey::new_unique();
+        assert_eq!(
+            try_stake(&mut stake_accounts(pool_key, own_account, mint, mint)).unwrap_err(),
+            anchor_lang::error::ErrorCode::ConstraintSeeds.into()
+        );
+
+        // Nor can stake arrive in a mint other than the vault's
+        let worthless = Pubkey::new_unique();
+        assert_eq!(
+            try_stake(&mut stake_accounts(pool_key, vault, mint, worthless)).unwrap_err(),
+            anchor_lang::error::ErrorCode::ConstraintAddress.into()
+        );
+    }
+
+    fn user() -> UserStake {
+        UserStake {
+            amount: 0,
+            last_staked: 0,
+            last_reward: 0,
+            reward_debt: 0,
+            unclaimed: 0,
+            referrer: None,
+            referral_owed: 0,
+        }
+    }
+
+    #[test]
+    fn test_late_staker_only_earns_after_joining() {
+        let mut pool = pool(10, 1_000_000);
+        let (mut alice, mut bob) = (user(), user());
+
+        stake(&mut pool, &mut alice, 100, 0);
+        stake(&mut pool, &mut bob, 100, 100);
+
+        pool.accrue_rewards(200).unwrap();
+        alice.settle(&pool).unwrap();
+        bob.settle(&pool).unwrap();
+
+        // Alice alone for 100s (1000), then both split 1000
+        assert_eq!(alice.unclaimed, 1_500);
+        assert_eq!(bob.unclaimed, 500);
+    }
+
+    #[test]
+    fn test_migrated_stake_keeps_rewards_and_lockup_age() {
+        let (mut old_pool, mut new_pool) = (pool(10, 1_000_000), pool(10, 1_000_000));
+        let mut alice = user();
+        stake(&mut old_pool, &mut alice, 100, 0);
+
+        old_pool.accrue_rewards(100).unwrap();
+        let (amount, rewards) = alice.take_for_migration(&mut old_pool).unwrap();
+        assert_eq!((amount, rewards), (100, 1_000));
+        assert_eq!((old_pool.total_staked, old_pool.total_weight), (0, 0));
+
+        let mut moved = user();
+        new_pool.accrue_rewards(100).unwrap();
+        moved.credit_migrated(&mut new_pool, amount, rewards, alice.last_staked).unwrap();
+        assert_eq!((moved.amount, moved.last_staked), (100, 0));
+
+        // Carried rewards stay claimable and earning resumes in the successor
+        new_pool.accrue_rewards(200).unwrap();
+        moved.settle(&new_pool).unwrap();
+        assert_eq!(moved.unclaimed, 2_000);
 
-#[derive(Accounts)]
-pub struct Stake<'info> {
-    #[account(mut)]
-    pub pool: Account<'info, PoolState>,
-    
-    #[account(
-        mut,
-        associated_token::mint = mint,
-        associated_token::authority = owner,
-    )]
-    pub user_token: Account<'info, TokenAccount>,
-    
-    #[account(
-        init_if_needed,
-        payer = owner,
-        space = UserStake::LEN,
-        seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()],
-        bump,
-    )]
-    pub user_stake: Account<'info, UserStake>,
-    
-    #[account(mut)]
-    pub vault: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    
-    pub mint: Account<'info, Mint>,
-    
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-}
+        // Merging into a newer stake never shortens its lockup
+        let mut fresh = user();
+        fresh.last_staked = 150;
+        fresh.credit_migrated(&mut new_pool, 50, 0, 0).unwrap();
+        assert_eq!(fresh.last_staked, 150);
+    }
 
-#[derive(Accounts)]
-pub struct Unstake<'info> {
-    // Similar to Stake with additional time checks
-}
+    #[test]
+    fn test_signer_seeds_match_pool_address_across_versions() {
+        let mut pool = pool(0, 0);
+        let pool_type = pool.pool_type.to_string();
+        let signer = |pool: &PoolState| {
+            let version_seed = pool.version_seed();
+            Pubkey::create_program_address(
+                &[b"pool", pool_type.as_bytes(), version_seed.as_slice(), &[pool.bump]],
+                &ID,
+            )
+            .unwrap()
+        };
 
-#[account]
-pub struct PoolState {
-    pub version: u8,
-    pub pool_type: PoolType,
-    pub reward_rate: u64,
-    pub lockup_period: i64,
-    pub total_staked: u64,
-    pub reward_reserve: u64,
-    pub bump: u8,
-    pub last_update: i64,
-}
+        // Address `initialize_pool` derives for the first pool of a type
+        let (first, bump) = Pubkey::find_program_address(&[b"pool", pool_type.as_bytes()], &ID);
+        pool.bump = bump;
+        assert_eq!(signer(&pool), first);
 
-#[account]
-pub struct UserStake {
-    pub amount: u64,
-    pub last_staked: i64,
-    pub last_reward: i64,
-}
+        // Address `migrate_pool` derives for its successor
+        let (successor, bump) =
+            Pubkey::find_program_address(&[b"pool", pool_type.as_bytes(), &[2]], &ID);
+        pool.version = 2;
+        pool.bump = bump;
+        assert_eq!(signer(&pool), successor);
+        assert_ne!(successor, first);
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum PoolType {
-    GPUProvider,
-    Validator,
-    Trainer,
-    Governance,
-}
+    #[test]
+    fn test_referral_cut_must_be_synced_before_migration() {
+        let mut pool = pool(10, 1_000_000);
+        pool.referral_bps = 1_000;
+        let mut alice = user();
+        alice.referrer = Some(Pubkey::new_unique());
+        stake(&mut pool, &mut alice, 100, 0);
 
-#[error_code]
-pub enum VaultError {
-    #[msg("Lockup period not expired")]
-    LockupActive,
-    #[msg("Insufficient staked amount")]
-    InsufficientStake,
-    #[msg("No rewards available")]
-    NoRewardsAvailable,
-    #[msg("Proposal no longer active")]
-    ProposalNotActive,
-    #[msg("Minimum voting stake not met")]
-    InsufficientVotingPower,
-    #[msg("Invalid reward distribution")]
-    InvalidRewardCalc,
-}
+        pool.accrue_rewards(100).unwrap();
+        assert!(alice.take_for_migration(&mut pool).is_err());
+    }
 
-#[event]
-pub enum PoolEvent {
-    PoolInitialized {
-        pool: Pubkey,
-        timestamp: i64,
-    },
-    Staked {
-        user: Pubkey,
-        amount: u64,
-        timestamp: i64,
-    },
-    Unstaked {
-        user: Pubkey,
-        amount: u64,
-        timestamp: i64,
-    },
-    RewardClaimed {
-        user: Pubkey,
-        amount: u64,
-        timestamp: i64,
-    },
-}
+    #[test]
+    fn test_migrated_position_keeps_lockup_window() {
+        let (mut old_pool, mut new_pool) = (pool(10, 1_000_000), pool(10, 1_000_000));
+        let mut position = open_position(&mut old_pool, 1_000, 1, 0);
+        old_pool.accrue_rewards(100).unwrap();
+        position.settle(&old_pool).unwrap();
 
-// Helper functions
-fn calculate_rewards(user: &UserStake, pool: &PoolState, now: i64) -> Result<u64> {
-    let duration = now - user.last_reward;
-    if duration <= 0 || pool.reward_rate == 0 {
-        return Ok(0);
+        new_pool.accrue_rewards(100).unwrap();
+        let key = Pubkey::new_unique();
+        // Transfer fees took 10 on the way across
+        let moved = position.migrated(&new_pool, key, 990, position.unclaimed, 7).unwrap();
+        assert_eq!((moved.pool, moved.bump), (key, 7));
+        assert_eq!((moved.tier, moved.start, moved.unlock_at), (1, 0, position.unlock_at));
+        assert_eq!((moved.weight, moved.unclaimed), (1_485, 1_000));
+        assert_eq!(moved.reward_debt, moved.accrued(&new_pool).unwrap());
     }
-    
-    let reward = user.amount
-        .checked_mul(pool.reward_rate)
-        .and_then(|r| r.checked_mul(duration.try_into().unwrap()))
-        .ok_or(VaultError::InvalidRewardCalc)?;
-    
-    Ok(reward / 1_000_000) // Normalize by precision factor
-}
 
-fn distribute_rewards(ctx: &mut ClaimRewards, amount: u64) -> Result<()> {
-    let transfer_ix = Transfer {
-        from: ctx.reward_vault.to_account_info(),
-        to: ctx.user_token.to_account_info(),
-        authority: ctx.pool.to_account_info(),
-    };
-    
-    let seeds = &[b"pool", &[ctx.pool.bump]];
-    let signer = &[&seeds[..]];
-    let cpi_ctx = CpiContext::new_with_signer(
-        ctx.token_program.to_account_info(),
-        transfer_ix,
-        signer,
-    );
-    token::transfer(cpi_ctx, amount)
+    #[test]
+    fn test_changed_mint_migration_draws_on_reserve() {
+        let mut migration = PoolMigration {
+            from_pool: Pubkey::new_unique(),
+            to_pool: Pubkey::new_unique(),
+            from_admin: Pubkey::new_unique(),
+            to_authority: Pubkey::new_unique(),
+            reserve: 1_000,
+            legacy_recipient: Some(Pubkey::new_unique()),
+            migrated_amount: 0,
+            migrated_stakes: 0,
+            created_at: 0,
+            bump: 0,
+        };
+        migration.record(600, 100).unwrap();
+        assert_eq!((migration.reserve, migration.migrated_amount), (300, 600));
+        assert!(migration.record(300, 1).is_err());
+
+        // Unchanged mints move the tokens themselves and need no reserve
+        migration.legacy_recipient = None;
+        migration.record(5_000, 500).unwrap();
+        assert_eq!((migration.reserve, migration.migrated_stakes), (300, 2));
+    }
+
+    #[test]
+    fn test_rate_change_is_prospective_and_capped_by_reserve() {
+        let mut pool = pool(10, 1_500);
+        let mut alice = user();
+        stake(&mut pool, &mut alice, 100, 0);
+
+        pool.accrue_rewards(100).unwrap();
+        pool.reward_rate = 100;
+        pool.accrue_rewards(200).unwrap();
+        alice.settle(&pool).unwrap();
+
+        // 1000 at the old rate, then only the remaining 500 of reserve
+        assert_eq!(alice.unclaimed, 1_500);
+        assert_eq!(pool.reward_reserve, 0);
+    }
+
+    #[test]
+    fn test_rate_change_ramps_in_linearly() {
+        let mut pool = pool(0, 1_000_000);
+        pool.rate_ramp_secs = 100;
+        let mut alice = user();
+        stake(&mut pool, &mut alice, 100, 0);
+
+        pool.schedule_rate(0, 100);
+        assert_eq!(pool.effective_rate(50), 50);
+
+        // Half way: ∫0..50 of t dt = 1250, not the 5000 an instant hike would pay
+        pool.accrue_rewards(50).unwrap();
+        alice.settle(&pool).unwrap();
+        assert_eq!(alice.unclaimed, 1_250);
+
+        // Rest of the ramp, then 10s at the full rate
+        pool.accrue_rewards(110).unwrap();
+        alice.settle(&pool).unwrap();
+        assert_eq!(alice.unclaimed, 5_000 + 1_000);
+        assert_eq!((pool.reward_rate, pool.rate_ramp), (100, None));
+    }
+
+    fn proposal(votes_for: u64, votes_against: u64) -> Proposal {
+        Proposal {
+            proposer: Pubkey::default(),
+            pool: Pubkey::default(),
+            proposal_type: ProposalType::RewardRateChange { reward_rate: 5 },
+            amount: None,
+            recipient: None,
+            votes_for,
+            votes_against,
+            created_at: 0,
+            total_staked_snapshot: 10_000,
+            voting_ends_at: VOTING_PERIOD,
+            timelock_delay: PARAMETER_TIMELOCK_DELAY,
+            executable_at: 0,
+            status: ProposalStatus::Active,
+            epoch: 0,
+            voters: 0,
+        }
+    }
+
+    #[test]
+    fn test_proposal_outcome_requires_quorum_and_majority() {
+        // 20% quorum of 10_000 staked
+        assert_eq!(proposal(1_500, 400).outcome(), ProposalStatus::Rejected);
+        assert_eq!(proposal(1_500, 500).outcome(), ProposalStatus::Passed);
+        // A tie does not clear the threshold
+        assert_eq!(proposal(1_000, 1_000).outcome(), ProposalStatus::Rejected);
+        let mut empty = proposal(0, 0);
+        empty.total_staked_snapshot = 0;
+        assert_eq!(empty.outcome(), ProposalStatus::Rejected);
+    }
+
+    #[test]
+    fn test_fund_moving_proposals_need_payload() {
+        let mut p = proposal(0, 0);
+        p.proposal_type = ProposalType::TreasuryTransfer;
+        assert!(p.validate_payload().is_err());
+
+        p.amount = Some(100);
+        p.recipient = Some(Pubkey::new_unique());
+        assert!(p.validate_payload().is_ok());
+        assert_eq!(p.proposal_type.timelock_delay(), FUNDS_TIMELOCK_DELAY);
+    }
+
+    #[test]
+    fn test_turnout_capped_at_full_snapshot() {
+        assert_eq!(proposal(1_500, 500).turnout_bps(), 2_000);
+        assert_eq!(proposal(9_000, 4_000).turnout_bps(), 10_000);
+        let mut empty = proposal(0, 0);
+        empty.total_staked_snapshot = 0;
+        assert_eq!(empty.turnout_bps(), 0);
+    }
+
+    fn participation_config(tiers: Vec<ParticipationTier>) -> ParticipationConfig {
+        ParticipationConfig {
+            pool: Pubkey::default(),
+            reward_per_epoch: 1_000,
+            epoch_budget: 10_000,
+            min_participation_bps: 5_000,
+            tiers,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_participation_reward_weighted_by_stake_tier() {
+        let tiers = vec![
+            ParticipationTier { min_stake: MIN_VOTING_STAKE, weight_bps: 2_500 },
+            ParticipationTier { min_stake: 10_000, weight_bps: 10_000 },
+        ];
+        validate_participation_tiers(&tiers).unwrap();
+        let config = participation_config(tiers);
+
+        assert_eq!(config.tier_for(MIN_VOTING_STAKE - 1), None);
+        assert_eq!(config.reward(config.tier_for(MIN_VOTING_STAKE).unwrap()).unwrap(), 250);
+        assert_eq!(config.reward(config.tier_for(9_999).unwrap()).unwrap(), 250);
+        assert_eq!(config.reward(config.tier_for(50_000).unwrap()).unwrap(), 1_000);
+
+        let participation = EpochParticipation {
+            pool: Pubkey::default(),
+            voter: Pubkey::default(),
+            epoch: 0,
+            votes: 2,
+            claimed: false,
+            bump: 0,
+        };
+        assert_eq!(participation.participation_bps(4), 5_000);
+        assert_eq!(participation.participation_bps(0), 0);
+    }
+
+    #[test]
+    fn test_participation_tiers_rejected_out_of_order_or_below_voting_stake() {
+        let tier = |min_stake, weight_bps| ParticipationTier { min_stake, weight_bps };
+        assert!(validate_participation_tiers(&[]).is_err());
+        assert!(validate_participation_tiers(&[tier(MIN_VOTING_STAKE - 1, 5_000)]).is_err());
+        assert!(validate_participation_tiers(&[tier(MIN_VOTING_STAKE, 0)]).is_err());
+        assert!(validate_participation_tiers(&[tier(5_000, 5_000), tier(2_000, 8_000)]).is_err());
+        assert!(validate_participation_tiers(&[tier(2_000, 8_000), tier(5_000, 5_000)]).is_err());
+    }
+
+    #[test]
+    fn test_voter_streak_resets_after_skipped_epoch() {
+        let mut streak = VoterStreak {
+            pool: Pubkey::default(),
+            voter: Pubkey::default(),
+            current: 0,
+            longest: 0,
+            last_epoch: None,
+            bump: 0,
+        };
+        streak.advance(3).unwrap();
+        streak.advance(4).unwrap();
+        streak.advance(5).unwrap();
+        assert_eq!((streak.current, streak.longest), (3, 3));
+        assert!(streak.advance(5).is_err());
+
+        streak.advance(7).unwrap();
+        assert_eq!((streak.current, streak.longest), (1, 3));
+        assert_eq!(governance_epoch(epoch_start(7) + 1), 7);
+    }
+
+    #[test]
+    fn test_vote_power_uses_stake_before_snapshot() {
+        let mut history = StakeHistory {
+            pool: Pubkey::default(),
+            owner: Pubkey::default(),
+            checkpoints: Vec::new(),
+            truncated: false,
+            bump: 0,
+            liquid_amount: 0,
+            locked_weight: 0,
+        };
+        history.record(100, 5_000).unwrap();
+        history.record(200, 50_000).unwrap();
+
+        assert_eq!(history.amount_at(50).unwrap(), 0);
+        assert_eq!(history.amount_at(150).unwrap(), 5_000);
+        // Stake added in the same second as the proposal does not count
+        assert_eq!(history.amount_at(200).unwrap(), 5_000);
+        assert_eq!(history.amount_at(201).unwrap(), 50_000);
+
+        for t in 0..MAX_STAKE_CHECKPOINTS as i64 {
+            history.record(300 + t, 1).unwrap();
+        }
+        assert!(history.amount_at(150).is_err());
+    }
+
+    #[test]
+    fn test_locked_positions_earn_by_tier_multiplier() {
+        let mut pool = pool(10, 1_000_000);
+        let mut alice = user();
+        stake(&mut pool, &mut alice, 100, 0);
+        // 180-day tier at 2x carries twice the weight of the same liquid stake
+        let mut position = open_position(&mut pool, 100, 2, 0);
+        assert_eq!(pool.total_staked, 200);
+        assert_eq!(pool.total_weight, 300);
+
+        pool.accrue_rewards(300).unwrap();
+        alice.settle(&pool).unwrap();
+        position.settle(&pool).unwrap();
+        assert_eq!(alice.unclaimed, 1_000);
+        assert_eq!(position.unclaimed, 2_000);
+        assert_eq!(position.unlock_at, 180 * 86_400);
+    }
+
+    #[test]
+    fn test_position_unlocks_at_tier_end() {
+        let mut pool = pool(10, 1_000_000);
+        let position = open_position(&mut pool, 100, 0, 1_000);
+
+        let _clock = env::testing::pin_clock(0, position.unlock_at - 1);
+        assert!(position.require_unlocked().is_err());
+        env::testing::advance(1, 1);
+        assert!(position.require_unlocked().is_ok());
+    }
+
+    #[test]
+    fn test_tiers_must_lengthen_with_non_decreasing_boost() {
+        let tier = |days: i64, multiplier_bps: u16| LockupTier {
+            duration: days * 86_400,
+            multiplier_bps,
+        };
+        assert!(validate_tiers(&pool(0, 0).tiers).is_ok());
+        assert!(validate_tiers(&[]).is_ok());
+        assert!(validate_tiers(&[tier(90, 15_000), tier(30, 20_000)]).is_err());
+        assert!(validate_tiers(&[tier(30, 15_000), tier(90, 12_000)]).is_err());
+        assert!(validate_tiers(&[tier(30, 9_000)]).is_err());
+        assert!(validate_tiers(&[tier(30, MAX_TIER_MULTIPLIER_BPS + 1)]).is_err());
+
+        let mut history = StakeHistory {
+            pool: Pubkey::default(),
+            owner: Pubkey::default(),
+            checkpoints: Vec::new(),
+            truncated: false,
+            bump: 0,
+            liquid_amount: 1_000,
+            locked_weight: tier(90, 15_000).weight(2_000).unwrap(),
+        };
+        history.update(10).unwrap();
+        assert_eq!(history.amount_at(11).unwrap(), 4_000);
+    }
+
+    #[test]
+    fn test_compounding_restakes_at_position_multiplier() {
+        let mut pool = pool(10, 1_000_000);
+        let mut position = open_position(&mut pool, 1_000, 1, 0);
+        assert_eq!(position.weight, 1_500);
+
+        pool.accrue_rewards(100).unwrap();
+        position.settle(&pool).unwrap();
+        assert_eq!(position.unclaimed, 1_000);
+
+        let added = position.add_stake(position.unclaimed).unwrap();
+        position.unclaimed = 0;
+        position.reward_debt = position.accrued(&pool).unwrap();
+        pool.total_staked += 1_000;
+        pool.total_weight += added;
+        assert_eq!(added, 1_500);
+        assert_eq!((position.amount, position.weight), (2_000, 3_000));
+        assert_eq!(pool.total_weight, 3_000);
+
+        // Nothing further is owed until new emissions accrue
+        position.settle(&pool).unwrap();
+        assert_eq!(position.unclaimed, 0);
+    }
+
+    #[test]
+    fn test_role_rotation_is_two_step_and_per_role() {
+        let (admin, slasher, nominee) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut config = PoolConfig {
+            pool: Pubkey::default(),
+            pool_admin: RoleSlot::new(admin),
+            slash_authority: RoleSlot::new(slasher),
+            fee_manager: RoleSlot::new(admin),
+            pauser: RoleSlot::new(admin),
+            bump: 0,
+        };
+
+        // Holding one role grants nothing over another
+        assert!(config.propose(Role::SlashAuthority, admin, Some(nominee)).is_err());
+        assert!(config.accept(Role::PoolAdmin, nominee).is_err());
+
+        config.propose(Role::PoolAdmin, admin, Some(nominee)).unwrap();
+        assert_eq!(config.pool_admin.holder, admin);
+        assert!(config.accept(Role::PoolAdmin, slasher).is_err());
+        assert_eq!(config.accept(Role::PoolAdmin, nominee).unwrap(), admin);
+        assert_eq!(config.pool_admin, RoleSlot::new(nominee));
+        assert_eq!(config.fee_manager.holder, admin);
+
+        // A withdrawn nomination cannot be accepted
+        config.propose(Role::Pauser, admin, Some(nominee)).unwrap();
+        config.propose(Role::Pauser, admin, None).unwrap();
+        assert!(config.accept(Role::Pauser, nominee).is_err());
+    }
+
+    fn delegation_pool() -> DelegationPool {
+        DelegationPool {
+            operator: Pubkey::default(),
+            worker: Pubkey::default(),
+            stake_pool: Pubkey::default(),
+            commission_bps: 1_000,
+            total_delegated: 0,
+            total_shares: 0,
+            bump: 0,
+        }
+    }
+
+    fn delegation() -> Delegation {
+        Delegation { delegator: Pubkey::default(), pool: Pubkey::default(), shares: 0 }
+    }
+
+    #[test]
+    fn test_delegate_then_undelegate_at_exchange_rate() {
+        let mut pool = delegation_pool();
+        let (mut alice, mut bob) = (delegation(), delegation());
+
+        assert_eq!(pool.deposit(&mut alice, 1_000).unwrap(), 1_000);
+        // Distributed rewards raise the share price for existing delegators
+        pool.total_delegated += 1_000;
+        assert_eq!(pool.deposit(&mut bob, 1_000).unwrap(), 500);
+        assert_eq!((pool.total_shares, pool.total_delegated), (1_500, 3_000));
+
+        assert_eq!(pool.withdraw(&mut alice, 1_000).unwrap(), 2_000);
+        assert_eq!(alice.shares, 0);
+        assert_eq!(pool.withdraw(&mut bob, 500).unwrap(), 1_000);
+        assert_eq!((pool.total_shares, pool.total_delegated), (0, 0));
+    }
+
+    #[test]
+    fn test_undelegate_rejects_more_than_held() {
+        let mut pool = delegation_pool();
+        let mut alice = delegation();
+        pool.deposit(&mut alice, 100).unwrap();
+
+        assert!(pool.withdraw(&mut alice, 101).is_err());
+        assert!(pool.withdraw(&mut alice, 0).is_err());
+        assert_eq!((alice.shares, pool.total_shares, pool.total_delegated), (100, 100, 100));
+
+        // Bookkeeping that no longer covers the shares errors instead of wrapping
+        pool.total_shares = 50;
+        assert!(pool.withdraw(&mut alice, 100).is_err());
+    }
+
+    #[test]
+    fn test_delegation_pool_requires_worker_signature() {
+        let worker = Pubkey::new_unique();
+        let metas = accounts::CreateDelegationPool {
+            delegation_pool: Pubkey::find_program_address(&[b"delegation", worker.as_ref()], &ID).0,
+            delegation_vault: Pubkey::new_unique(),
+            stake_pool: Pubkey::new_unique(),
+            operator: Pubkey::new_unique(),
+            worker,
+            mint: Pubkey::new_unique(),
+            system_program: System::id(),
+            token_program: Pubkey::new_unique(),
+        }
+        .to_account_metas(None);
+
+        // A squatter cannot open the pool seeded by a worker key it does not control
+        let worker_meta = metas.iter().find(|meta| meta.pubkey == worker).unwrap();
+        assert!(worker_meta.is_signer);
+    }
+
+    #[test]
+    fn test_slash_keeps_earned_rewards_and_shrinks_pool() {
+        let mut pool = pool(10, 1_000_000);
+        let mut provider = user();
+        let mut other = user();
+        stake(&mut pool, &mut provider, 100, 0);
+        stake(&mut pool, &mut other, 100, 0);
+
+        pool.accrue_rewards(100).unwrap();
+        assert_eq!(provider.slash(&mut pool, 1_000).unwrap(), 10);
+        assert_eq!(provider.amount, 90);
+        assert_eq!(provider.unclaimed, 500);
+        assert_eq!((pool.total_staked, pool.total_weight), (190, 190));
+
+        // Later emissions follow the reduced stake
+        pool.accrue_rewards(290).unwrap();
+        provider.settle(&pool).unwrap();
+        other.settle(&pool).unwrap();
+        assert_eq!(provider.unclaimed, 500 + 900);
+        assert_eq!(other.unclaimed, 500 + 1_000);
+
+        let mut p = proposal(0, 0);
+        p.proposal_type = ProposalType::SlashStake {
+            provider: Pubkey::new_unique(),
+            slash_bps: 0,
+            burn: true,
+        };
+        assert!(p.validate_payload().is_err());
+        assert_eq!(p.proposal_type.timelock_delay(), FUNDS_TIMELOCK_DELAY);
+    }
+
+    #[test]
+    fn test_slash_reaches_locked_positions() {
+        let mut pool = pool(10, 1_000_000);
+        let mut position = open_position(&mut pool, 1_000, 1, 0);
+        let unlock_at = position.unlock_at;
+
+        pool.accrue_rewards(100).unwrap();
+        assert_eq!(position.slash(&mut pool, 1_000).unwrap(), (100, 150));
+        assert_eq!((position.amount, position.weight), (900, 1_350));
+        assert_eq!(position.unclaimed, 1_000);
+        assert_eq!(position.unlock_at, unlock_at);
+        assert_eq!((pool.total_staked, pool.total_weight), (900, 1_350));
+
+        // A provider with only locked stake slashes nothing liquid
+        let mut provider = user();
+        assert_eq!(provider.slash(&mut pool, 1_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_schedule_decays_emissions_across_segments() {
+        let mut pool = pool(0, 1_000_000);
+        let mut alice = user();
+        stake(&mut pool, &mut alice, 100, 0);
+
+        let segments = vec![
+            EmissionSegment { start_ts: 0, end_ts: 100, rate: 10 },
+            EmissionSegment { start_ts: 100, end_ts: 200, rate: 5 },
+            // Gap from 200 to 300 emits nothing
+            EmissionSegment { start_ts: 300, end_ts: 400, rate: 1 },
+        ];
+        validate_segments(&segments).unwrap();
+        let schedule = EmissionSchedule {
+            pool: Pubkey::default(),
+            segments,
+            bump: 0,
+        };
+        pool.scheduled = true;
+        assert!(pool.accrue_rewards(50).is_err());
+
+        // One accrual spanning every boundary equals the per-segment sum
+        pool.accrue(1_000, Some(&schedule)).unwrap();
+        alice.settle(&pool).unwrap();
+        assert_eq!(alice.unclaimed, 1_000 + 500 + 100);
+
+        let overlapping = [
+            EmissionSegment { start_ts: 0, end_ts: 100, rate: 1 },
+            EmissionSegment { start_ts: 50, end_ts: 150, rate: 1 },
+        ];
+        assert!(validate_segments(&overlapping).is_err());
+        assert!(validate_segments(&[]).is_err());
+    }
+
+    #[test]
+    fn test_paused_pool_emits_nothing_until_unpaused() {
+        let mut pool = pool(10, 1_000_000);
+        let mut alice = user();
+        stake(&mut pool, &mut alice, 100, 0);
+
+        pool.accrue_rewards(100).unwrap();
+        pool.paused = true;
+        // An unstake during the pause settles against the frozen accumulator
+        pool.accrue_rewards(500).unwrap();
+        alice.settle(&pool).unwrap();
+        assert_eq!(alice.unclaimed, 1_000);
+
+        pool.accrue_rewards(1_000).unwrap();
+        pool.paused = false;
+        pool.accrue_rewards(1_100).unwrap();
+        alice.settle(&pool).unwrap();
+        assert_eq!(alice.unclaimed, 2_000);
+        assert_eq!(pool.reward_reserve, 998_000);
+    }
+
+    #[test]
+    fn test_referral_cut_withheld_from_referred_rewards() {
+        let mut pool = pool(10, 1_000_000);
+        pool.referral_bps = 1_000;
+        let mut referred = user();
+        referred.referrer = Some(Pubkey::new_unique());
+        let mut direct = user();
+
+        stake(&mut pool, &mut referred, 1_000, 0);
+        stake(&mut pool, &mut direct, 1_000, 0);
+        pool.accrue_rewards(200).unwrap();
+        referred.settle(&pool).unwrap();
+        direct.settle(&pool).unwrap();
+
+        // Each earned 1_000; 10% of the referred stake's share is held back
+        assert_eq!((referred.unclaimed, referred.referral_owed), (900, 100));
+        assert_eq!((direct.unclaimed, direct.referral_owed), (1_000, 0));
+    }
+
+    #[test]
+    fn test_liquid_rewards_raise_receipt_exchange_rate() {
+        let mut pool = pool(10, 1_000_000);
+        pool.receipt_mode = ReceiptMode::Liquid;
+
+        pool.accrue_rewards(0).unwrap();
+        let shares = pool.receipt_shares_for(1_000).unwrap();
+        pool.add_liquid(1_000, shares).unwrap();
+        let mut bob = user();
+        stake(&mut pool, &mut bob, 1_000, 0);
+
+        // Half of the 1_000 emitted goes to the liquid share and is restaked unminted
+        pool.accrue_rewards(100).unwrap();
+        let pending = pool.pending_liquid_rewards().unwrap();
+        assert_eq!(pending, 500);
+        pool.add_liquid(pending, 0).unwrap();
+        assert_eq!(pool.pending_liquid_rewards().unwrap(), 0);
+        assert_eq!((pool.liquid_backing, pool.receipt_supply), (1_500, 1_000));
+
+        // Late depositors buy in at 1.5 tokens per share
+        let shares = pool.receipt_shares_for(300).unwrap();
+        assert_eq!(shares, 200);
+        pool.add_liquid(300, shares).unwrap();
+        assert_eq!(pool.receipt_amount_for(200).unwrap(), 300);
+        assert!(pool.receipt_amount_for(1_201).is_err());
+
+        // Liquid stake earns but cannot vote
+        assert_eq!(pool.total_weight, 2_800);
+        assert_eq!(pool.voting_weight(), 1_000);
+
+        pool.remove_liquid(300, 200).unwrap();
+        assert_eq!((pool.liquid_backing, pool.receipt_supply), (1_500, 1_000));
+    }
+
+    #[test]
+    fn test_transfer_fee_read_from_token_2022_mint() {
+        use spl_token_2022::{
+            extension::{transfer_fee::TransferFee, ExtensionType, StateWithExtensionsMut},
+            state::Mint as MintState,
+        };
+
+        let space =
+            ExtensionType::try_calculate_account_len::<MintState>(&[ExtensionType::TransferFeeConfig])
+                .unwrap();
+        let mut data = vec![0u8; space];
+        let mut state = StateWithExtensionsMut::<MintState>::unpack_uninitialized(&mut data).unwrap();
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: 50.into(),
+            transfer_fee_basis_points: 100.into(),
+        };
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.older_transfer_fee = fee;
+        config.newer_transfer_fee = fee;
+        state.base = MintState {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        // 1% capped at 50, rounded up
+        assert_eq!(mint_transfer_fee(&data, 3, 1_000).unwrap(), 10);
+        assert_eq!(mint_transfer_fee(&data, 3, 1_050).unwrap(), 11);
+        assert_eq!(mint_transfer_fee(&data, 3, 1_000_000).unwrap(), 50);
+
+        // Token-2022 mints without the extension charge nothing
+        let plain_space = ExtensionType::try_calculate_account_len::<MintState>(&[]).unwrap();
+        let mut plain = vec![0u8; plain_space];
+        let mut state = StateWithExtensionsMut::<MintState>::unpack_uninitialized(&mut plain).unwrap();
+        state.base = MintState {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        assert_eq!(mint_transfer_fee(&plain, 3, 1_000).unwrap(), 0);
+    }
 }
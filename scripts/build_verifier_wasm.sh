@@ -0,0 +1,38 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+# Builds the on-chain verifier's checks to wasm for client-sdk pre-verification
+# (src/utils/preverify.ts). Re-run whenever zero-knowledge-zkml/verifier changes
+# so the SDK reports the same errors the deployed program would.
+
+export ROOT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")/.." && pwd)"
+export OUT_DIR="$ROOT_DIR/client-sdk/verifier-wasm"
+
+check_dependencies() {
+  declare -a required=("cargo" "wasm-pack" "jq")
+
+  for cmd in "${required[@]}"; do
+    if ! command -v "$cmd" &> /dev/null; then
+      echo "❌ Missing required tool: $cmd"
+      exit 1
+    fi
+  done
+  echo "✅ Verified all dependencies"
+}
+
+build_wasm() {
+  # `preverify` is compiled for every target except the Solana runtime
+  wasm-pack build "$ROOT_DIR/zero-knowledge-zkml/verifier" \
+    --release \
+    --target nodejs \
+    --out-dir "$OUT_DIR" \
+    --out-name verifier_wasm
+
+  # Publish under the name the SDK documents
+  jq '.name = "@haunti/verifier-wasm"' "$OUT_DIR/package.json" > "$OUT_DIR/package.json.tmp"
+  mv "$OUT_DIR/package.json.tmp" "$OUT_DIR/package.json"
+  echo "✅ Verifier wasm written to $OUT_DIR"
+}
+
+check_dependencies
+build_wasm
@@ -1,6 +1,6 @@
 //! FHE-accelerated inference benchmarks with multi-GPU support
 
-use criterion::{black_box, criterion_group, criterion_main, AxisScale, Criterion, PlotConfiguration, SamplingMode};
+use criterion::{black_box, criterion_group, criterion_main, AxisScale, BenchmarkId, Criterion, PlotConfiguration, SamplingMode, Throughput};
 use concrete::{
     prelude::*,
     CudaEngine,
@@ -10,9 +10,16 @@ use concrete::{
 };
 use concrete_nn::FheConvNet;
 use rand::Rng;
+use std::ffi::c_void;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+// Staging buffers exactly as the compute node allocates them
+#[path = "../../compute-network/node/src/pinned_memory.rs"]
+mod pinned_memory;
+
+use pinned_memory::{GpuLocality, PinnedBuffer, PinnedPool};
+
 // Security parameters meeting 128-bit security
 const FHE_CONFIG: &str = "
 lattice_dimension: 8192
@@ -42,6 +49,33 @@ impl Default for InferenceConfig {
     }
 }
 
+/// Serialized LWE ciphertext at `lattice_dimension: 8192`: mask plus body, 8 bytes each
+const CIPHERTEXT_BYTES: usize = (8192 + 1) * 8;
+/// Ciphertexts uploaded per input sample in the transfer benchmark
+const CIPHERTEXTS_PER_SAMPLE: usize = 64;
+
+mod cudart {
+    use std::ffi::c_void;
+
+    pub const MEMCPY_HOST_TO_DEVICE: i32 = 1;
+
+    extern "C" {
+        pub fn cudaSetDevice(device: i32) -> i32;
+        pub fn cudaMalloc(ptr: *mut *mut c_void, size: usize) -> i32;
+        pub fn cudaFree(ptr: *mut c_void) -> i32;
+        pub fn cudaStreamCreate(stream: *mut *mut c_void) -> i32;
+        pub fn cudaStreamDestroy(stream: *mut c_void) -> i32;
+        pub fn cudaStreamSynchronize(stream: *mut c_void) -> i32;
+        pub fn cudaMemcpyAsync(
+            dst: *mut c_void,
+            src: *const c_void,
+            count: usize,
+            kind: i32,
+            stream: *mut c_void,
+        ) -> i32;
+    }
+}
+
 struct BenchContext {
     engine: Arc<CudaEngine>,
     model: FheConvNet,
@@ -153,6 +187,106 @@ fn encryption_benchmark(c: &mut Criterion, cfg: &InferenceConfig) {
     });
 }
 
+/// Time `iters` host-to-device copies of `src` on `stream`
+fn timed_uploads(src: &[u8], device: *mut c_void, stream: *mut c_void, iters: u64) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iters {
+        unsafe {
+            cudart::cudaMemcpyAsync(
+                device,
+                src.as_ptr() as *const c_void,
+                src.len(),
+                cudart::MEMCPY_HOST_TO_DEVICE,
+                stream,
+            );
+            cudart::cudaStreamSynchronize(stream);
+        }
+    }
+    start.elapsed()
+}
+
+/// Some online NUMA node other than `local`, parsed from a kernel node list
+/// such as `0-1`
+fn remote_numa_node(local: u32) -> Option<u32> {
+    let online = std::fs::read_to_string("/sys/devices/system/node/online").ok()?;
+    online
+        .trim()
+        .split(',')
+        .flat_map(|range| {
+            let (lo, hi) = range.split_once('-').unwrap_or((range, range));
+            lo.parse::<u32>().unwrap_or(0)..=hi.parse::<u32>().unwrap_or(0)
+        })
+        .find(|&node| node != local)
+}
+
+/// Host-to-device upload of a batch of ciphertexts from each kind of host
+/// memory. Compare, per batch size:
+/// - `pageable` against `pinned_pooled_local`: the transfer-time reduction
+///   staging buffers bring;
+/// - `pinned_fresh`: what pinning per transfer would cost without the pool;
+/// - `pinned_pooled_remote` (multi-socket hosts only): the cost of staging on
+///   the wrong side of the interconnect.
+///
+/// Run on the GPU host with `cargo bench --bench fhe_inference_bench -- "H2D"`.
+fn transfer_benchmark(c: &mut Criterion, cfg: &InferenceConfig) {
+    let locality = GpuLocality::probe(0).expect("CUDA device 0");
+    let local_pool = PinnedPool::new(locality.numa_node, usize::MAX);
+    let remote_pool = locality
+        .numa_node
+        .and_then(remote_numa_node)
+        .map(|node| PinnedPool::new(Some(node), usize::MAX));
+
+    let max_bytes = cfg.batch_sizes.iter().max().unwrap() * CIPHERTEXTS_PER_SAMPLE * CIPHERTEXT_BYTES;
+    let (mut device, mut stream) = (std::ptr::null_mut(), std::ptr::null_mut());
+    unsafe {
+        cudart::cudaSetDevice(0);
+        cudart::cudaMalloc(&mut device, max_bytes);
+        cudart::cudaStreamCreate(&mut stream);
+    }
+
+    let mut group = c.benchmark_group("H2D Ciphertext Transfer");
+    group.sample_size(20);
+    group.measurement_time(Duration::from_secs(10));
+
+    for &batch_size in &cfg.batch_sizes {
+        let payload = vec![0x5a_u8; batch_size * CIPHERTEXTS_PER_SAMPLE * CIPHERTEXT_BYTES];
+        group.throughput(Throughput::Bytes(payload.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("pageable", batch_size), &payload, |b, payload| {
+            b.iter_custom(|iters| timed_uploads(payload, device, stream, iters))
+        });
+
+        group.bench_with_input(BenchmarkId::new("pinned_fresh", batch_size), &payload, |b, payload| {
+            b.iter_custom(|iters| {
+                let start = Instant::now();
+                for _ in 0..iters {
+                    let mut buffer = PinnedBuffer::allocate(payload.len(), locality.numa_node).unwrap();
+                    buffer.fill(payload);
+                    timed_uploads(buffer.as_slice(), device, stream, 1);
+                }
+                start.elapsed()
+            })
+        });
+
+        let pools = std::iter::once(("pinned_pooled_local", &local_pool))
+            .chain(remote_pool.iter().map(|pool| ("pinned_pooled_remote", pool)));
+        for (name, pool) in pools {
+            group.bench_with_input(BenchmarkId::new(name, batch_size), &payload, |b, payload| {
+                // Pinned once on warm-up, reused by every measured iteration
+                let mut buffer = pool.acquire(payload.len()).unwrap();
+                buffer.fill(payload);
+                b.iter_custom(|iters| timed_uploads(buffer.as_slice(), device, stream, iters))
+            });
+        }
+    }
+    group.finish();
+
+    unsafe {
+        cudart::cudaStreamDestroy(stream);
+        cudart::cudaFree(device);
+    }
+}
+
 fn configure_criterion() -> Criterion {
     Criterion::default()
         .with_plots()
@@ -165,6 +299,7 @@ criterion_group!{
     targets = 
         inference_benchmark,
         encryption_benchmark,
+        transfer_benchmark,
 }
 
 criterion_main!(benches);
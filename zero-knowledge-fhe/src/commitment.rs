@@ -0,0 +1,148 @@
+//! Ciphertext commitments shared by the encrypted programs
+//!
+//! New accounts commit with the BN254 Poseidon syscall so on-chain hashes match
+//! what the circuits recompute; accounts written before the switch keep SHA-256.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::poseidon::{hashv, Endianness, Parameters},
+};
+use haunti_utils::serialization::EncodedVector;
+
+/// Bytes packed per field element; 31 bytes always fit below the BN254 modulus
+pub const POSEIDON_CHUNK_BYTES: usize = 31;
+/// Elements absorbed per call next to the running state (the syscall takes at most 12)
+const POSEIDON_RATE: usize = 11;
+
+/// Hash used for a stored commitment. Appended after the existing fields, so legacy
+/// accounts decode their zero padding as `Sha256`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommitmentScheme {
+    #[default]
+    Sha256,
+    /// circomlib parameters (x^5 S-box, BN254), matching `Poseidon(n)` in the circuits
+    Poseidon,
+}
+
+impl CommitmentScheme {
+    /// Scheme for newly written commitments
+    pub const CURRENT: Self = Self::Poseidon;
+
+    /// Commit to a single ciphertext
+    pub fn commit(&self, ciphertext: &EncodedVector) -> Result<[u8; 32]> {
+        self.commit_parts(&[&ciphertext.data, &ciphertext.metadata])
+    }
+
+    /// Commit to an ordered batch of ciphertexts
+    pub fn commit_batch(&self, ciphertexts: &[EncodedVector]) -> Result<[u8; 32]> {
+        let parts: Vec<&[u8]> = ciphertexts
+            .iter()
+            .flat_map(|c| [c.data.as_slice(), c.metadata.as_slice()])
+            .collect();
+        self.commit_parts(&parts)
+    }
+
+    fn commit_parts(&self, parts: &[&[u8]]) -> Result<[u8; 32]> {
+        match self {
+            Self::Sha256 => Ok(sha256_commit(parts)),
+            Self::Poseidon => poseidon_commit(parts),
+        }
+    }
+}
+
+/// Fold a Poseidon batch commitment into a running accumulator, as recomputed by
+/// the dataset policy circuit: `Poseidon(acc, commitment)`, starting from zero
+pub fn chain_commitment(acc: &[u8; 32], commitment: &[u8; 32]) -> Result<[u8; 32]> {
+    Ok(hashv(Parameters::Bn254X5, Endianness::BigEndian, &[acc, commitment])
+        .map_err(|_| ProgramError::InvalidArgument)?
+        .to_bytes())
+}
+
+fn sha256_commit(parts: &[&[u8]]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// Sponge-style chain over field elements: each part is prefixed by its length so
+/// boundaries cannot shift between parts, then packed into 31-byte chunks
+fn poseidon_commit(parts: &[&[u8]]) -> Result<[u8; 32]> {
+    let mut elements: Vec<[u8; 32]> = Vec::new();
+    for part in parts {
+        elements.push(field_element(&(part.len() as u64).to_be_bytes()));
+        elements.extend(part.chunks(POSEIDON_CHUNK_BYTES).map(field_element));
+    }
+
+    let mut state = [0u8; 32];
+    for block in elements.chunks(POSEIDON_RATE) {
+        let mut inputs: Vec<&[u8]> = Vec::with_capacity(block.len() + 1);
+        inputs.push(&state);
+        inputs.extend(block.iter().map(|e| e.as_slice()));
+        state = hashv(Parameters::Bn254X5, Endianness::BigEndian, &inputs)
+            .map_err(|_| ProgramError::InvalidArgument)?
+            .to_bytes();
+    }
+    Ok(state)
+}
+
+/// Big-endian field element holding `bytes` (at most 31 of them)
+fn field_element(bytes: &[u8]) -> [u8; 32] {
+    let mut element = [0u8; 32];
+    element[32 - bytes.len()..].copy_from_slice(bytes);
+    element
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(data: &[u8], metadata: &[u8]) -> EncodedVector {
+        EncodedVector {
+            data: data.to_vec(),
+            metadata: metadata.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_poseidon_commitment_binds_part_boundaries() {
+        let scheme = CommitmentScheme::Poseidon;
+        let a = scheme.commit(&vector(&[1, 2, 3], &[4])).unwrap();
+        assert_eq!(a, scheme.commit(&vector(&[1, 2, 3], &[4])).unwrap());
+        // Same bytes split differently must not collide
+        assert_ne!(a, scheme.commit(&vector(&[1, 2], &[3, 4])).unwrap());
+        assert_ne!(a, CommitmentScheme::Sha256.commit(&vector(&[1, 2, 3], &[4])).unwrap());
+
+        // Inputs longer than one absorb block still hash
+        let large = vector(&[7u8; 1024], &[]);
+        assert!(scheme.commit(&large).is_ok());
+        assert_eq!(
+            scheme.commit_batch(&[large.clone()]).unwrap(),
+            scheme.commit(&large).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_commitment_chain_is_order_sensitive() {
+        let scheme = CommitmentScheme::Poseidon;
+        let a = scheme.commit(&vector(b"batch-a", &[])).unwrap();
+        let b = scheme.commit(&vector(b"batch-b", &[])).unwrap();
+
+        let ab = chain_commitment(&chain_commitment(&[0; 32], &a).unwrap(), &b).unwrap();
+        let ba = chain_commitment(&chain_commitment(&[0; 32], &b).unwrap(), &a).unwrap();
+        assert_ne!(ab, ba);
+        assert_ne!(ab, [0; 32]);
+    }
+
+    #[test]
+    fn test_legacy_accounts_default_to_sha256() {
+        assert_eq!(CommitmentScheme::try_from_slice(&[0]).unwrap(), CommitmentScheme::Sha256);
+        assert_eq!(
+            CommitmentScheme::Sha256.commit(&vector(b"ab", b"c")).unwrap(),
+            sha256_commit(&[b"abc"])
+        );
+    }
+}
@@ -7,7 +7,10 @@ use anchor_lang::{
         sysvar::instructions,
     },
 };
-use anchor_spl::token::{self, Token, TokenAccount};
+use anchor_spl::{
+    token::{self, Token, TokenAccount},
+    token_interface::{self, Mint, TokenAccount as InterfaceTokenAccount, TokenInterface},
+};
 use haunti_utils::{
     fhe::{FheCiphertext, FhePublicKey, FheContext},
     serialization::EncodedVector,
@@ -15,6 +18,9 @@ use haunti_utils::{
 };
 use std::convert::TryInto;
 
+mod commitment;
+use commitment::CommitmentScheme;
+
 declare_id!("HaunINF111111111111111111111111111111111111");
 
 #[program]
@@ -27,6 +33,11 @@ pub mod encrypted_infer {
     /// 1. [SIGNER] creator: Task owner
     /// 2. [] model_account: Model NFT
     /// 3. [] fhe_params: Global FHE config
+    /// 4. [] model_nft_state: Model NFT state with the usage fee (same as model_account)
+    /// 5-8. [WRITE] fee accounts: payer token, usage escrow, fee mint, token program;
+    ///      required when the model charges a usage fee
+    /// 9. [] model_token: creator's model NFT token account, for holders
+    /// 10-11. [WRITE] license, model NFT program: the creator's license, for licensees
     pub fn create_inference_task(
         ctx: Context<CreateInferenceTask>,
         max_steps: u16,
@@ -44,6 +55,61 @@ pub mod encrypted_infer {
             InferError::UnsupportedModelOperation
         );
         
+        // Holders run the model freely; anyone else spends a use of an active license
+        let holds_model = ctx
+            .accounts
+            .model_token
+            .as_ref()
+            .is_some_and(|token| token.amount > 0);
+        if !holds_model {
+            let (Some(license), Some(model_nft_program)) = (
+                ctx.accounts.license.as_ref(),
+                ctx.accounts.model_nft_program.as_ref(),
+            ) else {
+                return err!(InferError::ModelAccessDenied);
+            };
+            haunti_nft::cpi::consume_license_use(CpiContext::new(
+                model_nft_program.to_account_info(),
+                haunti_nft::cpi::accounts::ConsumeLicenseUse {
+                    licensee: ctx.accounts.creator.to_account_info(),
+                    license: license.to_account_info(),
+                },
+            ))?;
+        }
+        
+        // Pay the model's usage fee into its escrow
+        if let Some(fee) = ctx.accounts.model_nft_state.usage_fee {
+            let (Some(payer_token), Some(usage_escrow), Some(fee_mint), Some(token_program)) = (
+                ctx.accounts.payer_token.as_ref(),
+                ctx.accounts.usage_escrow.as_ref(),
+                ctx.accounts.fee_mint.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) else {
+                return err!(InferError::UsageFeeAccountsMissing);
+            };
+            require_keys_eq!(fee_mint.key(), fee.fee_mint, InferError::UsageFeeAccountsMissing);
+            
+            let cpi_ctx = CpiContext::new(
+                token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: payer_token.to_account_info(),
+                    mint: fee_mint.to_account_info(),
+                    to: usage_escrow.to_account_info(),
+                    authority: ctx.accounts.creator.to_account_info(),
+                },
+            );
+            token_interface::transfer_checked(cpi_ctx, fee.fee_per_inference, fee_mint.decimals)?;
+            task.usage_fee_paid = fee.fee_per_inference;
+            
+            emit!(UsageFeePaid {
+                task: task.key(),
+                model_mint: ctx.accounts.model_nft_state.mint,
+                payer: ctx.accounts.creator.key(),
+                amount: fee.fee_per_inference,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        
         Ok(())
     }
 
@@ -65,12 +131,14 @@ pub mod encrypted_infer {
         );
         
         // Verify input ownership and hash
-        let input_hash = compute_ciphertext_hash(&ciphertext);
+        let scheme = CommitmentScheme::CURRENT;
+        let input_hash = scheme.commit(&ciphertext)?;
         ctx.accounts.encrypted_input.set_inner(EncryptedInput {
             owner: ctx.accounts.input_provider.key(),
             task: task.key(),
             data_hash: input_hash,
             ciphertext,
+            commitment_scheme: scheme,
         });
         
         task.status = InferenceStatus::InputReady;
@@ -125,6 +193,22 @@ pub mod encrypted_infer {
         
         Ok(())
     }
+
+    /// Closes a completed or failed task, returning its rent to the creator
+    /// Accounts:
+    /// 0. [WRITE] inference_task: Task state
+    /// 1. [SIGNER] creator: Task owner
+    pub fn close_inference_task(ctx: Context<CloseInferenceTask>) -> Result<()> {
+        require!(
+            matches!(
+                ctx.accounts.inference_task.status,
+                InferenceStatus::Completed | InferenceStatus::Failed
+            ),
+            InferError::InvalidTaskState
+        );
+        
+        Ok(())
+    }
 }
 
 // Accounts ========================
@@ -152,6 +236,50 @@ pub struct CreateInferenceTask<'info> {
     #[account(executable, address = haunti_fhe::id())]
     pub fhe_params: AccountInfo<'info>,
     
+    #[account(
+        address = model_account.key(),
+        seeds = [b"model_state", model_nft_state.mint.as_ref()],
+        bump,
+        seeds::program = haunti_nft::id(),
+    )]
+    pub model_nft_state: Account<'info, haunti_nft::ModelState>,
+    
+    #[account(
+        mut,
+        token::mint = fee_mint,
+        token::authority = creator,
+        token::token_program = token_program,
+    )]
+    pub payer_token: Option<InterfaceAccount<'info, InterfaceTokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"usage_escrow", model_nft_state.mint.as_ref()],
+        bump,
+        seeds::program = haunti_nft::id(),
+    )]
+    pub usage_escrow: Option<InterfaceAccount<'info, InterfaceTokenAccount>>,
+    
+    pub fee_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    #[account(
+        constraint = model_token.mint == model_nft_state.mint @ InferError::ModelAccessDenied,
+        constraint = model_token.owner == creator.key() @ InferError::ModelAccessDenied,
+    )]
+    pub model_token: Option<InterfaceAccount<'info, InterfaceTokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"license", model_nft_state.mint.as_ref(), creator.key().as_ref()],
+        bump = license.bump,
+        seeds::program = haunti_nft::id(),
+    )]
+    pub license: Option<Account<'info, haunti_nft::ModelLicense>>,
+    
+    pub model_nft_program: Option<Program<'info, haunti_nft::program::ModelNft>>,
+    
     pub system_program: Program<'info, System>,
 }
 
@@ -178,6 +306,15 @@ pub struct SubmitEncryptedInput<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct CloseInferenceTask<'info> {
+    #[account(mut, close = creator, has_one = creator)]
+    pub inference_task: Account<'info, InferenceTask>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
 // States ==========================
 
 #[account]
@@ -189,6 +326,8 @@ pub struct InferenceTask {
     pub fhe_pubkey: Vec<u8>,
     pub max_steps: u16,
     pub completed_at: Option<i64>,
+    /// Usage fee paid to the model at creation
+    pub usage_fee_paid: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
@@ -206,6 +345,17 @@ pub struct EncryptedInput {
     pub task: Pubkey,
     pub data_hash: [u8; 32],
     pub ciphertext: EncodedVector,
+    /// Hash behind `data_hash`; `Sha256` for inputs submitted before the Poseidon switch
+    pub commitment_scheme: CommitmentScheme,
+}
+
+impl EncryptedInput {
+    /// Recompute `data_hash` under the scheme it was written with
+    pub fn verify_commitment(&self) -> Result<()> {
+        let expected = self.commitment_scheme.commit(&self.ciphertext)?;
+        require!(expected == self.data_hash, InferError::InputHashMismatch);
+        Ok(())
+    }
 }
 
 #[account]
@@ -216,6 +366,17 @@ pub struct InferenceResult {
     pub timestamp: i64,
 }
 
+// Events ==========================
+
+#[event]
+pub struct UsageFeePaid {
+    pub task: Pubkey,
+    pub model_mint: Pubkey,
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
 // Errors ==========================
 
 #[error_code]
@@ -230,15 +391,8 @@ pub enum InferError {
     InputHashMismatch,
     #[msg("Inference execution timeout")]
     ExecutionTimeout,
-}
-
-// Cryptographic Utilities =========
-
-fn compute_ciphertext_hash(ciphertext: &EncodedVector) -> [u8; 32] {
-    use sha2::{Digest, Sha256};
-    let mut hasher = Sha256::new();
-    hasher.update(&ciphertext.data);
-    hasher.update(&ciphertext.metadata);
-    let result = hasher.finalize();
-    result.into()
+    #[msg("Model charges a usage fee; fee accounts missing or wrong mint")]
+    UsageFeeAccountsMissing,
+    #[msg("Creator neither holds the model NFT nor has an active license")]
+    ModelAccessDenied,
 }
@@ -8,12 +8,17 @@ use anchor_lang::{
     },
 };
 use anchor_spl::token::{self, Token, TokenAccount};
+use haunti_nft::DatasetState;
 use haunti_utils::{
     fhe::{FheCiphertext, FhePublicKey, FheContext},
-    serialization::EncodedVector,
+    serialization::{deserialize_proof, EncodedVector},
+    zk::verify_plonky3_proof,
 };
 use std::convert::TryInto;
 
+mod commitment;
+use commitment::{chain_commitment, CommitmentScheme};
+
 declare_id!("HaunFHE111111111111111111111111111111111111");
 
 #[program]
@@ -30,6 +35,7 @@ pub mod encrypted_trainer {
         ctx: Context<CreateEncryptedTask>,
         epochs: u32,
         batch_size: u16,
+        data_policy: DataPolicy,
     ) -> Result<()> {
         let task = &mut ctx.accounts.training_task;
         task.creator = ctx.accounts.creator.key();
@@ -37,6 +43,8 @@ pub mod encrypted_trainer {
         task.fhe_pubkey = ctx.accounts.fhe_params.public_key.clone();
         task.status = TrainingStatus::Initialized;
         task.epochs_completed = 0;
+        task.data_policy = data_policy;
+        task.batch_accumulator = [0u8; 32];
         
         // Validate FHE compatibility
         require!(
@@ -64,12 +72,22 @@ pub mod encrypted_trainer {
             TrainerError::InvalidTaskState
         );
         
-        // 2. Verify encrypted data ownership
-        let data_hash = compute_ciphertext_hash(&ciphertexts);
+        // 2. Verify encrypted data ownership under the dataset's own scheme
+        let data_hash = ctx.accounts.encrypted_data.commitment_scheme.commit_batch(&ciphertexts)?;
         require!(
             ctx.accounts.encrypted_data.data_hash == data_hash,
             TrainerError::DataHashMismatch
         );
+
+        // 2b. Record the batch for the dataset policy proof; the circuit only
+        //     recomputes Poseidon commitments
+        if task.data_policy != DataPolicy::Unrestricted {
+            require!(
+                ctx.accounts.encrypted_data.commitment_scheme == CommitmentScheme::Poseidon,
+                TrainerError::LegacyDataCommitment
+            );
+            task.batch_accumulator = chain_commitment(&task.batch_accumulator, &data_hash)?;
+        }
         
         // 3. Execute FHE operations (simplified)
         let updated_weights = fhe_linear_layer_forward(
@@ -90,7 +108,11 @@ pub mod encrypted_trainer {
     /// 0. [WRITE] training_task: Task state
     /// 1. [SIGNER] creator: Task owner
     /// 2. [WRITE] trained_model: Output model account
-    pub fn finalize_training(ctx: Context<FinalizeTraining>) -> Result<()> {
+    /// 3. [] dataset_state: Root of the policy's dataset NFT, if the task has one
+    pub fn finalize_training(
+        ctx: Context<FinalizeTraining>,
+        data_proof: Option<Vec<u8>>,
+    ) -> Result<()> {
         let task = &mut ctx.accounts.training_task;
         
         // 1. Validate completion criteria
@@ -98,6 +120,33 @@ pub mod encrypted_trainer {
             task.epochs_completed >= task.epochs,
             TrainerError::TrainingIncomplete
         );
+
+        // 1b. Prove the processed batches satisfy the task's dataset policy
+        if let Some(dataset) = task.data_policy.dataset() {
+            let dataset_state = ctx
+                .accounts
+                .dataset_state
+                .as_ref()
+                .ok_or(TrainerError::DatasetMismatch)?;
+            require_keys_eq!(dataset_state.mint, dataset, TrainerError::DatasetMismatch);
+            let proof = data_proof.as_deref().ok_or(TrainerError::DataPolicyProofMissing)?;
+
+            verify_data_policy(
+                proof,
+                &dataset_state.data_root,
+                &task.batch_accumulator,
+                task.batches_processed,
+                matches!(task.data_policy, DataPolicy::Excluded(_)),
+            )?;
+
+            emit!(DataPolicyProven {
+                training_task: task.key(),
+                dataset,
+                excluded: matches!(task.data_policy, DataPolicy::Excluded(_)),
+                batches: task.batches_processed,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
         
         // 2. Generate training proof
         let proof = generate_training_proof(
@@ -168,6 +217,24 @@ pub struct ProcessEncryptedBatch<'info> {
     pub fhe_params: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct FinalizeTraining<'info> {
+    #[account(mut, has_one = creator)]
+    pub training_task: Account<'info, EncryptedTrainingTask>,
+
+    pub creator: Signer<'info>,
+
+    #[account(zero)]
+    pub trained_model: Account<'info, TrainedModel>,
+
+    #[account(
+        seeds = [b"dataset_state", dataset_state.mint.as_ref()],
+        bump = dataset_state.bump,
+        seeds::program = haunti_nft::id(),
+    )]
+    pub dataset_state: Option<Account<'info, DatasetState>>,
+}
+
 // States ==========================
 
 #[account]
@@ -182,6 +249,38 @@ pub struct EncryptedTrainingTask {
     pub epochs: u32,
     pub epochs_completed: u32,
     pub batches_processed: u32,
+    /// Which dataset the training data must (or must not) come from
+    pub data_policy: DataPolicy,
+    /// Poseidon chain over processed batch commitments, in order
+    pub batch_accumulator: [u8; 32],
+}
+
+/// Dataset licensing policy checked at `finalize_training`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DataPolicy {
+    #[default]
+    Unrestricted,
+    /// Every batch must belong to this dataset NFT
+    Licensed(Pubkey),
+    /// No batch may belong to this dataset NFT
+    Excluded(Pubkey),
+}
+
+impl DataPolicy {
+    /// Dataset NFT mint the policy refers to
+    pub fn dataset(&self) -> Option<Pubkey> {
+        match self {
+            Self::Unrestricted => None,
+            Self::Licensed(mint) | Self::Excluded(mint) => Some(*mint),
+        }
+    }
+}
+
+#[account]
+pub struct TrainedModel {
+    pub training_task: Pubkey,
+    pub weights: Vec<u8>,
+    pub proof: Vec<u8>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
@@ -198,6 +297,20 @@ pub struct EncryptedDataSet {
     pub training_task: Pubkey,
     pub data_hash: [u8; 32],
     pub ciphertexts: Vec<EncodedVector>,
+    /// Hash behind `data_hash`; `Sha256` for datasets registered before the Poseidon
+    /// switch, `CommitmentScheme::CURRENT` for new ones
+    pub commitment_scheme: CommitmentScheme,
+}
+
+// Events ==========================
+
+#[event]
+pub struct DataPolicyProven {
+    pub training_task: Pubkey,
+    pub dataset: Pubkey,
+    pub excluded: bool,
+    pub batches: u32,
+    pub timestamp: i64,
 }
 
 // Errors ==========================
@@ -214,6 +327,14 @@ pub enum TrainerError {
     DataHashMismatch,
     #[msg("Minimum epochs not completed")]
     TrainingIncomplete,
+    #[msg("Dataset policy requires Poseidon batch commitments")]
+    LegacyDataCommitment,
+    #[msg("Dataset account does not match the task's data policy")]
+    DatasetMismatch,
+    #[msg("Dataset policy proof missing")]
+    DataPolicyProofMissing,
+    #[msg("Dataset policy proof failed verification")]
+    DataPolicyProofInvalid,
 }
 
 // FHE Operations =================
@@ -228,6 +349,27 @@ fn fhe_linear_layer_forward(
     Ok(weights.to_vec())
 }
 
+/// Verify a `dataset_membership` circuit proof. Public inputs, big-endian field
+/// elements: `[dataset_root, batch_accumulator, batch_count, exclude]`.
+fn verify_data_policy(
+    proof: &[u8],
+    data_root: &[u8; 32],
+    batch_accumulator: &[u8; 32],
+    batch_count: u32,
+    exclude: bool,
+) -> Result<()> {
+    let mut count = [0u8; 32];
+    count[28..].copy_from_slice(&batch_count.to_be_bytes());
+    let mut flag = [0u8; 32];
+    flag[31] = exclude as u8;
+    let public_inputs = [*data_root, *batch_accumulator, count, flag];
+
+    let proof = deserialize_proof(proof).map_err(|_| TrainerError::DataPolicyProofInvalid)?;
+    verify_plonky3_proof(&proof, &public_inputs, data_root)
+        .map_err(|_| TrainerError::DataPolicyProofInvalid)?;
+    Ok(())
+}
+
 fn generate_training_proof(
     initial_weights: &[u8],
     final_weights: &[u8],
@@ -0,0 +1,230 @@
+pragma circom 2.1.6;
+
+/*
+Haunti Dataset Policy Circuit
+Proves which data a training run consumed without revealing the batches:
+- Licensed mode: every batch commitment is a leaf of the licensed dataset's
+  merkle root
+- Excluded mode: no batch commitment is a leaf of a forbidden dataset's root
+
+Batch commitments are the Poseidon `data_hash` values checked by
+`process_encrypted_batch`. The trainer program folds them in order into
+`batch_accumulator = Poseidon(acc, data_hash)` starting from zero; this circuit
+recomputes that chain so the proof is bound to the batches actually processed.
+
+Dataset trees are Poseidon(2) trees over the dataset's batch commitments, sorted
+ascending so that exclusion can be shown with two adjacent leaves; they start
+and end with the sentinels 0 and p - 1 so every commitment has neighbours.
+
+Public inputs: [dataset_root, batch_accumulator, batch_count, exclude]
+*/
+
+include "node_modules/circomlib/circuits/comparators.circom";
+include "node_modules/circomlib/circuits/bitify.circom";
+include "node_modules/circomlib/circuits/poseidon.circom";
+
+// Root of the tree containing `leaf` at the position given by `path_bits`
+template MerkleRoot(depth) {
+    signal input leaf;
+    signal input siblings[depth];
+    signal input path_bits[depth];  // 0 = node is the left child
+    signal output root;
+
+    component hashers[depth];
+    signal nodes[depth + 1];
+    signal left[depth];
+    signal right[depth];
+    nodes[0] <== leaf;
+
+    for (var i = 0; i < depth; i++) {
+        path_bits[i] * (1 - path_bits[i]) === 0;
+
+        left[i] <== nodes[i] + path_bits[i] * (siblings[i] - nodes[i]);
+        right[i] <== siblings[i] + path_bits[i] * (nodes[i] - siblings[i]);
+
+        hashers[i] = Poseidon(2);
+        hashers[i].inputs[0] <== left[i];
+        hashers[i].inputs[1] <== right[i];
+        nodes[i + 1] <== hashers[i].out;
+    }
+
+    root <== nodes[depth];
+}
+
+// Leaf index encoded by the path bits
+template PathIndex(depth) {
+    signal input path_bits[depth];
+    signal output index;
+
+    var acc = 0;
+    for (var i = 0; i < depth; i++) {
+        acc += path_bits[i] * (1 << i);
+    }
+    index <== acc;
+}
+
+// a < b over full BN254 field elements: alias-checked bits, compared as two
+// 127-bit halves since LessThan is limited to 252 bits
+template FieldLessThan() {
+    signal input a;
+    signal input b;
+    signal output out;
+
+    component a_bits = Num2Bits_strict();
+    component b_bits = Num2Bits_strict();
+    a_bits.in <== a;
+    b_bits.in <== b;
+
+    component a_lo = Bits2Num(127);
+    component a_hi = Bits2Num(127);
+    component b_lo = Bits2Num(127);
+    component b_hi = Bits2Num(127);
+    for (var i = 0; i < 127; i++) {
+        a_lo.in[i] <== a_bits.out[i];
+        a_hi.in[i] <== a_bits.out[i + 127];
+        b_lo.in[i] <== b_bits.out[i];
+        b_hi.in[i] <== b_bits.out[i + 127];
+    }
+
+    component hi_lt = LessThan(127);
+    hi_lt.in[0] <== a_hi.out;
+    hi_lt.in[1] <== b_hi.out;
+    component hi_eq = IsEqual();
+    hi_eq.in[0] <== a_hi.out;
+    hi_eq.in[1] <== b_hi.out;
+    component lo_lt = LessThan(127);
+    lo_lt.in[0] <== a_lo.out;
+    lo_lt.in[1] <== b_lo.out;
+
+    signal lo_decides;
+    lo_decides <== hi_eq.out * lo_lt.out;
+    out <== hi_lt.out + lo_decides;
+}
+
+// `leaf` lies strictly between two adjacent leaves of a sorted tree, so it is
+// not a leaf itself
+template NonMembership(depth) {
+    signal input leaf;
+    signal input low_leaf;
+    signal input low_siblings[depth];
+    signal input low_path[depth];
+    signal input high_leaf;
+    signal input high_siblings[depth];
+    signal input high_path[depth];
+    signal output low_root;
+    signal output high_root;
+    signal output between;
+
+    component low = MerkleRoot(depth);
+    low.leaf <== low_leaf;
+    low.siblings <== low_siblings;
+    low.path_bits <== low_path;
+    low_root <== low.root;
+
+    component high = MerkleRoot(depth);
+    high.leaf <== high_leaf;
+    high.siblings <== high_siblings;
+    high.path_bits <== high_path;
+    high_root <== high.root;
+
+    component low_index = PathIndex(depth);
+    low_index.path_bits <== low_path;
+    component high_index = PathIndex(depth);
+    high_index.path_bits <== high_path;
+    component adjacent = IsEqual();
+    adjacent.in[0] <== low_index.index + 1;
+    adjacent.in[1] <== high_index.index;
+
+    component above_low = FieldLessThan();
+    above_low.a <== low_leaf;
+    above_low.b <== leaf;
+    component below_high = FieldLessThan();
+    below_high.a <== leaf;
+    below_high.b <== high_leaf;
+
+    signal bounded;
+    bounded <== above_low.out * below_high.out;
+    between <== bounded * adjacent.out;
+}
+
+// Up to `max_batches` batches against a tree of `depth` levels; slots at or past
+// `batch_count` are ignored
+template DatasetPolicy(max_batches, depth) {
+    signal input dataset_root;
+    signal input batch_accumulator;
+    signal input batch_count;
+    signal input exclude;
+
+    signal input batches[max_batches];
+    // Licensed mode: inclusion path for each batch
+    signal input siblings[max_batches][depth];
+    signal input path_bits[max_batches][depth];
+    // Excluded mode: adjacent neighbours bracketing each batch
+    signal input low_leaves[max_batches];
+    signal input low_siblings[max_batches][depth];
+    signal input low_paths[max_batches][depth];
+    signal input high_leaves[max_batches];
+    signal input high_siblings[max_batches][depth];
+    signal input high_paths[max_batches][depth];
+
+    exclude * (1 - exclude) === 0;
+
+    component count_ok = LessEqThan(32);
+    count_ok.in[0] <== batch_count;
+    count_ok.in[1] <== max_batches;
+    count_ok.out === 1;
+
+    component active[max_batches];
+    component chain[max_batches];
+    component member[max_batches];
+    component outsider[max_batches];
+    signal acc[max_batches + 1];
+    signal step[max_batches];
+    signal check_member[max_batches];
+    signal check_outsider[max_batches];
+    signal check_low[max_batches];
+    signal check_high[max_batches];
+    signal check_between[max_batches];
+    acc[0] <== 0;
+
+    for (var i = 0; i < max_batches; i++) {
+        active[i] = LessThan(32);
+        active[i].in[0] <== i;
+        active[i].in[1] <== batch_count;
+
+        // Fold active batches into the accumulator in processing order
+        chain[i] = Poseidon(2);
+        chain[i].inputs[0] <== acc[i];
+        chain[i].inputs[1] <== batches[i];
+        step[i] <== active[i].out * (chain[i].out - acc[i]);
+        acc[i + 1] <== acc[i] + step[i];
+
+        member[i] = MerkleRoot(depth);
+        member[i].leaf <== batches[i];
+        member[i].siblings <== siblings[i];
+        member[i].path_bits <== path_bits[i];
+        check_member[i] <== active[i].out * (1 - exclude);
+        check_member[i] * (member[i].root - dataset_root) === 0;
+
+        outsider[i] = NonMembership(depth);
+        outsider[i].leaf <== batches[i];
+        outsider[i].low_leaf <== low_leaves[i];
+        outsider[i].low_siblings <== low_siblings[i];
+        outsider[i].low_path <== low_paths[i];
+        outsider[i].high_leaf <== high_leaves[i];
+        outsider[i].high_siblings <== high_siblings[i];
+        outsider[i].high_path <== high_paths[i];
+        check_outsider[i] <== active[i].out * exclude;
+        check_low[i] <== check_outsider[i] * (outsider[i].low_root - dataset_root);
+        check_low[i] === 0;
+        check_high[i] <== check_outsider[i] * (outsider[i].high_root - dataset_root);
+        check_high[i] === 0;
+        check_between[i] <== check_outsider[i] * (1 - outsider[i].between);
+        check_between[i] === 0;
+    }
+
+    acc[max_batches] === batch_accumulator;
+}
+
+component main {public [dataset_root, batch_accumulator, batch_count, exclude]} =
+    DatasetPolicy(256, 20);
@@ -0,0 +1,223 @@
+//! Compression of Plonky3 proofs into Groth16/BN254 proofs for on-chain checks
+//!
+//! FRI proofs are hundreds of kilobytes and far too expensive to verify within a
+//! Solana transaction. `wrap_to_groth16` runs two more proving steps:
+//!
+//! 1. a recursion layer re-proves the task proof with Poseidon over BN254 as the
+//!    FRI hash, which is cheap to verify inside an R1CS circuit;
+//! 2. a Groth16 proof over BN254 that the layer's proof verifies.
+//!
+//! The result is 256 bytes that `verify_ai_proof_groth16` checks with the
+//! `alt_bn128` syscalls. Public inputs go through unchanged: the Groth16 circuit
+//! exposes each 32-byte input as two 128-bit limbs, high limb first, the split
+//! the on-chain verifier applies.
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_snark::SNARK;
+use plonky3::{
+    iop::witness::{PartialWitness, WitnessWrite},
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        circuit_data::CircuitData,
+        config::PoseidonBN128GoldilocksConfig,
+        proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget},
+    },
+    recursion::recursive_circuit::add_virtual_recursive_proof,
+    wrapper::r1cs::Plonky3VerifierR1cs,
+};
+use rand::{CryptoRng, RngCore};
+
+use super::{C, D, F};
+
+/// Config of the recursion layer; its FRI hash is Poseidon over BN254
+type Bn254Config = PoseidonBN128GoldilocksConfig;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WrapError {
+    #[error("Recursion layer failed: {0}")]
+    Recursion(String),
+    #[error("Groth16 proving failed: {0}")]
+    Groth16(#[from] ark_relations::r1cs::SynthesisError),
+    #[error("Proof has {actual} public inputs, the wrapper expects {expected}")]
+    PublicInputs { expected: usize, actual: usize },
+}
+
+/// Groth16 proof in the encoding `verify_ai_proof_groth16` takes: `a || b || c`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrappedProof {
+    pub proof_data: [u8; 256],
+    /// Public inputs of the wrapped proof, unchanged
+    pub public_inputs: Vec<[u8; 32]>,
+}
+
+/// Arguments of `init_groth16_key` after the version, for this wrapper's
+/// verifying key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Groth16KeyBytes {
+    pub alpha_g1: [u8; 64],
+    pub beta_g2: [u8; 128],
+    pub gamma_g2: [u8; 128],
+    pub delta_g2: [u8; 128],
+    pub ic: Vec<[u8; 64]>,
+}
+
+/// Recursion layer and Groth16 keys for one inner circuit
+pub struct Groth16Wrapper {
+    layer: CircuitData<F, Bn254Config, D>,
+    layer_proof: ProofWithPublicInputsTarget<D>,
+    proving_key: ProvingKey<Bn254>,
+    /// Public inputs of the inner circuit, each 32 bytes
+    inputs: usize,
+}
+
+impl Groth16Wrapper {
+    /// Build the recursion layer for `inner` and run the Groth16 setup for its
+    /// verifier. The setup is circuit specific: a new inner circuit needs a new
+    /// wrapper and a new on-chain key.
+    pub fn setup<R: RngCore + CryptoRng>(
+        inner: &CircuitData<F, C, D>,
+        inputs: usize,
+        rng: &mut R,
+    ) -> Result<Self, WrapError> {
+        let mut builder = CircuitBuilder::<F, D>::new();
+        let layer_proof = add_virtual_recursive_proof(&mut builder, inner.common.clone());
+        builder.verify_proof::<C>(&layer_proof, &inner.common, &inner.verifier_only);
+        builder.register_public_inputs(&layer_proof.public_inputs);
+        let layer = builder.build::<Bn254Config>();
+
+        let circuit = Plonky3VerifierR1cs::<Fr>::setup(&layer.common, &layer.verifier_only, 2 * inputs);
+        let (proving_key, _) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng)?;
+
+        Ok(Self { layer, layer_proof, proving_key, inputs })
+    }
+
+    /// Compress `proof` of the inner circuit; `public_inputs` are the 32-byte
+    /// inputs it was generated for, as `verify_ai_proof` would receive them
+    pub fn wrap_to_groth16<R: RngCore + CryptoRng>(
+        &self,
+        proof: &ProofWithPublicInputs<F, C, D>,
+        public_inputs: &[[u8; 32]],
+        rng: &mut R,
+    ) -> Result<WrappedProof, WrapError> {
+        if public_inputs.len() != self.inputs {
+            return Err(WrapError::PublicInputs { expected: self.inputs, actual: public_inputs.len() });
+        }
+
+        let mut witness = PartialWitness::new();
+        witness.set_proof(&self.layer_proof, proof);
+        let layer_proof = self
+            .layer
+            .prove(witness)
+            .map_err(|e| WrapError::Recursion(e.to_string()))?;
+
+        let circuit = Plonky3VerifierR1cs::<Fr>::with_proof(
+            &self.layer.common,
+            &self.layer.verifier_only,
+            &layer_proof,
+            public_limbs(public_inputs),
+        );
+        let groth16 = Groth16::<Bn254>::prove(&self.proving_key, circuit, rng)?;
+
+        Ok(WrappedProof {
+            proof_data: proof_bytes(&groth16),
+            public_inputs: public_inputs.to_vec(),
+        })
+    }
+
+    pub fn verifying_key(&self) -> &VerifyingKey<Bn254> {
+        &self.proving_key.vk
+    }
+
+    /// The verifying key as `init_groth16_key` arguments
+    pub fn key_bytes(&self) -> Groth16KeyBytes {
+        let vk = self.verifying_key();
+        Groth16KeyBytes {
+            alpha_g1: g1_bytes(&vk.alpha_g1),
+            beta_g2: g2_bytes(&vk.beta_g2),
+            gamma_g2: g2_bytes(&vk.gamma_g2),
+            delta_g2: g2_bytes(&vk.delta_g2),
+            ic: vk.gamma_abc_g1.iter().map(g1_bytes).collect(),
+        }
+    }
+}
+
+/// Scalars the wrapper exposes: each input's high then low 128 bits
+pub fn public_limbs(public_inputs: &[[u8; 32]]) -> Vec<Fr> {
+    public_inputs
+        .iter()
+        .flat_map(|input| [&input[..16], &input[16..]])
+        .map(Fr::from_be_bytes_mod_order)
+        .collect()
+}
+
+fn fq_bytes(value: &Fq) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes.copy_from_slice(&value.into_bigint().to_bytes_be());
+    bytes
+}
+
+fn fq2_bytes(value: &Fq2) -> [u8; 64] {
+    let mut bytes = [0; 64];
+    bytes[..32].copy_from_slice(&fq_bytes(&value.c1));
+    bytes[32..].copy_from_slice(&fq_bytes(&value.c0));
+    bytes
+}
+
+/// `x || y` big-endian; the point at infinity is all zeros
+pub fn g1_bytes(point: &G1Affine) -> [u8; 64] {
+    let mut bytes = [0; 64];
+    if !point.infinity {
+        bytes[..32].copy_from_slice(&fq_bytes(&point.x));
+        bytes[32..].copy_from_slice(&fq_bytes(&point.y));
+    }
+    bytes
+}
+
+/// `x.c1 || x.c0 || y.c1 || y.c0` big-endian, the `alt_bn128` G2 encoding
+pub fn g2_bytes(point: &G2Affine) -> [u8; 128] {
+    let mut bytes = [0; 128];
+    if !point.infinity {
+        bytes[..64].copy_from_slice(&fq2_bytes(&point.x));
+        bytes[64..].copy_from_slice(&fq2_bytes(&point.y));
+    }
+    bytes
+}
+
+fn proof_bytes(proof: &Proof<Bn254>) -> [u8; 256] {
+    let mut bytes = [0; 256];
+    bytes[..64].copy_from_slice(&g1_bytes(&proof.a));
+    bytes[64..192].copy_from_slice(&g2_bytes(&proof.b));
+    bytes[192..].copy_from_slice(&g1_bytes(&proof.c));
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::AffineRepr;
+
+    #[test]
+    fn test_limbs_split_high_first() {
+        let mut input = [0u8; 32];
+        input[15] = 3;
+        input[31] = 5;
+        assert_eq!(public_limbs(&[input]), vec![Fr::from(3u64), Fr::from(5u64)]);
+    }
+
+    #[test]
+    fn test_points_use_syscall_encoding() {
+        // The BN254 G1 generator is (1, 2)
+        let bytes = g1_bytes(&G1Affine::generator());
+        assert_eq!(bytes[31], 1);
+        assert_eq!(bytes[63], 2);
+        assert!(bytes[..31].iter().chain(&bytes[32..63]).all(|&b| b == 0));
+        assert_eq!(g1_bytes(&G1Affine::zero()), [0; 64]);
+
+        let generator = G2Affine::generator();
+        let bytes = g2_bytes(&generator);
+        assert_eq!(bytes[..32], fq_bytes(&generator.x.c1));
+        assert_eq!(bytes[96..], fq_bytes(&generator.y.c0));
+    }
+}
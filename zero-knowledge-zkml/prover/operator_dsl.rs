@@ -0,0 +1,640 @@
+//! Declarative zkML operator descriptions and their compiler into circuit constraints
+//!
+//! Community operators ship as a JSON description instead of circuit-builder code:
+//!
+//! ```json
+//! {
+//!   "name": "relu8",
+//!   "version": 1,
+//!   "inputs":  [{ "name": "x", "len": 16 }],
+//!   "outputs": [{ "name": "y", "len": 16 }],
+//!   "lookups": [{ "name": "relu", "pairs": [[0, 0], [1, 1], [255, 0]] }],
+//!   "constraints": [{ "for": "i", "over": "y", "rule": "y[i] = relu(x[i])" }]
+//! }
+//! ```
+//!
+//! A rule is either a definition `out[idx] = expr`, which binds an output element
+//! to the constrained value, or an assertion `expr == expr`. Expressions use `+`,
+//! `-`, `*`, integer constants, tensor elements `t[i]`, `t[i+1]`, `t[3]`, and
+//! lookups `table(expr)`. Every output element must be defined exactly once.
+//!
+//! An operator is identified by the SHA-256 of its canonical JSON encoding; that
+//! hash is what haunti-core's operator registry and circuit descriptors store.
+
+use plonky3::{
+    field::types::Field,
+    iop::target::Target,
+    plonk::circuit_builder::CircuitBuilder,
+};
+use serde::{Deserialize, Serialize};
+use solana_program::hash::hash;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use super::{D, F};
+
+/// Largest tensor an operator may declare
+pub const MAX_TENSOR_LEN: usize = 1 << 16;
+/// Largest lookup table an operator may declare
+pub const MAX_LOOKUP_PAIRS: usize = 1 << 16;
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum DslError {
+    #[error("Invalid operator JSON: {0}")]
+    Json(String),
+    #[error("Syntax error in `{rule}` at column {column}: {reason}")]
+    Syntax { rule: String, column: usize, reason: &'static str },
+    #[error("Duplicate name `{0}`")]
+    DuplicateName(String),
+    #[error("Unknown tensor `{0}`")]
+    UnknownTensor(String),
+    #[error("Unknown lookup table `{0}`")]
+    UnknownTable(String),
+    #[error("Unknown index variable `{0}`")]
+    UnknownIndex(String),
+    #[error("Rule `{0}` needs both `for` and `over`, or neither")]
+    UnboundIndex(String),
+    #[error("Tensor `{0}` exceeds the size limit")]
+    TensorTooLarge(String),
+    #[error("Lookup table `{0}` is empty or exceeds the size limit")]
+    InvalidTable(String),
+    #[error("Index {index} out of bounds for `{tensor}`")]
+    IndexOutOfBounds { tensor: String, index: i64 },
+    #[error("Only outputs can be defined, `{0}` is an input")]
+    DefinesInput(String),
+    #[error("Output element `{tensor}[{index}]` defined twice")]
+    Redefined { tensor: String, index: usize },
+    #[error("Output element `{tensor}[{index}]` is never defined")]
+    Undefined { tensor: String, index: usize },
+    #[error("Input `{name}` has {actual} targets, expected {expected}")]
+    InputLength { name: String, expected: usize, actual: usize },
+    #[error("Operator {} is not registered", hex::encode(.0))]
+    Unregistered([u8; 32]),
+}
+
+/// A named, fixed-length vector of field elements
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TensorSpec {
+    pub name: String,
+    pub len: usize,
+}
+
+/// Input/output pairs a lookup argument accepts
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LookupSpec {
+    pub name: String,
+    pub pairs: Vec<(u16, u16)>,
+}
+
+/// One rule, instantiated for every index of `over` when `for` names a variable
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintTemplate {
+    #[serde(rename = "for", default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub over: Option<String>,
+    pub rule: String,
+}
+
+/// Declarative description of a zkML operator
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OperatorSpec {
+    pub name: String,
+    pub version: u32,
+    pub inputs: Vec<TensorSpec>,
+    pub outputs: Vec<TensorSpec>,
+    #[serde(default)]
+    pub lookups: Vec<LookupSpec>,
+    pub constraints: Vec<ConstraintTemplate>,
+}
+
+impl OperatorSpec {
+    /// Parse and validate a JSON description
+    pub fn from_json(raw: &str) -> Result<Self, DslError> {
+        let spec: Self = serde_json::from_str(raw).map_err(|e| DslError::Json(e.to_string()))?;
+        spec.rules()?;
+        Ok(spec)
+    }
+
+    /// Registry key: SHA-256 of the canonical JSON encoding
+    pub fn hash(&self) -> [u8; 32] {
+        let canonical = serde_json::to_vec(self).expect("operator specs always serialize");
+        hash(&canonical).to_bytes()
+    }
+
+    /// Add the operator's constraints to `builder`, wired to `inputs`; returns the
+    /// output targets by name
+    pub fn compile(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        inputs: &HashMap<String, Vec<Target>>,
+    ) -> Result<HashMap<String, Vec<Target>>, DslError> {
+        let rules = self.rules()?;
+
+        let mut tensors: HashMap<&str, Vec<Option<Target>>> = HashMap::new();
+        for spec in &self.inputs {
+            let targets = inputs
+                .get(&spec.name)
+                .ok_or_else(|| DslError::UnknownTensor(spec.name.clone()))?;
+            if targets.len() != spec.len {
+                return Err(DslError::InputLength {
+                    name: spec.name.clone(),
+                    expected: spec.len,
+                    actual: targets.len(),
+                });
+            }
+            tensors.insert(&spec.name, targets.iter().copied().map(Some).collect());
+        }
+        for spec in &self.outputs {
+            tensors.insert(&spec.name, vec![None; spec.len]);
+        }
+        let tables: HashMap<&str, usize> = self
+            .lookups
+            .iter()
+            .map(|t| {
+                let index = builder.add_lookup_table_from_pairs(Arc::new(t.pairs.clone()));
+                (t.name.as_str(), index)
+            })
+            .collect();
+
+        for (template, rule) in self.constraints.iter().zip(&rules) {
+            let range = match &template.over {
+                Some(over) => 0..self.tensor_len(over)? as i64,
+                None => 0..1,
+            };
+            for i in range {
+                let mut ctx = Lowering {
+                    builder: &mut *builder,
+                    tensors: &tensors,
+                    tables: &tables,
+                    i,
+                };
+                match rule {
+                    Rule::Define { tensor, index, value } => {
+                        let value = ctx.lower(value)?;
+                        let slot = resolve(&tensors, tensor, index, i)?;
+                        let element = &mut tensors.get_mut(tensor.as_str()).unwrap()[slot];
+                        if element.is_some() {
+                            return Err(DslError::Redefined { tensor: tensor.clone(), index: slot });
+                        }
+                        *element = Some(value);
+                    }
+                    Rule::Assert(lhs, rhs) => {
+                        let (lhs, rhs) = (ctx.lower(lhs)?, ctx.lower(rhs)?);
+                        builder.connect(lhs, rhs);
+                    }
+                }
+            }
+        }
+
+        self.outputs
+            .iter()
+            .map(|spec| {
+                let targets = tensors[spec.name.as_str()]
+                    .iter()
+                    .enumerate()
+                    .map(|(index, t)| {
+                        t.ok_or_else(|| DslError::Undefined { tensor: spec.name.clone(), index })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((spec.name.clone(), targets))
+            })
+            .collect()
+    }
+
+    /// Parse every rule and check names, sizes and index variables
+    fn rules(&self) -> Result<Vec<Rule>, DslError> {
+        let mut names = HashSet::new();
+        for tensor in self.inputs.iter().chain(&self.outputs) {
+            if !names.insert(tensor.name.as_str()) {
+                return Err(DslError::DuplicateName(tensor.name.clone()));
+            }
+            if tensor.len == 0 || tensor.len > MAX_TENSOR_LEN {
+                return Err(DslError::TensorTooLarge(tensor.name.clone()));
+            }
+        }
+        for table in &self.lookups {
+            if !names.insert(table.name.as_str()) {
+                return Err(DslError::DuplicateName(table.name.clone()));
+            }
+            if table.pairs.is_empty() || table.pairs.len() > MAX_LOOKUP_PAIRS {
+                return Err(DslError::InvalidTable(table.name.clone()));
+            }
+        }
+
+        self.constraints
+            .iter()
+            .map(|template| {
+                // An index variable needs a tensor to range over, and vice versa
+                if template.index.is_some() != template.over.is_some() {
+                    return Err(DslError::UnboundIndex(template.rule.clone()));
+                }
+                if let Some(over) = &template.over {
+                    self.tensor_len(over)?;
+                }
+                let rule = Parser::new(&template.rule).rule()?;
+                rule.check(self, template.index.as_deref())?;
+                Ok(rule)
+            })
+            .collect()
+    }
+
+    fn tensor_len(&self, name: &str) -> Result<usize, DslError> {
+        self.inputs
+            .iter()
+            .chain(&self.outputs)
+            .find(|t| t.name == name)
+            .map(|t| t.len)
+            .ok_or_else(|| DslError::UnknownTensor(name.to_string()))
+    }
+
+    fn is_output(&self, name: &str) -> bool {
+        self.outputs.iter().any(|t| t.name == name)
+    }
+}
+
+/// Community operators available to the prover, keyed by `OperatorSpec::hash`
+#[derive(Default)]
+pub struct OperatorRegistry {
+    operators: HashMap<[u8; 32], OperatorSpec>,
+}
+
+impl OperatorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validate and add an operator; returns its hash
+    pub fn register(&mut self, spec: OperatorSpec) -> Result<[u8; 32], DslError> {
+        spec.rules()?;
+        let hash = spec.hash();
+        self.operators.insert(hash, spec);
+        Ok(hash)
+    }
+
+    pub fn get(&self, hash: &[u8; 32]) -> Option<&OperatorSpec> {
+        self.operators.get(hash)
+    }
+
+    /// Look up every operator of a circuit descriptor, in order
+    pub fn resolve(&self, hashes: &[[u8; 32]]) -> Result<Vec<&OperatorSpec>, DslError> {
+        hashes
+            .iter()
+            .map(|h| self.get(h).ok_or(DslError::Unregistered(*h)))
+            .collect()
+    }
+}
+
+// Rule language ===================
+
+#[derive(Clone, Debug, PartialEq)]
+enum Index {
+    /// Fixed element
+    Const(i64),
+    /// Template variable plus an offset
+    Var(String, i64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Const(u64),
+    Element(String, Index),
+    Lookup(String, Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Rule {
+    Define { tensor: String, index: Index, value: Expr },
+    Assert(Expr, Expr),
+}
+
+impl Rule {
+    fn check(&self, spec: &OperatorSpec, var: Option<&str>) -> Result<(), DslError> {
+        match self {
+            Rule::Define { tensor, index, value } => {
+                if !spec.is_output(tensor) {
+                    spec.tensor_len(tensor)?;
+                    return Err(DslError::DefinesInput(tensor.clone()));
+                }
+                check_index(index, var)?;
+                check_expr(value, spec, var)
+            }
+            Rule::Assert(lhs, rhs) => {
+                check_expr(lhs, spec, var)?;
+                check_expr(rhs, spec, var)
+            }
+        }
+    }
+}
+
+fn check_index(index: &Index, var: Option<&str>) -> Result<(), DslError> {
+    match index {
+        Index::Var(name, _) if Some(name.as_str()) != var => {
+            Err(DslError::UnknownIndex(name.clone()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_expr(expr: &Expr, spec: &OperatorSpec, var: Option<&str>) -> Result<(), DslError> {
+    match expr {
+        Expr::Const(_) => Ok(()),
+        Expr::Element(tensor, index) => {
+            spec.tensor_len(tensor)?;
+            check_index(index, var)
+        }
+        Expr::Lookup(table, arg) => {
+            if !spec.lookups.iter().any(|t| &t.name == table) {
+                return Err(DslError::UnknownTable(table.clone()));
+            }
+            check_expr(arg, spec, var)
+        }
+        Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) => {
+            check_expr(a, spec, var)?;
+            check_expr(b, spec, var)
+        }
+    }
+}
+
+/// Concrete element index of `tensor[index]` for template iteration `i`
+fn resolve(
+    tensors: &HashMap<&str, Vec<Option<Target>>>,
+    tensor: &str,
+    index: &Index,
+    i: i64,
+) -> Result<usize, DslError> {
+    let at = match index {
+        Index::Const(c) => *c,
+        Index::Var(_, offset) => i + offset,
+    };
+    let len = tensors.get(tensor).map_or(0, Vec::len);
+    usize::try_from(at)
+        .ok()
+        .filter(|at| *at < len)
+        .ok_or_else(|| DslError::IndexOutOfBounds { tensor: tensor.to_string(), index: at })
+}
+
+struct Lowering<'a, 'b> {
+    builder: &'a mut CircuitBuilder<F, D>,
+    tensors: &'b HashMap<&'b str, Vec<Option<Target>>>,
+    tables: &'b HashMap<&'b str, usize>,
+    i: i64,
+}
+
+impl Lowering<'_, '_> {
+    fn lower(&mut self, expr: &Expr) -> Result<Target, DslError> {
+        Ok(match expr {
+            Expr::Const(c) => self.builder.constant(F::from_canonical_u64(*c)),
+            Expr::Element(tensor, index) => {
+                let slot = resolve(self.tensors, tensor, index, self.i)?;
+                // Outputs can be read once an earlier rule defined them
+                self.tensors[tensor.as_str()][slot].ok_or_else(|| DslError::Undefined {
+                    tensor: tensor.clone(),
+                    index: slot,
+                })?
+            }
+            Expr::Lookup(table, arg) => {
+                let arg = self.lower(arg)?;
+                self.builder.add_lookup_from_index(arg, self.tables[table.as_str()])
+            }
+            Expr::Add(a, b) => {
+                let (a, b) = (self.lower(a)?, self.lower(b)?);
+                self.builder.add(a, b)
+            }
+            Expr::Sub(a, b) => {
+                let (a, b) = (self.lower(a)?, self.lower(b)?);
+                self.builder.sub(a, b)
+            }
+            Expr::Mul(a, b) => {
+                let (a, b) = (self.lower(a)?, self.lower(b)?);
+                self.builder.mul(a, b)
+            }
+        })
+    }
+}
+
+/// Recursive-descent parser for a single rule
+///
+/// ```text
+/// rule   := element '=' expr | expr '==' expr
+/// expr   := term (('+' | '-') term)*
+/// term   := factor ('*' factor)*
+/// factor := number | ident '[' index ']' | ident '(' expr ')' | '(' expr ')'
+/// index  := number | ident (('+' | '-') number)?
+/// ```
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn rule(mut self) -> Result<Rule, DslError> {
+        let lhs = self.expr()?;
+        let rule = if self.eat("==") {
+            Rule::Assert(lhs, self.expr()?)
+        } else if self.eat("=") {
+            match lhs {
+                Expr::Element(tensor, index) => Rule::Define { tensor, index, value: self.expr()? },
+                _ => return Err(self.error("only a tensor element can be defined")),
+            }
+        } else {
+            return Err(self.error("expected `=` or `==`"));
+        };
+        self.skip_ws();
+        if self.pos != self.src.len() {
+            return Err(self.error("unexpected trailing input"));
+        }
+        Ok(rule)
+    }
+
+    fn expr(&mut self) -> Result<Expr, DslError> {
+        let mut lhs = self.term()?;
+        loop {
+            if self.eat("+") {
+                lhs = Expr::Add(Box::new(lhs), Box::new(self.term()?));
+            } else if self.peek() == Some('-') {
+                self.eat("-");
+                lhs = Expr::Sub(Box::new(lhs), Box::new(self.term()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, DslError> {
+        let mut lhs = self.factor()?;
+        while self.eat("*") {
+            lhs = Expr::Mul(Box::new(lhs), Box::new(self.factor()?));
+        }
+        Ok(lhs)
+    }
+
+    fn factor(&mut self) -> Result<Expr, DslError> {
+        if self.eat("(") {
+            let inner = self.expr()?;
+            self.expect(")")?;
+            return Ok(inner);
+        }
+        if let Some(n) = self.number()? {
+            return Ok(Expr::Const(n));
+        }
+        let name = self.ident().ok_or_else(|| self.error("expected an expression"))?;
+        if self.eat("[") {
+            let index = self.index()?;
+            self.expect("]")?;
+            Ok(Expr::Element(name, index))
+        } else if self.eat("(") {
+            let arg = self.expr()?;
+            self.expect(")")?;
+            Ok(Expr::Lookup(name, Box::new(arg)))
+        } else {
+            Err(self.error("expected `[` or `(` after a name"))
+        }
+    }
+
+    fn index(&mut self) -> Result<Index, DslError> {
+        if let Some(n) = self.number()? {
+            return Ok(Index::Const(n as i64));
+        }
+        let var = self.ident().ok_or_else(|| self.error("expected an index"))?;
+        let sign = if self.eat("+") {
+            1
+        } else if self.eat("-") {
+            -1
+        } else {
+            return Ok(Index::Var(var, 0));
+        };
+        let offset = self.number()?.ok_or_else(|| self.error("expected an index offset"))?;
+        Ok(Index::Var(var, sign * offset as i64))
+    }
+
+    fn number(&mut self) -> Result<Option<u64>, DslError> {
+        self.skip_ws();
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return Ok(None);
+        }
+        digits.parse().map(Some).map_err(|_| self.error("number out of range"))
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        self.skip_ws();
+        if !self.peek().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+        Some(self.take_while(|c| c.is_ascii_alphanumeric() || c == '_').to_string())
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.src[self.pos..].chars().next().is_some_and(&f) {
+            self.pos += 1;
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        // `=` must not swallow the first half of `==`
+        let rest = &self.src[self.pos..];
+        if rest.starts_with(token) && !(token == "=" && rest.starts_with("==")) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &'static str) -> Result<(), DslError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error("unbalanced brackets"))
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_ws();
+        self.src[self.pos..].chars().next()
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.src[self.pos..].chars().next().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn error(&self, reason: &'static str) -> DslError {
+        DslError::Syntax { rule: self.src.to_string(), column: self.pos + 1, reason }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELU: &str = r#"{
+        "name": "relu8",
+        "version": 1,
+        "inputs": [{ "name": "x", "len": 4 }],
+        "outputs": [{ "name": "y", "len": 4 }],
+        "lookups": [{ "name": "relu", "pairs": [[0, 0], [1, 1], [255, 0]] }],
+        "constraints": [{ "for": "i", "over": "y", "rule": "y[i] = relu(x[i])" }]
+    }"#;
+
+    #[test]
+    fn test_rule_grammar() {
+        let rule = Parser::new("y[i] = w[0] * x[i+1] + b[i] - 3").rule().unwrap();
+        let Rule::Define { tensor, index, value } = rule else { panic!("not a definition") };
+        assert_eq!((tensor.as_str(), index), ("y", Index::Var("i".into(), 0)));
+        assert!(matches!(value, Expr::Sub(..)));
+
+        assert!(matches!(Parser::new("relu(a[i-1]) == 0").rule().unwrap(), Rule::Assert(..)));
+        assert!(Parser::new("y[i] = (x[i]").rule().is_err());
+        assert!(Parser::new("x[i] + 1 = 2").rule().is_err());
+        assert!(Parser::new("y[i] = x[i] x").rule().is_err());
+    }
+
+    #[test]
+    fn test_spec_validation() {
+        let spec = OperatorSpec::from_json(RELU).unwrap();
+        assert_eq!(spec.hash(), OperatorSpec::from_json(RELU).unwrap().hash());
+
+        let mut bad = spec.clone();
+        bad.constraints[0].rule = "x[i] = relu(y[i])".into();
+        assert_eq!(bad.rules().unwrap_err(), DslError::DefinesInput("x".into()));
+
+        let mut bad = spec.clone();
+        bad.constraints[0].rule = "y[j] = relu(x[j])".into();
+        assert_eq!(bad.rules().unwrap_err(), DslError::UnknownIndex("j".into()));
+
+        let mut bad = spec.clone();
+        bad.constraints[0].rule = "y[i] = sigmoid(x[i])".into();
+        assert_eq!(bad.rules().unwrap_err(), DslError::UnknownTable("sigmoid".into()));
+
+        let mut bad = spec;
+        bad.outputs[0].name = "x".into();
+        assert_eq!(bad.rules().unwrap_err(), DslError::DuplicateName("x".into()));
+    }
+
+    #[test]
+    fn test_registry_resolves_by_hash() {
+        let mut registry = OperatorRegistry::new();
+        let hash = registry.register(OperatorSpec::from_json(RELU).unwrap()).unwrap();
+
+        assert_eq!(registry.resolve(&[hash]).unwrap()[0].name, "relu8");
+        assert_eq!(
+            registry.resolve(&[hash, [1; 32]]).unwrap_err(),
+            DslError::Unregistered([1; 32])
+        );
+    }
+}
@@ -15,7 +15,7 @@ use plonky3::{
         circuit_builder::CircuitBuilder,
         circuit_data::CircuitData,
         config::{GenericConfig, PoseidonGoldilocksConfig},
-        proof::ProofWithPublicInputs
+        proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget}
     },
     recursion::{
         recursive_circuit::{
@@ -37,6 +37,11 @@ use std::{
     time::Instant
 };
 
+/// Declarative community operators compiled into circuit constraints
+pub mod operator_dsl;
+/// Groth16/BN254 compression of proofs for cheap on-chain verification
+pub mod groth16_wrap;
+
 // Circuit Configuration
 const D: usize = 2;
 type C = PoseidonGoldilocksConfig;
@@ -143,6 +148,75 @@ pub fn generate_recursive_proof(
     Ok(proof)
 }
 
+/// Most task proofs one aggregated proof may cover; bounded by the compute
+/// budget of `verify_aggregated_proof` marking each task complete
+pub const MAX_AGGREGATED_PROOFS: usize = 16;
+
+/// Recursive circuit verifying K proofs of the same inner circuit
+pub struct AggregationCircuit {
+    pub circuit_data: CircuitData<F, C, D>,
+    proof_targets: Vec<ProofWithPublicInputsTarget<D>>,
+    /// Public inputs of each inner proof
+    pub inputs_per_proof: usize,
+}
+
+impl AggregationCircuit {
+    /// Number of task proofs folded into one
+    pub fn batch_size(&self) -> usize {
+        self.proof_targets.len()
+    }
+}
+
+/// Build the circuit folding `k` proofs of `inner` into one. The inner proofs'
+/// public inputs are re-exposed in batch order, so the on-chain verifier can
+/// bind every slice of the aggregated public inputs to its task.
+pub fn build_aggregation_circuit(
+    inner: &CircuitData<F, C, D>,
+    k: usize,
+) -> AggregationCircuit {
+    assert!((1..=MAX_AGGREGATED_PROOFS).contains(&k), "batch size out of range");
+    let mut builder = CircuitBuilder::<F, D>::new();
+
+    let proof_targets = (0..k)
+        .map(|_| {
+            let proof_target = add_virtual_recursive_proof(&mut builder, inner.common.clone());
+            builder.verify_proof::<C>(&proof_target, &inner.common, &inner.verifier_only);
+            builder.register_public_inputs(&proof_target.public_inputs);
+            proof_target
+        })
+        .collect();
+
+    AggregationCircuit {
+        circuit_data: builder.build::<C>(),
+        proof_targets,
+        inputs_per_proof: inner.common.num_public_inputs,
+    }
+}
+
+/// Fold `proofs` into one recursive proof; their order is the order tasks are
+/// passed to `verify_aggregated_proof`
+pub fn aggregate_proofs(
+    circuit: &AggregationCircuit,
+    proofs: &[ProofWithPublicInputs<F, C, D>],
+) -> Result<ProofWithPublicInputs<F, C, D>, ProgramError> {
+    if proofs.len() != circuit.batch_size() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let start = Instant::now();
+
+    let mut witness = PartialWitness::new();
+    for (target, proof) in circuit.proof_targets.iter().zip(proofs) {
+        witness.set_proof(target, proof);
+    }
+    let proof = circuit.circuit_data.prove(witness)?;
+
+    solana_program::log::sol_log_time(
+        &format!("Aggregated {} proofs in {:?}", proofs.len(), start.elapsed())
+    );
+
+    Ok(proof)
+}
+
 /// Entrypoint handler for Solana program
 pub fn process_proof_generation(
     accounts: &[AccountInfo],
@@ -225,4 +299,27 @@ mod tests {
         
         prover_state.circuit_data.verify(proof).unwrap();
     }
+
+    #[test]
+    fn test_aggregated_proof_exposes_inner_inputs_in_order() {
+        let base_circuit = build_training_circuit(&[4, 2]);
+        let proofs: Vec<_> = (0..3)
+            .map(|i| {
+                let mut witness = PartialWitness::new();
+                witness.set_target(base_circuit.prover_only.public_inputs[0], F::from_canonical_u64(i));
+                base_circuit.prove(witness).unwrap()
+            })
+            .collect();
+
+        let aggregation = build_aggregation_circuit(&base_circuit, 3);
+        assert!(aggregate_proofs(&aggregation, &proofs[..2]).is_err());
+
+        let proof = aggregate_proofs(&aggregation, &proofs).unwrap();
+        let inner = aggregation.inputs_per_proof;
+        assert_eq!(proof.public_inputs.len(), 3 * inner);
+        for (slice, inner_proof) in proof.public_inputs.chunks(inner).zip(&proofs) {
+            assert_eq!(slice, inner_proof.public_inputs.as_slice());
+        }
+        aggregation.circuit_data.verify(proof).unwrap();
+    }
 }
@@ -0,0 +1,199 @@
+//! Incremental merkle accumulator over the proofs verified in one epoch
+//!
+//! Each `verify_ai_proof` appends the verification's `proof_commitment` as a
+//! leaf. Only the frontier (the rightmost filled node per level) is stored, so
+//! the account stays at a fixed `DEPTH * 32` bytes and an append costs `DEPTH`
+//! hashes at most. Once the epoch closes its root is frozen, and anyone holding
+//! the epoch's leaves in append order (from `ProofAccumulated` events) can prove
+//! "proof X was verified in epoch E" with `DEPTH` sibling hashes.
+//!
+//! Hashing is domain separated so a leaf can never be passed off as a node:
+//! `leaf = sha256(0x00 || proof_hash)`, `node = sha256(0x01 || left || right)`,
+//! and empty subtrees hash up from an all-zero leaf.
+
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+/// Levels of the tree; caps an epoch at 2^20 verified proofs
+pub const DEPTH: usize = 20;
+/// Leaves one accumulator can take
+pub const CAPACITY: u64 = 1 << DEPTH;
+
+const LEAF_PREFIX: &[u8] = &[0x00];
+const NODE_PREFIX: &[u8] = &[0x01];
+
+/// Append-only merkle tree keeping only its frontier
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IncrementalMerkle {
+    /// Leaves appended so far
+    pub count: u64,
+    /// Left sibling awaiting a right one at each level
+    pub frontier: [[u8; 32]; DEPTH],
+}
+
+impl Default for IncrementalMerkle {
+    fn default() -> Self {
+        Self { count: 0, frontier: [[0; 32]; DEPTH] }
+    }
+}
+
+impl IncrementalMerkle {
+    pub const LEN: usize = 8 + // count
+        DEPTH * 32; // frontier
+
+    /// Append the leaf for `proof_hash`; returns its index, or `None` when full
+    pub fn append(&mut self, proof_hash: &[u8; 32]) -> Option<u64> {
+        if self.count >= CAPACITY {
+            return None;
+        }
+        let index = self.count;
+        let mut node = leaf_hash(proof_hash);
+        for level in 0..DEPTH {
+            if (index >> level) & 1 == 0 {
+                self.frontier[level] = node;
+                break;
+            }
+            node = node_hash(&self.frontier[level], &node);
+        }
+        self.count += 1;
+        Some(index)
+    }
+
+    /// Root over the appended leaves, padded with empty subtrees
+    pub fn root(&self) -> [u8; 32] {
+        let zeros = zero_hashes();
+        let mut node = zeros[0];
+        for level in 0..DEPTH {
+            node = if (self.count >> level) & 1 == 1 {
+                node_hash(&self.frontier[level], &node)
+            } else {
+                node_hash(&node, &zeros[level])
+            };
+        }
+        node
+    }
+}
+
+pub fn leaf_hash(proof_hash: &[u8; 32]) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, proof_hash]).to_bytes()
+}
+
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Root of an empty subtree at each level
+fn zero_hashes() -> [[u8; 32]; DEPTH] {
+    let mut zeros = [[0; 32]; DEPTH];
+    for level in 1..DEPTH {
+        zeros[level] = node_hash(&zeros[level - 1], &zeros[level - 1]);
+    }
+    zeros
+}
+
+/// Sibling path proving the leaf at `index` of `proof_hashes`, bottom level
+/// first; `proof_hashes` must be the epoch's leaves in append order
+#[cfg(not(target_os = "solana"))]
+pub fn membership_proof(proof_hashes: &[[u8; 32]], index: u64) -> Option<Vec<[u8; 32]>> {
+    if index >= proof_hashes.len() as u64 || proof_hashes.len() as u64 > CAPACITY {
+        return None;
+    }
+    let zeros = zero_hashes();
+    let mut level_nodes: Vec<[u8; 32]> = proof_hashes.iter().map(leaf_hash).collect();
+    let mut position = index as usize;
+    let mut path = Vec::with_capacity(DEPTH);
+    for zero in zeros {
+        let sibling = position ^ 1;
+        path.push(level_nodes.get(sibling).copied().unwrap_or(zero));
+        level_nodes = level_nodes
+            .chunks(2)
+            .map(|pair| node_hash(&pair[0], pair.get(1).unwrap_or(&zero)))
+            .collect();
+        position /= 2;
+    }
+    Some(path)
+}
+
+/// Whether `path` proves `proof_hash` sits at `index` under `root`
+#[cfg(not(target_os = "solana"))]
+pub fn verify_membership(
+    root: &[u8; 32],
+    proof_hash: &[u8; 32],
+    index: u64,
+    path: &[[u8; 32]],
+) -> bool {
+    if path.len() != DEPTH || index >= CAPACITY {
+        return false;
+    }
+    let node = path.iter().enumerate().fold(leaf_hash(proof_hash), |node, (level, sibling)| {
+        if (index >> level) & 1 == 0 {
+            node_hash(&node, sibling)
+        } else {
+            node_hash(sibling, &node)
+        }
+    });
+    node == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<[u8; 32]> {
+        (0..n).map(|i| [i; 32]).collect()
+    }
+
+    #[test]
+    fn test_paths_verify_against_incremental_root() {
+        for n in [1u8, 2, 3, 5, 8, 13] {
+            let leaves = leaves(n);
+            let mut tree = IncrementalMerkle::default();
+            for (i, leaf) in leaves.iter().enumerate() {
+                assert_eq!(tree.append(leaf), Some(i as u64));
+            }
+            let root = tree.root();
+            for index in 0..n as u64 {
+                let path = membership_proof(&leaves, index).unwrap();
+                assert!(verify_membership(&root, &leaves[index as usize], index, &path));
+                assert!(!verify_membership(&root, &[0xff; 32], index, &path));
+                assert!(!verify_membership(&root, &leaves[index as usize], index ^ 1, &path));
+            }
+            assert!(membership_proof(&leaves, n as u64).is_none());
+        }
+    }
+
+    /// Pinned so the SDK's `accumulatorRoot` is checked against the same value
+    #[test]
+    fn test_known_root() {
+        let mut tree = IncrementalMerkle::default();
+        assert_eq!(
+            hex::encode(tree.root()),
+            "51c20d66008024c04cf114564a998e49ef8f6e044e2d13a03a66521d6e200503"
+        );
+        for leaf in leaves(3) {
+            tree.append(&leaf).unwrap();
+        }
+        assert_eq!(
+            hex::encode(tree.root()),
+            "f96746fbde91b6d4557a1c52bced0a3f816f8c7fb0467bb4a593ad92bf83575e"
+        );
+    }
+
+    #[test]
+    fn test_root_changes_with_every_append() {
+        let mut tree = IncrementalMerkle::default();
+        let mut roots = vec![tree.root()];
+        for leaf in leaves(4) {
+            tree.append(&leaf).unwrap();
+            roots.push(tree.root());
+        }
+        roots.dedup();
+        assert_eq!(roots.len(), 5);
+    }
+
+    #[test]
+    fn test_full_accumulator_rejects_appends() {
+        let mut tree = IncrementalMerkle { count: CAPACITY, ..Default::default() };
+        assert_eq!(tree.append(&[1; 32]), None);
+        assert_eq!(tree.count, CAPACITY);
+    }
+}
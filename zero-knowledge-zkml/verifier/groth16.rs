@@ -0,0 +1,191 @@
+//! Groth16 over BN254, checked with Solana's `alt_bn128` syscalls
+//!
+//! Verifying a Plonky3 proof directly costs far more compute than a transaction
+//! gets. The prover instead wraps it: a Groth16 proof that "this Plonky3 proof
+//! verifies", which costs a fixed three pairings plus one scalar multiplication
+//! per public input to check, whatever the inner circuit.
+//!
+//! Points use the syscalls' encoding: G1 is `x || y`, G2 is
+//! `x.c1 || x.c0 || y.c1 || y.c0`, every coordinate 32 bytes big-endian. Haunti
+//! public inputs are 256-bit hashes, wider than the BN254 scalar field, so the
+//! wrapper circuit exposes each as two 128-bit limbs, high limb first.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::alt_bn128::prelude::{
+        alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing,
+    },
+};
+
+use super::{VerifierError, MAX_PUBLIC_INPUTS};
+
+pub const G1_LEN: usize = 64;
+pub const G2_LEN: usize = 128;
+/// Serialized proof: `a || b || c`
+pub const PROOF_LEN: usize = G1_LEN + G2_LEN + G1_LEN;
+/// Scalars the wrapper circuit exposes: two limbs per Haunti public input
+pub const MAX_SCALARS: usize = 2 * MAX_PUBLIC_INPUTS;
+/// Circuit id of the wrapper in haunti-core's verifier key registry
+pub const WRAPPER_CIRCUIT_ID: [u8; 32] = *b"haunti:groth16-wrapper:bn254\0\0\0\0";
+
+/// BN254 base field modulus, big-endian
+const FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Groth16Proof {
+    pub a: [u8; G1_LEN],
+    pub b: [u8; G2_LEN],
+    pub c: [u8; G1_LEN],
+}
+
+impl Groth16Proof {
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() != PROOF_LEN {
+            return None;
+        }
+        let (a, rest) = data.split_at(G1_LEN);
+        let (b, c) = rest.split_at(G2_LEN);
+        Some(Self {
+            a: a.try_into().ok()?,
+            b: b.try_into().ok()?,
+            c: c.try_into().ok()?,
+        })
+    }
+}
+
+/// Verifying key of the Groth16 wrapper circuit, PDA of
+/// `[b"groth16_vk", version_le]`. Written by the program's upgrade authority;
+/// only the version governance installed in the key registry is accepted.
+#[account]
+pub struct Groth16Key {
+    pub bump: u8,
+    pub version: u32,
+    pub alpha_g1: [u8; G1_LEN],
+    pub beta_g2: [u8; G2_LEN],
+    pub gamma_g2: [u8; G2_LEN],
+    pub delta_g2: [u8; G2_LEN],
+    /// One point per public scalar, plus the constant term first
+    pub ic: Vec<[u8; G1_LEN]>,
+}
+
+impl Groth16Key {
+    pub const LEN: usize = 8 + // discriminator
+        1 + // bump
+        4 + // version
+        G1_LEN + // alpha_g1
+        3 * G2_LEN + // beta_g2, gamma_g2, delta_g2
+        4 + (MAX_SCALARS + 1) * G1_LEN; // ic
+}
+
+/// Scalars the wrapper circuit exposes for `public_inputs`: each input split
+/// into its high and low 128 bits, zero-extended to 32 bytes
+pub fn public_scalars(public_inputs: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    public_inputs
+        .iter()
+        .flat_map(|input| {
+            let mut high = [0; 32];
+            let mut low = [0; 32];
+            high[16..].copy_from_slice(&input[..16]);
+            low[16..].copy_from_slice(&input[16..]);
+            [high, low]
+        })
+        .collect()
+}
+
+/// `-p` for a G1 point: `(x, q - y)`; the point at infinity is its own negation
+pub fn negate_g1(point: &[u8; G1_LEN]) -> [u8; G1_LEN] {
+    if point.iter().all(|&b| b == 0) {
+        return *point;
+    }
+    let mut negated = *point;
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let diff = FIELD_MODULUS[i] as i16 - point[32 + i] as i16 - borrow;
+        borrow = (diff < 0) as i16;
+        negated[32 + i] = diff.rem_euclid(256) as u8;
+    }
+    negated
+}
+
+/// Check `proof` against `scalars` under `key`:
+/// `e(-a, b) * e(alpha, beta) * e(vk_x, gamma) * e(c, delta) == 1`
+pub fn verify(
+    key: &Groth16Key,
+    proof: &Groth16Proof,
+    scalars: &[[u8; 32]],
+) -> std::result::Result<(), VerifierError> {
+    if scalars.len() + 1 != key.ic.len() {
+        return Err(VerifierError::InvalidPublicInputs);
+    }
+
+    // vk_x = ic[0] + sum(scalar_i * ic[i + 1])
+    let mut vk_x = key.ic[0];
+    for (scalar, point) in scalars.iter().zip(&key.ic[1..]) {
+        let product = alt_bn128_multiplication(&[point.as_slice(), scalar].concat())
+            .map_err(|_| VerifierError::InvalidProofEncoding)?;
+        let sum = alt_bn128_addition(&[vk_x.as_slice(), &product].concat())
+            .map_err(|_| VerifierError::InvalidProofEncoding)?;
+        vk_x.copy_from_slice(&sum);
+    }
+
+    let pairing_input = [
+        negate_g1(&proof.a).as_slice(),
+        &proof.b,
+        &key.alpha_g1,
+        &key.beta_g2,
+        &vk_x,
+        &key.gamma_g2,
+        &proof.c,
+        &key.delta_g2,
+    ]
+    .concat();
+    let result = alt_bn128_pairing(&pairing_input).map_err(|_| VerifierError::InvalidProofEncoding)?;
+    // The syscall returns 1 as a 32-byte big-endian integer when the product is the identity
+    if result.len() != 32 || result[31] != 1 || result[..31].iter().any(|&b| b != 0) {
+        return Err(VerifierError::ProofVerificationFailed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_inputs_split_into_limbs() {
+        let mut input = [0u8; 32];
+        input[0] = 0xff;
+        input[31] = 0x01;
+        let scalars = public_scalars(&[input]);
+        assert_eq!(scalars.len(), 2);
+        assert_eq!(scalars[0][..16], [0; 16]);
+        assert_eq!(scalars[0][16], 0xff);
+        assert_eq!(scalars[1][31], 0x01);
+        // Limbs are always below the scalar field modulus
+        assert!(scalars.iter().all(|s| s[..16] == [0; 16]));
+    }
+
+    #[test]
+    fn test_negation_is_an_involution() {
+        let mut point = [0u8; G1_LEN];
+        point[31] = 1;
+        point[63] = 2;
+        let negated = negate_g1(&point);
+        assert_eq!(negated[..32], point[..32]);
+        let mut expected = FIELD_MODULUS;
+        expected[31] -= 2;
+        assert_eq!(negated[32..], expected);
+        assert_eq!(negate_g1(&negated), point);
+        assert_eq!(negate_g1(&[0; G1_LEN]), [0; G1_LEN]);
+    }
+
+    #[test]
+    fn test_proof_requires_exact_length() {
+        assert!(Groth16Proof::from_bytes(&[0; PROOF_LEN - 1]).is_none());
+        let proof = Groth16Proof::from_bytes(&[7; PROOF_LEN]).unwrap();
+        assert_eq!(proof.b, [7; G2_LEN]);
+    }
+}
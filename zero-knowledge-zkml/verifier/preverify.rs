@@ -0,0 +1,105 @@
+//! Native/wasm build of the on-chain proof checks
+//! Lets clients learn the exact on-chain error before paying transaction fees
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use super::{check_proof, VerifierError};
+
+/// Anchor numbers custom program errors from this offset
+const ANCHOR_ERROR_OFFSET: u32 = 6000;
+
+/// Outcome mirroring what `verify_ai_proof` would return on-chain
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PreverifyOutcome {
+    pub ok: bool,
+    /// Anchor custom error code the program would fail with
+    pub error_code: Option<u32>,
+    pub error_name: Option<String>,
+    pub error_message: Option<String>,
+}
+
+impl From<std::result::Result<(), VerifierError>> for PreverifyOutcome {
+    fn from(result: std::result::Result<(), VerifierError>) -> Self {
+        match result {
+            Ok(()) => Self {
+                ok: true,
+                error_code: None,
+                error_name: None,
+                error_message: None,
+            },
+            Err(e) => Self {
+                ok: false,
+                error_code: Some(ANCHOR_ERROR_OFFSET + e as u32),
+                error_name: Some(format!("{:?}", e)),
+                error_message: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Run the on-chain checks locally
+pub fn preverify_proof(
+    proof_data: &[u8],
+    public_inputs: &[[u8; 32]],
+    model_hash: &[u8; 32],
+) -> PreverifyOutcome {
+    check_proof(proof_data, public_inputs, model_hash)
+        .map(|_| ())
+        .into()
+}
+
+/// wasm entrypoint; `public_inputs` is the concatenation of 32-byte inputs
+#[wasm_bindgen(js_name = preverifyProof)]
+pub fn preverify_proof_js(
+    proof_data: &[u8],
+    public_inputs: &[u8],
+    model_hash: &[u8],
+) -> Result<JsValue, JsValue> {
+    let model_hash: [u8; 32] = model_hash
+        .try_into()
+        .map_err(|_| JsValue::from_str("model hash must be 32 bytes"))?;
+    if public_inputs.len() % 32 != 0 {
+        return Err(JsValue::from_str("public inputs must be 32-byte aligned"));
+    }
+    let inputs: Vec<[u8; 32]> = public_inputs
+        .chunks_exact(32)
+        .map(|c| c.try_into().unwrap())
+        .collect();
+
+    serde_wasm_bindgen::to_value(&preverify_proof(proof_data, &inputs, &model_hash))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_on_chain_error_codes() {
+        let model_hash = [7u8; 32];
+
+        let oversized = vec![0u8; super::super::MAX_PROOF_DATA_LEN + 1];
+        let outcome = preverify_proof(&oversized, &[model_hash], &model_hash);
+        assert_eq!(outcome.error_code, Some(6000));
+        assert_eq!(outcome.error_name.as_deref(), Some("InvalidProofDataLength"));
+
+        // Exactly at the caps, the size checks pass
+        let at_limit = vec![0u8; super::super::MAX_PROOF_DATA_LEN];
+        let inputs = vec![model_hash; super::super::MAX_PUBLIC_INPUTS];
+        let outcome = preverify_proof(&at_limit, &inputs, &model_hash);
+        assert!(!matches!(
+            outcome.error_name.as_deref(),
+            Some("InvalidProofDataLength" | "InvalidPublicInputs")
+        ));
+
+        let too_many = vec![model_hash; super::super::MAX_PUBLIC_INPUTS + 1];
+        let outcome = preverify_proof(&[1, 2, 3], &too_many, &model_hash);
+        assert_eq!(outcome.error_name.as_deref(), Some("InvalidPublicInputs"));
+
+        let outcome = preverify_proof(&[1, 2, 3], &[[0u8; 32]], &model_hash);
+        assert_eq!(outcome.error_name.as_deref(), Some("PublicInputMismatch"));
+        assert!(!outcome.ok);
+    }
+}
@@ -0,0 +1,168 @@
+//! Staging accounts for proofs larger than one transaction
+//!
+//! Works like a BPF loader buffer: the prover creates an account owned by this
+//! program, sized with `ProofBuffer::space`, declares the proof's length and
+//! hash in `init_proof_buffer`, then writes it in chunks at any offset, in any
+//! order and over as many transactions as it takes. `finalize_proof` checks the
+//! bytes against the declared hash and freezes the buffer, after which
+//! `verify_ai_proof` can read the proof from it and closes it.
+//!
+//! The account is a zero-copy header followed by the raw proof bytes, so
+//! neither writes nor verification copy a 128 KiB proof onto the heap.
+
+use anchor_lang::{prelude::*, solana_program::hash::hash};
+
+use super::{VerifierError, MAX_PROOF_DATA_LEN};
+
+/// Largest chunk that fits a transaction next to its signature and accounts
+pub const MAX_CHUNK_LEN: usize = 900;
+
+/// Header of a proof buffer; the proof bytes follow it in the account
+#[account(zero_copy)]
+pub struct ProofBuffer {
+    /// Only signer allowed to write, finalize, use or close the buffer
+    pub authority: Pubkey,
+    /// SHA-256 of the complete proof, declared up front
+    pub proof_hash: [u8; 32],
+    pub proof_len: u32,
+    /// Set once the bytes match `proof_hash`; no writes after
+    pub finalized: u8,
+    pub _padding: [u8; 3],
+}
+
+impl ProofBuffer {
+    /// Offset of the proof bytes in the account data
+    pub const DATA_OFFSET: usize = 8 + std::mem::size_of::<ProofBuffer>();
+
+    /// Account size to allocate for a proof of `proof_len` bytes
+    pub fn space(proof_len: u32) -> usize {
+        Self::DATA_OFFSET + proof_len as usize
+    }
+
+    /// Check a declared proof against the caps and the allocated `data_len`
+    pub fn check_init(proof_len: u32, data_len: usize) -> std::result::Result<(), VerifierError> {
+        if proof_len == 0 || proof_len as usize > MAX_PROOF_DATA_LEN {
+            return Err(VerifierError::InvalidProofDataLength);
+        }
+        if data_len < Self::space(proof_len) {
+            return Err(VerifierError::InvalidProofBuffer);
+        }
+        Ok(())
+    }
+
+    /// Range of the account data a chunk of `len` bytes at `offset` covers
+    pub fn chunk_range(
+        &self,
+        offset: u32,
+        len: usize,
+    ) -> std::result::Result<std::ops::Range<usize>, VerifierError> {
+        if self.finalized != 0 {
+            return Err(VerifierError::BufferFinalized);
+        }
+        let end = (offset as usize)
+            .checked_add(len)
+            .filter(|end| *end <= self.proof_len as usize)
+            .ok_or(VerifierError::ChunkOutOfBounds)?;
+        Ok(Self::DATA_OFFSET + offset as usize..Self::DATA_OFFSET + end)
+    }
+
+    /// Proof bytes of the buffer whose account data is `data`
+    fn bytes<'a>(&self, data: &'a [u8]) -> std::result::Result<&'a [u8], VerifierError> {
+        data.get(Self::DATA_OFFSET..Self::space(self.proof_len))
+            .ok_or(VerifierError::InvalidProofBuffer)
+    }
+
+    /// Fail unless the written bytes hash to `proof_hash`
+    pub fn check_complete(&self, data: &[u8]) -> std::result::Result<(), VerifierError> {
+        if self.finalized != 0 {
+            return Err(VerifierError::BufferFinalized);
+        }
+        if hash(self.bytes(data)?).to_bytes() != self.proof_hash {
+            return Err(VerifierError::ProofHashMismatch);
+        }
+        Ok(())
+    }
+
+    /// The complete proof, once finalized
+    pub fn proof<'a>(&self, data: &'a [u8]) -> std::result::Result<&'a [u8], VerifierError> {
+        if self.finalized == 0 {
+            return Err(VerifierError::BufferNotFinalized);
+        }
+        self.bytes(data)
+    }
+}
+
+/// Run `f` on the proof being verified: `inline` itself, or the contents of
+/// `buffer` when one is supplied, in which case `inline` must be empty
+pub fn with_proof<R>(
+    buffer: Option<&AccountLoader<'_, ProofBuffer>>,
+    inline: &[u8],
+    f: impl FnOnce(&[u8]) -> Result<R>,
+) -> Result<R> {
+    let Some(buffer) = buffer else {
+        return f(inline);
+    };
+    require!(inline.is_empty(), VerifierError::InvalidProofBuffer);
+    let header = buffer.load()?;
+    let info = buffer.to_account_info();
+    let data = info.try_borrow_data()?;
+    f(header.proof(&data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(proof: &[u8]) -> (ProofBuffer, Vec<u8>) {
+        let header = ProofBuffer {
+            authority: Pubkey::new_unique(),
+            proof_hash: hash(proof).to_bytes(),
+            proof_len: proof.len() as u32,
+            finalized: 0,
+            _padding: [0; 3],
+        };
+        (header, vec![0; ProofBuffer::space(proof.len() as u32)])
+    }
+
+    #[test]
+    fn test_chunks_in_any_order_finalize() {
+        let proof: Vec<u8> = (0..2_000u32).map(|i| i as u8).collect();
+        let (mut header, mut data) = buffer(&proof);
+
+        // Last chunk first, as parallel uploads may land
+        for offset in [1_800usize, 0, 900] {
+            let len = MAX_CHUNK_LEN.min(proof.len() - offset);
+            let range = header.chunk_range(offset as u32, len).unwrap();
+            data[range].copy_from_slice(&proof[offset..offset + len]);
+        }
+        assert!(header.proof(&data).is_err());
+        header.check_complete(&data).unwrap();
+        header.finalized = 1;
+
+        assert_eq!(header.proof(&data).unwrap(), proof.as_slice());
+        assert!(header.chunk_range(0, 1).is_err());
+    }
+
+    #[test]
+    fn test_incomplete_or_overflowing_writes_rejected() {
+        let proof = [7u8; 100];
+        let (header, mut data) = buffer(&proof);
+        assert!(header.chunk_range(90, 11).is_err());
+        assert!(header.chunk_range(u32::MAX, 2).is_err());
+
+        let range = header.chunk_range(0, 50).unwrap();
+        data[range].copy_from_slice(&proof[..50]);
+        assert!(matches!(
+            header.check_complete(&data),
+            Err(VerifierError::ProofHashMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_buffer_must_fit_declared_proof() {
+        assert!(ProofBuffer::check_init(100, ProofBuffer::space(100)).is_ok());
+        assert!(ProofBuffer::check_init(100, ProofBuffer::space(99)).is_err());
+        assert!(ProofBuffer::check_init(0, ProofBuffer::space(0)).is_err());
+        assert!(ProofBuffer::check_init(MAX_PROOF_DATA_LEN as u32 + 1, usize::MAX).is_err());
+    }
+}
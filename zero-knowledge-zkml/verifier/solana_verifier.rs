@@ -3,20 +3,151 @@
 use anchor_lang::{
     prelude::*,
     solana_program::{
+        hash::hashv,
         program::invoke_signed,
         sysvar::instructions::load_instruction_at_checked,
     },
 };
 use anchor_spl::token::{self, Token, TokenAccount};
+use haunti_core::{
+    limits, state::TaskState, VerifierKeyRegistry, VERIFIER_AUTHORITY_SEED,
+    VERIFIER_KEY_REGISTRY_SEED,
+};
 use haunti_errors::VerifierError;
 use haunti_utils::{
-    zk::verify_plonky3_proof,
+    zk::{verify_aggregated_plonky3_proof, verify_plonky3_proof},
     cpi_context::CrossProgramInvocationContext,
     serialization::deserialize_proof,
 };
 
 declare_id!("HaunVrfy111111111111111111111111111111111111");
 
+/// Off-chain build of the verification checks for client-side pre-verification
+#[cfg(not(target_os = "solana"))]
+pub mod preverify;
+/// Per-epoch merkle accumulator of verified proofs
+pub mod accumulator;
+/// Groth16/BN254 wrapper proofs checked with the `alt_bn128` syscalls
+pub mod groth16;
+/// Chunked upload of proofs larger than one transaction
+pub mod proof_buffer;
+/// Registered public-input layouts per circuit version
+pub mod zk_schema;
+
+use accumulator::IncrementalMerkle;
+use groth16::{Groth16Key, Groth16Proof};
+use proof_buffer::ProofBuffer;
+use zk_schema::{SchemaInput, ZkSchema};
+
+/// Maximum accepted proof size (prevents DoS); shared with `submit_proof`
+pub const MAX_PROOF_DATA_LEN: usize = limits::MAX_PROOF_LEN;
+/// Maximum number of 32-byte public inputs
+pub const MAX_PUBLIC_INPUTS: usize = limits::MAX_PUBLIC_INPUTS;
+
+/// Require the proof's first public input to be the task's model hash.
+///
+/// Before this check the model hash was only handed to the proof verifier
+/// alongside the inputs, so a proof generated for one model could settle a task
+/// for another whenever the circuit did not constrain the hash itself. Circuits
+/// must now expose the model hash as public input 0; proofs from circuits that
+/// put anything else first fail with `PublicInputMismatch` even if they verify.
+pub fn check_model_binding(
+    public_inputs: &[[u8; 32]],
+    model_hash: &[u8; 32],
+) -> std::result::Result<(), VerifierError> {
+    if public_inputs.is_empty() || public_inputs.len() > MAX_PUBLIC_INPUTS {
+        return Err(VerifierError::InvalidPublicInputs);
+    }
+    if public_inputs[0] != *model_hash {
+        return Err(VerifierError::PublicInputMismatch);
+    }
+    Ok(())
+}
+
+/// Checks shared by the on-chain handler and client-side pre-verification: the
+/// size cap, [`check_model_binding`], then the proof itself
+pub fn check_proof(
+    proof_data: &[u8],
+    public_inputs: &[[u8; 32]],
+    model_hash: &[u8; 32],
+) -> std::result::Result<ProofVerification, VerifierError> {
+    if proof_data.len() > MAX_PROOF_DATA_LEN {
+        return Err(VerifierError::InvalidProofDataLength);
+    }
+    check_model_binding(public_inputs, model_hash)?;
+
+    let proof = deserialize_proof(proof_data).map_err(|_| VerifierError::InvalidProofEncoding)?;
+    verify_plonky3_proof(&proof, public_inputs, model_hash)
+        .map_err(|_| VerifierError::ProofVerificationFailed)
+}
+
+/// `check_proof` for a Groth16 wrapper of a Plonky3 proof
+pub fn check_groth16_proof(
+    proof_data: &[u8],
+    public_inputs: &[[u8; 32]],
+    model_hash: &[u8; 32],
+    key: &Groth16Key,
+) -> std::result::Result<ProofVerification, VerifierError> {
+    check_model_binding(public_inputs, model_hash)?;
+
+    let proof = Groth16Proof::from_bytes(proof_data).ok_or(VerifierError::InvalidProofEncoding)?;
+    groth16::verify(key, &proof, &groth16::public_scalars(public_inputs))?;
+    Ok(ProofVerification::from_public_inputs(public_inputs))
+}
+
+/// Most tasks one aggregated proof may settle; matches the prover's
+/// `MAX_AGGREGATED_PROOFS`
+pub const MAX_AGGREGATED_TASKS: usize = 16;
+
+/// Leading public inputs every task proof in an aggregate exposes: model hash,
+/// input hash, result hash
+pub const AGGREGATED_TASK_INPUTS: usize = 3;
+
+/// Checks of an aggregated proof against the `(model_hash, input_hash)` of each
+/// task it covers, in batch order. `public_inputs` holds `inputs_per_task`
+/// inputs per task; returns the result hash each task's proof attests.
+pub fn check_aggregated_proof(
+    proof_data: &[u8],
+    public_inputs: &[[u8; 32]],
+    inputs_per_task: usize,
+    tasks: &[([u8; 32], [u8; 32])],
+) -> std::result::Result<Vec<[u8; 32]>, VerifierError> {
+    if proof_data.len() > MAX_PROOF_DATA_LEN {
+        return Err(VerifierError::InvalidProofDataLength);
+    }
+    if tasks.is_empty() || tasks.len() > MAX_AGGREGATED_TASKS {
+        return Err(VerifierError::InvalidBatchSize);
+    }
+    if inputs_per_task < AGGREGATED_TASK_INPUTS
+        || inputs_per_task > MAX_PUBLIC_INPUTS
+        || public_inputs.len() != inputs_per_task * tasks.len()
+    {
+        return Err(VerifierError::InvalidPublicInputs);
+    }
+
+    let mut result_hashes = Vec::with_capacity(tasks.len());
+    for (inputs, (model_hash, input_hash)) in public_inputs.chunks(inputs_per_task).zip(tasks) {
+        if inputs[0] != *model_hash || inputs[1] != *input_hash {
+            return Err(VerifierError::PublicInputMismatch);
+        }
+        result_hashes.push(inputs[2]);
+    }
+
+    let proof = deserialize_proof(proof_data).map_err(|_| VerifierError::InvalidProofEncoding)?;
+    verify_aggregated_plonky3_proof(&proof, public_inputs, tasks.len())
+        .map_err(|_| VerifierError::ProofVerificationFailed)?;
+    Ok(result_hashes)
+}
+
+/// SHA-256 of the proof bytes followed by the public inputs; recorded at
+/// verification so an archived copy can be matched to what was verified
+pub fn proof_commitment(proof_data: &[u8], public_inputs: &[[u8; 32]]) -> [u8; 32] {
+    let mut parts: Vec<&[u8]> = Vec::with_capacity(1 + public_inputs.len());
+    parts.push(proof_data);
+    parts.extend(public_inputs.iter().map(|input| input.as_slice()));
+    hashv(&parts).to_bytes()
+}
+
 #[program]
 pub mod solana_verifier {
     use super::*;
@@ -29,63 +160,329 @@ pub mod solana_verifier {
     /// 3. [] task_account: Source task data
     /// 4. [] model_account: Verified model metadata
     /// 5. [] reward_vault: Token vault for staking rewards
-    /// 6. [] system_program: System program
+    /// 6. [WRITE] proof_accumulator: Accumulator of the current epoch
+    /// 7. [] system_program: System program
+    /// 8. [WRITE, OPTIONAL] proof_buffer: Finalized buffer holding the proof,
+    ///    with `proof_data` left empty; closed to the authority
     pub fn verify_ai_proof(
         ctx: Context<VerifyAIProof>,
         proof_data: Vec<u8>,
         public_inputs: Vec<[u8; 32]>,
+        epoch: u64,
     ) -> Result<()> {
         // --- Phase 1: Security Checks ---
-        // Validate proof data length (prevent DoS)
+        ctx.accounts.check_compute_budget()?;
+        ctx.accounts.check_schema(&public_inputs)?;
+
+        let accumulator_bump = *ctx.bumps.get("proof_accumulator").unwrap();
+        let buffer = ctx.accounts.proof_buffer.clone();
+        proof_buffer::with_proof(buffer.as_ref(), &proof_data, |proof_data| {
+            // --- Phase 2: Proof Verification ---
+            let verification_result = check_proof(
+                proof_data,
+                &public_inputs,
+                &ctx.accounts.model_account.model_hash,
+            )?;
+
+            // --- Phases 3 & 4: State Update, Rewards, Compute Budget ---
+            ctx.accounts.settle(
+                accumulator_bump,
+                proof_data,
+                &public_inputs,
+                epoch,
+                verification_result.reward_amount,
+            )
+        })
+    }
+
+    /// Same as `verify_ai_proof` for a Groth16/BN254 wrapper of the task's
+    /// Plonky3 proof, checked with the `alt_bn128` syscalls at a fraction of the
+    /// compute. `proof_data` is `groth16::PROOF_LEN` bytes; public inputs are
+    /// those of the wrapped proof.
+    /// Accounts: those of `verify_ai_proof`, then
+    /// 9. [] groth16_key: Verifying key of the wrapper circuit
+    /// 10. [] key_registry: haunti-core registry entry naming the active key
+    pub fn verify_ai_proof_groth16(
+        ctx: Context<VerifyGroth16Proof>,
+        proof_data: Vec<u8>,
+        public_inputs: Vec<[u8; 32]>,
+        epoch: u64,
+    ) -> Result<()> {
+        ctx.accounts.base.check_compute_budget()?;
+        ctx.accounts.base.check_schema(&public_inputs)?;
+
+        let accumulator_bump = *ctx.bumps.get("proof_accumulator").unwrap();
+        let buffer = ctx.accounts.base.proof_buffer.clone();
+        proof_buffer::with_proof(buffer.as_ref(), &proof_data, |proof_data| {
+            let verification_result = check_groth16_proof(
+                proof_data,
+                &public_inputs,
+                &ctx.accounts.base.model_account.model_hash,
+                &ctx.accounts.groth16_key,
+            )?;
+
+            ctx.accounts.base.settle(
+                accumulator_bump,
+                proof_data,
+                &public_inputs,
+                epoch,
+                verification_result.reward_amount,
+            )
+        })
+    }
+
+    /// Starts a chunked proof upload into an account the prover created with
+    /// `ProofBuffer::space(proof_len)` bytes, owned by this program
+    /// Accounts:
+    /// 0. [WRITE] proof_buffer: Uninitialized buffer account
+    /// 1. [SIGNER] authority: Prover; the only signer that may use the buffer
+    pub fn init_proof_buffer(
+        ctx: Context<InitProofBuffer>,
+        proof_hash: [u8; 32],
+        proof_len: u32,
+    ) -> Result<()> {
+        let info = ctx.accounts.proof_buffer.to_account_info();
+        ProofBuffer::check_init(proof_len, info.data_len())?;
+
+        let buffer = &mut ctx.accounts.proof_buffer.load_init()?;
+        buffer.authority = ctx.accounts.authority.key();
+        buffer.proof_hash = proof_hash;
+        buffer.proof_len = proof_len;
+        buffer.finalized = 0;
+        Ok(())
+    }
+
+    /// Writes up to `proof_buffer::MAX_CHUNK_LEN` bytes at `offset`. Chunks may
+    /// arrive in any order and be resent; `finalize_proof` checks the result.
+    /// Accounts:
+    /// 0. [WRITE] proof_buffer: Buffer being written
+    /// 1. [SIGNER] authority: Buffer authority
+    pub fn write_proof_chunk(
+        ctx: Context<WriteProofBuffer>,
+        offset: u32,
+        chunk: Vec<u8>,
+    ) -> Result<()> {
+        let range = ctx.accounts.proof_buffer.load()?.chunk_range(offset, chunk.len())?;
+        let info = ctx.accounts.proof_buffer.to_account_info();
+        info.try_borrow_mut_data()?[range].copy_from_slice(&chunk);
+        Ok(())
+    }
+
+    /// Freezes the buffer once its bytes match the declared hash, making it
+    /// usable by `verify_ai_proof`
+    /// Accounts:
+    /// 0. [WRITE] proof_buffer: Fully written buffer
+    /// 1. [SIGNER] authority: Buffer authority
+    pub fn finalize_proof(ctx: Context<WriteProofBuffer>) -> Result<()> {
+        {
+            let buffer = ctx.accounts.proof_buffer.load()?;
+            let info = ctx.accounts.proof_buffer.to_account_info();
+            buffer.check_complete(&info.try_borrow_data()?)?;
+        }
+        let buffer = &mut ctx.accounts.proof_buffer.load_mut()?;
+        buffer.finalized = 1;
+
+        emit!(ProofBufferFinalized {
+            buffer: ctx.accounts.proof_buffer.key(),
+            authority: buffer.authority,
+            proof_hash: buffer.proof_hash,
+            proof_len: buffer.proof_len,
+        });
+        Ok(())
+    }
+
+    /// Abandons an upload, returning the buffer's rent to its authority
+    /// Accounts:
+    /// 0. [WRITE] proof_buffer: Buffer to close
+    /// 1. [SIGNER, WRITE] authority: Buffer authority
+    pub fn close_proof_buffer(_ctx: Context<CloseProofBuffer>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Stores a version of the Groth16 wrapper's verifying key, by the
+    /// program's upgrade authority, who ships the matching prover. Storing does
+    /// not activate it: proofs are checked against it only once a governance
+    /// `VerifierKeyRotation` installs its hash in the key registry.
+    /// Accounts:
+    /// 0. [WRITE] groth16_key: PDA of `[b"groth16_vk", version_le]`
+    /// 1. [SIGNER] authority: Upgrade authority of this program
+    /// 2. [] program, program_data: This program and its data account
+    pub fn init_groth16_key(
+        ctx: Context<InitGroth16Key>,
+        version: u32,
+        alpha_g1: [u8; groth16::G1_LEN],
+        beta_g2: [u8; groth16::G2_LEN],
+        gamma_g2: [u8; groth16::G2_LEN],
+        delta_g2: [u8; groth16::G2_LEN],
+        ic: Vec<[u8; groth16::G1_LEN]>,
+    ) -> Result<()> {
         require!(
-            proof_data.len() <= 1024 * 128, // 128KB max
-            VerifierError::InvalidProofDataLength
+            !ic.is_empty() && ic.len() <= groth16::MAX_SCALARS + 1,
+            VerifierError::InvalidPublicInputs
         );
+        let key = &mut ctx.accounts.groth16_key;
+        key.bump = *ctx.bumps.get("groth16_key").unwrap();
+        key.version = version;
+        key.alpha_g1 = alpha_g1;
+        key.beta_g2 = beta_g2;
+        key.gamma_g2 = gamma_g2;
+        key.delta_g2 = delta_g2;
+        key.ic = ic;
+        Ok(())
+    }
+
+    /// Registers the public-input layout of a circuit version at the PDA of its
+    /// hash. Anyone may register; a model adopts the layout by pointing its
+    /// `zk_schema_uri` at `zk-schema:<hex hash>`.
+    /// Accounts:
+    /// 0. [WRITE] zk_schema: PDA of `[b"zk_schema", schema_hash(version, inputs)]`
+    /// 1. [SIGNER] payer: Pays for the account
+    pub fn register_zk_schema(
+        ctx: Context<RegisterZkSchema>,
+        version: u32,
+        inputs: Vec<SchemaInput>,
+    ) -> Result<()> {
+        zk_schema::validate_inputs(&inputs)?;
+        let schema = &mut ctx.accounts.zk_schema;
+        schema.bump = *ctx.bumps.get("zk_schema").unwrap();
+        schema.version = version;
+        schema.hash = zk_schema::schema_hash(version, &inputs);
+        schema.inputs = inputs;
 
-        // CPI security: Ensure compute_budget is official program
-        let compute_budget = &ctx.accounts.compute_budget;
-        let compute_budget_info = load_instruction_at_checked(0, &ctx.accounts.to_account_infos())?;
+        emit!(ZkSchemaRegistered {
+            schema: schema.key(),
+            hash: schema.hash,
+            version,
+        });
+        Ok(())
+    }
+
+    /// Records the Arweave transaction holding a copy of the verified proof and
+    /// its public inputs, since account data is lost once accounts close
+    /// Accounts:
+    /// 0. [WRITE] verification_result: Verified proof to annotate
+    /// 1. [SIGNER] verifier: Submitter of the verified proof
+    pub fn record_proof_archive(
+        ctx: Context<RecordProofArchive>,
+        archive_tx: [u8; 32],
+    ) -> Result<()> {
+        let verification = &mut ctx.accounts.verification_result;
         require!(
-            compute_budget_info.program_id == solana_program::compute_budget::id(),
-            VerifierError::UnauthorizedCpi
+            verification.status == VerificationStatus::Verified,
+            VerifierError::NotVerified
+        );
+        require!(
+            verification.archive_tx.is_none(),
+            VerifierError::ArchiveAlreadyRecorded
         );
+        verification.archive_tx = Some(archive_tx);
 
-        // --- Phase 2: Proof Verification ---
-        let proof = deserialize_proof(&proof_data)?;
-        let verification_result = verify_plonky3_proof(
-            &proof,
-            &public_inputs,
-            &ctx.accounts.model_account.model_hash,
-        )?;
+        emit!(ProofArchived {
+            verification: verification.key(),
+            proof_hash: verification.proof_hash,
+            archive_tx,
+            slot: verification.slot,
+        });
 
-        // --- Phase 3: State Update & Rewards ---
-        let verification_account = &mut ctx.accounts.verification_result;
-        verification_account.status = VerificationStatus::Verified;
-        verification_account.slot = Clock::get()?.slot;
-        verification_account.verifier = ctx.accounts.authority.key();
+        Ok(())
+    }
 
-        // Transfer rewards from vault to submitter
-        let cpi_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token::Transfer {
-                from: ctx.accounts.reward_vault.to_account_info(),
-                to: ctx.accounts.authority_token_account.to_account_info(),
-                authority: ctx.accounts.reward_vault_authority.to_account_info(),
-            },
+    /// Freezes the root of an ended epoch's accumulator; callable by anyone
+    /// Accounts:
+    /// 0. [WRITE] proof_accumulator: Accumulator of `epoch`
+    pub fn close_proof_epoch(ctx: Context<CloseProofEpoch>, epoch: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(clock.epoch > epoch, VerifierError::EpochNotEnded);
+        let accumulator = &mut ctx.accounts.proof_accumulator;
+        require!(accumulator.root.is_none(), VerifierError::EpochAlreadyClosed);
+
+        let root = accumulator.tree.root();
+        accumulator.root = Some(root);
+        accumulator.closed_slot = clock.slot;
+
+        emit!(ProofEpochClosed {
+            epoch,
+            root,
+            leaves: accumulator.tree.count,
+            slot: clock.slot,
+        });
+
+        Ok(())
+    }
+
+    /// Verifies one recursive proof covering up to `MAX_AGGREGATED_TASKS` task
+    /// proofs and completes every task in haunti-core, so a batch pays for a
+    /// single verification
+    /// Accounts:
+    /// 0. [SIGNER, WRITE] authority: Batch submitter; pays for a new accumulator
+    /// 1. [] verifier_authority: PDA signing the completion CPI
+    /// 2. [WRITE] proof_accumulator: Accumulator of the current epoch
+    /// 3. [] verifier_registry: haunti-core verifier registry
+    /// 4. [WRITE] event_counter: haunti-core task feed counter
+    /// 5. [] haunti_core_program
+    /// 6. [] system_program
+    /// Remaining: [WRITE] the task PDAs, in the order their proofs were folded,
+    /// then the pricing accounts of priced inference tasks among them
+    pub fn verify_aggregated_proof<'info>(
+        ctx: Context<'_, '_, '_, 'info, VerifyAggregatedProof<'info>>,
+        proof_data: Vec<u8>,
+        public_inputs: Vec<[u8; 32]>,
+        inputs_per_task: u8,
+        epoch: u64,
+    ) -> Result<()> {
+        // Every task exposes `inputs_per_task` inputs; the accounts after the
+        // tasks are pricing queues passed through to haunti-core
+        let task_count = public_inputs
+            .len()
+            .checked_div(inputs_per_task as usize)
+            .ok_or(VerifierError::InvalidPublicInputs)?;
+        require!(
+            task_count <= ctx.remaining_accounts.len(),
+            VerifierError::InvalidBatchSize
         );
-        token::transfer(cpi_ctx, verification_result.reward_amount)?;
+        let task_infos = &ctx.remaining_accounts[..task_count];
+        let mut tasks = Vec::with_capacity(task_infos.len());
+        for info in task_infos {
+            let task = Account::<TaskState>::try_from(info)?;
+            tasks.push((task.model_hash, task.input_hash));
+        }
+        let result_hashes =
+            check_aggregated_proof(&proof_data, &public_inputs, inputs_per_task as usize, &tasks)?;
 
-        // --- Phase 4: Compute Budget Management ---
-        // Request additional CU for heavy verification logic
-        let cu_ix = solana_program::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(200_000);
-        invoke_signed(
-            &cu_ix,
-            &[
-                ctx.accounts.compute_budget.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-            &[],
-        )?;
+        // One accumulator leaf stands for the whole batch
+        require!(epoch == Clock::get()?.epoch, VerifierError::WrongEpoch);
+        let proof_hash = proof_commitment(&proof_data, &public_inputs);
+        let accumulator = &mut ctx.accounts.proof_accumulator;
+        if accumulator.tree.count == 0 {
+            accumulator.bump = *ctx.bumps.get("proof_accumulator").unwrap();
+            accumulator.epoch = epoch;
+        }
+        let leaf_index = accumulator
+            .tree
+            .append(&proof_hash)
+            .ok_or(VerifierError::AccumulatorFull)?;
+
+        // haunti-core owns the task PDAs; it completes them on this program's word
+        let bump = *ctx.bumps.get("verifier_authority").unwrap();
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.haunti_core_program.to_account_info(),
+            haunti_core::cpi::accounts::CompleteAggregatedTasks {
+                verifier_authority: ctx.accounts.verifier_authority.to_account_info(),
+                verifier_registry: ctx.accounts.verifier_registry.to_account_info(),
+                event_counter: ctx.accounts.event_counter.to_account_info(),
+            },
+            &[&[VERIFIER_AUTHORITY_SEED, &[bump]]],
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        haunti_core::cpi::complete_aggregated_tasks(cpi_ctx, result_hashes)?;
+
+        emit!(AggregatedProofVerified {
+            epoch,
+            leaf_index,
+            proof_hash,
+            tasks: task_infos.iter().map(|info| info.key()).collect(),
+            verifier: ctx.accounts.authority.key(),
+        });
 
         Ok(())
     }
@@ -103,6 +500,7 @@ pub mod solana_verifier {
 // Accounts ========================
 
 #[derive(Accounts)]
+#[instruction(proof_data: Vec<u8>, public_inputs: Vec<[u8; 32]>, epoch: u64)]
 pub struct VerifyAIProof<'info> {
     #[account(mut, seeds = [b"verification"], bump)]
     pub verification_result: Account<'info, VerificationState>,
@@ -124,9 +522,263 @@ pub struct VerifyAIProof<'info> {
     
     #[account(constraint = reward_vault_authority.key() == task_account.reward_authority)]
     pub reward_vault_authority: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ProofAccumulator::LEN,
+        seeds = [b"proof_accumulator", epoch.to_le_bytes().as_ref()],
+        bump,
+        constraint = proof_accumulator.root.is_none() @ VerifierError::EpochAlreadyClosed
+    )]
+    pub proof_accumulator: Account<'info, ProofAccumulator>,
     
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+
+    #[account(mut, has_one = authority, close = authority)]
+    pub proof_buffer: Option<AccountLoader<'info, ProofBuffer>>,
+
+    /// Layout named by the model's `zk_schema_uri`; required when it names one
+    #[account(seeds = [b"zk_schema", zk_schema.hash.as_ref()], bump = zk_schema.bump)]
+    pub zk_schema: Option<Account<'info, ZkSchema>>,
+}
+
+impl<'info> VerifyAIProof<'info> {
+    /// CPI security: ensure the transaction's first instruction is the official
+    /// compute budget program
+    fn check_compute_budget(&self) -> Result<()> {
+        let compute_budget_info = load_instruction_at_checked(0, &self.to_account_infos())?;
+        require!(
+            compute_budget_info.program_id == solana_program::compute_budget::id(),
+            VerifierError::UnauthorizedCpi
+        );
+        Ok(())
+    }
+
+    /// Hold the public inputs to the model's registered layout when its
+    /// `zk_schema_uri` names one
+    fn check_schema(&self, public_inputs: &[[u8; 32]]) -> Result<()> {
+        let uri = &self.model_account.zk_schema_uri;
+        if !uri.starts_with(zk_schema::ZK_SCHEMA_URI_PREFIX) {
+            return Ok(());
+        }
+        let schema = self.zk_schema.as_ref().ok_or(VerifierError::SchemaRequired)?;
+        require!(schema.is_named_by(uri), VerifierError::SchemaMismatch);
+        schema.check(public_inputs, &self.model_account.model_hash, &self.task_account)?;
+        Ok(())
+    }
+
+    /// Record a verified proof, add it to the epoch accumulator and pay the
+    /// submitter
+    fn settle(
+        &mut self,
+        accumulator_bump: u8,
+        proof_data: &[u8],
+        public_inputs: &[[u8; 32]],
+        epoch: u64,
+        reward_amount: u64,
+    ) -> Result<()> {
+        let verification_account = &mut self.verification_result;
+        verification_account.status = VerificationStatus::Verified;
+        verification_account.slot = Clock::get()?.slot;
+        verification_account.verifier = self.authority.key();
+        verification_account.proof_hash = proof_commitment(proof_data, public_inputs);
+        // A new verification starts without an archived copy
+        verification_account.archive_tx = None;
+
+        // Record membership in this epoch's verified-proof set
+        require!(epoch == Clock::get()?.epoch, VerifierError::WrongEpoch);
+        let accumulator = &mut self.proof_accumulator;
+        if accumulator.tree.count == 0 {
+            accumulator.bump = accumulator_bump;
+            accumulator.epoch = epoch;
+        }
+        let leaf_index = accumulator
+            .tree
+            .append(&verification_account.proof_hash)
+            .ok_or(VerifierError::AccumulatorFull)?;
+        verification_account.epoch = epoch;
+        verification_account.leaf_index = leaf_index;
+
+        emit!(ProofAccumulated {
+            epoch,
+            leaf_index,
+            proof_hash: verification_account.proof_hash,
+            verification: verification_account.key(),
+        });
+
+        // Transfer rewards from vault to submitter
+        let cpi_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            token::Transfer {
+                from: self.reward_vault.to_account_info(),
+                to: self.authority_token_account.to_account_info(),
+                authority: self.reward_vault_authority.to_account_info(),
+            },
+        );
+        token::transfer(cpi_ctx, reward_amount)?;
+
+        // Request additional CU for heavy verification logic
+        let cu_ix = solana_program::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(200_000);
+        invoke_signed(
+            &cu_ix,
+            &[
+                self.compute_budget.to_account_info(),
+                self.system_program.to_account_info(),
+            ],
+            &[],
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(proof_data: Vec<u8>, public_inputs: Vec<[u8; 32]>, epoch: u64)]
+pub struct VerifyGroth16Proof<'info> {
+    pub base: VerifyAIProof<'info>,
+
+    #[account(
+        seeds = [b"groth16_vk", groth16_key.version.to_le_bytes().as_ref()],
+        bump = groth16_key.bump,
+        constraint = key_registry.accepts(&groth16_key.to_account_info())
+            @ VerifierError::UnregisteredKey
+    )]
+    pub groth16_key: Account<'info, Groth16Key>,
+
+    #[account(
+        seeds = [VERIFIER_KEY_REGISTRY_SEED, groth16::WRAPPER_CIRCUIT_ID.as_ref()],
+        bump = key_registry.bump,
+        seeds::program = haunti_core::ID
+    )]
+    pub key_registry: Account<'info, VerifierKeyRegistry>,
+}
+
+#[derive(Accounts)]
+#[instruction(version: u32)]
+pub struct InitGroth16Key<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Groth16Key::LEN,
+        seeds = [b"groth16_vk", version.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub groth16_key: Account<'info, Groth16Key>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::SolanaVerifier>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(version: u32, inputs: Vec<SchemaInput>)]
+pub struct RegisterZkSchema<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = ZkSchema::LEN,
+        seeds = [b"zk_schema", zk_schema::schema_hash(version, &inputs).as_ref()],
+        bump
+    )]
+    pub zk_schema: Account<'info, ZkSchema>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(proof_data: Vec<u8>, public_inputs: Vec<[u8; 32]>, inputs_per_task: u8, epoch: u64)]
+pub struct VerifyAggregatedProof<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: signing PDA only, holds no data
+    #[account(seeds = [VERIFIER_AUTHORITY_SEED], bump)]
+    pub verifier_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ProofAccumulator::LEN,
+        seeds = [b"proof_accumulator", epoch.to_le_bytes().as_ref()],
+        bump,
+        constraint = proof_accumulator.root.is_none() @ VerifierError::EpochAlreadyClosed
+    )]
+    pub proof_accumulator: Account<'info, ProofAccumulator>,
+
+    /// CHECK: validated by haunti-core
+    pub verifier_registry: UncheckedAccount<'info>,
+
+    /// CHECK: validated by haunti-core
+    #[account(mut)]
+    pub event_counter: UncheckedAccount<'info>,
+
+    /// CHECK: address checked
+    #[account(address = haunti_core::ID)]
+    pub haunti_core_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitProofBuffer<'info> {
+    /// Allocated by the prover: proofs outgrow the 10 KiB `init` can allocate
+    #[account(zero)]
+    pub proof_buffer: AccountLoader<'info, ProofBuffer>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WriteProofBuffer<'info> {
+    #[account(mut, has_one = authority)]
+    pub proof_buffer: AccountLoader<'info, ProofBuffer>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProofBuffer<'info> {
+    #[account(mut, has_one = authority, close = authority)]
+    pub proof_buffer: AccountLoader<'info, ProofBuffer>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecordProofArchive<'info> {
+    #[account(
+        mut,
+        seeds = [b"verification"],
+        bump,
+        has_one = verifier @ VerifierError::UnauthorizedArchive
+    )]
+    pub verification_result: Account<'info, VerificationState>,
+
+    pub verifier: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct CloseProofEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"proof_accumulator", epoch.to_le_bytes().as_ref()],
+        bump = proof_accumulator.bump
+    )]
+    pub proof_accumulator: Account<'info, ProofAccumulator>,
 }
 
 #[account]
@@ -136,6 +788,85 @@ pub struct VerificationState {
     pub slot: u64,
     pub verifier: Pubkey,
     pub reward_amount: u64,
+    /// `proof_commitment` of the verified proof and public inputs
+    pub proof_hash: [u8; 32],
+    /// Arweave transaction id of the archived proof, once mirrored
+    pub archive_tx: Option<[u8; 32]>,
+    /// Epoch whose accumulator holds `proof_hash`
+    pub epoch: u64,
+    /// Position of `proof_hash` in that accumulator
+    pub leaf_index: u64,
+}
+
+/// Verified proofs of one epoch, PDA of `[b"proof_accumulator", epoch_le]`
+#[account]
+#[derive(Default)]
+pub struct ProofAccumulator {
+    pub bump: u8,
+    pub epoch: u64,
+    pub tree: IncrementalMerkle,
+    /// Published once the epoch is closed; no appends after
+    pub root: Option<[u8; 32]>,
+    pub closed_slot: u64,
+}
+
+impl ProofAccumulator {
+    pub const LEN: usize = 8 + // discriminator
+        1 + // bump
+        8 + // epoch
+        IncrementalMerkle::LEN + // tree
+        1 + 32 + // root (option)
+        8; // closed_slot
+}
+
+#[event]
+pub struct ProofArchived {
+    pub verification: Pubkey,
+    pub proof_hash: [u8; 32],
+    pub archive_tx: [u8; 32],
+    pub slot: u64,
+}
+
+#[event]
+pub struct ProofAccumulated {
+    pub epoch: u64,
+    pub leaf_index: u64,
+    pub proof_hash: [u8; 32],
+    pub verification: Pubkey,
+}
+
+#[event]
+pub struct AggregatedProofVerified {
+    pub epoch: u64,
+    pub leaf_index: u64,
+    /// `proof_commitment` of the aggregated proof and all its public inputs
+    pub proof_hash: [u8; 32],
+    /// Tasks completed, in batch order
+    pub tasks: Vec<Pubkey>,
+    pub verifier: Pubkey,
+}
+
+#[event]
+pub struct ProofBufferFinalized {
+    pub buffer: Pubkey,
+    pub authority: Pubkey,
+    pub proof_hash: [u8; 32],
+    pub proof_len: u32,
+}
+
+#[event]
+pub struct ZkSchemaRegistered {
+    pub schema: Pubkey,
+    pub hash: [u8; 32],
+    pub version: u32,
+}
+
+#[event]
+pub struct ProofEpochClosed {
+    pub epoch: u64,
+    pub root: [u8; 32],
+    pub leaves: u64,
+    pub slot: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
@@ -155,4 +886,46 @@ pub enum VerifierError {
     UnauthorizedCpi,
     #[msg("FHE ciphertext validation failed")]
     FheValidationFailure,
+    #[msg("Public inputs missing or too many")]
+    InvalidPublicInputs,
+    #[msg("Public inputs are not bound to the task model")]
+    PublicInputMismatch,
+    #[msg("Proof bytes could not be decoded")]
+    InvalidProofEncoding,
+    #[msg("Proof failed verification")]
+    ProofVerificationFailed,
+    #[msg("Only verified proofs can be archived")]
+    NotVerified,
+    #[msg("Archive transaction already recorded")]
+    ArchiveAlreadyRecorded,
+    #[msg("Only the proof submitter may record its archive")]
+    UnauthorizedArchive,
+    #[msg("Epoch does not match the current one")]
+    WrongEpoch,
+    #[msg("Epoch accumulator is full")]
+    AccumulatorFull,
+    #[msg("Epoch has not ended")]
+    EpochNotEnded,
+    #[msg("Epoch accumulator is already closed")]
+    EpochAlreadyClosed,
+    #[msg("Aggregated proof covers no tasks or too many")]
+    InvalidBatchSize,
+    #[msg("Verifying key is not the one registered by governance")]
+    UnregisteredKey,
+    #[msg("Proof buffer is too small, or used alongside inline proof data")]
+    InvalidProofBuffer,
+    #[msg("Chunk extends past the declared proof length")]
+    ChunkOutOfBounds,
+    #[msg("Proof buffer is finalized")]
+    BufferFinalized,
+    #[msg("Proof buffer is not finalized")]
+    BufferNotFinalized,
+    #[msg("Buffered proof does not match its declared hash")]
+    ProofHashMismatch,
+    #[msg("Public-input schema is malformed")]
+    InvalidZkSchema,
+    #[msg("Public inputs do not match the model's schema")]
+    SchemaMismatch,
+    #[msg("Model names a public-input schema that was not supplied")]
+    SchemaRequired,
 }
@@ -0,0 +1,249 @@
+//! Registered layouts of a circuit's public inputs
+//!
+//! A proof's public inputs are bare 32-byte words; which word is the model hash
+//! and how the compute-unit count is packed is a property of the circuit
+//! version. A `ZkSchema` writes that down: one `SchemaInput` per word, in order.
+//! Schemas are content-addressed, stored at the PDA of their own hash, so anyone
+//! may register one and no one can change it afterwards. A model opts in by
+//! setting its `zk_schema_uri` to `zk-schema:<hex hash>`; its proofs must then
+//! match the schema word for word. Models with any other URI keep the fixed
+//! "model hash first" check alone.
+
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+use haunti_core::state::TaskState;
+
+use super::{VerifierError, MAX_PUBLIC_INPUTS};
+
+/// `zk_schema_uri` prefix naming an on-chain schema by hash
+pub const ZK_SCHEMA_URI_PREFIX: &str = "zk-schema:";
+
+/// Value a public input carries
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PublicInputField {
+    /// Hash of the model the proof was generated for
+    ModelHash,
+    /// Hash of the task's encrypted input
+    InputHash,
+    /// Commitment to the output; what the proof attests, so any value is accepted
+    OutputCommitment,
+    /// Compute units the task consumed; at most its allocation
+    ComputeUnits,
+    /// RNG seed committed at task creation, which the circuit derives all of
+    /// the run's randomness from
+    RngSeed,
+}
+
+/// How a value fills its 32-byte word
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEncoding {
+    /// The 32 bytes as they are; for hashes and commitments
+    Bytes32,
+    /// Integer little-endian in the low 8 bytes, rest zero
+    U64Le,
+    /// Integer big-endian in the last 8 bytes, rest zero, as a field element
+    /// is serialized
+    U64Be,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchemaInput {
+    pub field: PublicInputField,
+    pub encoding: InputEncoding,
+}
+
+/// Public-input layout of one circuit version, PDA of `[b"zk_schema", hash]`
+#[account]
+pub struct ZkSchema {
+    pub bump: u8,
+    /// Circuit version the layout belongs to
+    pub version: u32,
+    /// One entry per public input, in order
+    pub inputs: Vec<SchemaInput>,
+    /// `schema_hash(version, inputs)`; also the PDA seed
+    pub hash: [u8; 32],
+}
+
+impl ZkSchema {
+    pub const LEN: usize = 8 + // discriminator
+        1 + // bump
+        4 + // version
+        4 + MAX_PUBLIC_INPUTS * 2 + // inputs
+        32; // hash
+
+    /// Whether a model's `zk_schema_uri` names this schema
+    pub fn is_named_by(&self, uri: &str) -> bool {
+        schema_hash_from_uri(uri) == Some(self.hash)
+    }
+
+    /// Check `public_inputs` follow the layout for `task` on `model_hash`
+    pub fn check(
+        &self,
+        public_inputs: &[[u8; 32]],
+        model_hash: &[u8; 32],
+        task: &TaskState,
+    ) -> std::result::Result<(), VerifierError> {
+        if public_inputs.len() != self.inputs.len() {
+            return Err(VerifierError::InvalidPublicInputs);
+        }
+        for (word, input) in public_inputs.iter().zip(&self.inputs) {
+            let ok = match (input.field, input.encoding) {
+                (PublicInputField::ModelHash, InputEncoding::Bytes32) => word == model_hash,
+                (PublicInputField::InputHash, InputEncoding::Bytes32) => *word == task.input_hash,
+                (PublicInputField::OutputCommitment, InputEncoding::Bytes32) => true,
+                (PublicInputField::ComputeUnits, encoding) => {
+                    decode_u64(word, encoding).is_some_and(|cu| cu <= task.allocated_cu)
+                }
+                (PublicInputField::RngSeed, InputEncoding::Bytes32) => *word == task.rng_seed,
+                _ => return Err(VerifierError::InvalidZkSchema),
+            };
+            if !ok {
+                return Err(VerifierError::SchemaMismatch);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check a layout can be registered: within the input cap, hashes stored as
+/// raw words, integers as integers, and the model hash present exactly once
+pub fn validate_inputs(inputs: &[SchemaInput]) -> std::result::Result<(), VerifierError> {
+    if inputs.is_empty() || inputs.len() > MAX_PUBLIC_INPUTS {
+        return Err(VerifierError::InvalidPublicInputs);
+    }
+    let well_typed = inputs.iter().all(|input| {
+        (input.field == PublicInputField::ComputeUnits) != (input.encoding == InputEncoding::Bytes32)
+    });
+    let model_hashes = inputs
+        .iter()
+        .filter(|input| input.field == PublicInputField::ModelHash)
+        .count();
+    if !well_typed || model_hashes != 1 {
+        return Err(VerifierError::InvalidZkSchema);
+    }
+    Ok(())
+}
+
+/// Content address of a layout
+pub fn schema_hash(version: u32, inputs: &[SchemaInput]) -> [u8; 32] {
+    let encoded: Vec<u8> = inputs
+        .iter()
+        .flat_map(|input| [input.field as u8, input.encoding as u8])
+        .collect();
+    hashv(&[b"haunti-zk-schema", &version.to_le_bytes(), &encoded]).to_bytes()
+}
+
+/// Schema hash named by a `zk-schema:<hex hash>` URI
+pub fn schema_hash_from_uri(uri: &str) -> Option<[u8; 32]> {
+    let hex = uri.strip_prefix(ZK_SCHEMA_URI_PREFIX)?;
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    for (byte, pair) in hash.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(hash)
+}
+
+fn decode_u64(word: &[u8; 32], encoding: InputEncoding) -> Option<u64> {
+    let (value, padding) = match encoding {
+        InputEncoding::U64Le => (u64::from_le_bytes(word[..8].try_into().ok()?), &word[8..]),
+        InputEncoding::U64Be => (u64::from_be_bytes(word[24..].try_into().ok()?), &word[..24]),
+        InputEncoding::Bytes32 => return None,
+    };
+    padding.iter().all(|b| *b == 0).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(field: PublicInputField, encoding: InputEncoding) -> SchemaInput {
+        SchemaInput { field, encoding }
+    }
+
+    fn schema() -> ZkSchema {
+        let inputs = vec![
+            input(PublicInputField::ModelHash, InputEncoding::Bytes32),
+            input(PublicInputField::InputHash, InputEncoding::Bytes32),
+            input(PublicInputField::OutputCommitment, InputEncoding::Bytes32),
+            input(PublicInputField::ComputeUnits, InputEncoding::U64Be),
+        ];
+        ZkSchema { bump: 0, version: 2, hash: schema_hash(2, &inputs), inputs }
+    }
+
+    fn task(allocated_cu: u64) -> TaskState {
+        TaskState {
+            input_hash: [2; 32],
+            allocated_cu,
+            rng_seed: [6; 32],
+            ..Default::default()
+        }
+    }
+
+    fn cu_be(cu: u64) -> [u8; 32] {
+        let mut word = [0; 32];
+        word[24..].copy_from_slice(&cu.to_be_bytes());
+        word
+    }
+
+    #[test]
+    fn test_inputs_checked_word_for_word() {
+        let schema = schema();
+        let inputs = [[1; 32], [2; 32], [7; 32], cu_be(500)];
+        assert!(schema.check(&inputs, &[1; 32], &task(500)).is_ok());
+
+        // Swapped hashes, overspent CU, dirty padding and wrong arity all fail
+        let swapped = [[2; 32], [1; 32], [7; 32], cu_be(500)];
+        assert!(matches!(
+            schema.check(&swapped, &[1; 32], &task(500)),
+            Err(VerifierError::SchemaMismatch)
+        ));
+        assert!(schema.check(&inputs, &[1; 32], &task(499)).is_err());
+        let mut dirty = cu_be(500);
+        dirty[0] = 1;
+        assert!(schema.check(&[[1; 32], [2; 32], [7; 32], dirty], &[1; 32], &task(500)).is_err());
+        assert!(matches!(
+            schema.check(&inputs[..3], &[1; 32], &task(500)),
+            Err(VerifierError::InvalidPublicInputs)
+        ));
+    }
+
+    #[test]
+    fn test_rng_seed_must_be_the_tasks() {
+        let inputs = vec![
+            input(PublicInputField::ModelHash, InputEncoding::Bytes32),
+            input(PublicInputField::RngSeed, InputEncoding::Bytes32),
+        ];
+        assert!(validate_inputs(&inputs).is_ok());
+        let schema = ZkSchema { bump: 0, version: 3, hash: schema_hash(3, &inputs), inputs };
+
+        assert!(schema.check(&[[1; 32], [6; 32]], &[1; 32], &task(0)).is_ok());
+        // A proof run under randomness of the prover's choosing is refused
+        assert!(matches!(
+            schema.check(&[[1; 32], [9; 32]], &[1; 32], &task(0)),
+            Err(VerifierError::SchemaMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_uri_names_schema_by_hash() {
+        let schema = schema();
+        let hex: String = schema.hash.iter().map(|b| format!("{:02x}", b)).collect();
+        assert!(schema.is_named_by(&format!("{}{}", ZK_SCHEMA_URI_PREFIX, hex)));
+        assert!(!schema.is_named_by(&format!("ipfs://{}", hex)));
+        assert!(!schema.is_named_by(&format!("{}{}", ZK_SCHEMA_URI_PREFIX, &hex[2..])));
+
+        // The version is part of the address
+        assert_ne!(schema_hash(3, &schema.inputs), schema.hash);
+    }
+
+    #[test]
+    fn test_layout_validation() {
+        assert!(validate_inputs(&schema().inputs).is_ok());
+        assert!(validate_inputs(&[]).is_err());
+        // No model hash, or a hash packed as an integer
+        assert!(validate_inputs(&[input(PublicInputField::InputHash, InputEncoding::Bytes32)]).is_err());
+        assert!(validate_inputs(&[input(PublicInputField::ModelHash, InputEncoding::U64Le)]).is_err());
+    }
+}